@@ -3,11 +3,16 @@
 // See http://www.gnu.org/licenses/
 mod miner;
 
+pub use cfx_stratum::WorkerStats as StratumWorkerStats;
+
 use crate::miner::{
     stratum::{Options as StratumOption, Stratum},
     work_notify::NotifyWork,
 };
-use cfx_parameters::consensus::GENESIS_GAS_LIMIT;
+use cfx_parameters::consensus::{
+    DEFERRED_STATE_EPOCH_COUNT, GENESIS_GAS_LIMIT,
+};
+use cfx_stratum::VardiffConfig;
 use cfx_types::{Address, H256, U256};
 use cfxcore::{
     block_parameters::*,
@@ -26,6 +31,7 @@ use std::{
     cmp::max,
     collections::HashSet,
     sync::{
+        atomic::{AtomicBool, Ordering},
         mpsc::{self, TryRecvError},
         Arc,
     },
@@ -44,12 +50,63 @@ lazy_static! {
 const MINING_ITERATION: u64 = 20;
 const BLOCK_FORCE_UPDATE_INTERVAL_IN_SECS: u64 = 10;
 const BLOCKGEN_LOOP_SLEEP_IN_MILISECS: u64 = 30;
+/// Maximum time to wait for a block generated in dev mode to become the best
+/// block.
+const DEV_BLOCK_PROCESS_TIMEOUT: Duration = Duration::from_secs(10);
 
 enum MiningState {
     Start,
     Stop,
 }
 
+/// Chain manipulations of the dev mode RPCs.
+#[derive(Default)]
+struct DevChainState {
+    /// Seconds added to the system time for the timestamp of new blocks.
+    time_offset: u64,
+    /// Timestamp of the next assembled block.
+    next_timestamp: Option<u64>,
+    /// The best block hash and `time_offset` saved by `snapshot()`. The
+    /// snapshot id is the index plus one.
+    snapshots: Vec<(H256, u64)>,
+}
+
+impl DevChainState {
+    fn increase_time(&mut self, seconds: u64) -> u64 {
+        self.time_offset = self.time_offset.saturating_add(seconds);
+        self.time_offset
+    }
+
+    /// Returns the timestamp of the next block given the system time `now`.
+    fn next_block_timestamp(&mut self, now: u64) -> u64 {
+        match self.next_timestamp.take() {
+            Some(timestamp) => {
+                self.time_offset = timestamp.saturating_sub(now);
+                timestamp
+            }
+            None => now + self.time_offset,
+        }
+    }
+
+    fn snapshot(&mut self, best_block_hash: H256) -> usize {
+        self.snapshots.push((best_block_hash, self.time_offset));
+        self.snapshots.len()
+    }
+
+    /// Removes the snapshot `id` and the snapshots after it, restores the
+    /// saved time offset, and returns the saved best block hash.
+    fn revert(&mut self, id: usize) -> Result<H256, String> {
+        if id == 0 || id > self.snapshots.len() {
+            return Err(format!("snapshot {} does not exist", id));
+        }
+        let (snapshot_block, time_offset) = self.snapshots[id - 1];
+        self.snapshots.truncate(id - 1);
+        self.time_offset = time_offset;
+        self.next_timestamp = None;
+        Ok(snapshot_block)
+    }
+}
+
 /// The interface for a conflux block generator
 pub struct BlockGenerator {
     pub pow_config: ProofOfWorkConfig,
@@ -63,6 +120,9 @@ pub struct BlockGenerator {
     workers: Mutex<Vec<(Worker, mpsc::Sender<ProofOfWorkProblem>)>>,
     pub stratum: RwLock<Option<Stratum>>,
     pos_verifier: Arc<PosVerifier>,
+    /// Whether to seal new transactions received from RPC immediately.
+    automine: AtomicBool,
+    dev_chain_state: Mutex<DevChainState>,
 }
 
 pub struct Worker {
@@ -162,6 +222,8 @@ impl BlockGenerator {
             workers: Mutex::new(Vec::new()),
             stratum: RwLock::new(None),
             pos_verifier,
+            automine: AtomicBool::new(false),
+            dev_chain_state: Default::default(),
         }
     }
 
@@ -249,15 +311,10 @@ impl BlockGenerator {
             expected_difficulty = U256::from(difficulty);
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
         // Adjust the timestamp of the currently mined block to be later
         // than or equal to its parent's.
         // See comments in verify_header_graph_ready_block()
-        let my_timestamp = max(parent_timestamp, now);
+        let my_timestamp = max(parent_timestamp, self.next_block_timestamp());
 
         let custom = self
             .txpool
@@ -666,8 +723,132 @@ impl BlockGenerator {
         hash
     }
 
+    /// Generates `DEFERRED_STATE_EPOCH_COUNT` blocks on the pivot chain, so
+    /// the transactions packed in the first block are executed when it
+    /// returns. Returns the hash of the first block.
+    ///
+    /// Fails if a generated block does not become the best block in time,
+    /// which happens if blocks are generated concurrently.
+    pub fn generate_and_execute_block(
+        &self, num_txs: usize,
+    ) -> Result<H256, String> {
+        let mut first_block = None;
+        for _ in 0..DEFERRED_STATE_EPOCH_COUNT {
+            let generated = self.generate_block(
+                num_txs,
+                self.graph.verification_config.max_block_size_in_bytes,
+                vec![],
+            );
+            // Wait for the new block to be fully processed, so all
+            // generated blocks form a chain for the transactions to be
+            // executed.
+            self.consensus_graph()
+                .wait_for_best_block(&generated, DEV_BLOCK_PROCESS_TIMEOUT)?;
+            first_block.get_or_insert(generated);
+        }
+        Ok(first_block.expect("DEFERRED_STATE_EPOCH_COUNT > 0"))
+    }
+
+    /// Generates and executes blocks until all the pending transactions are
+    /// packed, or the rest can not be packed, e.g. because of nonce gaps.
+    pub fn mine_pending_transactions(&self) -> Result<(), String> {
+        loop {
+            let unpacked = self.txpool.total_unpacked();
+            if unpacked == 0 {
+                return Ok(());
+            }
+            self.generate_and_execute_block(MAX_TRANSACTION_COUNT_PER_BLOCK)?;
+            if self.txpool.total_unpacked() >= unpacked {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn automine(&self) -> bool { self.automine.load(Ordering::Relaxed) }
+
+    pub fn set_automine(&self, enabled: bool) {
+        self.automine.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Moves the timestamp of new blocks forward. Returns the total offset
+    /// to the system time in seconds.
+    pub fn increase_time(&self, seconds: u64) -> u64 {
+        self.dev_chain_state.lock().increase_time(seconds)
+    }
+
+    /// Sets the timestamp of the next block, and the blocks after it start
+    /// from this timestamp.
+    pub fn set_next_block_timestamp(
+        &self, timestamp: u64,
+    ) -> Result<(), String> {
+        let best_block_hash = self.consensus_graph().best_block_hash();
+        let best_timestamp = self
+            .graph
+            .data_man
+            .block_header_by_hash(&best_block_hash)
+            .ok_or("best block header is missing")?
+            .timestamp();
+        if timestamp < best_timestamp {
+            return Err(format!(
+                "timestamp {} is earlier than the best block timestamp {}",
+                timestamp, best_timestamp
+            ));
+        }
+        self.dev_chain_state.lock().next_timestamp = Some(timestamp);
+        Ok(())
+    }
+
+    fn next_block_timestamp(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.dev_chain_state.lock().next_block_timestamp(now)
+    }
+
+    /// Saves the current best block and time offset, and returns the
+    /// snapshot id.
+    pub fn snapshot(&self) -> usize {
+        let best_block_hash = self.consensus_graph().best_block_hash();
+        self.dev_chain_state.lock().snapshot(best_block_hash)
+    }
+
+    /// Rolls the chain back to a snapshot. The snapshot and all the
+    /// snapshots taken after it are removed.
+    ///
+    /// The blocks generated after the snapshot are removed, and the consensus
+    /// graph is rebuilt from the remaining blocks, so the best epoch and the
+    /// state are the same as when the snapshot was taken. The transactions
+    /// in the removed blocks are pending again.
+    pub fn revert_to_snapshot(&self, id: usize) -> Result<(), String> {
+        let snapshot_block = self.dev_chain_state.lock().revert(id)?;
+        if self.consensus_graph().best_block_hash() == snapshot_block {
+            return Ok(());
+        }
+
+        let (_, pooled_transactions) = self.txpool.content(None);
+        let removed_blocks = self.graph.revert_to_block(&snapshot_block)?;
+        // The pool state is built on the removed blocks, so it is rebuilt on
+        // the snapshot state.
+        self.txpool.clear_tx_pool();
+        self.txpool.insert_new_signed_transactions(
+            removed_blocks
+                .iter()
+                .flat_map(|block| block.transactions.iter().cloned())
+                .chain(pooled_transactions)
+                .collect(),
+        );
+        Ok(())
+    }
+
     pub fn pow_config(&self) -> ProofOfWorkConfig { self.pow_config.clone() }
 
+    /// Share statistics of stratum workers, or `None` if stratum is not
+    /// started.
+    pub fn stratum_worker_stats(&self) -> Option<Vec<StratumWorkerStats>> {
+        self.stratum.read().as_ref().map(|s| s.worker_stats())
+    }
+
     /// Start num_worker new workers
     pub fn start_new_worker(
         num_worker: u32, bg: Arc<BlockGenerator>,
@@ -688,6 +869,21 @@ impl BlockGenerator {
         solution_receiver
     }
 
+    fn stratum_vardiff_config(&self) -> VardiffConfig {
+        let share_interval_ms = self.pow_config.stratum_share_interval_ms;
+        VardiffConfig {
+            initial_difficulty: self.pow_config.stratum_share_difficulty.into(),
+            min_difficulty: self.pow_config.stratum_min_share_difficulty.into(),
+            max_difficulty: U256::MAX,
+            target_share_interval: match share_interval_ms {
+                0 => None,
+                ms => Some(Duration::from_millis(ms)),
+            },
+            // retarget after about 6 shares are expected
+            retarget_interval: Duration::from_millis(share_interval_ms * 6),
+        }
+    }
+
     pub fn start_new_stratum_worker(
         bg: Arc<BlockGenerator>,
     ) -> mpsc::Receiver<ProofOfWorkSolution> {
@@ -696,6 +892,8 @@ impl BlockGenerator {
             listen_addr: bg.pow_config.stratum_listen_addr.clone(),
             port: bg.pow_config.stratum_port,
             secret: bg.pow_config.stratum_secret,
+            vardiff: bg.stratum_vardiff_config(),
+            require_authorization: bg.pow_config.stratum_require_authorization,
         };
         let stratum = Stratum::start(
             &cfg,
@@ -736,6 +934,13 @@ impl BlockGenerator {
                 current_mining_block.as_ref(),
                 &last_assemble,
             ) {
+                // shares for the outdated block are no longer credited
+                if bg.pow_config.use_stratum() {
+                    if let Some(stratum) = bg.stratum.read().as_ref() {
+                        stratum.mark_jobs_stale();
+                    }
+                }
+
                 // TODO: #transations TBD
                 if !bg.pow_config.test_mode && bg.sync.catch_up_mode() {
                     thread::sleep(sleep_duration);
@@ -887,3 +1092,47 @@ impl BlockGenerator {
 impl Stopable for BlockGenerator {
     fn stop(&self) { Self::stop(self) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::DevChainState;
+    use cfx_types::H256;
+
+    #[test]
+    fn test_dev_chain_time_travel() {
+        let mut state = DevChainState::default();
+        assert_eq!(state.next_block_timestamp(100), 100);
+        assert_eq!(state.increase_time(10), 10);
+        assert_eq!(state.increase_time(5), 15);
+        assert_eq!(state.next_block_timestamp(100), 115);
+
+        // The blocks after the set timestamp keep the new offset.
+        state.next_timestamp = Some(300);
+        assert_eq!(state.next_block_timestamp(110), 300);
+        assert_eq!(state.next_block_timestamp(120), 310);
+    }
+
+    #[test]
+    fn test_dev_chain_snapshot_revert() {
+        let (a, b, c) = (H256::random(), H256::random(), H256::random());
+        let mut state = DevChainState::default();
+        assert_eq!(state.snapshot(a), 1);
+        state.increase_time(10);
+        assert_eq!(state.snapshot(b), 2);
+        state.increase_time(20);
+        assert_eq!(state.snapshot(c), 3);
+        state.next_timestamp = Some(1000);
+
+        assert_eq!(state.revert(2), Ok(b));
+        assert_eq!(state.time_offset, 10);
+        assert_eq!(state.next_timestamp, None);
+        // The reverted snapshot and the later ones can not be used again.
+        assert!(state.revert(2).is_err());
+        assert!(state.revert(3).is_err());
+        assert!(state.revert(0).is_err());
+
+        assert_eq!(state.revert(1), Ok(a));
+        assert_eq!(state.time_offset, 0);
+        assert!(state.revert(1).is_err());
+    }
+}
@@ -26,20 +26,25 @@
 
 use crate::miner::work_notify::NotifyWork;
 use cfx_stratum::{
-    Error as StratumServiceError, JobDispatcher, PushWorkHandler,
-    Stratum as StratumService,
+    Error as StratumServiceError, JobDispatcher, PushWorkHandler, ShareResult,
+    Stratum as StratumService, VardiffConfig, WorkerStats,
 };
 use cfx_types::{H256, U256};
 use cfxcore::pow::{
-    validate, PowComputer, ProofOfWorkProblem, ProofOfWorkSolution,
+    difficulty_to_boundary, PowComputer, ProofOfWorkProblem,
+    ProofOfWorkSolution,
 };
 use log::{info, trace, warn};
 use parking_lot::Mutex;
 use std::{
+    cmp::min,
     collections::HashSet,
     fmt,
     net::{AddrParseError, SocketAddr},
-    sync::{mpsc, Arc},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc,
+    },
 };
 
 /// Configures stratum server options.
@@ -51,6 +56,10 @@ pub struct Options {
     pub port: u16,
     /// Secret for peers
     pub secret: Option<H256>,
+    /// Share difficulty of authorized workers
+    pub vardiff: VardiffConfig,
+    /// Reject submissions from workers that never registered
+    pub require_authorization: bool,
 }
 
 fn clean_0x(s: &str) -> &str {
@@ -63,6 +72,7 @@ fn clean_0x(s: &str) -> &str {
 
 struct SubmitPayload {
     worker_id: String,
+    job_id: Option<u64>,
     nonce: U256,
     pow_hash: H256,
 }
@@ -75,6 +85,10 @@ impl SubmitPayload {
 
         let worker_id = payload[0].clone();
 
+        // Legacy miners fill the job id with the pow hash, which is not a
+        // job counter, so their submissions are matched by the pow hash.
+        let job_id = u64::from_str_radix(clean_0x(&payload[1]), 16).ok();
+
         let nonce = match clean_0x(&payload[2]).parse::<U256>() {
             Ok(nonce) => nonce,
             Err(e) => {
@@ -93,6 +107,7 @@ impl SubmitPayload {
 
         Ok(SubmitPayload {
             worker_id,
+            job_id,
             nonce,
             pow_hash,
         })
@@ -112,22 +127,36 @@ impl fmt::Display for PayloadError {
     }
 }
 
+/// Mining job pushed to the workers
+struct Job {
+    id: u64,
+    problem: ProofOfWorkProblem,
+    /// Nonces submitted for this job, used to reject duplicate shares
+    submitted_nonces: HashSet<U256>,
+    /// Whether the mining block of this job is outdated
+    stale: bool,
+}
+
 /// Job dispatcher for stratum service
 pub struct StratumJobDispatcher {
-    recent_problems: Mutex<Vec<(ProofOfWorkProblem, HashSet<U256>)>>,
+    recent_jobs: Mutex<Vec<Job>>,
+    next_job_id: AtomicU64,
     solution_sender: Mutex<mpsc::Sender<ProofOfWorkSolution>>,
     pow: Arc<PowComputer>,
     window_size: usize,
 }
 
 impl JobDispatcher for StratumJobDispatcher {
-    fn submit(&self, payload: Vec<String>) -> Result<(), StratumServiceError> {
+    fn submit(
+        &self, payload: Vec<String>, share_difficulty: U256,
+    ) -> Result<ShareResult, StratumServiceError> {
         let payload = SubmitPayload::from_args(payload)
             .map_err(|e| StratumServiceError::Dispatch(e.to_string()))?;
 
         trace!(
             target: "stratum",
-            "submit_work: Decoded: nonce={}, pow_hash={}, worker_id={}",
+            "submit_work: Decoded: job_id={:?}, nonce={}, pow_hash={}, worker_id={}",
+            payload.job_id,
             payload.nonce,
             payload.pow_hash,
             payload.worker_id,
@@ -136,55 +165,90 @@ impl JobDispatcher for StratumJobDispatcher {
         let sol = ProofOfWorkSolution {
             nonce: payload.nonce,
         };
-        {
-            let mut probs = self.recent_problems.lock();
-            let mut found = false;
-            for (pow_prob, solved_nonce) in probs.iter_mut() {
-                if pow_prob.block_hash == payload.pow_hash {
-                    if solved_nonce.contains(&sol.nonce) {
-                        return Err(StratumServiceError::InvalidSolution(
-                            format!(
-                                "Problem already solved with nonce = {}! worker_id = {}",
-                                sol.nonce, payload.worker_id
-                            ).into(),
-                        ));
-                    } else if validate(self.pow.clone(), pow_prob, &sol) {
-                        solved_nonce.insert(sol.nonce);
-                        info!(
-                            "Stratum worker {} mined a block!",
-                            payload.worker_id
-                        );
-                        found = true;
-                    } else {
-                        return Err(StratumServiceError::InvalidSolution(
-                            format!(
-                                "Incorrect Nonce! worker_id = {}!",
-                                payload.worker_id
-                            )
-                            .into(),
-                        ));
-                    }
+        let is_block = {
+            let mut jobs = self.recent_jobs.lock();
+            // Fall back to the latest job of the pow hash if the job id is
+            // unknown.
+            let job_index = jobs
+                .iter()
+                .position(|j| Some(j.id) == payload.job_id)
+                .or_else(|| {
+                    jobs.iter()
+                        .rposition(|j| j.problem.block_hash == payload.pow_hash)
+                });
+            let job = match job_index {
+                Some(index) => &mut jobs[index],
+                None => {
+                    return Err(StratumServiceError::StaleJob(format!(
+                        "Solution for an expired job! worker_id = {}",
+                        payload.worker_id
+                    )));
                 }
+            };
+            if job.problem.block_hash != payload.pow_hash {
+                return Err(StratumServiceError::InvalidSolution(format!(
+                    "Pow hash mismatches the job! worker_id = {}",
+                    payload.worker_id
+                )));
+            }
+            if job.submitted_nonces.contains(&sol.nonce) {
+                return Err(StratumServiceError::InvalidSolution(format!(
+                    "Share already submitted with nonce = {}! worker_id = {}",
+                    sol.nonce, payload.worker_id
+                )));
             }
-            if !found {
-                return Err(StratumServiceError::InvalidSolution(
-                    format!(
-                        "Solution for a stale job! worker_id = {}",
+
+            let hash = self.pow.compute(
+                &sol.nonce,
+                &job.problem.block_hash,
+                job.problem.block_height,
+            );
+            let is_block = ProofOfWorkProblem::validate_hash_against_boundary(
+                &hash,
+                &sol.nonce,
+                &job.problem.boundary,
+            );
+            if !is_block {
+                // Solutions of outdated blocks are still useful in Tree-Graph,
+                // but shares of them are not credited.
+                if job.stale {
+                    return Err(StratumServiceError::StaleJob(format!(
+                        "Share for a stale job! worker_id = {}",
                         payload.worker_id
-                    )
-                    .into(),
+                    )));
+                }
+                let share_boundary = difficulty_to_boundary(&min(
+                    share_difficulty,
+                    job.problem.difficulty,
                 ));
+                if !ProofOfWorkProblem::validate_hash_against_boundary(
+                    &hash,
+                    &sol.nonce,
+                    &share_boundary,
+                ) {
+                    return Err(StratumServiceError::InvalidSolution(format!(
+                        "Incorrect Nonce! worker_id = {}!",
+                        payload.worker_id
+                    )));
+                }
             }
+            job.submitted_nonces.insert(sol.nonce);
+            is_block
+        };
 
-            match self.solution_sender.lock().send(sol) {
-                Ok(_) => {}
-                Err(e) => {
-                    warn!("{}", e);
-                }
+        if !is_block {
+            return Ok(ShareResult::Share);
+        }
+
+        info!("Stratum worker {} mined a block!", payload.worker_id);
+        match self.solution_sender.lock().send(sol) {
+            Ok(_) => {}
+            Err(e) => {
+                warn!("{}", e);
             }
         }
 
-        Ok(())
+        Ok(ShareResult::Block)
     }
 }
 
@@ -196,30 +260,43 @@ impl StratumJobDispatcher {
     ) -> StratumJobDispatcher
     {
         StratumJobDispatcher {
-            recent_problems: Mutex::new(vec![]),
+            recent_jobs: Mutex::new(vec![]),
+            next_job_id: AtomicU64::new(1),
             solution_sender: Mutex::new(solution_sender),
             pow,
             window_size: pow_window_size,
         }
     }
 
-    fn notify_new_problem(&self, current_problem: &ProofOfWorkProblem) {
-        let mut probs = self.recent_problems.lock();
-        if probs.len() == self.window_size {
-            probs.remove(0);
+    /// Registers a new job for the problem, and returns the job id.
+    fn notify_new_problem(&self, current_problem: &ProofOfWorkProblem) -> u64 {
+        let id = self.next_job_id.fetch_add(1, Ordering::Relaxed);
+        let mut jobs = self.recent_jobs.lock();
+        if jobs.len() == self.window_size {
+            jobs.remove(0);
+        }
+        jobs.push(Job {
+            id,
+            problem: current_problem.clone(),
+            submitted_nonces: HashSet::new(),
+            stale: false,
+        });
+        id
+    }
+
+    fn mark_jobs_stale(&self) {
+        for job in self.recent_jobs.lock().iter_mut() {
+            job.stale = true;
         }
-        probs.push((current_problem.clone(), HashSet::new()));
     }
 
     /// Serializes payload for stratum service
     fn payload(
-        &self, block_height: u64, pow_hash: H256, boundary: U256,
+        &self, job_id: u64, block_height: u64, pow_hash: H256, boundary: U256,
     ) -> String {
-        // Now we just fill the job_id as pow_hash. This will be more consistent
-        // with the convention.
         format!(
             r#"["0x{:x}", "{}", "0x{:x}","0x{:x}"]"#,
-            pow_hash, block_height, pow_hash, boundary
+            job_id, block_height, pow_hash, boundary
         )
     }
 }
@@ -253,9 +330,9 @@ impl NotifyWork for Stratum {
     fn notify(&self, prob: ProofOfWorkProblem) {
         trace!(target: "stratum", "Notify work");
 
-        self.dispatcher.notify_new_problem(&prob);
+        let job_id = self.dispatcher.notify_new_problem(&prob);
         self.service.push_work_all(
-            self.dispatcher.payload(prob.block_height, prob.block_hash, prob.boundary)
+            self.dispatcher.payload(job_id, prob.block_height, prob.block_hash, prob.boundary)
         ).unwrap_or_else(
             |e| warn!(target: "stratum", "Error while pushing work: {:?}", e)
         );
@@ -285,6 +362,8 @@ impl Stratum {
             ),
             dispatcher.clone(),
             options.secret.clone(),
+            options.vardiff.clone(),
+            options.require_authorization,
         )?;
 
         Ok(Stratum {
@@ -292,4 +371,85 @@ impl Stratum {
             service: stratum_svc,
         })
     }
+
+    /// Marks all pushed jobs as stale, so that shares for them are no longer
+    /// accepted. Called when the mining block becomes outdated.
+    pub fn mark_jobs_stale(&self) { self.dispatcher.mark_jobs_stale(); }
+
+    /// Share statistics of the stratum workers
+    pub fn worker_stats(&self) -> Vec<WorkerStats> {
+        self.service.worker_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_dispatcher() -> (
+        StratumJobDispatcher,
+        mpsc::Receiver<ProofOfWorkSolution>,
+        ProofOfWorkProblem,
+        u64,
+    ) {
+        let (sender, receiver) = mpsc::channel();
+        let dispatcher = StratumJobDispatcher::new(
+            sender,
+            Arc::new(PowComputer::new(false)),
+            10, /* pow_window_size */
+        );
+        // Every nonce solves a problem of difficulty 1.
+        let problem = ProofOfWorkProblem::new(1, H256::random(), 1.into());
+        let job_id = dispatcher.notify_new_problem(&problem);
+        (dispatcher, receiver, problem, job_id)
+    }
+
+    fn submit_args(job_id: String, nonce: u64, pow_hash: &H256) -> Vec<String> {
+        vec![
+            "worker".into(),
+            job_id,
+            format!("0x{:x}", nonce),
+            format!("0x{:x}", pow_hash),
+        ]
+    }
+
+    #[test]
+    fn test_submit_with_job_id() {
+        let (dispatcher, receiver, problem, job_id) = new_dispatcher();
+        let args =
+            submit_args(format!("0x{:x}", job_id), 1, &problem.block_hash);
+        assert_eq!(
+            dispatcher.submit(args.clone(), 1.into()).unwrap(),
+            ShareResult::Block
+        );
+        assert_eq!(receiver.try_recv().unwrap().nonce, 1.into());
+        // Duplicate shares are rejected.
+        match dispatcher.submit(args, 1.into()) {
+            Err(StratumServiceError::InvalidSolution(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_submit_legacy_pow_hash_as_job_id() {
+        let (dispatcher, receiver, problem, _) = new_dispatcher();
+        let args = submit_args(
+            format!("0x{:x}", problem.block_hash),
+            2,
+            &problem.block_hash,
+        );
+        assert_eq!(
+            dispatcher.submit(args, 1.into()).unwrap(),
+            ShareResult::Block
+        );
+        assert_eq!(receiver.try_recv().unwrap().nonce, 2.into());
+
+        // A legacy submission for an unknown pow hash is still stale.
+        let args =
+            submit_args(format!("0x{:x}", H256::zero()), 3, &H256::zero());
+        match dispatcher.submit(args, 1.into()) {
+            Err(StratumServiceError::StaleJob(_)) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
 }
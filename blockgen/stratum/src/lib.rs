@@ -37,8 +37,12 @@ extern crate tokio;
 extern crate tokio_io;
 
 mod traits;
+mod worker;
 
-pub use traits::{Error, JobDispatcher, PushWorkHandler, ServiceConfiguration};
+pub use traits::{
+    Error, JobDispatcher, PushWorkHandler, ServiceConfiguration, ShareResult,
+};
+pub use worker::{ConnectionStats, VardiffConfig, WorkerStats};
 
 use jsonrpc_core::{
     to_value, Compatibility, IoDelegate, MetaIoHandler, Metadata, Params, Value,
@@ -49,13 +53,17 @@ use jsonrpc_tcp_server::{
 };
 use std::sync::Arc;
 
-use crate::traits::Error::InvalidSolution;
-use cfx_types::H256;
+use crate::{
+    traits::Error::{InvalidSolution, StaleJob},
+    worker::WorkerSession,
+};
+use cfx_types::{H256, U256};
 use hash::keccak;
 use parking_lot::RwLock;
 use std::{
     collections::{HashMap, HashSet},
     net::SocketAddr,
+    time::{Instant, SystemTime},
 };
 
 type RpcResult = Result<jsonrpc_core::Value, jsonrpc_core::Error>;
@@ -82,13 +90,17 @@ pub struct Stratum {
 impl Stratum {
     pub fn start(
         addr: &SocketAddr, dispatcher: Arc<dyn JobDispatcher>,
-        secret: Option<H256>,
+        secret: Option<H256>, vardiff: VardiffConfig,
+        require_authorization: bool,
     ) -> Result<Arc<Stratum>, Error>
     {
         let implementation = Arc::new(StratumImpl {
             dispatcher,
             workers: Arc::new(RwLock::default()),
+            stats: RwLock::default(),
             secret,
+            vardiff,
+            require_authorization,
             notify_counter: RwLock::new(NOTIFY_COUNTER_INITIAL),
         });
 
@@ -97,6 +109,8 @@ impl Stratum {
         );
         delegate
             .add_method_with_meta("mining.subscribe", StratumImpl::subscribe);
+        delegate
+            .add_method_with_meta("mining.authorize", StratumImpl::authorize);
         delegate.add_method_with_meta("mining.submit", StratumImpl::submit);
         let mut handler = MetaIoHandler::<SocketMetadata>::with_compatibility(
             Compatibility::Both,
@@ -118,6 +132,11 @@ impl Stratum {
 
         Ok(stratum)
     }
+
+    /// Share statistics of all workers that ever registered
+    pub fn worker_stats(&self) -> Vec<WorkerStats> {
+        self.implementation.worker_stats()
+    }
 }
 
 impl PushWorkHandler for Stratum {
@@ -141,45 +160,118 @@ impl Drop for Stratum {
 struct StratumImpl {
     /// Payload manager
     dispatcher: Arc<dyn JobDispatcher>,
-    /// Authorized workers (socket - worker_id)
-    workers: Arc<RwLock<HashMap<SocketAddr, String>>>,
+    /// Authorized workers (socket - worker session)
+    workers: Arc<RwLock<HashMap<SocketAddr, WorkerSession>>>,
+    /// Share statistics (worker_id - stats)
+    stats: RwLock<HashMap<String, WorkerStats>>,
     /// Secret if any
     secret: Option<H256>,
+    /// Share difficulty configuration of authorized workers
+    vardiff: VardiffConfig,
+    /// Reject submissions from connections that never registered
+    require_authorization: bool,
     /// Dispatch notify couinter
     notify_counter: RwLock<u32>,
 }
 
 impl StratumImpl {
     /// rpc method `mining.subscribe`
+    ///
+    /// Workers registered by `mining.subscribe` have no share difficulty, and
+    /// only submit block solutions.
     fn subscribe(&self, params: Params, meta: SocketMetadata) -> RpcResult {
         params.parse::<(String, String)>().map(|(worker_id, secret)|{
-            if let Some(valid_secret) = self.secret {
-                let hash = keccak(secret);
-                if hash != valid_secret {
-                    return to_value(&false);
-                }
+            if !self.check_secret(secret) {
+                return to_value(&false);
             }
             debug!(target: "stratum", "New worker #{} registered", worker_id);
-            self.workers.write().insert(meta.addr().clone(), worker_id);
+            self.register(meta.addr(), WorkerSession::legacy(worker_id));
             to_value(true)
         }).map(|v| v.expect("Only true/false is returned and it's always serializable; qed"))
     }
 
+    /// rpc method `mining.authorize`
+    ///
+    /// Authorized workers are assigned a share difficulty, which is sent with
+    /// `mining.set_difficulty` before the next job, and adjusted according
+    /// to the share rate of the connection.
+    fn authorize(&self, params: Params, meta: SocketMetadata) -> RpcResult {
+        params.parse::<Vec<String>>().map(|args| {
+            let mut args = args.into_iter();
+            let worker_id = match args.next() {
+                Some(worker_id) if !worker_id.is_empty() => worker_id,
+                _ => return to_value(&false),
+            };
+            if !self.check_secret(args.next().unwrap_or_default()) {
+                return to_value(&false);
+            }
+            debug!(target: "stratum", "New worker #{} authorized", worker_id);
+            self.register(
+                meta.addr(),
+                WorkerSession::authorized(worker_id, &self.vardiff),
+            );
+            to_value(true)
+        }).map(|v| v.expect("Only true/false is returned and it's always serializable; qed"))
+    }
+
+    fn check_secret(&self, secret: String) -> bool {
+        match self.secret {
+            Some(valid_secret) => keccak(secret) == valid_secret,
+            None => true,
+        }
+    }
+
+    fn register(&self, addr: &SocketAddr, session: WorkerSession) {
+        self.stats
+            .write()
+            .entry(session.worker.clone())
+            .or_insert_with(|| WorkerStats::new(session.worker.clone()));
+        self.workers.write().insert(*addr, session);
+    }
+
     /// rpc method `mining.submit`
-    fn submit(&self, params: Params, _meta: SocketMetadata) -> RpcResult {
+    fn submit(&self, params: Params, meta: SocketMetadata) -> RpcResult {
+        let session = self
+            .workers
+            .read()
+            .get(meta.addr())
+            .map(|s| (s.worker.clone(), s.share_difficulty()));
+        if session.is_none() && self.require_authorization {
+            debug!(target: "stratum", "Submit from unregistered worker {}", meta.addr());
+            return Ok(Value::Array(vec![
+                to_value(false).expect("serializable"),
+                to_value("Unauthorized worker").expect("serializable"),
+            ]));
+        }
+
         Ok(Value::Array(match params {
             Params::Array(vals) => {
                 // first two elements are service messages (worker_id & job_id)
-                match self.dispatcher.submit(
-                    vals.iter()
-                        .filter_map(|val| match *val {
-                            Value::String(ref s) => Some(s.to_owned()),
-                            _ => None,
-                        })
-                        .collect::<Vec<String>>(),
-                ) {
-                    Ok(()) => vec![to_value(true).expect("serializable")],
-                    Err(InvalidSolution(msg)) => {
+                let mut payload = vals
+                    .iter()
+                    .filter_map(|val| match *val {
+                        Value::String(ref s) => Some(s.to_owned()),
+                        _ => None,
+                    })
+                    .collect::<Vec<String>>();
+                let result = match session {
+                    Some((worker_id, share_difficulty)) => {
+                        // shares are credited to the registered worker
+                        if let Some(id) = payload.first_mut() {
+                            *id = worker_id.clone();
+                        }
+                        let result =
+                            self.dispatcher.submit(payload, share_difficulty);
+                        self.on_submit_result(meta.addr(), &worker_id, &result);
+                        result
+                    }
+                    // Unregistered connections are treated as legacy
+                    // workers, which only submit block solutions.
+                    None => self.dispatcher.submit(payload, U256::MAX),
+                };
+                match result {
+                    Ok(_) => vec![to_value(true).expect("serializable")],
+                    Err(InvalidSolution(msg)) | Err(StaleJob(msg)) => {
                         // When we have invalid solution, we propagate the
                         // reason to the client
                         warn!("Error because of invalid solution: {:?}", msg);
@@ -201,29 +293,83 @@ impl StratumImpl {
         }))
     }
 
+    /// Update share statistics and share difficulty with the submission
+    /// result.
+    fn on_submit_result(
+        &self, addr: &SocketAddr, worker_id: &str,
+        result: &Result<ShareResult, Error>,
+    )
+    {
+        let mut stats = self.stats.write();
+        let stats = stats
+            .entry(worker_id.to_owned())
+            .or_insert_with(|| WorkerStats::new(worker_id.to_owned()));
+        match result {
+            Ok(share) => {
+                stats.accepted_shares += 1;
+                if *share == ShareResult::Block {
+                    stats.blocks += 1;
+                }
+                stats.last_share_time = Some(SystemTime::now());
+                if let Some(session) = self.workers.write().get_mut(addr) {
+                    session.on_share(&self.vardiff, Instant::now());
+                }
+            }
+            Err(StaleJob(_)) => stats.stale_shares += 1,
+            Err(_) => stats.invalid_shares += 1,
+        }
+    }
+
+    fn worker_stats(&self) -> Vec<WorkerStats> {
+        let mut stats = self.stats.read().clone();
+        for (addr, session) in self.workers.read().iter() {
+            if let Some(s) = stats.get_mut(&session.worker) {
+                s.connections.push(ConnectionStats {
+                    addr: *addr,
+                    difficulty: session.difficulty,
+                });
+            }
+        }
+        stats.into_iter().map(|(_, s)| s).collect()
+    }
+
+    fn next_request_id(&self) -> u32 {
+        let mut counter = self.notify_counter.write();
+        if *counter == ::std::u32::MAX {
+            *counter = NOTIFY_COUNTER_INITIAL;
+        } else {
+            *counter += 1
+        }
+        *counter
+    }
+
     fn push_work_all(
         &self, payload: String, tcp_dispatcher: &Dispatcher,
     ) -> Result<(), Error> {
         let hup_peers = {
-            let workers = self.workers.read();
-            let next_request_id = {
-                let mut counter = self.notify_counter.write();
-                if *counter == ::std::u32::MAX {
-                    *counter = NOTIFY_COUNTER_INITIAL;
-                } else {
-                    *counter += 1
-                }
-                *counter
-            };
+            let mut workers = self.workers.write();
+            let next_request_id = self.next_request_id();
 
             let mut hup_peers = HashSet::with_capacity(0); // most of the cases won't be needed, hence avoid allocation
             let workers_msg = format!("{{ \"id\": {}, \"method\": \"mining.notify\", \"params\": {} }}", next_request_id, payload);
             trace!(target: "stratum", "Pushing work for {} workers (payload: '{}')", workers.len(), &workers_msg);
-            for (ref addr, worker_id) in workers.iter() {
-                trace!(target: "stratum", "Pushing work to {} at addr {}", &worker_id, &addr);
+            let now = Instant::now();
+            for (ref addr, session) in workers.iter_mut() {
+                trace!(target: "stratum", "Pushing work to {} at addr {}", &session.worker, &addr);
+
+                // Idle workers never retarget on submission. The new share
+                // difficulty is sent before the job it applies to.
+                session.retarget(&self.vardiff, now);
+                if let Some(difficulty) = session.pending_difficulty() {
+                    let difficulty_msg = format!("{{ \"id\": {}, \"method\": \"mining.set_difficulty\", \"params\": [\"0x{:x}\"] }}", self.next_request_id(), difficulty);
+                    if tcp_dispatcher.push_message(addr, difficulty_msg).is_ok() {
+                        session.notified_difficulty = Some(difficulty);
+                    }
+                }
+
                 match tcp_dispatcher.push_message(addr, workers_msg.clone()) {
                     Err(PushMessageError::NoSuchPeer) => {
-                        debug!(target: "stratum", "Worker no longer connected: {} addr {}", &worker_id, &addr);
+                        debug!(target: "stratum", "Worker no longer connected: {} addr {}", &session.worker, &addr);
                         hup_peers.insert(**addr);
                     }
                     Err(e) => {
@@ -309,7 +455,11 @@ mod tests {
     pub struct VoidManager;
 
     impl JobDispatcher for VoidManager {
-        fn submit(&self, _payload: Vec<String>) -> Result<(), Error> { Ok(()) }
+        fn submit(
+            &self, _payload: Vec<String>, _share_difficulty: U256,
+        ) -> Result<ShareResult, Error> {
+            Ok(ShareResult::Share)
+        }
     }
 
     fn dummy_request(addr: &SocketAddr, data: &str) -> Vec<u8> {
@@ -339,6 +489,8 @@ mod tests {
             &"127.0.0.1:19980".parse().unwrap(),
             Arc::new(VoidManager),
             None,
+            VardiffConfig::default(),
+            false,
         );
         assert!(stratum.is_ok());
     }
@@ -361,7 +513,11 @@ mod tests {
     }
 
     impl JobDispatcher for DummyManager {
-        fn submit(&self, _payload: Vec<String>) -> Result<(), Error> { Ok(()) }
+        fn submit(
+            &self, _payload: Vec<String>, _share_difficulty: U256,
+        ) -> Result<ShareResult, Error> {
+            Ok(ShareResult::Share)
+        }
     }
 
     fn terminated_str(origin: &'static str) -> String {
@@ -381,6 +537,8 @@ mod tests {
                     .of_initial(r#"["dummy autorize payload"]"#),
             ),
             None,
+            VardiffConfig::default(),
+            false,
         )
         .expect("There should be no error starting stratum");
 
@@ -395,6 +553,126 @@ mod tests {
         assert_eq!(1, stratum.implementation.workers.read().len());
     }
 
+    struct StaleManager;
+
+    impl JobDispatcher for StaleManager {
+        fn submit(
+            &self, payload: Vec<String>, share_difficulty: U256,
+        ) -> Result<ShareResult, Error> {
+            assert_eq!(payload[0], "miner1");
+            assert_eq!(
+                share_difficulty,
+                VardiffConfig::default().initial_difficulty
+            );
+            match payload[1].as_str() {
+                "0x1" => Ok(ShareResult::Share),
+                "0x2" => Ok(ShareResult::Block),
+                _ => Err(Error::StaleJob("stale".into())),
+            }
+        }
+    }
+
+    #[test]
+    fn can_authorize_and_count_shares() {
+        let stratum = Stratum::start(
+            &"127.0.0.1:19960".parse().unwrap(),
+            Arc::new(StaleManager),
+            None,
+            VardiffConfig::default(),
+            true,
+        )
+        .expect("There should be no error starting stratum");
+        let implementation = &stratum.implementation;
+        let meta = SocketMetadata {
+            addr: "127.0.0.1:20000".parse().unwrap(),
+            tcp_dispatcher: None,
+        };
+        let submit = |job_id: &str| {
+            let params = Params::Array(vec![
+                Value::String("spoofed".into()),
+                Value::String(job_id.into()),
+                Value::String("0x0".into()),
+                Value::String("0x0".into()),
+            ]);
+            implementation.submit(params, meta.clone()).unwrap()
+        };
+
+        // submission before authorization is rejected
+        assert_eq!(
+            submit("0x1"),
+            Value::Array(vec![
+                Value::Bool(false),
+                Value::String("Unauthorized worker".into())
+            ])
+        );
+
+        let params = Params::Array(vec![Value::String("miner1".into())]);
+        assert_eq!(
+            implementation.authorize(params, meta.clone()).unwrap(),
+            Value::Bool(true)
+        );
+
+        assert_eq!(submit("0x1"), Value::Array(vec![Value::Bool(true)]));
+        assert_eq!(submit("0x2"), Value::Array(vec![Value::Bool(true)]));
+        assert_eq!(
+            submit("0x3"),
+            Value::Array(vec![
+                Value::Bool(false),
+                Value::String("stale".into())
+            ])
+        );
+
+        let stats = stratum.worker_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].worker, "miner1");
+        assert_eq!(stats[0].accepted_shares, 2);
+        assert_eq!(stats[0].blocks, 1);
+        assert_eq!(stats[0].stale_shares, 1);
+        assert_eq!(stats[0].invalid_shares, 0);
+        assert_eq!(stats[0].connections.len(), 1);
+        assert_eq!(stats[0].connections[0].addr, meta.addr);
+    }
+
+    struct LegacyManager;
+
+    impl JobDispatcher for LegacyManager {
+        fn submit(
+            &self, payload: Vec<String>, share_difficulty: U256,
+        ) -> Result<ShareResult, Error> {
+            assert_eq!(payload[0], "legacy");
+            assert_eq!(share_difficulty, U256::MAX);
+            Ok(ShareResult::Block)
+        }
+    }
+
+    #[test]
+    fn accepts_unregistered_submit_without_required_authorization() {
+        let stratum = Stratum::start(
+            &"127.0.0.1:19961".parse().unwrap(),
+            Arc::new(LegacyManager),
+            None,
+            VardiffConfig::default(),
+            false,
+        )
+        .expect("There should be no error starting stratum");
+        let meta = SocketMetadata {
+            addr: "127.0.0.1:20001".parse().unwrap(),
+            tcp_dispatcher: None,
+        };
+        let params = Params::Array(vec![
+            Value::String("legacy".into()),
+            Value::String("0x1".into()),
+            Value::String("0x0".into()),
+            Value::String("0x0".into()),
+        ]);
+
+        assert_eq!(
+            stratum.implementation.submit(params, meta).unwrap(),
+            Value::Array(vec![Value::Bool(true)])
+        );
+        assert!(stratum.worker_stats().is_empty());
+    }
+
     #[test]
     fn can_push_work() {
         let _ = ::env_logger::try_init();
@@ -407,6 +685,8 @@ mod tests {
                     .of_initial(r#"["dummy autorize payload"]"#),
             ),
             None,
+            VardiffConfig::default(),
+            false,
         )
         .expect("There should be no error starting stratum");
 
@@ -18,7 +18,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity Ethereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use cfx_types::H256;
+use cfx_types::{H256, U256};
 use jsonrpc_tcp_server::PushMessageError;
 use std;
 
@@ -27,6 +27,7 @@ pub enum Error {
     NoWork,
     NoWorkers,
     InvalidSolution(String),
+    StaleJob(String),
     Io(String),
     Tcp(String),
     Dispatch(String),
@@ -42,10 +43,22 @@ impl From<PushMessageError> for Error {
     }
 }
 
+/// Share accepted by the job dispatcher
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareResult {
+    /// The solution only meets the share difficulty
+    Share,
+    /// The solution meets the block difficulty, and a block is mined
+    Block,
+}
+
 /// Interface that can provide pow/blockchain-specific responses for the clients
 pub trait JobDispatcher: Send + Sync {
-    // miner job result
-    fn submit(&self, payload: Vec<String>) -> Result<(), Error>;
+    // miner job result, `share_difficulty` is the difficulty that the solution
+    // should meet to be accepted as a share
+    fn submit(
+        &self, payload: Vec<String>, share_difficulty: U256,
+    ) -> Result<ShareResult, Error>;
 }
 
 /// Interface that can handle requests to push job for workers
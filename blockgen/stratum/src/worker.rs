@@ -0,0 +1,240 @@
+// Copyright 2019-2020 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Per-connection worker state and variable share difficulty (vardiff)
+
+use cfx_types::{U256, U512};
+use std::{
+    cmp::{max, min},
+    convert::TryFrom,
+    net::SocketAddr,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Maximum factor to change the share difficulty in one retarget.
+const MAX_RETARGET_FACTOR: u64 = 4;
+
+/// Configures the share difficulty of authorized workers.
+#[derive(Debug, PartialEq, Clone)]
+pub struct VardiffConfig {
+    /// Share difficulty assigned to newly authorized connections.
+    pub initial_difficulty: U256,
+    /// Lower bound of the share difficulty.
+    pub min_difficulty: U256,
+    /// Upper bound of the share difficulty.
+    pub max_difficulty: U256,
+    /// Expected interval between two shares of a connection. The share
+    /// difficulty is fixed to `initial_difficulty` if `None`.
+    pub target_share_interval: Option<Duration>,
+    /// Minimum interval between two retargets of a connection.
+    pub retarget_interval: Duration,
+}
+
+impl Default for VardiffConfig {
+    fn default() -> Self {
+        VardiffConfig {
+            initial_difficulty: 1_000_000.into(),
+            min_difficulty: 1_000.into(),
+            max_difficulty: U256::MAX,
+            target_share_interval: Some(Duration::from_secs(10)),
+            retarget_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// State of a connection registered by `mining.subscribe` or
+/// `mining.authorize`.
+#[derive(Debug, Clone)]
+pub struct WorkerSession {
+    /// Worker name given when registered.
+    pub worker: String,
+    /// Share difficulty of the connection. Legacy workers registered with
+    /// `mining.subscribe` are only credited for block solutions, and have no
+    /// share difficulty.
+    pub difficulty: Option<U256>,
+    /// Share difficulty last sent to the worker with `mining.set_difficulty`.
+    pub notified_difficulty: Option<U256>,
+    /// Start of the current retarget window.
+    window_start: Instant,
+    /// Accepted shares in the current retarget window.
+    window_shares: u64,
+}
+
+impl WorkerSession {
+    pub fn legacy(worker: String) -> Self {
+        WorkerSession {
+            worker,
+            difficulty: None,
+            notified_difficulty: None,
+            window_start: Instant::now(),
+            window_shares: 0,
+        }
+    }
+
+    pub fn authorized(worker: String, config: &VardiffConfig) -> Self {
+        WorkerSession {
+            difficulty: Some(config.initial_difficulty),
+            ..Self::legacy(worker)
+        }
+    }
+
+    /// Share difficulty to validate the submissions against. Legacy workers
+    /// have to meet the block difficulty, which is capped by the dispatcher.
+    pub fn share_difficulty(&self) -> U256 {
+        self.difficulty.unwrap_or(U256::MAX)
+    }
+
+    /// Returns the new share difficulty if it should be sent to the worker.
+    pub fn pending_difficulty(&self) -> Option<U256> {
+        match self.difficulty {
+            Some(d) if self.notified_difficulty != Some(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Records an accepted share and retargets the share difficulty.
+    pub fn on_share(&mut self, config: &VardiffConfig, now: Instant) {
+        self.window_shares += 1;
+        self.retarget(config, now);
+    }
+
+    /// Adjusts the share difficulty so that the worker submits shares at
+    /// `target_share_interval`. The difficulty changes at most
+    /// `MAX_RETARGET_FACTOR` times per retarget, and minor changes are
+    /// ignored to avoid flooding workers with `mining.set_difficulty`.
+    pub fn retarget(&mut self, config: &VardiffConfig, now: Instant) {
+        let (current, target_interval) =
+            match (self.difficulty, config.target_share_interval) {
+                (Some(d), Some(t)) => (d, t),
+                _ => return,
+            };
+
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < config.retarget_interval {
+            return;
+        }
+
+        // Expected shares in the window are `elapsed / target_interval`, so
+        // the difficulty scales with the ratio of accepted to expected.
+        let elapsed_ms = max(elapsed.as_millis() as u64, 1);
+        let target_ms = max(target_interval.as_millis() as u64, 1);
+        let adjusted = U512::from(current)
+            * U512::from(self.window_shares)
+            * U512::from(target_ms)
+            / U512::from(elapsed_ms);
+        let mut adjusted = U256::try_from(adjusted).unwrap_or(U256::MAX);
+
+        adjusted =
+            min(adjusted, current.saturating_mul(MAX_RETARGET_FACTOR.into()));
+        adjusted = max(adjusted, current / MAX_RETARGET_FACTOR);
+        adjusted =
+            min(max(adjusted, config.min_difficulty), config.max_difficulty);
+        adjusted = max(adjusted, U256::one());
+
+        self.window_start = now;
+        self.window_shares = 0;
+
+        // ignore changes within 25%
+        let (adjusted_512, current_512) =
+            (U512::from(adjusted) * 4, U512::from(current));
+        if adjusted_512 > current_512 * 5 || adjusted_512 < current_512 * 3 {
+            debug!(
+                target: "stratum",
+                "Retarget share difficulty of worker {}: {} -> {}",
+                self.worker, current, adjusted
+            );
+            self.difficulty = Some(adjusted);
+        }
+    }
+}
+
+/// Share statistics of a connection.
+#[derive(Debug, Clone)]
+pub struct ConnectionStats {
+    pub addr: SocketAddr,
+    pub difficulty: Option<U256>,
+}
+
+/// Share statistics of a worker, accumulated over all its connections.
+#[derive(Debug, Clone)]
+pub struct WorkerStats {
+    pub worker: String,
+    /// Connections of the worker that are still alive.
+    pub connections: Vec<ConnectionStats>,
+    pub accepted_shares: u64,
+    pub stale_shares: u64,
+    pub invalid_shares: u64,
+    /// Accepted shares that meet the block difficulty.
+    pub blocks: u64,
+    pub last_share_time: Option<SystemTime>,
+}
+
+impl WorkerStats {
+    pub fn new(worker: String) -> Self {
+        WorkerStats {
+            worker,
+            connections: Vec::new(),
+            accepted_shares: 0,
+            stale_shares: 0,
+            invalid_shares: 0,
+            blocks: 0,
+            last_share_time: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> VardiffConfig {
+        VardiffConfig {
+            initial_difficulty: 1000.into(),
+            min_difficulty: 100.into(),
+            max_difficulty: 100_000.into(),
+            target_share_interval: Some(Duration::from_secs(10)),
+            retarget_interval: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_retarget() {
+        let config = config();
+        let mut session = WorkerSession::authorized("w".into(), &config);
+        let start = session.window_start;
+        assert_eq!(session.pending_difficulty(), Some(1000.into()));
+
+        // no retarget within retarget interval
+        session.on_share(&config, start + Duration::from_secs(1));
+        assert_eq!(session.difficulty, Some(1000.into()));
+
+        // 12 shares in 60s, 2 times of the expected rate
+        for _ in 0..11 {
+            session.window_shares += 1;
+        }
+        session.retarget(&config, start + Duration::from_secs(60));
+        assert_eq!(session.difficulty, Some(2000.into()));
+
+        // 7 shares in 60s, minor change is ignored
+        session.window_shares = 7;
+        session.retarget(&config, start + Duration::from_secs(120));
+        assert_eq!(session.difficulty, Some(2000.into()));
+
+        // no shares, limited by the max retarget factor
+        session.retarget(&config, start + Duration::from_secs(180));
+        assert_eq!(session.difficulty, Some(500.into()));
+        session.retarget(&config, start + Duration::from_secs(240));
+        assert_eq!(session.difficulty, Some(125.into()));
+    }
+
+    #[test]
+    fn test_legacy_worker() {
+        let config = config();
+        let mut session = WorkerSession::legacy("w".into());
+        session.retarget(&config, Instant::now() + Duration::from_secs(60));
+        assert_eq!(session.difficulty, None);
+        assert_eq!(session.pending_difficulty(), None);
+        assert_eq!(session.share_difficulty(), U256::MAX);
+    }
+}
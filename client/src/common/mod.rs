@@ -13,6 +13,7 @@ use std::{
 };
 
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WSServer;
 use parking_lot::{Condvar, Mutex};
@@ -59,11 +60,15 @@ use crate::{
     configuration::parse_config_address_string,
     rpc::{
         extractor::RpcExtractor,
+        graphql::start_graphql,
+        health::{start_health, HealthChecker},
+        hyper_server::HyperServer,
         impls::{
-            cfx::RpcImpl, common::RpcImpl as CommonRpcImpl,
+            cfx::RpcImpl, common::RpcImpl as CommonRpcImpl, eth::EthHandler,
             pubsub::PubSubClient,
         },
-        setup_debug_rpc_apis, setup_public_eth_rpc_apis, setup_public_rpc_apis,
+        setup_debug_rpc_apis, setup_ipc_eth_rpc_apis, setup_ipc_rpc_apis,
+        setup_public_eth_rpc_apis, setup_public_rpc_apis,
     },
     GENESIS_VERSION,
 };
@@ -380,13 +385,19 @@ pub fn initialize_common_modules(
     let pow_config = conf.pow_config();
     let pow = Arc::new(PowComputer::new(pow_config.use_octopus()));
 
+    let trusted_checkpoint = conf.trusted_checkpoint()?;
+    let pos_trusted_waypoint = trusted_checkpoint
+        .as_ref()
+        .and_then(|checkpoint| checkpoint.pos_waypoint);
+    let mut data_man_config = conf.data_mananger_config();
+    data_man_config.trusted_checkpoint = trusted_checkpoint;
     let data_man = Arc::new(BlockDataManager::new(
         cache_config,
         Arc::new(genesis_block),
         ledger_db.clone(),
         storage_manager,
         worker_thread_pool,
-        conf.data_mananger_config(),
+        data_man_config,
         pow.clone(),
     ));
 
@@ -417,6 +428,7 @@ pub fn initialize_common_modules(
                 .clone(),
             vrf_proposal_threshold: conf.raw_conf.vrf_proposal_threshold,
             pos_state_config: conf.pos_state_config(),
+            trusted_waypoint: pos_trusted_waypoint,
         },
         conf.raw_conf.pos_reference_enable_height,
     ));
@@ -537,6 +549,10 @@ pub fn initialize_not_light_node_modules(
         Arc<PosVerifier>,
         Runtime,
         Option<HttpServer>,
+        Option<IpcServer>,
+        Option<IpcServer>,
+        Option<HyperServer>,
+        Option<HyperServer>,
     ),
     String,
 >
@@ -650,7 +666,9 @@ pub fn initialize_not_light_node_modules(
     ));
     if conf.is_dev_mode() {
         // If `dev_block_interval_ms` is None, blocks are generated after
-        // receiving RPC `cfx_sendRawTransaction`.
+        // receiving new transactions from RPC. It can be changed with RPC
+        // `evm_setAutomine`.
+        blockgen.set_automine(conf.raw_conf.dev_block_interval_ms.is_none());
         if let Some(interval_ms) = conf.raw_conf.dev_block_interval_ms {
             // Automatic block generation with fixed interval.
             let bg = blockgen.clone();
@@ -752,6 +770,53 @@ pub fn initialize_not_light_node_modules(
         ),
     )?;
 
+    let rpc_ipc_server = super::rpc::start_ipc(
+        conf.ipc_config(),
+        setup_ipc_rpc_apis(
+            common_impl.clone(),
+            rpc_impl.clone(),
+            pubsub.clone(),
+            &conf,
+        ),
+        RpcExtractor,
+    )?;
+
+    let eth_rpc_ipc_server = super::rpc::start_ipc(
+        conf.eth_ipc_config(),
+        setup_ipc_eth_rpc_apis(
+            common_impl.clone(),
+            rpc_impl.clone(),
+            pubsub.clone(),
+            &conf,
+        ),
+        RpcExtractor,
+    )?;
+
+    let graphql_server = start_graphql(
+        conf.graphql_config(),
+        Arc::new(EthHandler::new(
+            rpc_impl.config.clone(),
+            rpc_impl.consensus.clone(),
+            rpc_impl.sync.clone(),
+            rpc_impl.tx_pool.clone(),
+            rpc_impl.block_gen.clone(),
+        )),
+    )?;
+
+    let health_conf = conf.health_config();
+    let health_server = start_health(
+        &health_conf,
+        HealthChecker::new(
+            &health_conf,
+            Arc::downgrade(&data_man),
+            exit,
+            consensus.clone(),
+            sync.clone(),
+            network.clone(),
+            pos_verifier.clone(),
+        ),
+    )?;
+
     let rpc_http_server = super::rpc::start_http(
         conf.http_config(),
         setup_public_rpc_apis(common_impl, rpc_impl, pubsub, &conf),
@@ -775,6 +840,10 @@ pub fn initialize_not_light_node_modules(
         pos_verifier,
         runtime,
         eth_rpc_http_server,
+        rpc_ipc_server,
+        eth_rpc_ipc_server,
+        graphql_server,
+        health_server,
     ))
 }
 
@@ -818,11 +887,18 @@ pub fn initialize_txgens(
                 thread::Builder::new()
                     .name("txgen".into())
                     .spawn(move || {
-                        TransactionGenerator::generate_transactions_with_multiple_genesis_accounts(
-                            txgen_clone,
-                            txgen_conf,
-                            genesis_accounts,
-                        );
+                        let mut txgen_conf = txgen_conf;
+                        match txgen_conf.workload.take() {
+                            Some(workload) => TransactionGenerator::generate_transactions_with_workload(
+                                txgen_clone,
+                                workload,
+                            ),
+                            None => TransactionGenerator::generate_transactions_with_multiple_genesis_accounts(
+                                txgen_clone,
+                                txgen_conf,
+                                genesis_accounts,
+                            ),
+                        }
                     })
                     .expect("should succeed");
             multi_genesis_txgen.set_join_handle(join_handle);
@@ -15,11 +15,16 @@ use cfx_internal_common::{
 use cfx_parameters::block::DEFAULT_TARGET_BLOCK_GAS_LIMIT;
 use cfx_storage::{
     defaults::DEFAULT_DEBUG_SNAPSHOT_CHECKER_THREADS, storage_dir,
-    ConsensusParam, ProvideExtraSnapshotSyncConfig, StorageConfiguration,
+    ConsensusParam, ProvideExtraSnapshotSyncConfig, SnapshotDbType,
+    StorageConfiguration,
+};
+use cfx_types::{
+    Address, AddressSpaceUtil, AddressWithSpace, AllChainID, H256, U256,
 };
-use cfx_types::{Address, AllChainID, H256, U256};
 use cfxcore::{
-    block_data_manager::{DataManagerConfiguration, DbType},
+    block_data_manager::{
+        trusted_checkpoint::TrustedCheckpoint, DataManagerConfiguration, DbType,
+    },
     block_parameters::*,
     cache_config::{
         DEFAULT_INVALID_BLOCK_HASH_CACHE_SIZE_IN_COUNT,
@@ -37,7 +42,7 @@ use cfxcore::{
     spec::CommonParams,
     sync::{ProtocolConfiguration, StateSyncConfiguration, SyncGraphConfig},
     sync_parameters::*,
-    transaction_pool::TxPoolConfig,
+    transaction_pool::{SenderAdmissionConfig, TxPoolConfig},
     NodeType,
 };
 use diem_types::term_state::{
@@ -45,12 +50,15 @@ use diem_types::term_state::{
     OUT_QUEUE_LOCKED_VIEWS, ROUND_PER_TERM, TERM_ELECTED_SIZE, TERM_MAX_SIZE,
 };
 use metrics::MetricsConfiguration;
-use network::DiscoveryConfiguration;
-use txgen::TransactionGeneratorConfig;
+use network::{DiscoveryConfiguration, EncryptionMode};
+use txgen::{TransactionGeneratorConfig, WorkloadSpec};
 
 use crate::rpc::{
-    impls::RpcImplConfiguration, rpc_apis::ApiSet, HttpConfiguration,
-    TcpConfiguration, WsConfiguration,
+    graphql::{GraphQLConfiguration, QueryLimits},
+    health::HealthConfiguration,
+    impls::RpcImplConfiguration,
+    rpc_apis::ApiSet,
+    HttpConfiguration, IpcConfiguration, TcpConfiguration, WsConfiguration,
 };
 
 lazy_static! {
@@ -128,6 +136,7 @@ build_config! {
         (metrics_report_interval_ms, (u64), 3_000)
         (rocksdb_disable_wal, (bool), false)
         (txgen_account_count, (usize), 10)
+        (txgen_workload, (Option<String>), None)
 
         // Genesis section.
         (adaptive_weight_beta, (u64), ADAPTIVE_WEIGHT_DEFAULT_BETA)
@@ -151,6 +160,8 @@ build_config! {
         (cip78_patch_transition_number,(Option<u64>),None)
         (cip90_transition_height,(Option<u64>),None)
         (cip90_transition_number,(Option<u64>),None)
+        (cip94_transition_number,(Option<u64>),None)
+        (eip2537_transition_number,(Option<u64>),None)
         (referee_bound, (usize), REFEREE_DEFAULT_BOUND)
         (timer_chain_beta, (u64), TIMER_CHAIN_DEFAULT_BETA)
         (timer_chain_block_difficulty_ratio, (u64), TIMER_CHAIN_BLOCK_DEFAULT_DIFFICULTY_RATIO)
@@ -163,6 +174,10 @@ build_config! {
         (stratum_listen_address, (String), "127.0.0.1".into())
         (stratum_port, (u16), 32525)
         (stratum_secret, (Option<String>), None)
+        (stratum_share_difficulty, (Option<u64>), None)
+        (stratum_min_share_difficulty, (Option<u64>), None)
+        (stratum_share_interval_ms, (Option<u64>), None)
+        (stratum_require_authorization, (bool), false)
         (use_octopus_in_test_mode, (bool), false)
         (pow_problem_window_size, (usize), 1)
 
@@ -179,6 +194,17 @@ build_config! {
         (jsonrpc_ws_max_payload_bytes, (usize), 30 * 1024 * 1024)
         (jsonrpc_http_eth_port, (Option<u16>), None)
         (jsonrpc_ws_eth_port, (Option<u16>), None)
+        (jsonrpc_ipc_path, (Option<String>), None)
+        (jsonrpc_ipc_eth_path, (Option<String>), None)
+        (graphql_port, (Option<u16>), None)
+        (graphql_max_block_range, (u64), 1000)
+        (graphql_max_depth, (usize), 16)
+        (graphql_max_complexity, (usize), 500)
+        (graphql_max_batch_size, (usize), 10)
+        (graphql_enable_mutation, (bool), false)
+        (health_port, (Option<u16>), None)
+        (health_min_peers, (usize), 1)
+        (health_max_epoch_lag, (u64), 50)
         // The network_id, if unset, defaults to the chain_id.
         // Only override the network_id for local experiments,
         // when user would like to keep the existing blockchain data
@@ -224,10 +250,15 @@ build_config! {
         (throttling_conf, (Option<String>), None)
         (timeout_observing_period_s, (u64), 600)
         (transaction_request_timeout_ms, (u64), 30_000)
+        // A JSON file of the checkpoint that a full node with an empty database starts from.
+        (trusted_checkpoint_path, (Option<String>), None)
         (tx_maintained_for_peer_timeout_ms, (u64), 600_000)
 
         // Peer management section.
         (bootnodes, (Option<String>), None)
+        (compress_p2p_packets, (bool), true)
+        (compress_p2p_packets_threshold_bytes, (usize), 1024)
+        (node_allowlist_path, (Option<String>), None)
         (discovery_discover_node_count, (u32), 16)
         (discovery_expire_time_s, (u64), 20)
         (discovery_fast_refresh_timeout_ms, (u64), 10_000)
@@ -253,6 +284,12 @@ build_config! {
         (tx_pool_min_tx_gas_price, (u64), 1)
         (tx_weight_scaling, (u64), 1)
         (tx_weight_exp, (u8), 1)
+        // Comma separated addresses whose transactions are not evicted from
+        // the transaction pool before execution.
+        (tx_pool_local_senders, (Option<String>), None)
+        (tx_pool_max_pending_per_sender, (usize), 2000)
+        (tx_pool_max_nonce_gap, (u64), 2000)
+        (tx_pool_min_replace_price_bump, (u64), 0)
 
         // Storage Section.
         (additional_maintained_snapshot_count, (u32), 1)
@@ -262,6 +299,8 @@ build_config! {
         (additional_maintained_reward_epoch_count, (Option<usize>), None)
         (additional_maintained_trace_epoch_count, (Option<usize>), None)
         (additional_maintained_transaction_index_epoch_count, (Option<usize>), None)
+        (archive_historical_state, (bool), false)
+        (archive_state_snapshot_interval, (u32), cfx_storage::defaults::DEFAULT_ARCHIVE_STATE_SNAPSHOT_INTERVAL)
         (block_cache_gc_period_ms, (u64), 5_000)
         (block_db_dir, (Option<String>), None)
         (block_db_type, (String), "rocksdb".to_string())
@@ -294,10 +333,14 @@ build_config! {
         (get_logs_epoch_batch_size, (usize), 32)
         (max_trans_count_received_in_catch_up, (u64), 60_000)
         (persist_tx_index, (bool), false)
+        (persist_address_tx_index, (bool), false)
+        (address_tx_index_internal, (bool), false)
+        (persist_log_bloom_index, (bool), false)
         (persist_block_number_index, (bool), true)
         (print_memory_usage_period_s, (Option<u64>), None)
         (target_block_gas_limit, (u64), DEFAULT_TARGET_BLOCK_GAS_LIMIT)
         (executive_trace, (bool), false)
+        (parallel_execution_threads, (usize), 0)
         (check_status_genesis, (bool), true)
         (packing_gas_limit_block_count, (u64), 10)
 
@@ -355,9 +398,13 @@ build_config! {
             (Vec<ProvideExtraSnapshotSyncConfig>),
             vec![ProvideExtraSnapshotSyncConfig::StableCheckpoint],
             ProvideExtraSnapshotSyncConfig::parse_config_list)
+        (snapshot_db_type, (SnapshotDbType), SnapshotDbType::Sqlite, SnapshotDbType::from_str)
         (node_type, (Option<NodeType>), None, NodeType::from_str)
+        (p2p_encryption, (EncryptionMode), EncryptionMode::Disabled, EncryptionMode::from_str)
         (public_rpc_apis, (ApiSet), ApiSet::Safe, ApiSet::from_str)
         (public_evm_rpc_apis, (ApiSet), ApiSet::Evm, ApiSet::from_str)
+        (jsonrpc_ipc_apis, (ApiSet), ApiSet::Safe, ApiSet::from_str)
+        (jsonrpc_ipc_eth_apis, (ApiSet), ApiSet::Evm, ApiSet::from_str)
     }
 }
 
@@ -470,6 +517,13 @@ impl Configuration {
         network_config.max_outgoing_peers = self.raw_conf.max_outgoing_peers;
         network_config.max_outgoing_peers_archive =
             self.raw_conf.max_outgoing_peers_archive.unwrap_or(0);
+        if self.raw_conf.compress_p2p_packets {
+            network_config.compression_threshold =
+                Some(self.raw_conf.compress_p2p_packets_threshold_bytes);
+        }
+        network_config.encryption_mode = self.raw_conf.p2p_encryption;
+        network_config.allowlist_path =
+            self.raw_conf.node_allowlist_path.clone();
         Ok(network_config)
     }
 
@@ -606,7 +660,7 @@ impl Configuration {
                     .expect("Stratum secret should be 64-digit hex string")
             });
 
-        ProofOfWorkConfig::new(
+        let mut pow_config = ProofOfWorkConfig::new(
             self.is_test_or_dev_mode(),
             self.raw_conf.use_octopus_in_test_mode,
             self.raw_conf.mining_type.as_ref().map_or_else(
@@ -626,7 +680,19 @@ impl Configuration {
             stratum_secret,
             self.raw_conf.pow_problem_window_size,
             self.common_params().transition_heights.cip86,
-        )
+        );
+        if let Some(difficulty) = self.raw_conf.stratum_share_difficulty {
+            pow_config.stratum_share_difficulty = difficulty;
+        }
+        if let Some(difficulty) = self.raw_conf.stratum_min_share_difficulty {
+            pow_config.stratum_min_share_difficulty = difficulty;
+        }
+        if let Some(interval) = self.raw_conf.stratum_share_interval_ms {
+            pow_config.stratum_share_interval_ms = interval;
+        }
+        pow_config.stratum_require_authorization =
+            self.raw_conf.stratum_require_authorization;
+        pow_config
     }
 
     pub fn verification_config(
@@ -647,10 +713,16 @@ impl Configuration {
             // FIXME: this is not a good condition to check.
             self.raw_conf.genesis_secrets.is_some()
         {
+            let workload = self.raw_conf.txgen_workload.as_ref().map(|path| {
+                WorkloadSpec::from_file(Path::new(path)).unwrap_or_else(|e| {
+                    panic!("Error loading txgen-workload {}", e)
+                })
+            });
             Some(TransactionGeneratorConfig::new(
                 self.raw_conf.generate_tx,
                 self.raw_conf.generate_tx_period_us.expect("has default"),
                 self.raw_conf.txgen_account_count,
+                workload,
             ))
         } else {
             None
@@ -663,12 +735,15 @@ impl Configuration {
             additional_maintained_snapshot_count: self
                 .raw_conf
                 .additional_maintained_snapshot_count,
+            // Historical states are only served by archive nodes, which keep
+            // the execution results of all the epochs.
+            archive_historical_state: self.raw_conf.archive_historical_state
+                && matches!(self.raw_conf.node_type, Some(NodeType::Archive)),
+            archive_state_snapshot_interval: self
+                .raw_conf
+                .archive_state_snapshot_interval,
             consensus_param: ConsensusParam {
-                snapshot_epoch_count: if self.is_test_mode() {
-                    self.raw_conf.dev_snapshot_epoch_count
-                } else {
-                    SNAPSHOT_EPOCHS_CAPACITY
-                },
+                snapshot_epoch_count: self.snapshot_epoch_count(),
             },
             debug_snapshot_checker_threads:
                 DEFAULT_DEBUG_SNAPSHOT_CHECKER_THREADS,
@@ -699,6 +774,7 @@ impl Configuration {
                 .provide_more_snapshot_for_sync
                 .clone(),
             max_open_mpt_count: self.raw_conf.storage_max_open_mpt_count,
+            snapshot_db_type: self.raw_conf.snapshot_db_type,
         }
     }
 
@@ -815,6 +891,11 @@ impl Configuration {
     pub fn data_mananger_config(&self) -> DataManagerConfiguration {
         let mut conf = DataManagerConfiguration {
             persist_tx_index: self.raw_conf.persist_tx_index,
+            persist_address_tx_index: self.raw_conf.persist_address_tx_index,
+            // Internal participants are derived from execution traces.
+            address_tx_index_internal: self.raw_conf.address_tx_index_internal
+                && self.raw_conf.executive_trace,
+            persist_log_bloom_index: self.raw_conf.persist_log_bloom_index,
             persist_block_number_index: self
                 .raw_conf
                 .persist_block_number_index,
@@ -847,6 +928,7 @@ impl Configuration {
                 * self.raw_conf.era_epoch_count as f64)
                 as usize,
             strict_tx_index_gc: self.raw_conf.strict_tx_index_gc,
+            trusted_checkpoint: None,
         };
 
         // By default, we do not keep the block data for additional period,
@@ -917,6 +999,30 @@ impl Configuration {
     }
 
     pub fn txpool_config(&self) -> TxPoolConfig {
+        let network = NetworkConfiguration::network_id_to_known_cfx_network(
+            self.network_id(),
+        );
+        let local_senders = self
+            .raw_conf
+            .tx_pool_local_senders
+            .as_ref()
+            .map_or_else(Default::default, |senders| {
+                senders
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|addr_str| {
+                        parse_local_sender(addr_str, &network).unwrap_or_else(
+                            |err| {
+                                panic!(
+                                    "Error parsing tx-pool-local-senders {}",
+                                    err
+                                )
+                            },
+                        )
+                    })
+                    .collect()
+            });
         TxPoolConfig {
             capacity: self.raw_conf.tx_pool_size,
             max_tx_gas: RwLock::new(U256::from(
@@ -929,14 +1035,23 @@ impl Configuration {
                 .raw_conf
                 .packing_gas_limit_block_count,
             target_block_gas_limit: self.raw_conf.target_block_gas_limit,
+            sender_admission: SenderAdmissionConfig {
+                local_senders,
+                max_pending_per_sender: self
+                    .raw_conf
+                    .tx_pool_max_pending_per_sender,
+                max_nonce_gap: self.raw_conf.tx_pool_max_nonce_gap,
+                min_replace_price_bump: self
+                    .raw_conf
+                    .tx_pool_min_replace_price_bump,
+            },
         }
     }
 
     pub fn rpc_impl_config(&self) -> RpcImplConfiguration {
         RpcImplConfiguration {
             get_logs_filter_max_limit: self.raw_conf.get_logs_filter_max_limit,
-            dev_pack_tx_immediately: self.is_dev_mode()
-                && self.raw_conf.dev_block_interval_ms.is_none(),
+            dev_mode: self.is_dev_mode(),
             max_payload_bytes: self.raw_conf.jsonrpc_ws_max_payload_bytes,
             public_rpc_apis: self.raw_conf.public_rpc_apis.clone(),
         }
@@ -999,9 +1114,44 @@ impl Configuration {
         )
     }
 
+    pub fn ipc_config(&self) -> IpcConfiguration {
+        IpcConfiguration::new(self.raw_conf.jsonrpc_ipc_path.clone())
+    }
+
+    pub fn eth_ipc_config(&self) -> IpcConfiguration {
+        IpcConfiguration::new(self.raw_conf.jsonrpc_ipc_eth_path.clone())
+    }
+
+    pub fn graphql_config(&self) -> GraphQLConfiguration {
+        GraphQLConfiguration::new(
+            None,
+            self.raw_conf.graphql_port,
+            self.raw_conf.graphql_max_block_range,
+            QueryLimits {
+                max_depth: self.raw_conf.graphql_max_depth,
+                max_complexity: self.raw_conf.graphql_max_complexity,
+                max_batch_size: self.raw_conf.graphql_max_batch_size,
+            },
+            self.raw_conf.graphql_enable_mutation,
+            self.raw_conf.throttling_conf.clone(),
+        )
+    }
+
+    pub fn health_config(&self) -> HealthConfiguration {
+        HealthConfiguration::new(
+            None,
+            self.raw_conf.health_port,
+            self.raw_conf.health_min_peers,
+            self.raw_conf.health_max_epoch_lag,
+        )
+    }
+
     pub fn execution_config(&self) -> ConsensusExecutionConfiguration {
         ConsensusExecutionConfiguration {
             executive_trace: self.raw_conf.executive_trace,
+            parallel_execution_threads: self
+                .raw_conf
+                .parallel_execution_threads,
         }
     }
 
@@ -1146,6 +1296,14 @@ impl Configuration {
             .raw_conf
             .hydra_transition_number
             .unwrap_or(default_transition_time);
+        params.transition_numbers.cip94 = self
+            .raw_conf
+            .cip94_transition_number
+            .unwrap_or(default_transition_time);
+        params.transition_numbers.eip2537 = self
+            .raw_conf
+            .eip2537_transition_number
+            .unwrap_or(default_transition_time);
 
         params.transition_heights.cip76 = self
             .raw_conf
@@ -1176,6 +1334,31 @@ impl Configuration {
         self.raw_conf.node_type.unwrap_or(NodeType::Full)
     }
 
+    pub fn trusted_checkpoint(
+        &self,
+    ) -> Result<Option<TrustedCheckpoint>, String> {
+        let path = match &self.raw_conf.trusted_checkpoint_path {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        if self.node_type() != NodeType::Full {
+            return Err(
+                "trusted_checkpoint_path is only supported by full nodes"
+                    .into(),
+            );
+        }
+        TrustedCheckpoint::load(path, self.snapshot_epoch_count() as u64)
+            .map(Some)
+    }
+
+    pub fn snapshot_epoch_count(&self) -> u32 {
+        if self.is_test_mode() {
+            self.raw_conf.dev_snapshot_epoch_count
+        } else {
+            SNAPSHOT_EPOCHS_CAPACITY
+        }
+    }
+
     pub fn pos_state_config(&self) -> PosStateConfig {
         PosStateConfig::new(
             self.raw_conf.pos_round_per_term,
@@ -1246,11 +1429,27 @@ pub fn parse_config_address_string(
                 base32_err, hex_err))
 }
 
+/// Parses a local sender of the transaction pool. Base32 addresses are in
+/// the core space, and hex addresses are in the eSpace.
+fn parse_local_sender(
+    addr: &str, network: &Network,
+) -> Result<AddressWithSpace, String> {
+    let address = parse_config_address_string(addr, network)?;
+    if cfx_addr_decode(addr).is_ok() {
+        Ok(address.with_native_space())
+    } else {
+        Ok(address.with_evm_space())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use cfx_addr::Network;
+    use cfx_types::{AddressSpaceUtil, Space};
 
-    use crate::configuration::parse_config_address_string;
+    use crate::configuration::{
+        parse_config_address_string, parse_local_sender,
+    };
 
     #[test]
     fn test_config_address_string() {
@@ -1287,4 +1486,20 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    fn test_local_sender_space() {
+        let base32 = parse_local_sender(
+            "cfx:aarc9abycue0hhzgyrr53m6cxedgccrmmyybjgh4xg",
+            &Network::Main,
+        )
+        .unwrap();
+        assert_eq!(base32.space, Space::Native);
+        let hex = parse_local_sender(
+            "0x1a2f80341409639ea6a35bbcab8299066109aa55",
+            &Network::Main,
+        )
+        .unwrap();
+        assert_eq!(hex, base32.address.with_evm_space());
+    }
 }
@@ -0,0 +1,98 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::configuration::Configuration;
+use cfx_storage::StorageManager;
+use cfxcore::{
+    block_data_manager::{
+        db_checker::check_block_db, db_manager::DBManager, DbType,
+    },
+    pow::PowComputer,
+};
+use std::{fmt::Write, path::Path, sync::Arc};
+
+/// Check the integrity of the data directory of a stopped node, and return
+/// the report. The blocks and the execution results of the epochs in
+/// `[from_epoch, to_epoch]` are checked against the roots they commit to,
+/// and every current snapshot is checked against its merkle root.
+///
+/// With `repair`, the inconsistent execution results and snapshots are
+/// removed, so that the node re-executes the epochs and re-syncs the
+/// snapshots after restart. The inconsistent blocks are only reported.
+pub fn check_db(
+    conf: &Configuration, from_epoch: Option<u64>, to_epoch: Option<u64>,
+    repair: bool,
+) -> Result<String, String>
+{
+    let pow = Arc::new(PowComputer::new(conf.pow_config().use_octopus()));
+    let data_man_conf = conf.data_mananger_config();
+    let db_manager = match data_man_conf.db_type {
+        DbType::Rocksdb => {
+            let (db_path, db_config) = conf.db_config();
+            let ledger_db =
+                db::open_database(db_path.to_str().unwrap(), &db_config)
+                    .map_err(|e| format!("Failed to open database {:?}", e))?;
+            DBManager::new_from_rocksdb(ledger_db, pow)
+        }
+        DbType::Sqlite => {
+            DBManager::new_from_sqlite(Path::new("./sqlite_db"), pow)
+        }
+    };
+    let block_report = check_block_db(
+        &db_manager,
+        from_epoch,
+        to_epoch,
+        data_man_conf.additional_maintained_execution_result_epoch_count,
+        repair,
+    );
+
+    let storage_manager = StorageManager::new(conf.storage_config())
+        .map_err(|e| format!("Failed to open storage: {}", e))?;
+    let invalid_snapshots = storage_manager
+        .get_storage_manager()
+        .check_snapshots(repair);
+
+    let mut output = String::new();
+    writeln!(
+        output,
+        "Checked {} blocks in {} epochs, {} epochs not executed, {} epochs \
+         with execution results garbage collected, {} block bodies not in db.",
+        block_report.checked_blocks,
+        block_report.checked_epochs,
+        block_report.unexecuted_epochs,
+        block_report.gc_execution_result_epochs,
+        block_report.missing_bodies
+    )
+    .unwrap();
+    for inconsistency in &block_report.inconsistencies {
+        writeln!(output, "{}", inconsistency).unwrap();
+    }
+    for (snapshot_epoch_id, reason) in &invalid_snapshots {
+        writeln!(output, "snapshot {:?}: {}", snapshot_epoch_id, reason)
+            .unwrap();
+    }
+
+    let inconsistencies =
+        block_report.inconsistencies.len() + invalid_snapshots.len();
+    if inconsistencies == 0 {
+        write!(output, "No inconsistency found.").unwrap();
+    } else if repair {
+        write!(
+            output,
+            "Found {} inconsistencies, repaired {}.",
+            inconsistencies,
+            block_report.repaired as usize + invalid_snapshots.len()
+        )
+        .unwrap();
+    } else {
+        write!(
+            output,
+            "Found {} inconsistencies, run with --repair to remove the \
+             inconsistent data.",
+            inconsistencies
+        )
+        .unwrap();
+    }
+    Ok(output)
+}
@@ -3,12 +3,14 @@
 // See http://www.gnu.org/licenses/
 
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WsServer;
 
 use crate::{
     common::{initialize_not_light_node_modules, ClientComponents},
     configuration::Configuration,
+    rpc::hyper_server::HyperServer,
 };
 use blockgen::BlockGenerator;
 use cfxcore::{
@@ -33,6 +35,10 @@ pub struct FullClientExtraComponents {
     pub txpool: Arc<TransactionPool>,
     pub pow: Arc<PowComputer>,
     pub eth_rpc_http_server: Option<HttpServer>,
+    pub rpc_ipc_server: Option<IpcServer>,
+    pub eth_rpc_ipc_server: Option<IpcServer>,
+    pub graphql_server: Option<HyperServer>,
+    pub health_server: Option<HyperServer>,
 }
 
 impl MallocSizeOf for FullClientExtraComponents {
@@ -65,6 +71,10 @@ impl FullClient {
             pos_handler,
             runtime,
             eth_rpc_http_server,
+            rpc_ipc_server,
+            eth_rpc_ipc_server,
+            graphql_server,
+            health_server,
         ) = initialize_not_light_node_modules(&mut conf, exit, NodeType::Full)?;
         Ok(Box::new(ClientComponents {
             data_manager_weak_ptr: Arc::downgrade(&data_man),
@@ -83,6 +93,10 @@ impl FullClient {
                 txpool,
                 pow,
                 eth_rpc_http_server,
+                rpc_ipc_server,
+                eth_rpc_ipc_server,
+                graphql_server,
+                health_server,
             },
         }))
     }
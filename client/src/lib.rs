@@ -20,8 +20,10 @@ pub mod accounts;
 pub mod archive;
 pub mod common;
 pub mod configuration;
+pub mod db_check;
 pub mod full;
 pub mod light;
+pub mod logging;
 pub mod rpc;
 
 /// Used in Genesis author to indicate test-net/main-net version.
@@ -8,6 +8,7 @@ use parking_lot::{Condvar, Mutex};
 use secret_store::SecretStore;
 
 use jsonrpc_http_server::Server as HttpServer;
+use jsonrpc_ipc_server::Server as IpcServer;
 use jsonrpc_tcp_server::Server as TcpServer;
 use jsonrpc_ws_server::Server as WsServer;
 
@@ -16,7 +17,8 @@ use crate::{
     configuration::Configuration,
     rpc::{
         extractor::RpcExtractor, impls::light::RpcImpl,
-        setup_debug_rpc_apis_light, setup_public_rpc_apis_light,
+        setup_debug_rpc_apis_light, setup_ipc_rpc_apis_light,
+        setup_public_rpc_apis_light,
     },
 };
 use blockgen::BlockGenerator;
@@ -36,6 +38,7 @@ pub struct LightClientExtraComponents {
     pub rpc_http_server: Option<HttpServer>,
     pub rpc_tcp_server: Option<TcpServer>,
     pub rpc_ws_server: Option<WsServer>,
+    pub rpc_ipc_server: Option<IpcServer>,
     pub runtime: Runtime,
     pub secret_store: Arc<SecretStore>,
     pub txpool: Arc<TransactionPool>,
@@ -152,6 +155,17 @@ impl LightClient {
             RpcExtractor,
         )?;
 
+        let rpc_ipc_server = super::rpc::start_ipc(
+            conf.ipc_config(),
+            setup_ipc_rpc_apis_light(
+                common_impl.clone(),
+                rpc_impl.clone(),
+                pubsub.clone(),
+                &conf,
+            ),
+            RpcExtractor,
+        )?;
+
         let rpc_http_server = super::rpc::start_http(
             conf.http_config(),
             setup_public_rpc_apis_light(
@@ -177,6 +191,7 @@ impl LightClient {
                 rpc_http_server,
                 rpc_tcp_server,
                 rpc_ws_server,
+                rpc_ipc_server,
                 runtime,
                 secret_store,
                 txpool,
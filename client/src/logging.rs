@@ -0,0 +1,380 @@
+// Copyright 2022 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Initialization of log4rs, and the runtime change of log levels.
+//!
+//! The log config is always rebuilt from its source (the `log_conf` file or
+//! `log_file` and `log_level`) with the levels set by Debug RPC applied on top
+//! of it, so the levels set at runtime survive the refresh of `log_conf`.
+
+use crate::configuration::Configuration;
+use lazy_static::lazy_static;
+use log::LevelFilter;
+use log4rs::{
+    append::{console::ConsoleAppender, file::FileAppender},
+    config::{
+        load_config_file, Appender, Config as LogConfig, Deserializers, Logger,
+        RawConfig, Root,
+    },
+    encode::pattern::PatternEncoder,
+    Handle,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// The name of the root logger in `set_log_level`.
+pub const ROOT_LOGGER: &str = "root";
+
+// Should add new crate names here
+const CRATE_NAMES: &[&str] = &[
+    "blockgen",
+    "cfxcore",
+    "cfx_statedb",
+    "cfx_storage",
+    "conflux",
+    "db",
+    "keymgr",
+    "network",
+    "txgen",
+    "client",
+    "primitives",
+    "io",
+];
+
+enum LogSource {
+    File(PathBuf),
+    Default {
+        log_file: Option<String>,
+        log_level: LevelFilter,
+    },
+}
+
+impl LogSource {
+    fn build(&self) -> Result<LogConfig, String> {
+        match self {
+            LogSource::File(path) => {
+                load_config_file(path, Deserializers::default()).map_err(|e| {
+                    format!(
+                        "failed to initialize log with log config file: {:?}",
+                        e
+                    )
+                })
+            }
+            LogSource::Default {
+                log_file,
+                log_level,
+            } => {
+                // Set stdout to INFO and set all our crate log to log_level.
+                let mut conf_builder =
+                    LogConfig::builder().appender(Appender::builder().build(
+                        "stdout",
+                        Box::new(ConsoleAppender::builder().build()),
+                    ));
+                let mut root_builder = Root::builder().appender("stdout");
+                if let Some(log_file) = log_file {
+                    conf_builder =
+                        conf_builder.appender(Appender::builder().build(
+                            "logfile",
+                            Box::new(
+                                FileAppender::builder().encoder(
+                                    Box::new(
+                                        PatternEncoder::new(
+                                            "{d} {h({l}):5.5} {T:<20.20} {t:12.12} - {m}{n}")))
+                                    .build(log_file)
+                                    .map_err(
+                                        |e| format!("failed to build log pattern: {:?}", e))?,
+                            ),
+                        ));
+                    root_builder = root_builder.appender("logfile");
+                };
+                for crate_name in CRATE_NAMES {
+                    conf_builder = conf_builder.logger(
+                        Logger::builder().build(*crate_name, *log_level),
+                    );
+                }
+                conf_builder
+                    .build(root_builder.build(LevelFilter::Info))
+                    .map_err(|e| format!("failed to build log config: {:?}", e))
+            }
+        }
+    }
+}
+
+struct LogController {
+    handle: Handle,
+    source: LogSource,
+    /// The levels set at runtime, keyed by the logger name.
+    overrides: BTreeMap<String, LevelFilter>,
+}
+
+impl LogController {
+    fn build_config(&self) -> Result<LogConfig, String> {
+        let config = self.source.build()?;
+        if self.overrides.is_empty() {
+            return Ok(config);
+        }
+
+        let (appenders, root, loggers) = config.unpack();
+        let mut builder = LogConfig::builder().appenders(appenders);
+        let mut overrides = self.overrides.clone();
+        overrides.remove(ROOT_LOGGER);
+        for logger in loggers {
+            // Keep the appenders of the overridden loggers.
+            let logger = match overrides.remove(logger.name()) {
+                Some(level) => Logger::builder()
+                    .appenders(logger.appenders().iter().cloned())
+                    .additive(logger.additive())
+                    .build(logger.name(), level),
+                None => logger,
+            };
+            builder = builder.logger(logger);
+        }
+        // The new loggers inherit the appenders of their parents.
+        for (name, level) in overrides {
+            builder = builder.logger(Logger::builder().build(name, level));
+        }
+        let root_level = self
+            .overrides
+            .get(ROOT_LOGGER)
+            .cloned()
+            .unwrap_or_else(|| root.level());
+        let root = Root::builder()
+            .appenders(root.appenders().iter().cloned())
+            .build(root_level);
+        builder
+            .build(root)
+            .map_err(|e| format!("failed to build log config: {:?}", e))
+    }
+
+    /// The effective level of a module, which is the level of the logger with
+    /// the longest matching name.
+    fn effective_level(&self, module: &str) -> Result<LevelFilter, String> {
+        let config = self.build_config()?;
+        if module == ROOT_LOGGER {
+            return Ok(config.root().level());
+        }
+        let level = config
+            .loggers()
+            .iter()
+            .filter(|logger| {
+                let name = logger.name();
+                module == name
+                    || (module.starts_with(name)
+                        && module[name.len()..].starts_with("::"))
+            })
+            .max_by_key(|logger| logger.name().len())
+            .map_or(config.root().level(), |logger| logger.level());
+        Ok(level)
+    }
+
+    fn apply(&self) -> Result<(), String> {
+        self.handle.set_config(self.build_config()?);
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref LOG_CONTROLLER: Mutex<Option<LogController>> = Mutex::new(None);
+}
+
+/// Result of changing the level of a logger, which is used by Debug RPC.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevelChange {
+    pub module: String,
+    pub previous: String,
+    pub current: String,
+}
+
+/// Initialize log4rs. If `log_conf` is provided, use it for log configuration
+/// and ignore `log_file` and `log_level`.
+pub fn init_logging(conf: &Configuration) -> Result<(), String> {
+    let source = match conf.raw_conf.log_conf {
+        Some(ref log_conf) => LogSource::File(PathBuf::from(log_conf)),
+        None => LogSource::Default {
+            log_file: conf.raw_conf.log_file.clone(),
+            log_level: conf.raw_conf.log_level,
+        },
+    };
+    let handle = log4rs::init_config(source.build()?).map_err(|e| {
+        format!("failed to initialize log with config: {:?}", e)
+    })?;
+    if let LogSource::File(ref path) = source {
+        if let Some(refresh_rate) = log_conf_refresh_rate(path) {
+            watch_log_conf(path.clone(), refresh_rate);
+        }
+    }
+    *LOG_CONTROLLER.lock() = Some(LogController {
+        handle,
+        source,
+        overrides: BTreeMap::new(),
+    });
+    Ok(())
+}
+
+/// Set the level of the logger `module` (or `root`), and return the effective
+/// levels before and after the change.
+pub fn set_log_level(
+    module: String, level: &str,
+) -> Result<LogLevelChange, String> {
+    let level = LevelFilter::from_str(level)
+        .map_err(|_| format!("invalid log level: {}", level))?;
+    let mut guard = LOG_CONTROLLER.lock();
+    let controller = guard
+        .as_mut()
+        .ok_or_else(|| "log is not initialized".to_string())?;
+
+    let previous = controller.effective_level(&module)?;
+    let overridden = controller.overrides.insert(module.clone(), level);
+    if let Err(e) = controller.apply() {
+        match overridden {
+            Some(overridden) => {
+                controller.overrides.insert(module.clone(), overridden)
+            }
+            None => controller.overrides.remove(&module),
+        };
+        return Err(e);
+    }
+    info!(
+        "Log level of {} changed from {} to {}",
+        module, previous, level
+    );
+
+    Ok(LogLevelChange {
+        module,
+        previous: previous.to_string().to_lowercase(),
+        current: level.to_string().to_lowercase(),
+    })
+}
+
+/// The `refresh_rate` set in `log_conf`, or `None` if it is not set or the
+/// file fails to parse.
+fn log_conf_refresh_rate(path: &Path) -> Option<Duration> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_yaml::from_str::<RawConfig>(&content)
+        .ok()?
+        .refresh_rate()
+}
+
+/// Reload `log_conf` when it is modified, and keep the levels set at runtime.
+/// The file is checked every `refresh_rate` of the last loaded config, and
+/// the watching stops once the `refresh_rate` is removed from the file.
+fn watch_log_conf(path: PathBuf, mut refresh_rate: Duration) {
+    let modified =
+        |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last_modified: Option<SystemTime> = modified(&path);
+    thread::Builder::new()
+        .name("log_conf_watcher".into())
+        .spawn(move || loop {
+            thread::sleep(refresh_rate);
+            let current = modified(&path);
+            if current == last_modified {
+                continue;
+            }
+            last_modified = current;
+            if let Some(controller) = LOG_CONTROLLER.lock().as_ref() {
+                if let Err(e) = controller.apply() {
+                    warn!("Failed to reload log config: {}", e);
+                    continue;
+                }
+            }
+            match log_conf_refresh_rate(&path) {
+                Some(new_refresh_rate) => refresh_rate = new_refresh_rate,
+                None => {
+                    info!("Stop watching log config without refresh_rate");
+                    break;
+                }
+            }
+        })
+        .expect("spawn log_conf watcher thread");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Once;
+    use tempdir::TempDir;
+
+    /// Initialize the logger with the default config only once, since the
+    /// global logger can only be set once in the test process.
+    fn init_test_logging() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            let source = LogSource::Default {
+                log_file: None,
+                log_level: LevelFilter::Info,
+            };
+            let handle = log4rs::init_config(source.build().unwrap()).unwrap();
+            *LOG_CONTROLLER.lock() = Some(LogController {
+                handle,
+                source,
+                overrides: BTreeMap::new(),
+            });
+        });
+    }
+
+    fn effective_level(module: &str) -> LevelFilter {
+        LOG_CONTROLLER
+            .lock()
+            .as_ref()
+            .unwrap()
+            .effective_level(module)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_set_log_level() {
+        init_test_logging();
+
+        let change =
+            set_log_level("client::logging_test".into(), "debug").unwrap();
+        assert_eq!(change.previous, "info");
+        assert_eq!(change.current, "debug");
+        assert_eq!(
+            effective_level("client::logging_test::sub"),
+            LevelFilter::Debug
+        );
+        // The parent logger is not changed.
+        assert_eq!(effective_level("client"), LevelFilter::Info);
+
+        let change =
+            set_log_level("client::logging_test".into(), "WARN").unwrap();
+        assert_eq!(change.previous, "debug");
+        assert_eq!(change.current, "warn");
+    }
+
+    #[test]
+    fn test_set_log_level_rejected() {
+        init_test_logging();
+
+        let err = set_log_level("client::logging_rejected".into(), "verbose")
+            .unwrap_err();
+        assert!(err.contains("invalid log level"), "{}", err);
+        assert_eq!(
+            effective_level("client::logging_rejected"),
+            LevelFilter::Info
+        );
+    }
+
+    #[test]
+    fn test_log_conf_refresh_rate() {
+        let dir = TempDir::new("log_conf").unwrap();
+        let path = dir.path().join("log.yaml");
+        let root = "root:\n  level: info\n";
+
+        fs::write(&path, format!("refresh_rate: 5 seconds\n{}", root))
+            .unwrap();
+        assert_eq!(log_conf_refresh_rate(&path), Some(Duration::from_secs(5)));
+
+        fs::write(&path, root).unwrap();
+        assert_eq!(log_conf_refresh_rate(&path), None);
+    }
+}
@@ -2,11 +2,15 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use jsonrpc_core::{MetaIoHandler, Result as JsonRpcResult};
+use jsonrpc_core::{MetaIoHandler, RemoteProcedure, Result as JsonRpcResult};
 use jsonrpc_http_server::{
     AccessControlAllowOrigin, DomainsValidation, Server as HttpServer,
     ServerBuilder as HttpServerBuilder,
 };
+use jsonrpc_ipc_server::{
+    MetaExtractor as IpcMetaExtractor, Server as IpcServer,
+    ServerBuilder as IpcServerBuilder,
+};
 use jsonrpc_tcp_server::{
     MetaExtractor as TpcMetaExtractor, Server as TcpServer,
     ServerBuilder as TcpServerBuilder,
@@ -15,16 +19,23 @@ use jsonrpc_ws_server::{
     MetaExtractor as WsMetaExtractor, Server as WsServer,
     ServerBuilder as WsServerBuilder,
 };
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
 use std::{
+    collections::BTreeMap,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
 };
 
 mod authcodes;
 pub mod error_codes;
 pub mod extractor;
+pub mod graphql;
+pub mod health;
 mod helpers;
 mod http_common;
+pub mod hyper_server;
 pub mod impls;
 pub mod informant;
 mod interceptor;
@@ -54,7 +65,10 @@ use self::{
     traits::{
         cfx::Cfx,
         debug::LocalRpc,
-        eth_space::{eth::Eth, trace::Trace as EthTrace},
+        eth_space::{
+            dev::EthDev, eth::Eth, trace::Trace as EthTrace,
+            txpool::TxPool as EthTxPool,
+        },
         pool::TransactionPool,
         pos::Pos,
         pubsub::PubSub,
@@ -73,6 +87,7 @@ use crate::{
         rpc_apis::{Api, ApiSet},
     },
 };
+use cfx_types::Space;
 pub use metadata::Metadata;
 use std::collections::HashSet;
 use throttling::token_bucket::{ThrottleResult, TokenBucketManager};
@@ -163,6 +178,21 @@ impl WsConfiguration {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct IpcConfiguration {
+    pub enabled: bool,
+    pub socket_path: PathBuf,
+}
+
+impl IpcConfiguration {
+    pub fn new(socket_path: Option<String>) -> Self {
+        IpcConfiguration {
+            enabled: socket_path.is_some(),
+            socket_path: socket_path.unwrap_or_default().into(),
+        }
+    }
+}
+
 pub fn setup_public_rpc_apis(
     common: Arc<CommonImpl>, rpc: Arc<RpcImpl>, pubsub: PubSubClient,
     conf: &Configuration,
@@ -175,6 +205,7 @@ pub fn setup_public_rpc_apis(
         &conf.raw_conf.throttling_conf,
         "rpc",
         conf.raw_conf.public_rpc_apis.list_apis(),
+        Space::Native,
     )
 }
 
@@ -190,6 +221,7 @@ pub fn setup_public_eth_rpc_apis(
         &conf.raw_conf.throttling_conf,
         "rpc",
         conf.raw_conf.public_evm_rpc_apis.list_apis(),
+        Space::Ethereum,
     )
 }
 
@@ -205,15 +237,59 @@ pub fn setup_debug_rpc_apis(
         &conf.raw_conf.throttling_conf,
         "rpc_local",
         ApiSet::All.list_apis(),
+        Space::Native,
+    )
+}
+
+pub fn setup_ipc_rpc_apis(
+    common: Arc<CommonImpl>, rpc: Arc<RpcImpl>, pubsub: PubSubClient,
+    conf: &Configuration,
+) -> MetaIoHandler<Metadata>
+{
+    setup_rpc_apis(
+        common,
+        rpc,
+        pubsub,
+        &conf.raw_conf.throttling_conf,
+        "rpc_local",
+        conf.raw_conf.jsonrpc_ipc_apis.list_apis(),
+        Space::Native,
+    )
+}
+
+pub fn setup_ipc_eth_rpc_apis(
+    common: Arc<CommonImpl>, rpc: Arc<RpcImpl>, pubsub: PubSubClient,
+    conf: &Configuration,
+) -> MetaIoHandler<Metadata>
+{
+    setup_rpc_apis(
+        common,
+        rpc,
+        pubsub,
+        &conf.raw_conf.throttling_conf,
+        "rpc_local",
+        conf.raw_conf.jsonrpc_ipc_eth_apis.list_apis(),
+        Space::Ethereum,
     )
 }
 
+/// `space` is the space of the port. The `txpool` API is the one of this
+/// space, because the `txpool_*` RPCs of both spaces share names.
 fn setup_rpc_apis(
     common: Arc<CommonImpl>, rpc: Arc<RpcImpl>, pubsub: PubSubClient,
     throttling_conf: &Option<String>, throttling_section: &str,
-    apis: HashSet<Api>,
+    apis: HashSet<Api>, space: Space,
 ) -> MetaIoHandler<Metadata>
 {
+    let eth_handler = || {
+        EthHandler::new(
+            rpc.config.clone(),
+            rpc.consensus.clone(),
+            rpc.sync.clone(),
+            rpc.tx_pool.clone(),
+            rpc.block_gen.clone(),
+        )
+    };
     let mut handler = MetaIoHandler::default();
     for api in apis {
         match api {
@@ -228,13 +304,7 @@ fn setup_rpc_apis(
             }
             Api::Eth => {
                 info!("Add EVM RPC");
-                let evm = EthHandler::new(
-                    rpc.config.clone(),
-                    rpc.consensus.clone(),
-                    rpc.sync.clone(),
-                    rpc.tx_pool.clone(),
-                )
-                .to_delegate();
+                let evm = Eth::to_delegate(eth_handler());
                 let evm_trace_handler = EthTraceHandler {
                     trace_handler: TraceHandler::new(
                         rpc.consensus.get_data_manager().clone(),
@@ -250,6 +320,9 @@ fn setup_rpc_apis(
                 handler.extend_with(RpcProxy::new(evm, interceptor));
                 // TODO(lpl): Set this separately.
                 handler.extend_with(evm_trace_handler);
+                if rpc.config.dev_mode {
+                    handler.extend_with(EthDev::to_delegate(eth_handler()));
+                }
             }
             Api::Debug => {
                 handler.extend_with(
@@ -276,11 +349,12 @@ fn setup_rpc_apis(
                 );
                 handler.extend_with(RpcProxy::new(trace, interceptor));
             }
-            Api::TxPool => {
-                let txpool =
-                    TransactionPoolHandler::new(common.clone()).to_delegate();
-                handler.extend_with(txpool);
-            }
+            Api::TxPool => extend_with_txpool(
+                &mut handler,
+                space,
+                || TransactionPoolHandler::new(common.clone()).to_delegate(),
+                || EthTxPool::to_delegate(eth_handler()),
+            ),
             Api::Pos => {
                 let pos = PosHandler::new(
                     common.pos_handler.clone(),
@@ -297,6 +371,22 @@ fn setup_rpc_apis(
     handler
 }
 
+/// Adds the core space or the geth compatible eSpace `txpool_*` RPCs
+/// according to `space`. Only one of them is served on a port, otherwise
+/// the methods with the same name overwrite each other.
+fn extend_with_txpool<C, E>(
+    handler: &mut MetaIoHandler<Metadata>, space: Space,
+    core_txpool: impl FnOnce() -> C, eth_txpool: impl FnOnce() -> E,
+) where
+    C: IntoIterator<Item = (String, RemoteProcedure<Metadata>)>,
+    E: IntoIterator<Item = (String, RemoteProcedure<Metadata>)>,
+{
+    match space {
+        Space::Native => handler.extend_with(core_txpool()),
+        Space::Ethereum => handler.extend_with(eth_txpool()),
+    }
+}
+
 pub fn setup_public_rpc_apis_light(
     common: Arc<CommonImpl>, rpc: Arc<LightImpl>, pubsub: PubSubClient,
     conf: &Configuration,
@@ -329,6 +419,21 @@ pub fn setup_debug_rpc_apis_light(
     )
 }
 
+pub fn setup_ipc_rpc_apis_light(
+    common: Arc<CommonImpl>, rpc: Arc<LightImpl>, pubsub: PubSubClient,
+    conf: &Configuration,
+) -> MetaIoHandler<Metadata>
+{
+    setup_rpc_apis_light(
+        common,
+        rpc,
+        pubsub,
+        &conf.raw_conf.throttling_conf,
+        "rpc_local",
+        conf.raw_conf.jsonrpc_ipc_apis.list_apis(),
+    )
+}
+
 fn setup_rpc_apis_light(
     common: Arc<CommonImpl>, rpc: Arc<LightImpl>, pubsub: PubSubClient,
     throttling_conf: &Option<String>, throttling_section: &str,
@@ -444,8 +549,199 @@ where
     }
 }
 
+/// Start a JSON-RPC server on a Unix domain socket (a named pipe on
+/// Windows). The socket file is only accessible by the owner, so the access
+/// control is left to the file system.
+pub fn start_ipc<H, T>(
+    conf: IpcConfiguration, handler: H, extractor: T,
+) -> Result<Option<IpcServer>, String>
+where
+    H: Into<MetaIoHandler<Metadata>>,
+    T: IpcMetaExtractor<Metadata> + 'static,
+{
+    if !conf.enabled {
+        return Ok(None);
+    }
+
+    remove_stale_ipc_socket(&conf.socket_path)?;
+    let server = bind_owner_only(&conf.socket_path, |path| {
+        IpcServerBuilder::with_meta_extractor(handler, extractor)
+            .start(path)
+            .map_err(|io_error| {
+                format!("IPC error: {} (path = {})", io_error, path)
+            })
+    })?;
+    Ok(Some(server))
+}
+
+/// Remove the socket file left by a previous process, otherwise binding the
+/// socket fails.
+#[cfg(unix)]
+fn remove_stale_ipc_socket(path: &Path) -> Result<(), String> {
+    use std::{fs, os::unix::fs::FileTypeExt};
+
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            fs::remove_file(path).map_err(|e| {
+                format!("IPC error: failed to remove {:?}: {}", path, e)
+            })
+        }
+        Ok(_) => Err(format!("IPC error: {:?} is not a socket file", path)),
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn remove_stale_ipc_socket(_path: &Path) -> Result<(), String> { Ok(()) }
+
+/// Bind the socket in a new directory only accessible by the owner, and move
+/// it to `path` after its permissions are restricted to the owner, so that
+/// others can never connect to it. The server is closed if the permissions
+/// can not be restricted.
+#[cfg(unix)]
+fn bind_owner_only<F>(path: &Path, bind: F) -> Result<IpcServer, String>
+where F: FnOnce(&str) -> Result<IpcServer, String> {
+    use std::{
+        fs,
+        os::unix::fs::{DirBuilderExt, PermissionsExt},
+    };
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("IPC error: invalid socket path {:?}", path))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    // Creating the directory fails if it exists, so it is never shared with
+    // others.
+    let bind_dir = parent.join(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&bind_dir)
+        .map_err(|e| {
+            format!("IPC error: failed to create {:?}: {}", bind_dir, e)
+        })?;
+
+    let bind_path = bind_dir.join(file_name);
+    let result = bind(&bind_path.to_string_lossy()).and_then(|server| {
+        let restricted = fs::set_permissions(
+            &bind_path,
+            fs::Permissions::from_mode(0o600),
+        )
+        .and_then(|_| fs::rename(&bind_path, path));
+        match restricted {
+            Ok(()) => Ok(server),
+            Err(e) => {
+                server.close();
+                Err(format!(
+                    "IPC error: failed to restrict {:?} to the owner: {}",
+                    path, e
+                ))
+            }
+        }
+    });
+    fs::remove_dir_all(&bind_dir).ok();
+    result
+}
+
+#[cfg(not(unix))]
+fn bind_owner_only<F>(path: &Path, bind: F) -> Result<IpcServer, String>
+where F: FnOnce(&str) -> Result<IpcServer, String> {
+    bind(&path.to_string_lossy())
+}
+
+/// A token bucket manager of `ThrottleInterceptor`, registered so that it can
+/// be reloaded at runtime.
+struct ThrottlingRegistration {
+    file: Option<String>,
+    section: String,
+    manager: Weak<RwLock<TokenBucketManager>>,
+}
+
+lazy_static! {
+    static ref THROTTLING_REGISTRY: Mutex<Vec<ThrottlingRegistration>> =
+        Mutex::new(Vec::new());
+}
+
+/// Result of reloading a throttling section, which is used by Debug RPC.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottlingReloadResult {
+    pub section: String,
+    pub file: String,
+    pub previous: BTreeMap<String, String>,
+    pub current: BTreeMap<String, String>,
+}
+
+/// Reload the token buckets of all the RPC throttling interceptors from
+/// `file`, or from the configured `throttling_conf` if `file` is `None`.
+/// Nothing is changed if any section fails to load.
+pub fn reload_throttling(
+    file: Option<String>,
+) -> Result<Vec<ThrottlingReloadResult>, String> {
+    let mut registry = THROTTLING_REGISTRY.lock();
+    registry.retain(|r| r.manager.strong_count() > 0);
+
+    // Load all the sections before changing any interceptor.
+    let mut sections: BTreeMap<String, (String, Vec<TokenBucketManager>)> =
+        BTreeMap::new();
+    for registration in registry.iter() {
+        let path = match file.as_ref().or(registration.file.as_ref()) {
+            Some(path) => path.clone(),
+            None => {
+                return Err("throttling_conf is not configured".into());
+            }
+        };
+        let manager =
+            TokenBucketManager::load(&path, Some(&registration.section))?;
+        sections
+            .entry(registration.section.clone())
+            .or_insert_with(|| (path, Vec::new()))
+            .1
+            .push(manager);
+    }
+
+    let mut results: BTreeMap<String, ThrottlingReloadResult> = BTreeMap::new();
+    for registration in registry.iter() {
+        let (path, managers) = sections
+            .get_mut(&registration.section)
+            .expect("loaded above");
+        let new_manager = managers.pop().expect("loaded above");
+        let current = new_manager.describe();
+        let manager = match registration.manager.upgrade() {
+            Some(manager) => manager,
+            None => continue,
+        };
+        let previous =
+            std::mem::replace(&mut *manager.write(), new_manager).describe();
+        results
+            .entry(registration.section.clone())
+            .or_insert_with(|| ThrottlingReloadResult {
+                section: registration.section.clone(),
+                file: path.clone(),
+                previous,
+                current,
+            });
+    }
+
+    info!("RPC throttling reloaded, {:?}", results);
+    Ok(results.into_values().collect())
+}
+
+#[cfg(test)]
+lazy_static! {
+    /// Serializes the tests which create `ThrottleInterceptor`s, because
+    /// reloading the throttling applies to all the registered ones.
+    static ref THROTTLING_TEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
 struct ThrottleInterceptor {
-    manager: TokenBucketManager,
+    manager: Arc<RwLock<TokenBucketManager>>,
 }
 
 impl ThrottleInterceptor {
@@ -455,6 +751,12 @@ impl ThrottleInterceptor {
                 .expect("invalid throttling configuration file"),
             None => TokenBucketManager::default(),
         };
+        let manager = Arc::new(RwLock::new(manager));
+        THROTTLING_REGISTRY.lock().push(ThrottlingRegistration {
+            file: file.clone(),
+            section: section.into(),
+            manager: Arc::downgrade(&manager),
+        });
 
         ThrottleInterceptor { manager }
     }
@@ -462,7 +764,7 @@ impl ThrottleInterceptor {
 
 impl RpcInterceptor for ThrottleInterceptor {
     fn before(&self, name: &String) -> JsonRpcResult<()> {
-        let bucket = match self.manager.get(name) {
+        let bucket = match self.manager.read().get(name) {
             Some(bucket) => bucket,
             None => return Ok(()),
         };
@@ -487,3 +789,147 @@ impl RpcInterceptor for ThrottleInterceptor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        extend_with_txpool, reload_throttling, Metadata, ThrottleInterceptor,
+        THROTTLING_TEST_LOCK,
+    };
+    use cfx_types::Space;
+    use jsonrpc_core::{IoDelegate, MetaIoHandler, Params, Value};
+    use serde_json::json;
+    use std::{fs, sync::Arc};
+    use tempdir::TempDir;
+    #[cfg(unix)]
+    use {
+        super::{extractor::RpcExtractor, start_ipc, IpcConfiguration},
+        std::{
+            io::{BufRead, BufReader, Write},
+            os::unix::{fs::PermissionsExt, net::UnixStream},
+        },
+    };
+
+    /// Serves only `txpool_status`, which answers with `status`.
+    fn txpool_status_delegate(status: Value) -> IoDelegate<Value, Metadata> {
+        let mut delegate = IoDelegate::new(Arc::new(status));
+        delegate.add_method("txpool_status", |status: &Value, _: Params| {
+            Ok(status.clone())
+        });
+        delegate
+    }
+
+    fn txpool_status(space: Space) -> String {
+        let mut handler = MetaIoHandler::<Metadata>::default();
+        extend_with_txpool(
+            &mut handler,
+            space,
+            || txpool_status_delegate(json!({ "unexecuted": "0x0" })),
+            || txpool_status_delegate(json!({ "queued": "0x0" })),
+        );
+        handler
+            .handle_request_sync(
+                r#"{"jsonrpc":"2.0","method":"txpool_status","id":1}"#,
+                Metadata::default(),
+            )
+            .expect("txpool_status is a method call")
+    }
+
+    #[test]
+    fn test_txpool_namespace_of_space() {
+        let core = txpool_status(Space::Native);
+        assert!(core.contains("\"unexecuted\""), "{}", core);
+        assert!(!core.contains("\"queued\""), "{}", core);
+
+        let eth = txpool_status(Space::Ethereum);
+        assert!(eth.contains("\"queued\""), "{}", eth);
+        assert!(!eth.contains("\"unexecuted\""), "{}", eth);
+    }
+
+    #[test]
+    fn test_reload_throttling() {
+        let _guard = THROTTLING_TEST_LOCK.lock();
+        const SECTION: &str = "rpc_reload_test";
+        let dir = TempDir::new("throttling").unwrap();
+        let write_conf = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let initial = write_conf(
+            "initial.toml",
+            "[rpc_reload_test]\ncfx_epochNumber=\"10,10,1,1,0\"\n",
+        );
+        let updated = write_conf(
+            "updated.toml",
+            "[rpc_reload_test]\ncfx_epochNumber=\"20,20,2,1,0\"\n",
+        );
+        let other_section =
+            write_conf("other.toml", "[rpc_other]\ncfx_call=\"1,1,1,1,0\"\n");
+        let interceptor = ThrottleInterceptor::new(&Some(initial), SECTION);
+        let bucket = |interceptor: &ThrottleInterceptor| {
+            interceptor.manager.read().describe()["cfx_epochNumber"].clone()
+        };
+
+        // Nothing is changed if the file or the section is missing.
+        let missing = dir.path().join("missing.toml");
+        assert!(reload_throttling(Some(
+            missing.to_string_lossy().into_owned()
+        ))
+        .is_err());
+        let err = reload_throttling(Some(other_section)).unwrap_err();
+        assert!(err.contains(SECTION), "{}", err);
+        assert_eq!(bucket(&interceptor), "10,10,1,1,0");
+
+        let results = reload_throttling(Some(updated.clone())).unwrap();
+        let result = results
+            .iter()
+            .find(|r| r.section == SECTION)
+            .expect("section reloaded");
+        assert_eq!(result.file, updated);
+        assert_eq!(result.previous["cfx_epochNumber"], "10,10,1,1,0");
+        assert_eq!(result.current["cfx_epochNumber"], "20,20,2,1,0");
+        assert_eq!(bucket(&interceptor), "20,20,2,1,0");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ipc_start_connect_call() {
+        let dir = TempDir::new("ipc").unwrap();
+        let socket_path = dir.path().join("conflux.ipc");
+        let mut handler = MetaIoHandler::<Metadata>::default();
+        handler.add_method("test_hello", |_| async {
+            Ok(Value::String("hello".into()))
+        });
+
+        let server = start_ipc(
+            IpcConfiguration::new(Some(
+                socket_path.to_string_lossy().into_owned(),
+            )),
+            handler,
+            RpcExtractor,
+        )
+        .unwrap()
+        .expect("IPC is enabled");
+
+        let mode = fs::metadata(&socket_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // The directory the socket is bound in is removed.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let mut stream = UnixStream::connect(&socket_path).unwrap();
+        stream
+            .write_all(
+                b"{\"jsonrpc\":\"2.0\",\"method\":\"test_hello\",\"id\":1}\n",
+            )
+            .unwrap();
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response).unwrap();
+        assert_eq!(
+            response.trim(),
+            r#"{"jsonrpc":"2.0","result":"hello","id":1}"#
+        );
+
+        server.close();
+    }
+}
@@ -204,6 +204,23 @@ pub fn request_rejected_in_catch_up_mode(details: Option<String>) -> Error {
     }
 }
 
+pub fn dev_mode_required() -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::INCAPABLE),
+        message: "This API is only available in dev mode".into(),
+        data: None,
+    }
+}
+
+pub fn address_tx_index_disabled() -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::INCAPABLE),
+        message: "The address transaction index is not enabled on this node"
+            .into(),
+        data: None,
+    }
+}
+
 pub fn pivot_assumption_failed(expected: H256, got: H256) -> Error {
     Error {
         code: ErrorCode::ServerError(codes::CONFLUX_PIVOT_CHAIN_UNSTABLE),
@@ -18,6 +18,7 @@
 
 use crate::rpc::{http_common::HttpMetaExtractor, Metadata, Origin};
 use cfx_types::H256;
+use jsonrpc_ipc_server as ipc;
 use jsonrpc_pubsub::Session;
 use jsonrpc_tcp_server as tcp;
 use jsonrpc_ws_server as ws;
@@ -53,6 +54,15 @@ impl tcp::MetaExtractor<Metadata> for RpcExtractor {
     }
 }
 
+impl ipc::MetaExtractor<Metadata> for RpcExtractor {
+    fn extract(&self, req: &ipc::RequestContext) -> Metadata {
+        Metadata {
+            origin: Origin::Ipc(H256::from_low_u64_be(req.session_id)),
+            session: Some(Arc::new(Session::new(req.sender.clone()))),
+        }
+    }
+}
+
 impl ws::MetaExtractor<Metadata> for RpcExtractor {
     fn extract(&self, req: &ws::RequestContext) -> Metadata {
         Metadata {
@@ -0,0 +1,404 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! GraphQL endpoint for the eSpace, implementing the schema of
+//! [EIP-1767](https://eips.ethereum.org/EIPS/eip-1767).
+//!
+//! Queries are served at `/graphql` (both GET and POST) and GraphiQL is
+//! served at `/`. The resolvers share the implementation of `eth_*` RPCs.
+
+mod limits;
+mod schema;
+
+pub use self::{
+    limits::QueryLimits,
+    schema::{new_schema, Context, EthBackend, Schema},
+};
+
+use crate::rpc::hyper_server::{start_hyper_server, HyperServer};
+use hyper::{
+    header::{HeaderValue, CONTENT_TYPE},
+    Body, Method, Request, Response, StatusCode,
+};
+use juniper::{
+    http::{graphiql::graphiql_source, GraphQLBatchRequest, GraphQLRequest},
+    InputValue,
+};
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::Arc,
+};
+
+pub struct GraphQLConfiguration {
+    pub enabled: bool,
+    pub address: SocketAddr,
+    /// The maximal number of blocks returned by `blocks(from, to)`.
+    pub max_block_range: u64,
+    pub limits: QueryLimits,
+    /// Whether `sendRawTransaction` is served.
+    pub mutation_enabled: bool,
+    /// The resolvers are throttled by the `rpc` section of this file.
+    pub throttling_conf: Option<String>,
+}
+
+impl GraphQLConfiguration {
+    pub fn new(
+        ip: Option<(u8, u8, u8, u8)>, port: Option<u16>, max_block_range: u64,
+        limits: QueryLimits, mutation_enabled: bool,
+        throttling_conf: Option<String>,
+    ) -> Self
+    {
+        let ipv4 = match ip {
+            Some(ip) => Ipv4Addr::new(ip.0, ip.1, ip.2, ip.3),
+            None => Ipv4Addr::new(127, 0, 0, 1),
+        };
+        GraphQLConfiguration {
+            enabled: port.is_some(),
+            address: SocketAddr::V4(SocketAddrV4::new(ipv4, port.unwrap_or(0))),
+            max_block_range,
+            limits,
+            mutation_enabled,
+            throttling_conf,
+        }
+    }
+}
+
+/// A request in the format of `GraphQLRequest`, which is checked against the
+/// `QueryLimits` before it is handed to juniper.
+#[derive(Deserialize)]
+struct QueryRequest {
+    query: String,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    variables: Option<InputValue>,
+}
+
+impl From<QueryRequest> for GraphQLRequest {
+    fn from(request: QueryRequest) -> Self {
+        GraphQLRequest::new(
+            request.query,
+            request.operation_name,
+            request.variables,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BatchQueryRequest {
+    Single(QueryRequest),
+    Batch(Vec<QueryRequest>),
+}
+
+impl BatchQueryRequest {
+    fn check(&self, limits: &QueryLimits) -> Result<(), String> {
+        match self {
+            BatchQueryRequest::Single(request) => {
+                limits.check_query(&request.query)
+            }
+            BatchQueryRequest::Batch(requests) => {
+                limits.check_batch_size(requests.len())?;
+                requests
+                    .iter()
+                    .try_for_each(|request| limits.check_query(&request.query))
+            }
+        }
+    }
+}
+
+impl From<BatchQueryRequest> for GraphQLBatchRequest {
+    fn from(request: BatchQueryRequest) -> Self {
+        match request {
+            BatchQueryRequest::Single(request) => {
+                GraphQLBatchRequest::Single(request.into())
+            }
+            BatchQueryRequest::Batch(requests) => GraphQLBatchRequest::Batch(
+                requests.into_iter().map(Into::into).collect(),
+            ),
+        }
+    }
+}
+
+fn new_response(
+    status: StatusCode, content_type: &str, body: String,
+) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+    response
+}
+
+fn bad_request(message: String) -> Response<Body> {
+    new_response(StatusCode::BAD_REQUEST, "text/plain", message)
+}
+
+/// Parses the `query`, `operationName` and `variables` parameters of a GET
+/// request.
+fn parse_get_request(
+    query_string: Option<&str>,
+) -> Result<BatchQueryRequest, String> {
+    let mut query = None;
+    let mut operation_name = None;
+    let mut variables = None;
+    for (key, value) in
+        url::form_urlencoded::parse(query_string.unwrap_or("").as_bytes())
+    {
+        match key.as_ref() {
+            "query" => query = Some(value.into_owned()),
+            "operationName" => operation_name = Some(value.into_owned()),
+            "variables" => {
+                variables = Some(
+                    serde_json::from_str::<InputValue>(&value)
+                        .map_err(|e| format!("invalid variables: {}", e))?,
+                )
+            }
+            _ => {}
+        }
+    }
+    let query = query.ok_or("missing query parameter")?;
+    Ok(BatchQueryRequest::Single(QueryRequest {
+        query,
+        operation_name,
+        variables,
+    }))
+}
+
+/// Parses the body of a POST request, either a JSON encoded (batch) request
+/// or a raw query with content type `application/graphql`.
+async fn parse_post_request(
+    req: Request<Body>,
+) -> Result<BatchQueryRequest, String> {
+    let is_raw_query = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.starts_with("application/graphql"));
+    let body = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| format!("failed to read body: {}", e))?;
+    if is_raw_query {
+        let query = String::from_utf8(body.to_vec())
+            .map_err(|e| format!("invalid query: {}", e))?;
+        Ok(BatchQueryRequest::Single(QueryRequest {
+            query,
+            operation_name: None,
+            variables: None,
+        }))
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| format!("invalid request: {}", e))
+    }
+}
+
+async fn handle_request(
+    schema: Arc<Schema>, context: Arc<Context>, limits: QueryLimits,
+    req: Request<Body>,
+) -> Response<Body>
+{
+    let request = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/") => {
+            return new_response(
+                StatusCode::OK,
+                "text/html; charset=utf-8",
+                graphiql_source("/graphql", None),
+            );
+        }
+        (&Method::GET, "/graphql") => parse_get_request(req.uri().query()),
+        (&Method::POST, "/graphql") => parse_post_request(req).await,
+        _ => {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            return response;
+        }
+    };
+    let request = match request.and_then(|r| r.check(&limits).map(|_| r)) {
+        Ok(request) => GraphQLBatchRequest::from(request),
+        Err(e) => return bad_request(e),
+    };
+
+    // The resolvers are blocking, so keep them off the reactor threads.
+    let result = tokio::task::spawn_blocking(move || {
+        let response = request.execute_sync(&schema, &context);
+        let status = if response.is_ok() {
+            StatusCode::OK
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        serde_json::to_string(&response).map(|body| (status, body))
+    })
+    .await;
+    match result {
+        Ok(Ok((status, body))) => {
+            new_response(status, "application/json", body)
+        }
+        Ok(Err(e)) => new_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "text/plain",
+            e.to_string(),
+        ),
+        Err(e) => new_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "text/plain",
+            e.to_string(),
+        ),
+    }
+}
+
+pub fn start_graphql(
+    conf: GraphQLConfiguration, eth: Arc<dyn EthBackend>,
+) -> Result<Option<HyperServer>, String> {
+    if !conf.enabled {
+        return Ok(None);
+    }
+
+    let schema = Arc::new(new_schema());
+    let context = Arc::new(Context::new(
+        eth,
+        conf.max_block_range,
+        conf.mutation_enabled,
+        &conf.throttling_conf,
+    ));
+    let limits = conf.limits;
+    start_hyper_server("GraphQL", &conf.address, None, move |req| {
+        handle_request(schema.clone(), context.clone(), limits, req)
+    })
+    .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        schema::tests::MockEth, start_graphql, GraphQLConfiguration,
+        QueryLimits,
+    };
+    use crate::rpc::THROTTLING_TEST_LOCK;
+    use serde_json::{json, Value};
+    use std::{
+        io::{Read, Write},
+        net::{SocketAddr, TcpStream},
+        sync::Arc,
+    };
+
+    const LIMITS: QueryLimits = QueryLimits {
+        max_depth: 3,
+        max_complexity: 10,
+        max_batch_size: 2,
+    };
+
+    /// Sends `request` and returns the status code and the body.
+    fn http_request(address: &SocketAddr, request: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = match response.find("\r\n\r\n") {
+            Some(index) => response[index + 4..].to_string(),
+            None => String::new(),
+        };
+        (status, body)
+    }
+
+    fn post(address: &SocketAddr, body: &Value) -> (u16, String) {
+        let body = body.to_string();
+        http_request(
+            address,
+            &format!(
+                "POST /graphql HTTP/1.1\r\nHost: localhost\r\n\
+                 Content-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+        )
+    }
+
+    #[test]
+    fn test_graphql_configuration() {
+        let conf = GraphQLConfiguration::new(
+            None,
+            Some(8547),
+            1000,
+            LIMITS,
+            false,
+            None,
+        );
+        assert!(conf.enabled);
+        assert_eq!(conf.address, "127.0.0.1:8547".parse().unwrap());
+        assert!(
+            !GraphQLConfiguration::new(None, None, 1000, LIMITS, false, None)
+                .enabled
+        );
+    }
+
+    #[test]
+    fn test_http_round_trip() {
+        let _guard = THROTTLING_TEST_LOCK.lock();
+        let conf = GraphQLConfiguration::new(
+            Some((127, 0, 0, 1)),
+            Some(0),
+            1000,
+            LIMITS,
+            false,
+            None,
+        );
+        let server = start_graphql(conf, Arc::new(MockEth))
+            .unwrap()
+            .expect("enabled");
+        let address = server.local_addr();
+
+        let (status, body) =
+            post(&address, &json!({ "query": "{ block { number } }" }));
+        assert_eq!(status, 200);
+        assert_eq!(
+            serde_json::from_str::<Value>(&body).unwrap(),
+            json!({ "data": { "block": { "number": "0x1" } } })
+        );
+
+        let (status, body) = http_request(
+            &address,
+            "GET /graphql?query=%7B%20gasPrice%20%7D HTTP/1.1\r\n\
+             Host: localhost\r\nConnection: close\r\n\r\n",
+        );
+        assert_eq!(status, 200);
+        assert_eq!(
+            serde_json::from_str::<Value>(&body).unwrap(),
+            json!({ "data": { "gasPrice": "0x3b9aca00" } })
+        );
+
+        let (status, body) = post(
+            &address,
+            &json!([{ "query": "{ gasPrice }" }, { "query": "{ chainID }" }]),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(
+            serde_json::from_str::<Value>(&body).unwrap(),
+            json!([
+                { "data": { "gasPrice": "0x3b9aca00" } },
+                { "data": { "chainID": "0x47" } },
+            ])
+        );
+
+        // The limits are checked before the query is executed.
+        let batch = vec![json!({ "query": "{ gasPrice }" }); 3];
+        let (status, body) = post(&address, &Value::Array(batch));
+        assert_eq!(status, 400);
+        assert!(body.contains("batch size 3"), "{}", body);
+        let (status, body) = post(
+            &address,
+            &json!({
+                "query": "{ block { transactions { from { balance } } } }"
+            }),
+        );
+        assert_eq!(status, 400);
+        assert!(body.contains("depth 4"), "{}", body);
+
+        let query = r#"mutation { sendRawTransaction(data: "0x01") }"#;
+        let (status, body) = post(&address, &json!({ "query": query }));
+        assert!(body.contains("sendRawTransaction is disabled"), "{}", body);
+        assert_eq!(status, 200);
+    }
+}
@@ -0,0 +1,476 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The depth, complexity and batch size limits of GraphQL requests.
+//!
+//! The query is only scanned for its selection sets before it is handed to
+//! juniper, so the other syntax and validation errors are still reported by
+//! juniper.
+
+use std::collections::HashMap;
+
+/// The maximal nesting of selection sets and fragments which is scanned, so
+/// that a malicious query can not overflow the stack.
+const MAX_NESTING: usize = 128;
+
+#[derive(Clone, Copy, Debug)]
+pub struct QueryLimits {
+    /// The maximal nesting depth of the selected fields.
+    pub max_depth: usize,
+    /// The maximal number of selected fields, where the fields of a fragment
+    /// are counted once for every spread of it.
+    pub max_complexity: usize,
+    /// The maximal number of requests in a batch.
+    pub max_batch_size: usize,
+}
+
+impl QueryLimits {
+    pub fn check_batch_size(&self, size: usize) -> Result<(), String> {
+        if size > self.max_batch_size {
+            return Err(format!(
+                "The batch size {} is larger than {}",
+                size, self.max_batch_size
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks every operation of `query`, no matter which one is executed.
+    pub fn check_query(&self, query: &str) -> Result<(), String> {
+        let document = Parser::new(tokenize(query)?).document()?;
+        let mut measure = Measure {
+            fragments: &document.fragments,
+            costs: HashMap::new(),
+            visiting: Vec::new(),
+        };
+        for operation in &document.operations {
+            let cost = measure.selection_set(operation)?;
+            if cost.depth > self.max_depth {
+                return Err(format!(
+                    "The query depth {} is larger than {}",
+                    cost.depth, self.max_depth
+                ));
+            }
+            if cost.complexity > self.max_complexity {
+                return Err(format!(
+                    "The query complexity {} is larger than {}",
+                    cost.complexity, self.max_complexity
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Punctuator(char),
+    Spread,
+    Name(&'a str),
+    /// A number or a string, which never changes the selections.
+    Value,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let bytes = query.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\n' | b'\r' | b',' => i += 1,
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' && bytes[i] != b'\r'
+                {
+                    i += 1;
+                }
+            }
+            b'"' => {
+                i = skip_string(bytes, i)?;
+                tokens.push(Token::Value);
+            }
+            b'.' if bytes[i..].starts_with(b"...") => {
+                tokens.push(Token::Spread);
+                i += 3;
+            }
+            b'!' | b'$' | b'&' | b'(' | b')' | b'=' | b':' | b'@' | b'['
+            | b']' | b'{' | b'|' | b'}' => {
+                tokens.push(Token::Punctuator(bytes[i] as char));
+                i += 1;
+            }
+            b'_' | b'A'..=b'Z' | b'a'..=b'z' => {
+                let start = i;
+                while i < bytes.len()
+                    && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric())
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Name(&query[start..i]));
+            }
+            b'-' | b'0'..=b'9' => {
+                i += 1;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric()
+                        || bytes[i] == b'.'
+                        || bytes[i] == b'+'
+                        || bytes[i] == b'-')
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Value);
+            }
+            _ if query[i..].starts_with('\u{feff}') => {
+                i += '\u{feff}'.len_utf8();
+            }
+            _ => return Err(format!("Unexpected character at {}", i)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Returns the position after the string starting at `start`.
+fn skip_string(bytes: &[u8], start: usize) -> Result<usize, String> {
+    if bytes[start..].starts_with(b"\"\"\"") {
+        let mut i = start + 3;
+        while i < bytes.len() {
+            if bytes[i..].starts_with(b"\\\"\"\"") {
+                i += 4;
+            } else if bytes[i..].starts_with(b"\"\"\"") {
+                return Ok(i + 3);
+            } else {
+                i += 1;
+            }
+        }
+    } else {
+        let mut i = start + 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => return Ok(i + 1),
+                b'\n' | b'\r' => break,
+                _ => i += 1,
+            }
+        }
+    }
+    Err(format!("Unterminated string at {}", start))
+}
+
+enum Selection<'a> {
+    Field(Vec<Selection<'a>>),
+    FragmentSpread(&'a str),
+    InlineFragment(Vec<Selection<'a>>),
+}
+
+struct Document<'a> {
+    operations: Vec<Vec<Selection<'a>>>,
+    fragments: HashMap<&'a str, Vec<Selection<'a>>>,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: Vec<Token<'a>>) -> Self {
+        Parser {
+            tokens,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    fn document(&mut self) -> Result<Document<'a>, String> {
+        let mut document = Document {
+            operations: Vec::new(),
+            fragments: HashMap::new(),
+        };
+        while let Some(token) = self.next_token() {
+            match token {
+                Token::Name("fragment") => {
+                    let name = match self.next_token() {
+                        Some(Token::Name(name)) => name,
+                        _ => return Err("Missing fragment name".into()),
+                    };
+                    self.skip_to_selection_set()?;
+                    let selections = self.selection_set(1)?;
+                    document.fragments.insert(name, selections);
+                }
+                Token::Punctuator('{') => {
+                    document.operations.push(self.selection_set(1)?);
+                }
+                Token::Name(_) => {
+                    self.skip_to_selection_set()?;
+                    document.operations.push(self.selection_set(1)?);
+                }
+                token => return Err(format!("Unexpected {:?}", token)),
+            }
+        }
+        Ok(document)
+    }
+
+    /// Skips the name, variables, type condition and directives of a
+    /// definition, up to and including the `{` of its selection set.
+    fn skip_to_selection_set(&mut self) -> Result<(), String> {
+        loop {
+            match self.next_token() {
+                Some(Token::Punctuator('{')) => return Ok(()),
+                Some(Token::Punctuator('(')) => self.skip_arguments()?,
+                Some(_) => {}
+                None => return Err("Missing selection set".into()),
+            }
+        }
+    }
+
+    /// Skips the tokens up to and including the `)` which closes the
+    /// arguments, as object values may contain braces.
+    fn skip_arguments(&mut self) -> Result<(), String> {
+        loop {
+            match self.next_token() {
+                Some(Token::Punctuator(')')) => return Ok(()),
+                Some(_) => {}
+                None => return Err("Unterminated arguments".into()),
+            }
+        }
+    }
+
+    fn skip_directives(&mut self) -> Result<(), String> {
+        while self.peek() == Some(Token::Punctuator('@')) {
+            // The `@` and the name of the directive.
+            self.position += 2;
+            if self.peek() == Some(Token::Punctuator('(')) {
+                self.position += 1;
+                self.skip_arguments()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses the selections up to and including the `}` which closes the
+    /// selection set.
+    fn selection_set(
+        &mut self, nesting: usize,
+    ) -> Result<Vec<Selection<'a>>, String> {
+        if nesting > MAX_NESTING {
+            return Err("The query is nested too deeply".into());
+        }
+        let mut selections = Vec::new();
+        loop {
+            match self.next_token() {
+                Some(Token::Punctuator('}')) => return Ok(selections),
+                Some(Token::Spread) => match self.peek() {
+                    Some(Token::Name(name)) if name != "on" => {
+                        self.position += 1;
+                        self.skip_directives()?;
+                        selections.push(Selection::FragmentSpread(name));
+                    }
+                    _ => {
+                        self.skip_to_selection_set()?;
+                        selections.push(Selection::InlineFragment(
+                            self.selection_set(nesting + 1)?,
+                        ));
+                    }
+                },
+                Some(Token::Name(_)) => {
+                    // The alias.
+                    if self.peek() == Some(Token::Punctuator(':')) {
+                        self.position += 2;
+                    }
+                    if self.peek() == Some(Token::Punctuator('(')) {
+                        self.position += 1;
+                        self.skip_arguments()?;
+                    }
+                    self.skip_directives()?;
+                    let children =
+                        if self.peek() == Some(Token::Punctuator('{')) {
+                            self.position += 1;
+                            self.selection_set(nesting + 1)?
+                        } else {
+                            Vec::new()
+                        };
+                    selections.push(Selection::Field(children));
+                }
+                Some(token) => {
+                    return Err(format!(
+                        "Unexpected {:?} in selection set",
+                        token
+                    ))
+                }
+                None => return Err("Unterminated selection set".into()),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Cost {
+    depth: usize,
+    complexity: usize,
+}
+
+struct Measure<'d, 'a> {
+    fragments: &'d HashMap<&'a str, Vec<Selection<'a>>>,
+    costs: HashMap<&'a str, Cost>,
+    /// The fragments being measured, to detect the cycles.
+    visiting: Vec<&'a str>,
+}
+
+impl<'d, 'a> Measure<'d, 'a> {
+    fn selection_set(
+        &mut self, selections: &[Selection<'a>],
+    ) -> Result<Cost, String> {
+        let mut cost = Cost::default();
+        for selection in selections {
+            let (depth, complexity) = match selection {
+                Selection::Field(children) => {
+                    let children = self.selection_set(children)?;
+                    (children.depth + 1, children.complexity.saturating_add(1))
+                }
+                Selection::FragmentSpread(name) => {
+                    let fragment = self.fragment(*name)?;
+                    (fragment.depth, fragment.complexity)
+                }
+                Selection::InlineFragment(selections) => {
+                    let fragment = self.selection_set(selections)?;
+                    (fragment.depth, fragment.complexity)
+                }
+            };
+            cost.depth = cost.depth.max(depth);
+            cost.complexity = cost.complexity.saturating_add(complexity);
+        }
+        Ok(cost)
+    }
+
+    fn fragment(&mut self, name: &'a str) -> Result<Cost, String> {
+        if let Some(cost) = self.costs.get(name) {
+            return Ok(*cost);
+        }
+        let fragments = self.fragments;
+        let selections = match fragments.get(name) {
+            Some(selections) => selections,
+            // The unknown fragments are reported by juniper.
+            None => return Ok(Cost::default()),
+        };
+        if self.visiting.contains(&name) {
+            return Err(format!("The fragment {} spreads itself", name));
+        }
+        if self.visiting.len() >= MAX_NESTING {
+            return Err("The fragments are nested too deeply".into());
+        }
+        self.visiting.push(name);
+        let cost = self.selection_set(selections)?;
+        self.visiting.pop();
+        self.costs.insert(name, cost);
+        Ok(cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryLimits;
+
+    const LIMITS: QueryLimits = QueryLimits {
+        max_depth: 3,
+        max_complexity: 6,
+        max_batch_size: 2,
+    };
+
+    #[test]
+    fn test_depth_and_complexity() {
+        assert!(LIMITS.check_query("{ block { number } }").is_ok());
+        assert!(LIMITS
+            .check_query("{ block { transactions { from { balance } } } }")
+            .unwrap_err()
+            .contains("depth 4"));
+        assert!(LIMITS
+            .check_query("{ gasPrice a: gasPrice b: gasPrice c: gasPrice }")
+            .is_ok());
+        let query =
+            "{ block { a: number b: number c: number d: hash e: hash f: \
+                     hash } }";
+        assert!(LIMITS
+            .check_query(query)
+            .unwrap_err()
+            .contains("complexity 7"));
+
+        // Every operation is checked.
+        assert!(LIMITS
+            .check_query("query A { gasPrice } query B { a { b { c { d } } } }")
+            .is_err());
+    }
+
+    #[test]
+    fn test_arguments_and_strings() {
+        // The braces in the arguments, strings and comments are not
+        // selection sets.
+        let query = r#"
+            query Q($f: FilterCriteria = { addresses: [] }) @dir(x: "{{") {
+                # { { {
+                logs(filter: { topics: [["}{"]] }) { data }
+                a: block(hash: """ {{{ \""" """) @include(if: true) { number }
+            }
+        "#;
+        assert!(LIMITS.check_query(query).is_ok());
+        assert!(LIMITS.check_query("{ block(number: \"0x1) { a }").is_err());
+    }
+
+    #[test]
+    fn test_fragments() {
+        let query = "
+            { block { ...F } }
+            fragment F on Block { transactions { ...G } }
+            fragment G on Transaction { from { balance } }
+        ";
+        assert!(LIMITS.check_query(query).unwrap_err().contains("depth 4"));
+
+        // Inline fragments do not increase the depth, and the fields of a
+        // fragment are counted for every spread.
+        let query = "
+            { block { ... on Block { number } ...F ...F } }
+            fragment F on Block { hash number }
+        ";
+        assert!(LIMITS.check_query(query).is_ok());
+        let query = "
+            { block { ...F ...F ...F } }
+            fragment F on Block { hash number }
+        ";
+        assert!(LIMITS
+            .check_query(query)
+            .unwrap_err()
+            .contains("complexity 7"));
+
+        // The fragments can not multiply the complexity without a bound.
+        let mut query = "{ ...F0 }".to_string();
+        for i in 0..100 {
+            query += &format!(
+                " fragment F{} on Query {{ ...F{} ...F{} }}",
+                i,
+                i + 1,
+                i + 1
+            );
+        }
+        query += " fragment F100 on Query { gasPrice }";
+        assert!(LIMITS.check_query(&query).is_err());
+
+        let query = "{ ...F } fragment F on Query { block { ...F } }";
+        assert!(LIMITS.check_query(query).unwrap_err().contains("itself"));
+    }
+
+    #[test]
+    fn test_nesting_and_batch_size() {
+        let query = "{ a ".repeat(10_000) + &"}".repeat(10_000);
+        assert!(LIMITS.check_query(&query).is_err());
+
+        assert!(LIMITS.check_batch_size(2).is_ok());
+        assert!(LIMITS.check_batch_size(3).is_err());
+    }
+}
@@ -0,0 +1,1242 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::{
+    impls::eth::EthHandler,
+    interceptor::RpcInterceptor,
+    traits::eth_space::eth::Eth,
+    types::{
+        eth::{
+            Block as RpcBlock, BlockNumber, BlockTransactions, CallRequest,
+            EthRpcLogFilter, Log as RpcLog, Receipt as RpcReceipt, SyncStatus,
+            Transaction as RpcTransaction, VariadicValue,
+        },
+        Bytes as RpcBytes,
+    },
+    ThrottleInterceptor,
+};
+use cfx_types::{H160, H256, U256, U64};
+use cfxcore::executive::ExecutionOutcome;
+use jsonrpc_core::{Error as RpcError, Result as RpcResult};
+use juniper::{
+    graphql_object, graphql_scalar, graphql_value, EmptySubscription,
+    FieldError, FieldResult, GraphQLInputObject, GraphQLObject, InputValue,
+    ParseScalarResult, ParseScalarValue, RootNode, ScalarToken, ScalarValue,
+    Value,
+};
+use rustc_hex::{FromHex, ToHex};
+use std::sync::Arc;
+
+/// The eSpace RPCs which the resolvers are built on.
+pub trait EthBackend: Send + Sync {
+    fn block_number(&self) -> RpcResult<U256>;
+
+    fn block_by_number(
+        &self, number: BlockNumber, include_txs: bool,
+    ) -> RpcResult<Option<RpcBlock>>;
+
+    fn block_by_hash(
+        &self, hash: H256, include_txs: bool,
+    ) -> RpcResult<Option<RpcBlock>>;
+
+    fn balance(
+        &self, address: H160, block: Option<BlockNumber>,
+    ) -> RpcResult<U256>;
+
+    fn transaction_count(
+        &self, address: H160, block: Option<BlockNumber>,
+    ) -> RpcResult<U256>;
+
+    fn code_at(
+        &self, address: H160, block: Option<BlockNumber>,
+    ) -> RpcResult<RpcBytes>;
+
+    fn storage_at(
+        &self, address: H160, slot: U256, block: Option<BlockNumber>,
+    ) -> RpcResult<H256>;
+
+    fn transaction_by_hash(
+        &self, hash: H256,
+    ) -> RpcResult<Option<RpcTransaction>>;
+
+    fn transaction_receipt(&self, hash: H256) -> RpcResult<Option<RpcReceipt>>;
+
+    fn logs(&self, filter: EthRpcLogFilter) -> RpcResult<Vec<RpcLog>>;
+
+    fn get_logs_filter_max_limit(&self) -> Option<usize>;
+
+    fn exec_transaction(
+        &self, request: CallRequest, block: Option<BlockNumber>,
+    ) -> RpcResult<ExecutionOutcome>;
+
+    fn estimate_gas(
+        &self, request: CallRequest, block: Option<BlockNumber>,
+    ) -> RpcResult<U256>;
+
+    fn gas_price(&self) -> RpcResult<U256>;
+
+    fn max_priority_fee_per_gas(&self) -> RpcResult<U256>;
+
+    fn chain_id(&self) -> RpcResult<Option<U64>>;
+
+    fn syncing(&self) -> RpcResult<SyncStatus>;
+
+    fn send_raw_transaction(&self, raw: RpcBytes) -> RpcResult<H256>;
+}
+
+impl EthBackend for EthHandler {
+    fn block_number(&self) -> RpcResult<U256> { Eth::block_number(self) }
+
+    fn block_by_number(
+        &self, number: BlockNumber, include_txs: bool,
+    ) -> RpcResult<Option<RpcBlock>> {
+        Eth::block_by_number(self, number, include_txs)
+    }
+
+    fn block_by_hash(
+        &self, hash: H256, include_txs: bool,
+    ) -> RpcResult<Option<RpcBlock>> {
+        Eth::block_by_hash(self, hash, include_txs)
+    }
+
+    fn balance(
+        &self, address: H160, block: Option<BlockNumber>,
+    ) -> RpcResult<U256> {
+        Eth::balance(self, address, block)
+    }
+
+    fn transaction_count(
+        &self, address: H160, block: Option<BlockNumber>,
+    ) -> RpcResult<U256> {
+        Eth::transaction_count(self, address, block)
+    }
+
+    fn code_at(
+        &self, address: H160, block: Option<BlockNumber>,
+    ) -> RpcResult<RpcBytes> {
+        Eth::code_at(self, address, block)
+    }
+
+    fn storage_at(
+        &self, address: H160, slot: U256, block: Option<BlockNumber>,
+    ) -> RpcResult<H256> {
+        Eth::storage_at(self, address, slot, block)
+    }
+
+    fn transaction_by_hash(
+        &self, hash: H256,
+    ) -> RpcResult<Option<RpcTransaction>> {
+        Eth::transaction_by_hash(self, hash)
+    }
+
+    fn transaction_receipt(&self, hash: H256) -> RpcResult<Option<RpcReceipt>> {
+        Eth::transaction_receipt(self, hash)
+    }
+
+    fn logs(&self, filter: EthRpcLogFilter) -> RpcResult<Vec<RpcLog>> {
+        Eth::logs(self, filter)
+    }
+
+    fn get_logs_filter_max_limit(&self) -> Option<usize> {
+        EthHandler::get_logs_filter_max_limit(self)
+    }
+
+    fn exec_transaction(
+        &self, request: CallRequest, block: Option<BlockNumber>,
+    ) -> RpcResult<ExecutionOutcome> {
+        EthHandler::exec_transaction(self, request, block).map_err(Into::into)
+    }
+
+    fn estimate_gas(
+        &self, request: CallRequest, block: Option<BlockNumber>,
+    ) -> RpcResult<U256> {
+        Eth::estimate_gas(self, request, block)
+    }
+
+    fn gas_price(&self) -> RpcResult<U256> { Eth::gas_price(self) }
+
+    fn max_priority_fee_per_gas(&self) -> RpcResult<U256> {
+        Eth::max_priority_fee_per_gas(self)
+    }
+
+    fn chain_id(&self) -> RpcResult<Option<U64>> { Eth::chain_id(self) }
+
+    fn syncing(&self) -> RpcResult<SyncStatus> { Eth::syncing(self) }
+
+    fn send_raw_transaction(&self, raw: RpcBytes) -> RpcResult<H256> {
+        Eth::send_raw_transaction(self, raw)
+    }
+}
+
+pub struct Context {
+    eth: Arc<dyn EthBackend>,
+    max_block_range: u64,
+    mutation_enabled: bool,
+    interceptor: ThrottleInterceptor,
+}
+
+impl Context {
+    pub fn new(
+        eth: Arc<dyn EthBackend>, max_block_range: u64, mutation_enabled: bool,
+        throttling_conf: &Option<String>,
+    ) -> Self
+    {
+        Context {
+            eth,
+            max_block_range,
+            mutation_enabled,
+            interceptor: ThrottleInterceptor::new(throttling_conf, "rpc"),
+        }
+    }
+
+    /// Throttles a resolver with the token bucket of the eSpace RPC it is
+    /// built on, e.g. `eth_call`, in the `rpc` section of `throttling_conf`.
+    fn throttle(&self, rpc: &str) -> FieldResult<()> {
+        self.interceptor.before(&rpc.to_string()).map_err(rpc_error)
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub type Schema =
+    RootNode<'static, Query, Mutation, EmptySubscription<Context>>;
+
+pub fn new_schema() -> Schema {
+    Schema::new(Query, Mutation, EmptySubscription::new())
+}
+
+fn rpc_error(e: RpcError) -> FieldError {
+    let code = e.code.code() as i32;
+    FieldError::new(e.message, graphql_value!({ "code": code }))
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    s.strip_prefix("0x").unwrap_or(s).from_hex().ok()
+}
+
+/// A 32 byte value.
+#[derive(Clone, Copy, Debug)]
+pub struct Bytes32(H256);
+
+impl Bytes32 {
+    fn to_hex(&self) -> String { format!("{:?}", self.0) }
+
+    fn from_hex(s: &str) -> Option<Self> {
+        parse_hex(s)
+            .filter(|bytes| bytes.len() == 32)
+            .map(|bytes| Bytes32(H256::from_slice(&bytes)))
+    }
+}
+
+/// A 20 byte eSpace address.
+#[derive(Clone, Copy, Debug)]
+pub struct Address(H160);
+
+impl Address {
+    fn to_hex(&self) -> String { format!("{:?}", self.0) }
+
+    fn from_hex(s: &str) -> Option<Self> {
+        parse_hex(s)
+            .filter(|bytes| bytes.len() == 20)
+            .map(|bytes| Address(H160::from_slice(&bytes)))
+    }
+}
+
+/// An arbitrary length byte string.
+#[derive(Clone, Debug)]
+pub struct Bytes(Vec<u8>);
+
+impl Bytes {
+    fn to_hex(&self) -> String { format!("0x{}", self.0.to_hex::<String>()) }
+
+    fn from_hex(s: &str) -> Option<Self> { parse_hex(s).map(Bytes) }
+}
+
+/// A 256 bit integer. The input can be either a hex or a decimal string.
+#[derive(Clone, Copy, Debug)]
+pub struct BigInt(U256);
+
+impl BigInt {
+    fn to_hex(&self) -> String { format!("{:#x}", self.0) }
+
+    fn from_hex(s: &str) -> Option<Self> {
+        match s.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(s).ok(),
+        }
+        .map(BigInt)
+    }
+}
+
+macro_rules! impl_hex_scalar {
+    ($name:ident, $description:literal) => {
+        #[graphql_scalar(description = $description)]
+        impl<S> GraphQLScalar for $name
+        where S: ScalarValue
+        {
+            fn resolve(&self) -> Value { Value::scalar(self.to_hex()) }
+
+            fn from_input_value(value: &InputValue) -> Option<$name> {
+                value.as_string_value().and_then($name::from_hex)
+            }
+
+            fn from_str<'a>(
+                value: ScalarToken<'a>,
+            ) -> ParseScalarResult<'a, S> {
+                <String as ParseScalarValue<S>>::from_str(value)
+            }
+        }
+    };
+}
+
+impl_hex_scalar!(Bytes32, "A 32 byte value in hex.");
+impl_hex_scalar!(Address, "A 20 byte eSpace address in hex.");
+impl_hex_scalar!(Bytes, "An arbitrary length byte string in hex.");
+impl_hex_scalar!(BigInt, "A 256 bit integer in hex or decimal.");
+
+/// A 64 bit integer. It is returned in hex because GraphQL `Int` only has
+/// 32 bits.
+#[derive(Clone, Copy, Debug)]
+pub struct Long(u64);
+
+#[graphql_scalar(description = "A 64 bit integer.")]
+impl<S> GraphQLScalar for Long
+where S: ScalarValue
+{
+    fn resolve(&self) -> Value { Value::scalar(format!("{:#x}", self.0)) }
+
+    fn from_input_value(value: &InputValue) -> Option<Long> {
+        if let Some(i) = value.as_int_value() {
+            return if i >= 0 { Some(Long(i as u64)) } else { None };
+        }
+        value.as_string_value().and_then(|s| {
+            match s.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16).ok(),
+                None => s.parse().ok(),
+            }
+            .map(Long)
+        })
+    }
+
+    fn from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a, S> {
+        match value {
+            ScalarToken::Int(_) => {
+                <i32 as ParseScalarValue<S>>::from_str(value)
+            }
+            _ => <String as ParseScalarValue<S>>::from_str(value),
+        }
+    }
+}
+
+fn block_number(block: Option<Long>) -> Option<BlockNumber> {
+    block.map(|b| BlockNumber::Num(b.0))
+}
+
+fn topics_filter(
+    topics: Option<Vec<Vec<Bytes32>>>,
+) -> Option<Vec<VariadicValue<H256>>> {
+    topics.map(|topics| {
+        topics
+            .into_iter()
+            .map(|topic| {
+                if topic.is_empty() {
+                    // An empty list matches any topic.
+                    VariadicValue::Null
+                } else {
+                    VariadicValue::Multiple(
+                        topic.into_iter().map(|t| t.0).collect(),
+                    )
+                }
+            })
+            .collect()
+    })
+}
+
+fn addresses_filter(
+    addresses: Option<Vec<Address>>,
+) -> Option<VariadicValue<H160>> {
+    addresses.map(|addresses| {
+        VariadicValue::Multiple(addresses.into_iter().map(|a| a.0).collect())
+    })
+}
+
+fn query_logs(
+    context: &Context, filter: EthRpcLogFilter,
+) -> FieldResult<Vec<Log>> {
+    context.throttle("eth_getLogs")?;
+    Ok(context
+        .eth
+        .logs(filter)
+        .map_err(rpc_error)?
+        .into_iter()
+        .map(Log)
+        .collect())
+}
+
+fn query_block(
+    context: &Context, number: BlockNumber,
+) -> FieldResult<Option<Block>> {
+    context.throttle("eth_getBlockByNumber")?;
+    Ok(context
+        .eth
+        .block_by_number(number, true)
+        .map_err(rpc_error)?
+        .map(Block))
+}
+
+/// An account at a given block.
+pub struct Account {
+    address: H160,
+    block: Option<BlockNumber>,
+}
+
+#[graphql_object(context = Context)]
+impl Account {
+    fn address(&self) -> Address { Address(self.address) }
+
+    fn balance(&self, context: &Context) -> FieldResult<BigInt> {
+        Ok(BigInt(
+            context
+                .eth
+                .balance(self.address, self.block.clone())
+                .map_err(rpc_error)?,
+        ))
+    }
+
+    fn transaction_count(&self, context: &Context) -> FieldResult<Long> {
+        Ok(Long(
+            context
+                .eth
+                .transaction_count(self.address, self.block.clone())
+                .map_err(rpc_error)?
+                .low_u64(),
+        ))
+    }
+
+    fn code(&self, context: &Context) -> FieldResult<Bytes> {
+        Ok(Bytes(
+            context
+                .eth
+                .code_at(self.address, self.block.clone())
+                .map_err(rpc_error)?
+                .into_vec(),
+        ))
+    }
+
+    fn storage(
+        &self, context: &Context, slot: Bytes32,
+    ) -> FieldResult<Bytes32> {
+        Ok(Bytes32(
+            context
+                .eth
+                .storage_at(
+                    self.address,
+                    U256::from_big_endian(slot.0.as_bytes()),
+                    self.block.clone(),
+                )
+                .map_err(rpc_error)?,
+        ))
+    }
+}
+
+/// An event emitted by a transaction.
+pub struct Log(RpcLog);
+
+#[graphql_object(context = Context)]
+impl Log {
+    fn index(&self) -> i32 {
+        self.0.log_index.unwrap_or_default().low_u32() as i32
+    }
+
+    fn account(&self, block: Option<Long>) -> Account {
+        Account {
+            address: self.0.address,
+            block: block_number(block),
+        }
+    }
+
+    fn topics(&self) -> Vec<Bytes32> {
+        self.0.topics.iter().cloned().map(Bytes32).collect()
+    }
+
+    fn data(&self) -> Bytes { Bytes(self.0.data.0.clone()) }
+
+    fn transaction(&self, context: &Context) -> FieldResult<Transaction> {
+        context
+            .eth
+            .transaction_by_hash(self.0.transaction_hash)
+            .map_err(rpc_error)?
+            .map(Transaction)
+            .ok_or_else(|| "Transaction of the log is not found".into())
+    }
+}
+
+/// A transaction, which may be pending.
+pub struct Transaction(RpcTransaction);
+
+impl Transaction {
+    fn receipt(&self, context: &Context) -> FieldResult<Option<RpcReceipt>> {
+        if self.0.block_hash.is_none() {
+            return Ok(None);
+        }
+        context
+            .eth
+            .transaction_receipt(self.0.hash)
+            .map_err(rpc_error)
+    }
+}
+
+#[graphql_object(context = Context)]
+impl Transaction {
+    fn hash(&self) -> Bytes32 { Bytes32(self.0.hash) }
+
+    fn nonce(&self) -> Long { Long(self.0.nonce.low_u64()) }
+
+    fn index(&self) -> Option<i32> {
+        self.0.transaction_index.map(|i| i.low_u32() as i32)
+    }
+
+    fn from(&self, block: Option<Long>) -> Account {
+        Account {
+            address: self.0.from,
+            block: block_number(block),
+        }
+    }
+
+    fn to(&self, block: Option<Long>) -> Option<Account> {
+        self.0.to.map(|address| Account {
+            address,
+            block: block_number(block),
+        })
+    }
+
+    fn value(&self) -> BigInt { BigInt(self.0.value) }
+
+    fn gas_price(&self) -> BigInt { BigInt(self.0.gas_price) }
+
+    fn max_fee_per_gas(&self) -> Option<BigInt> {
+        self.0.max_fee_per_gas.map(BigInt)
+    }
+
+    fn gas(&self) -> Long { Long(self.0.gas.low_u64()) }
+
+    fn input_data(&self) -> Bytes { Bytes(self.0.input.0.clone()) }
+
+    fn raw(&self) -> Bytes { Bytes(self.0.raw.0.clone()) }
+
+    fn block(&self, context: &Context) -> FieldResult<Option<Block>> {
+        match self.0.block_hash {
+            None => Ok(None),
+            Some(hash) => Ok(context
+                .eth
+                .block_by_hash(hash, true)
+                .map_err(rpc_error)?
+                .map(Block)),
+        }
+    }
+
+    fn status(&self, context: &Context) -> FieldResult<Option<Long>> {
+        Ok(self.receipt(context)?.map(|r| Long(r.status_code.as_u64())))
+    }
+
+    fn gas_used(&self, context: &Context) -> FieldResult<Option<Long>> {
+        Ok(self.receipt(context)?.map(|r| Long(r.gas_used.low_u64())))
+    }
+
+    fn cumulative_gas_used(
+        &self, context: &Context,
+    ) -> FieldResult<Option<Long>> {
+        Ok(self
+            .receipt(context)?
+            .map(|r| Long(r.cumulative_gas_used.low_u64())))
+    }
+
+    fn effective_gas_price(
+        &self, context: &Context,
+    ) -> FieldResult<Option<BigInt>> {
+        Ok(self
+            .receipt(context)?
+            .map(|r| BigInt(r.effective_gas_price)))
+    }
+
+    fn created_contract(
+        &self, context: &Context, block: Option<Long>,
+    ) -> FieldResult<Option<Account>> {
+        Ok(self.receipt(context)?.and_then(|r| r.contract_address).map(
+            |address| Account {
+                address,
+                block: block_number(block),
+            },
+        ))
+    }
+
+    fn logs(&self, context: &Context) -> FieldResult<Option<Vec<Log>>> {
+        Ok(self
+            .receipt(context)?
+            .map(|r| r.logs.into_iter().map(Log).collect()))
+    }
+
+    fn r(&self) -> BigInt { BigInt(self.0.r) }
+
+    fn s(&self) -> BigInt { BigInt(self.0.s) }
+
+    fn v(&self) -> BigInt { BigInt(self.0.v) }
+}
+
+/// The input of `call` and `estimateGas`.
+#[derive(GraphQLInputObject)]
+pub struct CallData {
+    from: Option<Address>,
+    to: Option<Address>,
+    gas: Option<Long>,
+    gas_price: Option<BigInt>,
+    max_fee_per_gas: Option<BigInt>,
+    max_priority_fee_per_gas: Option<BigInt>,
+    value: Option<BigInt>,
+    data: Option<Bytes>,
+}
+
+impl From<CallData> for CallRequest {
+    fn from(data: CallData) -> Self {
+        CallRequest {
+            from: data.from.map(|a| a.0),
+            to: data.to.map(|a| a.0),
+            gas_price: data.gas_price.map(|v| v.0),
+            max_fee_per_gas: data.max_fee_per_gas.map(|v| v.0),
+            gas: data.gas.map(|v| v.0.into()),
+            value: data.value.map(|v| v.0),
+            data: data.data.map(|d| RpcBytes::new(d.0)),
+            nonce: None,
+            max_priority_fee_per_gas: data
+                .max_priority_fee_per_gas
+                .map(|v| v.0),
+        }
+    }
+}
+
+/// The result of `call`.
+#[derive(GraphQLObject)]
+pub struct CallResult {
+    /// The return data of the call.
+    data: Bytes,
+    gas_used: Long,
+    /// 1 if the call succeeds, otherwise 0.
+    status: Long,
+}
+
+/// The log filter of `logs` in a block.
+#[derive(GraphQLInputObject)]
+pub struct BlockFilterCriteria {
+    addresses: Option<Vec<Address>>,
+    topics: Option<Vec<Vec<Bytes32>>>,
+}
+
+/// The log filter of the query `logs`.
+#[derive(GraphQLInputObject)]
+pub struct FilterCriteria {
+    from_block: Option<Long>,
+    to_block: Option<Long>,
+    addresses: Option<Vec<Address>>,
+    topics: Option<Vec<Vec<Bytes32>>>,
+}
+
+/// A pivot block of the eSpace.
+pub struct Block(RpcBlock);
+
+impl Block {
+    fn block_number(&self) -> BlockNumber {
+        BlockNumber::Num(self.0.number.as_u64())
+    }
+
+    fn txs(&self) -> &[RpcTransaction] {
+        match &self.0.transactions {
+            BlockTransactions::Full(txs) => txs,
+            BlockTransactions::Hashes(_) => &[],
+        }
+    }
+}
+
+#[graphql_object(context = Context)]
+impl Block {
+    fn number(&self) -> Long { Long(self.0.number.as_u64()) }
+
+    fn hash(&self) -> Bytes32 { Bytes32(self.0.hash) }
+
+    fn parent(&self, context: &Context) -> FieldResult<Option<Block>> {
+        if self.0.number.is_zero() {
+            return Ok(None);
+        }
+        Ok(context
+            .eth
+            .block_by_hash(self.0.parent_hash, true)
+            .map_err(rpc_error)?
+            .map(Block))
+    }
+
+    fn nonce(&self) -> Bytes { Bytes(self.0.nonce.as_bytes().to_vec()) }
+
+    fn transactions_root(&self) -> Bytes32 { Bytes32(self.0.transactions_root) }
+
+    fn transaction_count(&self) -> Option<i32> { Some(self.txs().len() as i32) }
+
+    fn state_root(&self) -> Bytes32 { Bytes32(self.0.state_root) }
+
+    fn receipts_root(&self) -> Bytes32 { Bytes32(self.0.receipts_root) }
+
+    fn miner(&self, block: Option<Long>) -> Account {
+        Account {
+            address: self.0.miner,
+            block: block_number(block),
+        }
+    }
+
+    fn extra_data(&self) -> Bytes { Bytes(self.0.extra_data.0.clone()) }
+
+    fn gas_limit(&self) -> Long { Long(self.0.gas_limit.low_u64()) }
+
+    fn gas_used(&self) -> Long { Long(self.0.gas_used.low_u64()) }
+
+    fn base_fee_per_gas(&self) -> Option<BigInt> {
+        self.0.base_fee_per_gas.map(BigInt)
+    }
+
+    fn timestamp(&self) -> Long { Long(self.0.timestamp.low_u64()) }
+
+    fn logs_bloom(&self) -> Bytes {
+        Bytes(self.0.logs_bloom.as_bytes().to_vec())
+    }
+
+    fn mix_hash(&self) -> Bytes32 { Bytes32(self.0.mix_hash) }
+
+    fn difficulty(&self) -> BigInt { BigInt(self.0.difficulty) }
+
+    fn total_difficulty(&self) -> BigInt { BigInt(self.0.total_difficulty) }
+
+    /// There are no ommers in the eSpace.
+    fn ommer_count(&self) -> Option<i32> { Some(0) }
+
+    fn ommers(&self) -> Option<Vec<Option<Block>>> { Some(vec![]) }
+
+    fn ommer_at(&self, _index: i32) -> Option<Block> { None }
+
+    fn ommer_hash(&self) -> Bytes32 { Bytes32(self.0.uncles_hash) }
+
+    fn transactions(&self) -> Option<Vec<Transaction>> {
+        Some(self.txs().iter().cloned().map(Transaction).collect())
+    }
+
+    fn transaction_at(&self, index: i32) -> Option<Transaction> {
+        if index < 0 {
+            return None;
+        }
+        self.txs().get(index as usize).cloned().map(Transaction)
+    }
+
+    fn logs(
+        &self, context: &Context, filter: BlockFilterCriteria,
+    ) -> FieldResult<Vec<Log>> {
+        query_logs(
+            context,
+            EthRpcLogFilter {
+                from_block: None,
+                to_block: None,
+                block_hash: Some(self.0.hash),
+                address: addresses_filter(filter.addresses),
+                topics: topics_filter(filter.topics),
+                limit: context.eth.get_logs_filter_max_limit(),
+            },
+        )
+    }
+
+    fn account(&self, address: Address) -> Account {
+        Account {
+            address: address.0,
+            block: Some(self.block_number()),
+        }
+    }
+
+    fn call(
+        &self, context: &Context, data: CallData,
+    ) -> FieldResult<Option<CallResult>> {
+        context.throttle("eth_call")?;
+        let outcome = context
+            .eth
+            .exec_transaction(data.into(), Some(self.block_number()))
+            .map_err(rpc_error)?;
+        let (executed, status) = match outcome {
+            ExecutionOutcome::Finished(executed) => (executed, 1),
+            ExecutionOutcome::ExecutionErrorBumpNonce(_, executed) => {
+                (executed, 0)
+            }
+            ExecutionOutcome::NotExecutedDrop(e) => {
+                return Err(
+                    format!("Transaction can not be executed: {:?}", e).into()
+                )
+            }
+            ExecutionOutcome::NotExecutedToReconsiderPacking(e) => {
+                return Err(
+                    format!("Transaction can not be executed: {:?}", e).into()
+                )
+            }
+        };
+        Ok(Some(CallResult {
+            data: Bytes(executed.output),
+            gas_used: Long(executed.gas_used.low_u64()),
+            status: Long(status),
+        }))
+    }
+
+    fn estimate_gas(
+        &self, context: &Context, data: CallData,
+    ) -> FieldResult<Long> {
+        context.throttle("eth_estimateGas")?;
+        Ok(Long(
+            context
+                .eth
+                .estimate_gas(data.into(), Some(self.block_number()))
+                .map_err(rpc_error)?
+                .low_u64(),
+        ))
+    }
+}
+
+/// The sync progress. It is only returned when the node is syncing.
+#[derive(GraphQLObject)]
+pub struct SyncState {
+    starting_block: Long,
+    current_block: Long,
+    highest_block: Long,
+}
+
+pub struct Query;
+
+#[graphql_object(context = Context)]
+impl Query {
+    /// Returns the block by number or by hash. The latest block is returned
+    /// if neither is given.
+    fn block(
+        context: &Context, number: Option<Long>, hash: Option<Bytes32>,
+    ) -> FieldResult<Option<Block>> {
+        match (number, hash) {
+            (Some(_), Some(_)) => {
+                Err("Only one of number or hash can be given".into())
+            }
+            (_, Some(hash)) => Ok(context
+                .eth
+                .block_by_hash(hash.0, true)
+                .map_err(rpc_error)?
+                .map(Block)),
+            (Some(number), None) => {
+                query_block(context, BlockNumber::Num(number.0))
+            }
+            (None, None) => query_block(context, BlockNumber::Latest),
+        }
+    }
+
+    /// Returns the blocks in `[from, to]`. `to` defaults to the latest block.
+    fn blocks(
+        context: &Context, from: Long, to: Option<Long>,
+    ) -> FieldResult<Vec<Block>> {
+        let to = match to {
+            Some(to) => to.0,
+            None => context.eth.block_number().map_err(rpc_error)?.as_u64(),
+        };
+        if to < from.0 {
+            return Ok(vec![]);
+        }
+        if to - from.0 >= context.max_block_range {
+            return Err(format!(
+                "The block range is larger than {}",
+                context.max_block_range
+            )
+            .into());
+        }
+        let mut blocks = Vec::new();
+        for number in from.0..=to {
+            match query_block(context, BlockNumber::Num(number))? {
+                Some(block) => blocks.push(block),
+                None => break,
+            }
+        }
+        Ok(blocks)
+    }
+
+    fn transaction(
+        context: &Context, hash: Bytes32,
+    ) -> FieldResult<Option<Transaction>> {
+        Ok(context
+            .eth
+            .transaction_by_hash(hash.0)
+            .map_err(rpc_error)?
+            .map(Transaction))
+    }
+
+    fn logs(
+        context: &Context, filter: FilterCriteria,
+    ) -> FieldResult<Vec<Log>> {
+        query_logs(
+            context,
+            EthRpcLogFilter {
+                from_block: block_number(filter.from_block),
+                to_block: block_number(filter.to_block),
+                block_hash: None,
+                address: addresses_filter(filter.addresses),
+                topics: topics_filter(filter.topics),
+                limit: context.eth.get_logs_filter_max_limit(),
+            },
+        )
+    }
+
+    fn gas_price(context: &Context) -> FieldResult<BigInt> {
+        Ok(BigInt(context.eth.gas_price().map_err(rpc_error)?))
+    }
+
+    fn max_priority_fee_per_gas(context: &Context) -> FieldResult<BigInt> {
+        Ok(BigInt(
+            context.eth.max_priority_fee_per_gas().map_err(rpc_error)?,
+        ))
+    }
+
+    #[graphql(name = "chainID")]
+    fn chain_id(context: &Context) -> FieldResult<BigInt> {
+        let chain_id = context.eth.chain_id().map_err(rpc_error)?;
+        Ok(BigInt(chain_id.unwrap_or_default().as_u64().into()))
+    }
+
+    fn syncing(context: &Context) -> FieldResult<Option<SyncState>> {
+        Ok(match context.eth.syncing().map_err(rpc_error)? {
+            SyncStatus::Info(info) => Some(SyncState {
+                starting_block: Long(info.starting_block.low_u64()),
+                current_block: Long(info.current_block.low_u64()),
+                highest_block: Long(info.highest_block.low_u64()),
+            }),
+            SyncStatus::None => None,
+        })
+    }
+}
+
+pub struct Mutation;
+
+#[graphql_object(context = Context)]
+impl Mutation {
+    /// Only available if `graphql_enable_mutation` is set.
+    fn send_raw_transaction(
+        context: &Context, data: Bytes,
+    ) -> FieldResult<Bytes32> {
+        if !context.mutation_enabled {
+            return Err("sendRawTransaction is disabled".into());
+        }
+        context.throttle("eth_sendRawTransaction")?;
+        Ok(Bytes32(
+            context
+                .eth
+                .send_raw_transaction(RpcBytes::new(data.0))
+                .map_err(rpc_error)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::{new_schema, BigInt, Bytes, Bytes32, Context, EthBackend};
+    use crate::rpc::{
+        types::{
+            eth::{
+                Block as RpcBlock, BlockNumber, BlockTransactions, CallRequest,
+                EthRpcLogFilter, Log as RpcLog, Receipt as RpcReceipt,
+                SyncStatus, Transaction as RpcTransaction,
+            },
+            Bytes as RpcBytes,
+        },
+        THROTTLING_TEST_LOCK,
+    };
+    use cfx_types::{H160, H256, U256, U64};
+    use cfxcore::executive::ExecutionOutcome;
+    use jsonrpc_core::{Error as RpcError, Result as RpcResult};
+    use juniper::http::GraphQLRequest;
+    use serde_json::{json, Value};
+    use std::{fs, sync::Arc};
+    use tempdir::TempDir;
+
+    const LATEST_BLOCK: u64 = 1;
+
+    fn block_hash(number: u64) -> H256 {
+        H256::from_low_u64_be(number + 1)
+    }
+
+    fn mock_block(number: u64) -> RpcBlock {
+        RpcBlock {
+            hash: block_hash(number),
+            parent_hash: match number {
+                0 => H256::zero(),
+                _ => block_hash(number - 1),
+            },
+            uncles_hash: H256::zero(),
+            author: H160::zero(),
+            miner: H160::zero(),
+            state_root: H256::zero(),
+            transactions_root: H256::zero(),
+            receipts_root: H256::zero(),
+            number: number.into(),
+            gas_used: U256::zero(),
+            gas_limit: 30_000_000.into(),
+            extra_data: RpcBytes::new(vec![]),
+            logs_bloom: Default::default(),
+            timestamp: number.into(),
+            difficulty: U256::zero(),
+            total_difficulty: U256::zero(),
+            base_fee_per_gas: None,
+            uncles: vec![],
+            transactions: BlockTransactions::Full(vec![]),
+            size: U256::zero(),
+            nonce: Default::default(),
+            mix_hash: H256::zero(),
+        }
+    }
+
+    /// Serves blocks 0 and 1 without transactions. An account has a balance
+    /// of 100 except at block 0.
+    pub struct MockEth;
+
+    impl EthBackend for MockEth {
+        fn block_number(&self) -> RpcResult<U256> { Ok(LATEST_BLOCK.into()) }
+
+        fn block_by_number(
+            &self, number: BlockNumber, _include_txs: bool,
+        ) -> RpcResult<Option<RpcBlock>> {
+            Ok(match number {
+                BlockNumber::Num(number) if number <= LATEST_BLOCK => {
+                    Some(mock_block(number))
+                }
+                BlockNumber::Latest => Some(mock_block(LATEST_BLOCK)),
+                _ => None,
+            })
+        }
+
+        fn block_by_hash(
+            &self, hash: H256, _include_txs: bool,
+        ) -> RpcResult<Option<RpcBlock>> {
+            Ok((0..=LATEST_BLOCK)
+                .find(|number| block_hash(*number) == hash)
+                .map(mock_block))
+        }
+
+        fn balance(
+            &self, _address: H160, block: Option<BlockNumber>,
+        ) -> RpcResult<U256> {
+            Ok(match block {
+                Some(BlockNumber::Num(0)) => U256::zero(),
+                _ => 100.into(),
+            })
+        }
+
+        fn transaction_count(
+            &self, _address: H160, _block: Option<BlockNumber>,
+        ) -> RpcResult<U256> {
+            Err(RpcError::internal_error())
+        }
+
+        fn code_at(
+            &self, _address: H160, _block: Option<BlockNumber>,
+        ) -> RpcResult<RpcBytes> {
+            Err(RpcError::internal_error())
+        }
+
+        fn storage_at(
+            &self, _address: H160, _slot: U256, _block: Option<BlockNumber>,
+        ) -> RpcResult<H256> {
+            Err(RpcError::internal_error())
+        }
+
+        fn transaction_by_hash(
+            &self, _hash: H256,
+        ) -> RpcResult<Option<RpcTransaction>> {
+            Ok(None)
+        }
+
+        fn transaction_receipt(
+            &self, _hash: H256,
+        ) -> RpcResult<Option<RpcReceipt>> {
+            Ok(None)
+        }
+
+        fn logs(&self, _filter: EthRpcLogFilter) -> RpcResult<Vec<RpcLog>> {
+            Ok(vec![])
+        }
+
+        fn get_logs_filter_max_limit(&self) -> Option<usize> { None }
+
+        fn exec_transaction(
+            &self, _request: CallRequest, _block: Option<BlockNumber>,
+        ) -> RpcResult<ExecutionOutcome> {
+            Err(RpcError::internal_error())
+        }
+
+        fn estimate_gas(
+            &self, _request: CallRequest, _block: Option<BlockNumber>,
+        ) -> RpcResult<U256> {
+            Ok(21000.into())
+        }
+
+        fn gas_price(&self) -> RpcResult<U256> { Ok(1_000_000_000.into()) }
+
+        fn max_priority_fee_per_gas(&self) -> RpcResult<U256> {
+            Ok(U256::one())
+        }
+
+        fn chain_id(&self) -> RpcResult<Option<U64>> { Ok(Some(71.into())) }
+
+        fn syncing(&self) -> RpcResult<SyncStatus> { Ok(SyncStatus::None) }
+
+        fn send_raw_transaction(&self, raw: RpcBytes) -> RpcResult<H256> {
+            Ok(H256::from_low_u64_be(raw.0.len() as u64))
+        }
+    }
+
+    /// A context over `MockEth`, where at most 2 blocks can be queried by
+    /// `blocks`.
+    pub fn new_context(
+        mutation_enabled: bool, throttling_conf: &Option<String>,
+    ) -> Context {
+        Context::new(Arc::new(MockEth), 2, mutation_enabled, throttling_conf)
+    }
+
+    fn execute(context: &Context, query: &str) -> Value {
+        let request: GraphQLRequest =
+            GraphQLRequest::new(query.into(), None, None);
+        serde_json::to_value(&request.execute_sync(&new_schema(), context))
+            .unwrap()
+    }
+
+    fn error_message(result: &Value) -> &str {
+        result["errors"][0]["message"].as_str().expect("has error")
+    }
+
+    #[test]
+    fn test_hex_scalars() {
+        assert_eq!(BigInt::from_hex("0x10").unwrap().0, U256::from(16));
+        assert_eq!(BigInt::from_hex("10").unwrap().0, U256::from(10));
+        assert!(BigInt::from_hex("0xzz").is_none());
+        assert_eq!(BigInt(U256::from(255)).to_hex(), "0xff");
+
+        let hash = H256::from_low_u64_be(1);
+        let hex = Bytes32(hash).to_hex();
+        assert_eq!(Bytes32::from_hex(&hex).unwrap().0, hash);
+        assert!(Bytes32::from_hex("0x01").is_none());
+
+        assert_eq!(Bytes(vec![0xab, 0x01]).to_hex(), "0xab01");
+        assert_eq!(Bytes::from_hex("ab01").unwrap().0, vec![0xab, 0x01]);
+    }
+
+    #[test]
+    fn test_block_resolvers() {
+        let _guard = THROTTLING_TEST_LOCK.lock();
+        let context = new_context(false, &None);
+        let query = r#"{
+            block(number: 1) {
+                number
+                hash
+                transactionCount
+                parent { number account(address: $address) { balance } }
+                account(address: $address) { balance }
+                estimateGas(data: {})
+            }
+            gasPrice
+            chainID
+        }"#
+        .replace("$address", &format!("\"{:?}\"", H160::from_low_u64_be(1)));
+        let result = execute(&context, &query);
+        assert_eq!(
+            result,
+            json!({
+                "data": {
+                    "block": {
+                        "number": "0x1",
+                        "hash": format!("{:?}", block_hash(1)),
+                        "transactionCount": 0,
+                        "parent": {
+                            "number": "0x0",
+                            "account": { "balance": "0x0" },
+                        },
+                        "account": { "balance": "0x64" },
+                        "estimateGas": "0x5208",
+                    },
+                    "gasPrice": "0x3b9aca00",
+                    "chainID": "0x47",
+                }
+            })
+        );
+
+        let hash = format!("{:?}", block_hash(0));
+        let result = execute(
+            &context,
+            &format!("{{ block(hash: \"{}\") {{ number }} }}", hash),
+        );
+        assert_eq!(result, json!({ "data": { "block": { "number": "0x0" } } }));
+        let result = execute(&context, "{ block(number: 2) { number } }");
+        assert_eq!(result, json!({ "data": { "block": null } }));
+        let result = execute(
+            &context,
+            &format!("{{ block(number: 0, hash: \"{}\") {{ number }} }}", hash),
+        );
+        assert_eq!(
+            error_message(&result),
+            "Only one of number or hash can be given"
+        );
+    }
+
+    #[test]
+    fn test_blocks_range() {
+        let _guard = THROTTLING_TEST_LOCK.lock();
+        let context = new_context(false, &None);
+        let result = execute(&context, "{ blocks(from: 0) { number } }");
+        assert_eq!(
+            result,
+            json!({
+                "data": { "blocks": [{ "number": "0x0" }, { "number": "0x1" }] }
+            })
+        );
+        let result = execute(&context, "{ blocks(from: 0, to: 2) { number } }");
+        assert_eq!(error_message(&result), "The block range is larger than 2");
+    }
+
+    #[test]
+    fn test_send_raw_transaction() {
+        let _guard = THROTTLING_TEST_LOCK.lock();
+        let query = r#"mutation { sendRawTransaction(data: "0x0102") }"#;
+        let result = execute(&new_context(false, &None), query);
+        assert_eq!(error_message(&result), "sendRawTransaction is disabled");
+
+        let result = execute(&new_context(true, &None), query);
+        assert_eq!(
+            result,
+            json!({
+                "data": {
+                    "sendRawTransaction": format!(
+                        "{:?}",
+                        H256::from_low_u64_be(2)
+                    )
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_throttling() {
+        let _guard = THROTTLING_TEST_LOCK.lock();
+        let dir = TempDir::new("graphql_throttling").unwrap();
+        let path = dir.path().join("throttling.toml");
+        fs::write(&path, "[rpc]\neth_getBlockByNumber=\"1,1,1,1,0\"\n")
+            .unwrap();
+        let context =
+            new_context(false, &Some(path.to_string_lossy().into_owned()));
+
+        // The second block is throttled.
+        let result = execute(&context, "{ blocks(from: 0) { number } }");
+        assert_eq!(error_message(&result), "Request rejected.");
+        // The other resolvers are not throttled.
+        let result = execute(&context, "{ gasPrice }");
+        assert_eq!(result, json!({ "data": { "gasPrice": "0x3b9aca00" } }));
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright 2022 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Lightweight HTTP endpoints for load balancers and container probes.
+//!
+//! - `GET /health` reports whether the process is alive and the block database
+//!   can still be read.
+//! - `GET /ready` reports whether the node is in `NormalSyncPhase`, has enough
+//!   peers, executes epochs in time and has caught up with PoS.
+//!
+//! Both endpoints respond `200 OK` on success and `503 Service Unavailable`
+//! otherwise, with the details of the checks as a JSON body.
+
+use crate::rpc::hyper_server::{start_hyper_server, HyperServer};
+use cfxcore::{
+    block_data_manager::BlockDataManager, consensus::pos_handler::PosVerifier,
+    sync::SyncPhaseType, ConsensusGraph, ConsensusGraphTrait,
+    SynchronizationService,
+};
+use hyper::{
+    header::CONTENT_TYPE, Body, Method, Request, Response, StatusCode,
+};
+use network::NetworkService;
+use parking_lot::{Condvar, Mutex};
+use serde::Serialize;
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::{Arc, Weak},
+};
+
+pub struct HealthConfiguration {
+    pub enabled: bool,
+    pub address: SocketAddr,
+    /// The minimal number of connected peers for the node to be ready.
+    pub min_peers: usize,
+    /// The maximal number of epochs that the best executed epoch may lag
+    /// behind the best epoch for the node to be ready.
+    pub max_epoch_lag: u64,
+}
+
+impl HealthConfiguration {
+    pub fn new(
+        ip: Option<(u8, u8, u8, u8)>, port: Option<u16>, min_peers: usize,
+        max_epoch_lag: u64,
+    ) -> Self
+    {
+        let ipv4 = match ip {
+            Some(ip) => Ipv4Addr::new(ip.0, ip.1, ip.2, ip.3),
+            None => Ipv4Addr::new(127, 0, 0, 1),
+        };
+        HealthConfiguration {
+            enabled: port.is_some(),
+            address: SocketAddr::V4(SocketAddrV4::new(ipv4, port.unwrap_or(0))),
+            min_peers,
+            max_epoch_lag,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthStatus {
+    pub healthy: bool,
+    pub db_open: bool,
+    pub shutting_down: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadinessStatus {
+    pub ready: bool,
+    pub sync_phase: &'static str,
+    pub peer_count: usize,
+    pub min_peers: usize,
+    pub best_epoch_number: u64,
+    pub best_executed_epoch_number: u64,
+    pub max_epoch_lag: u64,
+    pub pos_enabled: bool,
+    pub pos_synced: bool,
+}
+
+impl ReadinessStatus {
+    fn all_checks_pass(&self, normal_sync_phase: bool) -> bool {
+        normal_sync_phase
+            && self.peer_count >= self.min_peers
+            && self.best_epoch_number
+                <= self.best_executed_epoch_number + self.max_epoch_lag
+            && (!self.pos_enabled || self.pos_synced)
+    }
+}
+
+/// Evaluates the checks of the endpoints from the state of the running node.
+pub struct HealthChecker {
+    data_man: Weak<BlockDataManager>,
+    exit: Arc<(Mutex<bool>, Condvar)>,
+    consensus: Arc<ConsensusGraph>,
+    sync: Arc<SynchronizationService>,
+    network: Arc<NetworkService>,
+    pos_verifier: Arc<PosVerifier>,
+    min_peers: usize,
+    max_epoch_lag: u64,
+}
+
+impl HealthChecker {
+    pub fn new(
+        conf: &HealthConfiguration, data_man: Weak<BlockDataManager>,
+        exit: Arc<(Mutex<bool>, Condvar)>, consensus: Arc<ConsensusGraph>,
+        sync: Arc<SynchronizationService>, network: Arc<NetworkService>,
+        pos_verifier: Arc<PosVerifier>,
+    ) -> Self
+    {
+        HealthChecker {
+            data_man,
+            exit,
+            consensus,
+            sync,
+            network,
+            pos_verifier,
+            min_peers: conf.min_peers,
+            max_epoch_lag: conf.max_epoch_lag,
+        }
+    }
+
+    pub fn health(&self) -> HealthStatus {
+        // The block data manager owns the database and is dropped when the
+        // client shuts down.
+        let db_open = self
+            .data_man
+            .upgrade()
+            .map_or(false, |data_man| data_man.db_manager.is_readable());
+        let shutting_down = *self.exit.0.lock();
+        HealthStatus {
+            healthy: db_open && !shutting_down,
+            db_open,
+            shutting_down,
+        }
+    }
+
+    pub fn readiness(&self) -> ReadinessStatus {
+        let phase = self.sync.current_sync_phase();
+        let peer_count =
+            self.network.get_peer_info().map_or(0, |peers| peers.len());
+        let best_epoch_number = self.consensus.best_epoch_number();
+        let best_executed_epoch_number =
+            self.consensus.best_executed_state_epoch_number();
+        let pos_enabled =
+            self.pos_verifier.is_enabled_at_height(best_epoch_number);
+        let pos_synced = !pos_enabled || self.pos_synced();
+
+        let mut status = ReadinessStatus {
+            ready: false,
+            sync_phase: phase.name(),
+            peer_count,
+            min_peers: self.min_peers,
+            best_epoch_number,
+            best_executed_epoch_number,
+            max_epoch_lag: self.max_epoch_lag,
+            pos_enabled,
+            pos_synced,
+        };
+        status.ready =
+            status.all_checks_pass(phase.phase_type() == SyncPhaseType::Normal);
+        status
+    }
+
+    /// PoS is regarded as synced if the pivot decision of the latest committed
+    /// PoS block is already in our consensus graph.
+    fn pos_synced(&self) -> bool {
+        if self.pos_verifier.pos_option().is_none() {
+            return false;
+        }
+        let latest = self.pos_verifier.get_latest_pos_reference();
+        match self.pos_verifier.get_pivot_decision(&latest) {
+            Some(pivot_decision) => self
+                .consensus
+                .get_block_epoch_number(&pivot_decision)
+                .is_some(),
+            None => false,
+        }
+    }
+}
+
+fn json_response<T: Serialize>(ok: bool, body: &T) -> Response<Body> {
+    let mut response = Response::new(Body::from(
+        serde_json::to_vec(body).expect("serialization cannot fail; qed"),
+    ));
+    if !ok {
+        *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+    }
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    response
+}
+
+/// Run a check on the blocking threads, because the checks read the
+/// database and wait for the locks of the consensus graph and the network.
+async fn blocking_check(
+    checker: Arc<HealthChecker>, check: fn(&HealthChecker) -> Response<Body>,
+) -> Response<Body>
+{
+    tokio::task::spawn_blocking(move || check(&checker))
+        .await
+        .unwrap_or_else(|e| {
+            let mut response = Response::new(Body::from(e.to_string()));
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        })
+}
+
+async fn handle_request(
+    checker: Arc<HealthChecker>, req: Request<Body>,
+) -> Response<Body> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/health") => {
+            blocking_check(checker, |checker| {
+                let status = checker.health();
+                json_response(status.healthy, &status)
+            })
+            .await
+        }
+        (&Method::GET, "/ready") => {
+            blocking_check(checker, |checker| {
+                let status = checker.readiness();
+                json_response(status.ready, &status)
+            })
+            .await
+        }
+        _ => {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            response
+        }
+    }
+}
+
+pub fn start_health(
+    conf: &HealthConfiguration, checker: HealthChecker,
+) -> Result<Option<HyperServer>, String> {
+    if !conf.enabled {
+        return Ok(None);
+    }
+
+    let checker = Arc::new(checker);
+    start_hyper_server("Health", &conf.address, Some(1), move |req| {
+        handle_request(checker.clone(), req)
+    })
+    .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_response, HealthConfiguration, ReadinessStatus};
+    use hyper::StatusCode;
+    use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+    fn ready_status() -> ReadinessStatus {
+        ReadinessStatus {
+            ready: false,
+            sync_phase: "NormalSyncPhase",
+            peer_count: 3,
+            min_peers: 3,
+            best_epoch_number: 150,
+            best_executed_epoch_number: 100,
+            max_epoch_lag: 50,
+            pos_enabled: true,
+            pos_synced: true,
+        }
+    }
+
+    #[test]
+    fn test_readiness_checks() {
+        let status = ready_status();
+        assert!(status.all_checks_pass(true));
+        assert!(!status.all_checks_pass(false));
+
+        let mut status = ready_status();
+        status.peer_count = 2;
+        assert!(!status.all_checks_pass(true));
+
+        let mut status = ready_status();
+        status.best_executed_epoch_number = 99;
+        assert!(!status.all_checks_pass(true));
+
+        let mut status = ready_status();
+        status.pos_synced = false;
+        assert!(!status.all_checks_pass(true));
+        status.pos_enabled = false;
+        assert!(status.all_checks_pass(true));
+    }
+
+    #[test]
+    fn test_readiness_response() {
+        let mut status = ready_status();
+        assert_eq!(
+            json_response(status.ready, &status).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        status.ready = true;
+        let response = json_response(status.ready, &status);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+    }
+
+    #[test]
+    fn test_health_configuration() {
+        let conf = HealthConfiguration::new(None, Some(8548), 1, 50);
+        assert!(conf.enabled);
+        assert_eq!(
+            conf.address,
+            SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8548))
+        );
+        assert!(!HealthConfiguration::new(None, None, 1, 50).enabled);
+    }
+}
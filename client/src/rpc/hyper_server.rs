@@ -0,0 +1,100 @@
+// Copyright 2022 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! A small HTTP server on top of hyper for the endpoints which are not
+//! JSON-RPC, e.g. GraphQL and the health checks.
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use std::{
+    convert::Infallible, future::Future, net::SocketAddr, sync::Arc,
+    time::Duration,
+};
+use tokio::{runtime::Runtime, sync::oneshot};
+
+/// A running server. It runs on its own runtime and is stopped when dropped.
+pub struct HyperServer {
+    address: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    runtime: Option<Runtime>,
+}
+
+impl HyperServer {
+    /// The address which the server is bound to, e.g. with the port chosen
+    /// by the OS if the configured port is 0.
+    pub fn local_addr(&self) -> SocketAddr { self.address }
+}
+
+impl Drop for HyperServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            // The server may have already exited.
+            shutdown.send(()).ok();
+        }
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_timeout(Duration::from_secs(1));
+        }
+    }
+}
+
+/// Start serving the requests at `address` with `handler`. `name` is used
+/// for the runtime threads and the log and error messages, and
+/// `worker_threads` defaults to the number of cores.
+pub fn start_hyper_server<F, R>(
+    name: &str, address: &SocketAddr, worker_threads: Option<usize>, handler: F,
+) -> Result<HyperServer, String>
+where
+    F: Fn(Request<Body>) -> R + Send + Sync + 'static,
+    R: Future<Output = Response<Body>> + Send + 'static,
+{
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    if let Some(worker_threads) = worker_threads {
+        builder.worker_threads(worker_threads);
+    }
+    let runtime = builder
+        .thread_name(name.to_lowercase())
+        .enable_all()
+        .build()
+        .map_err(|e| format!("{} server error: {}", name, e))?;
+    let handler = Arc::new(handler);
+    let make_service = make_service_fn(move |_| {
+        let handler = handler.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let response = handler(req);
+                async move { Ok::<_, Infallible>(response.await) }
+            }))
+        }
+    });
+
+    let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+    let server = {
+        // Binding the listener requires the reactor of the runtime.
+        let _guard = runtime.enter();
+        Server::try_bind(address)
+            .map_err(|e| {
+                format!("{} server error: {} (addr = {})", name, e, address)
+            })?
+            .serve(make_service)
+    };
+    let address = server.local_addr();
+    let server = server.with_graceful_shutdown(async {
+        shutdown_signal.await.ok();
+    });
+    let server_name = name.to_string();
+    runtime.spawn(async move {
+        if let Err(e) = server.await {
+            warn!("{} server exits with error: {}", server_name, e);
+        }
+    });
+    info!("{} server started at {}", name, address);
+
+    Ok(HyperServer {
+        address,
+        shutdown: Some(shutdown),
+        runtime: Some(runtime),
+    })
+}
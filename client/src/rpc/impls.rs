@@ -7,10 +7,9 @@ use crate::rpc::rpc_apis::ApiSet;
 #[derive(Clone, Default)]
 pub struct RpcImplConfiguration {
     pub get_logs_filter_max_limit: Option<usize>,
-    /// If it's `true`, `DEFERRED_STATE_EPOCH_COUNT` blocks are generated after
-    /// receiving a new tx through RPC calling to pack and execute this
-    /// transaction.
-    pub dev_pack_tx_immediately: bool,
+    /// If it's `true`, the dev mode RPCs which manipulate the chain and the
+    /// state are enabled.
+    pub dev_mode: bool,
 
     // maximum response payload size allowed
     // note: currently we only handle this for `cfx_getEpochReceipts`,
@@ -22,6 +21,7 @@ pub struct RpcImplConfiguration {
 
 pub mod cfx;
 pub mod common;
+pub mod dev;
 pub mod eth;
 pub mod light;
 pub mod pool;
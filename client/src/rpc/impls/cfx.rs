@@ -5,7 +5,7 @@
 use crate::rpc::types::{
     call_request::rpc_call_request_network, errors::check_rpc_address_network,
     pos::PoSEpochReward, PoSEconomics, RpcAddress, SponsorInfo,
-    TokenSupplyInfo, MAX_GAS_CALL_REQUEST,
+    TokenSupplyInfo, VoteParamsInfo, MAX_GAS_CALL_REQUEST,
 };
 use blockgen::BlockGenerator;
 use cfx_state::state_trait::StateOpsTrait;
@@ -15,7 +15,13 @@ use cfx_types::{
     U64,
 };
 use cfxcore::{
-    executive::{ExecutionError, ExecutionOutcome, TxDropError},
+    executive::{
+        internal_contract::impls::params_control::{
+            next_value, total_votes, vote_round, INTEREST_RATE_INDEX,
+            POW_BASE_REWARD_INDEX,
+        },
+        ExecutionError, ExecutionOutcome, TxDropError,
+    },
     rpc_errors::{account_result_to_rpc_result, invalid_params_check},
     state_exposer::STATE_EXPOSER,
     vm, ConsensusGraph, ConsensusGraphTrait, PeerInfo, SharedConsensusGraph,
@@ -27,7 +33,8 @@ use diem_types::transaction::TransactionPayload;
 use jsonrpc_core::{BoxFuture, Error as JsonRpcError, Result as JsonRpcResult};
 use network::{
     node_table::{Node, NodeId},
-    throttling, SessionDetails, UpdateNodeOperation,
+    throttling, AllowlistReloadResult, SessionDetails, SessionLimitsUpdate,
+    SessionLimitsUpdateResult, UpdateNodeOperation,
 };
 use parking_lot::Mutex;
 use primitives::{
@@ -39,12 +46,14 @@ use random_crash::*;
 use rlp::Rlp;
 use rustc_hex::ToHex;
 use std::{
-    collections::BTreeMap, net::SocketAddr, sync::Arc, thread, time::Duration,
+    cmp::min, collections::BTreeMap, convert::TryFrom, net::SocketAddr,
+    sync::Arc,
 };
 use txgen::{DirectTransactionGenerator, TransactionGenerator};
 // To convert from RpcResult to BoxFuture by delegate! macro automatically.
 use crate::{
     common::delegate_convert,
+    logging::LogLevelChange,
     rpc::{
         error_codes::{
             call_execution_error, invalid_params, pivot_assumption_failed,
@@ -52,33 +61,36 @@ use crate::{
         },
         impls::{
             common::{self, RpcImpl as CommonImpl},
+            dev::DevRpcImpl,
             RpcImplConfiguration,
         },
         traits::{cfx::Cfx, debug::LocalRpc, test::TestRpc},
         types::{
             pos::Block as PosBlock, sign_call, Account as RpcAccount,
-            AccountPendingInfo, AccountPendingTransactions, BlameInfo,
-            Block as RpcBlock, BlockHashOrEpochNumber, Bytes, CallRequest,
-            CfxRpcLogFilter, CheckBalanceAgainstTransactionResponse,
+            AccountPendingInfo, AccountPendingTransactions,
+            AddressTransactionsPage, BlameInfo, Block as RpcBlock,
+            BlockHashOrEpochNumber, Bytes, CallRequest, CfxRpcLogFilter,
+            CheckBalanceAgainstTransactionResponse, ConsensusBlockInfo,
             ConsensusGraphStates, EpochNumber,
-            EstimateGasAndCollateralResponse, Log as RpcLog, PackedOrExecuted,
-            Receipt as RpcReceipt, RewardInfo as RpcRewardInfo, SendTxRequest,
-            Status as RpcStatus, SyncGraphStates,
-            Transaction as RpcTransaction,
+            EstimateGasAndCollateralResponse, Log as RpcLog, LogCursor,
+            LogsPage, PackedOrExecuted, Receipt as RpcReceipt,
+            RewardInfo as RpcRewardInfo, SendTxRequest, Status as RpcStatus,
+            StratumWorker, SyncGraphStates, Transaction as RpcTransaction,
+            DEFAULT_LOGS_PAGE_SIZE,
         },
-        RpcResult,
+        RpcResult, ThrottlingReloadResult,
     },
 };
 use cfx_addr::Network;
 use cfx_parameters::consensus_internal::REWARD_EPOCH_COUNT;
 use cfxcore::{
     consensus::{MaybeExecutedTxExtraInfo, TransactionInfo},
-    consensus_parameters::DEFERRED_STATE_EPOCH_COUNT,
     executive::revert_reason_decode,
     observer::ErrorUnwind,
     spec::genesis::{
         genesis_contract_address_four_year, genesis_contract_address_two_year,
     },
+    state::StateOverride,
 };
 use diem_types::account_address::AccountAddress;
 use lazy_static::lazy_static;
@@ -92,6 +104,13 @@ lazy_static! {
         register_timer_with_group("rpc", "rpc:getLogs");
 }
 
+/// The maximum number of epochs that can be queried by `consensus_dag`.
+const MAX_CONSENSUS_DAG_EPOCHS: u64 = 100;
+/// The maximum number of epochs that can be queried by `consensus_dag` with
+/// the anticone sizes, each of which is computed by traversing the whole
+/// graph.
+const MAX_CONSENSUS_DAG_EPOCHS_WITH_ANTICONE: u64 = 10;
+
 #[derive(Debug)]
 pub(crate) struct BlockExecInfo {
     pub(crate) block_receipts: Arc<BlockReceipts>,
@@ -105,11 +124,12 @@ pub struct RpcImpl {
     pub config: RpcImplConfiguration,
     pub consensus: SharedConsensusGraph,
     pub sync: SharedSynchronizationService,
-    block_gen: Arc<BlockGenerator>,
+    pub block_gen: Arc<BlockGenerator>,
     pub tx_pool: SharedTransactionPool,
     maybe_txgen: Option<Arc<TransactionGenerator>>,
     maybe_direct_txgen: Option<Arc<Mutex<DirectTransactionGenerator>>>,
     accounts: Arc<AccountProvider>,
+    pub dev: DevRpcImpl,
 }
 
 impl RpcImpl {
@@ -121,6 +141,11 @@ impl RpcImpl {
         config: RpcImplConfiguration, accounts: Arc<AccountProvider>,
     ) -> Self
     {
+        let dev = DevRpcImpl::new(
+            config.dev_mode,
+            consensus.clone(),
+            block_gen.clone(),
+        );
         RpcImpl {
             consensus,
             sync,
@@ -130,6 +155,7 @@ impl RpcImpl {
             maybe_direct_txgen,
             config,
             accounts,
+            dev,
         }
     }
 
@@ -244,6 +270,24 @@ impl RpcImpl {
         }
     }
 
+    fn transactions_by_address(
+        &self, address: RpcAddress, cursor: Option<U64>, limit: Option<U64>,
+    ) -> RpcResult<AddressTransactionsPage> {
+        self.check_address_network(address.network)?;
+
+        info!(
+            "RPC Request: cfx_getTransactionsByAddress address={:?} cursor={:?} limit={:?}",
+            address, cursor, limit
+        );
+
+        Ok(AddressTransactionsPage::query(
+            self.consensus.get_data_manager(),
+            address.hex_address.with_native_space(),
+            cursor,
+            limit,
+        )?)
+    }
+
     fn staking_balance(
         &self, address: RpcAddress, num: Option<EpochNumber>,
     ) -> RpcResult<U256> {
@@ -424,30 +468,10 @@ impl RpcImpl {
             ));
         }
 
-        let r = self.send_transaction_with_signature(tx);
-        if r.is_ok() && self.config.dev_pack_tx_immediately {
-            // Try to pack and execute this new tx.
-            for _ in 0..DEFERRED_STATE_EPOCH_COUNT {
-                let generated = self.generate_one_block(
-                    1, /* num_txs */
-                    self.sync
-                        .get_synchronization_graph()
-                        .verification_config
-                        .max_block_size_in_bytes,
-                )?;
-                loop {
-                    // Wait for the new block to be fully processed, so all
-                    // generated blocks form a chain for
-                    // `tx` to be executed.
-                    if self.consensus.best_block_hash() == generated {
-                        break;
-                    } else {
-                        thread::sleep(Duration::from_millis(10));
-                    }
-                }
-            }
-        }
-        r
+        let hash = self.send_transaction_with_signature(tx)?;
+        // Try to pack and execute this new tx.
+        self.dev.automine()?;
+        Ok(hash)
     }
 
     fn storage_at(
@@ -558,8 +582,11 @@ impl RpcImpl {
     ) -> RpcResult<H256> {
         info!("RPC Request: cfx_sendTransaction, tx = {:?}", tx);
 
-        self.prepare_transaction(tx, password)
-            .and_then(|tx| self.send_transaction_with_signature(tx))
+        let hash = self
+            .prepare_transaction(tx, password)
+            .and_then(|tx| self.send_transaction_with_signature(tx))?;
+        self.dev.automine()?;
+        Ok(hash)
     }
 
     pub fn sign_transaction(
@@ -904,6 +931,42 @@ impl RpcImpl {
             .generate_block(num_txs, block_size_limit, vec![]))
     }
 
+    fn set_balance(&self, address: RpcAddress, balance: U256) -> RpcResult<()> {
+        info!(
+            "RPC Request: test_setBalance address={:?} balance={:?}",
+            address, balance
+        );
+        self.check_address_network(address.network)?;
+        self.dev.override_state(StateOverride::Balance {
+            address: address.hex_address.with_native_space(),
+            balance,
+        })
+    }
+
+    fn set_code(&self, address: RpcAddress, code: Bytes) -> RpcResult<()> {
+        info!("RPC Request: test_setCode address={:?}", address);
+        self.check_address_network(address.network)?;
+        self.dev.override_state(StateOverride::Code {
+            address: address.hex_address.with_native_space(),
+            code: code.into_vec(),
+        })
+    }
+
+    fn set_storage_at(
+        &self, address: RpcAddress, position: U256, value: H256,
+    ) -> RpcResult<()> {
+        info!(
+            "RPC Request: test_setStorageAt address={:?} position={:?} value={:?}",
+            address, position, value
+        );
+        self.check_address_network(address.network)?;
+        self.dev.override_state(StateOverride::Storage {
+            address: address.hex_address.with_native_space(),
+            key: position,
+            value: value.into_uint(),
+        })
+    }
+
     fn generate_one_block_with_direct_txgen(
         &self, num_txs: usize, mut block_size_limit: usize,
         num_txs_simple: usize, num_txs_erc20: usize,
@@ -1034,7 +1097,9 @@ impl RpcImpl {
         ))
     }
 
-    fn get_logs(&self, filter: CfxRpcLogFilter) -> RpcResult<Vec<RpcLog>> {
+    fn check_log_filter_address_network(
+        &self, filter: &CfxRpcLogFilter,
+    ) -> RpcResult<()> {
         // all addresses specified should be for the correct network
         if let Some(addresses) = &filter.address {
             for address in addresses.iter() {
@@ -1047,6 +1112,11 @@ impl RpcImpl {
                 )?;
             }
         }
+        Ok(())
+    }
+
+    fn get_logs(&self, filter: CfxRpcLogFilter) -> RpcResult<Vec<RpcLog>> {
+        self.check_log_filter_address_network(&filter)?;
 
         let _timer = ScopeTimer::time_scope(GET_LOGS_TIMER.as_ref());
         let consensus_graph = self.consensus_graph();
@@ -1076,6 +1146,51 @@ impl RpcImpl {
             .collect::<Result<_, _>>()?)
     }
 
+    fn get_logs_with_cursor(
+        &self, filter: CfxRpcLogFilter, cursor: Option<LogCursor>,
+    ) -> RpcResult<LogsPage<RpcLog>> {
+        self.check_log_filter_address_network(&filter)?;
+
+        let _timer = ScopeTimer::time_scope(GET_LOGS_TIMER.as_ref());
+        let consensus_graph = self.consensus_graph();
+
+        info!(
+            "RPC Request: cfx_getLogsWithCursor({:?}, {:?})",
+            filter, cursor
+        );
+        let filter: LogFilter = filter.into_primitive()?;
+
+        if filter.offset.is_some() {
+            bail!(invalid_params(
+                "filter.offset",
+                "offset is not supported with cursors"
+            ));
+        }
+        let mut limit = filter.limit.unwrap_or(DEFAULT_LOGS_PAGE_SIZE);
+        if let Some(max_limit) = self.config.get_logs_filter_max_limit {
+            limit = min(limit, max_limit);
+        }
+        if limit == 0 {
+            bail!(invalid_params("filter.limit", "limit should be positive"));
+        }
+
+        let (logs, next_cursor) =
+            consensus_graph.logs_page(filter, cursor.map(Into::into), limit)?;
+
+        Ok(LogsPage {
+            logs: logs
+                .into_iter()
+                .map(|l| {
+                    RpcLog::try_from_localized(
+                        l,
+                        *self.sync.network.get_network_type(),
+                    )
+                })
+                .collect::<Result<_, _>>()?,
+            next_cursor: next_cursor.map(Into::into),
+        })
+    }
+
     fn get_block_reward_info(
         &self, epoch: EpochNumber,
     ) -> RpcResult<Vec<RpcRewardInfo>> {
@@ -1408,11 +1523,50 @@ impl RpcImpl {
         Ok(ConsensusGraphStates::new(consensus_graph_states))
     }
 
+    pub fn consensus_block_info(
+        &self, block_hash: H256,
+    ) -> RpcResult<Option<ConsensusBlockInfo>> {
+        let inner = self.consensus_graph().inner.read();
+        Ok(inner
+            .block_consensus_info(&block_hash, true /* with_anticone */)
+            .map(ConsensusBlockInfo::new))
+    }
+
+    pub fn consensus_dag(
+        &self, from_epoch: U64, to_epoch: U64, with_anticone: Option<bool>,
+    ) -> RpcResult<Vec<ConsensusBlockInfo>> {
+        let (from_epoch, to_epoch) = (from_epoch.as_u64(), to_epoch.as_u64());
+        let with_anticone = with_anticone.unwrap_or(false);
+        // The consensus graph is locked while the blocks are collected, so
+        // the range is bounded.
+        let max_epochs = if with_anticone {
+            MAX_CONSENSUS_DAG_EPOCHS_WITH_ANTICONE
+        } else {
+            MAX_CONSENSUS_DAG_EPOCHS
+        };
+        if to_epoch.saturating_sub(from_epoch) >= max_epochs {
+            bail!(invalid_params(
+                "to_epoch",
+                format!("At most {} epochs can be queried", max_epochs)
+            ));
+        }
+        let inner = self.consensus_graph().inner.read();
+        let blocks = inner
+            .epoch_range_consensus_info(from_epoch, to_epoch, with_anticone)
+            .map_err(|e| invalid_params("epoch range", e))?;
+        Ok(blocks.into_iter().map(ConsensusBlockInfo::new).collect())
+    }
+
     pub fn sync_graph_state(&self) -> RpcResult<SyncGraphStates> {
         let sync_graph_states = STATE_EXPOSER.sync_graph.lock().retrieve();
         Ok(SyncGraphStates::new(sync_graph_states))
     }
 
+    pub fn stratum_workers(&self) -> RpcResult<Vec<StratumWorker>> {
+        let stats = self.block_gen.stratum_worker_stats().unwrap_or_default();
+        Ok(stats.into_iter().map(StratumWorker::new).collect())
+    }
+
     /// Return (block_info.status, state_valid)
     /// Return Error if either field is missing
     pub fn get_block_status(&self, block_hash: H256) -> RpcResult<(u8, bool)> {
@@ -1462,6 +1616,57 @@ impl RpcImpl {
         })
     }
 
+    pub fn get_params_from_vote(
+        &self, epoch: Option<EpochNumber>,
+    ) -> RpcResult<VoteParamsInfo> {
+        let epoch: primitives::EpochNumber =
+            epoch.unwrap_or(EpochNumber::LatestState).into();
+        let state = self
+            .consensus
+            .get_state_by_epoch_number(epoch.clone(), "epoch")?;
+        let pivot_hash = self
+            .consensus
+            .get_hash_from_epoch_number(epoch)
+            .map_err(|e| invalid_params("epoch", e))?;
+        let height = self
+            .consensus
+            .get_block_epoch_number(&pivot_hash)
+            .ok_or_else(|| invalid_params("epoch", "Epoch not found"))?;
+        let block_number = self
+            .consensus
+            .get_block_number(&pivot_hash)
+            .map_err(|e| invalid_params("epoch", e))?
+            .ok_or_else(|| invalid_params("epoch", "Epoch not executed"))?;
+        let round = vote_round(block_number);
+
+        let pow_base_reward = match state.pow_base_reward() {
+            // No round has been applied, so the base reward is the default one.
+            reward if reward.is_zero() => U256::try_from(
+                self.tx_pool
+                    .machine()
+                    .params()
+                    .base_reward_in_ucfx(0, height),
+            )
+            .unwrap_or_else(|_| U256::max_value()),
+            reward => reward,
+        };
+        let interest_rate = state.interest_rate_per_block();
+        let votes = total_votes(&state, round)?;
+        Ok(VoteParamsInfo {
+            round: round.into(),
+            pow_base_reward,
+            interest_rate,
+            pending_pow_base_reward: next_value(
+                pow_base_reward,
+                &votes[POW_BASE_REWARD_INDEX as usize],
+            ),
+            pending_interest_rate: next_value(
+                interest_rate,
+                &votes[INTEREST_RATE_INDEX as usize],
+            ),
+        })
+    }
+
     pub fn set_db_crash(
         &self, crash_probability: f64, crash_exit_code: i32,
     ) -> RpcResult<()> {
@@ -1643,6 +1848,8 @@ impl Cfx for CfxHandler {
                 &self, account_addr: RpcAddress, contract_addr: RpcAddress, gas_limit: U256, gas_price: U256, storage_limit: U256, epoch: Option<EpochNumber>,
             ) -> BoxFuture<CheckBalanceAgainstTransactionResponse>;
             fn get_logs(&self, filter: CfxRpcLogFilter) -> BoxFuture<Vec<RpcLog>>;
+            fn get_logs_with_cursor(&self, filter: CfxRpcLogFilter, cursor: Option<LogCursor>)
+                -> BoxFuture<LogsPage<RpcLog>>;
             fn get_block_reward_info(&self, num: EpochNumber) -> JsonRpcResult<Vec<RpcRewardInfo>>;
             fn send_raw_transaction(&self, raw: Bytes) -> JsonRpcResult<H256>;
             fn storage_at(&self, addr: RpcAddress, pos: U256, epoch_number: Option<EpochNumber>)
@@ -1651,6 +1858,9 @@ impl Cfx for CfxHandler {
             fn transaction_receipt(&self, tx_hash: H256) -> BoxFuture<Option<RpcReceipt>>;
             fn storage_root(&self, address: RpcAddress, epoch_num: Option<EpochNumber>) -> BoxFuture<Option<StorageRoot>>;
             fn get_supply_info(&self, epoch_num: Option<EpochNumber>) -> JsonRpcResult<TokenSupplyInfo>;
+            fn get_params_from_vote(&self, epoch_num: Option<EpochNumber>) -> JsonRpcResult<VoteParamsInfo>;
+            fn transactions_by_address(&self, address: RpcAddress, cursor: Option<U64>, limit: Option<U64>)
+                -> JsonRpcResult<AddressTransactionsPage>;
             fn opened_method_groups(&self) -> JsonRpcResult<Vec<String>>;
         }
     }
@@ -1721,6 +1931,18 @@ impl TestRpc for TestRpcImpl {
             fn get_block_status(&self, block_hash: H256) -> JsonRpcResult<(u8, bool)>;
             fn send_usable_genesis_accounts(& self, account_start_index: usize) -> JsonRpcResult<Bytes>;
             fn set_db_crash(&self, crash_probability: f64, crash_exit_code: i32) -> JsonRpcResult<()>;
+            fn set_balance(&self, address: RpcAddress, balance: U256) -> JsonRpcResult<()>;
+            fn set_code(&self, address: RpcAddress, code: Bytes) -> JsonRpcResult<()>;
+            fn set_storage_at(&self, address: RpcAddress, position: U256, value: H256) -> JsonRpcResult<()>;
+        }
+
+        to self.rpc_impl.dev {
+            fn evm_snapshot(&self) -> JsonRpcResult<U64>;
+            fn evm_revert(&self, snapshot_id: U64) -> JsonRpcResult<bool>;
+            fn evm_increase_time(&self, seconds: u64) -> JsonRpcResult<u64>;
+            fn evm_set_next_block_timestamp(&self, timestamp: u64) -> JsonRpcResult<()>;
+            fn evm_mine(&self) -> JsonRpcResult<H256>;
+            fn evm_set_automine(&self, enabled: bool) -> JsonRpcResult<()>;
         }
     }
 }
@@ -1749,6 +1971,10 @@ impl LocalRpc for LocalRpcImpl {
             fn net_disconnect_node(&self, id: NodeId, op: Option<UpdateNodeOperation>)
                 -> JsonRpcResult<bool>;
             fn net_sessions(&self, node_id: Option<NodeId>) -> JsonRpcResult<Vec<SessionDetails>>;
+            fn net_reload_allowlist(&self) -> JsonRpcResult<AllowlistReloadResult>;
+            fn net_set_session_limits(&self, update: SessionLimitsUpdate) -> JsonRpcResult<SessionLimitsUpdateResult>;
+            fn log_set_level(&self, module: String, level: String) -> JsonRpcResult<LogLevelChange>;
+            fn rpc_reload_throttling(&self, file: Option<String>) -> JsonRpcResult<Vec<ThrottlingReloadResult>>;
             fn net_throttling(&self) -> JsonRpcResult<throttling::Service>;
             fn accounts(&self) -> JsonRpcResult<Vec<RpcAddress>>;
             fn new_account(&self, password: String) -> JsonRpcResult<RpcAddress>;
@@ -1764,8 +1990,11 @@ impl LocalRpc for LocalRpcImpl {
         to self.rpc_impl {
             fn current_sync_phase(&self) -> JsonRpcResult<String>;
             fn consensus_graph_state(&self) -> JsonRpcResult<ConsensusGraphStates>;
+            fn consensus_block_info(&self, block_hash: H256) -> JsonRpcResult<Option<ConsensusBlockInfo>>;
+            fn consensus_dag(&self, from_epoch: U64, to_epoch: U64, with_anticone: Option<bool>) -> JsonRpcResult<Vec<ConsensusBlockInfo>>;
             fn epoch_receipts(&self, epoch: BlockHashOrEpochNumber) -> JsonRpcResult<Option<Vec<Vec<RpcReceipt>>>>;
             fn sync_graph_state(&self) -> JsonRpcResult<SyncGraphStates>;
+            fn stratum_workers(&self) -> JsonRpcResult<Vec<StratumWorker>>;
             fn send_transaction(
                 &self, tx: SendTxRequest, password: Option<String>) -> BoxFuture<H256>;
             fn sign_transaction(&self, tx: SendTxRequest, password: Option<String>) -> JsonRpcResult<String>;
@@ -31,7 +31,14 @@ use keccak_hash::keccak;
 use num_bigint::{BigInt, ToBigInt};
 use parking_lot::{Condvar, Mutex};
 
-use crate::rpc::types::pos::{Block as RpcPosBlock, Decision};
+use crate::{
+    logging::{self, LogLevelChange},
+    rpc::{
+        reload_throttling,
+        types::pos::{Block as RpcPosBlock, Decision},
+        ThrottlingReloadResult,
+    },
+};
 use cfx_addr::Network;
 use cfx_parameters::staking::DRIPS_PER_STORAGE_COLLATERAL_UNIT;
 use cfx_types::{
@@ -53,7 +60,8 @@ use diem_types::{
 use network::{
     node_table::{Node, NodeEndpoint, NodeEntry, NodeId},
     throttling::{self, THROTTLING_SERVICE},
-    NetworkService, SessionDetails, UpdateNodeOperation,
+    AllowlistReloadResult, NetworkService, SessionDetails, SessionLimitsUpdate,
+    SessionLimitsUpdateResult, UpdateNodeOperation,
 };
 use primitives::{Account, Action, Block, SignedTransaction, Transaction};
 use storage_interface::DBReaderForPoW;
@@ -937,6 +945,48 @@ impl RpcImpl {
         }
     }
 
+    pub fn net_reload_allowlist(&self) -> JsonRpcResult<AllowlistReloadResult> {
+        info!("RPC Request: net_reload_allowlist");
+        self.network.reload_allowlist().map_err(|e| {
+            let mut rpc_error = RpcError::internal_error();
+            rpc_error.data = Some(RpcValue::String(format!("{}", e)));
+            rpc_error
+        })
+    }
+
+    pub fn net_set_session_limits(
+        &self, update: SessionLimitsUpdate,
+    ) -> JsonRpcResult<SessionLimitsUpdateResult> {
+        info!("RPC Request: net_set_session_limits({:?})", update);
+        self.network.set_session_limits(update).map_err(|e| {
+            let mut rpc_error = RpcError::internal_error();
+            rpc_error.data = Some(RpcValue::String(format!("{}", e)));
+            rpc_error
+        })
+    }
+
+    pub fn log_set_level(
+        &self, module: String, level: String,
+    ) -> JsonRpcResult<LogLevelChange> {
+        info!("RPC Request: log_set_level({}, {})", module, level);
+        logging::set_log_level(module, &level).map_err(|e| {
+            let mut rpc_error = RpcError::invalid_params("invalid log level");
+            rpc_error.data = Some(RpcValue::String(e));
+            rpc_error
+        })
+    }
+
+    pub fn rpc_reload_throttling(
+        &self, file: Option<String>,
+    ) -> JsonRpcResult<Vec<ThrottlingReloadResult>> {
+        info!("RPC Request: rpc_reload_throttling({:?})", file);
+        reload_throttling(file).map_err(|e| {
+            let mut rpc_error = RpcError::internal_error();
+            rpc_error.data = Some(RpcValue::String(e));
+            rpc_error
+        })
+    }
+
     pub fn net_throttling(&self) -> JsonRpcResult<throttling::Service> {
         Ok(THROTTLING_SERVICE.read().clone())
     }
@@ -978,6 +1028,13 @@ impl RpcImpl {
             ret.local_nonce = local_nonce;
             ret.state_balance = state_balance;
             ret.state_nonce = state_nonce;
+            ret.local_sender = self.tx_pool.is_local_sender(&tx.sender());
+            ret.sender_pending_count =
+                self.tx_pool.get_sender_pending_count(&tx.sender()).into();
+            ret.replacement_min_gas_price = self
+                .tx_pool
+                .get_replacement_min_gas_price(&tx.sender(), tx.nonce())
+                .unwrap_or_default();
         }
         Ok(ret)
     }
@@ -0,0 +1,129 @@
+// Copyright 2022 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The dev mode RPCs shared by the core space and the eSpace, which only
+//! differ in the address format of the state overrides.
+
+use crate::rpc::{
+    error_codes::{dev_mode_required, internal_error, invalid_params},
+    RpcResult,
+};
+use blockgen::BlockGenerator;
+use cfx_types::{H256, U64};
+use cfxcore::{
+    block_parameters::MAX_TRANSACTION_COUNT_PER_BLOCK, state::StateOverride,
+    ConsensusGraph, SharedConsensusGraph,
+};
+use std::sync::Arc;
+
+pub struct DevRpcImpl {
+    dev_mode: bool,
+    consensus: SharedConsensusGraph,
+    block_gen: Arc<BlockGenerator>,
+}
+
+impl DevRpcImpl {
+    pub fn new(
+        dev_mode: bool, consensus: SharedConsensusGraph,
+        block_gen: Arc<BlockGenerator>,
+    ) -> Self
+    {
+        DevRpcImpl {
+            dev_mode,
+            consensus,
+            block_gen,
+        }
+    }
+
+    fn consensus_graph(&self) -> &ConsensusGraph {
+        self.consensus
+            .as_any()
+            .downcast_ref::<ConsensusGraph>()
+            .expect("downcast should succeed")
+    }
+
+    fn check_dev_mode(&self) -> RpcResult<()> {
+        if self.dev_mode {
+            Ok(())
+        } else {
+            bail!(dev_mode_required())
+        }
+    }
+
+    /// Packs and executes the pending transactions if automine is enabled.
+    /// It is called after a transaction is received from RPC, and the error
+    /// is returned to the caller.
+    pub fn automine(&self) -> RpcResult<()> {
+        if self.block_gen.automine() {
+            self.block_gen
+                .mine_pending_transactions()
+                .map_err(internal_error)?;
+        }
+        Ok(())
+    }
+
+    pub fn evm_snapshot(&self) -> RpcResult<U64> {
+        info!("RPC Request: evm_snapshot()");
+        self.check_dev_mode()?;
+        Ok(U64::from(self.block_gen.snapshot()))
+    }
+
+    pub fn evm_revert(&self, snapshot_id: U64) -> RpcResult<bool> {
+        info!("RPC Request: evm_revert({:?})", snapshot_id);
+        self.check_dev_mode()?;
+        match self.block_gen.revert_to_snapshot(snapshot_id.as_usize()) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                warn!("evm_revert failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+
+    pub fn evm_increase_time(&self, seconds: u64) -> RpcResult<u64> {
+        info!("RPC Request: evm_increaseTime({})", seconds);
+        self.check_dev_mode()?;
+        Ok(self.block_gen.increase_time(seconds))
+    }
+
+    pub fn evm_set_next_block_timestamp(
+        &self, timestamp: u64,
+    ) -> RpcResult<()> {
+        info!("RPC Request: evm_setNextBlockTimestamp({})", timestamp);
+        self.check_dev_mode()?;
+        self.block_gen
+            .set_next_block_timestamp(timestamp)
+            .map_err(|e| invalid_params("timestamp", e))?;
+        Ok(())
+    }
+
+    pub fn evm_mine(&self) -> RpcResult<H256> {
+        info!("RPC Request: evm_mine()");
+        self.check_dev_mode()?;
+        Ok(self
+            .block_gen
+            .generate_and_execute_block(MAX_TRANSACTION_COUNT_PER_BLOCK)
+            .map_err(internal_error)?)
+    }
+
+    pub fn evm_set_automine(&self, enabled: bool) -> RpcResult<()> {
+        info!("RPC Request: evm_setAutomine({})", enabled);
+        self.check_dev_mode()?;
+        self.block_gen.set_automine(enabled);
+        Ok(())
+    }
+
+    /// Applies a state override and generates blocks to make it visible in
+    /// the latest state. The pending transactions are not packed.
+    pub fn override_state(
+        &self, state_override: StateOverride,
+    ) -> RpcResult<()> {
+        self.check_dev_mode()?;
+        self.consensus_graph().add_state_override(state_override);
+        self.block_gen
+            .generate_and_execute_block(0 /* num_txs */)
+            .map_err(internal_error)?;
+        Ok(())
+    }
+}
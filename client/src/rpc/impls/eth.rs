@@ -7,16 +7,23 @@ use crate::rpc::{
         call_execution_error, internal_error, invalid_params,
         request_rejected_in_catch_up_mode, unimplemented, unknown_block,
     },
-    impls::RpcImplConfiguration,
-    traits::eth_space::eth::{Eth, EthFilter},
+    impls::{dev::DevRpcImpl, RpcImplConfiguration},
+    traits::eth_space::{
+        dev::EthDev,
+        eth::{Eth, EthFilter},
+        txpool::TxPool,
+    },
     types::{
         eth::{
             Block as RpcBlock, BlockNumber, CallRequest, EthRpcLogFilter,
             FilterChanges, Log, Receipt, SyncInfo, SyncStatus, Transaction,
+            TxpoolContent, TxpoolContentFrom, TxpoolNonce, TxpoolStatus,
         },
-        Bytes, Index, MAX_GAS_CALL_REQUEST,
+        AddressTransactionsPage, Bytes, Index, LogCursor, LogsPage,
+        DEFAULT_LOGS_PAGE_SIZE, MAX_GAS_CALL_REQUEST,
     },
 };
+use blockgen::BlockGenerator;
 use cfx_statedb::StateDbExt;
 use cfx_types::{
     Address, AddressSpaceUtil, BigEndianHash, Space, H160, H256, U256, U64,
@@ -30,6 +37,7 @@ use cfxcore::{
     rpc_errors::{
         invalid_params_check, Error as CfxRpcError, Result as CfxRpcResult,
     },
+    state::StateOverride,
     vm, ConsensusGraph, SharedConsensusGraph, SharedSynchronizationService,
     SharedTransactionPool,
 };
@@ -41,26 +49,31 @@ use primitives::{
     StorageKey, StorageValue, TransactionOutcome, TransactionWithSignature,
 };
 use rlp::Rlp;
-use std::{cmp::min, convert::TryInto};
+use std::{cmp::min, collections::BTreeMap, convert::TryInto, sync::Arc};
 
 pub struct EthHandler {
     config: RpcImplConfiguration,
     consensus: SharedConsensusGraph,
     sync: SharedSynchronizationService,
     tx_pool: SharedTransactionPool,
+    dev: DevRpcImpl,
 }
 
 impl EthHandler {
     pub fn new(
         config: RpcImplConfiguration, consensus: SharedConsensusGraph,
         sync: SharedSynchronizationService, tx_pool: SharedTransactionPool,
+        block_gen: Arc<BlockGenerator>,
     ) -> Self
     {
+        let dev =
+            DevRpcImpl::new(config.dev_mode, consensus.clone(), block_gen);
         EthHandler {
             config,
             consensus,
             sync,
             tx_pool,
+            dev,
         }
     }
 
@@ -70,6 +83,10 @@ impl EthHandler {
             .downcast_ref::<ConsensusGraph>()
             .expect("downcast should succeed")
     }
+
+    pub(crate) fn get_logs_filter_max_limit(&self) -> Option<usize> {
+        self.config.get_logs_filter_max_limit
+    }
 }
 
 pub fn sign_call(
@@ -121,7 +138,7 @@ fn block_tx_by_index(
 }
 
 impl EthHandler {
-    fn exec_transaction(
+    pub(crate) fn exec_transaction(
         &self, request: CallRequest, epoch: Option<BlockNumber>,
     ) -> CfxRpcResult<ExecutionOutcome> {
         let consensus_graph = self.consensus_graph();
@@ -636,6 +653,8 @@ impl Eth for EthHandler {
         }
 
         let r = self.send_transaction_with_signature(tx)?;
+        // Try to pack and execute this new tx.
+        self.dev.automine()?;
         Ok(r)
     }
 
@@ -785,6 +804,22 @@ impl Eth for EthHandler {
         Ok(estimated_gas_limit)
     }
 
+    fn transactions_by_address(
+        &self, address: H160, cursor: Option<U64>, limit: Option<U64>,
+    ) -> jsonrpc_core::Result<AddressTransactionsPage> {
+        info!(
+            "RPC Request: eth_getTransactionsByAddress({:?}, {:?}, {:?})",
+            address, cursor, limit
+        );
+
+        AddressTransactionsPage::query(
+            self.consensus.get_data_manager(),
+            address.with_evm_space(),
+            cursor,
+            limit,
+        )
+    }
+
     fn transaction_by_hash(
         &self, hash: H256,
     ) -> jsonrpc_core::Result<Option<Transaction>> {
@@ -978,6 +1013,39 @@ impl Eth for EthHandler {
             .collect::<Result<_, _>>()?)
     }
 
+    fn logs_with_cursor(
+        &self, filter: EthRpcLogFilter, cursor: Option<LogCursor>,
+    ) -> jsonrpc_core::Result<LogsPage<Log>> {
+        info!(
+            "RPC Request: eth_getLogsWithCursor({:?}, {:?})",
+            filter, cursor
+        );
+
+        let filter: LogFilter =
+            filter.into_primitive(self.consensus.clone())?;
+
+        let mut limit = filter.limit.unwrap_or(DEFAULT_LOGS_PAGE_SIZE);
+        if let Some(max_limit) = self.config.get_logs_filter_max_limit {
+            limit = min(limit, max_limit);
+        }
+        if limit == 0 {
+            bail!(invalid_params("filter.limit", "limit should be positive"));
+        }
+
+        let (logs, next_cursor) = self
+            .consensus_graph()
+            .logs_page(filter, cursor.map(Into::into), limit)
+            .map_err(|err| CfxRpcError::from(err))?;
+
+        Ok(LogsPage {
+            logs: logs
+                .into_iter()
+                .map(|l| Log::try_from_localized(l, self.consensus.clone()))
+                .collect::<Result<_, _>>()?,
+            next_cursor: next_cursor.map(Into::into),
+        })
+    }
+
     fn submit_hashrate(&self, _: U256, _: H256) -> jsonrpc_core::Result<bool> {
         info!("RPC Request: eth_submitHashrate");
         // We do not care mining
@@ -1079,3 +1147,148 @@ impl EthFilter for EthHandler {
         )));
     }
 }
+
+/// Group transactions by sender and nonce.
+fn grouped_by_sender<T, F>(
+    txs: Vec<Arc<SignedTransaction>>, converter: F,
+) -> BTreeMap<H160, BTreeMap<TxpoolNonce, T>>
+where F: Fn(&SignedTransaction) -> T {
+    let mut grouped: BTreeMap<H160, BTreeMap<TxpoolNonce, T>> =
+        BTreeMap::new();
+    for tx in txs {
+        grouped
+            .entry(tx.sender().address)
+            .or_default()
+            .insert(TxpoolNonce(*tx.nonce()), converter(&tx));
+    }
+    grouped
+}
+
+fn txpool_inspect_summary(tx: &SignedTransaction) -> String {
+    let to = match tx.action() {
+        Action::Create => "contract creation".into(),
+        Action::Call(addr) => format!("{:?}", addr),
+    };
+    format!(
+        "{}: {} wei + {} gas \u{00d7} {} wei",
+        to,
+        tx.value(),
+        tx.gas(),
+        tx.gas_price()
+    )
+}
+
+fn txpool_transaction(tx: &SignedTransaction) -> Transaction {
+    Transaction::from_signed(tx, (None, None, None), (None, None))
+}
+
+impl TxPool for EthHandler {
+    fn txpool_status(&self) -> jsonrpc_core::Result<TxpoolStatus> {
+        info!("RPC Request: txpool_status");
+        let (pending, queued) = self
+            .tx_pool
+            .pending_and_queued_content(Space::Ethereum, None);
+        Ok(TxpoolStatus {
+            pending: pending.len().into(),
+            queued: queued.len().into(),
+        })
+    }
+
+    fn txpool_inspect(&self) -> jsonrpc_core::Result<TxpoolContent<String>> {
+        info!("RPC Request: txpool_inspect");
+        let (pending, queued) = self
+            .tx_pool
+            .pending_and_queued_content(Space::Ethereum, None);
+        Ok(TxpoolContent {
+            pending: grouped_by_sender(pending, txpool_inspect_summary),
+            queued: grouped_by_sender(queued, txpool_inspect_summary),
+        })
+    }
+
+    fn txpool_content(
+        &self,
+    ) -> jsonrpc_core::Result<TxpoolContent<Transaction>> {
+        info!("RPC Request: txpool_content");
+        let (pending, queued) = self
+            .tx_pool
+            .pending_and_queued_content(Space::Ethereum, None);
+        Ok(TxpoolContent {
+            pending: grouped_by_sender(pending, txpool_transaction),
+            queued: grouped_by_sender(queued, txpool_transaction),
+        })
+    }
+
+    fn txpool_content_from(
+        &self, address: H160,
+    ) -> jsonrpc_core::Result<TxpoolContentFrom<Transaction>> {
+        info!("RPC Request: txpool_contentFrom address={:?}", address);
+        let (pending, queued) = self
+            .tx_pool
+            .pending_and_queued_content(Space::Ethereum, Some(address));
+        Ok(TxpoolContentFrom {
+            pending: grouped_by_sender(pending, txpool_transaction)
+                .remove(&address)
+                .unwrap_or_default(),
+            queued: grouped_by_sender(queued, txpool_transaction)
+                .remove(&address)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl EthDev for EthHandler {
+    fn evm_snapshot(&self) -> RpcResult<U64> {
+        Ok(self.dev.evm_snapshot()?)
+    }
+
+    fn evm_revert(&self, snapshot_id: U64) -> RpcResult<bool> {
+        Ok(self.dev.evm_revert(snapshot_id)?)
+    }
+
+    fn evm_increase_time(&self, seconds: u64) -> RpcResult<u64> {
+        Ok(self.dev.evm_increase_time(seconds)?)
+    }
+
+    fn evm_set_next_block_timestamp(&self, timestamp: u64) -> RpcResult<()> {
+        Ok(self.dev.evm_set_next_block_timestamp(timestamp)?)
+    }
+
+    fn evm_mine(&self) -> RpcResult<H256> { Ok(self.dev.evm_mine()?) }
+
+    fn evm_set_automine(&self, enabled: bool) -> RpcResult<()> {
+        Ok(self.dev.evm_set_automine(enabled)?)
+    }
+
+    fn set_balance(&self, address: H160, balance: U256) -> RpcResult<()> {
+        info!(
+            "RPC Request: hardhat_setBalance address={:?} balance={:?}",
+            address, balance
+        );
+        Ok(self.dev.override_state(StateOverride::Balance {
+            address: address.with_evm_space(),
+            balance,
+        })?)
+    }
+
+    fn set_code(&self, address: H160, code: Bytes) -> RpcResult<()> {
+        info!("RPC Request: hardhat_setCode address={:?}", address);
+        Ok(self.dev.override_state(StateOverride::Code {
+            address: address.with_evm_space(),
+            code: code.into_vec(),
+        })?)
+    }
+
+    fn set_storage_at(
+        &self, address: H160, position: U256, value: H256,
+    ) -> RpcResult<()> {
+        info!(
+            "RPC Request: hardhat_setStorageAt address={:?} position={:?} value={:?}",
+            address, position, value
+        );
+        Ok(self.dev.override_state(StateOverride::Storage {
+            address: address.with_evm_space(),
+            key: position,
+            value: value.into_uint(),
+        })?)
+    }
+}
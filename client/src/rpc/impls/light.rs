@@ -22,7 +22,8 @@ use futures::future::{self, FutureExt, TryFutureExt};
 use jsonrpc_core::{BoxFuture, Error as RpcError, Result as JsonRpcResult};
 use network::{
     node_table::{Node, NodeId},
-    throttling, SessionDetails, UpdateNodeOperation,
+    throttling, AllowlistReloadResult, SessionDetails, SessionLimitsUpdate,
+    SessionLimitsUpdateResult, UpdateNodeOperation,
 };
 use primitives::{
     Account, DepositInfo, StorageRoot, TransactionWithSignature, VoteStakeInfo,
@@ -32,6 +33,7 @@ use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
 // To convert from RpcResult to BoxFuture by delegate! macro automatically.
 use crate::{
     common::delegate_convert,
+    logging::LogLevelChange,
     rpc::{
         error_codes,
         impls::{
@@ -42,15 +44,17 @@ use crate::{
         types::{
             pos::{Block as PosBlock, PoSEpochReward},
             Account as RpcAccount, AccountPendingInfo,
-            AccountPendingTransactions, BlameInfo, Block as RpcBlock,
-            BlockHashOrEpochNumber, Bytes, CallRequest, CfxRpcLogFilter,
-            CheckBalanceAgainstTransactionResponse, ConsensusGraphStates,
-            EpochNumber, EstimateGasAndCollateralResponse, Log as RpcLog,
-            PoSEconomics, Receipt as RpcReceipt, RewardInfo as RpcRewardInfo,
-            RpcAddress, SendTxRequest, SponsorInfo, Status as RpcStatus,
-            SyncGraphStates, TokenSupplyInfo, Transaction as RpcTransaction,
+            AccountPendingTransactions, AddressTransactionsPage, BlameInfo,
+            Block as RpcBlock, BlockHashOrEpochNumber, Bytes, CallRequest,
+            CfxRpcLogFilter, CheckBalanceAgainstTransactionResponse,
+            ConsensusBlockInfo, ConsensusGraphStates, EpochNumber,
+            EstimateGasAndCollateralResponse, Log as RpcLog, LogCursor,
+            LogsPage, PoSEconomics, Receipt as RpcReceipt,
+            RewardInfo as RpcRewardInfo, RpcAddress, SendTxRequest,
+            SponsorInfo, Status as RpcStatus, StratumWorker, SyncGraphStates,
+            TokenSupplyInfo, Transaction as RpcTransaction, VoteParamsInfo,
         },
-        RpcBoxFuture, RpcResult,
+        RpcBoxFuture, RpcResult, ThrottlingReloadResult,
     },
 };
 use cfx_addr::Network;
@@ -1116,8 +1120,11 @@ impl Cfx for CfxHandler {
         fn estimate_gas_and_collateral(&self, request: CallRequest, epoch_num: Option<EpochNumber>) -> JsonRpcResult<EstimateGasAndCollateralResponse>;
         fn get_block_reward_info(&self, num: EpochNumber) -> JsonRpcResult<Vec<RpcRewardInfo>>;
         fn get_supply_info(&self, epoch_num: Option<EpochNumber>) -> JsonRpcResult<TokenSupplyInfo>;
+        fn get_params_from_vote(&self, epoch_num: Option<EpochNumber>) -> JsonRpcResult<VoteParamsInfo>;
         fn opened_method_groups(&self) -> JsonRpcResult<Vec<String>>;
         fn get_pos_reward_by_epoch(&self, epoch: EpochNumber) -> JsonRpcResult<Option<PoSEpochReward>>;
+        fn transactions_by_address(&self, address: RpcAddress, cursor: Option<U64>, limit: Option<U64>) -> JsonRpcResult<AddressTransactionsPage>;
+        fn get_logs_with_cursor(&self, filter: CfxRpcLogFilter, cursor: Option<LogCursor>) -> BoxFuture<LogsPage<RpcLog>>;
     }
 }
 
@@ -1177,6 +1184,15 @@ impl TestRpc for TestRpcImpl {
         fn get_pivot_chain_and_weight(&self, height_range: Option<(u64, u64)>) -> JsonRpcResult<Vec<(H256, U256)>>;
         fn send_usable_genesis_accounts(&self, account_start_index: usize) -> JsonRpcResult<Bytes>;
         fn set_db_crash(&self, crash_probability: f64, crash_exit_code: i32) -> JsonRpcResult<()>;
+        fn evm_snapshot(&self) -> JsonRpcResult<U64>;
+        fn evm_revert(&self, snapshot_id: U64) -> JsonRpcResult<bool>;
+        fn evm_increase_time(&self, seconds: u64) -> JsonRpcResult<u64>;
+        fn evm_set_next_block_timestamp(&self, timestamp: u64) -> JsonRpcResult<()>;
+        fn evm_mine(&self) -> JsonRpcResult<H256>;
+        fn evm_set_automine(&self, enabled: bool) -> JsonRpcResult<()>;
+        fn set_balance(&self, address: RpcAddress, balance: U256) -> JsonRpcResult<()>;
+        fn set_code(&self, address: RpcAddress, code: Bytes) -> JsonRpcResult<()>;
+        fn set_storage_at(&self, address: RpcAddress, position: U256, value: H256) -> JsonRpcResult<()>;
     }
 }
 
@@ -1205,6 +1221,10 @@ impl LocalRpc for DebugRpcImpl {
             fn net_disconnect_node(&self, id: NodeId, op: Option<UpdateNodeOperation>) -> JsonRpcResult<bool>;
             fn net_node(&self, id: NodeId) -> JsonRpcResult<Option<(String, Node)>>;
             fn net_sessions(&self, node_id: Option<NodeId>) -> JsonRpcResult<Vec<SessionDetails>>;
+            fn net_reload_allowlist(&self) -> JsonRpcResult<AllowlistReloadResult>;
+            fn net_set_session_limits(&self, update: SessionLimitsUpdate) -> JsonRpcResult<SessionLimitsUpdateResult>;
+            fn log_set_level(&self, module: String, level: String) -> JsonRpcResult<LogLevelChange>;
+            fn rpc_reload_throttling(&self, file: Option<String>) -> JsonRpcResult<Vec<ThrottlingReloadResult>>;
             fn net_throttling(&self) -> JsonRpcResult<throttling::Service>;
             fn new_account(&self, password: String) -> JsonRpcResult<RpcAddress>;
             fn sign(&self, data: Bytes, address: RpcAddress, password: Option<String>) -> JsonRpcResult<H520>;
@@ -1218,9 +1238,12 @@ impl LocalRpc for DebugRpcImpl {
 
     not_supported! {
         fn consensus_graph_state(&self) -> JsonRpcResult<ConsensusGraphStates>;
+        fn consensus_block_info(&self, block_hash: H256) -> JsonRpcResult<Option<ConsensusBlockInfo>>;
+        fn consensus_dag(&self, from_epoch: U64, to_epoch: U64, with_anticone: Option<bool>) -> JsonRpcResult<Vec<ConsensusBlockInfo>>;
         fn current_sync_phase(&self) -> JsonRpcResult<String>;
         fn epoch_receipts(&self, epoch: BlockHashOrEpochNumber) -> JsonRpcResult<Option<Vec<Vec<RpcReceipt>>>>;
         fn sign_transaction(&self, tx: SendTxRequest, password: Option<String>) -> JsonRpcResult<String>;
         fn sync_graph_state(&self) -> JsonRpcResult<SyncGraphStates>;
+        fn stratum_workers(&self) -> JsonRpcResult<Vec<StratumWorker>>;
     }
 }
@@ -82,7 +82,7 @@ impl ApiSet {
                 .iter()
                 .cloned()
                 .collect(),
-            ApiSet::Evm => [Api::Eth].iter().cloned().collect(),
+            ApiSet::Evm => [Api::Eth, Api::TxPool].iter().cloned().collect(),
         }
     }
 }
@@ -4,11 +4,13 @@
 
 use crate::rpc::types::{
     pos::PoSEpochReward, Account as RpcAccount, AccountPendingInfo,
-    AccountPendingTransactions, Block, BlockHashOrEpochNumber, Bytes,
-    CallRequest, CfxRpcLogFilter, CheckBalanceAgainstTransactionResponse,
-    EpochNumber, EstimateGasAndCollateralResponse, Log as RpcLog, PoSEconomics,
-    Receipt as RpcReceipt, RewardInfo as RpcRewardInfo, RpcAddress,
-    SponsorInfo, Status as RpcStatus, TokenSupplyInfo, Transaction,
+    AccountPendingTransactions, AddressTransactionsPage, Block,
+    BlockHashOrEpochNumber, Bytes, CallRequest, CfxRpcLogFilter,
+    CheckBalanceAgainstTransactionResponse, EpochNumber,
+    EstimateGasAndCollateralResponse, Log as RpcLog, LogCursor, LogsPage,
+    PoSEconomics, Receipt as RpcReceipt, RewardInfo as RpcRewardInfo,
+    RpcAddress, SponsorInfo, Status as RpcStatus, TokenSupplyInfo, Transaction,
+    VoteParamsInfo,
 };
 use cfx_types::{H256, U256, U64};
 use jsonrpc_core::{BoxFuture, Result as JsonRpcResult};
@@ -166,6 +168,14 @@ pub trait Cfx {
     #[rpc(name = "cfx_getLogs")]
     fn get_logs(&self, filter: CfxRpcLogFilter) -> BoxFuture<Vec<RpcLog>>;
 
+    /// Returns a page of the logs matching the filter provided, and the cursor
+    /// to get the next page with the same filter. Only epoch range filters
+    /// are supported, and `limit` is the page size.
+    #[rpc(name = "cfx_getLogsWithCursor")]
+    fn get_logs_with_cursor(
+        &self, filter: CfxRpcLogFilter, cursor: Option<LogCursor>,
+    ) -> BoxFuture<LogsPage<RpcLog>>;
+
     /// Get transaction by its hash.
     #[rpc(name = "cfx_getTransactionByHash")]
     fn transaction_by_hash(
@@ -185,6 +195,13 @@ pub trait Cfx {
         maybe_limit: Option<U64>,
     ) -> BoxFuture<AccountPendingTransactions>;
 
+    /// Get the executed transactions that involve the given address, from the
+    /// newest to the oldest. Requires `persist_address_tx_index`.
+    #[rpc(name = "cfx_getTransactionsByAddress")]
+    fn transactions_by_address(
+        &self, address: RpcAddress, cursor: Option<U64>, limit: Option<U64>,
+    ) -> JsonRpcResult<AddressTransactionsPage>;
+
     /// Return estimated gas and collateral usage.
     #[rpc(name = "cfx_estimateGasAndCollateral")]
     fn estimate_gas_and_collateral(
@@ -270,6 +287,13 @@ pub trait Cfx {
         &self, epoch: EpochNumber,
     ) -> JsonRpcResult<Option<PoSEpochReward>>;
 
+    /// Return the parameters decided by the votes of stakers, and their
+    /// values once the votes of the current round are applied.
+    #[rpc(name = "cfx_getParamsFromVote")]
+    fn get_params_from_vote(
+        &self, epoch_number: Option<EpochNumber>,
+    ) -> JsonRpcResult<VoteParamsInfo>;
+
     //        /// Returns transaction at given block hash and index.
     //        #[rpc(name = "cfx_getTransactionByBlockHashAndIndex")]
     //        fn transaction_by_block_hash_and_index(&self, H256, Index) ->
@@ -2,17 +2,25 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::rpc::types::{
-    BlockHashOrEpochNumber, Bytes as RpcBytes, ConsensusGraphStates,
-    Receipt as RpcReceipt, RpcAddress, SendTxRequest, SyncGraphStates,
-    Transaction as RpcTransaction,
+use crate::{
+    logging::LogLevelChange,
+    rpc::{
+        types::{
+            BlockHashOrEpochNumber, Bytes as RpcBytes, ConsensusBlockInfo,
+            ConsensusGraphStates, Receipt as RpcReceipt, RpcAddress,
+            SendTxRequest, StratumWorker, SyncGraphStates,
+            Transaction as RpcTransaction,
+        },
+        ThrottlingReloadResult,
+    },
 };
-use cfx_types::{H256, H520, U128};
+use cfx_types::{H256, H520, U128, U64};
 use jsonrpc_core::{BoxFuture, Result as JsonRpcResult};
 use jsonrpc_derive::rpc;
 use network::{
     node_table::{Node, NodeId},
-    throttling, SessionDetails, UpdateNodeOperation,
+    throttling, AllowlistReloadResult, SessionDetails, SessionLimitsUpdate,
+    SessionLimitsUpdateResult, UpdateNodeOperation,
 };
 use std::collections::BTreeMap;
 
@@ -63,15 +71,54 @@ pub trait LocalRpc {
         &self, node_id: Option<NodeId>,
     ) -> JsonRpcResult<Vec<SessionDetails>>;
 
+    #[rpc(name = "net_reload_allowlist")]
+    fn net_reload_allowlist(&self) -> JsonRpcResult<AllowlistReloadResult>;
+
+    #[rpc(name = "net_set_session_limits")]
+    fn net_set_session_limits(
+        &self, update: SessionLimitsUpdate,
+    ) -> JsonRpcResult<SessionLimitsUpdateResult>;
+
+    /// Set the log level of a module (e.g. `cfxcore::sync`) or `root`.
+    #[rpc(name = "log_set_level")]
+    fn log_set_level(
+        &self, module: String, level: String,
+    ) -> JsonRpcResult<LogLevelChange>;
+
+    /// Reload the RPC throttling buckets from the given file, or from the
+    /// configured `throttling_conf` if not specified.
+    #[rpc(name = "rpc_reload_throttling")]
+    fn rpc_reload_throttling(
+        &self, file: Option<String>,
+    ) -> JsonRpcResult<Vec<ThrottlingReloadResult>>;
+
     #[rpc(name = "current_sync_phase")]
     fn current_sync_phase(&self) -> JsonRpcResult<String>;
 
     #[rpc(name = "consensus_graph_state")]
     fn consensus_graph_state(&self) -> JsonRpcResult<ConsensusGraphStates>;
 
+    /// Return the consensus information of a block in the consensus graph.
+    #[rpc(name = "consensus_block_info")]
+    fn consensus_block_info(
+        &self, block_hash: H256,
+    ) -> JsonRpcResult<Option<ConsensusBlockInfo>>;
+
+    /// Return the consensus information of the blocks in the epochs
+    /// `[from_epoch, to_epoch]` of the current era. The anticone sizes are
+    /// only computed if `with_anticone` is true, in which case fewer epochs
+    /// can be queried.
+    #[rpc(name = "consensus_dag")]
+    fn consensus_dag(
+        &self, from_epoch: U64, to_epoch: U64, with_anticone: Option<bool>,
+    ) -> JsonRpcResult<Vec<ConsensusBlockInfo>>;
+
     #[rpc(name = "sync_graph_state")]
     fn sync_graph_state(&self) -> JsonRpcResult<SyncGraphStates>;
 
+    #[rpc(name = "stratum_workers")]
+    fn stratum_workers(&self) -> JsonRpcResult<Vec<StratumWorker>>;
+
     #[rpc(name = "cfx_sendTransaction")]
     fn send_transaction(
         &self, tx: SendTxRequest, password: Option<String>,
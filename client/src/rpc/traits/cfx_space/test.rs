@@ -2,7 +2,9 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::rpc::types::{pos::Block as PosBlock, BlameInfo, Block, Bytes};
+use crate::rpc::types::{
+    pos::Block as PosBlock, BlameInfo, Block, Bytes, RpcAddress,
+};
 use cfx_types::{H256, U256, U64};
 use cfxcore::PeerInfo;
 use diem_types::{
@@ -149,4 +151,41 @@ pub trait TestRpc {
 
     #[rpc(name = "pos_get_chosen_proposal")]
     fn pos_get_chosen_proposal(&self) -> RpcResult<Option<PosBlock>>;
+
+    /// Saves the current best block, and returns the snapshot id. Only
+    /// available in dev mode, like the following methods.
+    #[rpc(name = "evm_snapshot")]
+    fn evm_snapshot(&self) -> RpcResult<U64>;
+
+    /// Rolls the chain back to a snapshot. The snapshot and the snapshots
+    /// taken after it can not be used again.
+    #[rpc(name = "evm_revert")]
+    fn evm_revert(&self, snapshot_id: U64) -> RpcResult<bool>;
+
+    /// Moves the timestamp of new blocks forward, and returns the total
+    /// offset in seconds.
+    #[rpc(name = "evm_increaseTime")]
+    fn evm_increase_time(&self, seconds: u64) -> RpcResult<u64>;
+
+    #[rpc(name = "evm_setNextBlockTimestamp")]
+    fn evm_set_next_block_timestamp(&self, timestamp: u64) -> RpcResult<()>;
+
+    /// Packs the pending transactions and executes them.
+    #[rpc(name = "evm_mine")]
+    fn evm_mine(&self) -> RpcResult<H256>;
+
+    /// Enables or disables sealing new transactions immediately.
+    #[rpc(name = "evm_setAutomine")]
+    fn evm_set_automine(&self, enabled: bool) -> RpcResult<()>;
+
+    #[rpc(name = "test_setBalance")]
+    fn set_balance(&self, address: RpcAddress, balance: U256) -> RpcResult<()>;
+
+    #[rpc(name = "test_setCode")]
+    fn set_code(&self, address: RpcAddress, code: Bytes) -> RpcResult<()>;
+
+    #[rpc(name = "test_setStorageAt")]
+    fn set_storage_at(
+        &self, address: RpcAddress, position: U256, value: H256,
+    ) -> RpcResult<()>;
 }
@@ -0,0 +1,50 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::types::Bytes;
+use cfx_types::{H160, H256, U256, U64};
+use jsonrpc_core::Result as JsonRpcResult;
+use jsonrpc_derive::rpc;
+
+/// Hardhat compatible rpc interface of eSpace for contract tests. It is only
+/// available in dev mode.
+#[rpc(server)]
+pub trait EthDev {
+    /// Saves the current best block, and returns the snapshot id.
+    #[rpc(name = "evm_snapshot")]
+    fn evm_snapshot(&self) -> JsonRpcResult<U64>;
+
+    /// Rolls the chain back to a snapshot. The snapshot and the snapshots
+    /// taken after it can not be used again.
+    #[rpc(name = "evm_revert")]
+    fn evm_revert(&self, snapshot_id: U64) -> JsonRpcResult<bool>;
+
+    /// Moves the timestamp of new blocks forward, and returns the total
+    /// offset in seconds.
+    #[rpc(name = "evm_increaseTime")]
+    fn evm_increase_time(&self, seconds: u64) -> JsonRpcResult<u64>;
+
+    #[rpc(name = "evm_setNextBlockTimestamp")]
+    fn evm_set_next_block_timestamp(&self, timestamp: u64)
+        -> JsonRpcResult<()>;
+
+    /// Packs the pending transactions and executes them.
+    #[rpc(name = "evm_mine")]
+    fn evm_mine(&self) -> JsonRpcResult<H256>;
+
+    /// Enables or disables sealing new transactions immediately.
+    #[rpc(name = "evm_setAutomine")]
+    fn evm_set_automine(&self, enabled: bool) -> JsonRpcResult<()>;
+
+    #[rpc(name = "hardhat_setBalance")]
+    fn set_balance(&self, address: H160, balance: U256) -> JsonRpcResult<()>;
+
+    #[rpc(name = "hardhat_setCode")]
+    fn set_code(&self, address: H160, code: Bytes) -> JsonRpcResult<()>;
+
+    #[rpc(name = "hardhat_setStorageAt")]
+    fn set_storage_at(
+        &self, address: H160, position: U256, value: H256,
+    ) -> JsonRpcResult<()>;
+}
@@ -28,7 +28,7 @@ use crate::rpc::types::{
         Block, BlockNumber, CallRequest, EthRpcLogFilter, FilterChanges, Log,
         Receipt, SyncStatus, Transaction,
     },
-    Bytes, Index,
+    AddressTransactionsPage, Bytes, Index, LogCursor, LogsPage,
 };
 
 /// Eth rpc interface.
@@ -164,6 +164,13 @@ pub trait Eth {
     #[rpc(name = "eth_getTransactionByHash")]
     fn transaction_by_hash(&self, _: H256) -> Result<Option<Transaction>>;
 
+    /// Returns the executed transactions that involve the given address, from
+    /// the newest to the oldest. Requires `persist_address_tx_index`.
+    #[rpc(name = "eth_getTransactionsByAddress")]
+    fn transactions_by_address(
+        &self, _: H160, _: Option<U64>, _: Option<U64>,
+    ) -> Result<AddressTransactionsPage>;
+
     /// Returns transaction at given block hash and index.
     #[rpc(name = "eth_getTransactionByBlockHashAndIndex")]
     fn transaction_by_block_hash_and_index(
@@ -216,6 +223,13 @@ pub trait Eth {
     #[rpc(name = "eth_getLogs")]
     fn logs(&self, _: EthRpcLogFilter) -> Result<Vec<Log>>;
 
+    /// Returns a page of the logs matching given filter object, and the cursor
+    /// to get the next page with the same filter. `limit` is the page size.
+    #[rpc(name = "eth_getLogsWithCursor")]
+    fn logs_with_cursor(
+        &self, _: EthRpcLogFilter, _: Option<LogCursor>,
+    ) -> Result<LogsPage<Log>>;
+
     // /// Returns the hash of the current block, the seedHash, and the boundary
     // condition to be met. #[rpc(name = "eth_getWork")]
     // fn work(&self, _: Option<u64>) -> Result<Work>;
@@ -1,2 +1,4 @@
+pub mod dev;
 pub mod eth;
 pub mod trace;
+pub mod txpool;
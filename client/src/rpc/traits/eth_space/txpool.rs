@@ -0,0 +1,32 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::types::eth::{
+    Transaction, TxpoolContent, TxpoolContentFrom, TxpoolStatus,
+};
+use cfx_types::H160;
+use jsonrpc_core::Result as JsonRpcResult;
+use jsonrpc_derive::rpc;
+
+/// Geth compatible transaction pool rpc interface of eSpace.
+#[rpc(server)]
+pub trait TxPool {
+    /// Returns the number of pending and queued transactions.
+    #[rpc(name = "txpool_status")]
+    fn txpool_status(&self) -> JsonRpcResult<TxpoolStatus>;
+
+    /// Returns the summary of pending and queued transactions.
+    #[rpc(name = "txpool_inspect")]
+    fn txpool_inspect(&self) -> JsonRpcResult<TxpoolContent<String>>;
+
+    /// Returns the pending and queued transactions.
+    #[rpc(name = "txpool_content")]
+    fn txpool_content(&self) -> JsonRpcResult<TxpoolContent<Transaction>>;
+
+    /// Returns the pending and queued transactions of an address.
+    #[rpc(name = "txpool_contentFrom")]
+    fn txpool_content_from(
+        &self, address: H160,
+    ) -> JsonRpcResult<TxpoolContentFrom<Transaction>>;
+}
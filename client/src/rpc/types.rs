@@ -4,6 +4,7 @@
 
 mod account;
 pub mod address;
+mod address_transactions;
 mod blame_info;
 mod block;
 mod bytes;
@@ -15,6 +16,7 @@ pub mod eth;
 mod filter;
 mod index;
 mod log;
+mod logs_page;
 pub mod pos;
 mod pos_economics;
 mod provenance;
@@ -23,16 +25,19 @@ mod receipt;
 mod reward_info;
 mod sponsor_info;
 mod status;
+mod stratum;
 mod sync_graph_states;
 mod token_supply_info;
 mod trace;
 mod trace_filter;
 mod transaction;
 mod tx_pool;
+mod vote_params_info;
 
 pub use self::{
     account::Account,
     address::RpcAddress,
+    address_transactions::{AddressTransaction, AddressTransactionsPage},
     blame_info::BlameInfo,
     block::{Block, BlockTransactions, Header},
     bytes::Bytes,
@@ -40,17 +45,19 @@ pub use self::{
         sign_call, CallRequest, CheckBalanceAgainstTransactionResponse,
         EstimateGasAndCollateralResponse, SendTxRequest, MAX_GAS_CALL_REQUEST,
     },
-    consensus_graph_states::ConsensusGraphStates,
+    consensus_graph_states::{ConsensusBlockInfo, ConsensusGraphStates},
     epoch_number::{BlockHashOrEpochNumber, EpochNumber},
     filter::CfxRpcLogFilter,
     index::Index,
     log::Log,
+    logs_page::{LogCursor, LogsPage, DEFAULT_LOGS_PAGE_SIZE},
     pos_economics::PoSEconomics,
     provenance::Origin,
     receipt::Receipt,
     reward_info::RewardInfo,
     sponsor_info::SponsorInfo,
     status::Status,
+    stratum::StratumWorker,
     sync_graph_states::SyncGraphStates,
     token_supply_info::TokenSupplyInfo,
     trace::{
@@ -62,4 +69,5 @@ pub use self::{
         AccountPendingInfo, AccountPendingTransactions,
         TxPoolPendingNonceRange, TxPoolStatus, TxWithPoolInfo,
     },
+    vote_params_info::VoteParamsInfo,
 };
@@ -0,0 +1,94 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::rpc::error_codes::{address_tx_index_disabled, invalid_params};
+use cfx_types::{AddressWithSpace, H256, U64};
+use cfxcore::block_data_manager::{
+    AddressTransactionEntry, BlockDataManager, ADDRESS_ROLE_INTERNAL,
+    ADDRESS_ROLE_RECIPIENT, ADDRESS_ROLE_SENDER,
+};
+use jsonrpc_core::Result as JsonRpcResult;
+
+const DEFAULT_ADDRESS_TRANSACTIONS_PAGE_SIZE: u64 = 100;
+const MAX_ADDRESS_TRANSACTIONS_PAGE_SIZE: u64 = 1000;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransaction {
+    pub transaction_hash: H256,
+    pub epoch_number: U64,
+    /// The roles of the address in the transaction, which are `sender`,
+    /// `recipient` and `internal`.
+    pub roles: Vec<&'static str>,
+}
+
+impl From<AddressTransactionEntry> for AddressTransaction {
+    fn from(entry: AddressTransactionEntry) -> Self {
+        let mut roles = Vec::new();
+        if entry.roles & ADDRESS_ROLE_SENDER != 0 {
+            roles.push("sender");
+        }
+        if entry.roles & ADDRESS_ROLE_RECIPIENT != 0 {
+            roles.push("recipient");
+        }
+        if entry.roles & ADDRESS_ROLE_INTERNAL != 0 {
+            roles.push("internal");
+        }
+        AddressTransaction {
+            transaction_hash: entry.transaction_hash,
+            epoch_number: entry.epoch_number.into(),
+            roles,
+        }
+    }
+}
+
+/// A page of the transactions of an address, from the newest to the oldest.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransactionsPage {
+    pub transactions: Vec<AddressTransaction>,
+    /// The `cursor` to get the next page, or null if this is the last page.
+    pub next_cursor: Option<U64>,
+}
+
+impl AddressTransactionsPage {
+    pub fn query(
+        data_man: &BlockDataManager, address: AddressWithSpace,
+        cursor: Option<U64>, limit: Option<U64>,
+    ) -> JsonRpcResult<Self>
+    {
+        if !data_man.address_tx_index_enabled() {
+            return Err(address_tx_index_disabled());
+        }
+        let limit = limit
+            .map_or(DEFAULT_ADDRESS_TRANSACTIONS_PAGE_SIZE, |l| l.as_u64());
+        if limit == 0 || limit > MAX_ADDRESS_TRANSACTIONS_PAGE_SIZE {
+            return Err(invalid_params(
+                "limit",
+                format!(
+                    "limit should be in [1, {}]",
+                    MAX_ADDRESS_TRANSACTIONS_PAGE_SIZE
+                ),
+            ));
+        }
+
+        let entries = data_man.address_transactions(
+            &address,
+            cursor.map(|c| c.as_u64()),
+            limit as usize,
+        );
+        let next_cursor = if entries.len() == limit as usize {
+            entries.last().map(|(seq, _)| (*seq).into())
+        } else {
+            None
+        };
+        Ok(AddressTransactionsPage {
+            transactions: entries
+                .into_iter()
+                .map(|(_, entry)| entry.into())
+                .collect(),
+            next_cursor,
+        })
+    }
+}
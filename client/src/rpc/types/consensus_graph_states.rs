@@ -2,8 +2,11 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use cfx_types::{H256, U64};
-use cfxcore::state_exposer::ConsensusGraphStates as PrimitiveConsensusGraphStates;
+use cfx_types::{H256, U256, U64};
+use cfxcore::{
+    consensus::consensus_inner::BlockConsensusInfo,
+    state_exposer::ConsensusGraphStates as PrimitiveConsensusGraphStates,
+};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -65,3 +68,54 @@ impl ConsensusGraphStates {
         }
     }
 }
+
+/// The consensus information of a block, which is used to inspect the DAG.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsensusBlockInfo {
+    pub hash: H256,
+    pub height: U64,
+    pub parent_hash: H256,
+    pub referee_hashes: Vec<H256>,
+    pub era_block_hash: Option<H256>,
+    pub epoch_number: Option<U64>,
+    pub is_pivot: bool,
+    pub is_timer: bool,
+    pub is_on_timer_chain: bool,
+    pub is_heavy: bool,
+    pub adaptive: bool,
+    pub partial_invalid: bool,
+    pub pending: bool,
+    pub blame: U64,
+    pub weight: U256,
+    pub subtree_weight: U256,
+    pub past_size: U64,
+    pub anticone_size: Option<U64>,
+}
+
+impl ConsensusBlockInfo {
+    pub fn new(info: BlockConsensusInfo) -> Self {
+        // The weights are never negative.
+        let to_u256 = |weight: i128| U256::from(weight.max(0) as u128);
+        Self {
+            hash: info.hash,
+            height: info.height.into(),
+            parent_hash: info.parent_hash,
+            referee_hashes: info.referee_hashes,
+            era_block_hash: info.era_block_hash,
+            epoch_number: info.epoch_number.map(Into::into),
+            is_pivot: info.is_pivot,
+            is_timer: info.is_timer,
+            is_on_timer_chain: info.is_on_timer_chain,
+            is_heavy: info.is_heavy,
+            adaptive: info.adaptive,
+            partial_invalid: info.partial_invalid,
+            pending: info.pending,
+            blame: info.blame.into(),
+            weight: to_u256(info.weight),
+            subtree_weight: to_u256(info.subtree_weight),
+            past_size: info.past_size.into(),
+            anticone_size: info.anticone_size.map(Into::into),
+        }
+    }
+}
@@ -12,16 +12,18 @@ mod sync;
 mod trace;
 mod trace_filter;
 mod transaction;
+mod txpool;
 
 pub use self::{
-    block::Block,
+    block::{Block, BlockTransactions},
     block_number::BlockNumber,
     call_request::CallRequest,
-    filter::{EthRpcLogFilter, FilterChanges},
+    filter::{EthRpcLogFilter, FilterChanges, VariadicValue},
     log::Log,
     receipt::Receipt,
     sync::{SyncInfo, SyncStatus},
     trace::{LocalizedTrace, Res},
     trace_filter::TraceFilter,
     transaction::Transaction,
+    txpool::{TxpoolContent, TxpoolContentFrom, TxpoolNonce, TxpoolStatus},
 };
@@ -0,0 +1,85 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{H160, U256, U64};
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Transaction nonce used as a map key. It is serialized as a decimal
+/// string like in geth, and covers the full `U256` range accepted by the
+/// transaction pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TxpoolNonce(pub U256);
+
+impl Serialize for TxpoolNonce {
+    fn serialize<S: Serializer>(
+        &self, serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+/// Number of pending and queued transactions, returned by `txpool_status`.
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct TxpoolStatus {
+    /// Transactions ready to be packed.
+    pub pending: U64,
+    /// Transactions blocked by a missing nonce.
+    pub queued: U64,
+}
+
+/// Transactions grouped by sender and nonce, returned by `txpool_content`
+/// and `txpool_inspect`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TxpoolContent<T: Serialize> {
+    pub pending: BTreeMap<H160, BTreeMap<TxpoolNonce, T>>,
+    pub queued: BTreeMap<H160, BTreeMap<TxpoolNonce, T>>,
+}
+
+impl<T: Serialize> Default for TxpoolContent<T> {
+    fn default() -> Self {
+        TxpoolContent {
+            pending: BTreeMap::new(),
+            queued: BTreeMap::new(),
+        }
+    }
+}
+
+/// Transactions of a sender grouped by nonce, returned by
+/// `txpool_contentFrom`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TxpoolContentFrom<T: Serialize> {
+    pub pending: BTreeMap<TxpoolNonce, T>,
+    pub queued: BTreeMap<TxpoolNonce, T>,
+}
+
+impl<T: Serialize> Default for TxpoolContentFrom<T> {
+    fn default() -> Self {
+        TxpoolContentFrom {
+            pending: BTreeMap::new(),
+            queued: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TxpoolContentFrom, TxpoolNonce};
+    use cfx_types::U256;
+
+    #[test]
+    fn test_serialize_nonce_keys() {
+        let mut content = TxpoolContentFrom::<u8>::default();
+        content.pending.insert(TxpoolNonce(U256::from(10)), 1);
+        content.pending.insert(TxpoolNonce(U256::from(9)), 2);
+        content.queued.insert(TxpoolNonce(U256::MAX), 3);
+        assert_eq!(
+            serde_json::to_string(&content).unwrap(),
+            format!(
+                r#"{{"pending":{{"9":2,"10":1}},"queued":{{"{}":3}}}}"#,
+                U256::MAX
+            )
+        );
+    }
+}
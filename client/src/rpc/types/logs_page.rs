@@ -0,0 +1,48 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{H256, U64};
+use primitives::filter::LogCursor as PrimitiveLogCursor;
+
+/// The page size used if the filter does not set `limit`.
+pub const DEFAULT_LOGS_PAGE_SIZE: usize = 1000;
+
+/// The position of the next page of a paginated log query. It should be passed
+/// back unmodified together with the same filter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogCursor {
+    pub epoch_number: U64,
+    pub pivot_hash: H256,
+    pub skip: U64,
+}
+
+impl From<PrimitiveLogCursor> for LogCursor {
+    fn from(cursor: PrimitiveLogCursor) -> Self {
+        LogCursor {
+            epoch_number: cursor.epoch.into(),
+            pivot_hash: cursor.pivot_hash,
+            skip: (cursor.skip as u64).into(),
+        }
+    }
+}
+
+impl From<LogCursor> for PrimitiveLogCursor {
+    fn from(cursor: LogCursor) -> Self {
+        PrimitiveLogCursor {
+            epoch: cursor.epoch_number.as_u64(),
+            pivot_hash: cursor.pivot_hash,
+            skip: cursor.skip.as_usize(),
+        }
+    }
+}
+
+/// A page of logs in ascending order. The next page contains older logs.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogsPage<L> {
+    pub logs: Vec<L>,
+    /// The cursor of the next page, or null if there are no more logs.
+    pub next_cursor: Option<LogCursor>,
+}
@@ -0,0 +1,52 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use blockgen::StratumWorkerStats;
+use cfx_types::{U256, U64};
+use std::{net::SocketAddr, time::UNIX_EPOCH};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StratumConnection {
+    pub addr: SocketAddr,
+    /// Share difficulty of the connection, `None` for legacy workers that
+    /// only submit block solutions.
+    pub difficulty: Option<U256>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StratumWorker {
+    pub worker: String,
+    pub connections: Vec<StratumConnection>,
+    pub accepted_shares: U64,
+    pub stale_shares: U64,
+    pub invalid_shares: U64,
+    pub blocks: U64,
+    /// Unix timestamp in seconds of the last accepted share.
+    pub last_share_time: Option<U64>,
+}
+
+impl StratumWorker {
+    pub fn new(stats: StratumWorkerStats) -> Self {
+        StratumWorker {
+            worker: stats.worker,
+            connections: stats
+                .connections
+                .into_iter()
+                .map(|c| StratumConnection {
+                    addr: c.addr,
+                    difficulty: c.difficulty,
+                })
+                .collect(),
+            accepted_shares: stats.accepted_shares.into(),
+            stale_shares: stats.stale_shares.into(),
+            invalid_shares: stats.invalid_shares.into(),
+            blocks: stats.blocks.into(),
+            last_share_time: stats.last_share_time.and_then(|t| {
+                t.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs().into())
+            }),
+        }
+    }
+}
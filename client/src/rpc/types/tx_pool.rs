@@ -12,6 +12,12 @@ pub struct TxWithPoolInfo {
     pub state_balance: U256,
     pub local_balance_enough: bool,
     pub state_balance_enough: bool,
+    /// Whether the sender is configured as a local sender, whose
+    /// transactions are not evicted before execution.
+    pub local_sender: bool,
+    pub sender_pending_count: U256,
+    /// The minimum gas price to replace this transaction.
+    pub replacement_min_gas_price: U256,
 }
 
 #[derive(Default, Serialize)]
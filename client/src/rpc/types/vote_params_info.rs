@@ -0,0 +1,20 @@
+// Copyright 2022 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{U256, U64};
+
+#[derive(Debug, Serialize, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteParamsInfo {
+    /// The voting round of the last executed block.
+    pub round: U64,
+    /// The PoW base reward per block in Drip.
+    pub pow_base_reward: U256,
+    /// The annual interest rate scaled by 10^6, e.g., 40000 for 4%.
+    pub interest_rate: U256,
+    /// The PoW base reward after the votes so far in this round are applied.
+    pub pending_pow_base_reward: U256,
+    /// The interest rate after the votes so far in this round are applied.
+    pub pending_interest_rate: U256,
+}
@@ -168,6 +168,12 @@ pub mod pow {
     // and the confirmation speed
     pub const TARGET_AVERAGE_BLOCK_GENERATION_PERIOD: u64 = 500000;
     pub const INITIAL_DIFFICULTY: u64 = 20_000_000_000;
+    // Share difficulty of stratum workers, which is adjusted to get a share
+    // from each connection every `DEFAULT_STRATUM_SHARE_INTERVAL_MS`. The
+    // initial value is about 10 seconds of work for a 50 MH/s GPU.
+    pub const DEFAULT_STRATUM_SHARE_DIFFICULTY: u64 = 500_000_000;
+    pub const DEFAULT_STRATUM_MIN_SHARE_DIFFICULTY: u64 = 1_000_000;
+    pub const DEFAULT_STRATUM_SHARE_INTERVAL_MS: u64 = 10_000;
 }
 
 pub mod block {
@@ -226,6 +232,10 @@ pub mod staking {
     pub const BLOCKS_PER_YEAR: u64 = BLOCKS_PER_DAY * 365;
     /// The inverse of interest rate
     pub const INVERSE_INTEREST_RATE: u64 = 25;
+    /// The number of blocks in a voting round of the parameters controlled by
+    /// the stakers. The votes of a round are applied at the first block
+    /// number of the next round, which is a multiple of this period.
+    pub const PARAMS_VOTE_PERIOD: u64 = BLOCKS_PER_DAY * 60;
 
     /// This is the storage collateral units for each KiB of code, amount in
     /// COLLATERAL_UNITs. Code collateral is calculated by each whole KiB
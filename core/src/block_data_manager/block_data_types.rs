@@ -1,6 +1,6 @@
 use crate::observer::trace::BlockExecTraces;
 use cfx_internal_common::{DatabaseDecodable, DatabaseEncodable};
-use cfx_types::{Address, Bloom, H256, U256};
+use cfx_types::{Address, AddressWithSpace, Bloom, Space, H256, U256};
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use primitives::BlockReceipts;
@@ -360,6 +360,66 @@ impl PosRewardInfo {
     }
 }
 
+/// The address sends the transaction.
+pub const ADDRESS_ROLE_SENDER: u8 = 1;
+/// The address is the recipient of the transaction.
+pub const ADDRESS_ROLE_RECIPIENT: u8 = 2;
+/// The address is called, created or sends a call in the execution trace.
+pub const ADDRESS_ROLE_INTERNAL: u8 = 4;
+
+/// The position of an entry in the address transaction index. Entries of the
+/// same address are numbered by `seq` in the order of insertion.
+#[derive(Clone, Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct AddressTransactionKey {
+    pub address: Address,
+    pub space: Space,
+    pub seq: u64,
+}
+
+impl AddressTransactionKey {
+    pub fn address_with_space(&self) -> AddressWithSpace {
+        AddressWithSpace {
+            address: self.address,
+            space: self.space,
+        }
+    }
+}
+
+/// A transaction in the address transaction index.
+#[derive(Clone, Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct AddressTransactionEntry {
+    pub transaction_hash: H256,
+    pub epoch_number: u64,
+    /// A combination of `ADDRESS_ROLE_*`.
+    pub roles: u8,
+}
+
+/// The entries of an address are in `[start, end)`. Entries below `start`
+/// have been garbage collected, and there may be holes left by re-executed
+/// epochs in the range.
+#[derive(Clone, Copy, Debug, Default, RlpEncodable, RlpDecodable)]
+pub struct AddressTransactionRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// The number of levels in the log bloom index. A bloom at level `l` is the
+/// union of the log blooms of `16^l` consecutive epochs, i.e. 1, 16, 256 and
+/// 4096 epochs.
+pub const LOG_BLOOM_INDEX_LEVELS: u32 = 4;
+/// Each level aggregates `2^LOG_BLOOM_INDEX_LEVEL_BITS` groups of the level
+/// below.
+pub const LOG_BLOOM_INDEX_LEVEL_BITS: u32 = 4;
+
+/// The epochs in `[start, end]` are all covered by the log bloom index. The
+/// range is empty if `start > end`, e.g. after all indexed epochs are garbage
+/// collected.
+#[derive(Clone, Copy, Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct LogBloomIndexRange {
+    pub start: u64,
+    pub end: u64,
+}
+
 pub fn db_encode_list<T>(list: &[T]) -> Vec<u8>
 where T: DatabaseEncodable {
     let mut rlp_stream = RlpStream::new();
@@ -387,3 +447,7 @@ impl_db_encoding_as_rlp!(EpochExecutionContext);
 impl_db_encoding_as_rlp!(BlockRewardResult);
 impl_db_encoding_as_rlp!(BlamedHeaderVerifiedRoots);
 impl_db_encoding_as_rlp!(PosRewardInfo);
+impl_db_encoding_as_rlp!(AddressTransactionKey);
+impl_db_encoding_as_rlp!(AddressTransactionEntry);
+impl_db_encoding_as_rlp!(AddressTransactionRange);
+impl_db_encoding_as_rlp!(LogBloomIndexRange);
@@ -0,0 +1,511 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    block_data_manager::{db_manager::DBManager, DataVersionTuple},
+    verification::{compute_receipts_root, compute_transaction_root},
+};
+use cfx_types::{Bloom, H256};
+use primitives::BlockHeaderBuilder;
+use rlp::DecoderError;
+use std::{cmp::max, fmt};
+
+/// An inconsistency found in the block database by `check_block_db`.
+#[derive(Debug)]
+pub enum DbInconsistency {
+    MissingEpochSet {
+        epoch: u64,
+    },
+    CorruptedEpochSet {
+        epoch: u64,
+        error: DecoderError,
+    },
+    MissingHeader {
+        epoch: u64,
+        block_hash: H256,
+    },
+    CorruptedHeader {
+        epoch: u64,
+        block_hash: H256,
+        error: DecoderError,
+    },
+    HeaderHashMismatch {
+        epoch: u64,
+        block_hash: H256,
+        computed: H256,
+    },
+    CorruptedBody {
+        epoch: u64,
+        block_hash: H256,
+        error: DecoderError,
+    },
+    TransactionsRootMismatch {
+        epoch: u64,
+        block_hash: H256,
+        expected: H256,
+        computed: H256,
+    },
+    MissingExecutionResult {
+        epoch: u64,
+        block_hash: H256,
+    },
+    CorruptedExecutionResult {
+        epoch: u64,
+        block_hash: H256,
+        error: DecoderError,
+    },
+    /// The execution result of the block is not computed under the pivot
+    /// block of the epoch.
+    StaleExecutionResult {
+        epoch: u64,
+        block_hash: H256,
+        result_pivot_hash: H256,
+    },
+    BlockBloomMismatch {
+        epoch: u64,
+        block_hash: H256,
+    },
+    CorruptedExecutionCommitment {
+        epoch: u64,
+        pivot_hash: H256,
+        error: DecoderError,
+    },
+    ReceiptsRootMismatch {
+        epoch: u64,
+        pivot_hash: H256,
+        expected: H256,
+        computed: H256,
+    },
+    LogsBloomHashMismatch {
+        epoch: u64,
+        pivot_hash: H256,
+        expected: H256,
+        computed: H256,
+    },
+}
+
+impl fmt::Display for DbInconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DbInconsistency::MissingEpochSet { epoch } => {
+                write!(f, "epoch {}: executed block set is missing", epoch)
+            }
+            DbInconsistency::CorruptedEpochSet { epoch, error } => write!(
+                f,
+                "epoch {}: executed block set is corrupted: {:?}",
+                epoch, error
+            ),
+            DbInconsistency::MissingHeader { epoch, block_hash } => write!(
+                f,
+                "epoch {}: header of block {:?} is missing",
+                epoch, block_hash
+            ),
+            DbInconsistency::CorruptedHeader {
+                epoch,
+                block_hash,
+                error,
+            } => write!(
+                f,
+                "epoch {}: header of block {:?} is corrupted: {:?}",
+                epoch, block_hash, error
+            ),
+            DbInconsistency::HeaderHashMismatch {
+                epoch,
+                block_hash,
+                computed,
+            } => write!(
+                f,
+                "epoch {}: header stored as block {:?} hashes to {:?}",
+                epoch, block_hash, computed
+            ),
+            DbInconsistency::CorruptedBody {
+                epoch,
+                block_hash,
+                error,
+            } => write!(
+                f,
+                "epoch {}: body of block {:?} is corrupted: {:?}",
+                epoch, block_hash, error
+            ),
+            DbInconsistency::TransactionsRootMismatch {
+                epoch,
+                block_hash,
+                expected,
+                computed,
+            } => write!(
+                f,
+                "epoch {}: block {:?} has transactions root {:?} in header, \
+                 computed {:?} from body",
+                epoch, block_hash, expected, computed
+            ),
+            DbInconsistency::MissingExecutionResult { epoch, block_hash } => {
+                write!(
+                    f,
+                    "epoch {}: execution result of block {:?} is missing",
+                    epoch, block_hash
+                )
+            }
+            DbInconsistency::CorruptedExecutionResult {
+                epoch,
+                block_hash,
+                error,
+            } => write!(
+                f,
+                "epoch {}: execution result of block {:?} is corrupted: {:?}",
+                epoch, block_hash, error
+            ),
+            DbInconsistency::StaleExecutionResult {
+                epoch,
+                block_hash,
+                result_pivot_hash,
+            } => write!(
+                f,
+                "epoch {}: execution result of block {:?} is computed under \
+                 pivot block {:?}",
+                epoch, block_hash, result_pivot_hash
+            ),
+            DbInconsistency::BlockBloomMismatch { epoch, block_hash } => {
+                write!(
+                    f,
+                    "epoch {}: logs bloom of block {:?} does not match its \
+                     receipts",
+                    epoch, block_hash
+                )
+            }
+            DbInconsistency::CorruptedExecutionCommitment {
+                epoch,
+                pivot_hash,
+                error,
+            } => write!(
+                f,
+                "epoch {}: execution commitment of pivot block {:?} is \
+                 corrupted: {:?}",
+                epoch, pivot_hash, error
+            ),
+            DbInconsistency::ReceiptsRootMismatch {
+                epoch,
+                pivot_hash,
+                expected,
+                computed,
+            } => write!(
+                f,
+                "epoch {}: execution commitment of pivot block {:?} has \
+                 receipts root {:?}, computed {:?} from receipts",
+                epoch, pivot_hash, expected, computed
+            ),
+            DbInconsistency::LogsBloomHashMismatch {
+                epoch,
+                pivot_hash,
+                expected,
+                computed,
+            } => write!(
+                f,
+                "epoch {}: execution commitment of pivot block {:?} has logs \
+                 bloom hash {:?}, computed {:?} from receipts",
+                epoch, pivot_hash, expected, computed
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DbCheckReport {
+    pub checked_epochs: u64,
+    pub checked_blocks: u64,
+    /// Blocks without body in the database. This is expected for blocks
+    /// whose bodies are garbage collected on non-archive nodes.
+    pub missing_bodies: u64,
+    /// Epochs without execution commitment, which are not executed yet.
+    pub unexecuted_epochs: u64,
+    /// Epochs whose execution results are garbage collected, which are not
+    /// checked against the execution commitments.
+    pub gc_execution_result_epochs: u64,
+    pub inconsistencies: Vec<DbInconsistency>,
+    /// The number of inconsistencies which are repaired.
+    pub repaired: u64,
+}
+
+/// Walk the executed epoch sets in `[from_epoch, to_epoch]` and verify the
+/// blocks and the execution results stored in the block database.
+///
+/// `from_epoch` defaults to the current era genesis, and without `to_epoch`
+/// the check stops at the first epoch whose executed block set is not in the
+/// database.
+///
+/// The execution results of the epochs before the GC progress minus
+/// `additional_maintained_execution_result_epoch_count` are expected to be
+/// removed by the database GC, so they are not checked or repaired.
+///
+/// With `repair`, the inconsistent execution results are removed so that the
+/// node re-executes the epochs after restart. Inconsistencies of the blocks
+/// and of the executed epoch sets themselves are only reported, because the
+/// consensus graph is recovered from the headers in the executed epoch sets.
+pub fn check_block_db(
+    db_manager: &DBManager, from_epoch: Option<u64>, to_epoch: Option<u64>,
+    additional_maintained_execution_result_epoch_count: Option<usize>,
+    repair: bool,
+) -> DbCheckReport
+{
+    let mut report = DbCheckReport::default();
+    // The same as `BlockDataManager::earliest_epoch_with_execution_result`,
+    // but with the persisted GC progress.
+    let earliest_epoch_with_execution_result =
+        match additional_maintained_execution_result_epoch_count {
+            Some(defer) => db_manager
+                .gc_progress_from_db()
+                .unwrap_or(0)
+                .saturating_sub(defer as u64),
+            None => 0,
+        };
+    let from_epoch = match from_epoch {
+        Some(epoch) => epoch,
+        None => db_manager
+            .checkpoint_hashes_from_db()
+            .and_then(|(_, cur_era_genesis_hash)| {
+                db_manager
+                    .checked_block_header_from_db(&cur_era_genesis_hash)
+                    .ok()?
+            })
+            .map_or(0, |header| header.height()),
+    };
+    // The executed block set of the true genesis is not stored.
+    let mut epoch = max(from_epoch, 1);
+    loop {
+        if to_epoch.map_or(false, |to_epoch| epoch > to_epoch) {
+            break;
+        }
+        let epoch_hashes =
+            match db_manager.checked_executed_epoch_set_hashes_from_db(epoch) {
+                Ok(Some(epoch_hashes)) => epoch_hashes,
+                Ok(None) => {
+                    if to_epoch.is_some() {
+                        report
+                            .inconsistencies
+                            .push(DbInconsistency::MissingEpochSet { epoch });
+                        epoch += 1;
+                        continue;
+                    }
+                    break;
+                }
+                Err(error) => {
+                    report.inconsistencies.push(
+                        DbInconsistency::CorruptedEpochSet { epoch, error },
+                    );
+                    epoch += 1;
+                    continue;
+                }
+            };
+        report.checked_epochs += 1;
+        check_epoch(
+            db_manager,
+            epoch,
+            &epoch_hashes,
+            epoch >= earliest_epoch_with_execution_result,
+            repair,
+            &mut report,
+        );
+        epoch += 1;
+    }
+    report
+}
+
+fn check_epoch(
+    db_manager: &DBManager, epoch: u64, epoch_hashes: &Vec<H256>,
+    has_execution_results: bool, repair: bool, report: &mut DbCheckReport,
+) {
+    for block_hash in epoch_hashes {
+        report.checked_blocks += 1;
+        if let Some(inconsistency) =
+            check_block(db_manager, epoch, block_hash, report)
+        {
+            report.inconsistencies.push(inconsistency);
+        }
+    }
+
+    let pivot_hash = match epoch_hashes.last() {
+        Some(pivot_hash) => *pivot_hash,
+        None => return,
+    };
+    if !has_execution_results {
+        report.gc_execution_result_epochs += 1;
+        return;
+    }
+    let commitment = match db_manager
+        .checked_epoch_execution_commitment_from_db(&pivot_hash)
+    {
+        Ok(Some(commitment)) => commitment,
+        Ok(None) => {
+            report.unexecuted_epochs += 1;
+            return;
+        }
+        Err(error) => {
+            report.inconsistencies.push(
+                DbInconsistency::CorruptedExecutionCommitment {
+                    epoch,
+                    pivot_hash,
+                    error,
+                },
+            );
+            if repair {
+                remove_epoch_execution_results(
+                    db_manager,
+                    epoch_hashes,
+                    &pivot_hash,
+                );
+                report.repaired += 1;
+            }
+            return;
+        }
+    };
+
+    let mut epoch_receipts = Vec::with_capacity(epoch_hashes.len());
+    let mut inconsistencies = Vec::new();
+    for block_hash in epoch_hashes {
+        match db_manager.checked_block_execution_result_from_db(block_hash) {
+            Ok(Some(DataVersionTuple(result_pivot_hash, execution_result))) => {
+                if result_pivot_hash != pivot_hash {
+                    inconsistencies.push(
+                        DbInconsistency::StaleExecutionResult {
+                            epoch,
+                            block_hash: *block_hash,
+                            result_pivot_hash,
+                        },
+                    );
+                    continue;
+                }
+                let bloom = execution_result
+                    .block_receipts
+                    .receipts
+                    .iter()
+                    .fold(Bloom::zero(), |mut b, r| {
+                        b.accrue_bloom(&r.log_bloom);
+                        b
+                    });
+                if bloom != execution_result.bloom {
+                    inconsistencies.push(DbInconsistency::BlockBloomMismatch {
+                        epoch,
+                        block_hash: *block_hash,
+                    });
+                }
+                epoch_receipts.push(execution_result.block_receipts);
+            }
+            Ok(None) => {
+                inconsistencies.push(DbInconsistency::MissingExecutionResult {
+                    epoch,
+                    block_hash: *block_hash,
+                })
+            }
+            Err(error) => inconsistencies.push(
+                DbInconsistency::CorruptedExecutionResult {
+                    epoch,
+                    block_hash: *block_hash,
+                    error,
+                },
+            ),
+        }
+    }
+
+    // The epoch roots can only be recomputed with all the block receipts.
+    if inconsistencies.is_empty() {
+        let receipts_root = compute_receipts_root(&epoch_receipts);
+        if receipts_root != commitment.receipts_root {
+            inconsistencies.push(DbInconsistency::ReceiptsRootMismatch {
+                epoch,
+                pivot_hash,
+                expected: commitment.receipts_root,
+                computed: receipts_root,
+            });
+        }
+        let logs_bloom_hash =
+            BlockHeaderBuilder::compute_block_logs_bloom_hash(&epoch_receipts);
+        if logs_bloom_hash != commitment.logs_bloom_hash {
+            inconsistencies.push(DbInconsistency::LogsBloomHashMismatch {
+                epoch,
+                pivot_hash,
+                expected: commitment.logs_bloom_hash,
+                computed: logs_bloom_hash,
+            });
+        }
+    }
+
+    if !inconsistencies.is_empty() {
+        if repair {
+            remove_epoch_execution_results(
+                db_manager,
+                epoch_hashes,
+                &pivot_hash,
+            );
+            report.repaired += inconsistencies.len() as u64;
+        }
+        report.inconsistencies.append(&mut inconsistencies);
+    }
+}
+
+/// Check the header and the body of a block. Return the inconsistency found,
+/// if any.
+fn check_block(
+    db_manager: &DBManager, epoch: u64, block_hash: &H256,
+    report: &mut DbCheckReport,
+) -> Option<DbInconsistency>
+{
+    let block_hash = *block_hash;
+    let header = match db_manager.checked_block_header_from_db(&block_hash) {
+        Ok(Some(header)) => header,
+        Ok(None) => {
+            return Some(DbInconsistency::MissingHeader { epoch, block_hash })
+        }
+        Err(error) => {
+            return Some(DbInconsistency::CorruptedHeader {
+                epoch,
+                block_hash,
+                error,
+            })
+        }
+    };
+    // The hash is recomputed from the header content when it is decoded.
+    if header.hash() != block_hash {
+        return Some(DbInconsistency::HeaderHashMismatch {
+            epoch,
+            block_hash,
+            computed: header.hash(),
+        });
+    }
+
+    let transactions = match db_manager.checked_block_body_from_db(&block_hash)
+    {
+        Ok(Some(transactions)) => transactions,
+        Ok(None) => {
+            report.missing_bodies += 1;
+            return None;
+        }
+        Err(error) => {
+            return Some(DbInconsistency::CorruptedBody {
+                epoch,
+                block_hash,
+                error,
+            })
+        }
+    };
+    let transactions_root = compute_transaction_root(&transactions);
+    if transactions_root != *header.transactions_root() {
+        return Some(DbInconsistency::TransactionsRootMismatch {
+            epoch,
+            block_hash,
+            expected: *header.transactions_root(),
+            computed: transactions_root,
+        });
+    }
+    None
+}
+
+/// Remove the execution results of an epoch, so that the epoch is executed
+/// again after restart.
+fn remove_epoch_execution_results(
+    db_manager: &DBManager, epoch_hashes: &Vec<H256>, pivot_hash: &H256,
+) {
+    for block_hash in epoch_hashes {
+        db_manager.remove_block_execution_result_from_db(block_hash);
+    }
+    db_manager.remove_epoch_execution_commitment_from_db(pivot_hash);
+}
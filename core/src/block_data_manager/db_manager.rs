@@ -1,14 +1,16 @@
 use crate::{
     block_data_manager::{
-        db_decode_list, db_encode_list, BlamedHeaderVerifiedRoots,
-        BlockExecutionResultWithEpoch, BlockRewardResult, BlockTracesWithEpoch,
-        CheckpointHashes, DataVersionTuple, EpochExecutionContext,
-        LocalBlockInfo, PosRewardInfo,
+        db_decode_list, db_encode_list, AddressTransactionEntry,
+        AddressTransactionKey, AddressTransactionRange,
+        BlamedHeaderVerifiedRoots, BlockExecutionResultWithEpoch,
+        BlockRewardResult, BlockTracesWithEpoch, CheckpointHashes,
+        DataVersionTuple, EpochExecutionContext, LocalBlockInfo,
+        LogBloomIndexRange, PosRewardInfo,
     },
     db::{
-        COL_BLAMED_HEADER_VERIFIED_ROOTS, COL_BLOCKS, COL_BLOCK_TRACES,
-        COL_EPOCH_NUMBER, COL_HASH_BY_BLOCK_NUMBER, COL_MISC,
-        COL_REWARD_BY_POS_EPOCH, COL_TX_INDEX,
+        COL_ADDRESS_TX_INDEX, COL_BLAMED_HEADER_VERIFIED_ROOTS, COL_BLOCKS,
+        COL_BLOCK_TRACES, COL_EPOCH_NUMBER, COL_HASH_BY_BLOCK_NUMBER,
+        COL_LOG_BLOOM_INDEX, COL_MISC, COL_REWARD_BY_POS_EPOCH, COL_TX_INDEX,
     },
     pow::PowComputer,
     verification::VerificationConfig,
@@ -20,12 +22,14 @@ use cfx_internal_common::{
 use cfx_storage::{
     storage_db::KeyValueDbTrait, KvdbRocksdb, KvdbSqlite, KvdbSqliteStatements,
 };
-use cfx_types::H256;
+use cfx_types::{AddressWithSpace, Bloom, Space, H256};
 use db::SystemDB;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use primitives::{Block, BlockHeader, SignedTransaction, TransactionIndex};
-use rlp::Rlp;
-use std::{collections::HashMap, fs, path::Path, sync::Arc};
+use rlp::{DecoderError, Rlp};
+use std::{
+    collections::HashMap, convert::TryInto, fs, path::Path, sync::Arc,
+};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
@@ -39,6 +43,8 @@ const EPOCH_SKIPPED_BLOCK_SET_SUFFIX_BYTE: u8 = 7;
 const BLOCK_REWARD_RESULT_SUFFIX_BYTE: u8 = 8;
 const BLOCK_TERMINAL_KEY: &[u8] = b"block_terminals";
 const GC_PROGRESS_KEY: &[u8] = b"gc_progress";
+const EPOCH_ADDRESS_TRANSACTIONS_PREFIX_BYTE: u8 = 0;
+const LOG_BLOOM_INDEX_RANGE_SUFFIX_BYTE: u8 = 0xff;
 
 #[derive(Clone, Copy, Hash, Ord, PartialOrd, Eq, PartialEq, EnumIter)]
 enum DBTable {
@@ -50,6 +56,8 @@ enum DBTable {
     BlockTraces,
     HashByBlockNumber,
     RewardByPosEpoch,
+    AddressTransactions,
+    LogBloomIndex,
 }
 
 fn rocks_db_col(table: DBTable) -> u32 {
@@ -62,6 +70,8 @@ fn rocks_db_col(table: DBTable) -> u32 {
         DBTable::BlockTraces => COL_BLOCK_TRACES,
         DBTable::HashByBlockNumber => COL_HASH_BY_BLOCK_NUMBER,
         DBTable::RewardByPosEpoch => COL_REWARD_BY_POS_EPOCH,
+        DBTable::AddressTransactions => COL_ADDRESS_TX_INDEX,
+        DBTable::LogBloomIndex => COL_LOG_BLOOM_INDEX,
     }
 }
 
@@ -75,12 +85,17 @@ fn sqlite_db_table(table: DBTable) -> String {
         DBTable::BlockTraces => "block_traces",
         DBTable::HashByBlockNumber => "hash_by_block_number",
         DBTable::RewardByPosEpoch => "reward_by_pos_epoch",
+        DBTable::AddressTransactions => "address_transactions",
+        DBTable::LogBloomIndex => "log_bloom_index",
     }
     .into()
 }
 
 pub struct DBManager {
     table_db: HashMap<DBTable, Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>>,
+    /// The RocksDB database for range iteration, which is not supported by
+    /// the SQLite backend.
+    rocksdb: Option<Arc<SystemDB>>,
     pow: Arc<PowComputer>,
 }
 
@@ -98,7 +113,11 @@ impl DBManager {
                     as Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>,
             );
         }
-        Self { table_db, pow }
+        Self {
+            table_db,
+            rocksdb: Some(db),
+            pow,
+        }
     }
 }
 
@@ -132,7 +151,11 @@ impl DBManager {
                     as Box<dyn KeyValueDbTrait<ValueType = Box<[u8]>>>,
             );
         }
-        Self { table_db, pow }
+        Self {
+            table_db,
+            rocksdb: None,
+            pow,
+        }
     }
 }
 
@@ -452,6 +475,16 @@ impl DBManager {
         self.load_decodable_val(DBTable::Misc, b"instance")
     }
 
+    /// Whether the database still serves reads. The small instance id entry
+    /// is read so that the check is cheap.
+    pub fn is_readable(&self) -> bool {
+        self.table_db
+            .get(&DBTable::Misc)
+            .unwrap()
+            .get(b"instance")
+            .is_ok()
+    }
+
     pub fn insert_execution_context_to_db(
         &self, hash: &H256, ctx: &EpochExecutionContext,
     ) {
@@ -506,6 +539,279 @@ impl DBManager {
         )
     }
 
+    pub fn insert_address_transaction_range_to_db(
+        &self, address: &AddressWithSpace, range: &AddressTransactionRange,
+    ) {
+        self.insert_encodable_val(
+            DBTable::AddressTransactions,
+            &address_transaction_range_key(address),
+            range,
+        )
+    }
+
+    pub fn address_transaction_range_from_db(
+        &self, address: &AddressWithSpace,
+    ) -> Option<AddressTransactionRange> {
+        self.load_decodable_val(
+            DBTable::AddressTransactions,
+            &address_transaction_range_key(address),
+        )
+    }
+
+    pub fn insert_address_transaction_to_db(
+        &self, key: &AddressTransactionKey, entry: &AddressTransactionEntry,
+    ) {
+        self.insert_encodable_val(
+            DBTable::AddressTransactions,
+            &address_transaction_key(key),
+            entry,
+        )
+    }
+
+    pub fn address_transaction_from_db(
+        &self, key: &AddressTransactionKey,
+    ) -> Option<AddressTransactionEntry> {
+        self.load_decodable_val(
+            DBTable::AddressTransactions,
+            &address_transaction_key(key),
+        )
+    }
+
+    pub fn remove_address_transaction_from_db(
+        &self, key: &AddressTransactionKey,
+    ) {
+        self.remove_from_db(
+            DBTable::AddressTransactions,
+            &address_transaction_key(key),
+        )
+    }
+
+    /// Returns at most `limit` index entries of `address` with `seq` in
+    /// `[start, end)` from the newest to the oldest, together with their
+    /// `seq`. With RocksDB, the entries are read by one reverse range scan
+    /// over the address prefix. Otherwise they are read one by one, and the
+    /// holes left by re-executed epochs are skipped.
+    pub fn address_transactions_from_db(
+        &self, address: &AddressWithSpace, start: u64, end: u64, limit: usize,
+    ) -> Vec<(u64, AddressTransactionEntry)> {
+        if start >= end || limit == 0 {
+            return vec![];
+        }
+        let key_at = |seq| {
+            address_transaction_key(&AddressTransactionKey {
+                address: address.address,
+                space: address.space,
+                seq,
+            })
+        };
+        if let Some(db) = &self.rocksdb {
+            let prefix_len = address_transaction_range_key(address).len();
+            return db
+                .key_value()
+                .iter_range_rev(
+                    COL_ADDRESS_TX_INDEX,
+                    &key_at(start),
+                    &key_at(end),
+                    limit,
+                )
+                .expect("db read failure")
+                .into_iter()
+                .map(|(key, value)| {
+                    let seq = u64::from_be_bytes(
+                        key[prefix_len..].try_into().expect("seq key"),
+                    );
+                    let entry = AddressTransactionEntry::db_decode(&value)
+                        .expect("decode succeeds");
+                    (seq, entry)
+                })
+                .collect();
+        }
+        let mut result = Vec::new();
+        for seq in (start..end).rev() {
+            if result.len() >= limit {
+                break;
+            }
+            if let Some(entry) = self.load_decodable_val(
+                DBTable::AddressTransactions,
+                &key_at(seq),
+            ) {
+                result.push((seq, entry));
+            }
+        }
+        result
+    }
+
+    /// The keys of the address index entries written by an epoch, so they can
+    /// be removed when the epoch is garbage collected or re-executed.
+    pub fn insert_epoch_address_transactions_to_db(
+        &self, epoch: u64, keys: &Vec<AddressTransactionKey>,
+    ) {
+        self.insert_encodable_list(
+            DBTable::AddressTransactions,
+            &epoch_address_transactions_key(epoch),
+            keys,
+        )
+    }
+
+    pub fn epoch_address_transactions_from_db(
+        &self, epoch: u64,
+    ) -> Option<Vec<AddressTransactionKey>> {
+        self.load_decodable_list(
+            DBTable::AddressTransactions,
+            &epoch_address_transactions_key(epoch),
+        )
+    }
+
+    pub fn remove_epoch_address_transactions_from_db(&self, epoch: u64) {
+        self.remove_from_db(
+            DBTable::AddressTransactions,
+            &epoch_address_transactions_key(epoch),
+        )
+    }
+
+    /// Blooms are stored as raw bytes. Empty blooms are not stored.
+    pub fn insert_log_bloom_to_db(
+        &self, space: Space, level: u32, group: u64, bloom: &Bloom,
+    ) {
+        self.insert_to_db(
+            DBTable::LogBloomIndex,
+            &log_bloom_key(space, level, group),
+            bloom.as_bytes().to_vec(),
+        )
+    }
+
+    pub fn log_bloom_from_db(
+        &self, space: Space, level: u32, group: u64,
+    ) -> Option<Bloom> {
+        let encoded = self.load_from_db(
+            DBTable::LogBloomIndex,
+            &log_bloom_key(space, level, group),
+        )?;
+        Some(Bloom::from_slice(&encoded))
+    }
+
+    pub fn remove_log_bloom_from_db(
+        &self, space: Space, level: u32, group: u64,
+    ) {
+        self.remove_from_db(
+            DBTable::LogBloomIndex,
+            &log_bloom_key(space, level, group),
+        )
+    }
+
+    pub fn insert_log_bloom_index_range_to_db(
+        &self, space: Space, range: &LogBloomIndexRange,
+    ) {
+        self.insert_encodable_val(
+            DBTable::LogBloomIndex,
+            &log_bloom_index_range_key(space),
+            range,
+        )
+    }
+
+    pub fn log_bloom_index_range_from_db(
+        &self, space: Space,
+    ) -> Option<LogBloomIndexRange> {
+        self.load_decodable_val(
+            DBTable::LogBloomIndex,
+            &log_bloom_index_range_key(space),
+        )
+    }
+
+    /// The functions below return an error instead of panicking on corrupted
+    /// data. They are used by the offline database checker.
+    pub fn checked_block_header_from_db(
+        &self, hash: &H256,
+    ) -> Result<Option<BlockHeader>, DecoderError> {
+        self.try_load_decodable_val(DBTable::Blocks, hash.as_bytes())
+    }
+
+    pub fn checked_block_body_from_db(
+        &self, hash: &H256,
+    ) -> Result<Option<Vec<Arc<SignedTransaction>>>, DecoderError> {
+        match self.load_from_db(DBTable::Blocks, &block_body_key(hash)) {
+            Some(encoded) => {
+                Block::decode_body_with_tx_public(&Rlp::new(&encoded)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn checked_block_execution_result_from_db(
+        &self, hash: &H256,
+    ) -> Result<Option<BlockExecutionResultWithEpoch>, DecoderError> {
+        self.try_load_decodable_val(
+            DBTable::Blocks,
+            &block_execution_result_key(hash),
+        )
+    }
+
+    pub fn checked_epoch_execution_commitment_from_db(
+        &self, hash: &H256,
+    ) -> Result<Option<EpochExecutionCommitment>, DecoderError> {
+        self.try_load_decodable_val(
+            DBTable::Blocks,
+            &epoch_consensus_epoch_execution_commitment_key(hash),
+        )
+    }
+
+    pub fn checked_executed_epoch_set_hashes_from_db(
+        &self, epoch: u64,
+    ) -> Result<Option<Vec<H256>>, DecoderError> {
+        match self.load_from_db(
+            DBTable::EpochNumbers,
+            &executed_epoch_set_key(epoch)[0..9],
+        ) {
+            Some(encoded) => db_decode_list(&encoded).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The functions below overwrite the entries with arbitrary bytes to
+    /// simulate corrupted data in tests.
+    #[cfg(test)]
+    pub fn insert_raw_block_header_to_db(&self, hash: &H256, raw: Vec<u8>) {
+        self.insert_to_db(DBTable::Blocks, hash.as_bytes(), raw)
+    }
+
+    #[cfg(test)]
+    pub fn insert_raw_block_body_to_db(&self, hash: &H256, raw: Vec<u8>) {
+        self.insert_to_db(DBTable::Blocks, &block_body_key(hash), raw)
+    }
+
+    #[cfg(test)]
+    pub fn insert_raw_block_execution_result_to_db(
+        &self, hash: &H256, raw: Vec<u8>,
+    ) {
+        self.insert_to_db(
+            DBTable::Blocks,
+            &block_execution_result_key(hash),
+            raw,
+        )
+    }
+
+    #[cfg(test)]
+    pub fn insert_raw_epoch_execution_commitment_to_db(
+        &self, hash: &H256, raw: Vec<u8>,
+    ) {
+        self.insert_to_db(
+            DBTable::Blocks,
+            &epoch_consensus_epoch_execution_commitment_key(hash),
+            raw,
+        )
+    }
+
+    #[cfg(test)]
+    pub fn insert_raw_executed_epoch_set_hashes_to_db(
+        &self, epoch: u64, raw: Vec<u8>,
+    ) {
+        self.insert_to_db(
+            DBTable::EpochNumbers,
+            &executed_epoch_set_key(epoch)[0..9],
+            raw,
+        )
+    }
+
     /// The functions below are private utils used by the DBManager to access
     /// database
     fn insert_to_db(&self, table: DBTable, db_key: &[u8], value: Vec<u8>) {
@@ -560,6 +866,16 @@ impl DBManager {
         V::db_decode(&encoded).ok()
     }
 
+    fn try_load_decodable_val<V>(
+        &self, table: DBTable, db_key: &[u8],
+    ) -> Result<Option<V>, DecoderError>
+    where V: DatabaseDecodable {
+        match self.load_from_db(table, db_key) {
+            Some(encoded) => V::db_decode(&encoded).map(Some),
+            None => Ok(None),
+        }
+    }
+
     fn load_decodable_list<V>(
         &self, table: DBTable, db_key: &[u8],
     ) -> Option<Vec<V>>
@@ -620,6 +936,48 @@ fn epoch_consensus_epoch_execution_commitment_key(hash: &H256) -> Vec<u8> {
     append_suffix(hash, EPOCH_CONSENSUS_EXECUTION_INFO_SUFFIX_BYTE)
 }
 
+fn space_key_byte(space: Space) -> u8 {
+    match space {
+        Space::Native => 1,
+        Space::Ethereum => 2,
+    }
+}
+
+/// The keys of different kinds of records in `DBTable::AddressTransactions`
+/// have different lengths, so they never collide: 9 bytes for epoch records,
+/// 21 bytes for address ranges and 29 bytes for entries.
+fn address_transaction_range_key(address: &AddressWithSpace) -> Vec<u8> {
+    let mut key = Vec::with_capacity(21);
+    key.push(space_key_byte(address.space));
+    key.extend_from_slice(address.address.as_bytes());
+    key
+}
+
+fn address_transaction_key(key: &AddressTransactionKey) -> Vec<u8> {
+    let mut db_key = address_transaction_range_key(&key.address_with_space());
+    db_key.extend_from_slice(&key.seq.to_be_bytes());
+    db_key
+}
+
+fn epoch_address_transactions_key(epoch_number: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(9);
+    key.push(EPOCH_ADDRESS_TRANSACTIONS_PREFIX_BYTE);
+    key.extend_from_slice(&epoch_number.to_be_bytes());
+    key
+}
+
+fn log_bloom_key(space: Space, level: u32, group: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(10);
+    key.push(space_key_byte(space));
+    key.push(level as u8);
+    key.extend_from_slice(&group.to_be_bytes());
+    key
+}
+
+fn log_bloom_index_range_key(space: Space) -> Vec<u8> {
+    vec![space_key_byte(space), LOG_BLOOM_INDEX_RANGE_SUFFIX_BYTE]
+}
+
 impl MallocSizeOf for DBManager {
     fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
         // Here we only handle the case that all columns are stored within the
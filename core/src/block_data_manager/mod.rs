@@ -5,7 +5,9 @@
 use crate::{
     cache_config::CacheConfig,
     cache_manager::{CacheId, CacheManager, CacheSize},
-    consensus::consensus_inner::consensus_executor::RewardExecutionInfo,
+    consensus::consensus_inner::consensus_executor::{
+        trace_participants, RewardExecutionInfo,
+    },
     ext_db::SystemDB,
     pow::{PowComputer, TargetDifficultyManager},
 };
@@ -13,15 +15,15 @@ use cfx_storage::{
     state_manager::StateIndex, utils::guarded_value::*, StorageManager,
     StorageManagerTrait, StorageStateTrait,
 };
-use cfx_types::{Bloom, Space, H256};
+use cfx_types::{AddressSpaceUtil, AddressWithSpace, Bloom, Space, H256};
 use malloc_size_of::{new_malloc_size_ops, MallocSizeOf, MallocSizeOfOps};
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockUpgradableReadGuard};
 use primitives::{
     block::CompactBlock,
     receipt::{BlockReceipts, TransactionOutcome},
-    Block, BlockHeader, EpochId, Receipt, SignedTransaction, TransactionIndex,
-    TransactionWithSignature, NULL_EPOCH,
+    Action, Block, BlockHeader, EpochId, Receipt, SignedTransaction,
+    TransactionIndex, TransactionWithSignature, NULL_EPOCH,
 };
 use rlp::DecoderError;
 use std::{
@@ -30,12 +32,19 @@ use std::{
 };
 use threadpool::ThreadPool;
 pub mod block_data_types;
+pub mod db_checker;
 pub mod db_gc_manager;
 pub mod db_manager;
+pub mod trusted_checkpoint;
 pub mod tx_data_manager;
+
+#[cfg(test)]
+mod tests;
+
 use crate::{
     block_data_manager::{
-        db_manager::DBManager, tx_data_manager::TransactionDataManager,
+        db_manager::DBManager, trusted_checkpoint::TrustedCheckpoint,
+        tx_data_manager::TransactionDataManager,
     },
     consensus::pos_handler::PosVerifier,
     executive::internal_contract::{
@@ -47,10 +56,16 @@ pub use block_data_types::*;
 use cfx_internal_common::{
     EpochExecutionCommitment, StateAvailabilityBoundary, StateRootWithAuxInfo,
 };
+use cfx_parameters::consensus::DEFERRED_STATE_EPOCH_COUNT;
 use db_gc_manager::GCProgress;
 use metrics::{register_meter_with_group, Meter, MeterTimer};
 use primitives::pos::PosBlockId;
-use std::{hash::Hash, path::Path, time::Duration};
+use std::{
+    cmp::{max, min},
+    hash::Hash,
+    path::Path,
+    time::Duration,
+};
 
 lazy_static! {
     static ref TX_POOL_RECOVER_TIMER: Arc<dyn Meter> =
@@ -127,6 +142,11 @@ pub struct BlockDataManager {
     cur_consensus_era_genesis_hash: RwLock<H256>,
     cur_consensus_era_stable_hash: RwLock<H256>,
     instance_id: Mutex<u64>,
+    /// Serializes the read-modify-write of the address transaction index
+    /// between epoch execution and database GC.
+    address_tx_index_lock: Mutex<()>,
+    /// Serializes the read-modify-write of the aggregated log blooms.
+    log_bloom_index_lock: Mutex<()>,
 
     config: DataManagerConfiguration,
 
@@ -217,6 +237,8 @@ impl BlockDataManager {
             storage_manager,
             cache_man,
             instance_id: Mutex::new(0),
+            address_tx_index_lock: Mutex::new(()),
+            log_bloom_index_lock: Mutex::new(()),
             config,
             target_difficulty_manager: TargetDifficultyManager::new(
                 cache_conf.target_difficulties_cache_size_in_count,
@@ -236,6 +258,17 @@ impl BlockDataManager {
 
         data_man.initialize_instance_id();
 
+        if let Some(trusted_checkpoint) = &data_man.config.trusted_checkpoint {
+            if data_man.db_manager.checkpoint_hashes_from_db().is_none() {
+                info!(
+                    "Start from trusted checkpoint {:?} at height {}",
+                    trusted_checkpoint.era_genesis_hash(),
+                    trusted_checkpoint.height()
+                );
+                data_man.insert_trusted_checkpoint(trusted_checkpoint);
+            }
+        }
+
         let cur_era_genesis_hash =
             match data_man.db_manager.checkpoint_hashes_from_db() {
                 None => true_genesis.hash(),
@@ -326,6 +359,165 @@ impl BlockDataManager {
         data_man
     }
 
+    /// Persist the era genesis of a trusted checkpoint as the current
+    /// checkpoint, so that it is recovered in the same way as a checkpoint
+    /// made by consensus.
+    fn insert_trusted_checkpoint(&self, checkpoint: &TrustedCheckpoint) {
+        let era_genesis_hash = checkpoint.era_genesis_hash();
+        let height = checkpoint.height();
+        // Only write the header to db, so that its pow quality is filled when
+        // it is loaded.
+        self.db_manager
+            .insert_block_header_to_db(&checkpoint.era_genesis_header);
+        self.insert_executed_epoch_set_hashes_to_db(
+            height,
+            &checkpoint.epoch_block_hashes,
+        );
+        self.insert_skipped_epoch_set_hashes_to_db(height, &vec![]);
+        // The pivot chain before the era genesis is only used to sync its
+        // snapshot. The blocks are ordered before the era genesis, so that
+        // they are treated as processed instead of being inserted into the
+        // graph.
+        let pivot_block_count = checkpoint.pivot_block_headers.len() as u64;
+        for (i, header) in checkpoint.pivot_block_headers.iter().enumerate() {
+            self.db_manager.insert_block_header_to_db(header);
+            self.db_manager.insert_local_block_info_to_db(
+                &header.hash(),
+                &LocalBlockInfo::new(
+                    BlockStatus::Valid,
+                    pivot_block_count - 1 - i as u64,
+                    self.get_instance_id(),
+                ),
+            );
+        }
+        for (i, epoch_block_hashes) in
+            checkpoint.pivot_epoch_block_hashes.iter().enumerate()
+        {
+            self.insert_executed_epoch_set_hashes_to_db(
+                height - 1 - i as u64,
+                epoch_block_hashes,
+            );
+        }
+        self.insert_epoch_execution_context(
+            era_genesis_hash,
+            EpochExecutionContext {
+                start_block_number: checkpoint.start_block_number,
+            },
+            true, /* persistent */
+        );
+        self.db_manager.insert_local_block_info_to_db(
+            &era_genesis_hash,
+            &LocalBlockInfo::new(
+                BlockStatus::Valid,
+                pivot_block_count,
+                self.get_instance_id(),
+            ),
+        );
+        self.db_manager.insert_checkpoint_hashes_to_db(
+            &era_genesis_hash,
+            &era_genesis_hash,
+        );
+    }
+
+    pub fn trusted_checkpoint(&self) -> Option<&TrustedCheckpoint> {
+        self.config.trusted_checkpoint.as_ref()
+    }
+
+    /// Check that the pivot chain ending at `epoch` goes through the trusted
+    /// checkpoint and commits its state root. The state root can only be
+    /// compared if the pivot block committing it is not blamed.
+    pub fn verify_trusted_checkpoint(
+        &self, epoch: &H256,
+    ) -> Result<(), String> {
+        let checkpoint = match &self.config.trusted_checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+        if *epoch == checkpoint.era_genesis_hash() {
+            // The headers committing its state root are verified when they
+            // are inserted.
+            return Ok(());
+        }
+        let deferred_height = checkpoint.height() + DEFERRED_STATE_EPOCH_COUNT;
+        let mut header = self
+            .block_header_by_hash(epoch)
+            .ok_or_else(|| format!("Missing header of epoch {:?}", epoch))?;
+        let mut deferred_state_root = None;
+        while header.height() > checkpoint.height() {
+            if header.height() == deferred_height && header.blame() == 0 {
+                deferred_state_root = Some(*header.deferred_state_root());
+            }
+            let parent_hash = *header.parent_hash();
+            header = self
+                .block_header_by_hash(&parent_hash)
+                .ok_or_else(|| format!("Missing header {:?}", parent_hash))?;
+        }
+        if header.hash() != checkpoint.era_genesis_hash() {
+            return Err(format!(
+                "Pivot chain goes through {:?} at height {} instead of the \
+                 trusted checkpoint {:?}",
+                header.hash(),
+                header.height(),
+                checkpoint.era_genesis_hash()
+            ));
+        }
+        match deferred_state_root {
+            Some(state_root) if state_root != checkpoint.state_root => {
+                Err(format!(
+                    "Pivot chain commits state root {:?} for the trusted \
+                     checkpoint instead of {:?}",
+                    state_root, checkpoint.state_root
+                ))
+            }
+            Some(_) => Ok(()),
+            None => {
+                warn!(
+                    "Cannot verify the state root of the trusted checkpoint \
+                     because the pivot block at height {} is not available \
+                     or blamed",
+                    deferred_height
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Check a header received from peers before it is inserted. A header
+    /// committing the state of the trusted checkpoint without blame is
+    /// invalid if it commits another state root. The header is not checked if
+    /// its ancestors are not available yet.
+    pub fn verify_header_with_trusted_checkpoint(
+        &self, header: &BlockHeader,
+    ) -> Result<(), String> {
+        let checkpoint = match &self.config.trusted_checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+        if header.height() != checkpoint.height() + DEFERRED_STATE_EPOCH_COUNT
+            || header.blame() != 0
+            || *header.deferred_state_root() == checkpoint.state_root
+        {
+            return Ok(());
+        }
+        let mut ancestor_hash = *header.parent_hash();
+        for _ in 1..DEFERRED_STATE_EPOCH_COUNT {
+            match self.block_header_by_hash(&ancestor_hash) {
+                Some(ancestor) => ancestor_hash = *ancestor.parent_hash(),
+                None => return Ok(()),
+            }
+        }
+        if ancestor_hash != checkpoint.era_genesis_hash() {
+            return Ok(());
+        }
+        Err(format!(
+            "Block {:?} commits state root {:?} for the trusted checkpoint \
+             instead of {:?}",
+            header.hash(),
+            header.deferred_state_root(),
+            checkpoint.state_root
+        ))
+    }
+
     pub fn get_instance_id(&self) -> u64 { *self.instance_id.lock() }
 
     pub fn initialize_instance_id(&self) {
@@ -803,6 +995,300 @@ impl BlockDataManager {
         }
     }
 
+    pub fn address_tx_index_enabled(&self) -> bool {
+        self.config.persist_address_tx_index
+    }
+
+    pub fn address_tx_index_internal_enabled(&self) -> bool {
+        self.config.persist_address_tx_index
+            && self.config.address_tx_index_internal
+    }
+
+    /// Record the transactions executed in an epoch on the local pivot chain
+    /// in the address transaction index. `transactions` are in the execution
+    /// order, and the roles of the same address in the same transaction are
+    /// merged. If the epoch has been indexed before, e.g., the pivot chain
+    /// has switched, the previous entries of the epoch are removed first.
+    pub fn insert_address_transactions(
+        &self, epoch_number: u64,
+        transactions: Vec<(AddressWithSpace, H256, u8)>,
+    ) {
+        if !self.config.persist_address_tx_index {
+            return;
+        }
+        let _lock = self.address_tx_index_lock.lock();
+        self.remove_epoch_address_transactions(
+            epoch_number,
+            false, /* is_gc */
+        );
+
+        let mut merged: Vec<(AddressWithSpace, AddressTransactionEntry)> =
+            Vec::with_capacity(transactions.len());
+        let mut positions = HashMap::new();
+        for (address, transaction_hash, roles) in transactions {
+            match positions.get(&(address, transaction_hash)) {
+                Some(&i) => merged[i].1.roles |= roles,
+                None => {
+                    positions.insert((address, transaction_hash), merged.len());
+                    merged.push((
+                        address,
+                        AddressTransactionEntry {
+                            transaction_hash,
+                            epoch_number,
+                            roles,
+                        },
+                    ));
+                }
+            }
+        }
+
+        let mut ranges = HashMap::new();
+        let mut keys = Vec::with_capacity(merged.len());
+        for (address, entry) in merged {
+            let range = ranges.entry(address).or_insert_with(|| {
+                self.db_manager
+                    .address_transaction_range_from_db(&address)
+                    .unwrap_or_default()
+            });
+            let key = AddressTransactionKey {
+                address: address.address,
+                space: address.space,
+                seq: range.end,
+            };
+            range.end += 1;
+            self.db_manager
+                .insert_address_transaction_to_db(&key, &entry);
+            keys.push(key);
+        }
+        for (address, range) in ranges {
+            self.db_manager
+                .insert_address_transaction_range_to_db(&address, &range);
+        }
+        self.db_manager
+            .insert_epoch_address_transactions_to_db(epoch_number, &keys);
+    }
+
+    /// Return at most `limit` entries of `address` with `seq < before` from
+    /// the newest to the oldest, together with their `seq`.
+    pub fn address_transactions(
+        &self, address: &AddressWithSpace, before: Option<u64>, limit: usize,
+    ) -> Vec<(u64, AddressTransactionEntry)> {
+        let range =
+            match self.db_manager.address_transaction_range_from_db(address) {
+                Some(range) => range,
+                None => return vec![],
+            };
+        let end = before.map_or(range.end, |b| min(b, range.end));
+        self.db_manager.address_transactions_from_db(
+            address,
+            range.start,
+            end,
+            limit,
+        )
+    }
+
+    /// Remove the address index entries written by an epoch. In GC, the
+    /// ranges of the addresses are also shrunk because all older entries
+    /// have been removed.
+    fn remove_epoch_address_transactions(
+        &self, epoch_number: u64, is_gc: bool,
+    ) {
+        let keys = match self
+            .db_manager
+            .epoch_address_transactions_from_db(epoch_number)
+        {
+            Some(keys) => keys,
+            None => return,
+        };
+        let mut ranges = HashMap::new();
+        for key in &keys {
+            self.db_manager.remove_address_transaction_from_db(key);
+            if is_gc {
+                let address = key.address_with_space();
+                let range = ranges.entry(address).or_insert_with(|| {
+                    self.db_manager
+                        .address_transaction_range_from_db(&address)
+                        .unwrap_or_default()
+                });
+                range.start = max(range.start, key.seq + 1);
+            }
+        }
+        for (address, range) in ranges {
+            self.db_manager
+                .insert_address_transaction_range_to_db(&address, &range);
+        }
+        self.db_manager
+            .remove_epoch_address_transactions_from_db(epoch_number);
+    }
+
+    pub fn log_bloom_index_enabled(&self) -> bool {
+        self.config.persist_log_bloom_index
+    }
+
+    /// Add the log blooms of an epoch executed on the local pivot chain to the
+    /// log bloom index. The blooms are accrued rather than replaced, so if the
+    /// epoch is executed again after a pivot chain switch, the index covers
+    /// the logs of both executions. This only causes false positives.
+    pub fn insert_epoch_log_blooms(
+        &self, epoch_number: u64, epoch_receipts: &[Arc<BlockReceipts>],
+    ) {
+        if !self.config.persist_log_bloom_index {
+            return;
+        }
+        let mut native_bloom = Bloom::zero();
+        let mut evm_bloom = Bloom::zero();
+        for block_receipts in epoch_receipts {
+            for receipt in &block_receipts.receipts {
+                for log in &receipt.logs {
+                    let bloom = match log.space {
+                        Space::Native => &mut native_bloom,
+                        Space::Ethereum => &mut evm_bloom,
+                    };
+                    bloom.accrue_bloom(&log.bloom());
+                }
+            }
+        }
+
+        let _lock = self.log_bloom_index_lock.lock();
+        for (space, bloom) in
+            vec![(Space::Native, native_bloom), (Space::Ethereum, evm_bloom)]
+        {
+            if !bloom.is_zero() {
+                for level in 0..LOG_BLOOM_INDEX_LEVELS {
+                    let group =
+                        epoch_number >> (level * LOG_BLOOM_INDEX_LEVEL_BITS);
+                    let mut aggregated = self
+                        .db_manager
+                        .log_bloom_from_db(space, level, group)
+                        .unwrap_or_default();
+                    // A group always contains the blooms of its subgroups, so
+                    // the upper levels already contain `bloom`.
+                    if aggregated.contains_bloom(&bloom) {
+                        break;
+                    }
+                    aggregated.accrue_bloom(&bloom);
+                    self.db_manager.insert_log_bloom_to_db(
+                        space,
+                        level,
+                        group,
+                        &aggregated,
+                    );
+                }
+            }
+
+            let old_range =
+                self.db_manager.log_bloom_index_range_from_db(space);
+            let new_range = match old_range {
+                Some(range)
+                    if epoch_number + 1 >= range.start
+                        && epoch_number <= range.end + 1 =>
+                {
+                    LogBloomIndexRange {
+                        start: min(range.start, epoch_number),
+                        end: max(range.end, epoch_number),
+                    }
+                }
+                // The index is new, or some epochs were executed without
+                // being indexed, so only this epoch is covered.
+                _ => LogBloomIndexRange {
+                    start: epoch_number,
+                    end: epoch_number,
+                },
+            };
+            if old_range != Some(new_range) {
+                self.db_manager
+                    .insert_log_bloom_index_range_to_db(space, &new_range);
+            }
+        }
+    }
+
+    /// Remove the log blooms of a garbage collected epoch, and move the start
+    /// of the index range after it, so the epoch is treated as not indexed
+    /// instead of having no logs.
+    fn prune_log_bloom_index(&self, epoch_number: u64) {
+        let _lock = self.log_bloom_index_lock.lock();
+        for space in &[Space::Native, Space::Ethereum] {
+            self.db_manager.remove_log_bloom_from_db(
+                *space,
+                0, /* level */
+                epoch_number,
+            );
+            if let Some(range) =
+                self.db_manager.log_bloom_index_range_from_db(*space)
+            {
+                if range.start <= epoch_number {
+                    self.db_manager.insert_log_bloom_index_range_to_db(
+                        *space,
+                        &LogBloomIndexRange {
+                            start: epoch_number + 1,
+                            end: max(range.end, epoch_number),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Find the epochs in `[from_epoch, to_epoch]` whose logs in `space` may
+    /// match any of `blooms`, from the newest to the oldest. At most
+    /// `max_count` epochs are returned. If the search stops early, the newest
+    /// epoch which is not searched yet is also returned. The epochs not
+    /// covered by the index are always returned.
+    pub fn log_bloom_candidate_epochs(
+        &self, space: Space, from_epoch: u64, to_epoch: u64, blooms: &[Bloom],
+        max_count: usize,
+    ) -> (Vec<u64>, Option<u64>)
+    {
+        let range = self.db_manager.log_bloom_index_range_from_db(space);
+        let mut group_matches = HashMap::new();
+        let mut candidates = Vec::new();
+        let mut epoch = to_epoch;
+        while epoch >= from_epoch {
+            if candidates.len() >= max_count {
+                return (candidates, Some(epoch));
+            }
+            // Find the largest group containing `epoch` without matching
+            // logs, and skip the epochs of the group not newer than `epoch`.
+            let skip_to = match range {
+                Some(range) if epoch >= range.start && epoch <= range.end => {
+                    (0..LOG_BLOOM_INDEX_LEVELS).rev().find_map(|level| {
+                        let shift = level * LOG_BLOOM_INDEX_LEVEL_BITS;
+                        let group = epoch >> shift;
+                        let matches = *group_matches
+                            .entry((level, group))
+                            .or_insert_with(|| {
+                                self.db_manager
+                                    .log_bloom_from_db(space, level, group)
+                                    .map_or(false, |aggregated| {
+                                        blooms.iter().any(|bloom| {
+                                            aggregated.contains_bloom(bloom)
+                                        })
+                                    })
+                            });
+                        if matches {
+                            None
+                        } else {
+                            Some(max(group << shift, range.start))
+                        }
+                    })
+                }
+                _ => None,
+            };
+            let lowest = match skip_to {
+                Some(lowest) => lowest,
+                None => {
+                    candidates.push(epoch);
+                    epoch
+                }
+            };
+            if lowest == 0 {
+                break;
+            }
+            epoch = lowest - 1;
+        }
+        (candidates, None)
+    }
+
     pub fn hash_by_block_number(
         &self, block_number: u64, update_cache: bool,
     ) -> Option<H256> {
@@ -1236,6 +1722,7 @@ impl BlockDataManager {
         if on_local_pivot {
             // Check if all blocks receipts and traces are from this epoch
             let mut epoch_receipts = Vec::new();
+            let mut epoch_traces = Vec::new();
             let mut epoch_staking_events = Vec::new();
             for h in epoch_block_hashes {
                 if let Some(r) = self.block_execution_result_by_hash_with_epoch(
@@ -1248,19 +1735,23 @@ impl BlockDataManager {
                 }
                 if update_trace {
                     // Update block traces in db if needed.
-                    if self
-                        .block_traces_by_hash_with_epoch(
-                            h, epoch_hash,
-                            true, /* update_pivot_assumption */
-                            true, /* update_cache */
-                        )
-                        .is_none()
-                    {
-                        return false;
+                    match self.block_traces_by_hash_with_epoch(
+                        h, epoch_hash, true, /* update_pivot_assumption */
+                        true, /* update_cache */
+                    ) {
+                        Some(traces) => epoch_traces.push(traces),
+                        None => return false,
                     }
                 }
             }
 
+            // The address index entries of this epoch may have been replaced
+            // by another pivot chain, so they are recovered as well.
+            let index_addresses = self.config.persist_address_tx_index;
+            let index_internal_addresses =
+                update_trace && self.address_tx_index_internal_enabled();
+            let mut address_transactions = Vec::new();
+
             let mut evm_tx_index = 0;
 
             // Recover tx address if we will skip pivot chain execution
@@ -1331,6 +1822,35 @@ impl BlockDataManager {
                                     epoch_staking_events.push(event);
                                 }
                             }
+
+                            if index_addresses {
+                                address_transactions.push((
+                                    tx.sender(),
+                                    tx.hash(),
+                                    ADDRESS_ROLE_SENDER,
+                                ));
+                                if let Action::Call(to) = tx.action() {
+                                    address_transactions.push((
+                                        to.with_space(tx.space()),
+                                        tx.hash(),
+                                        ADDRESS_ROLE_RECIPIENT,
+                                    ));
+                                }
+                                if let Some(traces) = epoch_traces
+                                    .get(block_idx)
+                                    .and_then(|b| b.0.get(tx_idx))
+                                    .filter(|_| index_internal_addresses)
+                                {
+                                    for address in trace_participants(&traces.0)
+                                    {
+                                        address_transactions.push((
+                                            address,
+                                            tx.hash(),
+                                            ADDRESS_ROLE_INTERNAL,
+                                        ));
+                                    }
+                                }
+                            }
                         }
                         _ => {}
                     }
@@ -1350,6 +1870,13 @@ impl BlockDataManager {
                 }
             }
             let me_height = self.block_height_by_hash(epoch_hash).unwrap();
+            if index_addresses {
+                self.insert_address_transactions(
+                    me_height,
+                    address_transactions,
+                );
+            }
+            self.insert_epoch_log_blooms(me_height, &epoch_receipts);
             if pos_verifier.pos_option().is_some() && me_height != 0 {
                 trace!(
                     "staking events update: height={}, new={}",
@@ -1572,6 +2099,23 @@ impl BlockDataManager {
             .find_missing_tx_indices_encoded(compact_block)
     }
 
+    /// Return `true` if the states of the pivot epochs below the state
+    /// availability lower bound are served, either from the kept states or
+    /// by re-execution.
+    pub fn archive_historical_state(&self) -> bool {
+        self.storage_manager
+            .get_storage_manager()
+            .archive_historical_state()
+    }
+
+    /// The number of epochs between the historical states kept in archive
+    /// mode.
+    pub fn archive_state_epoch_interval(&self) -> u64 {
+        self.storage_manager
+            .get_storage_manager()
+            .archive_state_epoch_interval()
+    }
+
     /// Caller should make sure the state exists.
     pub fn get_state_readonly_index(
         &self, block_hash: &EpochId,
@@ -1714,6 +2258,13 @@ impl BlockDataManager {
                                 }
                             }
                         }
+                        if self.config.persist_address_tx_index {
+                            let _lock = self.address_tx_index_lock.lock();
+                            self.remove_epoch_address_transactions(
+                                epoch_to_remove,
+                                true, /* is_gc */
+                            );
+                        }
                         let epoch_block_set: HashSet<H256> =
                             epoch_blocks.into_iter().collect();
                         for tx in transaction_set {
@@ -1753,6 +2304,20 @@ impl BlockDataManager {
                 .additional_maintained_execution_result_epoch_count,
             |h| self.remove_block_result(h, true /* remove_db */),
         );
+        // The per-epoch log blooms are useless without the receipts. The
+        // blooms of the upper levels are small and kept.
+        if self.config.persist_log_bloom_index {
+            if let Some(defer_epochs) = self
+                .config
+                .additional_maintained_execution_result_epoch_count
+            {
+                if base_epoch > defer_epochs as u64 {
+                    self.prune_log_bloom_index(
+                        base_epoch - defer_epochs as u64,
+                    );
+                }
+            }
+        }
         self.gc_epoch_with_defer(
             base_epoch,
             self.config.additional_maintained_reward_epoch_count,
@@ -1805,6 +2370,16 @@ pub struct DataManagerConfiguration {
     pub additional_maintained_transaction_index_epoch_count: Option<usize>,
     pub checkpoint_gc_time_in_epoch_count: usize,
     pub strict_tx_index_gc: bool,
+    /// Maintain the index from addresses to their transactions. It is
+    /// garbage collected together with the transaction index.
+    pub persist_address_tx_index: bool,
+    /// Also index the addresses that only appear in the execution traces.
+    pub address_tx_index_internal: bool,
+    /// Maintain the multi-level log bloom index to speed up log filtering.
+    pub persist_log_bloom_index: bool,
+    /// Start from this checkpoint instead of the true genesis if the
+    /// database is empty.
+    pub trusted_checkpoint: Option<TrustedCheckpoint>,
 }
 
 impl MallocSizeOf for DataManagerConfiguration {
@@ -1829,6 +2404,10 @@ impl DataManagerConfiguration {
             additional_maintained_transaction_index_epoch_count: None,
             checkpoint_gc_time_in_epoch_count: 1,
             strict_tx_index_gc: true,
+            persist_address_tx_index: false,
+            address_tx_index_internal: false,
+            persist_log_bloom_index: false,
+            trusted_checkpoint: None,
         }
     }
 }
@@ -0,0 +1,846 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use super::{
+    db_checker::{check_block_db, DbCheckReport, DbInconsistency},
+    db_manager::DBManager,
+    trusted_checkpoint::TrustedCheckpoint,
+    AddressTransactionEntry, AddressTransactionKey, BlockDataManager,
+    BlockExecutionResult, DataManagerConfiguration, DataVersionTuple, DbType,
+    ADDRESS_ROLE_RECIPIENT, ADDRESS_ROLE_SENDER,
+};
+use crate::{
+    pow::PowComputer,
+    sync::utils::initialize_data_manager_with_config,
+    verification::{compute_receipts_root, compute_transaction_root},
+    vm_factory::VmFactory,
+};
+use cfx_internal_common::{EpochExecutionCommitment, StateRootWithAuxInfo};
+use cfx_types::{
+    Address, AddressSpaceUtil, AddressWithSpace, Bloom, Space, H256,
+};
+use primitives::{
+    Block, BlockHeader, BlockHeaderBuilder, BlockReceipts, LogEntry,
+    NativeTransaction, Receipt, TransactionOutcome, MERKLE_NULL_NODE,
+};
+use rustc_hex::ToHex;
+use std::{fs, sync::Arc, time::Duration};
+use tempdir::TempDir;
+
+fn new_data_manager(
+    db_dir: &TempDir, db_type: DbType,
+    config_fn: impl FnOnce(&mut DataManagerConfiguration),
+) -> Arc<BlockDataManager>
+{
+    let mut config = DataManagerConfiguration::new(
+        false, /* persist_tx_index */
+        false, /* persist_block_number_index */
+        Duration::from_millis(300_000),
+        db_type,
+    );
+    config_fn(&mut config);
+    initialize_data_manager_with_config(
+        db_dir.path().to_str().unwrap(),
+        config,
+        Arc::new(PowComputer::new(true)),
+        VmFactory::new(1024 * 32),
+    )
+    .0
+}
+
+fn address_transaction_hashes(
+    data_man: &BlockDataManager, address: &AddressWithSpace,
+    before: Option<u64>, limit: usize,
+) -> Vec<(u64, H256)>
+{
+    data_man
+        .address_transactions(address, before, limit)
+        .into_iter()
+        .map(|(seq, entry)| (seq, entry.transaction_hash))
+        .collect()
+}
+
+#[test]
+fn test_address_transactions() {
+    let db_dir = TempDir::new("address_tx_index").unwrap();
+    let data_man = new_data_manager(&db_dir, DbType::Rocksdb, |config| {
+        config.persist_address_tx_index = true
+    });
+    let a = Address::random().with_evm_space();
+    let b = Address::random().with_evm_space();
+    let h: Vec<H256> = (0..5).map(|_| H256::random()).collect();
+
+    // The roles of the same address in the same transaction are merged.
+    data_man.insert_address_transactions(
+        1,
+        vec![
+            (a, h[0], ADDRESS_ROLE_SENDER),
+            (b, h[0], ADDRESS_ROLE_RECIPIENT),
+            (a, h[0], ADDRESS_ROLE_RECIPIENT),
+            (a, h[1], ADDRESS_ROLE_SENDER),
+        ],
+    );
+    data_man
+        .insert_address_transactions(2, vec![(a, h[2], ADDRESS_ROLE_SENDER)]);
+    let entries = data_man.address_transactions(&a, None, 10);
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        entries[2].1.roles,
+        ADDRESS_ROLE_SENDER | ADDRESS_ROLE_RECIPIENT
+    );
+    assert_eq!(entries[0].1.epoch_number, 2);
+    assert_eq!(
+        address_transaction_hashes(&data_man, &b, None, 10),
+        vec![(0, h[0])]
+    );
+    assert!(address_transaction_hashes(
+        &data_man,
+        &Address::random().with_evm_space(),
+        None,
+        10
+    )
+    .is_empty());
+
+    // Pagination from the newest to the oldest.
+    assert_eq!(
+        address_transaction_hashes(&data_man, &a, None, 2),
+        vec![(2, h[2]), (1, h[1])]
+    );
+    assert_eq!(
+        address_transaction_hashes(&data_man, &a, Some(1), 2),
+        vec![(0, h[0])]
+    );
+    assert!(address_transaction_hashes(&data_man, &a, Some(0), 2).is_empty());
+    assert!(address_transaction_hashes(&data_man, &a, None, 0).is_empty());
+
+    // A re-executed epoch replaces its entries and leaves a hole.
+    data_man.insert_address_transactions(
+        2,
+        vec![(a, h[3], ADDRESS_ROLE_SENDER), (b, h[4], ADDRESS_ROLE_SENDER)],
+    );
+    assert_eq!(
+        address_transaction_hashes(&data_man, &a, None, 2),
+        vec![(3, h[3]), (1, h[1])]
+    );
+    assert_eq!(
+        address_transaction_hashes(&data_man, &a, Some(3), 10),
+        vec![(1, h[1]), (0, h[0])]
+    );
+    assert_eq!(
+        address_transaction_hashes(&data_man, &b, None, 10),
+        vec![(1, h[4]), (0, h[0])]
+    );
+}
+
+#[test]
+fn test_address_transactions_from_sqlite() {
+    let db_dir = TempDir::new("address_tx_index_sqlite").unwrap();
+    let db_manager = DBManager::new_from_sqlite(
+        db_dir.path(),
+        Arc::new(PowComputer::new(true)),
+    );
+    let address = Address::random().with_native_space();
+    let hashes: Vec<H256> = (0..4).map(|_| H256::random()).collect();
+    for (seq, hash) in hashes.iter().enumerate() {
+        // Leave a hole at seq 1 like a re-executed epoch.
+        if seq == 1 {
+            continue;
+        }
+        db_manager.insert_address_transaction_to_db(
+            &AddressTransactionKey {
+                address: address.address,
+                space: address.space,
+                seq: seq as u64,
+            },
+            &AddressTransactionEntry {
+                transaction_hash: *hash,
+                epoch_number: 1,
+                roles: ADDRESS_ROLE_SENDER,
+            },
+        );
+    }
+    let seqs = |start, end, limit| {
+        db_manager
+            .address_transactions_from_db(&address, start, end, limit)
+            .into_iter()
+            .map(|(seq, _)| seq)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(seqs(0, 4, 10), vec![3, 2, 0]);
+    assert_eq!(seqs(0, 4, 2), vec![3, 2]);
+    assert_eq!(seqs(1, 3, 10), vec![2]);
+    assert!(seqs(2, 2, 10).is_empty());
+}
+
+fn evm_log(address: Address) -> LogEntry {
+    LogEntry {
+        address,
+        topics: vec![],
+        data: vec![],
+        space: Space::Ethereum,
+    }
+}
+
+fn epoch_receipts(logs: Vec<LogEntry>) -> Vec<Arc<BlockReceipts>> {
+    let log_bloom = logs.iter().fold(Bloom::zero(), |mut bloom, log| {
+        bloom.accrue_bloom(&log.bloom());
+        bloom
+    });
+    let receipt = Receipt::new(
+        TransactionOutcome::Success,
+        0.into(),
+        0.into(),
+        false, /* gas_sponsor_paid */
+        logs,
+        log_bloom,
+        false, /* storage_sponsor_paid */
+        vec![],
+        vec![],
+    );
+    vec![Arc::new(BlockReceipts {
+        receipts: vec![receipt],
+        block_number: 0,
+        secondary_reward: 0.into(),
+        tx_execution_error_messages: vec![String::new()],
+    })]
+}
+
+#[test]
+fn test_log_bloom_candidate_epochs() {
+    let db_dir = TempDir::new("log_bloom_index").unwrap();
+    let data_man = new_data_manager(&db_dir, DbType::Rocksdb, |config| {
+        config.persist_log_bloom_index = true
+    });
+    let address = Address::random();
+    // The epochs at the boundaries of the groups of each level. The top level
+    // group of 4096 epochs starting from 4096 is partially filled.
+    let log_epochs = [15, 16, 255, 256, 4095, 4100];
+    let last_indexed = 4200;
+    for epoch in 0..=last_indexed {
+        let logs = if log_epochs.contains(&epoch) {
+            vec![evm_log(address)]
+        } else {
+            vec![]
+        };
+        data_man.insert_epoch_log_blooms(epoch, &epoch_receipts(logs));
+    }
+    let blooms = vec![evm_log(address).bloom()];
+    let candidates = |from, to, max_count| {
+        data_man.log_bloom_candidate_epochs(
+            Space::Ethereum,
+            from,
+            to,
+            &blooms,
+            max_count,
+        )
+    };
+
+    assert_eq!(
+        candidates(0, last_indexed, 100),
+        (vec![4100, 4095, 256, 255, 16, 15], None)
+    );
+    assert_eq!(candidates(17, 4094, 100), (vec![256, 255], None));
+    assert_eq!(candidates(4096, 4099, 100), (vec![], None));
+    assert_eq!(candidates(0, last_indexed, 2), (vec![4100, 4095], Some(4094)));
+    assert_eq!(
+        data_man.log_bloom_candidate_epochs(
+            Space::Native,
+            0,
+            last_indexed,
+            &blooms,
+            100,
+        ),
+        (vec![], None)
+    );
+    assert_eq!(
+        data_man.log_bloom_candidate_epochs(
+            Space::Ethereum,
+            0,
+            last_indexed,
+            &vec![evm_log(Address::random()).bloom()],
+            100,
+        ),
+        (vec![], None)
+    );
+
+    // The epochs not covered by the index are always candidates.
+    let (epochs, rest) = candidates(0, last_indexed + 3, 100);
+    assert_eq!(rest, None);
+    assert_eq!(
+        epochs,
+        vec![last_indexed + 3, last_indexed + 2, last_indexed + 1, 4100, 4095]
+            .into_iter()
+            .chain(vec![256, 255, 16, 15])
+            .collect::<Vec<_>>()
+    );
+
+    // Pruned epochs are no longer covered by the index, rather than being
+    // skipped as epochs without logs.
+    for epoch in 0..=15 {
+        data_man.prune_log_bloom_index(epoch);
+    }
+    let (epochs, rest) = candidates(0, 20, 100);
+    assert_eq!(rest, None);
+    assert_eq!(
+        epochs,
+        vec![16].into_iter().chain((0..=15).rev()).collect::<Vec<_>>()
+    );
+}
+
+/// Insert an executed epoch of `block_count` blocks with consistent execution
+/// results, and return the block hashes with the pivot block last.
+fn insert_checked_epoch(
+    db_manager: &DBManager, epoch: u64, block_count: usize,
+) -> Vec<H256>
+{
+    let headers: Vec<BlockHeader> = (0..block_count)
+        .map(|_| {
+            BlockHeaderBuilder::new()
+                .with_parent_hash(H256::random())
+                .with_height(epoch)
+                .with_transactions_root(compute_transaction_root(&vec![]))
+                .build()
+        })
+        .collect();
+    let epoch_hashes: Vec<H256> = headers.iter().map(|h| h.hash()).collect();
+    let pivot_hash = *epoch_hashes.last().unwrap();
+    let mut all_receipts = Vec::new();
+    for header in headers {
+        db_manager.insert_block_header_to_db(&header);
+        db_manager.insert_block_body_to_db(&Block::new(header.clone(), vec![]));
+        let block_receipts =
+            epoch_receipts(vec![evm_log(Address::random())]).remove(0);
+        db_manager.insert_block_execution_result_to_db(
+            &header.hash(),
+            &DataVersionTuple(
+                pivot_hash,
+                BlockExecutionResult {
+                    bloom: block_receipts.receipts[0].log_bloom,
+                    block_receipts: block_receipts.clone(),
+                },
+            ),
+        );
+        all_receipts.push(block_receipts);
+    }
+    db_manager.insert_epoch_execution_commitment_to_db(
+        &pivot_hash,
+        &EpochExecutionCommitment {
+            state_root_with_aux_info: StateRootWithAuxInfo::genesis(
+                &MERKLE_NULL_NODE,
+            ),
+            receipts_root: compute_receipts_root(&all_receipts),
+            logs_bloom_hash: BlockHeaderBuilder::compute_block_logs_bloom_hash(
+                &all_receipts,
+            ),
+        },
+    );
+    db_manager.insert_executed_epoch_set_hashes_to_db(epoch, &epoch_hashes);
+    epoch_hashes
+}
+
+#[test]
+fn test_check_block_db_healthy() {
+    let db_dir = TempDir::new("check_block_db_healthy").unwrap();
+    let data_man = new_data_manager(&db_dir, DbType::Rocksdb, |_| {});
+    let db_manager = &data_man.db_manager;
+    let mut epoch_hashes = vec![vec![]];
+    for epoch in 1..=6 {
+        epoch_hashes.push(insert_checked_epoch(db_manager, epoch, 2));
+    }
+    // Not executed yet.
+    db_manager.remove_epoch_execution_commitment_from_db(&epoch_hashes[6][1]);
+    for block_hash in &epoch_hashes[6] {
+        db_manager.remove_block_execution_result_from_db(block_hash);
+    }
+    // Block bodies and execution results garbage collected.
+    db_manager.remove_block_body_from_db(&epoch_hashes[1][0]);
+    for epoch in 1..=2 {
+        for block_hash in &epoch_hashes[epoch] {
+            db_manager.remove_block_execution_result_from_db(block_hash);
+        }
+    }
+    db_manager.insert_gc_progress_to_db(5);
+
+    let report = check_block_db(db_manager, Some(1), None, Some(2), false);
+    assert!(report.inconsistencies.is_empty(), "{:?}", report);
+    assert_eq!(report.checked_epochs, 6);
+    assert_eq!(report.checked_blocks, 12);
+    assert_eq!(report.unexecuted_epochs, 1);
+    assert_eq!(report.gc_execution_result_epochs, 2);
+    assert_eq!(report.missing_bodies, 1);
+
+    // The garbage collected execution results are missing if they are
+    // maintained.
+    let report = check_block_db(db_manager, Some(1), None, None, false);
+    assert_eq!(report.inconsistencies.len(), 4);
+    assert!(report.inconsistencies.iter().all(|inconsistency| matches!(
+        inconsistency,
+        DbInconsistency::MissingExecutionResult { .. }
+    )));
+}
+
+#[test]
+fn test_check_block_db_inconsistencies() {
+    let db_dir = TempDir::new("check_block_db_inconsistencies").unwrap();
+    let data_man = new_data_manager(&db_dir, DbType::Rocksdb, |_| {});
+    let db_manager = &data_man.db_manager;
+    let mut epoch_hashes = vec![vec![]];
+    for epoch in 1..=13 {
+        epoch_hashes.push(insert_checked_epoch(db_manager, epoch, 2));
+    }
+    let garbage = || vec![0xff, 0x00, 0x01];
+    let block = |epoch: usize| epoch_hashes[epoch][0];
+    let pivot = |epoch: usize| epoch_hashes[epoch][1];
+
+    db_manager.remove_block_header_from_db(&block(1));
+    db_manager.insert_raw_block_header_to_db(&block(2), garbage());
+    let other_header = BlockHeaderBuilder::new().with_height(3).build();
+    db_manager
+        .insert_raw_block_header_to_db(&block(3), rlp::encode(&other_header));
+    db_manager.insert_raw_block_body_to_db(&block(4), garbage());
+    let transaction = NativeTransaction::default()
+        .fake_sign(Address::random().with_native_space());
+    db_manager.insert_block_body_to_db(&Block::new(
+        db_manager.block_header_from_db(&block(5)).unwrap(),
+        vec![Arc::new(transaction)],
+    ));
+    db_manager.insert_raw_executed_epoch_set_hashes_to_db(6, garbage());
+    db_manager.remove_block_execution_result_from_db(&block(7));
+    db_manager.insert_raw_block_execution_result_to_db(&block(8), garbage());
+    let mut execution_result = db_manager
+        .checked_block_execution_result_from_db(&block(9))
+        .unwrap()
+        .unwrap();
+    execution_result.0 = H256::random();
+    db_manager
+        .insert_block_execution_result_to_db(&block(9), &execution_result);
+    let mut execution_result = db_manager
+        .checked_block_execution_result_from_db(&block(10))
+        .unwrap()
+        .unwrap();
+    execution_result.1.bloom = Bloom::zero();
+    db_manager
+        .insert_block_execution_result_to_db(&block(10), &execution_result);
+    db_manager
+        .insert_raw_epoch_execution_commitment_to_db(&pivot(11), garbage());
+    let mut commitment = db_manager
+        .epoch_execution_commitment_from_db(&pivot(12))
+        .unwrap();
+    commitment.receipts_root = H256::random();
+    db_manager.insert_epoch_execution_commitment_to_db(&pivot(12), &commitment);
+    let mut commitment = db_manager
+        .epoch_execution_commitment_from_db(&pivot(13))
+        .unwrap();
+    commitment.logs_bloom_hash = H256::random();
+    db_manager.insert_epoch_execution_commitment_to_db(&pivot(13), &commitment);
+
+    let check = |epoch| {
+        let report = check_block_db(
+            db_manager,
+            Some(epoch),
+            Some(epoch),
+            None,
+            false, /* repair */
+        );
+        assert_eq!(report.inconsistencies.len(), 1, "{:?}", report);
+        report.inconsistencies.into_iter().next().unwrap()
+    };
+    assert!(matches!(check(1), DbInconsistency::MissingHeader { .. }));
+    assert!(matches!(check(2), DbInconsistency::CorruptedHeader { .. }));
+    assert!(matches!(
+        check(3),
+        DbInconsistency::HeaderHashMismatch { .. }
+    ));
+    assert!(matches!(check(4), DbInconsistency::CorruptedBody { .. }));
+    assert!(matches!(
+        check(5),
+        DbInconsistency::TransactionsRootMismatch { .. }
+    ));
+    assert!(matches!(
+        check(6),
+        DbInconsistency::CorruptedEpochSet { .. }
+    ));
+    assert!(matches!(
+        check(7),
+        DbInconsistency::MissingExecutionResult { .. }
+    ));
+    assert!(matches!(
+        check(8),
+        DbInconsistency::CorruptedExecutionResult { .. }
+    ));
+    assert!(matches!(
+        check(9),
+        DbInconsistency::StaleExecutionResult { .. }
+    ));
+    assert!(matches!(
+        check(10),
+        DbInconsistency::BlockBloomMismatch { .. }
+    ));
+    assert!(matches!(
+        check(11),
+        DbInconsistency::CorruptedExecutionCommitment { .. }
+    ));
+    assert!(matches!(
+        check(12),
+        DbInconsistency::ReceiptsRootMismatch { .. }
+    ));
+    assert!(matches!(
+        check(13),
+        DbInconsistency::LogsBloomHashMismatch { .. }
+    ));
+    assert!(matches!(check(14), DbInconsistency::MissingEpochSet { .. }));
+
+    // The epochs with inconsistent execution results are executed again,
+    // while the inconsistent blocks are only reported.
+    let report = check_block_db(db_manager, Some(1), Some(14), None, true);
+    assert_eq!(report.inconsistencies.len(), 14);
+    assert_eq!(report.repaired, 7);
+    let describe = |report: &DbCheckReport| {
+        report
+            .inconsistencies
+            .iter()
+            .map(|inconsistency| inconsistency.to_string())
+            .collect::<Vec<_>>()
+    };
+    let repaired_report =
+        check_block_db(db_manager, Some(1), Some(14), None, true);
+    assert_eq!(repaired_report.unexecuted_epochs, 7);
+    assert_eq!(repaired_report.repaired, 0);
+    assert_eq!(repaired_report.inconsistencies.len(), 7);
+    assert!(repaired_report.inconsistencies.iter().all(|inconsistency| {
+        matches!(
+            inconsistency,
+            DbInconsistency::MissingHeader { .. }
+                | DbInconsistency::CorruptedHeader { .. }
+                | DbInconsistency::HeaderHashMismatch { .. }
+                | DbInconsistency::CorruptedBody { .. }
+                | DbInconsistency::TransactionsRootMismatch { .. }
+                | DbInconsistency::CorruptedEpochSet { .. }
+                | DbInconsistency::MissingEpochSet { .. }
+        )
+    }));
+    // Repairing again changes nothing.
+    let report = check_block_db(db_manager, Some(1), Some(14), None, true);
+    assert_eq!(describe(&report), describe(&repaired_report));
+    assert_eq!(report.unexecuted_epochs, 7);
+}
+
+#[test]
+fn test_check_block_db_repair_and_restart() {
+    let db_dir = TempDir::new("check_block_db_repair_and_restart").unwrap();
+    let data_man = new_data_manager(&db_dir, DbType::Rocksdb, |_| {});
+    let db_manager = &data_man.db_manager;
+    let mut epoch_hashes = vec![vec![]];
+    for epoch in 1..=4 {
+        epoch_hashes.push(insert_checked_epoch(db_manager, epoch, 2));
+    }
+    db_manager.insert_raw_block_body_to_db(&epoch_hashes[2][0], vec![0xff]);
+    let mut execution_result = db_manager
+        .checked_block_execution_result_from_db(&epoch_hashes[3][0])
+        .unwrap()
+        .unwrap();
+    execution_result.0 = H256::random();
+    db_manager.insert_block_execution_result_to_db(
+        &epoch_hashes[3][0],
+        &execution_result,
+    );
+    let report = check_block_db(db_manager, Some(1), None, None, true);
+    assert_eq!(report.inconsistencies.len(), 2, "{:?}", report);
+    assert_eq!(report.repaired, 1);
+    drop(data_man);
+
+    // The headers of the executed epochs are kept for the consensus graph
+    // recovery, and only the repaired epoch is not executed.
+    let data_man = new_data_manager(&db_dir, DbType::Rocksdb, |_| {});
+    for epoch in 1..=4 {
+        for block_hash in &epoch_hashes[epoch] {
+            assert!(data_man.block_header_by_hash(block_hash).is_some());
+        }
+        let pivot_hash = epoch_hashes[epoch][1];
+        assert_eq!(
+            data_man
+                .load_epoch_execution_commitment_from_db(&pivot_hash)
+                .is_some(),
+            epoch != 3
+        );
+    }
+    assert!(data_man
+        .block_execution_result_by_hash_from_db(&epoch_hashes[3][0])
+        .is_none());
+    let report =
+        check_block_db(&data_man.db_manager, Some(1), None, None, false);
+    assert_eq!(report.checked_epochs, 4);
+    assert_eq!(report.unexecuted_epochs, 1);
+    assert_eq!(report.inconsistencies.len(), 1, "{:?}", report);
+    assert!(matches!(
+        report.inconsistencies[0],
+        DbInconsistency::CorruptedBody { .. }
+    ));
+}
+
+fn child_header(
+    parent: &BlockHeader, deferred_state_root: H256, blame: u32,
+) -> BlockHeader {
+    BlockHeaderBuilder::new()
+        .with_parent_hash(parent.hash())
+        .with_height(parent.height() + 1)
+        .with_deferred_state_root(deferred_state_root)
+        .with_blame(blame)
+        .build()
+}
+
+/// The snapshot epoch count which the trusted checkpoints are loaded with.
+const TEST_SNAPSHOT_EPOCH_COUNT: u64 = 4;
+
+/// Build a pivot chain from a fake true genesis up to `height`, with the
+/// highest header first.
+fn pivot_chain(height: u64) -> Vec<BlockHeader> {
+    let mut chain = vec![BlockHeaderBuilder::new()
+        .with_parent_hash(H256::random())
+        .build()];
+    for _ in 0..height {
+        let header = child_header(chain.last().unwrap(), H256::random(), 0);
+        chain.push(header);
+    }
+    chain.reverse();
+    chain
+}
+
+/// The trusted checkpoint of the highest header of `chain`, with all the
+/// pivot blocks before it except the true genesis.
+fn trusted_checkpoint_json(
+    chain: &[BlockHeader], state_root: H256,
+) -> serde_json::Value {
+    let era_genesis_header = &chain[0];
+    let pivot_block_headers = &chain[1..chain.len() - 1];
+    serde_json::json!({
+        "eraGenesisHash": era_genesis_header.hash(),
+        "height": era_genesis_header.height(),
+        "stateRoot": state_root,
+        "blockHeader": rlp::encode(era_genesis_header).to_hex::<String>(),
+        "epochBlockHashes": [era_genesis_header.hash()],
+        "startBlockNumber": 100,
+        "pivotBlockHeaders": pivot_block_headers
+            .iter()
+            .map(|header| rlp::encode(header).to_hex::<String>())
+            .collect::<Vec<_>>(),
+        "pivotEpochBlockHashes": pivot_block_headers
+            .iter()
+            .map(|header| vec![header.hash()])
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn load_trusted_checkpoint(
+    dir: &TempDir, content: &str,
+) -> Result<TrustedCheckpoint, String> {
+    let path = dir.path().join("trusted_checkpoint.json");
+    fs::write(&path, content).unwrap();
+    TrustedCheckpoint::load(path.to_str().unwrap(), TEST_SNAPSHOT_EPOCH_COUNT)
+}
+
+#[test]
+fn test_trusted_checkpoint_load() {
+    let dir = TempDir::new("trusted_checkpoint_load").unwrap();
+    let chain = pivot_chain(10);
+    let era_genesis_header = chain[0].clone();
+    let state_root = H256::random();
+    let json = trusted_checkpoint_json(&chain, state_root);
+
+    let checkpoint = load_trusted_checkpoint(&dir, &json.to_string()).unwrap();
+    assert_eq!(checkpoint.era_genesis_hash(), era_genesis_header.hash());
+    assert_eq!(checkpoint.height(), 10);
+    assert_eq!(checkpoint.state_root, state_root);
+    assert_eq!(checkpoint.start_block_number, 100);
+    assert_eq!(
+        checkpoint.epoch_block_hashes,
+        vec![era_genesis_header.hash()]
+    );
+    assert_eq!(checkpoint.pivot_block_headers.len(), 9);
+    assert_eq!(checkpoint.pivot_block_headers[0].hash(), chain[1].hash());
+    assert_eq!(checkpoint.pivot_epoch_block_hashes.len(), 9);
+    assert!(checkpoint.pos_waypoint.is_none());
+
+    // The header does not match the era genesis.
+    let mut mismatch = json.clone();
+    mismatch["eraGenesisHash"] = serde_json::json!(H256::random());
+    assert!(load_trusted_checkpoint(&dir, &mismatch.to_string()).is_err());
+    let mut mismatch = json.clone();
+    mismatch["height"] = serde_json::json!(11);
+    assert!(load_trusted_checkpoint(&dir, &mismatch.to_string()).is_err());
+    let mut mismatch = json.clone();
+    mismatch["epochBlockHashes"] = serde_json::json!([H256::random()]);
+    assert!(load_trusted_checkpoint(&dir, &mismatch.to_string()).is_err());
+
+    // The pivot chain before the era genesis is broken or incomplete.
+    let mut mismatch = json.clone();
+    mismatch["pivotBlockHeaders"]
+        .as_array_mut()
+        .unwrap()
+        .swap(0, 1);
+    assert!(load_trusted_checkpoint(&dir, &mismatch.to_string()).is_err());
+    let mut mismatch = json.clone();
+    mismatch["pivotEpochBlockHashes"][0] = serde_json::json!([H256::random()]);
+    assert!(load_trusted_checkpoint(&dir, &mismatch.to_string()).is_err());
+    let mut incomplete = json.clone();
+    incomplete["pivotBlockHeaders"]
+        .as_array_mut()
+        .unwrap()
+        .truncate(TEST_SNAPSHOT_EPOCH_COUNT as usize - 1);
+    incomplete["pivotEpochBlockHashes"]
+        .as_array_mut()
+        .unwrap()
+        .truncate(TEST_SNAPSHOT_EPOCH_COUNT as usize - 1);
+    assert!(load_trusted_checkpoint(&dir, &incomplete.to_string()).is_err());
+    let mut incomplete = json.clone();
+    incomplete["pivotEpochBlockHashes"]
+        .as_array_mut()
+        .unwrap()
+        .pop();
+    assert!(load_trusted_checkpoint(&dir, &incomplete.to_string()).is_err());
+
+    // Malformed files.
+    assert!(load_trusted_checkpoint(&dir, "{").is_err());
+    let mut malformed = json.clone();
+    malformed["blockHeader"] = serde_json::json!("0xzz");
+    assert!(load_trusted_checkpoint(&dir, &malformed.to_string()).is_err());
+    let mut malformed = json.clone();
+    malformed["blockHeader"] = serde_json::json!("0x0102");
+    assert!(load_trusted_checkpoint(&dir, &malformed.to_string()).is_err());
+    let mut malformed = json;
+    malformed["posLedgerInfo"] = serde_json::json!("0x00");
+    assert!(load_trusted_checkpoint(&dir, &malformed.to_string()).is_err());
+}
+
+#[test]
+fn test_insert_trusted_checkpoint() {
+    let checkpoint_dir = TempDir::new("trusted_checkpoint_file").unwrap();
+    let db_dir = TempDir::new("insert_trusted_checkpoint").unwrap();
+    let chain = pivot_chain(10);
+    let checkpoint = load_trusted_checkpoint(
+        &checkpoint_dir,
+        &trusted_checkpoint_json(&chain, H256::random()).to_string(),
+    )
+    .unwrap();
+    let data_man = new_data_manager(&db_dir, DbType::Rocksdb, |config| {
+        config.trusted_checkpoint = Some(checkpoint)
+    });
+
+    let era_genesis_hash = chain[0].hash();
+    assert_eq!(
+        data_man.get_cur_consensus_era_genesis_hash(),
+        era_genesis_hash
+    );
+    let era_genesis_seq_num = data_man
+        .local_block_info_by_hash(&era_genesis_hash)
+        .unwrap()
+        .get_seq_num();
+    // The pivot chain before the era genesis is ordered before it, and is
+    // available to sync its snapshot.
+    for header in &chain[1..chain.len() - 1] {
+        assert!(data_man.block_header_by_hash(&header.hash()).is_some());
+        assert!(
+            data_man
+                .local_block_info_by_hash(&header.hash())
+                .unwrap()
+                .get_seq_num()
+                < era_genesis_seq_num
+        );
+        assert_eq!(
+            data_man.executed_epoch_set_hashes_from_db(header.height()),
+            Some(vec![header.hash()])
+        );
+    }
+    let (parent_snapshot_epoch, pivot_chain_parts) = data_man
+        .get_parent_epochs_for(era_genesis_hash, TEST_SNAPSHOT_EPOCH_COUNT);
+    assert_eq!(parent_snapshot_epoch, chain[4].hash());
+    assert_eq!(
+        pivot_chain_parts,
+        chain[..4]
+            .iter()
+            .rev()
+            .map(|header| header.hash())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_verify_trusted_checkpoint() {
+    let checkpoint_dir = TempDir::new("trusted_checkpoint_file").unwrap();
+    let db_dir = TempDir::new("verify_trusted_checkpoint").unwrap();
+    let checkpoint_chain = pivot_chain(10);
+    let era_genesis_header = checkpoint_chain[0].clone();
+    let state_root = H256::random();
+    let checkpoint = load_trusted_checkpoint(
+        &checkpoint_dir,
+        &trusted_checkpoint_json(&checkpoint_chain, state_root).to_string(),
+    )
+    .unwrap();
+    let data_man = new_data_manager(&db_dir, DbType::Rocksdb, |config| {
+        config.trusted_checkpoint = Some(checkpoint)
+    });
+    let insert_header = |header: &BlockHeader| {
+        data_man.insert_block_header(
+            header.hash(),
+            Arc::new(header.clone()),
+            true, /* persistent */
+        )
+    };
+    // The snapshot of the era genesis itself can be synced.
+    assert!(data_man
+        .verify_trusted_checkpoint(&era_genesis_header.hash())
+        .is_ok());
+
+    // The pivot block at height 15 commits the state of the checkpoint.
+    let mut chain = vec![era_genesis_header];
+    for height in 11..=16 {
+        let deferred_state_root = if height == 15 {
+            state_root
+        } else {
+            H256::random()
+        };
+        let header =
+            child_header(chain.last().unwrap(), deferred_state_root, 0);
+        assert!(data_man
+            .verify_header_with_trusted_checkpoint(&header)
+            .is_ok());
+        insert_header(&header);
+        chain.push(header);
+    }
+    assert!(data_man.verify_trusted_checkpoint(&chain[6].hash()).is_ok());
+
+    // A state root mismatch.
+    let header = child_header(&chain[4], H256::random(), 0);
+    assert!(data_man
+        .verify_header_with_trusted_checkpoint(&header)
+        .is_err());
+    insert_header(&header);
+    let tip = child_header(&header, H256::random(), 0);
+    insert_header(&tip);
+    assert!(data_man.verify_trusted_checkpoint(&tip.hash()).is_err());
+
+    // The state root committed by a blamed block is not compared.
+    let header = child_header(&chain[4], H256::random(), 1);
+    assert!(data_man
+        .verify_header_with_trusted_checkpoint(&header)
+        .is_ok());
+    insert_header(&header);
+    assert!(data_man.verify_trusted_checkpoint(&header.hash()).is_ok());
+
+    // A chain not going through the checkpoint.
+    let other_genesis_header = BlockHeaderBuilder::new()
+        .with_parent_hash(H256::random())
+        .with_height(10)
+        .build();
+    insert_header(&other_genesis_header);
+    let mut header = other_genesis_header;
+    for _ in 11..=15 {
+        header = child_header(&header, H256::random(), 0);
+        insert_header(&header);
+    }
+    assert!(data_man
+        .verify_header_with_trusted_checkpoint(&header)
+        .is_ok());
+    assert!(data_man.verify_trusted_checkpoint(&header.hash()).is_err());
+}
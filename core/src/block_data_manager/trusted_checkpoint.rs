@@ -0,0 +1,206 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_parameters::consensus_internal::REWARD_EPOCH_COUNT;
+use cfx_types::H256;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, waypoint::Waypoint};
+use primitives::BlockHeader;
+use rustc_hex::FromHex;
+use serde_derive::Deserialize;
+use std::{cmp::min, fs};
+
+/// An era genesis trusted by the operator. A full node started with an empty
+/// database begins from this era as if it had made the checkpoint itself, so
+/// it only syncs the headers after it before syncing the snapshot of this
+/// checkpoint, or of a later one made by consensus.
+#[derive(Clone, Debug)]
+pub struct TrustedCheckpoint {
+    pub era_genesis_header: BlockHeader,
+    /// The blocks executed in the era genesis epoch, with the pivot block
+    /// last.
+    pub epoch_block_hashes: Vec<H256>,
+    /// The number of blocks executed before the era genesis epoch.
+    pub start_block_number: u64,
+    /// The pivot chain before the era genesis, from its parent backward and
+    /// back to the parent snapshot, which is needed to verify and restore
+    /// the snapshot of the era genesis.
+    pub pivot_block_headers: Vec<BlockHeader>,
+    /// The blocks executed in the epochs of `pivot_block_headers`, each with
+    /// the pivot block last. The epochs rewarded after the era genesis are
+    /// required.
+    pub pivot_epoch_block_hashes: Vec<Vec<H256>>,
+    /// The state root hash of the era genesis epoch, which is committed as
+    /// `deferred_state_root` by the pivot block `DEFERRED_STATE_EPOCH_COUNT`
+    /// epochs later.
+    pub state_root: H256,
+    /// The waypoint of the trusted PoS ledger info, which the PoS ledger
+    /// synced from peers has to match.
+    pub pos_waypoint: Option<Waypoint>,
+}
+
+/// The JSON file format of a trusted checkpoint. Byte strings are hex
+/// encoded, the block header in RLP and the PoS ledger info in BCS.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrustedCheckpointFile {
+    era_genesis_hash: H256,
+    height: u64,
+    state_root: H256,
+    block_header: String,
+    epoch_block_hashes: Vec<H256>,
+    start_block_number: u64,
+    pivot_block_headers: Vec<String>,
+    pivot_epoch_block_hashes: Vec<Vec<H256>>,
+    pos_ledger_info: Option<String>,
+}
+
+impl TrustedCheckpoint {
+    /// Load the checkpoint from a JSON file. The pivot chain before the era
+    /// genesis should cover `snapshot_epoch_count` epochs unless it reaches
+    /// the true genesis.
+    pub fn load(path: &str, snapshot_epoch_count: u64) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            format!("Failed to read trusted checkpoint file: {:?}", e)
+        })?;
+        let file: TrustedCheckpointFile = serde_json::from_str(&content)
+            .map_err(|e| {
+                format!("Failed to parse trusted checkpoint file: {:?}", e)
+            })?;
+
+        let era_genesis_header: BlockHeader =
+            rlp::decode(&parse_hex(&file.block_header)?).map_err(|e| {
+                format!("Invalid trusted checkpoint block header: {:?}", e)
+            })?;
+        if era_genesis_header.hash() != file.era_genesis_hash
+            || era_genesis_header.height() != file.height
+        {
+            return Err(format!(
+                "Trusted checkpoint block header {:?} at height {} does not \
+                 match era genesis {:?} at height {}",
+                era_genesis_header.hash(),
+                era_genesis_header.height(),
+                file.era_genesis_hash,
+                file.height
+            ));
+        }
+        if file.height == 0 {
+            return Err("Trusted checkpoint is the true genesis".into());
+        }
+        if file.epoch_block_hashes.last() != Some(&file.era_genesis_hash) {
+            return Err("The last trusted checkpoint epoch block should be \
+                        the era genesis"
+                .into());
+        }
+
+        let mut pivot_block_headers =
+            Vec::with_capacity(file.pivot_block_headers.len());
+        let mut parent_hash = *era_genesis_header.parent_hash();
+        for header in &file.pivot_block_headers {
+            if pivot_block_headers.len() as u64 == file.height {
+                return Err("Trusted pivot block headers go beyond the true \
+                            genesis"
+                    .into());
+            }
+            let header: BlockHeader = rlp::decode(&parse_hex(header)?)
+                .map_err(|e| {
+                    format!("Invalid trusted pivot block header: {:?}", e)
+                })?;
+            let parent_height =
+                file.height - pivot_block_headers.len() as u64 - 1;
+            if header.hash() != parent_hash || header.height() != parent_height
+            {
+                return Err(format!(
+                    "Trusted pivot block header {:?} at height {} is not \
+                     the parent {:?} at height {}",
+                    header.hash(),
+                    header.height(),
+                    parent_hash,
+                    parent_height
+                ));
+            }
+            parent_hash = *header.parent_hash();
+            pivot_block_headers.push(header);
+        }
+        // The true genesis is always available, so it can be omitted.
+        let required_headers = min(snapshot_epoch_count, file.height - 1);
+        if (pivot_block_headers.len() as u64) < required_headers {
+            return Err(format!(
+                "{} trusted pivot block headers are given, but {} are \
+                 required to sync the snapshot",
+                pivot_block_headers.len(),
+                required_headers
+            ));
+        }
+        let required_epochs = min(REWARD_EPOCH_COUNT - 1, file.height - 1);
+        if file.pivot_epoch_block_hashes.len() > pivot_block_headers.len() {
+            return Err(format!(
+                "{} trusted pivot epochs are given, but only {} pivot block \
+                 headers",
+                file.pivot_epoch_block_hashes.len(),
+                pivot_block_headers.len()
+            ));
+        }
+        if (file.pivot_epoch_block_hashes.len() as u64) < required_epochs {
+            return Err(format!(
+                "{} trusted pivot epochs are given, but {} are required to \
+                 compute the rewards",
+                file.pivot_epoch_block_hashes.len(),
+                required_epochs
+            ));
+        }
+        for (epoch_block_hashes, pivot_header) in file
+            .pivot_epoch_block_hashes
+            .iter()
+            .zip(pivot_block_headers.iter())
+        {
+            if epoch_block_hashes.last() != Some(&pivot_header.hash()) {
+                return Err(format!(
+                    "The last block of trusted pivot epoch {} should be its \
+                     pivot block",
+                    pivot_header.height()
+                ));
+            }
+        }
+
+        let pos_waypoint = match &file.pos_ledger_info {
+            Some(ledger_info) => {
+                let ledger_info: LedgerInfoWithSignatures =
+                    bcs::from_bytes(&parse_hex(ledger_info)?).map_err(|e| {
+                        format!("Invalid trusted PoS ledger info: {:?}", e)
+                    })?;
+                Some(
+                    Waypoint::new_epoch_boundary(ledger_info.ledger_info())
+                        .map_err(|e| {
+                            format!(
+                                "Trusted PoS ledger info does not end an \
+                                 epoch: {:?}",
+                                e
+                            )
+                        })?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(TrustedCheckpoint {
+            era_genesis_header,
+            epoch_block_hashes: file.epoch_block_hashes,
+            start_block_number: file.start_block_number,
+            pivot_block_headers,
+            pivot_epoch_block_hashes: file.pivot_epoch_block_hashes,
+            state_root: file.state_root,
+            pos_waypoint,
+        })
+    }
+
+    pub fn era_genesis_hash(&self) -> H256 { self.era_genesis_header.hash() }
+
+    pub fn height(&self) -> u64 { self.era_genesis_header.height() }
+}
+
+fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+    s.trim_start_matches("0x")
+        .from_hex()
+        .map_err(|e| format!("Invalid hex string: {:?}", e))
+}
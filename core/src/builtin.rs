@@ -21,6 +21,7 @@
 //! Standard built-in contracts.
 
 mod blake2f;
+mod bls12_381;
 
 use std::{
     cmp::{max, min},
@@ -35,6 +36,11 @@ use parity_crypto::digest;
 
 use crate::bytes::BytesRef;
 use blake2f::compress;
+use bls12_381::{
+    Bls12G1AddImpl, Bls12G1MsmImpl, Bls12G2AddImpl, Bls12G2MsmImpl,
+    Bls12MapFp2ToG2Impl, Bls12MapFpToG1Impl, Bls12PairingImpl,
+};
+pub(crate) use bls12_381::{Bls12MsmPricer, Bls12PairingPricer};
 use cfx_types::{Space, H256, U256};
 use cfxkey::{public_to_address, Address};
 use keylib::{recover as ec_recover, Signature};
@@ -273,6 +279,13 @@ pub fn builtin_factory(name: &str) -> Box<dyn Impl> {
         "alt_bn128_mul" => Box::new(Bn128MulImpl) as Box<dyn Impl>,
         "alt_bn128_pairing" => Box::new(Bn128PairingImpl) as Box<dyn Impl>,
         "blake2_f" => Box::new(Blake2FImpl) as Box<dyn Impl>,
+        "bls12_g1_add" => Box::new(Bls12G1AddImpl) as Box<dyn Impl>,
+        "bls12_g1_msm" => Box::new(Bls12G1MsmImpl) as Box<dyn Impl>,
+        "bls12_g2_add" => Box::new(Bls12G2AddImpl) as Box<dyn Impl>,
+        "bls12_g2_msm" => Box::new(Bls12G2MsmImpl) as Box<dyn Impl>,
+        "bls12_pairing" => Box::new(Bls12PairingImpl) as Box<dyn Impl>,
+        "bls12_map_fp_to_g1" => Box::new(Bls12MapFpToG1Impl) as Box<dyn Impl>,
+        "bls12_map_fp2_to_g2" => Box::new(Bls12MapFp2ToG2Impl) as Box<dyn Impl>,
         _ => panic!("invalid builtin name: {}", name),
     }
 }
@@ -284,6 +297,9 @@ pub fn builtin_factory(name: &str) -> Box<dyn Impl> {
 // - sha256
 // - ripemd160
 // - modexp (EIP198)
+// - alt_bn128 add, mul and pairing (EIP196, EIP197)
+// - blake2 f (EIP152)
+// - bls12-381 operations (EIP2537)
 
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -0,0 +1,635 @@
+// Copyright 2022 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! BLS12-381 curve operations (EIP-2537).
+//!
+//! A base field element is encoded as 64 bytes in big-endian, with the top 16
+//! bytes being zero. A G1 point is encoded as `x | y` and a G2 point is encoded
+//! as `x.c0 | x.c1 | y.c0 | y.c1`. The point at infinity is encoded as all
+//! zeros. A scalar is encoded as 32 bytes in big-endian and is not required to
+//! be less than the group order.
+
+use super::{Error, Impl, Pricer};
+use crate::bytes::BytesRef;
+use blst::{
+    blst_bendian_from_fp, blst_final_exp, blst_fp, blst_fp12, blst_fp12_is_one,
+    blst_fp12_mul, blst_fp12_one, blst_fp2, blst_fp_from_bendian,
+    blst_map_to_g1, blst_map_to_g2, blst_miller_loop, blst_p1,
+    blst_p1_add_or_double, blst_p1_affine, blst_p1_affine_in_g1,
+    blst_p1_affine_is_inf, blst_p1_affine_on_curve, blst_p1_from_affine,
+    blst_p1_mult, blst_p1_to_affine, blst_p2, blst_p2_add_or_double,
+    blst_p2_affine, blst_p2_affine_in_g2, blst_p2_affine_is_inf,
+    blst_p2_affine_on_curve, blst_p2_from_affine, blst_p2_mult,
+    blst_p2_to_affine,
+};
+use cfx_types::U256;
+use std::cmp::min;
+
+const PADDED_FP_LENGTH: usize = 64;
+const PADDING_LENGTH: usize = 16;
+const FP_LENGTH: usize = 48;
+const PADDED_FP2_LENGTH: usize = 2 * PADDED_FP_LENGTH;
+const G1_LENGTH: usize = 2 * PADDED_FP_LENGTH;
+const G2_LENGTH: usize = 2 * PADDED_FP2_LENGTH;
+const SCALAR_LENGTH: usize = 32;
+const G1_MSM_PAIR_LENGTH: usize = G1_LENGTH + SCALAR_LENGTH;
+const G2_MSM_PAIR_LENGTH: usize = G2_LENGTH + SCALAR_LENGTH;
+const PAIRING_PAIR_LENGTH: usize = G1_LENGTH + G2_LENGTH;
+
+/// Discounts (per mille) for G1 multi-scalar multiplication, indexed by the
+/// number of pairs minus one.
+pub(crate) const G1_MSM_DISCOUNTS: [u64; 128] = [
+    1000, 949, 848, 797, 764, 750, 738, 728, 719, 712, 705, 698, 692, 687, 682,
+    677, 673, 669, 665, 661, 658, 654, 651, 648, 645, 642, 640, 637, 635, 632,
+    630, 627, 625, 623, 621, 619, 617, 615, 613, 611, 609, 608, 606, 604, 603,
+    601, 599, 598, 596, 595, 593, 592, 591, 589, 588, 586, 585, 584, 582, 581,
+    580, 579, 577, 576, 575, 574, 573, 572, 570, 569, 568, 567, 566, 565, 564,
+    563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553, 552, 551, 550, 549,
+    548, 547, 547, 546, 545, 544, 543, 542, 541, 540, 540, 539, 538, 537, 536,
+    536, 535, 534, 533, 532, 532, 531, 530, 529, 528, 528, 527, 526, 525, 525,
+    524, 523, 522, 522, 521, 520, 520, 519,
+];
+
+/// Discounts (per mille) for G2 multi-scalar multiplication, indexed by the
+/// number of pairs minus one.
+pub(crate) const G2_MSM_DISCOUNTS: [u64; 128] = [
+    1000, 1000, 923, 884, 855, 832, 812, 796, 782, 770, 759, 749, 740, 732,
+    724, 717, 711, 704, 699, 693, 688, 683, 679, 674, 670, 666, 663, 659, 655,
+    652, 649, 646, 643, 640, 637, 634, 632, 629, 627, 624, 622, 620, 618, 615,
+    613, 611, 609, 607, 606, 604, 602, 600, 598, 597, 595, 593, 592, 590, 589,
+    587, 586, 584, 583, 582, 580, 579, 578, 576, 575, 574, 573, 571, 570, 569,
+    568, 567, 566, 565, 563, 562, 561, 560, 559, 558, 557, 556, 555, 554, 553,
+    552, 552, 551, 550, 549, 548, 547, 546, 545, 545, 544, 543, 542, 541, 541,
+    540, 539, 538, 537, 537, 536, 535, 535, 534, 533, 532, 532, 531, 530, 530,
+    529, 528, 528, 527, 526, 526, 525, 524, 524,
+];
+
+/// A pricing model for the BLS12-381 multi-scalar multiplications. The price
+/// is `k * multiplication * discount(k) / 1000` for `k` pairs of point and
+/// scalar.
+pub(crate) struct Bls12MsmPricer {
+    pair_length: usize,
+    multiplication: u64,
+    discounts: &'static [u64],
+}
+
+impl Bls12MsmPricer {
+    pub(crate) fn g1(multiplication: u64) -> Bls12MsmPricer {
+        Bls12MsmPricer {
+            pair_length: G1_MSM_PAIR_LENGTH,
+            multiplication,
+            discounts: &G1_MSM_DISCOUNTS,
+        }
+    }
+
+    pub(crate) fn g2(multiplication: u64) -> Bls12MsmPricer {
+        Bls12MsmPricer {
+            pair_length: G2_MSM_PAIR_LENGTH,
+            multiplication,
+            discounts: &G2_MSM_DISCOUNTS,
+        }
+    }
+}
+
+impl Pricer for Bls12MsmPricer {
+    fn cost(&self, input: &[u8]) -> U256 {
+        let k = input.len() / self.pair_length;
+        // Returning zero is fine because `execute()` will reject the input.
+        if k == 0 {
+            return U256::zero();
+        }
+        let discount = self.discounts[min(k, self.discounts.len()) - 1];
+        U256::from(k) * U256::from(self.multiplication) * U256::from(discount)
+            / U256::from(1000)
+    }
+}
+
+/// A BLS12-381 pairing pricing model. This computes a price using a base cost
+/// and a cost per pair.
+pub(crate) struct Bls12PairingPricer {
+    base: usize,
+    pair: usize,
+}
+
+impl Bls12PairingPricer {
+    pub(crate) fn new(base: usize, pair: usize) -> Bls12PairingPricer {
+        Bls12PairingPricer { base, pair }
+    }
+}
+
+impl Pricer for Bls12PairingPricer {
+    fn cost(&self, input: &[u8]) -> U256 {
+        U256::from(self.base)
+            + U256::from(self.pair)
+                * U256::from(input.len() / PAIRING_PAIR_LENGTH)
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Bls12G1AddImpl;
+
+#[derive(Debug)]
+pub(super) struct Bls12G1MsmImpl;
+
+#[derive(Debug)]
+pub(super) struct Bls12G2AddImpl;
+
+#[derive(Debug)]
+pub(super) struct Bls12G2MsmImpl;
+
+#[derive(Debug)]
+pub(super) struct Bls12PairingImpl;
+
+#[derive(Debug)]
+pub(super) struct Bls12MapFpToG1Impl;
+
+#[derive(Debug)]
+pub(super) struct Bls12MapFp2ToG2Impl;
+
+impl Impl for Bls12G1AddImpl {
+    /// Can fail if the input is not 256 bytes or any of the points is not on
+    /// the curve. The subgroup check is not required.
+    fn execute(
+        &self, input: &[u8], output: &mut BytesRef,
+    ) -> Result<(), Error> {
+        if input.len() != 2 * G1_LENGTH {
+            return Err("Invalid input length, must be 256 bytes".into());
+        }
+        let a = read_g1(&input[..G1_LENGTH], false)?;
+        let b = read_g1(&input[G1_LENGTH..], false)?;
+
+        let mut sum = blst_p1::default();
+        unsafe { blst_p1_add_or_double(&mut sum, &a, &b) };
+        output.write(0, &encode_g1(&sum));
+        Ok(())
+    }
+}
+
+impl Impl for Bls12G1MsmImpl {
+    /// Can fail if the input is empty or not a multiple of 160 bytes, or any
+    /// of the points is not in G1.
+    fn execute(
+        &self, input: &[u8], output: &mut BytesRef,
+    ) -> Result<(), Error> {
+        if input.is_empty() || input.len() % G1_MSM_PAIR_LENGTH != 0 {
+            return Err(
+                "Invalid input length, must be a non-zero multiple of 160"
+                    .into(),
+            );
+        }
+
+        let mut acc = blst_p1::default();
+        for pair in input.chunks(G1_MSM_PAIR_LENGTH) {
+            let point = read_g1(&pair[..G1_LENGTH], true)?;
+            let scalar = read_scalar(&pair[G1_LENGTH..]);
+            let mut product = blst_p1::default();
+            unsafe {
+                blst_p1_mult(
+                    &mut product,
+                    &point,
+                    scalar.as_ptr(),
+                    SCALAR_LENGTH * 8,
+                );
+            }
+            let mut sum = blst_p1::default();
+            unsafe { blst_p1_add_or_double(&mut sum, &acc, &product) };
+            acc = sum;
+        }
+        output.write(0, &encode_g1(&acc));
+        Ok(())
+    }
+}
+
+impl Impl for Bls12G2AddImpl {
+    /// Can fail if the input is not 512 bytes or any of the points is not on
+    /// the curve. The subgroup check is not required.
+    fn execute(
+        &self, input: &[u8], output: &mut BytesRef,
+    ) -> Result<(), Error> {
+        if input.len() != 2 * G2_LENGTH {
+            return Err("Invalid input length, must be 512 bytes".into());
+        }
+        let a = read_g2(&input[..G2_LENGTH], false)?;
+        let b = read_g2(&input[G2_LENGTH..], false)?;
+
+        let mut sum = blst_p2::default();
+        unsafe { blst_p2_add_or_double(&mut sum, &a, &b) };
+        output.write(0, &encode_g2(&sum));
+        Ok(())
+    }
+}
+
+impl Impl for Bls12G2MsmImpl {
+    /// Can fail if the input is empty or not a multiple of 288 bytes, or any
+    /// of the points is not in G2.
+    fn execute(
+        &self, input: &[u8], output: &mut BytesRef,
+    ) -> Result<(), Error> {
+        if input.is_empty() || input.len() % G2_MSM_PAIR_LENGTH != 0 {
+            return Err(
+                "Invalid input length, must be a non-zero multiple of 288"
+                    .into(),
+            );
+        }
+
+        let mut acc = blst_p2::default();
+        for pair in input.chunks(G2_MSM_PAIR_LENGTH) {
+            let point = read_g2(&pair[..G2_LENGTH], true)?;
+            let scalar = read_scalar(&pair[G2_LENGTH..]);
+            let mut product = blst_p2::default();
+            unsafe {
+                blst_p2_mult(
+                    &mut product,
+                    &point,
+                    scalar.as_ptr(),
+                    SCALAR_LENGTH * 8,
+                );
+            }
+            let mut sum = blst_p2::default();
+            unsafe { blst_p2_add_or_double(&mut sum, &acc, &product) };
+            acc = sum;
+        }
+        output.write(0, &encode_g2(&acc));
+        Ok(())
+    }
+}
+
+impl Impl for Bls12PairingImpl {
+    /// Can fail if:
+    ///     - input is empty or its length is not a multiple of 384
+    ///     - any of the G1 points is not in G1
+    ///     - any of the G2 points is not in G2
+    fn execute(
+        &self, input: &[u8], output: &mut BytesRef,
+    ) -> Result<(), Error> {
+        if input.is_empty() || input.len() % PAIRING_PAIR_LENGTH != 0 {
+            return Err(
+                "Invalid input length, must be a non-zero multiple of 384"
+                    .into(),
+            );
+        }
+
+        let mut acc = unsafe { *blst_fp12_one() };
+        for pair in input.chunks(PAIRING_PAIR_LENGTH) {
+            let p = read_g1_affine(&pair[..G1_LENGTH], true)?;
+            let q = read_g2_affine(&pair[G1_LENGTH..], true)?;
+            // The pairing with the point at infinity is always one.
+            if unsafe { blst_p1_affine_is_inf(&p) || blst_p2_affine_is_inf(&q) }
+            {
+                continue;
+            }
+            let mut ml = blst_fp12::default();
+            let mut product = blst_fp12::default();
+            unsafe {
+                blst_miller_loop(&mut ml, &q, &p);
+                blst_fp12_mul(&mut product, &acc, &ml);
+            }
+            acc = product;
+        }
+        let mut result = blst_fp12::default();
+        unsafe { blst_final_exp(&mut result, &acc) };
+
+        let mut buf = [0u8; 32];
+        if unsafe { blst_fp12_is_one(&result) } {
+            buf[31] = 1;
+        }
+        output.write(0, &buf);
+        Ok(())
+    }
+}
+
+impl Impl for Bls12MapFpToG1Impl {
+    /// Can fail if the input is not an encoded base field element.
+    fn execute(
+        &self, input: &[u8], output: &mut BytesRef,
+    ) -> Result<(), Error> {
+        if input.len() != PADDED_FP_LENGTH {
+            return Err("Invalid input length, must be 64 bytes".into());
+        }
+        let u = read_fp(input)?;
+
+        let mut point = blst_p1::default();
+        unsafe { blst_map_to_g1(&mut point, &u, std::ptr::null()) };
+        output.write(0, &encode_g1(&point));
+        Ok(())
+    }
+}
+
+impl Impl for Bls12MapFp2ToG2Impl {
+    /// Can fail if the input is not an encoded element of the quadratic
+    /// extension field.
+    fn execute(
+        &self, input: &[u8], output: &mut BytesRef,
+    ) -> Result<(), Error> {
+        if input.len() != PADDED_FP2_LENGTH {
+            return Err("Invalid input length, must be 128 bytes".into());
+        }
+        let u = read_fp2(input)?;
+
+        let mut point = blst_p2::default();
+        unsafe { blst_map_to_g2(&mut point, &u, std::ptr::null()) };
+        output.write(0, &encode_g2(&point));
+        Ok(())
+    }
+}
+
+fn read_fp(input: &[u8]) -> Result<blst_fp, Error> {
+    debug_assert_eq!(input.len(), PADDED_FP_LENGTH);
+    if input[..PADDING_LENGTH].iter().any(|b| *b != 0) {
+        return Err("Invalid field element, top 16 bytes must be zero".into());
+    }
+    let mut fp = blst_fp::default();
+    let mut canonical = [0u8; FP_LENGTH];
+    unsafe {
+        blst_fp_from_bendian(&mut fp, input[PADDING_LENGTH..].as_ptr());
+        blst_bendian_from_fp(canonical.as_mut_ptr(), &fp);
+    }
+    // The element is reduced by the field modulus, so a different encoding
+    // means the input is not less than the modulus.
+    if canonical[..] != input[PADDING_LENGTH..] {
+        return Err("Invalid field element, not less than the modulus".into());
+    }
+    Ok(fp)
+}
+
+fn read_fp2(input: &[u8]) -> Result<blst_fp2, Error> {
+    debug_assert_eq!(input.len(), PADDED_FP2_LENGTH);
+    Ok(blst_fp2 {
+        fp: [
+            read_fp(&input[..PADDED_FP_LENGTH])?,
+            read_fp(&input[PADDED_FP_LENGTH..])?,
+        ],
+    })
+}
+
+/// Converts a big-endian scalar into the little-endian bytes used by blst.
+fn read_scalar(input: &[u8]) -> [u8; SCALAR_LENGTH] {
+    let mut scalar = [0u8; SCALAR_LENGTH];
+    for (i, b) in input.iter().rev().enumerate() {
+        scalar[i] = *b;
+    }
+    scalar
+}
+
+fn read_g1_affine(
+    input: &[u8], subgroup_check: bool,
+) -> Result<blst_p1_affine, Error> {
+    let point = blst_p1_affine {
+        x: read_fp(&input[..PADDED_FP_LENGTH])?,
+        y: read_fp(&input[PADDED_FP_LENGTH..])?,
+    };
+    if unsafe { blst_p1_affine_is_inf(&point) } {
+        return Ok(point);
+    }
+    if !unsafe { blst_p1_affine_on_curve(&point) } {
+        return Err("Invalid G1 point, not on curve".into());
+    }
+    if subgroup_check && !unsafe { blst_p1_affine_in_g1(&point) } {
+        return Err("Invalid G1 point, not in the subgroup".into());
+    }
+    Ok(point)
+}
+
+fn read_g1(input: &[u8], subgroup_check: bool) -> Result<blst_p1, Error> {
+    let affine = read_g1_affine(input, subgroup_check)?;
+    let mut point = blst_p1::default();
+    unsafe { blst_p1_from_affine(&mut point, &affine) };
+    Ok(point)
+}
+
+fn read_g2_affine(
+    input: &[u8], subgroup_check: bool,
+) -> Result<blst_p2_affine, Error> {
+    let point = blst_p2_affine {
+        x: read_fp2(&input[..PADDED_FP2_LENGTH])?,
+        y: read_fp2(&input[PADDED_FP2_LENGTH..])?,
+    };
+    if unsafe { blst_p2_affine_is_inf(&point) } {
+        return Ok(point);
+    }
+    if !unsafe { blst_p2_affine_on_curve(&point) } {
+        return Err("Invalid G2 point, not on curve".into());
+    }
+    if subgroup_check && !unsafe { blst_p2_affine_in_g2(&point) } {
+        return Err("Invalid G2 point, not in the subgroup".into());
+    }
+    Ok(point)
+}
+
+fn read_g2(input: &[u8], subgroup_check: bool) -> Result<blst_p2, Error> {
+    let affine = read_g2_affine(input, subgroup_check)?;
+    let mut point = blst_p2::default();
+    unsafe { blst_p2_from_affine(&mut point, &affine) };
+    Ok(point)
+}
+
+fn encode_fp(fp: &blst_fp, output: &mut [u8]) {
+    debug_assert_eq!(output.len(), PADDED_FP_LENGTH);
+    unsafe { blst_bendian_from_fp(output[PADDING_LENGTH..].as_mut_ptr(), fp) };
+}
+
+fn encode_g1(point: &blst_p1) -> [u8; G1_LENGTH] {
+    let mut affine = blst_p1_affine::default();
+    unsafe { blst_p1_to_affine(&mut affine, point) };
+
+    let mut buf = [0u8; G1_LENGTH];
+    encode_fp(&affine.x, &mut buf[..PADDED_FP_LENGTH]);
+    encode_fp(&affine.y, &mut buf[PADDED_FP_LENGTH..]);
+    buf
+}
+
+fn encode_g2(point: &blst_p2) -> [u8; G2_LENGTH] {
+    let mut affine = blst_p2_affine::default();
+    unsafe { blst_p2_to_affine(&mut affine, point) };
+
+    let mut buf = [0u8; G2_LENGTH];
+    let coordinates = [
+        &affine.x.fp[0],
+        &affine.x.fp[1],
+        &affine.y.fp[0],
+        &affine.y.fp[1],
+    ];
+    for (fp, chunk) in coordinates.iter().zip(buf.chunks_mut(PADDED_FP_LENGTH))
+    {
+        encode_fp(fp, chunk);
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::builtin_factory, Bls12MsmPricer, Bls12PairingPricer,
+        G1_MSM_DISCOUNTS, G2_MSM_DISCOUNTS,
+    };
+    use crate::{builtin::Pricer, bytes::BytesRef};
+    use cfx_types::U256;
+    use rustc_hex::FromHex;
+
+    const G1_GENERATOR: &str = "\
+        0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0f\
+        c3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb\
+        0000000000000000000000000000000008b3f481e3aaa0f1a09e30ed741d8ae4\
+        fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1";
+
+    const G1_GENERATOR_NEG: &str = "\
+        0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0f\
+        c3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb\
+        00000000000000000000000000000000114d1d6855d545a8aa7d76c8cf2e21f2\
+        67816aef1db507c96655b9d5caac42364e6f38ba0ecb751bad54dcd6b939c2ca";
+
+    const G2_GENERATOR: &str = "\
+        00000000000000000000000000000000024aa2b2f08f0a91260805272dc51051\
+        c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8\
+        0000000000000000000000000000000013e02b6052719f607dacd3a088274f65\
+        596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e\
+        000000000000000000000000000000000ce5d527727d6e118cc9cdc6da2e351a\
+        adfd9baa8cbdd3a76d429a695160d12c923ac9cc3baca289e193548608b82801\
+        000000000000000000000000000000000606c4a02ea734cc32acd2b02bc28b99\
+        cb3e287e85a763af267492ab572e99ab3f370d275cec1da1aaa9075ff05f79be";
+
+    const FIELD_MODULUS: &str = "\
+        000000000000000000000000000000001a0111ea397fe69a4b1ba7b6434bacd7\
+        64774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab";
+
+    fn hex(s: &str) -> Vec<u8> { FromHex::from_hex(s).unwrap() }
+
+    fn execute(name: &str, input: &[u8]) -> Result<Vec<u8>, super::Error> {
+        let mut output = Vec::new();
+        builtin_factory(name)
+            .execute(input, &mut BytesRef::Flexible(&mut output))?;
+        Ok(output)
+    }
+
+    #[test]
+    fn bls12_g1_add() {
+        // G + O == G
+        let mut input = hex(G1_GENERATOR);
+        input.extend_from_slice(&[0u8; 128]);
+        assert_eq!(execute("bls12_g1_add", &input).unwrap(), hex(G1_GENERATOR));
+
+        // G + (-G) == O
+        let mut input = hex(G1_GENERATOR);
+        input.extend(hex(G1_GENERATOR_NEG));
+        assert_eq!(execute("bls12_g1_add", &input).unwrap(), vec![0u8; 128]);
+
+        // invalid input length
+        assert!(execute("bls12_g1_add", &hex(G1_GENERATOR)).is_err());
+
+        // point not on curve
+        let mut input = hex(G1_GENERATOR);
+        input[127] ^= 1;
+        input.extend(hex(G1_GENERATOR));
+        assert!(execute("bls12_g1_add", &input).is_err());
+    }
+
+    #[test]
+    fn bls12_g1_msm() {
+        // G + G == 2 * G
+        let mut input = hex(G1_GENERATOR);
+        input.extend(hex(G1_GENERATOR));
+        let doubled = execute("bls12_g1_add", &input).unwrap();
+
+        let mut input = hex(G1_GENERATOR);
+        let mut scalar = [0u8; 32];
+        scalar[31] = 2;
+        input.extend_from_slice(&scalar);
+        assert_eq!(execute("bls12_g1_msm", &input).unwrap(), doubled);
+
+        // G * 1 + G * 1 == 2 * G
+        let mut input = Vec::new();
+        scalar[31] = 1;
+        for _ in 0..2 {
+            input.extend(hex(G1_GENERATOR));
+            input.extend_from_slice(&scalar);
+        }
+        assert_eq!(execute("bls12_g1_msm", &input).unwrap(), doubled);
+
+        assert!(execute("bls12_g1_msm", &[]).is_err());
+    }
+
+    #[test]
+    fn bls12_g2_add() {
+        let mut input = hex(G2_GENERATOR);
+        input.extend_from_slice(&[0u8; 256]);
+        assert_eq!(execute("bls12_g2_add", &input).unwrap(), hex(G2_GENERATOR));
+
+        let mut input = hex(G2_GENERATOR);
+        input.extend(hex(G2_GENERATOR));
+        let doubled = execute("bls12_g2_add", &input).unwrap();
+        let mut input = hex(G2_GENERATOR);
+        let mut scalar = [0u8; 32];
+        scalar[31] = 2;
+        input.extend_from_slice(&scalar);
+        assert_eq!(execute("bls12_g2_msm", &input).unwrap(), doubled);
+    }
+
+    #[test]
+    fn bls12_pairing() {
+        let one = {
+            let mut buf = vec![0u8; 32];
+            buf[31] = 1;
+            buf
+        };
+
+        // e(G1, G2) * e(-G1, G2) == 1
+        let mut input = hex(G1_GENERATOR);
+        input.extend(hex(G2_GENERATOR));
+        input.extend(hex(G1_GENERATOR_NEG));
+        input.extend(hex(G2_GENERATOR));
+        assert_eq!(execute("bls12_pairing", &input).unwrap(), one);
+
+        // e(G1, G2) != 1
+        let mut input = hex(G1_GENERATOR);
+        input.extend(hex(G2_GENERATOR));
+        assert_eq!(execute("bls12_pairing", &input).unwrap(), vec![0u8; 32]);
+
+        // e(O, G2) == 1
+        let mut input = vec![0u8; 128];
+        input.extend(hex(G2_GENERATOR));
+        assert_eq!(execute("bls12_pairing", &input).unwrap(), one);
+
+        assert!(execute("bls12_pairing", &[]).is_err());
+    }
+
+    #[test]
+    fn bls12_map_to_curve() {
+        let mut input = vec![0u8; 64];
+        input[63] = 1;
+        assert_eq!(execute("bls12_map_fp_to_g1", &input).unwrap().len(), 128);
+
+        let mut input = vec![0u8; 128];
+        input[127] = 1;
+        assert_eq!(execute("bls12_map_fp2_to_g2", &input).unwrap().len(), 256);
+
+        // the top 16 bytes must be zero
+        let mut input = vec![0u8; 64];
+        input[0] = 1;
+        assert!(execute("bls12_map_fp_to_g1", &input).is_err());
+
+        // not less than the modulus
+        assert!(execute("bls12_map_fp_to_g1", &hex(FIELD_MODULUS)).is_err());
+    }
+
+    #[test]
+    fn bls12_pricers() {
+        assert_eq!(*G1_MSM_DISCOUNTS.last().unwrap(), 519);
+        assert_eq!(*G2_MSM_DISCOUNTS.last().unwrap(), 524);
+
+        let g1 = Bls12MsmPricer::g1(12000);
+        assert_eq!(g1.cost(&[]), U256::zero());
+        assert_eq!(g1.cost(&[0u8; 160]), U256::from(12000));
+        assert_eq!(g1.cost(&[0u8; 320]), U256::from(2 * 12000 * 949 / 1000));
+        assert_eq!(
+            g1.cost(&vec![0u8; 160 * 200]),
+            U256::from(200 * 12000 * 519 / 1000)
+        );
+
+        let g2 = Bls12MsmPricer::g2(22500);
+        assert_eq!(g2.cost(&[0u8; 288]), U256::from(22500));
+
+        let pairing = Bls12PairingPricer::new(37700, 32600);
+        assert_eq!(pairing.cost(&[0u8; 768]), U256::from(37700 + 2 * 32600));
+    }
+}
@@ -3,7 +3,10 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
-    block_data_manager::{BlockDataManager, BlockRewardResult, PosRewardInfo},
+    block_data_manager::{
+        BlockDataManager, BlockRewardResult, PosRewardInfo,
+        ADDRESS_ROLE_INTERNAL, ADDRESS_ROLE_RECIPIENT, ADDRESS_ROLE_SENDER,
+    },
     consensus::{
         consensus_inner::{
             consensus_new_block_handler::ConsensusNewBlockHandler,
@@ -15,20 +18,25 @@ use crate::{
     evm::Spec,
     executive::{
         internal_contract::{
-            build_bloom_and_recover_phantom, impls::pos::decode_register_info,
+            build_bloom_and_recover_phantom,
+            impls::{
+                params_control::settle_vote_round, pos::decode_register_info,
+            },
         },
         revert_reason_decode, ExecutionError, ExecutionOutcome, Executive,
-        TransactOptions,
+        ParallelExecutor, TransactOptions,
     },
     machine::Machine,
-    observer::trace::{ExecTrace, TransactionExecTraces},
+    observer::trace::{
+        Action as TraceAction, ExecTrace, TransactionExecTraces,
+    },
     rpc_errors::{invalid_params_check, Result as RpcResult},
     spec::genesis::initialize_internal_contract_accounts,
     state::{
         prefetcher::{
             prefetch_accounts, ExecutionStatePrefetcher, PrefetchTaskHandle,
         },
-        State,
+        State, StateOverride, StateOverrideStore,
     },
     verification::{
         compute_receipts_root, VerificationConfig, VerifyTxLocalMode,
@@ -40,19 +48,23 @@ use crate::{
 use cfx_internal_common::{
     debug::*, EpochExecutionCommitment, StateRootWithAuxInfo,
 };
-use cfx_parameters::consensus::*;
-use cfx_state::{state_trait::*, CleanupMode};
+use cfx_parameters::{
+    consensus::*, consensus_internal::REWARD_EPOCH_COUNT,
+    staking::PARAMS_VOTE_PERIOD,
+};
+use cfx_state::{state_trait::*, CleanupMode, CollateralCheckResult};
 use cfx_statedb::{ErrorKind as DbErrorKind, Result as DbResult, StateDb};
 use cfx_storage::{
     defaults::DEFAULT_EXECUTION_PREFETCH_THREADS, StateIndex,
-    StorageManagerTrait,
+    StorageManagerTrait, StorageState,
 };
 use cfx_types::{
-    address_util::AddressUtil, AddressSpaceUtil, AllChainID, BigEndianHash,
-    Space, H160, H256, KECCAK_EMPTY_BLOOM, U256, U512,
+    address_util::AddressUtil, AddressSpaceUtil, AddressWithSpace, AllChainID,
+    BigEndianHash, Space, H160, H256, KECCAK_EMPTY_BLOOM, U256, U512,
 };
 use core::convert::TryFrom;
 use hash::KECCAK_EMPTY_LIST_RLP;
+use lru_time_cache::LruCache;
 use metrics::{register_meter_with_group, Meter, MeterTimer};
 use parking_lot::{Mutex, RwLock};
 use primitives::{
@@ -795,6 +807,17 @@ impl ConsensusExecutor {
     }
 }
 
+/// The number of historical states reconstructed by re-execution to cache.
+/// Each of them holds the changes of up to an archive interval of epochs.
+const RECONSTRUCTED_STATE_CACHE_SIZE: usize = 16;
+
+/// The state of a historical pivot epoch reconstructed by re-execution, as
+/// the key values changed from the kept state of an earlier pivot epoch.
+struct ReconstructedState {
+    base_epoch: H256,
+    changes: BTreeMap<Vec<u8>, Option<Arc<[u8]>>>,
+}
+
 pub struct ConsensusExecutionHandler {
     tx_pool: SharedTransactionPool,
     data_man: Arc<BlockDataManager>,
@@ -803,6 +826,14 @@ pub struct ConsensusExecutionHandler {
     machine: Arc<Machine>,
     pos_verifier: Arc<PosVerifier>,
     execution_state_prefetcher: Option<Arc<ExecutionStatePrefetcher>>,
+    parallel_executor: Option<ParallelExecutor>,
+    /// State overrides requested by the dev mode RPCs. They are applied
+    /// before executing the transactions of the next epoch on the local
+    /// pivot chain, and again whenever that epoch is re-executed.
+    state_overrides: Mutex<StateOverrideStore>,
+    /// The historical states recently reconstructed in archive mode, keyed
+    /// by the pivot epoch hash.
+    reconstructed_states: Mutex<LruCache<H256, Arc<ReconstructedState>>>,
 }
 
 impl ConsensusExecutionHandler {
@@ -813,6 +844,17 @@ impl ConsensusExecutionHandler {
         pos_verifier: Arc<PosVerifier>,
     ) -> Self
     {
+        let parallel_executor = if config.parallel_execution_threads > 0 {
+            Some(
+                ParallelExecutor::new(config.parallel_execution_threads)
+                    .expect(
+                        // Do not accept error at starting up.
+                        &concat!(file!(), ":", line!(), ":", column!()),
+                    ),
+            )
+        } else {
+            None
+        };
         ConsensusExecutionHandler {
             tx_pool,
             data_man,
@@ -835,6 +877,39 @@ impl ConsensusExecutionHandler {
             } else {
                 None
             },
+            parallel_executor,
+            state_overrides: Default::default(),
+            reconstructed_states: Mutex::new(LruCache::with_capacity(
+                RECONSTRUCTED_STATE_CACHE_SIZE,
+            )),
+        }
+    }
+
+    /// Queues a state override for the dev mode RPCs. The override is not
+    /// visible until the next epoch on the local pivot chain is executed.
+    pub fn add_state_override(&self, state_override: StateOverride) {
+        self.state_overrides.lock().add(state_override);
+    }
+
+    fn apply_state_overrides(
+        &self, state: &mut State, epoch_hash: &H256, block_number: u64,
+    ) {
+        let account_start_nonce =
+            self.machine.spec(block_number).account_start_nonce;
+        let overrides = self.state_overrides.lock().take_for_epoch(epoch_hash);
+        for state_override in overrides {
+            match state
+                .apply_override(&state_override, account_start_nonce)
+                .expect("db error")
+            {
+                CollateralCheckResult::Valid => {
+                    debug!("Apply state override {:?}", state_override)
+                }
+                res => warn!(
+                    "Discard state override {:?}: {:?}",
+                    state_override, res
+                ),
+            }
         }
     }
 
@@ -1026,6 +1101,14 @@ impl ConsensusExecutionHandler {
         ))
         .expect("Failed to initialize state");
 
+        if on_local_pivot && debug_record.is_none() {
+            self.apply_state_overrides(
+                &mut state,
+                epoch_hash,
+                start_block_number,
+            );
+        }
+
         let epoch_receipts = self
             .process_epoch_transactions(
                 *epoch_hash,
@@ -1033,6 +1116,7 @@ impl ConsensusExecutionHandler {
                 &epoch_blocks,
                 start_block_number,
                 on_local_pivot,
+                true, /* persist_results */
             )
             // TODO: maybe propagate the error all the way up so that the
             // program may restart by itself.
@@ -1056,56 +1140,14 @@ impl ConsensusExecutionHandler {
 
         // TODO(peilun): Specify if we unlock before or after executing the
         // transactions.
-        let maybe_parent_pos_ref = self
-            .data_man
-            .block_header_by_hash(&pivot_block.block_header.parent_hash()) // `None` only for genesis.
-            .and_then(|parent| parent.pos_reference().clone());
-        if self
-            .pos_verifier
-            .is_enabled_at_height(pivot_block.block_header.height())
-            && maybe_parent_pos_ref.is_some()
-            && *pivot_block.block_header.pos_reference() != maybe_parent_pos_ref
-        {
-            let current_pos_ref = pivot_block
-                .block_header
-                .pos_reference()
-                .as_ref()
-                .expect("checked before sync graph insertion");
-            let parent_pos_ref = &maybe_parent_pos_ref.expect("checked");
-            // The pos_reference is continuous, so after seeing a new
-            // pos_reference, we only need to process the new
-            // unlock_txs in it.
-            for (unlock_node_id, votes) in self
-                .pos_verifier
-                .get_unlock_nodes(current_pos_ref, parent_pos_ref)
-            {
-                debug!("unlock node: {:?} {}", unlock_node_id, votes);
-                state
-                    .update_pos_status(unlock_node_id, votes)
-                    .expect("db error");
-            }
-            if let Some((pos_epoch, reward_event)) = self
-                .pos_verifier
-                .get_reward_distribution_event(current_pos_ref, parent_pos_ref)
-                .as_ref()
-                .and_then(|x| x.first())
-            {
-                debug!("distribute_pos_interest: {:?}", reward_event);
-                let account_rewards = state
-                    .distribute_pos_interest(
-                        Box::new(reward_event.rewards()),
-                        self.machine
-                            .spec(current_block_number)
-                            .account_start_nonce,
-                        current_block_number,
-                    )
-                    .expect("db error");
-                self.data_man.insert_pos_reward(
-                    *pos_epoch,
-                    &PosRewardInfo::new(account_rewards, *epoch_hash),
-                )
-            }
-        }
+        self.process_pos_events(
+            &mut state,
+            pivot_block,
+            epoch_hash,
+            current_block_number,
+            /* insert_pos_reward = */ true,
+        )
+        .expect("db error");
 
         // FIXME: We may want to propagate the error up.
         let state_root;
@@ -1150,10 +1192,103 @@ impl ConsensusExecutionHandler {
             .adjust_upper_bound(&pivot_block.block_header);
     }
 
+    /// Unlock the PoS votes and distribute the PoS interest for the new
+    /// pos_reference in the pivot block, if any.
+    fn process_pos_events(
+        &self, state: &mut State, pivot_block: &Block, epoch_hash: &H256,
+        current_block_number: u64, insert_pos_reward: bool,
+    ) -> DbResult<()>
+    {
+        let maybe_parent_pos_ref = self
+            .data_man
+            .block_header_by_hash(&pivot_block.block_header.parent_hash()) // `None` only for genesis.
+            .and_then(|parent| parent.pos_reference().clone());
+        if self
+            .pos_verifier
+            .is_enabled_at_height(pivot_block.block_header.height())
+            && maybe_parent_pos_ref.is_some()
+            && *pivot_block.block_header.pos_reference() != maybe_parent_pos_ref
+        {
+            let current_pos_ref = pivot_block
+                .block_header
+                .pos_reference()
+                .as_ref()
+                .expect("checked before sync graph insertion");
+            let parent_pos_ref = &maybe_parent_pos_ref.expect("checked");
+            // The pos_reference is continuous, so after seeing a new
+            // pos_reference, we only need to process the new
+            // unlock_txs in it.
+            for (unlock_node_id, votes) in self
+                .pos_verifier
+                .get_unlock_nodes(current_pos_ref, parent_pos_ref)
+            {
+                debug!("unlock node: {:?} {}", unlock_node_id, votes);
+                state.update_pos_status(unlock_node_id, votes)?;
+            }
+            if let Some((pos_epoch, reward_event)) = self
+                .pos_verifier
+                .get_reward_distribution_event(current_pos_ref, parent_pos_ref)
+                .as_ref()
+                .and_then(|x| x.first())
+            {
+                debug!("distribute_pos_interest: {:?}", reward_event);
+                let account_rewards = state.distribute_pos_interest(
+                    Box::new(reward_event.rewards()),
+                    self.machine.spec(current_block_number).account_start_nonce,
+                    current_block_number,
+                )?;
+                if insert_pos_reward {
+                    self.data_man.insert_pos_reward(
+                        *pos_epoch,
+                        &PosRewardInfo::new(account_rewards, *epoch_hash),
+                    )
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute the transactions of a block in order, in parallel if
+    /// configured, and return their outcomes.
+    fn execute_block_transactions(
+        &self, state: &mut State, env: &Env, spec: &Spec,
+        transactions: &[Arc<SignedTransaction>],
+    ) -> DbResult<Vec<ExecutionOutcome>>
+    {
+        if let Some(executor) = &self.parallel_executor {
+            return executor.execute_transactions(
+                state,
+                env,
+                self.machine.as_ref(),
+                spec,
+                transactions,
+                self.config.executive_trace,
+            );
+        }
+
+        let mut outcomes = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            let options = if self.config.executive_trace {
+                TransactOptions::with_tracing()
+            } else {
+                TransactOptions::with_no_tracing()
+            };
+            outcomes.push(
+                Executive::new(state, env, self.machine.as_ref(), spec)
+                    .transact(transaction, options)?,
+            );
+        }
+        Ok(outcomes)
+    }
+
+    /// Execute the transactions of `epoch_blocks` on `state`. With
+    /// `persist_results` false, the execution results, traces and staking
+    /// events are not written, e.g. when an epoch is only replayed to rebuild
+    /// a historical state.
     fn process_epoch_transactions(
         &self, epoch_id: EpochId, state: &mut State,
         epoch_blocks: &Vec<Arc<Block>>, start_block_number: u64,
-        on_local_pivot: bool,
+        on_local_pivot: bool, persist_results: bool,
     ) -> DbResult<Vec<Arc<BlockReceipts>>>
     {
         // Prefetch accounts for transactions.
@@ -1205,6 +1340,13 @@ impl ConsensusExecutionHandler {
 
         let mut evm_tx_index = 0;
 
+        let index_addresses =
+            on_local_pivot && self.data_man.address_tx_index_enabled();
+        let index_internal_addresses = index_addresses
+            && self.config.executive_trace
+            && self.data_man.address_tx_index_internal_enabled();
+        let mut address_transactions = Vec::new();
+
         for block in epoch_blocks.iter() {
             let mut cfx_tx_index = 0;
 
@@ -1246,6 +1388,20 @@ impl ConsensusExecutionHandler {
             if !spec.cip43_contract {
                 state.bump_block_number_accumulate_interest();
             }
+            if spec.cip94
+                && env.number != 0
+                && env.number % PARAMS_VOTE_PERIOD == 0
+            {
+                // Saturate instead of panicking, otherwise all the nodes
+                // crash at the same epoch.
+                let default_pow_base_reward = U256::try_from(
+                    self.machine
+                        .params()
+                        .base_reward_in_ucfx(0, env.epoch_height),
+                )
+                .unwrap_or_else(|_| U256::max_value());
+                settle_vote_round(state, env.number, default_pow_base_reward)?;
+            }
             let secondary_reward = state.secondary_reward();
             state.inc_distributable_pos_interest(env.number)?;
             initialize_internal_contract_accounts(
@@ -1258,20 +1414,20 @@ impl ConsensusExecutionHandler {
             last_block_hash = block.hash();
             let mut block_traces: Vec<TransactionExecTraces> =
                 Default::default();
-            for (idx, transaction) in block.transactions.iter().enumerate() {
+            let outcomes = self.execute_block_transactions(
+                state,
+                &env,
+                &spec,
+                &block.transactions,
+            )?;
+            for (idx, (transaction, r)) in
+                block.transactions.iter().zip(outcomes).enumerate()
+            {
                 let tx_outcome_status;
                 let mut transaction_logs = Vec::new();
                 let mut storage_released = Vec::new();
                 let mut storage_collateralized = Vec::new();
-
-                let options = if self.config.executive_trace {
-                    TransactOptions::with_tracing()
-                } else {
-                    TransactOptions::with_no_tracing()
-                };
-                let r =
-                    Executive::new(state, &env, self.machine.as_ref(), &spec)
-                        .transact(transaction, options)?;
+                let mut internal_participants = Vec::new();
 
                 let gas_fee;
                 let mut gas_sponsor_paid = false;
@@ -1331,6 +1487,10 @@ impl ConsensusExecutionHandler {
                         };
                         env.accumulated_gas_used += executed.gas_used;
                         gas_fee = executed.fee;
+                        if index_internal_addresses {
+                            internal_participants =
+                                trace_participants(&executed.trace);
+                        }
                         if self.config.executive_trace {
                             block_traces.push(executed.trace.into());
                         }
@@ -1361,6 +1521,10 @@ impl ConsensusExecutionHandler {
 
                         trace!("tx executed successfully: result={:?}, transaction={:?}, in block {:?}", executed, transaction, block.hash());
 
+                        if index_internal_addresses {
+                            internal_participants =
+                                trace_participants(&executed.trace);
+                        }
                         if self.config.executive_trace {
                             block_traces.push(executed.trace.into());
                         }
@@ -1429,6 +1593,28 @@ impl ConsensusExecutionHandler {
                         },
                     );
 
+                    if index_addresses {
+                        address_transactions.push((
+                            transaction.sender(),
+                            hash,
+                            ADDRESS_ROLE_SENDER,
+                        ));
+                        if let Action::Call(to) = transaction.action() {
+                            address_transactions.push((
+                                to.with_space(transaction.space()),
+                                hash,
+                                ADDRESS_ROLE_RECIPIENT,
+                            ));
+                        }
+                        for address in internal_participants {
+                            address_transactions.push((
+                                address,
+                                hash,
+                                ADDRESS_ROLE_INTERNAL,
+                            ));
+                        }
+                    }
+
                     // note: the lock on chain_id is never held
                     // so this should be OK.
                     let evm_chain_id = self
@@ -1461,7 +1647,7 @@ impl ConsensusExecutionHandler {
                 }
             }
 
-            if self.config.executive_trace {
+            if persist_results && self.config.executive_trace {
                 self.data_man.insert_block_traces(
                     block.hash(),
                     block_traces.into(),
@@ -1476,16 +1662,18 @@ impl ConsensusExecutionHandler {
                 secondary_reward,
                 tx_execution_error_messages: tx_exec_error_messages,
             });
-            self.data_man.insert_block_execution_result(
-                block.hash(),
-                pivot_block.hash(),
-                block_receipts.clone(),
-                on_local_pivot,
-            );
+            if persist_results {
+                self.data_man.insert_block_execution_result(
+                    block.hash(),
+                    pivot_block.hash(),
+                    block_receipts.clone(),
+                    on_local_pivot,
+                );
+            }
 
             epoch_receipts.push(block_receipts);
         }
-        if self.pos_verifier.pos_option().is_some() {
+        if persist_results && self.pos_verifier.pos_option().is_some() {
             debug!(
                 "put_staking_events: {:?} height={} len={}",
                 pivot_block.hash(),
@@ -1509,14 +1697,34 @@ impl ConsensusExecutionHandler {
         if on_local_pivot {
             self.tx_pool.recycle_transactions(to_pending);
         }
+        if index_addresses {
+            self.data_man.insert_address_transactions(
+                pivot_block.block_header.height(),
+                address_transactions,
+            );
+        }
+        if on_local_pivot {
+            self.data_man.insert_epoch_log_blooms(
+                pivot_block.block_header.height(),
+                &epoch_receipts,
+            );
+        }
 
         debug!("Finish processing tx for epoch");
         Ok(epoch_receipts)
     }
 
     fn compute_block_base_reward(
-        &self, past_block_count: u64, pivot_height: u64,
-    ) -> U512 {
+        &self, past_block_count: u64, pivot_height: u64, state: &State,
+        spec: &Spec,
+    ) -> U512
+    {
+        // The base reward is decided by the votes of stakers after CIP-94,
+        // once the first voting round is applied.
+        let voted_base_reward = state.pow_base_reward();
+        if spec.cip94 && !voted_base_reward.is_zero() {
+            return voted_base_reward.into();
+        }
         self.machine
             .params()
             .base_reward_in_ucfx(past_block_count, pivot_height)
@@ -1547,6 +1755,8 @@ impl ConsensusExecutionHandler {
         let base_reward_per_block = self.compute_block_base_reward(
             reward_info.past_block_count,
             pivot_block.block_header.height(),
+            state,
+            &spec,
         );
 
         // Base reward and anticone penalties.
@@ -1866,9 +2076,282 @@ impl ConsensusExecutionHandler {
             &epoch_blocks,
             start_block_number,
             false,
+            true, /* persist_results */
         )
     }
 
+    /// Return the state of the pivot epoch `epoch_id` at `height` below the
+    /// state availability lower bound in archive mode. If the state is not
+    /// kept, it's reconstructed by re-execution.
+    pub fn get_historical_state_db(
+        &self, epoch_id: &H256, height: u64,
+    ) -> RpcResult<StateDb> {
+        if self.pivot_hash_from_db(height) != Some(*epoch_id) {
+            bail!(format!(
+                "State for epoch (number={:?} hash={:?}) does not exist: \
+                 not on the pivot chain",
+                height, epoch_id
+            ));
+        }
+        if let Some(storage) = self.get_readonly_storage_state(epoch_id)? {
+            return Ok(StateDb::new(storage));
+        }
+        let state = self.reconstruct_state(epoch_id, height)?;
+        let storage = self
+            .get_readonly_storage_state(&state.base_epoch)?
+            .ok_or_else(|| {
+                format!(
+                    "State for epoch {:?} to reconstruct from is deleted",
+                    state.base_epoch
+                )
+            })?;
+        Ok(StateDb::new_with_changes(storage, &state.changes))
+    }
+
+    fn pivot_hash_from_db(&self, height: u64) -> Option<H256> {
+        self.data_man
+            .executed_epoch_set_hashes_from_db(height)
+            .and_then(|hashes| hashes.last().cloned())
+    }
+
+    fn get_readonly_storage_state(
+        &self, epoch_id: &H256,
+    ) -> RpcResult<Option<StorageState>> {
+        match self.data_man.get_state_readonly_index(epoch_id) {
+            Some(state_index) => Ok(self
+                .data_man
+                .storage_manager
+                .get_state_no_commit(state_index, /* try_open = */ true)?),
+            None => Ok(None),
+        }
+    }
+
+    /// Reconstruct the state of a historical pivot epoch by re-executing the
+    /// epochs after the nearest pivot epoch whose state is kept or cached.
+    fn reconstruct_state(
+        &self, epoch_id: &H256, height: u64,
+    ) -> RpcResult<Arc<ReconstructedState>> {
+        if let Some(state) = self.reconstructed_states.lock().get(epoch_id) {
+            return Ok(state.clone());
+        }
+
+        // The kept states are at most one interval and two snapshot periods
+        // apart, see `extra_snapshots_to_keep_predicate`.
+        let max_epochs = self.data_man.archive_state_epoch_interval()
+            + 2 * self.data_man.get_snapshot_epoch_count() as u64;
+        let mut epochs_to_execute = vec![(height, *epoch_id)];
+        let mut base = None;
+        for base_height in (height.saturating_sub(max_epochs)..height).rev() {
+            let base_hash = match self.pivot_hash_from_db(base_height) {
+                Some(hash) => hash,
+                None => break,
+            };
+            if let Some(state) =
+                self.reconstructed_states.lock().get(&base_hash)
+            {
+                base = Some((state.base_epoch, state.changes.clone()));
+                break;
+            }
+            if let Ok(Some(_)) = self.get_readonly_storage_state(&base_hash) {
+                base = Some((base_hash, BTreeMap::new()));
+                break;
+            }
+            epochs_to_execute.push((base_height, base_hash));
+        }
+        let (base_epoch, mut changes) = match base {
+            Some(base) => base,
+            None => bail!(format!(
+                "State for epoch (number={:?} hash={:?}) does not exist: \
+                 no state to reconstruct from",
+                height, epoch_id
+            )),
+        };
+        debug!(
+            "Reconstruct state epoch_id={:?} from base_epoch={:?} by \
+             re-executing {} epochs",
+            epoch_id,
+            base_epoch,
+            epochs_to_execute.len()
+        );
+
+        let storage = self
+            .get_readonly_storage_state(&base_epoch)?
+            .ok_or_else(|| {
+                format!(
+                    "State for epoch {:?} to reconstruct from is deleted",
+                    base_epoch
+                )
+            })?;
+        let mut state_db = StateDb::new_with_changes(storage, &changes);
+        for (epoch_height, epoch_hash) in epochs_to_execute.into_iter().rev() {
+            state_db =
+                self.reexecute_epoch(state_db, epoch_height, &epoch_hash)?;
+        }
+        changes.extend(state_db.modified_entries());
+
+        let state = Arc::new(ReconstructedState {
+            base_epoch,
+            changes,
+        });
+        self.reconstructed_states
+            .lock()
+            .insert(*epoch_id, state.clone());
+        Ok(state)
+    }
+
+    /// Re-execute the pivot epoch `epoch_hash` at `height` over `state_db`
+    /// without committing it or writing any execution results. The rewards
+    /// are replayed from the stored reward results, because the consensus
+    /// graph to compute them is gone.
+    fn reexecute_epoch(
+        &self, state_db: StateDb, height: u64, epoch_hash: &H256,
+    ) -> RpcResult<StateDb> {
+        let epoch_block_hashes = self
+            .data_man
+            .executed_epoch_set_hashes_from_db(height)
+            .ok_or_else(|| {
+                format!("Blocks of epoch {:?} not found", epoch_hash)
+            })?;
+        let epoch_blocks = self
+            .data_man
+            .blocks_by_hash_list(
+                &epoch_block_hashes,
+                false, /* update_cache */
+            )
+            .ok_or_else(|| {
+                format!("Block bodies of epoch {:?} not found", epoch_hash)
+            })?;
+        let start_block_number = self
+            .data_man
+            .get_epoch_execution_context(epoch_hash)
+            .ok_or_else(|| {
+                format!("Execution context of epoch {:?} not found", epoch_hash)
+            })?
+            .start_block_number;
+
+        let mut state = State::new(state_db)?;
+        let epoch_receipts = self.process_epoch_transactions(
+            *epoch_hash,
+            &mut state,
+            &epoch_blocks,
+            start_block_number,
+            false, /* on_local_pivot */
+            false, /* persist_results */
+        )?;
+        let current_block_number =
+            start_block_number + epoch_receipts.len() as u64 - 1;
+        if height > REWARD_EPOCH_COUNT {
+            self.replay_rewards_and_fees(
+                &mut state,
+                height - REWARD_EPOCH_COUNT,
+                epoch_hash,
+                &self.machine.spec(current_block_number),
+            )?;
+        }
+        self.process_pos_events(
+            &mut state,
+            epoch_blocks.last().expect("Not empty"),
+            epoch_hash,
+            current_block_number,
+            /* insert_pos_reward = */ false,
+        )?;
+        Ok(state.into_flushed_db()?)
+    }
+
+    /// Give the rewards of the pivot epoch at `reward_height` as
+    /// `process_rewards_and_fees` did in the epoch `epoch_later`, with the
+    /// block reward results stored then.
+    fn replay_rewards_and_fees(
+        &self, state: &mut State, reward_height: u64, epoch_later: &H256,
+        spec: &Spec,
+    ) -> RpcResult<()>
+    {
+        let reward_block_hashes = self
+            .data_man
+            .executed_epoch_set_hashes_from_db(reward_height)
+            .ok_or_else(|| {
+                format!("Blocks of epoch {} not found", reward_height)
+            })?;
+        let reward_epoch_hash = *reward_block_hashes.last().expect("Not empty");
+        let reward_blocks = self
+            .data_man
+            .blocks_by_hash_list(
+                &reward_block_hashes,
+                false, /* update_cache */
+            )
+            .ok_or_else(|| {
+                format!("Block bodies of epoch {} not found", reward_height)
+            })?;
+
+        let mut merged_rewards = BTreeMap::new();
+        // The base rewards and the secondary rewards.
+        let mut new_mint = U256::zero();
+        let mut distributed_fee = U256::zero();
+        // The same transaction is charged only once in an epoch.
+        let mut tx_fee = HashMap::new();
+        for block in &reward_blocks {
+            let block_hash = block.hash();
+            let reward = self
+                .data_man
+                .block_reward_result_by_hash_with_epoch(
+                    &block_hash,
+                    epoch_later,
+                    false, /* update_pivot_assumption */
+                    false, /* update_cache */
+                )
+                .ok_or_else(|| {
+                    format!("Reward result of block {:?} not found", block_hash)
+                })?;
+            *merged_rewards
+                .entry(*block.block_header.author())
+                .or_insert(U256::zero()) += reward.total_reward;
+            new_mint += reward.total_reward - reward.tx_fee;
+            distributed_fee += reward.tx_fee;
+
+            let block_receipts = self
+                .data_man
+                .block_execution_result_by_hash_with_epoch(
+                    &block_hash,
+                    &reward_epoch_hash,
+                    false, /* update_pivot_assumption */
+                    false, /* update_cache */
+                )
+                .ok_or_else(|| {
+                    format!("Receipts of block {:?} not found", block_hash)
+                })?
+                .block_receipts;
+            for (tx, receipt) in
+                block.transactions.iter().zip(&block_receipts.receipts)
+            {
+                let fee = tx_fee.entry(tx.hash()).or_insert(U256::zero());
+                if fee.is_zero() {
+                    *fee = receipt.gas_fee;
+                }
+            }
+        }
+        // The fees not shared by any block are burnt.
+        let total_fee =
+            tx_fee.values().fold(U256::zero(), |sum, fee| sum + *fee);
+        let burnt_fee = total_fee - distributed_fee;
+
+        for (address, reward) in merged_rewards {
+            if spec.is_valid_address(&address) {
+                state.add_balance(
+                    &address.with_native_space(),
+                    &reward,
+                    CleanupMode::ForceCreate,
+                    spec.account_start_nonce,
+                )?;
+            }
+        }
+        if new_mint >= burnt_fee {
+            state.add_total_issued(new_mint - burnt_fee);
+        } else {
+            state.subtract_total_issued(burnt_fee - new_mint);
+        }
+        Ok(())
+    }
+
     pub fn call_virtual(
         &self, tx: &SignedTransaction, epoch_id: &H256, epoch_size: usize,
     ) -> RpcResult<ExecutionOutcome> {
@@ -1909,24 +2392,35 @@ impl ConsensusExecutionHandler {
         // expire.
         let state_availability_boundary =
             self.data_man.state_availability_boundary.read();
-        if !state_availability_boundary
+        trace!("best_block_header: {:?}", best_block_header);
+        let time_stamp = best_block_header.timestamp();
+        let state_db = if state_availability_boundary
             .check_availability(best_block_header.height(), epoch_id)
         {
+            let state_index = self.data_man.get_state_readonly_index(epoch_id);
+            let state_db = StateDb::new(
+                self.data_man
+                    .storage_manager
+                    .get_state_no_commit(
+                        state_index.unwrap(),
+                        /* try_open = */ true,
+                    )?
+                    .ok_or("state deleted")?,
+            );
+            drop(state_availability_boundary);
+            state_db
+        } else if self.data_man.archive_historical_state()
+            && best_block_header.height()
+                < state_availability_boundary.lower_bound
+        {
+            // The historical states are kept forever or reconstructed from
+            // the ones kept forever, so the lock is not needed.
+            drop(state_availability_boundary);
+            self.get_historical_state_db(epoch_id, best_block_header.height())?
+        } else {
             bail!("state is not ready");
-        }
-        let state_index = self.data_man.get_state_readonly_index(epoch_id);
-        trace!("best_block_header: {:?}", best_block_header);
-        let time_stamp = best_block_header.timestamp();
-        let mut state = State::new(StateDb::new(
-            self.data_man
-                .storage_manager
-                .get_state_no_commit(
-                    state_index.unwrap(),
-                    /* try_open = */ true,
-                )?
-                .ok_or("state deleted")?,
-        ))?;
-        drop(state_availability_boundary);
+        };
+        let mut state = State::new(state_db)?;
 
         let author = {
             let mut address = H160::random();
@@ -1987,6 +2481,142 @@ impl ConsensusExecutionHandler {
     }
 }
 
+/// The addresses which are called, created, or send calls in the execution
+/// trace of a transaction.
+pub(crate) fn trace_participants(
+    traces: &[ExecTrace],
+) -> Vec<AddressWithSpace> {
+    let mut participants = Vec::new();
+    // `CreateResult` does not record the space, which is the same as the
+    // matching `Create`.
+    let mut create_spaces = Vec::new();
+    for trace in traces {
+        match &trace.action {
+            TraceAction::Call(call) => {
+                participants.push(call.from.with_space(call.space));
+                participants.push(call.to.with_space(call.space));
+            }
+            TraceAction::Create(create) => {
+                participants.push(create.from.with_space(create.space));
+                create_spaces.push(create.space);
+            }
+            TraceAction::CreateResult(result) => {
+                if let Some(space) = create_spaces.pop() {
+                    if !result.addr.is_zero() {
+                        participants.push(result.addr.with_space(space));
+                    }
+                }
+            }
+            TraceAction::CallResult(_)
+            | TraceAction::InternalTransferAction(_) => {}
+        }
+    }
+    participants
+}
+
 pub struct ConsensusExecutionConfiguration {
     pub executive_trace: bool,
+    /// The number of threads to execute the transactions in a block in
+    /// parallel. The transactions are executed sequentially if it's 0.
+    pub parallel_execution_threads: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        block_data_manager::DbType,
+        pow::PowComputer,
+        sync::utils::{
+            create_simple_block, initialize_data_manager,
+            initialize_executing_synchronization_graph_with_data_manager,
+        },
+        vm_factory::VmFactory,
+    };
+    use cfx_statedb::StateDb;
+    use std::{sync::Arc, thread, time::Duration};
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_reexecute_epoch() {
+        const CHAIN_LENGTH: u64 = 25;
+        // Above `REWARD_EPOCH_COUNT`, so that the rewards are replayed.
+        const EPOCH_HEIGHT: u64 = 15;
+
+        let db_dir = TempDir::new("test_reexecute_epoch").unwrap();
+        let pow = Arc::new(PowComputer::new(true));
+        let vm = VmFactory::new(1024 * 32);
+        let (data_man, genesis_block) = initialize_data_manager(
+            db_dir.path().to_str().unwrap(),
+            DbType::Rocksdb,
+            pow.clone(),
+            vm.clone(),
+        );
+        let (sync, consensus) =
+            initialize_executing_synchronization_graph_with_data_manager(
+                data_man,
+                1,    /* beta */
+                1,    /* h */
+                1,    /* tcr */
+                1,    /* tcb */
+                5000, /* era_epoch_count */
+                pow,
+                vm,
+            );
+
+        let mut pivot_hashes = vec![genesis_block.hash()];
+        for height in 1..=CHAIN_LENGTH {
+            let (hash, mut block) = create_simple_block(
+                sync.clone(),
+                *pivot_hashes.last().unwrap(),
+                vec![],
+                height,
+                1,     /* block_weight */
+                false, /* adaptive */
+            );
+            sync.insert_block_header(
+                &mut block.block_header,
+                false, /* need_to_verify */
+                true,  /* bench_mode */
+                false, /* insert_to_consensus */
+                true,  /* persistent */
+            );
+            sync.insert_block(
+                block, false, /* need_to_verify */
+                true,  /* persistent */
+                false, /* recover_from_db */
+            );
+            pivot_hashes.push(hash);
+        }
+        while sync.is_consensus_worker_busy() {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let epoch_hash = pivot_hashes[EPOCH_HEIGHT as usize];
+        let committed_state_root = consensus
+            .executor
+            .wait_for_result(epoch_hash)
+            .unwrap()
+            .state_root_with_aux_info
+            .state_root;
+
+        // Replay the epoch over the state of its parent.
+        let handler = &consensus.executor.handler;
+        let parent_storage = handler
+            .get_readonly_storage_state(
+                &pivot_hashes[EPOCH_HEIGHT as usize - 1],
+            )
+            .unwrap()
+            .unwrap();
+        let mut state_db = handler
+            .reexecute_epoch(
+                StateDb::new(parent_storage),
+                EPOCH_HEIGHT,
+                &epoch_hash,
+            )
+            .unwrap();
+        assert_eq!(
+            state_db.compute_state_root(None).unwrap().state_root,
+            committed_state_root
+        );
+    }
 }
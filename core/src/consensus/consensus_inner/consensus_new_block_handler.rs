@@ -5,7 +5,7 @@
 use super::blame_verifier::BlameVerifier;
 use crate::{
     block_data_manager::{BlockDataManager, BlockStatus, LocalBlockInfo},
-    channel::Channel,
+    channel::{Channel, Receiver},
     consensus::{
         consensus_inner::{
             confirmation_meter::ConfirmationMeter,
@@ -81,6 +81,15 @@ impl ConsensusNewBlockHandler {
         }
     }
 
+    /// Subscribes to the epochs ordered by the pivot chain updates.
+    pub fn subscribe_epochs(&self) -> Receiver<(u64, Vec<H256>)> {
+        self.epochs_sender.subscribe()
+    }
+
+    pub fn unsubscribe_epochs(&self, id: u64) {
+        self.epochs_sender.unsubscribe(id);
+    }
+
     /// Return (old_era_block_set, new_era_block_set).
     /// `old_era_block_set` includes the blocks in the past of
     /// `new_era_block_arena_index`. `new_era_block_set` includes all other
@@ -574,6 +574,40 @@ impl ConsensusGraphNode {
     pub fn era_block(&self) -> usize { self.era_block }
 }
 
+/// The consensus information of a block in the current consensus graph, which
+/// is used to inspect the DAG for debugging, e.g., investigating reorgs.
+#[derive(Clone, Debug)]
+pub struct BlockConsensusInfo {
+    pub hash: H256,
+    pub height: u64,
+    pub parent_hash: H256,
+    pub referee_hashes: Vec<H256>,
+    /// The genesis of the era that the block is in, or `None` if the block is
+    /// not in the subtree of the current era genesis.
+    pub era_block_hash: Option<H256>,
+    /// The epoch that the block is in, or `None` if it has not been assigned.
+    pub epoch_number: Option<u64>,
+    pub is_pivot: bool,
+    pub is_timer: bool,
+    /// Whether the block is on the current longest timer chain.
+    pub is_on_timer_chain: bool,
+    pub is_heavy: bool,
+    pub adaptive: bool,
+    pub partial_invalid: bool,
+    pub pending: bool,
+    pub blame: u32,
+    /// The weight of the block itself, in which adaptive blocks are weighted
+    /// as zero unless they are heavy.
+    pub weight: i128,
+    /// The total weight of the subtree rooted at the block.
+    pub subtree_weight: i128,
+    /// The total number of executed blocks in its past.
+    pub past_size: u64,
+    /// The number of blocks in its anticone in the current graph, or `None`
+    /// if it is not requested. It is computed by traversing the whole graph.
+    pub anticone_size: Option<usize>,
+}
+
 impl ConsensusGraphInner {
     pub fn with_era_genesis(
         pow_config: ProofOfWorkConfig, pow: Arc<PowComputer>,
@@ -2353,6 +2387,106 @@ impl ConsensusGraphInner {
             .and_then(|index| Some(self.arena[*index].data.pending))
     }
 
+    pub fn block_consensus_info(
+        &self, block_hash: &H256, with_anticone: bool,
+    ) -> Option<BlockConsensusInfo> {
+        self.hash_to_arena_indices.get(block_hash).map(|index| {
+            self.block_consensus_info_by_index(*index, with_anticone)
+        })
+    }
+
+    /// Return the consensus information of the blocks in the epochs
+    /// `[from_epoch, to_epoch]`, in which the blocks of each epoch are in
+    /// execution order with the pivot block at last. Only the epochs of the
+    /// current era are available.
+    pub fn epoch_range_consensus_info(
+        &self, from_epoch: u64, to_epoch: u64, with_anticone: bool,
+    ) -> Result<Vec<BlockConsensusInfo>, String> {
+        if from_epoch > to_epoch {
+            return Err(format!(
+                "Invalid epoch range [{}, {}]",
+                from_epoch, to_epoch
+            ));
+        }
+        if from_epoch < self.cur_era_genesis_height {
+            return Err(format!(
+                "Epoch {} is before the current era genesis {}",
+                from_epoch, self.cur_era_genesis_height
+            ));
+        }
+        let mut blocks = Vec::new();
+        for epoch_number in from_epoch..=to_epoch {
+            let pivot_arena_index =
+                self.get_arena_index_from_epoch_number(epoch_number)?;
+            // The epoch set of the current era genesis is not maintained.
+            if pivot_arena_index == self.cur_era_genesis_block_arena_index {
+                blocks.push(self.block_consensus_info_by_index(
+                    pivot_arena_index,
+                    with_anticone,
+                ));
+                continue;
+            }
+            for index in
+                self.get_ordered_executable_epoch_blocks(pivot_arena_index)
+            {
+                blocks.push(
+                    self.block_consensus_info_by_index(*index, with_anticone),
+                );
+            }
+        }
+        Ok(blocks)
+    }
+
+    fn block_consensus_info_by_index(
+        &self, index: usize, with_anticone: bool,
+    ) -> BlockConsensusInfo {
+        let node = &self.arena[index];
+        let block_header = self
+            .data_man
+            .block_header_by_hash(&node.hash)
+            .expect("header exist");
+        let is_pivot = node.height >= self.cur_era_genesis_height
+            && self
+                .pivot_chain
+                .get(self.height_to_pivot_index(node.height))
+                == Some(&index);
+        let anticone_size = if with_anticone {
+            Some(consensus_new_block_handler::ConsensusNewBlockHandler::compute_anticone_bruteforce(
+                self, index,
+            )
+            .iter()
+            .count())
+        } else {
+            None
+        };
+        BlockConsensusInfo {
+            hash: node.hash,
+            height: node.height,
+            parent_hash: *block_header.parent_hash(),
+            referee_hashes: block_header.referee_hashes().clone(),
+            era_block_hash: match node.era_block {
+                NULL => None,
+                era_block => Some(self.arena[era_block].hash),
+            },
+            epoch_number: match node.data.epoch_number {
+                NULLU64 => None,
+                epoch => Some(epoch),
+            },
+            is_pivot,
+            is_timer: node.is_timer,
+            is_on_timer_chain: self.get_timer_chain_index(index) != NULL,
+            is_heavy: node.is_heavy,
+            adaptive: node.adaptive,
+            partial_invalid: node.data.partial_invalid,
+            pending: node.data.pending,
+            blame: block_header.blame(),
+            weight: self.block_weight(index),
+            subtree_weight: self.weight_tree.get(index),
+            past_size: node.past_num_blocks,
+            anticone_size,
+        }
+    }
+
     pub fn get_transaction_info(
         &self, tx_hash: &H256,
     ) -> Option<TransactionInfo> {
@@ -40,7 +40,7 @@ use crate::{
     },
     pow::{PowComputer, ProofOfWorkConfig},
     rpc_errors::{invalid_params_check, Result as RpcResult},
-    state::State,
+    state::{State, StateOverride},
     statistics::SharedStatistics,
     transaction_pool::SharedTransactionPool,
     verification::VerificationConfig,
@@ -57,7 +57,6 @@ use cfx_statedb::StateDb;
 use cfx_storage::state_manager::StateManagerTrait;
 use cfx_types::{AddressWithSpace, AllChainID, Bloom, Space, H256, U256};
 use either::Either;
-use itertools::Itertools;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use metrics::{
@@ -67,7 +66,7 @@ use parking_lot::{Mutex, RwLock};
 use primitives::{
     compute_block_number,
     epoch::BlockHashOrEpochNumber,
-    filter::{FilterError, LogFilter},
+    filter::{FilterError, LogCursor, LogFilter},
     log_entry::LocalizedLogEntry,
     pos::PosBlockId,
     receipt::Receipt,
@@ -84,7 +83,7 @@ use std::{
         Arc,
     },
     thread::sleep,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 lazy_static! {
@@ -617,6 +616,44 @@ impl ConsensusGraph {
         self.best_info.read_recursive().best_block_hash
     }
 
+    /// Waits until `hash` becomes the best block. The check is triggered by
+    /// the epochs ordered in consensus, and the wait fails after `timeout`,
+    /// e.g. if other blocks are generated concurrently and take over the
+    /// pivot chain.
+    pub fn wait_for_best_block(
+        &self, hash: &H256, timeout: Duration,
+    ) -> Result<(), String> {
+        // `best_info` is updated after the epochs are sent, so a notification
+        // may arrive before the new best block is visible.
+        const RECHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+        let mut epochs = self.new_block_handler.subscribe_epochs();
+        let deadline = Instant::now() + timeout;
+        let result = loop {
+            if self.best_block_hash() == *hash {
+                break Ok(());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break Err(format!(
+                    "block {:?} is not the best block after {:?}",
+                    hash, timeout
+                ));
+            }
+            // Both a notification and a timeout lead to a recheck.
+            let wait = min(deadline - now, RECHECK_INTERVAL);
+            epochs.recv_with_timeout(wait).ok();
+        };
+        self.new_block_handler.unsubscribe_epochs(epochs.id);
+        result
+    }
+
+    /// Modifies the state without a transaction, which is only used in dev
+    /// mode. See `ConsensusExecutionHandler::add_state_override`.
+    pub fn add_state_override(&self, state_override: StateOverride) {
+        self.executor.handler.add_state_override(state_override);
+    }
+
     /// Returns the latest epoch whose state can be exposed safely, which means
     /// its state is available and it's not only visible to optimistic
     /// execution.
@@ -956,10 +993,13 @@ impl ConsensusGraph {
         Ok(epoch_batch_logs.into_iter().flatten().collect())
     }
 
+    /// Resolve the epoch range of a log filter. If the log bloom index is
+    /// enabled, the size of the range is not limited here, see
+    /// `log_filter_epoch_batches`.
     pub fn get_log_filter_epoch_range(
         &self, from_epoch: EpochNumber, to_epoch: EpochNumber,
         check_range: bool,
-    ) -> Result<impl Iterator<Item = u64>, FilterError>
+    ) -> Result<(u64, u64), FilterError>
     {
         // lock so that we have a consistent view
         let _inner = self.inner.read_recursive();
@@ -982,7 +1022,7 @@ impl ConsensusGraph {
             });
         }
 
-        if check_range {
+        if check_range && !self.data_man.log_bloom_index_enabled() {
             if let Some(max_gap) = self.config.get_logs_filter_max_epoch_range {
                 // The range includes both ends.
                 if to_epoch - from_epoch + 1 > max_gap {
@@ -995,7 +1035,63 @@ impl ConsensusGraph {
             }
         }
 
-        return Ok((from_epoch..=to_epoch).rev());
+        return Ok((from_epoch, to_epoch));
+    }
+
+    /// Split the epochs in `[from_epoch, to_epoch]` into batches, from the
+    /// newest to the oldest. If the log bloom index is enabled, the epochs
+    /// without matching logs are skipped, and `check_range` limits the number
+    /// of the remaining epochs instead of the size of the range.
+    fn log_filter_epoch_batches<'a>(
+        &'a self, space: Space, bloom_possibilities: &'a Vec<Bloom>,
+        from_epoch: u64, to_epoch: u64, check_range: bool,
+    ) -> impl Iterator<Item = Result<Vec<u64>, FilterError>> + 'a
+    {
+        let batch_size = self.config.get_logs_epoch_batch_size;
+        let use_index = self.data_man.log_bloom_index_enabled();
+        let max_epochs = if check_range && use_index {
+            self.config.get_logs_filter_max_epoch_range
+        } else {
+            None
+        };
+        let mut next = Some(to_epoch);
+        let mut scanned = 0;
+
+        std::iter::from_fn(move || {
+            let to = next.filter(|to| *to >= from_epoch)?;
+            let epochs: Vec<u64> = if use_index {
+                let (epochs, rest) = self.data_man.log_bloom_candidate_epochs(
+                    space,
+                    from_epoch,
+                    to,
+                    bloom_possibilities,
+                    batch_size,
+                );
+                next = rest;
+                epochs
+            } else {
+                let start =
+                    max(from_epoch, (to + 1).saturating_sub(batch_size as u64));
+                next = start.checked_sub(1);
+                (start..=to).rev().collect()
+            };
+            if epochs.is_empty() {
+                return None;
+            }
+
+            scanned += epochs.len() as u64;
+            if let Some(max_epochs) = max_epochs {
+                if scanned > max_epochs {
+                    next = None;
+                    return Some(Err(FilterError::TooManyCandidateEpochs {
+                        from_epoch,
+                        to_epoch,
+                        max_epochs,
+                    }));
+                }
+            }
+            Some(Ok(epochs))
+        })
     }
 
     pub fn get_trace_filter_epoch_range(
@@ -1035,22 +1131,32 @@ impl ConsensusGraph {
         let offset = filter.offset.unwrap_or(0);
         let limit = filter.limit.unwrap_or(::std::usize::MAX);
 
+        let (from_epoch, to_epoch) = self.get_log_filter_epoch_range(
+            from_epoch,
+            to_epoch,
+            !filter.trusted,
+        )?;
+
         // we store the last epoch processed and the corresponding pivot hash so
         // that we can check whether it changed between batches
         let mut consistency_check_data: Option<(u64, H256)> = None;
 
         let mut logs = self
             // iterate over epochs in reverse order
-            .get_log_filter_epoch_range(from_epoch, to_epoch, !filter.trusted)?
             // we process epochs in each batch in parallel
             // but batches are processed one-by-one
-            .chunks(self.config.get_logs_epoch_batch_size)
-            .into_iter()
-            .map(move |epochs| {
+            .log_filter_epoch_batches(
+                filter.space,
+                &bloom_possibilities,
+                from_epoch,
+                to_epoch,
+                !filter.trusted,
+            )
+            .map(|epochs| {
                 self.filter_epoch_batch(
                     &filter,
                     &bloom_possibilities,
-                    epochs.into_iter().collect(),
+                    epochs?,
                     &mut consistency_check_data,
                 )
             })
@@ -1304,6 +1410,109 @@ impl ConsensusGraph {
         }
     }
 
+    /// Return at most `limit` logs matching an epoch log filter, starting from
+    /// `cursor` and going from the newest epoch to the oldest one. The cursor
+    /// of the next page is returned if the page is full. As in `logs`, the
+    /// logs within a page are in ascending order.
+    pub fn logs_page(
+        &self, filter: LogFilter, cursor: Option<LogCursor>, limit: usize,
+    ) -> Result<(Vec<LocalizedLogEntry>, Option<LogCursor>), FilterError>
+    {
+        let (from_epoch, to_epoch) = match &filter {
+            LogFilter::EpochLogFilter {
+                from_epoch,
+                to_epoch,
+                ..
+            } => self.get_log_filter_epoch_range(
+                from_epoch.clone(),
+                to_epoch.clone(),
+                !filter.trusted,
+            )?,
+            _ => bail!(FilterError::Custom(
+                "Only epoch range filters can be paginated".into()
+            )),
+        };
+
+        let to_epoch = match &cursor {
+            None => to_epoch,
+            Some(cursor) => {
+                if cursor.epoch < from_epoch || cursor.epoch > to_epoch {
+                    bail!(FilterError::Custom(format!(
+                        "Cursor epoch {} is out of the filter range",
+                        cursor.epoch
+                    )));
+                }
+                let pivot_hash = self
+                    .inner
+                    .read_recursive()
+                    .get_pivot_hash_from_epoch_number(cursor.epoch)?;
+                if pivot_hash != cursor.pivot_hash {
+                    bail!(FilterError::PivotChainReorg {
+                        epoch: cursor.epoch,
+                        from: cursor.pivot_hash,
+                        to: pivot_hash,
+                    });
+                }
+                cursor.epoch
+            }
+        };
+
+        let bloom_possibilities = filter.bloom_possibilities();
+        let mut consistency_check_data: Option<(u64, H256)> = None;
+        let mut logs = Vec::new();
+        let mut next_cursor = None;
+        // the position of the current log in its epoch, counting from the
+        // newest log
+        let mut current_epoch = None;
+        let mut index_in_epoch = 0;
+
+        'batches: for epochs in self.log_filter_epoch_batches(
+            filter.space,
+            &bloom_possibilities,
+            from_epoch,
+            to_epoch,
+            !filter.trusted,
+        ) {
+            for log in self.filter_epoch_batch(
+                &filter,
+                &bloom_possibilities,
+                epochs?,
+                &mut consistency_check_data,
+            )? {
+                if current_epoch != Some(log.epoch_number) {
+                    current_epoch = Some(log.epoch_number);
+                    index_in_epoch = 0;
+                }
+                index_in_epoch += 1;
+
+                if let Some(cursor) = &cursor {
+                    if cursor.epoch == log.epoch_number
+                        && index_in_epoch <= cursor.skip
+                    {
+                        continue;
+                    }
+                }
+
+                let epoch = log.epoch_number;
+                logs.push(log);
+                if logs.len() >= limit {
+                    next_cursor = Some(LogCursor {
+                        epoch,
+                        pivot_hash: self
+                            .inner
+                            .read_recursive()
+                            .get_pivot_hash_from_epoch_number(epoch)?,
+                        skip: index_in_epoch,
+                    });
+                    break 'batches;
+                }
+            }
+        }
+
+        logs.reverse();
+        Ok((logs, next_cursor))
+    }
+
     // TODO(lpl): Limit epoch range in filter.
     pub fn filter_traces(
         &self, mut filter: TraceFilter,
@@ -1345,20 +1554,33 @@ impl ConsensusGraph {
     fn get_state_db_by_height_and_hash(
         &self, height: u64, hash: &H256,
     ) -> RpcResult<StateDb> {
-        // Keep the lock until we get the desired State, otherwise the State may
-        // expire.
-        let state_availability_boundary =
-            self.data_man.state_availability_boundary.read();
-        if !state_availability_boundary.check_availability(height, &hash) {
-            debug!(
-                "State for epoch (number={:?} hash={:?}) does not exist: out-of-bound {:?}",
-                height, hash, state_availability_boundary
-            );
-            bail!(format!(
-                "State for epoch (number={:?} hash={:?}) does not exist: out-of-bound {:?}",
-                height, hash, state_availability_boundary
-            ));
+        {
+            // Keep the lock until we get the desired State, otherwise the State
+            // may expire.
+            let state_availability_boundary =
+                self.data_man.state_availability_boundary.read();
+            if state_availability_boundary.check_availability(height, &hash) {
+                return self.open_state_db(height, hash);
+            }
+            if !(self.data_man.archive_historical_state()
+                && height < state_availability_boundary.lower_bound)
+            {
+                debug!(
+                    "State for epoch (number={:?} hash={:?}) does not exist: out-of-bound {:?}",
+                    height, hash, state_availability_boundary
+                );
+                bail!(format!(
+                    "State for epoch (number={:?} hash={:?}) does not exist: out-of-bound {:?}",
+                    height, hash, state_availability_boundary
+                ));
+            }
         }
+        // The historical states are kept forever or reconstructed from the
+        // ones kept forever, so the boundary lock is not needed.
+        self.executor.handler.get_historical_state_db(hash, height)
+    }
+
+    fn open_state_db(&self, height: u64, hash: &H256) -> RpcResult<StateDb> {
         let maybe_state_readonly_index =
             self.data_man.get_state_readonly_index(&hash).into();
         let maybe_state = match maybe_state_readonly_index {
@@ -3,7 +3,10 @@ use std::sync::{mpsc, Arc, Weak};
 use once_cell::sync::OnceCell;
 
 use cfx_types::{H256, U256, U64};
-use diem_config::{config::NodeConfig, keys::ConfigKey};
+use diem_config::{
+    config::{NodeConfig, WaypointConfig},
+    keys::ConfigKey,
+};
 use diem_crypto::HashValue;
 use diem_types::{
     contract_event::ContractEvent,
@@ -45,6 +48,7 @@ use diem_types::{
     chain_id::ChainId,
     term_state::pos_state_config::{PosStateConfig, POS_STATE_CONFIG},
     transaction::TransactionPayload,
+    waypoint::Waypoint,
 };
 use network::NetworkService;
 use parking_lot::Mutex;
@@ -180,6 +184,9 @@ impl PosHandler {
         let mut pos_config = NodeConfig::load(pos_config_path)
             .map_err(|e| format!("Failed to load node config: e={:?}", e))?;
         pos_config.set_data_dir(pos_config.data_dir().to_path_buf());
+        if let Some(waypoint) = self.conf.trusted_waypoint {
+            pos_config.base.waypoint = WaypointConfig::FromConfig(waypoint);
+        }
         let pos_genesis = read_initial_nodes_from_file(
             self.conf.pos_initial_nodes_path.as_str(),
         )?;
@@ -599,6 +606,9 @@ pub struct PosConfiguration {
     pub pos_initial_nodes_path: String,
     pub vrf_proposal_threshold: U256,
     pub pos_state_config: PosStateConfig,
+    /// Overrides the waypoint in the PoS config, e.g. with the ledger info of
+    /// a trusted checkpoint.
+    pub trusted_waypoint: Option<Waypoint>,
 }
 
 fn diem_hash_to_h256(h: &HashValue) -> PosBlockId { H256::from(h.as_ref()) }
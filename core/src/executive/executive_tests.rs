@@ -5,7 +5,12 @@
 use super::{executive::*, Executed, ExecutionError};
 use crate::{
     evm::FinalizationResult,
-    executive::{CollateralCheckResultToVmResult, ExecutionOutcome},
+    executive::{
+        internal_contract::impls::params_control::{
+            settle_vote_round, total_votes, POW_BASE_REWARD_INDEX,
+        },
+        CollateralCheckResultToVmResult, ExecutionOutcome,
+    },
     machine::Machine,
     state::{State, Substate},
     test_helpers::get_state_for_genesis_write,
@@ -18,6 +23,7 @@ use crate::{
 use cfx_internal_common::debug::ComputeEpochDebugRecord;
 use cfx_parameters::{
     internal_contract_addresses::{
+        PARAMS_CONTROL_CONTRACT_ADDRESS,
         SPONSOR_WHITELIST_CONTROL_CONTRACT_ADDRESS,
         STORAGE_INTEREST_STAKING_CONTRACT_ADDRESS,
     },
@@ -43,6 +49,7 @@ use primitives::{
     NativeTransaction, Transaction,
 };
 use rustc_hex::FromHex;
+use solidity_abi::ABIEncodable;
 use std::{
     cmp::{self, min},
     str::FromStr,
@@ -986,6 +993,151 @@ fn test_deposit_withdraw_lock() {
     );
 }
 
+#[test]
+fn test_params_control_vote() {
+    let mut sender = Address::zero();
+    sender.set_user_account_type_bits();
+    let sender_with_space = sender.with_native_space();
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    let env = Env::default();
+    let machine = make_byzantium_machine(0);
+    let spec = machine.spec(env.number);
+    let mut substate = Substate::new();
+    let stake = U256::from(1_000_000_000_000_000_000u64);
+    state
+        .add_balance(
+            &sender_with_space,
+            &stake,
+            CleanupMode::NoEmpty,
+            spec.account_start_nonce,
+        )
+        .unwrap();
+    state.deposit(&sender, &stake, env.number).unwrap();
+    // Locked for long enough to get the full vote power.
+    state
+        .vote_lock(&sender, &stake, 10 * PARAMS_VOTE_PERIOD + BLOCKS_PER_YEAR)
+        .unwrap();
+
+    let cast_vote_data = |round: u64, index: u64, votes: [U256; 3]| {
+        let mut data: Vec<u8> = "1d4b037c".from_hex().unwrap();
+        data.extend((round, index, votes).abi_encode());
+        data
+    };
+    let mut read_vote_data: Vec<u8> = "d28c9ad0".from_hex().unwrap();
+    read_vote_data.extend(sender.abi_encode());
+
+    let mut params = ActionParams::default();
+    params.code_address = PARAMS_CONTROL_CONTRACT_ADDRESS.clone();
+    params.address = params.code_address;
+    params.sender = sender;
+    params.original_sender = sender;
+    params.storage_owner = params.code_address;
+    params.gas = U256::from(1000000);
+    params.call_type = CallType::Call;
+
+    // no votes yet
+    params.data = Some(read_vote_data.clone());
+    let mut tracer = ();
+    let result = Executive::new(&mut state, &env, &machine, &spec)
+        .call(params.clone(), &mut substate, &mut tracer)
+        .expect("no db error")
+        .unwrap();
+    assert_eq!(
+        result.return_data.to_vec(),
+        vec![[U256::zero(); 3]; 2].abi_encode()
+    );
+
+    // vote in a past or future round
+    params.data = Some(cast_vote_data(1, 0, [U256::zero(), stake, 0.into()]));
+    let mut tracer = ();
+    let result = Executive::new(&mut state, &env, &machine, &spec)
+        .call(params.clone(), &mut substate, &mut tracer)
+        .expect("no db error");
+    assert_eq!(
+        result.unwrap_err(),
+        vm::Error::InternalContract(
+            "Can only vote in the current round 0".into()
+        )
+    );
+
+    // unknown parameter
+    params.data = Some(cast_vote_data(0, 2, [U256::zero(), stake, 0.into()]));
+    let mut tracer = ();
+    let result = Executive::new(&mut state, &env, &machine, &spec)
+        .call(params.clone(), &mut substate, &mut tracer)
+        .expect("no db error");
+    assert_eq!(
+        result.unwrap_err(),
+        vm::Error::InternalContract("Unknown parameter index".into())
+    );
+
+    // more votes than the vote power
+    params.data = Some(cast_vote_data(0, 0, [U256::one(), stake, 0.into()]));
+    let mut tracer = ();
+    let result = Executive::new(&mut state, &env, &machine, &spec)
+        .call(params.clone(), &mut substate, &mut tracer)
+        .expect("no db error");
+    assert_eq!(
+        result.unwrap_err(),
+        vm::Error::InternalContract("Votes exceed the vote power".into())
+    );
+
+    // vote for decreasing the base reward, then change it to increasing
+    params.data = Some(cast_vote_data(0, 0, [U256::zero(), 0.into(), stake]));
+    let mut tracer = ();
+    let result = Executive::new(&mut state, &env, &machine, &spec)
+        .call(params.clone(), &mut substate, &mut tracer)
+        .expect("no db error");
+    assert!(result.is_ok());
+    params.data = Some(cast_vote_data(0, 0, [U256::zero(), stake, 0.into()]));
+    let mut tracer = ();
+    let result = Executive::new(&mut state, &env, &machine, &spec)
+        .call(params.clone(), &mut substate, &mut tracer)
+        .expect("no db error");
+    assert!(result.is_ok());
+    assert_eq!(
+        total_votes(&state, 0).unwrap()[POW_BASE_REWARD_INDEX as usize],
+        [U256::zero(), stake, U256::zero()]
+    );
+
+    params.data = Some(read_vote_data.clone());
+    let mut tracer = ();
+    let result = Executive::new(&mut state, &env, &machine, &spec)
+        .call(params.clone(), &mut substate, &mut tracer)
+        .expect("no db error")
+        .unwrap();
+    assert_eq!(
+        result.return_data.to_vec(),
+        vec![[U256::zero(), stake, U256::zero()], [U256::zero(); 3]]
+            .abi_encode()
+    );
+
+    // Only the voted parameter is changed, and the base reward starts from
+    // the default one.
+    let interest_rate = state.interest_rate_per_block();
+    settle_vote_round(&mut state, PARAMS_VOTE_PERIOD, U256::from(100)).unwrap();
+    assert_eq!(state.pow_base_reward(), U256::from(200));
+    assert_eq!(state.interest_rate_per_block(), interest_rate);
+
+    // A round without any votes changes nothing.
+    settle_vote_round(&mut state, 2 * PARAMS_VOTE_PERIOD, U256::from(100))
+        .unwrap();
+    assert_eq!(state.pow_base_reward(), U256::from(200));
+    assert_eq!(state.interest_rate_per_block(), interest_rate);
+}
+
+#[test]
+fn test_params_control_settle_without_votes() {
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    let interest_rate = state.interest_rate_per_block();
+    settle_vote_round(&mut state, PARAMS_VOTE_PERIOD, U256::from(100)).unwrap();
+    // The base reward stays unset, so the default one is still followed.
+    assert_eq!(state.pow_base_reward(), U256::zero());
+    assert_eq!(state.interest_rate_per_block(), interest_rate);
+}
+
 #[test]
 fn test_commission_privilege_all_whitelisted_across_epochs() {
     let code: Vec<u8> = "7c601080600c6000396000f3006000355415600957005b60203560003555600052601d60036017f0600055".from_hex().unwrap();
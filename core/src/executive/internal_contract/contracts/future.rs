@@ -10,9 +10,6 @@ make_solidity_contract! {
     pub(super) struct AntiReentrancyConfig(ANTI_REENTRANCY_CONTRACT_ADDRESS, "placeholder");
 }
 
-make_solidity_contract! {
-    pub(super) struct Reserved7(RESERVED7, "placeholder");
-}
 make_solidity_contract! {
     pub(super) struct Reserved8(RESERVED8, "placeholder");
 }
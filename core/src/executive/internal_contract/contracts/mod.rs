@@ -6,6 +6,7 @@ mod admin;
 mod context;
 pub mod cross_space;
 mod future;
+mod params_control;
 #[allow(unused)]
 mod pos;
 mod sponsor;
@@ -42,10 +43,12 @@ mod macros {
 
 pub use self::{
     admin::AdminControl, context::Context, cross_space::CrossSpaceCall,
-    pos::PoSRegister, sponsor::SponsorWhitelistControl, staking::Staking,
+    params_control::ParamsControl, pos::PoSRegister,
+    sponsor::SponsorWhitelistControl, staking::Staking,
 };
 pub(super) use self::{
     cross_space::{CallEvent, CreateEvent, ReturnEvent, WithdrawEvent},
+    params_control::CastVoteEvent,
     pos::{IncreaseStakeEvent, RegisterEvent, RetireEvent},
 };
 
@@ -218,7 +221,7 @@ pub fn all_internal_contracts() -> Vec<Box<dyn InternalContractTrait>> {
         Box::new(Context::instance()),
         Box::new(PoSRegister::instance()),
         Box::new(CrossSpaceCall::instance()),
-        Box::new(future::Reserved7::instance()),
+        Box::new(ParamsControl::instance()),
         Box::new(future::Reserved8::instance()),
         Box::new(future::Reserved9::instance()),
         Box::new(future::Reserved10::instance()),
@@ -0,0 +1,128 @@
+// Copyright 2022 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use super::{
+    super::impls::params_control::*, macros::*, SimpleExecutionTrait,
+    SolFnTable,
+};
+use crate::{
+    evm::{ActionParams, GasPriceTier, Spec},
+    executive::InternalRefContext,
+    observer::VmObserve,
+    vm,
+};
+use cfx_parameters::internal_contract_addresses::PARAMS_CONTROL_CONTRACT_ADDRESS;
+use cfx_state::state_trait::StateOpsTrait;
+use cfx_types::{Address, U256};
+
+make_solidity_contract! {
+    pub struct ParamsControl(PARAMS_CONTROL_CONTRACT_ADDRESS, generate_fn_table, initialize: |params: &CommonParams| params.transition_numbers.cip94, is_active: |spec: &Spec| spec.cip94);
+}
+fn generate_fn_table() -> SolFnTable {
+    make_function_table!(CastVote, ReadVote, CurrentRound, TotalVotes)
+}
+group_impl_is_active!(
+    |spec: &Spec| spec.cip94,
+    CastVote,
+    ReadVote,
+    CurrentRound,
+    TotalVotes
+);
+
+make_solidity_event! {
+    pub struct CastVoteEvent("CastVote(uint64,address,uint64,uint256[3])", indexed: (u64, Address, u64), non_indexed: [U256; 3]);
+}
+
+make_solidity_function! {
+    struct CastVote((u64, u64, [U256; 3]), "castVote(uint64,uint64,uint256[3])");
+}
+impl_function_type!(CastVote, "non_payable_write");
+
+impl UpfrontPaymentTrait for CastVote {
+    fn upfront_gas_payment(
+        &self, _: &Self::Input, params: &ActionParams,
+        context: &InternalRefContext,
+    ) -> DbResult<U256>
+    {
+        let spec = context.spec;
+        let length = context.state.vote_stake_list_length(&params.sender)?;
+        let vote_power_gas = spec.sload_gas * (length + 1);
+        let log_gas =
+            3 * 32 * spec.log_data_gas + spec.log_gas + 4 * spec.log_topic_gas;
+        // The voted round, plus the total and the vote of each option.
+        let io_gas =
+            7 * spec.sstore_reset_gas + 7 * spec.sload_gas + 7 * spec.sha3_gas;
+        Ok(U256::from(vote_power_gas + log_gas + io_gas))
+    }
+}
+
+impl SimpleExecutionTrait for CastVote {
+    fn execute_inner(
+        &self, (round, index, votes): (u64, u64, [U256; 3]),
+        params: &ActionParams, context: &mut InternalRefContext,
+        _tracer: &mut dyn VmObserve,
+    ) -> vm::Result<()>
+    {
+        cast_vote(round, index, votes, params, context)
+    }
+}
+
+make_solidity_function! {
+    struct ReadVote(Address, "readVote(address)", Vec<[U256; 3]>);
+}
+// the voted round and the three votes of each parameter
+impl_function_type!(ReadVote, "query", gas: |spec: &Spec| 4 * PARAMETER_COUNT as usize * spec.sload_gas);
+
+impl SimpleExecutionTrait for ReadVote {
+    fn execute_inner(
+        &self, address: Address, params: &ActionParams,
+        context: &mut InternalRefContext, _tracer: &mut dyn VmObserve,
+    ) -> vm::Result<Vec<[U256; 3]>>
+    {
+        read_vote(&address, params, context)
+    }
+}
+
+make_solidity_function! {
+    struct CurrentRound((), "currentRound()", u64);
+}
+// same gas cost as the `NUMBER` opcode
+impl_function_type!(CurrentRound, "query", gas: |spec: &Spec| spec.tier_step_gas[(GasPriceTier::Base).idx()]);
+
+impl SimpleExecutionTrait for CurrentRound {
+    fn execute_inner(
+        &self, _input: (), _params: &ActionParams,
+        context: &mut InternalRefContext, _tracer: &mut dyn VmObserve,
+    ) -> vm::Result<u64>
+    {
+        Ok(vote_round(context.env.number))
+    }
+}
+
+make_solidity_function! {
+    struct TotalVotes(u64, "totalVotes(uint64)", Vec<[U256; 3]>);
+}
+impl_function_type!(TotalVotes, "query", gas: |spec: &Spec| 3 * PARAMETER_COUNT as usize * spec.sload_gas);
+
+impl SimpleExecutionTrait for TotalVotes {
+    fn execute_inner(
+        &self, round: u64, _params: &ActionParams,
+        context: &mut InternalRefContext, _tracer: &mut dyn VmObserve,
+    ) -> vm::Result<Vec<[U256; 3]>>
+    {
+        Ok(total_votes(context.state, round)?)
+    }
+}
+
+#[test]
+fn test_params_control_contract_sig() {
+    check_func_signature!(CastVote, "1d4b037c");
+    check_func_signature!(ReadVote, "d28c9ad0");
+    check_func_signature!(CurrentRound, "8a19c8bc");
+    check_func_signature!(TotalVotes, "d16bc6b3");
+    check_event_signature!(
+        CastVoteEvent,
+        "bdca38c287d71189805eb876b908228ddd067296b44cc3c68b1792279375258a"
+    );
+}
@@ -4,6 +4,7 @@
 
 pub(super) mod admin;
 pub(super) mod cross_space;
+pub mod params_control;
 pub(crate) mod pos;
 pub(super) mod sponsor;
 pub(super) mod staking;
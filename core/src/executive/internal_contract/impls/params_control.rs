@@ -0,0 +1,247 @@
+// Copyright 2022 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use std::convert::TryFrom;
+
+use cfx_parameters::{
+    internal_contract_addresses::PARAMS_CONTROL_CONTRACT_ADDRESS,
+    staking::PARAMS_VOTE_PERIOD,
+};
+use cfx_state::state_trait::StateOpsTrait;
+use cfx_statedb::Result as DbResult;
+use cfx_types::{Address, AddressSpaceUtil, U256, U512};
+use entries::*;
+
+use super::{super::contracts::CastVoteEvent, staking::get_vote_power};
+use crate::{
+    executive::{internal_contract::SolidityEventTrait, InternalRefContext},
+    vm::{self, ActionParams},
+};
+
+/// The index of the PoW base reward per block in Drip.
+pub const POW_BASE_REWARD_INDEX: u64 = 0;
+/// The index of the interest rate per block, scaled by
+/// `INTEREST_RATE_PER_BLOCK_SCALE`.
+pub const INTEREST_RATE_INDEX: u64 = 1;
+pub const PARAMETER_COUNT: u64 = 2;
+
+/// The votes on a parameter, which are for keeping, increasing and decreasing
+/// the parameter in order.
+pub type Votes = [U256; 3];
+
+const OPTION_UNCHANGE: usize = 0;
+const OPTION_INCREASE: usize = 1;
+const OPTION_DECREASE: usize = 2;
+
+#[inline]
+pub fn vote_round(block_number: u64) -> u64 {
+    block_number / PARAMS_VOTE_PERIOD
+}
+
+/// Implementation of `castVote(uint64,uint64,uint256[3])`.
+pub fn cast_vote(
+    round: u64, index: u64, votes: Votes, params: &ActionParams,
+    context: &mut InternalRefContext,
+) -> vm::Result<()>
+{
+    let current_round = vote_round(context.env.number);
+    if round != current_round {
+        return Err(vm::Error::InternalContract(format!(
+            "Can only vote in the current round {}",
+            current_round
+        )));
+    }
+    if index >= PARAMETER_COUNT {
+        return Err(vm::Error::InternalContract(
+            "Unknown parameter index".into(),
+        ));
+    }
+
+    // The stake must be locked until the votes are applied.
+    let round_end = (current_round + 1) * PARAMS_VOTE_PERIOD;
+    let vote_power = get_vote_power(
+        params.sender,
+        round_end.into(),
+        context.env.number,
+        context.state,
+    )?;
+    let total = votes
+        .iter()
+        .try_fold(U256::zero(), |acc, value| acc.checked_add(*value));
+    match total {
+        Some(total) if total <= vote_power => {}
+        _ => {
+            return Err(vm::Error::InternalContract(
+                "Votes exceed the vote power".into(),
+            ));
+        }
+    }
+
+    // Replace the votes cast by the sender earlier in this round.
+    let voted_round_key = voted_round_entry(&params.sender, index);
+    let revote =
+        context.storage_at(params, &voted_round_key)? == U256::from(round + 1);
+    for (option, value) in votes.iter().enumerate() {
+        let vote_key = vote_entry(&params.sender, index, option);
+        let total_key = total_votes_entry(round, index, option);
+        let mut total = context.storage_at(params, &total_key)?;
+        if revote {
+            total -= context.storage_at(params, &vote_key)?;
+        }
+        total += *value;
+        context.set_storage(params, total_key, total)?;
+        context.set_storage(params, vote_key, *value)?;
+    }
+    // Zero means that the sender has never voted, so the round is stored
+    // with an offset.
+    context.set_storage(params, voted_round_key, U256::from(round + 1))?;
+
+    CastVoteEvent::log(&(round, params.sender, index), &votes, params, context)
+}
+
+/// Implementation of `readVote(address)`. Only the votes in the current round
+/// are returned.
+pub fn read_vote(
+    address: &Address, params: &ActionParams, context: &mut InternalRefContext,
+) -> vm::Result<Vec<Votes>> {
+    let current_round = vote_round(context.env.number);
+    let mut answer = Vec::with_capacity(PARAMETER_COUNT as usize);
+    for index in 0..PARAMETER_COUNT {
+        let mut votes = Votes::default();
+        let voted_round =
+            context.storage_at(params, &voted_round_entry(address, index))?;
+        if voted_round == U256::from(current_round + 1) {
+            for (option, value) in votes.iter_mut().enumerate() {
+                *value = context
+                    .storage_at(params, &vote_entry(address, index, option))?;
+            }
+        }
+        answer.push(votes);
+    }
+    Ok(answer)
+}
+
+/// The total votes on each parameter in a round. Implementation of
+/// `totalVotes(uint64)`.
+pub fn total_votes(
+    state: &dyn StateOpsTrait, round: u64,
+) -> DbResult<Vec<Votes>> {
+    let address = PARAMS_CONTROL_CONTRACT_ADDRESS.with_native_space();
+    let mut answer = Vec::with_capacity(PARAMETER_COUNT as usize);
+    for index in 0..PARAMETER_COUNT {
+        let mut votes = Votes::default();
+        for (option, value) in votes.iter_mut().enumerate() {
+            *value = state.storage_at(
+                &address,
+                &total_votes_entry(round, index, option),
+            )?;
+        }
+        answer.push(votes);
+    }
+    Ok(answer)
+}
+
+#[inline]
+fn is_empty(votes: &Votes) -> bool { votes.iter().all(U256::is_zero) }
+
+/// The value of a parameter after applying the votes of a round. The value is
+/// doubled if all the votes are for increasing it and halved if all the votes
+/// are for decreasing it.
+pub fn next_value(value: U256, votes: &Votes) -> U256 {
+    let unchange = U512::from(votes[OPTION_UNCHANGE]);
+    let increase = U512::from(votes[OPTION_INCREASE]);
+    let decrease = U512::from(votes[OPTION_DECREASE]);
+    let total = unchange + increase + decrease;
+    if total.is_zero() {
+        return value;
+    }
+    let next = U512::from(value) * (unchange * 2 + increase * 4 + decrease)
+        / (total * 2);
+    // A parameter never reaches zero, otherwise it can not be increased
+    // anymore.
+    U256::try_from(next)
+        .unwrap_or_else(|_| U256::max_value())
+        .max(U256::one())
+}
+
+/// Apply the votes of the round ended before `block_number` to the
+/// parameters. It should be called at the beginning of the blocks whose number
+/// is a multiple of `PARAMS_VOTE_PERIOD`. `default_pow_base_reward` is the
+/// base reward before the first round is applied.
+pub fn settle_vote_round(
+    state: &mut dyn StateOpsTrait, block_number: u64,
+    default_pow_base_reward: U256,
+) -> DbResult<()>
+{
+    debug_assert_eq!(block_number % PARAMS_VOTE_PERIOD, 0);
+    let round = vote_round(block_number) - 1;
+    let votes = total_votes(state, round)?;
+
+    // A parameter without any votes is left untouched. In particular, the
+    // base reward stays zero until it is voted on, so the default one keeps
+    // being used.
+    let pow_base_reward_votes = &votes[POW_BASE_REWARD_INDEX as usize];
+    if !is_empty(pow_base_reward_votes) {
+        let pow_base_reward = match state.pow_base_reward() {
+            reward if reward.is_zero() => default_pow_base_reward,
+            reward => reward,
+        };
+        state.set_pow_base_reward(next_value(
+            pow_base_reward,
+            pow_base_reward_votes,
+        ));
+    }
+    let interest_rate_votes = &votes[INTEREST_RATE_INDEX as usize];
+    if !is_empty(interest_rate_votes) {
+        let interest_rate = state.interest_rate_per_block();
+        state.set_interest_rate_per_block(next_value(
+            interest_rate,
+            interest_rate_votes,
+        ));
+    }
+    Ok(())
+}
+
+pub mod entries {
+    use cfx_types::{Address, H256};
+    use tiny_keccak::{Hasher, Keccak};
+
+    pub type StorageEntryKey = Vec<u8>;
+
+    fn prefix_and_hash(prefix: u64, data: &[u8]) -> StorageEntryKey {
+        let mut hasher = Keccak::v256();
+        hasher.update(&prefix.to_be_bytes());
+        hasher.update(data);
+        let mut hash = H256::default();
+        hasher.finalize(hash.as_bytes_mut());
+        hash.as_bytes().to_vec()
+    }
+
+    #[inline]
+    pub fn total_votes_entry(
+        round: u64, index: u64, option: usize,
+    ) -> StorageEntryKey {
+        let mut data = round.to_be_bytes().to_vec();
+        data.extend_from_slice(&index.to_be_bytes());
+        data.extend_from_slice(&(option as u64).to_be_bytes());
+        prefix_and_hash(0, &data)
+    }
+
+    #[inline]
+    pub fn voted_round_entry(sender: &Address, index: u64) -> StorageEntryKey {
+        let mut data = sender.as_bytes().to_vec();
+        data.extend_from_slice(&index.to_be_bytes());
+        prefix_and_hash(1, &data)
+    }
+
+    #[inline]
+    pub fn vote_entry(
+        sender: &Address, index: u64, option: usize,
+    ) -> StorageEntryKey {
+        let mut data = sender.as_bytes().to_vec();
+        data.extend_from_slice(&index.to_be_bytes());
+        data.extend_from_slice(&(option as u64).to_be_bytes());
+        prefix_and_hash(2, &data)
+    }
+}
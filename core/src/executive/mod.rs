@@ -8,6 +8,9 @@ mod executive;
 #[cfg(test)]
 mod executive_tests;
 pub mod internal_contract;
+mod parallel;
+#[cfg(test)]
+mod parallel_tests;
 mod vm_exec;
 
 trait CollateralCheckResultToVmResult {
@@ -39,6 +42,7 @@ pub use self::{
         InternalContractMap, InternalContractTrait, InternalRefContext,
         SolidityEventTrait, SolidityFunctionTrait,
     },
+    parallel::ParallelExecutor,
 };
 use crate::vm::Error as vmError;
 use cfx_state::CollateralCheckResult;
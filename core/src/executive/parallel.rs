@@ -0,0 +1,271 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Optimistic parallel execution of the transactions in a block.
+//!
+//! The transactions are executed speculatively in batches, each against the
+//! state before the batch, recording the keys they read and write. The
+//! results are then committed in the block order. A transaction which reads
+//! no key written by the transactions committed before it in the batch has
+//! the same outcome as in sequential execution, otherwise it's executed
+//! again against the up-to-date state. Transactions the speculative state
+//! can't handle, e.g. those which delete a contract, are executed on the
+//! state directly. So the outcomes and the final state are identical to the
+//! sequential execution.
+
+use super::{ExecutionOutcome, Executive, ExecutiveGeneric, TransactOptions};
+use crate::{
+    machine::Machine,
+    state::{State, StateGeneric},
+    vm::{Env, Spec},
+};
+use cfx_internal_common::StateRootWithAuxInfo;
+use cfx_state::StateTrait;
+use cfx_statedb::{Result as DbResult, StateDb, StateDbGeneric};
+use cfx_storage::{
+    utils::access_mode, ErrorKind, MptKeyValue, Result, StorageStateTrait,
+};
+use primitives::{
+    EpochId, SignedTransaction, StorageKeyWithSpace, MERKLE_NULL_NODE,
+};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use std::{
+    cell::RefCell,
+    cmp::min,
+    collections::{BTreeMap, HashSet},
+    rc::Rc,
+    sync::Arc,
+};
+
+/// The number of transactions speculated against the same state for each
+/// thread.
+const BATCH_SIZE_PER_THREAD: usize = 4;
+
+pub struct ParallelExecutor {
+    thread_pool: ThreadPool,
+    batch_size: usize,
+}
+
+impl ParallelExecutor {
+    pub fn new(num_threads: usize) -> std::result::Result<Self, String> {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|i| format!("Parallel execution worker {}", i))
+            .build()
+            .map_err(|e| format!("Failed to start execution threads: {}", e))?;
+        Ok(Self {
+            thread_pool,
+            batch_size: num_threads * BATCH_SIZE_PER_THREAD,
+        })
+    }
+
+    /// Execute the transactions of a block in order and return their
+    /// outcomes. The cached changes of `state` are flushed to the state db
+    /// in between.
+    pub fn execute_transactions(
+        &self, state: &mut State, env: &Env, machine: &Machine, spec: &Spec,
+        transactions: &[Arc<SignedTransaction>], executive_trace: bool,
+    ) -> DbResult<Vec<ExecutionOutcome>>
+    {
+        let mut outcomes = Vec::with_capacity(transactions.len());
+        while outcomes.len() < transactions.len() {
+            state.flush_cache(None)?;
+            let batch = &transactions[outcomes.len()
+                ..min(transactions.len(), outcomes.len() + self.batch_size)];
+            let speculations: Vec<_> = {
+                let db = state.db();
+                self.thread_pool.install(|| {
+                    batch
+                        .par_iter()
+                        .map(|tx| {
+                            speculate(
+                                db,
+                                env,
+                                machine,
+                                spec,
+                                tx,
+                                executive_trace,
+                            )
+                        })
+                        .collect()
+                })
+            };
+
+            let mut written_keys = HashSet::new();
+            for (transaction, speculation) in batch.iter().zip(speculations) {
+                let speculation = match speculation {
+                    Some(speculation)
+                        if speculation
+                            .read_keys
+                            .iter()
+                            .any(|key| written_keys.contains(key)) =>
+                    {
+                        trace!(
+                            "Re-execute conflicting transaction {:?}",
+                            transaction.hash()
+                        );
+                        speculate(
+                            state.db(),
+                            env,
+                            machine,
+                            spec,
+                            transaction,
+                            executive_trace,
+                        )
+                    }
+                    speculation => speculation,
+                };
+                match speculation {
+                    Some(speculation) => {
+                        state.apply_raw_changes(&speculation.changes)?;
+                        written_keys.extend(
+                            speculation.changes.into_iter().map(|(key, _)| key),
+                        );
+                        outcomes.push(speculation.outcome);
+                    }
+                    None => {
+                        // The following transactions are speculated again
+                        // after the state is flushed.
+                        outcomes.push(
+                            Executive::new(state, env, machine, spec)
+                                .transact(
+                                    transaction,
+                                    transact_options(executive_trace),
+                                )?,
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(outcomes)
+    }
+}
+
+struct Speculation {
+    outcome: ExecutionOutcome,
+    read_keys: HashSet<Vec<u8>>,
+    changes: Vec<(Vec<u8>, Option<Box<[u8]>>)>,
+}
+
+#[derive(Default)]
+struct AccessRecord {
+    read_keys: HashSet<Vec<u8>>,
+    changes: BTreeMap<Vec<u8>, Option<Box<[u8]>>>,
+}
+
+/// Execute a transaction against the flushed state db, returning `None` if
+/// the transaction can't be executed speculatively.
+fn speculate(
+    base: &StateDb, env: &Env, machine: &Machine, spec: &Spec,
+    transaction: &SignedTransaction, executive_trace: bool,
+) -> Option<Speculation>
+{
+    let record = Rc::new(RefCell::new(AccessRecord::default()));
+    let storage = SpeculativeStorage {
+        base,
+        record: record.clone(),
+    };
+    let outcome = StateGeneric::new(StateDbGeneric::new(storage)).and_then(
+        |mut state| {
+            let outcome = ExecutiveGeneric::new(&mut state, env, machine, spec)
+                .transact(transaction, transact_options(executive_trace))?;
+            // Write the changes to the speculative storage.
+            state.compute_state_root(None)?;
+            Ok(outcome)
+        },
+    );
+    match outcome {
+        Ok(outcome) => {
+            let record = record.replace(Default::default());
+            Some(Speculation {
+                outcome,
+                read_keys: record.read_keys,
+                changes: record.changes.into_iter().collect(),
+            })
+        }
+        Err(e) => {
+            trace!(
+                "Transaction {:?} not executed speculatively: {}",
+                transaction.hash(),
+                e
+            );
+            None
+        }
+    }
+}
+
+fn transact_options(executive_trace: bool) -> TransactOptions {
+    if executive_trace {
+        TransactOptions::with_tracing()
+    } else {
+        TransactOptions::with_no_tracing()
+    }
+}
+
+/// A storage which reads from a state db without modifying it, and records
+/// the keys read and the values written.
+struct SpeculativeStorage<'a> {
+    base: &'a StateDb,
+    record: Rc<RefCell<AccessRecord>>,
+}
+
+impl<'a> StorageStateTrait for SpeculativeStorage<'a> {
+    fn get(
+        &self, access_key: StorageKeyWithSpace,
+    ) -> Result<Option<Box<[u8]>>> {
+        self.record
+            .borrow_mut()
+            .read_keys
+            .insert(access_key.to_key_bytes());
+        match self.base.get_raw(access_key) {
+            Ok(value) => Ok(value.map(|value| (&*value).into())),
+            Err(e) => Err(ErrorKind::Msg(format!("{}", e)).into()),
+        }
+    }
+
+    fn set(
+        &mut self, access_key: StorageKeyWithSpace, value: Box<[u8]>,
+    ) -> Result<()> {
+        self.record
+            .borrow_mut()
+            .changes
+            .insert(access_key.to_key_bytes(), Some(value));
+        Ok(())
+    }
+
+    fn delete(&mut self, access_key: StorageKeyWithSpace) -> Result<()> {
+        self.record
+            .borrow_mut()
+            .changes
+            .insert(access_key.to_key_bytes(), None);
+        Ok(())
+    }
+
+    fn delete_test_only(
+        &mut self, _access_key: StorageKeyWithSpace,
+    ) -> Result<Option<Box<[u8]>>> {
+        Err(ErrorKind::Msg("Not supported in speculation".to_owned()).into())
+    }
+
+    /// The keys under a prefix are unknown without iterating the underlying
+    /// storage, so the transaction is executed on the state instead.
+    fn delete_all<AM: access_mode::AccessMode>(
+        &mut self, _access_key_prefix: StorageKeyWithSpace,
+    ) -> Result<Option<Vec<MptKeyValue>>> {
+        Err(ErrorKind::Msg("Not supported in speculation".to_owned()).into())
+    }
+
+    fn compute_state_root(&mut self) -> Result<StateRootWithAuxInfo> {
+        Ok(StateRootWithAuxInfo::genesis(&MERKLE_NULL_NODE))
+    }
+
+    fn get_state_root(&self) -> Result<StateRootWithAuxInfo> {
+        Err(ErrorKind::Msg("No state root".to_owned()).into())
+    }
+
+    fn commit(&mut self, _epoch: EpochId) -> Result<StateRootWithAuxInfo> {
+        Err(ErrorKind::Msg("Not supported in speculation".to_owned()).into())
+    }
+}
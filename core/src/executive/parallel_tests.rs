@@ -0,0 +1,177 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use super::{ExecutionOutcome, Executive, ParallelExecutor, TransactOptions};
+use crate::{
+    machine::{new_machine_with_builtin, Machine},
+    state::State,
+    test_helpers::get_state_for_genesis_write,
+    vm::{Env, Spec},
+    vm_factory::VmFactory,
+};
+use cfx_state::{state_trait::StateOpsTrait, CleanupMode, StateTrait};
+use cfx_storage::tests::new_state_manager_for_unit_test;
+use cfx_types::{address_util::AddressUtil, Address, AddressSpaceUtil, U256};
+use keylib::{Generator, KeyPair, Random};
+use primitives::{
+    transaction::Action, NativeTransaction, SignedTransaction, Transaction,
+};
+use rustc_hex::FromHex;
+use std::sync::Arc;
+
+fn make_machine() -> Machine {
+    new_machine_with_builtin(Default::default(), VmFactory::new(1024 * 32))
+}
+
+fn random_user_address() -> Address {
+    let mut address = Address::random();
+    address.set_user_account_type_bits();
+    address
+}
+
+fn make_transaction(
+    sender: &KeyPair, nonce: u64, action: Action, value: u64, data: &str,
+    storage_limit: u64,
+) -> Arc<SignedTransaction>
+{
+    Arc::new(
+        Transaction::from(NativeTransaction {
+            action,
+            value: U256::from(value),
+            data: data.from_hex().unwrap(),
+            gas: U256::from(100_000),
+            gas_price: U256::one(),
+            storage_limit,
+            epoch_height: 0,
+            chain_id: 1,
+            nonce: U256::from(nonce),
+        })
+        .sign(sender.secret()),
+    )
+}
+
+fn make_state(senders: &[KeyPair], spec: &Spec) -> State {
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    for sender in senders {
+        state
+            .add_balance(
+                &sender.address().with_native_space(),
+                &U256::from(10_000_000_000_000_000_000u128),
+                CleanupMode::NoEmpty,
+                spec.account_start_nonce,
+            )
+            .unwrap();
+    }
+    state
+}
+
+/// Execute the transactions sequentially and in parallel and check that the
+/// outcomes and the state roots are the same.
+fn check_parallel_execution(
+    senders: &[KeyPair], transactions: &[Arc<SignedTransaction>],
+) {
+    let mut env = Env::default();
+    env.gas_limit = U256::from(10_000_000);
+    let machine = make_machine();
+    let spec = machine.spec(env.number);
+
+    let mut state = make_state(senders, &spec);
+    let sequential_outcomes: Vec<ExecutionOutcome> = transactions
+        .iter()
+        .map(|tx| {
+            Executive::new(&mut state, &env, &machine, &spec)
+                .transact(tx, TransactOptions::with_no_tracing())
+                .unwrap()
+        })
+        .collect();
+    let sequential_root = state.compute_state_root(None).unwrap();
+
+    for num_threads in &[1, 2, 4] {
+        let mut state = make_state(senders, &spec);
+        let parallel_outcomes = ParallelExecutor::new(*num_threads)
+            .unwrap()
+            .execute_transactions(
+                &mut state,
+                &env,
+                &machine,
+                &spec,
+                transactions,
+                /* executive_trace = */ false,
+            )
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", parallel_outcomes),
+            format!("{:?}", sequential_outcomes)
+        );
+        assert_eq!(state.compute_state_root(None).unwrap(), sequential_root);
+    }
+}
+
+#[test]
+fn test_parallel_independent_transfers() {
+    let senders: Vec<_> = (0..8).map(|_| Random.generate().unwrap()).collect();
+    let transactions: Vec<_> = senders
+        .iter()
+        .map(|sender| {
+            make_transaction(
+                sender,
+                0,
+                Action::Call(random_user_address()),
+                1_000_000,
+                "",
+                0,
+            )
+        })
+        .collect();
+    check_parallel_execution(&senders, &transactions);
+}
+
+#[test]
+fn test_parallel_conflicting_transactions() {
+    let senders: Vec<_> = (0..4).map(|_| Random.generate().unwrap()).collect();
+    let recipient = random_user_address();
+    let mut transactions = Vec::new();
+    for round in 0..3 {
+        for (i, sender) in senders.iter().enumerate() {
+            // Transfers to the same recipient and the next sender.
+            transactions.push(make_transaction(
+                sender,
+                round * 3,
+                Action::Call(recipient),
+                1_000,
+                "",
+                0,
+            ));
+            transactions.push(make_transaction(
+                sender,
+                round * 3 + 1,
+                Action::Call(senders[(i + 1) % senders.len()].address()),
+                1_000,
+                "",
+                0,
+            ));
+            // Contracts writing the storage, which changes the collateral
+            // for storage.
+            transactions.push(make_transaction(
+                sender,
+                round * 3 + 2,
+                Action::Create,
+                18,
+                "3331600055",
+                1_000,
+            ));
+        }
+    }
+    // A transaction with a wrong nonce.
+    transactions.push(make_transaction(
+        &senders[0],
+        100,
+        Action::Call(recipient),
+        1_000,
+        "",
+        0,
+    ));
+    check_parallel_execution(&senders, &transactions);
+}
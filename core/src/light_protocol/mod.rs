@@ -6,6 +6,9 @@ mod common;
 mod config;
 mod error;
 mod handler;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod message;
+#[cfg(not(any(test, feature = "fuzzing")))]
 mod message;
 mod provider;
 pub mod query_service;
@@ -5,8 +5,8 @@
 use super::builtin::Builtin;
 use crate::{
     builtin::{
-        builtin_factory, AltBn128PairingPricer, Blake2FPricer, Linear,
-        ModexpPricer,
+        builtin_factory, AltBn128PairingPricer, Blake2FPricer, Bls12MsmPricer,
+        Bls12PairingPricer, Linear, ModexpPricer,
     },
     executive::InternalContractMap,
     spec::CommonParams,
@@ -168,6 +168,62 @@ fn new_builtin_map(
             params.transition_numbers.cip92,
         ),
     );
+    btree.insert(
+        Address::from(H256::from_low_u64_be(0x0b)),
+        Builtin::new(
+            Box::new(Linear::new(375, 0)),
+            builtin_factory("bls12_g1_add"),
+            params.transition_numbers.eip2537,
+        ),
+    );
+    btree.insert(
+        Address::from(H256::from_low_u64_be(0x0c)),
+        Builtin::new(
+            Box::new(Bls12MsmPricer::g1(12_000)),
+            builtin_factory("bls12_g1_msm"),
+            params.transition_numbers.eip2537,
+        ),
+    );
+    btree.insert(
+        Address::from(H256::from_low_u64_be(0x0d)),
+        Builtin::new(
+            Box::new(Linear::new(600, 0)),
+            builtin_factory("bls12_g2_add"),
+            params.transition_numbers.eip2537,
+        ),
+    );
+    btree.insert(
+        Address::from(H256::from_low_u64_be(0x0e)),
+        Builtin::new(
+            Box::new(Bls12MsmPricer::g2(22_500)),
+            builtin_factory("bls12_g2_msm"),
+            params.transition_numbers.eip2537,
+        ),
+    );
+    btree.insert(
+        Address::from(H256::from_low_u64_be(0x0f)),
+        Builtin::new(
+            Box::new(Bls12PairingPricer::new(37_700, 32_600)),
+            builtin_factory("bls12_pairing"),
+            params.transition_numbers.eip2537,
+        ),
+    );
+    btree.insert(
+        Address::from(H256::from_low_u64_be(0x10)),
+        Builtin::new(
+            Box::new(Linear::new(5_500, 0)),
+            builtin_factory("bls12_map_fp_to_g1"),
+            params.transition_numbers.eip2537,
+        ),
+    );
+    btree.insert(
+        Address::from(H256::from_low_u64_be(0x11)),
+        Builtin::new(
+            Box::new(Linear::new(23_800, 0)),
+            builtin_factory("bls12_map_fp2_to_g2"),
+            params.transition_numbers.eip2537,
+        ),
+    );
     btree
 }
 
@@ -96,6 +96,15 @@ pub struct ProofOfWorkConfig {
     pub stratum_listen_addr: String,
     pub stratum_port: u16,
     pub stratum_secret: Option<H256>,
+    /// Initial share difficulty of authorized stratum workers.
+    pub stratum_share_difficulty: u64,
+    pub stratum_min_share_difficulty: u64,
+    /// Expected interval between shares of a stratum connection. The share
+    /// difficulty is not adjusted if it is 0.
+    pub stratum_share_interval_ms: u64,
+    /// Reject submissions from stratum connections which are not registered
+    /// with `mining.subscribe` or `mining.authorize`.
+    pub stratum_require_authorization: bool,
     pub pow_problem_window_size: usize,
     pub cip86_height: u64,
 }
@@ -118,6 +127,11 @@ impl ProofOfWorkConfig {
                 stratum_listen_addr,
                 stratum_port,
                 stratum_secret,
+                stratum_share_difficulty: DEFAULT_STRATUM_SHARE_DIFFICULTY,
+                stratum_min_share_difficulty:
+                    DEFAULT_STRATUM_MIN_SHARE_DIFFICULTY,
+                stratum_share_interval_ms: DEFAULT_STRATUM_SHARE_INTERVAL_MS,
+                stratum_require_authorization: false,
                 pow_problem_window_size,
                 cip86_height,
             }
@@ -131,6 +145,11 @@ impl ProofOfWorkConfig {
                 stratum_listen_addr,
                 stratum_port,
                 stratum_secret,
+                stratum_share_difficulty: DEFAULT_STRATUM_SHARE_DIFFICULTY,
+                stratum_min_share_difficulty:
+                    DEFAULT_STRATUM_MIN_SHARE_DIFFICULTY,
+                stratum_share_interval_ms: DEFAULT_STRATUM_SHARE_INTERVAL_MS,
+                stratum_require_authorization: false,
                 pow_problem_window_size,
                 cip86_height,
             }
@@ -73,6 +73,11 @@ pub struct TransitionsBlockNumber {
     pub cip90b: BlockNumber,
     /// CIP92: Enable Blake2F builtin function
     pub cip92: BlockNumber,
+    /// CIP94: Adjust the PoW base reward and the interest rate by the votes
+    /// of stakers
+    pub cip94: BlockNumber,
+    /// EIP2537: Enable BLS12-381 curve operation builtin functions
+    pub eip2537: BlockNumber,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -58,6 +58,7 @@ use crate::{
 use self::account_entry::{AccountEntry, AccountState};
 pub use self::{
     account_entry::{OverlayAccount, COMMISSION_PRIVILEGE_SPECIAL_KEY},
+    state_override::{StateOverride, StateOverrideStore},
     substate::{cleanup_mode, CallStackInfo, Substate},
 };
 
@@ -65,6 +66,7 @@ mod account_entry;
 #[cfg(test)]
 mod account_entry_tests;
 pub mod prefetcher;
+mod state_override;
 #[cfg(test)]
 mod state_tests;
 mod substate;
@@ -98,6 +100,9 @@ struct WorldStatistics {
     last_distribute_block: u64,
     // This is the tokens in the EVM space.
     total_evm_tokens: U256,
+    // This is the PoW base reward per block voted by the stakers. Zero means
+    // no vote has been applied yet.
+    pow_base_reward: U256,
 }
 
 pub type State = StateGeneric<StorageState>;
@@ -270,34 +275,7 @@ impl<StateDbStorage: StorageStateTrait> StateTrait
     ) -> DbResult<StateRootWithAuxInfo> {
         debug!("state.compute_state_root");
 
-        assert!(self.checkpoints.get_mut().is_empty());
-        assert!(self.world_statistics_checkpoints.get_mut().is_empty());
-
-        let mut sorted_dirty_accounts =
-            self.cache.get_mut().drain().collect::<Vec<_>>();
-        sorted_dirty_accounts.sort_by(|a, b| a.0.cmp(&b.0));
-
-        let mut killed_addresses = Vec::new();
-        for (address, entry) in sorted_dirty_accounts.iter_mut() {
-            entry.state = AccountState::Committed;
-            match &mut entry.account {
-                None => {}
-                Some(account) if account.removed_without_update() => {
-                    killed_addresses.push(*address);
-                    self.accounts_to_notify.push(Err(*address));
-                }
-                Some(account) => {
-                    account.commit(
-                        self,
-                        address,
-                        debug_record.as_deref_mut(),
-                    )?;
-                    self.accounts_to_notify.push(Ok(account.as_account()));
-                }
-            }
-        }
-        self.recycle_storage(killed_addresses, debug_record.as_deref_mut())?;
-        self.commit_world_statistics(debug_record.as_deref_mut())?;
+        self.flush_cache(debug_record.as_deref_mut())?;
         self.db.compute_state_root(debug_record)
     }
 
@@ -387,10 +365,16 @@ impl<StateDbStorage: StorageStateTrait> StateOpsTrait
             self.world_statistics.total_pos_staking_tokens;
 
         // The `interest_amount` exactly equals to the floor of
-        // pos_amount * 4% / blocks_per_year / sqrt(pos_amount/total_issued)
+        // pos_amount * interest_rate / blocks_per_year
+        //     / sqrt(pos_amount/total_issued)
+        // The initial interest rate per block is
+        // `4% / BLOCKS_PER_YEAR * INTEREST_RATE_PER_BLOCK_SCALE`, so the result
+        // is the same as with the fixed rate `1 / INVERSE_INTEREST_RATE`
+        // before the stakers vote for a new one.
         let interest_amount =
             sqrt_u256(total_circulating_tokens * total_pos_staking_tokens)
-                / (BLOCKS_PER_YEAR * INVERSE_INTEREST_RATE);
+                * self.world_statistics.interest_rate_per_block
+                / *INTEREST_RATE_PER_BLOCK_SCALE;
         self.world_statistics.distributable_pos_interest += interest_amount;
 
         Ok(())
@@ -1030,6 +1014,22 @@ impl<StateDbStorage: StorageStateTrait> StateOpsTrait
         self.world_statistics.last_distribute_block
     }
 
+    fn interest_rate_per_block(&self) -> U256 {
+        self.world_statistics.interest_rate_per_block
+    }
+
+    fn set_interest_rate_per_block(&mut self, interest_rate_per_block: U256) {
+        assert!(self.world_statistics_checkpoints.get_mut().is_empty());
+        self.world_statistics.interest_rate_per_block = interest_rate_per_block;
+    }
+
+    fn pow_base_reward(&self) -> U256 { self.world_statistics.pow_base_reward }
+
+    fn set_pow_base_reward(&mut self, pow_base_reward: U256) {
+        assert!(self.world_statistics_checkpoints.get_mut().is_empty());
+        self.world_statistics.pow_base_reward = pow_base_reward;
+    }
+
     fn remove_contract(&mut self, address: &AddressWithSpace) -> DbResult<()> {
         if address.space == Space::Native {
             let removed_whitelist = self
@@ -1213,6 +1213,7 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         let distributable_pos_interest = db.get_distributable_pos_interest()?;
         let last_distribute_block = db.get_last_distribute_block()?;
         let total_evm_tokens = db.get_total_evm_tokens()?;
+        let pow_base_reward = db.get_pow_base_reward()?;
 
         let world_stat = if db.is_initialized()? {
             WorldStatistics {
@@ -1226,6 +1227,7 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
                 distributable_pos_interest,
                 last_distribute_block,
                 total_evm_tokens,
+                pow_base_reward,
             }
         } else {
             // If db is not initialized, all the loaded value should be zero.
@@ -1261,6 +1263,10 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
                 last_distribute_block == 0,
                 "last_distribute_block is non-zero when db is un-init"
             );
+            assert!(
+                pow_base_reward.is_zero(),
+                "pow_base_reward is non-zero when db is un-init"
+            );
 
             WorldStatistics {
                 total_issued_tokens: U256::default(),
@@ -1272,6 +1278,7 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
                 distributable_pos_interest: U256::default(),
                 last_distribute_block: u64::default(),
                 total_evm_tokens: U256::default(),
+                pow_base_reward: U256::default(),
             }
         };
 
@@ -1285,6 +1292,102 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         })
     }
 
+    /// Write the cached changes and the world statistics to the state db.
+    /// The cache is transparent to the execution, so it can be flushed
+    /// between transactions.
+    pub fn flush_cache(
+        &mut self, mut debug_record: Option<&mut ComputeEpochDebugRecord>,
+    ) -> DbResult<()> {
+        assert!(self.checkpoints.get_mut().is_empty());
+        assert!(self.world_statistics_checkpoints.get_mut().is_empty());
+
+        let mut sorted_dirty_accounts =
+            self.cache.get_mut().drain().collect::<Vec<_>>();
+        sorted_dirty_accounts.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut killed_addresses = Vec::new();
+        for (address, entry) in sorted_dirty_accounts.iter_mut() {
+            entry.state = AccountState::Committed;
+            match &mut entry.account {
+                None => {}
+                Some(account) if account.removed_without_update() => {
+                    killed_addresses.push(*address);
+                    self.accounts_to_notify.push(Err(*address));
+                }
+                Some(account) => {
+                    account.commit(
+                        self,
+                        address,
+                        debug_record.as_deref_mut(),
+                    )?;
+                    self.accounts_to_notify.push(Ok(account.as_account()));
+                }
+            }
+        }
+        self.recycle_storage(killed_addresses, debug_record.as_deref_mut())?;
+        self.commit_world_statistics(debug_record)
+    }
+
+    /// Apply the raw key values written by a transaction executed on another
+    /// state over the flushed db of this state.
+    pub fn apply_raw_changes(
+        &mut self, changes: &[(Vec<u8>, Option<Box<[u8]>>)],
+    ) -> DbResult<()> {
+        assert!(self.cache.get_mut().is_empty());
+        for (key, value) in changes {
+            let key =
+                StorageKeyWithSpace::from_key_bytes::<SkipInputCheck>(key);
+            match value {
+                Some(value) => self.db.set_raw(key, value.clone(), None)?,
+                None => self.db.delete(key, None)?,
+            }
+            if let StorageKey::AccountKey(address_bytes) = key.key {
+                let address =
+                    Address::from_slice(address_bytes).with_space(key.space);
+                match self.db.get_account(&address)? {
+                    Some(account) => self.accounts_to_notify.push(Ok(account)),
+                    None => self.accounts_to_notify.push(Err(address)),
+                }
+            }
+        }
+        self.reload_world_statistics()
+    }
+
+    /// Read by the speculative execution of transactions, which reads the
+    /// flushed db of this state concurrently.
+    pub fn db(&self) -> &StateDb<StateDbStorage> { &self.db }
+
+    /// Flush the cache and return the state db without committing it, for
+    /// the re-execution of epochs whose states are not kept.
+    pub fn into_flushed_db(mut self) -> DbResult<StateDb<StateDbStorage>> {
+        self.flush_cache(None)?;
+        Ok(self.db)
+    }
+
+    fn reload_world_statistics(&mut self) -> DbResult<()> {
+        self.world_statistics.interest_rate_per_block =
+            self.db.get_annual_interest_rate()? / U256::from(BLOCKS_PER_YEAR);
+        self.world_statistics.accumulate_interest_rate =
+            self.db.get_accumulate_interest_rate()?;
+        self.world_statistics.total_issued_tokens =
+            self.db.get_total_issued_tokens()?;
+        self.world_statistics.total_staking_tokens =
+            self.db.get_total_staking_tokens()?;
+        self.world_statistics.total_storage_tokens =
+            self.db.get_total_storage_tokens()?;
+        self.world_statistics.total_pos_staking_tokens =
+            self.db.get_total_pos_staking_tokens()?;
+        self.world_statistics.distributable_pos_interest =
+            self.db.get_distributable_pos_interest()?;
+        self.world_statistics.last_distribute_block =
+            self.db.get_last_distribute_block()?;
+        self.world_statistics.total_evm_tokens =
+            self.db.get_total_evm_tokens()?;
+        self.world_statistics.pow_base_reward =
+            self.db.get_pow_base_reward()?;
+        Ok(())
+    }
+
     /// Charges or refund storage collateral and update `total_storage_tokens`.
     fn settle_collateral_for_address(
         &mut self, addr: &Address, substate: &dyn SubstateTrait,
@@ -1494,6 +1597,10 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         )?;
         self.db.set_total_evm_tokens(
             &self.world_statistics.total_evm_tokens,
+            debug_record.as_deref_mut(),
+        )?;
+        self.db.set_pow_base_reward(
+            &self.world_statistics.pow_base_reward,
             debug_record,
         )?;
         Ok(())
@@ -1963,27 +2070,7 @@ impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
         assert!(self.checkpoints.get_mut().is_empty());
         assert!(self.world_statistics_checkpoints.get_mut().is_empty());
         self.cache.get_mut().clear();
-        self.world_statistics.interest_rate_per_block =
-            self.db.get_annual_interest_rate().expect("no db error")
-                / U256::from(BLOCKS_PER_YEAR);
-        self.world_statistics.accumulate_interest_rate =
-            self.db.get_accumulate_interest_rate().expect("no db error");
-        self.world_statistics.total_issued_tokens =
-            self.db.get_total_issued_tokens().expect("no db error");
-        self.world_statistics.total_staking_tokens =
-            self.db.get_total_staking_tokens().expect("no db error");
-        self.world_statistics.total_storage_tokens =
-            self.db.get_total_storage_tokens().expect("no db error");
-        self.world_statistics.total_pos_staking_tokens =
-            self.db.get_total_pos_staking_tokens().expect("no db error");
-        self.world_statistics.distributable_pos_interest = self
-            .db
-            .get_distributable_pos_interest()
-            .expect("no db error");
-        self.world_statistics.last_distribute_block =
-            self.db.get_last_distribute_block().expect("no db error");
-        self.world_statistics.total_evm_tokens =
-            self.db.get_total_evm_tokens().expect("no db error");
+        self.reload_world_statistics().expect("no db error");
     }
 }
 
@@ -0,0 +1,173 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use super::{StateGeneric, Substate};
+use cfx_bytes::Bytes;
+use cfx_parameters::staking::code_collateral_units;
+use cfx_state::{
+    state_trait::{CheckpointTrait, StateOpsTrait},
+    CleanupMode, CollateralCheckResult, StateTrait, SubstateTrait,
+};
+use cfx_statedb::Result as DbResult;
+use cfx_storage::StorageStateTrait;
+use cfx_types::{Address, AddressWithSpace, Space, H256, U256};
+use std::collections::HashMap;
+
+/// A state modification which bypasses transaction execution. It is only
+/// requested by the dev mode RPCs (`*_setBalance`, `*_setCode` and
+/// `*_setStorageAt`).
+#[derive(Clone, Debug)]
+pub enum StateOverride {
+    Balance {
+        address: AddressWithSpace,
+        balance: U256,
+    },
+    Code {
+        address: AddressWithSpace,
+        code: Bytes,
+    },
+    Storage {
+        address: AddressWithSpace,
+        key: U256,
+        value: U256,
+    },
+}
+
+impl StateOverride {
+    pub fn address(&self) -> &AddressWithSpace {
+        match self {
+            StateOverride::Balance { address, .. }
+            | StateOverride::Code { address, .. }
+            | StateOverride::Storage { address, .. } => address,
+        }
+    }
+}
+
+/// Keeps the `StateOverride`s requested by the dev mode RPCs. A new override
+/// is pending until an epoch takes it, and it stays bound to that epoch so
+/// that it is applied again if the epoch is re-executed.
+#[derive(Default)]
+pub struct StateOverrideStore {
+    pending: Vec<StateOverride>,
+    by_epoch: HashMap<H256, Vec<StateOverride>>,
+}
+
+impl StateOverrideStore {
+    pub fn add(&mut self, state_override: StateOverride) {
+        self.pending.push(state_override);
+    }
+
+    /// Binds the pending overrides to `epoch_hash`, and returns all the
+    /// overrides to apply before executing the epoch.
+    pub fn take_for_epoch(&mut self, epoch_hash: &H256) -> Vec<StateOverride> {
+        let pending = std::mem::take(&mut self.pending);
+        if pending.is_empty() {
+            return self
+                .by_epoch
+                .get(epoch_hash)
+                .cloned()
+                .unwrap_or_default();
+        }
+        let overrides = self.by_epoch.entry(*epoch_hash).or_default();
+        overrides.extend(pending);
+        overrides.clone()
+    }
+}
+
+impl<StateDbStorage: StorageStateTrait> StateGeneric<StateDbStorage> {
+    /// Applies a `StateOverride`. In the native space, the collateral of the
+    /// code and storage is charged from the contract like it is written by
+    /// the contract itself, and the override is reverted if the contract
+    /// can not afford it.
+    pub fn apply_override(
+        &mut self, state_override: &StateOverride, account_start_nonce: U256,
+    ) -> DbResult<CollateralCheckResult> {
+        let address = *state_override.address();
+        let mut substate = Substate::new();
+        self.checkpoint();
+
+        match state_override {
+            StateOverride::Balance { balance, .. } => {
+                let current = self.balance(&address)?;
+                if *balance > current {
+                    let by = *balance - current;
+                    self.add_balance(
+                        &address,
+                        &by,
+                        CleanupMode::ForceCreate,
+                        account_start_nonce,
+                    )?;
+                    self.add_total_issued(by);
+                    if address.space == Space::Ethereum {
+                        self.add_total_evm_tokens(by);
+                    }
+                } else if *balance < current {
+                    let by = current - *balance;
+                    self.sub_balance(&address, &by, &mut CleanupMode::NoEmpty)?;
+                    self.subtract_total_issued(by);
+                    if address.space == Space::Ethereum {
+                        self.subtract_total_evm_tokens(by);
+                    }
+                }
+            }
+            StateOverride::Code { code, .. } => {
+                self.add_balance(
+                    &address,
+                    &U256::zero(),
+                    CleanupMode::ForceCreate,
+                    account_start_nonce,
+                )?;
+                let owner = if address.space == Space::Native {
+                    if let Some(old_owner) = self.code_owner(&address)? {
+                        let old_size = self.code_size(&address)?.unwrap_or(0);
+                        substate.record_storage_release(
+                            &old_owner,
+                            code_collateral_units(old_size),
+                        );
+                    }
+                    substate.record_storage_occupy(
+                        &address.address,
+                        code_collateral_units(code.len()),
+                    );
+                    address.address
+                } else {
+                    Address::zero()
+                };
+                self.init_code(&address, code.clone(), owner)?;
+            }
+            StateOverride::Storage { key, value, .. } => {
+                self.add_balance(
+                    &address,
+                    &U256::zero(),
+                    CleanupMode::ForceCreate,
+                    account_start_nonce,
+                )?;
+                let mut storage_key = vec![0; 32];
+                key.to_big_endian(storage_key.as_mut());
+                self.set_storage(
+                    &address,
+                    storage_key,
+                    *value,
+                    address.address,
+                )?;
+            }
+        }
+
+        let result = if address.space == Space::Native {
+            self.collect_ownership_changed(&mut substate)?;
+            self.settle_collateral_for_all(
+                &substate,
+                &mut (),
+                account_start_nonce,
+            )?
+        } else {
+            CollateralCheckResult::Valid
+        };
+        match result {
+            CollateralCheckResult::Valid => self.discard_checkpoint(),
+            _ => self.revert_to_checkpoint(),
+        }
+        Ok(result)
+    }
+}
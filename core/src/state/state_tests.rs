@@ -2,7 +2,10 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use super::{CleanupMode, CollateralCheckResult, State, Substate};
+use super::{
+    CleanupMode, CollateralCheckResult, State, StateOverride,
+    StateOverrideStore, Substate,
+};
 use crate::{
     spec::genesis::DEV_GENESIS_KEY_PAIR,
     test_helpers::get_state_for_genesis_write, vm::Spec,
@@ -19,7 +22,8 @@ use cfx_storage::{
     StorageManagerTrait,
 };
 use cfx_types::{
-    address_util::AddressUtil, Address, AddressSpaceUtil, BigEndianHash, U256,
+    address_util::AddressUtil, Address, AddressSpaceUtil, BigEndianHash, H256,
+    U256,
 };
 use keccak_hash::{keccak, KECCAK_EMPTY};
 use primitives::{EpochId, StorageKey, StorageLayout};
@@ -1867,3 +1871,115 @@ fn test_automatic_collateral_contract_account() {
     assert_eq!(state.total_storage_tokens(), U256::from(0));
     assert_eq!(state.secondary_reward(), U256::from(0));
 }
+
+#[test]
+fn test_apply_override() {
+    let storage_manager = new_state_manager_for_unit_test();
+    let mut state = get_state_for_genesis_write(&storage_manager);
+    let account_start_nonce = Spec::new_spec_for_test().account_start_nonce;
+    let evm_account = Address::random().with_evm_space();
+    let total_issued = state.total_issued_tokens();
+
+    let set_balance = |state: &mut State, balance: u64| {
+        state
+            .apply_override(
+                &StateOverride::Balance {
+                    address: evm_account,
+                    balance: balance.into(),
+                },
+                account_start_nonce,
+            )
+            .unwrap()
+    };
+    assert_eq!(set_balance(&mut state, 1000), CollateralCheckResult::Valid);
+    assert_eq!(state.balance(&evm_account).unwrap(), U256::from(1000));
+    assert_eq!(state.total_issued_tokens(), total_issued + U256::from(1000));
+    assert_eq!(state.total_espace_tokens(), U256::from(1000));
+    assert_eq!(set_balance(&mut state, 400), CollateralCheckResult::Valid);
+    assert_eq!(state.balance(&evm_account).unwrap(), U256::from(400));
+    assert_eq!(state.total_issued_tokens(), total_issued + U256::from(400));
+    assert_eq!(state.total_espace_tokens(), U256::from(400));
+
+    let key = U256::from(1);
+    assert_eq!(
+        state
+            .apply_override(
+                &StateOverride::Storage {
+                    address: evm_account,
+                    key,
+                    value: 7.into(),
+                },
+                account_start_nonce,
+            )
+            .unwrap(),
+        CollateralCheckResult::Valid
+    );
+    assert_eq!(
+        state.storage_at(&evm_account, &u256_to_vec(&key)).unwrap(),
+        U256::from(7)
+    );
+
+    // The storage collateral can not be afforded by the native contract.
+    let mut contract = Address::random();
+    contract.set_contract_type_bits();
+    let contract = contract.with_native_space();
+    state
+        .new_contract(&contract, U256::zero(), account_start_nonce)
+        .unwrap();
+    assert!(matches!(
+        state
+            .apply_override(
+                &StateOverride::Storage {
+                    address: contract,
+                    key,
+                    value: 7.into(),
+                },
+                account_start_nonce,
+            )
+            .unwrap(),
+        CollateralCheckResult::NotEnoughBalance { .. }
+    ));
+    assert_eq!(
+        state.storage_at(&contract, &u256_to_vec(&key)).unwrap(),
+        U256::zero()
+    );
+}
+
+#[test]
+fn test_state_override_store_reexecution() {
+    let account_start_nonce = Spec::new_spec_for_test().account_start_nonce;
+    let account = Address::random().with_evm_space();
+    let (epoch, next_epoch) = (H256::random(), H256::random());
+    let mut store = StateOverrideStore::default();
+    store.add(StateOverride::Balance {
+        address: account,
+        balance: 1000.into(),
+    });
+
+    let execute = |overrides: Vec<StateOverride>| {
+        let storage_manager = new_state_manager_for_unit_test();
+        let mut state = get_state_for_genesis_write(&storage_manager);
+        for state_override in &overrides {
+            assert_eq!(
+                state
+                    .apply_override(state_override, account_start_nonce)
+                    .unwrap(),
+                CollateralCheckResult::Valid
+            );
+        }
+        state.balance(&account).unwrap()
+    };
+    assert_eq!(execute(store.take_for_epoch(&epoch)), U256::from(1000));
+
+    // The override added later belongs to the next executed epoch.
+    store.add(StateOverride::Balance {
+        address: account,
+        balance: 2000.into(),
+    });
+    assert_eq!(execute(store.take_for_epoch(&next_epoch)), U256::from(2000));
+
+    // Re-executing the epochs applies the same overrides again.
+    assert_eq!(execute(store.take_for_epoch(&epoch)), U256::from(1000));
+    assert_eq!(execute(store.take_for_epoch(&next_epoch)), U256::from(2000));
+    assert!(store.take_for_epoch(&H256::random()).is_empty());
+}
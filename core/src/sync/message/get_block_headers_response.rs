@@ -181,6 +181,17 @@ impl GetBlockHeadersResponse {
                 assert!(true);
             }
 
+            if let Err(e) = ctx
+                .manager
+                .graph
+                .data_man
+                .verify_header_with_trusted_checkpoint(header)
+            {
+                warn!("Invalid header {:?}: {}", hash, e);
+                has_invalid_header = true;
+                continue;
+            }
+
             // insert into sync graph
             let (insert_result, to_relay) = {
                 let _pm_lock = ctx.manager.phase_manager_lock.lock();
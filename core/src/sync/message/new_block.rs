@@ -75,6 +75,15 @@ fn on_new_decoded_block(
     match ctx.manager.graph.block_header_by_hash(&hash) {
         Some(header) => block.block_header = header,
         None => {
+            if let Err(e) = ctx
+                .manager
+                .graph
+                .data_man
+                .verify_header_with_trusted_checkpoint(&block.block_header)
+            {
+                warn!("Invalid header {:?}: {}", hash, e);
+                return Err(Error::from_kind(ErrorKind::InvalidBlock));
+            }
             let (insert_result, to_relay) =
                 ctx.manager.graph.insert_block_header(
                     &mut block.block_header,
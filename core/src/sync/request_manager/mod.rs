@@ -228,7 +228,7 @@ impl RequestManager {
         if peer.is_none() || delay.is_some() {
             debug!("request_with_delay: add request to waiting_requests, peer={:?}, request={:?}, delay={:?}", peer, request, cur_delay);
             self.waiting_requests.lock().push(TimedWaitingRequest::new(
-                Instant::now() + cur_delay,
+                io.now() + cur_delay,
                 WaitingRequest(request, next_delay),
                 peer,
             ));
@@ -249,7 +249,7 @@ impl RequestManager {
                 self.remove_net_inflight_blocks(hashes.iter())
             }
             self.waiting_requests.lock().push(TimedWaitingRequest::new(
-                Instant::now() + cur_delay,
+                io.now() + cur_delay,
                 WaitingRequest(e, next_delay),
                 None,
             ));
@@ -885,7 +885,7 @@ impl RequestManager {
     {
         debug!("resend_waiting_requests: start");
         let mut waiting_requests = self.waiting_requests.lock();
-        let now = Instant::now();
+        let now = io.now();
         let mut batcher =
             RequestBatcher::new(*DEFAULT_REQUEST_BATCH_BUCKET_SIZE);
 
@@ -948,7 +948,7 @@ impl RequestManager {
                         self.remove_net_inflight_blocks(hashes.iter())
                     }
                     waiting_requests.push(TimedWaitingRequest::new(
-                        Instant::now() + next_delay,
+                        io.now() + next_delay,
                         WaitingRequest(request, next_delay),
                         None,
                     ));
@@ -967,7 +967,7 @@ impl RequestManager {
                 Some(next_delay),
             ) {
                 waiting_requests.push(TimedWaitingRequest::new(
-                    Instant::now() + next_delay,
+                    io.now() + next_delay,
                     WaitingRequest(request, next_delay),
                     None,
                 ));
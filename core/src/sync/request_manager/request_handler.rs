@@ -135,10 +135,11 @@ impl RequestHandler {
         Ok(())
     }
 
-    fn get_timeout_sync_requests(&self) -> Vec<Arc<TimedSyncRequests>> {
+    fn get_timeout_sync_requests(
+        &self, now: Instant,
+    ) -> Vec<Arc<TimedSyncRequests>> {
         let mut requests = self.requests_queue.lock();
         let mut timeout_requests = Vec::new();
-        let now = Instant::now();
         loop {
             if requests.is_empty() {
                 break;
@@ -165,7 +166,7 @@ impl RequestHandler {
         let mut timeout_requests = Vec::new();
         let mut peers_to_disconnect = HashSet::new();
         let mut peers_to_send_pending_requests = HashSet::new();
-        for sync_req in self.get_timeout_sync_requests() {
+        for sync_req in self.get_timeout_sync_requests(io.now()) {
             if let Ok(mut req) =
                 self.match_request(&sync_req.peer_id, sync_req.request_id)
             {
@@ -337,6 +338,7 @@ impl RequestContainer {
             &request_message,
             protocol_config,
             is_send_error,
+            io.now(),
         ));
         self.append_inflight_request(
             request_id,
@@ -520,11 +522,11 @@ pub struct TimedSyncRequests {
 
 impl TimedSyncRequests {
     pub fn new(
-        peer_id: NodeId, timeout: Duration, request_id: u64,
+        peer_id: NodeId, timeout: Duration, request_id: u64, now: Instant,
     ) -> TimedSyncRequests {
         TimedSyncRequests {
             peer_id,
-            timeout_time: Instant::now() + timeout,
+            timeout_time: now + timeout,
             request_id,
             removed: AtomicBool::new(false),
         }
@@ -532,7 +534,7 @@ impl TimedSyncRequests {
 
     pub fn from_request(
         peer_id: NodeId, request_id: u64, msg: &RequestMessage,
-        conf: &ProtocolConfiguration, is_send_error: bool,
+        conf: &ProtocolConfiguration, is_send_error: bool, now: Instant,
     ) -> TimedSyncRequests
     {
         let timeout = if is_send_error {
@@ -540,7 +542,7 @@ impl TimedSyncRequests {
         } else {
             msg.request.timeout(conf)
         };
-        TimedSyncRequests::new(peer_id, timeout, request_id)
+        TimedSyncRequests::new(peer_id, timeout, request_id, now)
     }
 }
 
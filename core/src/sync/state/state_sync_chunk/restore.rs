@@ -4,9 +4,9 @@
 
 use crate::sync::state::storage::{Chunk, ChunkKey};
 use cfx_storage::{
-    state_manager::StateManager,
+    state_manager::{SnapshotDbManager, StateManager},
     storage_db::{SnapshotDbManagerTrait, SnapshotInfo},
-    FullSyncVerifier, Result as StorageResult, SnapshotDbManagerSqlite,
+    FullSyncVerifier, Result as StorageResult,
 };
 use primitives::{EpochId, MerkleHash};
 use std::sync::Arc;
@@ -17,7 +17,7 @@ pub struct Restorer {
 
     /// The verifier for chunks.
     /// Initialized after receiving a valid manifest.
-    verifier: Option<FullSyncVerifier<SnapshotDbManagerSqlite>>,
+    verifier: Option<FullSyncVerifier<SnapshotDbManager>>,
 }
 
 impl Restorer {
@@ -32,7 +32,7 @@ impl Restorer {
     }
 
     pub fn initialize_verifier(
-        &mut self, verifier: FullSyncVerifier<SnapshotDbManagerSqlite>,
+        &mut self, verifier: FullSyncVerifier<SnapshotDbManager>,
     ) {
         self.verifier = Some(verifier);
     }
@@ -1913,6 +1913,75 @@ impl SynchronizationGraph {
         inner.remove_blocks(&index_set_and_future);
     }
 
+    /// Remove the blocks out of the past of `block_hash` from the graph and
+    /// the database, and rebuild the consensus graph with the remaining
+    /// blocks in the same way as recovering from the database, so
+    /// `block_hash` becomes the best block again. The execution results of
+    /// the remaining epochs are kept, so their states are not recomputed.
+    /// Return the removed blocks.
+    ///
+    /// This is only used to revert the chain in dev mode, and fails if the
+    /// consensus worker has not processed all the blocks.
+    pub fn revert_to_block(
+        &self, block_hash: &H256,
+    ) -> Result<Vec<Arc<Block>>, String> {
+        if self.is_consensus_worker_busy() {
+            return Err("consensus worker is busy".into());
+        }
+        let inner = &mut *self.inner.write();
+        let index = *inner.hash_to_arena_indices.get(block_hash).ok_or_else(
+            || format!("block {:?} is not in the graph", block_hash),
+        )?;
+        let past: HashSet<usize> = dag::get_future(
+            vec![index],
+            |i| inner.predecessor_edges(i),
+            |_| false,
+        );
+        let to_remove: HashSet<usize> = inner
+            .hash_to_arena_indices
+            .values()
+            .filter(|i| !past.contains(i))
+            .cloned()
+            .collect();
+        let removed_blocks = to_remove
+            .iter()
+            .filter_map(|i| {
+                let hash = inner.arena[*i].block_header.hash();
+                self.data_man.block_by_hash(&hash, false /* update_cache */)
+            })
+            .collect();
+        let removed_hashes: Vec<H256> = to_remove
+            .iter()
+            .map(|i| inner.arena[*i].block_header.hash())
+            .collect();
+        inner.remove_blocks(&to_remove);
+        // The removed blocks have entered consensus, so they are not removed
+        // from the database by `remove_blocks`.
+        for hash in &removed_hashes {
+            self.data_man.remove_block_header(hash, true /* remove_db */);
+            self.data_man.remove_block_body(hash, true /* remove_db */);
+        }
+
+        self.consensus.reset();
+        let genesis_hash = self.data_man.get_cur_consensus_era_genesis_hash();
+        if let Some(genesis_local_info) =
+            self.data_man.local_block_info_by_hash(&genesis_hash)
+        {
+            self.consensus
+                .set_initial_sequence_number(genesis_local_info.get_seq_num());
+        }
+        for i in inner.topological_sort(past) {
+            let hash = inner.arena[i].block_header.hash();
+            // Genesis block is already in consensus graph.
+            if hash != genesis_hash {
+                self.consensus.on_new_block(&hash);
+            }
+        }
+        self.consensus.construct_pivot_state();
+        self.consensus.enter_normal_phase();
+        Ok(removed_blocks)
+    }
+
     pub fn is_consensus_worker_busy(&self) -> bool {
         self.consensus_unprocessed_count.load(Ordering::SeqCst) != 0
     }
@@ -14,8 +14,9 @@ use crate::{
 };
 use cfx_internal_common::StateAvailabilityBoundary;
 use cfx_parameters::sync::CATCH_UP_EPOCH_LAG_THRESHOLD;
+use cfx_types::H256;
 use network::NetworkContext;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::{
     collections::HashMap,
     sync::{
@@ -34,6 +35,10 @@ use std::{
 ///     CatchUpRecoverBlockHeaderFromDB --> CatchUpSyncBlockHeader -->
 ///     CatchUpCheckpoint --> CatchUpFillBlockBody -->
 ///     CatchUpSyncBlock --> Normal
+///
+/// A full node with a trusted checkpoint starts the header sync from the era
+/// of the checkpoint instead of the true genesis, and syncs the snapshot of
+/// the trusted checkpoint unless consensus makes a later one.
 
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub enum SyncPhaseType {
@@ -302,6 +307,10 @@ pub struct CatchUpCheckpointPhase {
     /// Is `true` if we have the state locally and do not need to sync
     /// checkpoints. Only set when the phase starts.
     has_state: AtomicBool,
+
+    /// The last epoch to sync which is verified against the trusted
+    /// checkpoint.
+    trusted_epoch_to_sync: Mutex<Option<H256>>,
 }
 
 impl CatchUpCheckpointPhase {
@@ -309,6 +318,39 @@ impl CatchUpCheckpointPhase {
         CatchUpCheckpointPhase {
             state_sync,
             has_state: AtomicBool::new(false),
+            trusted_epoch_to_sync: Mutex::new(None),
+        }
+    }
+
+    /// Return `false` if the pivot chain ending at `epoch_to_sync` conflicts
+    /// with the trusted checkpoint. The state is not synced in this case,
+    /// and the check is repeated until the pivot chain changes.
+    fn verify_trusted_checkpoint(
+        &self, epoch_to_sync: &H256,
+        sync_handler: &SynchronizationProtocolHandler,
+    ) -> bool
+    {
+        let mut trusted_epoch_to_sync = self.trusted_epoch_to_sync.lock();
+        if trusted_epoch_to_sync.as_ref() == Some(epoch_to_sync) {
+            return true;
+        }
+        match sync_handler
+            .graph
+            .data_man
+            .verify_trusted_checkpoint(epoch_to_sync)
+        {
+            Ok(()) => {
+                *trusted_epoch_to_sync = Some(*epoch_to_sync);
+                true
+            }
+            Err(e) => {
+                error!(
+                    "Synced headers conflict with trusted checkpoint, do not \
+                     sync the state of epoch {:?}: {}",
+                    epoch_to_sync, e
+                );
+                false
+            }
         }
     }
 }
@@ -327,6 +369,9 @@ impl SynchronizationPhaseTrait for CatchUpCheckpointPhase {
             return SyncPhaseType::CatchUpFillBlockBodyPhase;
         }
         let epoch_to_sync = sync_handler.graph.consensus.get_to_sync_epoch_id();
+        if !self.verify_trusted_checkpoint(&epoch_to_sync, sync_handler) {
+            return self.phase_type();
+        }
         let current_era_genesis = sync_handler
             .graph
             .data_man
@@ -383,6 +428,10 @@ impl SynchronizationPhaseTrait for CatchUpCheckpointPhase {
             return;
         }
 
+        if !self.verify_trusted_checkpoint(&epoch_to_sync, sync_handler) {
+            return;
+        }
+
         self.state_sync.update_status(
             current_era_genesis,
             epoch_to_sync,
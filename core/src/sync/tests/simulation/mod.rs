@@ -0,0 +1,348 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! A harness to run several full nodes in one process over a simulated
+//! network, so that the sync and consensus scenarios can be scripted and
+//! replayed from a seed.
+
+mod network;
+mod scenarios;
+
+pub use self::network::{Delivery, LinkConfig, SimulatedNetwork};
+
+use crate::{
+    block_data_manager::{
+        trusted_checkpoint::TrustedCheckpoint, BlockDataManager,
+        DataManagerConfiguration, DbType,
+    },
+    consensus::{ConsensusGraph, ConsensusGraphTrait},
+    light_protocol::Provider as LightProvider,
+    pow::{self, PowComputer, ProofOfWorkProblem},
+    sync::{
+        utils::{
+            initialize_data_manager_with_config,
+            initialize_full_node_synchronization_graph_with_data_manager,
+            initialize_synchronization_graph_with_data_manager,
+        },
+        ProtocolConfiguration, StateSyncConfiguration, SyncPhaseType,
+        SynchronizationGraph, SynchronizationProtocolHandler,
+    },
+    vm_factory::VmFactory,
+    NodeType,
+};
+use cfx_parameters::{
+    consensus::{DEFERRED_STATE_EPOCH_COUNT, GENESIS_GAS_LIMIT},
+    sync::DEFAULT_CHUNK_SIZE,
+};
+use cfx_types::{address_util::AddressUtil, Address, H256, U256};
+use primitives::{Block, BlockHeaderBuilder};
+use std::{sync::Arc, thread, time::Duration};
+use tempdir::TempDir;
+
+pub struct SimulatedNode {
+    pub sync_handler: Arc<SynchronizationProtocolHandler>,
+    pub sync_graph: Arc<SynchronizationGraph>,
+    pub consensus: Arc<ConsensusGraph>,
+    pub data_man: Arc<BlockDataManager>,
+    _db_dir: TempDir,
+}
+
+pub struct Simulation {
+    pub network: SimulatedNetwork,
+    pub nodes: Vec<SimulatedNode>,
+    pub genesis_hash: H256,
+    pow: Arc<PowComputer>,
+}
+
+impl Simulation {
+    /// Start `node_count` archive nodes, all connected to each other.
+    pub fn new(seed: u64, node_count: usize) -> Self {
+        let network = SimulatedNetwork::new(
+            seed,
+            *b"cfx",
+            crate::sync::SYNCHRONIZATION_PROTOCOL_VERSION,
+        );
+        let mut sim = Simulation {
+            network,
+            nodes: Vec::with_capacity(node_count),
+            genesis_hash: H256::zero(),
+            pow: Arc::new(PowComputer::new(true)),
+        };
+        for _ in 0..node_count {
+            sim.add_node(NodeType::Archive, None);
+        }
+        sim.network.connect_all();
+        sim
+    }
+
+    /// Start a full node from `trusted_checkpoint` and connect it to all the
+    /// other nodes. It does not leave the catch-up phases without peers.
+    pub fn add_full_node(
+        &mut self, trusted_checkpoint: TrustedCheckpoint,
+    ) -> usize {
+        let node = self.add_node(NodeType::Full, Some(trusted_checkpoint));
+        for peer in 0..node {
+            self.network.connect(node, peer);
+        }
+        node
+    }
+
+    fn add_node(
+        &mut self, node_type: NodeType,
+        trusted_checkpoint: Option<TrustedCheckpoint>,
+    ) -> usize
+    {
+        let db_dir = TempDir::new("simulated_node").unwrap();
+        let vm = VmFactory::new(1024 * 32);
+        let mut data_man_config = DataManagerConfiguration::new(
+            false, /* persist_tx_index */
+            false, /* persist_block_number_index */
+            Duration::from_millis(300_000),
+            DbType::Rocksdb,
+        );
+        data_man_config.trusted_checkpoint = trusted_checkpoint;
+        let (data_man, genesis_block) = initialize_data_manager_with_config(
+            db_dir.path().to_str().unwrap(),
+            data_man_config,
+            self.pow.clone(),
+            vm.clone(),
+        );
+        self.genesis_hash = genesis_block.hash();
+        let initialize_synchronization_graph = match node_type {
+            NodeType::Full => {
+                initialize_full_node_synchronization_graph_with_data_manager
+            }
+            _ => initialize_synchronization_graph_with_data_manager,
+        };
+        let (sync_graph, consensus) = initialize_synchronization_graph(
+            data_man.clone(),
+            1,    /* beta */
+            1,    /* h */
+            1,    /* tcr */
+            1,    /* tcb */
+            5000, /* era_epoch_count */
+            self.pow.clone(),
+            vm,
+        );
+        let light_provider = Arc::new(LightProvider::new(
+            consensus.clone(),
+            sync_graph.clone(),
+            Default::default(), /* network */
+            consensus.txpool.clone(),
+            None, /* throttling_config_file */
+            node_type,
+        ));
+        let mut protocol_config = protocol_config(self.genesis_hash);
+        protocol_config.allow_phase_change_without_peer =
+            node_type == NodeType::Archive;
+        let sync_handler = Arc::new(SynchronizationProtocolHandler::new(
+            node_type,
+            protocol_config,
+            StateSyncConfiguration {
+                max_downloading_chunks: 8,
+                candidate_request_timeout: Duration::from_secs(10),
+                chunk_request_timeout: Duration::from_secs(30),
+                manifest_request_timeout: Duration::from_secs(30),
+            },
+            SyncPhaseType::CatchUpRecoverBlockHeaderFromDB,
+            sync_graph.clone(),
+            light_provider,
+            consensus.clone(),
+        ));
+        let node = self.network.add_node(sync_handler.clone());
+        self.nodes.push(SimulatedNode {
+            sync_handler,
+            sync_graph,
+            consensus,
+            data_man,
+            _db_dir: db_dir,
+        });
+        let sync_graphs: Vec<_> = self
+            .nodes
+            .iter()
+            .map(|node| node.sync_graph.clone())
+            .collect();
+        self.network.set_idle_waiter(move |node| {
+            wait_for_consensus_worker(&sync_graphs[node])
+        });
+        node
+    }
+
+    /// Build a trusted checkpoint from the pivot chain of `node` at `height`,
+    /// with the pivot chain before it back to the true genesis. The pivot
+    /// block committing its state has to be mined already.
+    pub fn trusted_checkpoint(
+        &self, node: usize, height: u64,
+    ) -> TrustedCheckpoint {
+        let data_man = &self.nodes[node].data_man;
+        let inner = self.nodes[node].consensus.inner.read();
+        let header_at = |height: u64| {
+            let hash = inner.get_pivot_hash_from_epoch_number(height).unwrap();
+            data_man.block_header_by_hash(&hash).unwrap()
+        };
+        let epoch_at =
+            |height: u64| inner.block_hashes_by_epoch(height).unwrap();
+        let start_block_number =
+            (0..height).map(|h| epoch_at(h).len() as u64).sum();
+        TrustedCheckpoint {
+            era_genesis_header: (*header_at(height)).clone(),
+            epoch_block_hashes: epoch_at(height),
+            start_block_number,
+            pivot_block_headers: (1..height)
+                .rev()
+                .map(|h| (*header_at(h)).clone())
+                .collect(),
+            pivot_epoch_block_hashes: (1..height).rev().map(epoch_at).collect(),
+            state_root: *header_at(height + DEFERRED_STATE_EPOCH_COUNT)
+                .deferred_state_root(),
+            pos_waypoint: None,
+        }
+    }
+
+    /// Run until all the nodes leave the catch-up phases.
+    pub fn wait_for_normal_phase(&self) -> bool {
+        self.network.run_until_condition(
+            Duration::from_millis(100),
+            Duration::from_secs(60),
+            || {
+                self.nodes
+                    .iter()
+                    .all(|node| !node.sync_handler.catch_up_mode())
+            },
+        )
+    }
+
+    /// Mine a block on `node` with a valid proof of work and relay it to its
+    /// peers. The block is distinct from those mined by the other nodes on
+    /// the same parent.
+    pub fn mine_block(
+        &self, node: usize, parent_hash: H256, referee_hashes: Vec<H256>,
+    ) -> H256 {
+        let parent = self.nodes[node]
+            .data_man
+            .block_header_by_hash(&parent_hash)
+            .expect("parent exists");
+        let mut author = Address::from_low_u64_be(node as u64 + 1);
+        author.set_user_account_type_bits();
+        let mut header = BlockHeaderBuilder::new()
+            .with_parent_hash(parent_hash)
+            .with_height(parent.height() + 1)
+            .with_referee_hashes(referee_hashes)
+            .with_gas_limit(GENESIS_GAS_LIMIT.into())
+            .with_difficulty(U256::from(10))
+            .with_author(author)
+            .with_timestamp(self.network.now().as_secs())
+            .build();
+
+        let boundary = pow::difficulty_to_boundary(header.difficulty());
+        let problem_hash = header.problem_hash();
+        let mut nonce = U256::zero();
+        loop {
+            let pow_hash =
+                self.pow.compute(&nonce, &problem_hash, header.height());
+            if ProofOfWorkProblem::validate_hash_against_boundary(
+                &pow_hash, &nonce, &boundary,
+            ) {
+                header.set_nonce(nonce);
+                header.pow_hash = Some(pow_hash);
+                break;
+            }
+            nonce += U256::one();
+        }
+        let hash = header.compute_hash();
+
+        let sync_handler = &self.nodes[node].sync_handler;
+        sync_handler.on_mined_block(Block::new(header, vec![]));
+        self.network.wait_for_idle(node);
+        self.network.with_context(node, |io| {
+            sync_handler.relay_blocks(io, vec![hash]).unwrap()
+        });
+        hash
+    }
+
+    /// Mine a block on the pivot chain of `node` referencing the other
+    /// terminal blocks it knows.
+    pub fn mine_on_best(&self, node: usize) -> H256 {
+        let consensus = &self.nodes[node].consensus;
+        let parent_hash = consensus.best_block_hash();
+        let referee_hashes = consensus
+            .best_info()
+            .bounded_terminal_block_hashes
+            .iter()
+            .filter(|hash| **hash != parent_hash)
+            .cloned()
+            .collect();
+        self.mine_block(node, parent_hash, referee_hashes)
+    }
+
+    pub fn has_block(&self, node: usize, hash: &H256) -> bool {
+        self.nodes[node].sync_graph.contains_block(hash)
+    }
+
+    /// Run until all the nodes have the block in their consensus graph.
+    pub fn wait_for_block(&self, hash: &H256, timeout: Duration) -> bool {
+        self.network.run_until_condition(
+            Duration::from_millis(100),
+            timeout,
+            || {
+                self.nodes.iter().all(|node| {
+                    node.consensus
+                        .inner
+                        .read()
+                        .hash_to_arena_indices
+                        .contains_key(hash)
+                })
+            },
+        )
+    }
+}
+
+/// Wait for the consensus worker to process the blocks inserted into the
+/// synchronization graph.
+fn wait_for_consensus_worker(sync_graph: &SynchronizationGraph) {
+    while sync_graph.is_consensus_worker_busy() {
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn protocol_config(genesis_hash: H256) -> ProtocolConfiguration {
+    ProtocolConfiguration {
+        is_consortium: false,
+        send_tx_period: Duration::from_millis(1300),
+        check_request_period: Duration::from_millis(1000),
+        check_phase_change_period: Duration::from_millis(1000),
+        heartbeat_period_interval: Duration::from_secs(30),
+        heartbeat_timeout: Duration::from_secs(180),
+        block_cache_gc_period: Duration::from_secs(5),
+        expire_block_gc_period: Duration::from_secs(900),
+        sync_expire_block_timeout: Duration::from_secs(7200),
+        headers_request_timeout: Duration::from_secs(10),
+        blocks_request_timeout: Duration::from_secs(20),
+        transaction_request_timeout: Duration::from_secs(30),
+        snapshot_candidate_request_timeout: Duration::from_secs(10),
+        snapshot_manifest_request_timeout: Duration::from_secs(30),
+        snapshot_chunk_request_timeout: Duration::from_secs(30),
+        tx_maintained_for_peer_timeout: Duration::from_secs(600),
+        max_inflight_request_count: 64,
+        received_tx_index_maintain_timeout: Duration::from_secs(300),
+        inflight_pending_tx_index_maintain_timeout: Duration::from_secs(30),
+        request_block_with_public: false,
+        max_trans_count_received_in_catch_up: 60_000,
+        min_peers_tx_propagation: 8,
+        max_peers_tx_propagation: 128,
+        max_downloading_chunks: 8,
+        test_mode: true,
+        dev_mode: false,
+        throttling_config_file: None,
+        chunk_size_byte: DEFAULT_CHUNK_SIZE,
+        timeout_observing_period_s: 600,
+        max_allowed_timeout_in_observing_period: 10,
+        demote_peer_for_timeout: false,
+        max_unprocessed_block_size: 128_000_000,
+        max_chunk_number_in_manifest: 500,
+        allow_phase_change_without_peer: true,
+        pos_genesis_pivot_decision: genesis_hash,
+        check_status_genesis: true,
+    }
+}
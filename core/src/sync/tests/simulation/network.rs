@@ -0,0 +1,587 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use io::TimerToken;
+use network::{
+    node_table::NodeId, service::ProtocolVersion, Error as NetworkError,
+    HandlerWorkType, NetworkContext, NetworkProtocolHandler, ProtocolId,
+    UpdateNodeOperation,
+};
+use parking_lot::Mutex;
+use priority_send_queue::SendQueuePriority;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    cmp::{max, Ordering},
+    collections::{BinaryHeap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// The latency and the reliability of the messages sent from one node to
+/// another.
+#[derive(Clone, Debug)]
+pub struct LinkConfig {
+    pub min_latency: Duration,
+    pub max_latency: Duration,
+    /// The probability that a message is lost.
+    pub drop_rate: f64,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        LinkConfig {
+            min_latency: Duration::from_millis(10),
+            max_latency: Duration::from_millis(100),
+            drop_rate: 0.0,
+        }
+    }
+}
+
+/// A message delivered to a node, recorded to compare the runs with the same
+/// seed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delivery {
+    pub time: Duration,
+    pub from: usize,
+    pub to: usize,
+    pub data: Vec<u8>,
+}
+
+enum EventKind {
+    Connected {
+        node: usize,
+        peer: usize,
+    },
+    Disconnected {
+        node: usize,
+        peer: usize,
+    },
+    Message {
+        from: usize,
+        to: usize,
+        data: Vec<u8>,
+    },
+    /// The timers registered by the handlers are periodic, like those of the
+    /// network service.
+    Timer {
+        node: usize,
+        token: TimerToken,
+        period: Duration,
+    },
+    Work {
+        node: usize,
+        work_type: HandlerWorkType,
+    },
+}
+
+struct Event {
+    time: Duration,
+    /// Events at the same time are processed in the order they're scheduled.
+    seq: u64,
+    kind: EventKind,
+}
+
+impl PartialEq for Event {
+    fn eq(&self, other: &Self) -> bool {
+        self.time == other.time && self.seq == other.seq
+    }
+}
+
+impl Eq for Event {}
+
+impl PartialOrd for Event {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Event {
+    // Reversed for the earliest event to be on the top of the heap.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .time
+            .cmp(&self.time)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+struct NetworkState {
+    /// The virtual clock, i.e. the time since the start of the simulation.
+    now: Duration,
+    next_seq: u64,
+    rng: StdRng,
+    events: BinaryHeap<Event>,
+    default_link: LinkConfig,
+    links: HashMap<(usize, usize), LinkConfig>,
+    /// The time the last message sent on a link is delivered. Like a TCP
+    /// connection, a link never reorders the messages.
+    link_busy_until: HashMap<(usize, usize), Duration>,
+    /// Maps a connection to whether the first node originated it.
+    connections: HashMap<(usize, usize), bool>,
+    /// The partition of each node. Messages between partitions are lost.
+    partitions: Vec<usize>,
+    deliveries: Vec<Delivery>,
+}
+
+impl NetworkState {
+    fn schedule(&mut self, delay: Duration, kind: EventKind) {
+        let event = Event {
+            time: self.now + delay,
+            seq: self.next_seq,
+            kind,
+        };
+        self.next_seq += 1;
+        self.events.push(event);
+    }
+
+    fn is_connected(&self, a: usize, b: usize) -> bool {
+        self.connections.contains_key(&(a, b))
+    }
+}
+
+/// A network which runs the protocol handlers of several nodes in one
+/// process. The messages are delivered and the timers fire by a virtual
+/// clock, which only advances when the simulation runs, in an order decided
+/// by the seed.
+///
+/// The handlers are called on the thread running the simulation. The work
+/// they do on their own threads, e.g. the consensus graph processing the
+/// blocks, is waited for by the idle waiter after each event, so that it is
+/// done before the virtual clock advances.
+pub struct SimulatedNetwork {
+    protocol: ProtocolId,
+    protocol_version: ProtocolVersion,
+    handlers: Vec<Arc<dyn NetworkProtocolHandler>>,
+    state: Mutex<NetworkState>,
+    /// The instant of the virtual clock at the start of the simulation, which
+    /// is given to the handlers as the current time.
+    start: Instant,
+    idle_waiter: Option<Box<dyn Fn(usize) + Send + Sync>>,
+}
+
+impl SimulatedNetwork {
+    pub fn new(
+        seed: u64, protocol: ProtocolId, protocol_version: ProtocolVersion,
+    ) -> Self {
+        SimulatedNetwork {
+            protocol,
+            protocol_version,
+            handlers: Vec::new(),
+            state: Mutex::new(NetworkState {
+                now: Duration::from_secs(0),
+                next_seq: 0,
+                rng: StdRng::seed_from_u64(seed),
+                events: BinaryHeap::new(),
+                default_link: Default::default(),
+                links: HashMap::new(),
+                link_busy_until: HashMap::new(),
+                connections: HashMap::new(),
+                partitions: Vec::new(),
+                deliveries: Vec::new(),
+            }),
+            start: Instant::now(),
+            idle_waiter: None,
+        }
+    }
+
+    /// Set the function called with a node after it handles an event, which
+    /// returns when the work the node hands over to other threads is done.
+    pub fn set_idle_waiter(
+        &mut self, idle_waiter: impl Fn(usize) + Send + Sync + 'static,
+    ) {
+        self.idle_waiter = Some(Box::new(idle_waiter));
+    }
+
+    /// Wait for the work of a node on other threads to be done.
+    pub fn wait_for_idle(&self, node: usize) {
+        if let Some(idle_waiter) = &self.idle_waiter {
+            idle_waiter(node);
+        }
+    }
+
+    /// Add a node, initializing its handler, and return its index.
+    pub fn add_node(
+        &mut self, handler: Arc<dyn NetworkProtocolHandler>,
+    ) -> usize {
+        let node = self.handlers.len();
+        self.handlers.push(handler);
+        self.state.lock().partitions.push(0);
+        self.with_context(node, |io| self.handlers[node].initialize(io));
+        node
+    }
+
+    pub fn node_count(&self) -> usize { self.handlers.len() }
+
+    pub fn node_id(node: usize) -> NodeId {
+        NodeId::from_low_u64_be(node as u64 + 1)
+    }
+
+    fn node_index(&self, node_id: &NodeId) -> Option<usize> {
+        let index = node_id.to_low_u64_be() as usize;
+        if index >= 1
+            && index <= self.handlers.len()
+            && Self::node_id(index - 1) == *node_id
+        {
+            Some(index - 1)
+        } else {
+            None
+        }
+    }
+
+    pub fn now(&self) -> Duration { self.state.lock().now }
+
+    fn now_instant(&self) -> Instant { self.start + self.now() }
+
+    pub fn set_default_link(&self, link: LinkConfig) {
+        self.state.lock().default_link = link;
+    }
+
+    /// Configure the link from `from` to `to`. The other direction is not
+    /// changed.
+    pub fn set_link(&self, from: usize, to: usize, link: LinkConfig) {
+        self.state.lock().links.insert((from, to), link);
+    }
+
+    /// Connect two nodes, `from` being the originator. The handlers are
+    /// notified when the simulation runs.
+    pub fn connect(&self, from: usize, to: usize) {
+        let mut state = self.state.lock();
+        if from == to || state.is_connected(from, to) {
+            return;
+        }
+        state.connections.insert((from, to), true);
+        state.connections.insert((to, from), false);
+        state.schedule(
+            Duration::from_secs(0),
+            EventKind::Connected {
+                node: from,
+                peer: to,
+            },
+        );
+        state.schedule(
+            Duration::from_secs(0),
+            EventKind::Connected {
+                node: to,
+                peer: from,
+            },
+        );
+    }
+
+    /// Connect every pair of nodes.
+    pub fn connect_all(&self) {
+        for from in 0..self.handlers.len() {
+            for to in from + 1..self.handlers.len() {
+                self.connect(from, to);
+            }
+        }
+    }
+
+    pub fn disconnect(&self, a: usize, b: usize) {
+        Self::disconnect_inner(&mut *self.state.lock(), a, b);
+    }
+
+    fn disconnect_inner(state: &mut NetworkState, a: usize, b: usize) {
+        if state.connections.remove(&(a, b)).is_none() {
+            return;
+        }
+        state.connections.remove(&(b, a));
+        state.schedule(
+            Duration::from_secs(0),
+            EventKind::Disconnected { node: a, peer: b },
+        );
+        state.schedule(
+            Duration::from_secs(0),
+            EventKind::Disconnected { node: b, peer: a },
+        );
+    }
+
+    /// Split the nodes into partitions. The messages between different
+    /// partitions are lost, including those already sent, but the nodes stay
+    /// connected. The nodes not in any group form another partition.
+    pub fn partition(&self, groups: &[&[usize]]) {
+        let mut state = self.state.lock();
+        for partition in state.partitions.iter_mut() {
+            *partition = 0;
+        }
+        for (i, group) in groups.iter().enumerate() {
+            for node in group.iter() {
+                state.partitions[*node] = i + 1;
+            }
+        }
+    }
+
+    pub fn heal_partitions(&self) { self.partition(&[]); }
+
+    /// The messages delivered so far.
+    pub fn deliveries(&self) -> Vec<Delivery> {
+        self.state.lock().deliveries.clone()
+    }
+
+    /// Call `f` with the network context of a node, e.g. to relay a block
+    /// mined by the node.
+    pub fn with_context<T>(
+        &self, node: usize, f: impl FnOnce(&dyn NetworkContext) -> T,
+    ) -> T {
+        f(&SimulatedContext {
+            network: self,
+            node,
+        })
+    }
+
+    /// Process the next event unless it's after `deadline`. Returns whether
+    /// an event is processed.
+    pub fn step(&self, deadline: Duration) -> bool {
+        let event = {
+            let mut state = self.state.lock();
+            match state.events.peek() {
+                Some(event) if event.time <= deadline => {}
+                _ => return false,
+            }
+            let event = state.events.pop().unwrap();
+            state.now = event.time;
+            match event.kind {
+                EventKind::Message { from, to, data } => {
+                    if !state.is_connected(from, to)
+                        || state.partitions[from] != state.partitions[to]
+                    {
+                        return true;
+                    }
+                    state.deliveries.push(Delivery {
+                        time: event.time,
+                        from,
+                        to,
+                        data: data.clone(),
+                    });
+                    EventKind::Message { from, to, data }
+                }
+                EventKind::Timer {
+                    node,
+                    token,
+                    period,
+                } => {
+                    state.schedule(
+                        period,
+                        EventKind::Timer {
+                            node,
+                            token,
+                            period,
+                        },
+                    );
+                    EventKind::Timer {
+                        node,
+                        token,
+                        period,
+                    }
+                }
+                kind => kind,
+            }
+        };
+
+        let node = match event {
+            EventKind::Connected { node, peer } => {
+                self.with_context(node, |io| {
+                    self.handlers[node].on_peer_connected(
+                        io,
+                        &Self::node_id(peer),
+                        self.protocol_version,
+                        None, /* pos_public_key */
+                    )
+                });
+                node
+            }
+            EventKind::Disconnected { node, peer } => {
+                self.with_context(node, |io| {
+                    self.handlers[node]
+                        .on_peer_disconnected(io, &Self::node_id(peer))
+                });
+                node
+            }
+            EventKind::Message { from, to, data } => {
+                self.with_context(to, |io| {
+                    self.handlers[to].on_message(
+                        io,
+                        &Self::node_id(from),
+                        &data,
+                    )
+                });
+                to
+            }
+            EventKind::Timer { node, token, .. } => {
+                self.with_context(node, |io| {
+                    self.handlers[node].on_timeout(io, token)
+                });
+                node
+            }
+            EventKind::Work { node, work_type } => {
+                self.with_context(node, |io| {
+                    self.handlers[node].on_work_dispatch(io, work_type)
+                });
+                node
+            }
+        };
+        self.wait_for_idle(node);
+        true
+    }
+
+    /// Process the events until the virtual clock reaches `deadline`.
+    pub fn run_until(&self, deadline: Duration) {
+        while self.step(deadline) {}
+        let mut state = self.state.lock();
+        state.now = max(state.now, deadline);
+    }
+
+    pub fn run_for(&self, duration: Duration) {
+        let deadline = self.now() + duration;
+        self.run_until(deadline);
+    }
+
+    /// Process the events until `condition` holds, checking it after every
+    /// `interval` of virtual time. Returns false if it doesn't hold within
+    /// `timeout`.
+    pub fn run_until_condition(
+        &self, interval: Duration, timeout: Duration,
+        mut condition: impl FnMut() -> bool,
+    ) -> bool
+    {
+        let deadline = self.now() + timeout;
+        while !condition() {
+            if self.now() >= deadline {
+                return false;
+            }
+            self.run_for(interval);
+        }
+        true
+    }
+}
+
+struct SimulatedContext<'a> {
+    network: &'a SimulatedNetwork,
+    node: usize,
+}
+
+impl<'a> NetworkContext for SimulatedContext<'a> {
+    fn get_protocol(&self) -> ProtocolId { self.network.protocol }
+
+    fn get_peer_connection_origin(&self, node_id: &NodeId) -> Option<bool> {
+        let peer = self.network.node_index(node_id)?;
+        self.network
+            .state
+            .lock()
+            .connections
+            .get(&(self.node, peer))
+            .cloned()
+    }
+
+    fn send(
+        &self, node_id: &NodeId, msg: Vec<u8>,
+        _min_protocol_version: ProtocolVersion,
+        _version_valid_till: ProtocolVersion, _priority: SendQueuePriority,
+    ) -> Result<(), NetworkError>
+    {
+        if self.is_peer_self(node_id) {
+            self.network.handlers[self.node].send_local_message(self, msg);
+            return Ok(());
+        }
+        let peer = match self.network.node_index(node_id) {
+            Some(peer) => peer,
+            None => return Ok(()),
+        };
+
+        let mut state = self.network.state.lock();
+        if !state.is_connected(self.node, peer) {
+            return Ok(());
+        }
+        let link = state
+            .links
+            .get(&(self.node, peer))
+            .unwrap_or(&state.default_link)
+            .clone();
+        if link.drop_rate > 0.0 && state.rng.gen_bool(link.drop_rate) {
+            trace!("Drop message from {} to {}", self.node, peer);
+            return Ok(());
+        }
+        let latency = if link.max_latency > link.min_latency {
+            Duration::from_micros(state.rng.gen_range(
+                link.min_latency.as_micros() as u64,
+                link.max_latency.as_micros() as u64,
+            ))
+        } else {
+            link.min_latency
+        };
+        let now = state.now;
+        let delivery_time = max(
+            now + latency,
+            state
+                .link_busy_until
+                .get(&(self.node, peer))
+                .cloned()
+                .unwrap_or_default(),
+        );
+        state
+            .link_busy_until
+            .insert((self.node, peer), delivery_time);
+        state.schedule(
+            delivery_time - now,
+            EventKind::Message {
+                from: self.node,
+                to: peer,
+                data: msg,
+            },
+        );
+        Ok(())
+    }
+
+    fn disconnect_peer(
+        &self, node_id: &NodeId, _op: Option<UpdateNodeOperation>, reason: &str,
+    ) {
+        if let Some(peer) = self.network.node_index(node_id) {
+            debug!(
+                "Node {} disconnects node {}, reason={}",
+                self.node, peer, reason
+            );
+            SimulatedNetwork::disconnect_inner(
+                &mut *self.network.state.lock(),
+                self.node,
+                peer,
+            );
+        }
+    }
+
+    fn register_timer(
+        &self, token: TimerToken, delay: Duration,
+    ) -> Result<(), NetworkError> {
+        // A zero period would fire the timer forever without advancing the
+        // clock.
+        let delay = max(delay, Duration::from_millis(1));
+        self.network.state.lock().schedule(
+            delay,
+            EventKind::Timer {
+                node: self.node,
+                token,
+                period: delay,
+            },
+        );
+        Ok(())
+    }
+
+    fn dispatch_work(&self, work_type: HandlerWorkType) {
+        self.network.state.lock().schedule(
+            Duration::from_secs(0),
+            EventKind::Work {
+                node: self.node,
+                work_type,
+            },
+        );
+    }
+
+    fn insert_peer_node_tag(&self, _peer: NodeId, _key: &str, _value: &str) {}
+
+    fn is_peer_self(&self, node_id: &NodeId) -> bool {
+        *node_id == self.self_node_id()
+    }
+
+    fn self_node_id(&self) -> NodeId { SimulatedNetwork::node_id(self.node) }
+
+    fn now(&self) -> Instant { self.network.now_instant() }
+}
@@ -0,0 +1,312 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use super::{Delivery, LinkConfig, SimulatedNetwork, Simulation};
+use crate::{consensus::ConsensusGraphTrait, sync::SyncPhaseType};
+use diem_types::validator_config::{ConsensusPublicKey, ConsensusVRFPublicKey};
+use io::TimerToken;
+use network::{
+    node_table::NodeId, service::ProtocolVersion, HandlerWorkType,
+    NetworkContext, NetworkProtocolHandler,
+};
+use parking_lot::Mutex;
+use priority_send_queue::SendQueuePriority;
+use std::{sync::Arc, time::Duration};
+
+const GOSSIP_TIMER: TimerToken = 0;
+
+/// A handler which sends a counter to its peers periodically and forwards the
+/// counters it receives for the first time.
+#[derive(Default)]
+struct GossipHandler {
+    peers: Mutex<Vec<NodeId>>,
+    counter: Mutex<u8>,
+    received: Mutex<Vec<Vec<u8>>>,
+}
+
+impl GossipHandler {
+    fn broadcast(&self, io: &dyn NetworkContext, msg: Vec<u8>) {
+        for peer in self.peers.lock().iter() {
+            io.send(
+                peer,
+                msg.clone(),
+                ProtocolVersion(1),
+                ProtocolVersion(1),
+                SendQueuePriority::High,
+            )
+            .unwrap();
+        }
+    }
+}
+
+impl NetworkProtocolHandler for GossipHandler {
+    fn minimum_supported_version(&self) -> ProtocolVersion {
+        ProtocolVersion(1)
+    }
+
+    fn initialize(&self, io: &dyn NetworkContext) {
+        io.register_timer(GOSSIP_TIMER, Duration::from_millis(50))
+            .unwrap();
+    }
+
+    fn on_message(
+        &self, io: &dyn NetworkContext, _node_id: &NodeId, data: &[u8],
+    ) {
+        let mut received = self.received.lock();
+        if received.iter().any(|msg| msg.as_slice() == data) {
+            return;
+        }
+        received.push(data.to_vec());
+        drop(received);
+        self.broadcast(io, data.to_vec());
+    }
+
+    fn on_peer_connected(
+        &self, _io: &dyn NetworkContext, node_id: &NodeId,
+        _peer_protocol_version: ProtocolVersion,
+        _pos_public_key: Option<(ConsensusPublicKey, ConsensusVRFPublicKey)>,
+    )
+    {
+        self.peers.lock().push(*node_id);
+    }
+
+    fn on_peer_disconnected(&self, _io: &dyn NetworkContext, node_id: &NodeId) {
+        self.peers.lock().retain(|peer| peer != node_id);
+    }
+
+    fn on_timeout(&self, io: &dyn NetworkContext, _timer: TimerToken) {
+        let msg = {
+            let mut counter = self.counter.lock();
+            *counter += 1;
+            let self_id = io.self_node_id();
+            vec![self_id.to_low_u64_be() as u8, *counter]
+        };
+        self.received.lock().push(msg.clone());
+        self.broadcast(io, msg);
+    }
+
+    fn send_local_message(&self, _io: &dyn NetworkContext, _message: Vec<u8>) {}
+
+    fn on_work_dispatch(
+        &self, _io: &dyn NetworkContext, _work_type: HandlerWorkType,
+    ) {
+    }
+}
+
+fn run_gossip(
+    seed: u64, node_count: usize, link: LinkConfig,
+) -> (SimulatedNetwork, Vec<Arc<GossipHandler>>) {
+    let mut network = SimulatedNetwork::new(seed, *b"tst", ProtocolVersion(1));
+    let handlers: Vec<_> = (0..node_count)
+        .map(|_| Arc::new(GossipHandler::default()))
+        .collect();
+    for handler in &handlers {
+        network.add_node(handler.clone());
+    }
+    network.set_default_link(link);
+    network.connect_all();
+    network.run_for(Duration::from_secs(1));
+    (network, handlers)
+}
+
+fn lossy_link() -> LinkConfig {
+    LinkConfig {
+        min_latency: Duration::from_millis(5),
+        max_latency: Duration::from_millis(200),
+        drop_rate: 0.3,
+    }
+}
+
+#[test]
+fn test_same_seed_same_deliveries() {
+    let (network, _) = run_gossip(7, 4, lossy_link());
+    let deliveries = network.deliveries();
+    assert!(!deliveries.is_empty());
+    let (replayed, _) = run_gossip(7, 4, lossy_link());
+    assert_eq!(replayed.deliveries(), deliveries);
+    let (other, _) = run_gossip(8, 4, lossy_link());
+    assert_ne!(other.deliveries(), deliveries);
+}
+
+#[test]
+fn test_link_preserves_order() {
+    let (network, _) = run_gossip(1, 3, lossy_link());
+    let deliveries = network.deliveries();
+    for (from, to) in &[(0, 1), (1, 2), (2, 0)] {
+        let link: Vec<&Delivery> = deliveries
+            .iter()
+            .filter(|d| d.from == *from && d.to == *to)
+            .collect();
+        assert!(link.windows(2).all(|pair| pair[0].time <= pair[1].time));
+        // The counters of the sender itself are sent in order.
+        let own: Vec<u8> = link
+            .iter()
+            .filter(|d| d.data[0] as usize == from + 1)
+            .map(|d| d.data[1])
+            .collect();
+        assert!(own.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
+
+#[test]
+fn test_partition_drops_messages() {
+    let (network, handlers) = run_gossip(3, 4, LinkConfig::default());
+    network.partition(&[&[0, 1], &[2, 3]]);
+    let start = network.now();
+    network.run_for(Duration::from_secs(1));
+    assert!(network
+        .deliveries()
+        .iter()
+        .filter(|d| d.time > start)
+        .all(|d| (d.from < 2) == (d.to < 2)));
+
+    network.heal_partitions();
+    network.run_for(Duration::from_secs(1));
+    // The latest counter of node 0 reaches node 3 again.
+    let counter = *handlers[0].counter.lock();
+    assert!(handlers[3]
+        .received
+        .lock()
+        .iter()
+        .any(|msg| msg[0] == 1 && msg[1] + 5 >= counter));
+}
+
+#[test]
+fn test_blocks_propagate() {
+    let sim = Simulation::new(1, 3);
+    assert!(sim.wait_for_normal_phase());
+    for i in 0..6 {
+        let hash = sim.mine_on_best(i % 3);
+        assert!(sim.wait_for_block(&hash, Duration::from_secs(30)));
+    }
+}
+
+#[test]
+fn test_partitioned_nodes_converge() {
+    let sim = Simulation::new(2, 4);
+    assert!(sim.wait_for_normal_phase());
+    sim.network.partition(&[&[0, 1], &[2, 3]]);
+
+    let mut left = sim.genesis_hash;
+    let mut right = sim.genesis_hash;
+    for _ in 0..3 {
+        left = sim.mine_block(0, left, vec![]);
+        right = sim.mine_block(2, right, vec![]);
+        sim.network.run_for(Duration::from_secs(1));
+    }
+    assert!(sim.has_block(1, &left) && !sim.has_block(1, &right));
+    assert!(sim.has_block(3, &right) && !sim.has_block(3, &left));
+
+    sim.network.heal_partitions();
+    // The missing parents of the new block are requested from the peers in
+    // the other partition.
+    let hash = sim.mine_block(2, right, vec![]);
+    assert!(sim.wait_for_block(&hash, Duration::from_secs(60)));
+    let merge = sim.mine_on_best(0);
+    assert!(sim.wait_for_block(&merge, Duration::from_secs(60)));
+    assert!(sim.has_block(3, &left));
+}
+
+#[test]
+fn test_context_clock_is_virtual() {
+    let (network, _) = run_gossip(1, 2, LinkConfig::default());
+    let start = network.with_context(0, |io| io.now());
+    network.run_for(Duration::from_secs(30));
+    assert_eq!(
+        network.with_context(1, |io| io.now()) - start,
+        Duration::from_secs(30)
+    );
+}
+
+#[test]
+fn test_lost_requests_time_out() {
+    let sim = Simulation::new(4, 2);
+    assert!(sim.wait_for_normal_phase());
+    // The requests of node 1 are lost until they time out on the virtual
+    // clock and are sent again.
+    sim.network.set_link(
+        1,
+        0,
+        LinkConfig {
+            drop_rate: 1.0,
+            ..Default::default()
+        },
+    );
+    let hash = sim.mine_on_best(0);
+    sim.network.run_for(Duration::from_secs(2));
+    assert!(!sim.has_block(1, &hash));
+    sim.network.set_link(1, 0, LinkConfig::default());
+    assert!(sim.wait_for_block(&hash, Duration::from_secs(60)));
+}
+
+#[test]
+fn test_epoch_range_consensus_info() {
+    let sim = Simulation::new(5, 2);
+    assert!(sim.wait_for_normal_phase());
+    // Two blocks on the genesis, which are merged in epoch 2.
+    let a = sim.mine_block(0, sim.genesis_hash, vec![]);
+    let b = sim.mine_block(1, sim.genesis_hash, vec![]);
+    assert!(sim.wait_for_block(&a, Duration::from_secs(30)));
+    assert!(sim.wait_for_block(&b, Duration::from_secs(30)));
+    let merge = sim.mine_on_best(0);
+    assert!(sim.wait_for_block(&merge, Duration::from_secs(30)));
+
+    let inner = sim.nodes[0].consensus.inner.read();
+    let blocks = inner.epoch_range_consensus_info(0, 2, true).unwrap();
+    let hashes: Vec<_> = blocks.iter().map(|block| block.hash).collect();
+    let (pivot, other) = if hashes[1] == a { (a, b) } else { (b, a) };
+    assert_eq!(hashes, vec![sim.genesis_hash, pivot, other, merge]);
+    let epochs: Vec<_> =
+        blocks.iter().map(|block| block.epoch_number).collect();
+    assert_eq!(epochs, vec![Some(0), Some(1), Some(2), Some(2)]);
+    let pivots: Vec<_> = blocks.iter().map(|block| block.is_pivot).collect();
+    assert_eq!(pivots, vec![true, true, false, true]);
+    assert_eq!(blocks[3].parent_hash, pivot);
+    assert_eq!(blocks[3].referee_hashes, vec![other]);
+    // The two blocks on the genesis are in the anticone of each other.
+    let anticone_sizes: Vec<_> =
+        blocks.iter().map(|block| block.anticone_size).collect();
+    assert_eq!(anticone_sizes, vec![Some(0), Some(1), Some(1), Some(0)]);
+
+    // The anticone sizes are only computed on request.
+    let blocks = inner.epoch_range_consensus_info(1, 2, false).unwrap();
+    assert_eq!(blocks.len(), 3);
+    assert!(blocks.iter().all(|block| block.anticone_size.is_none()));
+
+    assert!(inner.epoch_range_consensus_info(2, 1, false).is_err());
+    assert!(inner.epoch_range_consensus_info(0, 3, false).is_err());
+}
+
+#[test]
+fn test_full_node_syncs_trusted_checkpoint() {
+    let mut sim = Simulation::new(6, 1);
+    assert!(sim.wait_for_normal_phase());
+    let tip = (0..60).map(|_| sim.mine_on_best(0)).last().unwrap();
+    assert!(sim.wait_for_block(&tip, Duration::from_secs(30)));
+
+    // The checkpoint is the latest one before the tip, so the snapshot to
+    // sync is the one of the trusted checkpoint itself.
+    let checkpoint = sim.trusted_checkpoint(0, 10);
+    let era_genesis_hash = checkpoint.era_genesis_hash();
+    let node = sim.add_full_node(checkpoint);
+    let sync_handler = sim.nodes[node].sync_handler.clone();
+    assert!(sim.network.run_until_condition(
+        Duration::from_millis(100),
+        Duration::from_secs(60),
+        || {
+            sync_handler.phase_manager.get_current_phase().phase_type()
+                == SyncPhaseType::CatchUpCheckpoint
+        },
+    ));
+    let full_node = &sim.nodes[node];
+    assert!(full_node.data_man.block_header_by_hash(&tip).is_some());
+    assert_eq!(full_node.consensus.get_to_sync_epoch_id(), era_genesis_hash);
+    // The pivot chain before the checkpoint is available to verify its
+    // snapshot.
+    let (parent, epochs) = full_node
+        .data_man
+        .get_parent_epochs_for(era_genesis_hash, 10);
+    assert_eq!(parent, sim.genesis_hash);
+    assert_eq!(epochs.len(), 10);
+}
@@ -10,6 +10,7 @@ use cfx_internal_common::ChainIdParamsInner;
 use cfx_parameters::{
     block::{MAX_BLOCK_SIZE_IN_BYTES, REFEREE_DEFAULT_BOUND},
     consensus::{GENESIS_GAS_LIMIT, TRANSACTION_DEFAULT_EPOCH_BOUND},
+    sync::CATCH_UP_EPOCH_LAG_THRESHOLD,
     WORKER_COMPUTATION_PARALLELISM,
 };
 use cfx_storage::{StorageConfiguration, StorageManager};
@@ -109,6 +110,25 @@ pub fn create_simple_block(
 pub fn initialize_data_manager(
     db_dir: &str, dbtype: DbType, pow: Arc<PowComputer>, vm: VmFactory,
 ) -> (Arc<BlockDataManager>, Arc<Block>) {
+    initialize_data_manager_with_config(
+        db_dir,
+        DataManagerConfiguration::new(
+            false,                          /* do not persist transaction
+                                             * address */
+            false, /* do not persist block number index */
+            Duration::from_millis(300_000), /* max cached tx count */
+            dbtype,
+        ),
+        pow,
+        vm,
+    )
+}
+
+pub fn initialize_data_manager_with_config(
+    db_dir: &str, config: DataManagerConfiguration, pow: Arc<PowComputer>,
+    vm: VmFactory,
+) -> (Arc<BlockDataManager>, Arc<Block>)
+{
     let ledger_db = db::open_database(
         db_dir,
         &db::db_config(
@@ -166,13 +186,7 @@ pub fn initialize_data_manager(
         ledger_db.clone(),
         storage_manager,
         worker_thread_pool,
-        DataManagerConfiguration::new(
-            false,                          /* do not persist transaction
-                                             * address */
-            false, /* do not persist block number index */
-            Duration::from_millis(300_000), /* max cached tx count */
-            dbtype,
-        ),
+        config,
         pow,
     ));
     (data_man, genesis_block)
@@ -182,6 +196,69 @@ pub fn initialize_synchronization_graph_with_data_manager(
     data_man: Arc<BlockDataManager>, beta: u64, h: u64, tcr: u64, tcb: u64,
     era_epoch_count: u64, pow: Arc<PowComputer>, vm: VmFactory,
 ) -> (Arc<SynchronizationGraph>, Arc<ConsensusGraph>)
+{
+    initialize_synchronization_graph_with_bench_mode(
+        data_man,
+        beta,
+        h,
+        tcr,
+        tcb,
+        era_epoch_count,
+        pow,
+        vm,
+        true, /* bench_mode */
+        NodeType::Archive,
+    )
+}
+
+/// Like `initialize_synchronization_graph_with_data_manager`, but the
+/// consensus graph executes the epochs, so that the tests can check the
+/// execution results.
+pub fn initialize_executing_synchronization_graph_with_data_manager(
+    data_man: Arc<BlockDataManager>, beta: u64, h: u64, tcr: u64, tcb: u64,
+    era_epoch_count: u64, pow: Arc<PowComputer>, vm: VmFactory,
+) -> (Arc<SynchronizationGraph>, Arc<ConsensusGraph>)
+{
+    initialize_synchronization_graph_with_bench_mode(
+        data_man,
+        beta,
+        h,
+        tcr,
+        tcb,
+        era_epoch_count,
+        pow,
+        vm,
+        false, /* bench_mode */
+        NodeType::Archive,
+    )
+}
+
+/// Like `initialize_synchronization_graph_with_data_manager`, but for a full
+/// node, which syncs the state once its best epoch is close to its peers.
+pub fn initialize_full_node_synchronization_graph_with_data_manager(
+    data_man: Arc<BlockDataManager>, beta: u64, h: u64, tcr: u64, tcb: u64,
+    era_epoch_count: u64, pow: Arc<PowComputer>, vm: VmFactory,
+) -> (Arc<SynchronizationGraph>, Arc<ConsensusGraph>)
+{
+    initialize_synchronization_graph_with_bench_mode(
+        data_man,
+        beta,
+        h,
+        tcr,
+        tcb,
+        era_epoch_count,
+        pow,
+        vm,
+        true, /* bench_mode */
+        NodeType::Full,
+    )
+}
+
+fn initialize_synchronization_graph_with_bench_mode(
+    data_man: Arc<BlockDataManager>, beta: u64, h: u64, tcr: u64, tcb: u64,
+    era_epoch_count: u64, pow: Arc<PowComputer>, vm: VmFactory,
+    bench_mode: bool, node_type: NodeType,
+) -> (Arc<SynchronizationGraph>, Arc<ConsensusGraph>)
 {
     let machine = Arc::new(new_machine_with_builtin(Default::default(), vm));
     let mut rng = StdRng::from_seed([0u8; 32]);
@@ -196,6 +273,7 @@ pub fn initialize_synchronization_graph_with_data_manager(
             pos_initial_nodes_path: "".to_string(),
             vrf_proposal_threshold: Default::default(),
             pos_state_config: Default::default(),
+            trusted_waypoint: None,
         },
         u64::MAX,
     ));
@@ -249,14 +327,17 @@ pub fn initialize_synchronization_graph_with_data_manager(
                 debug_dump_dir_invalid_state_root: None,
                 debug_invalid_state_root_epoch: None,
             },
-            bench_mode: true, /* Set bench_mode to true so that we skip
-                               * execution */
+            // With bench_mode, the execution is skipped.
+            bench_mode,
             transaction_epoch_bound: TRANSACTION_DEFAULT_EPOCH_BOUND,
             referee_bound: REFEREE_DEFAULT_BOUND,
             get_logs_epoch_batch_size: 32,
             get_logs_filter_max_epoch_range: None,
             sync_state_starting_epoch: None,
-            sync_state_epoch_gap: None,
+            sync_state_epoch_gap: match node_type {
+                NodeType::Archive => None,
+                _ => Some(CATCH_UP_EPOCH_LAG_THRESHOLD),
+            },
         },
         txpool.clone(),
         statistics.clone(),
@@ -266,9 +347,10 @@ pub fn initialize_synchronization_graph_with_data_manager(
         notifications.clone(),
         ConsensusExecutionConfiguration {
             executive_trace: false,
+            parallel_execution_threads: 0,
         },
         verification_config.clone(),
-        NodeType::Archive,
+        node_type,
         pos_verifier.clone(),
     ));
 
@@ -14,7 +14,10 @@ mod transaction_pool_inner;
 
 extern crate rand;
 
-pub use self::{impls::TreapMap, transaction_pool_inner::TransactionStatus};
+pub use self::{
+    impls::TreapMap,
+    transaction_pool_inner::{SenderAdmissionConfig, TransactionStatus},
+};
 use crate::{
     block_data_manager::BlockDataManager, consensus::BestInformation,
     machine::Machine, state::State, verification::VerificationConfig,
@@ -29,7 +32,9 @@ use account_cache::AccountCache;
 use cfx_parameters::block::DEFAULT_TARGET_BLOCK_GAS_LIMIT;
 use cfx_statedb::{Result as StateDbResult, StateDb};
 use cfx_storage::{StateIndex, StorageManagerTrait};
-use cfx_types::{AddressWithSpace as Address, AllChainID, H256, U256};
+use cfx_types::{
+    AddressWithSpace as Address, AllChainID, Space, H160, H256, U256,
+};
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use metrics::{
     register_meter_with_group, Gauge, GaugeUsize, Lock, Meter, MeterTimer,
@@ -90,6 +95,7 @@ pub struct TxPoolConfig {
     pub tx_weight_exp: u8,
     pub packing_gas_limit_block_count: u64,
     pub target_block_gas_limit: u64,
+    pub sender_admission: SenderAdmissionConfig,
 }
 
 impl MallocSizeOf for TxPoolConfig {
@@ -110,6 +116,7 @@ impl Default for TxPoolConfig {
             tx_weight_exp: 1,
             packing_gas_limit_block_count: 10,
             target_block_gas_limit: DEFAULT_TARGET_BLOCK_GAS_LIMIT,
+            sender_admission: SenderAdmissionConfig::default(),
         }
     }
 }
@@ -168,6 +175,7 @@ impl TransactionPool {
             (config.packing_gas_limit_block_count
                 * config.target_block_gas_limit)
                 .into(),
+            config.sender_admission.clone(),
         );
         let best_executed_state = Mutex::new(
             Self::best_executed_state(
@@ -219,6 +227,22 @@ impl TransactionPool {
             .unwrap_or((0.into(), 0.into()))
     }
 
+    pub fn is_local_sender(&self, address: &Address) -> bool {
+        self.inner.read().is_local_sender(address)
+    }
+
+    pub fn get_sender_pending_count(&self, address: &Address) -> usize {
+        self.inner.read().sender_pending_count(address)
+    }
+
+    /// Return the minimum gas price to replace the transaction of `address`
+    /// with `nonce` in the pool, or `None` if there is no such transaction.
+    pub fn get_replacement_min_gas_price(
+        &self, address: &Address, nonce: &U256,
+    ) -> Option<U256> {
+        self.inner.read().replacement_min_gas_price(address, nonce)
+    }
+
     pub fn get_next_nonce(&self, address: &Address) -> U256 {
         let (state_nonce, _) = self
             .get_state_account_info(address)
@@ -686,6 +710,14 @@ impl TransactionPool {
         inner.content(address)
     }
 
+    /// Return the pending and queued transactions in `space`, in the sense of
+    /// Ethereum transaction pools.
+    pub fn pending_and_queued_content(
+        &self, space: Space, address: Option<H160>,
+    ) -> (Vec<Arc<SignedTransaction>>, Vec<Arc<SignedTransaction>>) {
+        self.inner.read().pending_and_queued(space, address)
+    }
+
     pub fn notify_new_best_info(
         &self, best_info: Arc<BestInformation>,
     ) -> StateDbResult<()> {
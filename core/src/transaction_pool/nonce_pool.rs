@@ -381,8 +381,8 @@ impl NoncePool {
         }
     }
 
-    // FIXME: later we should limit the number of txs from one sender.
-    //  the FURTHEST_FUTURE_TRANSACTION_NONCE_OFFSET roughly doing this job
+    // The number of txs from one sender is limited by `TransactionPoolInner`
+    // with `SenderAdmissionConfig`.
     pub fn insert(
         &mut self, tx: &TxWithReadyInfo, force: bool,
     ) -> InsertResult {
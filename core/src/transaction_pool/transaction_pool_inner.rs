@@ -25,8 +25,9 @@ use primitives::{
 use rlp::*;
 use serde::Serialize;
 use std::{
-    cmp::{Ordering, Reverse},
-    collections::HashMap,
+    cmp::{max, Ordering, Reverse},
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
     sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -58,6 +59,8 @@ lazy_static! {
         CounterUsize::register_with_group("txpool", "gc_ready");
     static ref GC_METER: Arc<dyn Meter> =
         register_meter_with_group("txpool", "gc_txs_tps");
+    static ref SENDER_LIMIT_REJECT_METER: Arc<dyn Meter> =
+        register_meter_with_group("txpool", "sender_limit_reject_tps");
 }
 
 #[derive(DeriveMallocSizeOf)]
@@ -104,6 +107,14 @@ impl DeferredPool {
         }
     }
 
+    fn count_from(&self, sender: &AddressWithSpace, nonce: &U256) -> usize {
+        if let Some(bucket) = self.buckets.get(sender) {
+            bucket.count_from(nonce)
+        } else {
+            0
+        }
+    }
+
     fn remove_lowest_nonce(
         &mut self, addr: &AddressWithSpace,
     ) -> Option<TxWithReadyInfo> {
@@ -622,6 +633,35 @@ pub enum PendingReason {
     NotEnoughCash,
 }
 
+/// Per-sender admission rules of the transaction pool.
+#[derive(Clone, DeriveMallocSizeOf)]
+pub struct SenderAdmissionConfig {
+    /// Senders whose unexecuted transactions are never garbage collected,
+    /// and which are not limited by `max_pending_per_sender` and
+    /// `max_nonce_gap`. The same address in the other space is not local.
+    pub local_senders: HashSet<AddressWithSpace>,
+    /// The maximum number of unexecuted transactions of a sender.
+    pub max_pending_per_sender: usize,
+    /// The maximum distance from the nonce of a new transaction to the first
+    /// missing nonce of its sender.
+    pub max_nonce_gap: u64,
+    /// The minimum gas price increase in percentage to replace a transaction
+    /// with the same nonce. A higher gas price is always required.
+    pub min_replace_price_bump: u64,
+}
+
+impl Default for SenderAdmissionConfig {
+    fn default() -> Self {
+        SenderAdmissionConfig {
+            local_senders: HashSet::new(),
+            max_pending_per_sender: FURTHEST_FUTURE_TRANSACTION_NONCE_OFFSET
+                as usize,
+            max_nonce_gap: FURTHEST_FUTURE_TRANSACTION_NONCE_OFFSET as u64,
+            min_replace_price_bump: 0,
+        }
+    }
+}
+
 #[derive(DeriveMallocSizeOf)]
 pub struct TransactionPoolInner {
     capacity: usize,
@@ -644,12 +684,13 @@ pub struct TransactionPoolInner {
     /// It should contain the same transaction set as `deferred_pool`.
     txs: HashMap<H256, Arc<SignedTransaction>>,
     tx_sponsored_gas_map: HashMap<H256, (U256, u64)>,
+    sender_admission: SenderAdmissionConfig,
 }
 
 impl TransactionPoolInner {
     pub fn new(
         capacity: usize, tx_weight_scaling: u64, tx_weight_exp: u8,
-        total_gas_capacity: U256,
+        total_gas_capacity: U256, sender_admission: SenderAdmissionConfig,
     ) -> Self
     {
         TransactionPoolInner {
@@ -666,6 +707,7 @@ impl TransactionPoolInner {
             garbage_collector: GarbageCollector::default(),
             txs: HashMap::new(),
             tx_sponsored_gas_map: HashMap::new(),
+            sender_admission,
         }
     }
 
@@ -701,6 +743,94 @@ impl TransactionPoolInner {
         bucket.get_tx_by_nonce(nonce).map(|tx| tx.transaction)
     }
 
+    pub fn is_local_sender(&self, sender: &AddressWithSpace) -> bool {
+        self.sender_admission.local_senders.contains(sender)
+    }
+
+    /// Return the number of transactions of `sender` whose nonce is not less
+    /// than the local nonce.
+    pub fn sender_pending_count(&self, sender: &AddressWithSpace) -> usize {
+        let (local_nonce, _) = self
+            .get_local_nonce_and_balance(sender)
+            .unwrap_or((0.into(), 0.into()));
+        self.deferred_pool.count_from(sender, &local_nonce)
+    }
+
+    /// Return the minimum gas price for a new transaction to replace the
+    /// transaction with the same sender and nonce in the pool.
+    pub fn replacement_min_gas_price(
+        &self, sender: &AddressWithSpace, nonce: &U256,
+    ) -> Option<U256> {
+        let bucket = self.deferred_pool.buckets.get(sender)?;
+        let existing = bucket.get_tx_by_nonce(*nonce)?;
+        let gas_price = *existing.gas_price();
+        let bumped = gas_price.full_mul(
+            (100 + self.sender_admission.min_replace_price_bump).into(),
+        ) / U512::from(100);
+        let bumped = if bumped > U512::from(U256::MAX) {
+            U256::MAX
+        } else {
+            U256::try_from(bumped).expect("checked bound")
+        };
+        Some(max(bumped, gas_price.saturating_add(1.into())))
+    }
+
+    /// Check the per-sender admission rules for a new transaction which is
+    /// not packed yet.
+    fn check_sender_admission(
+        &self, transaction: &SignedTransaction, state_nonce: U256,
+    ) -> Result<(), String> {
+        let sender = transaction.sender();
+        let nonce = *transaction.nonce();
+        if self
+            .deferred_pool
+            .check_sender_and_nonce_exists(&sender, &nonce)
+        {
+            // Replace a transaction with the same nonce. The replacement
+            // with an equal gas price is handled by `NoncePool`.
+            if self.sender_admission.min_replace_price_bump == 0 {
+                return Ok(());
+            }
+            let packed = self.deferred_pool.check_tx_packed(sender, nonce);
+            if let Some(min_gas_price) =
+                self.replacement_min_gas_price(&sender, &nonce)
+            {
+                if !packed && *transaction.gas_price() < min_gas_price {
+                    return Err(format!("Tx with same nonce already inserted. To replace it, you need to specify a gas price >= {}", min_gas_price));
+                }
+            }
+            return Ok(());
+        }
+
+        if self.is_local_sender(&sender) {
+            return Ok(());
+        }
+
+        let pending_count =
+            self.deferred_pool.count_from(&sender, &state_nonce);
+        if pending_count >= self.sender_admission.max_pending_per_sender {
+            SENDER_LIMIT_REJECT_METER.mark(1);
+            return Err(format!(
+                "Sender {:?} has too many pending transactions: {}",
+                sender.address, pending_count
+            ));
+        }
+
+        let next_nonce = self.get_next_nonce(&sender, state_nonce);
+        if nonce > next_nonce
+            && nonce - next_nonce
+                > U256::from(self.sender_admission.max_nonce_gap)
+        {
+            SENDER_LIMIT_REJECT_METER.mark(1);
+            return Err(format!(
+                "Transaction nonce {} is too far from the next nonce {} of the sender",
+                nonce, next_nonce
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn is_full(&self) -> bool {
         return self.total_deferred() >= self.capacity;
     }
@@ -723,7 +853,8 @@ impl TransactionPoolInner {
     /// timestamp will be picked.
     pub fn collect_garbage(&mut self, new_tx: &SignedTransaction) {
         let count_before_gc = self.total_deferred();
-        let mut skipped_self_node = None;
+        let new_tx_is_local = self.is_local_sender(&new_tx.sender());
+        let mut skipped_nodes = Vec::new();
         while self.is_full() && !self.garbage_collector.is_empty() {
             let current_timestamp = self.get_current_timestamp();
             let (victim_address, victim) =
@@ -739,22 +870,27 @@ impl TransactionPoolInner {
             // no unconditional garbage collection to conduct and we need to
             // check if we should replace one unexecuted tx.
             if victim.count == 0 {
-                if *victim_address == new_tx.sender() {
+                if *victim_address == new_tx.sender()
+                    || self.is_local_sender(victim_address)
+                {
                     // We do not GC a not-executed transaction from the same
-                    // sender, so save it and try another account.
+                    // sender or a local sender, so save it and try another
+                    // account.
                     let (victim_address, victim) =
                         self.garbage_collector.pop().unwrap();
-                    skipped_self_node = Some((victim_address, victim));
+                    skipped_nodes.push((victim_address, victim));
                     continue;
                 } else if victim.has_ready_tx
                     && victim.first_tx_gas_price >= *new_tx.gas_price()
+                    && !new_tx_is_local
                 {
                     // If all transactions are not executed but some accounts
                     // are not ready to be packed, we directly replace a
                     // not-ready transaction (with the least gas_price in
                     // garbage_collector). If all accounts
                     // are ready, we check if the new tx has larger gas price
-                    // than some.
+                    // than some. Transactions from local senders are always
+                    // admitted.
                     trace!("txpool::collect_garbage fails, victim={:?} new_tx={:?} \
                     new_tx_gas_price={:?}", victim, new_tx.hash(), new_tx.gas_price());
                     return;
@@ -842,7 +978,7 @@ impl TransactionPoolInner {
 
         // Insert back skipped nodes to keep `garbage_collector`
         // unchanged.
-        if let Some((addr, node)) = skipped_self_node {
+        for (addr, node) in skipped_nodes {
             self.garbage_collector.insert(
                 &addr,
                 node.count,
@@ -1324,6 +1460,38 @@ impl TransactionPoolInner {
         (ready_txs, deferred_txs)
     }
 
+    /// Return the unpacked transactions of the senders in `space`, split into
+    /// pending transactions, whose nonces are continuous from the local nonce
+    /// of the sender, and queued transactions after the first missing nonce.
+    pub fn pending_and_queued(
+        &self, space: Space, address: Option<Address>,
+    ) -> (Vec<Arc<SignedTransaction>>, Vec<Arc<SignedTransaction>>) {
+        let mut pending_txs = Vec::new();
+        let mut queued_txs = Vec::new();
+        for (sender, bucket) in &self.deferred_pool.buckets {
+            if sender.space != space
+                || address.map_or(false, |addr| addr != sender.address)
+            {
+                continue;
+            }
+            let (local_nonce, _) = self
+                .get_local_nonce_and_balance(sender)
+                .unwrap_or((0.into(), 0.into()));
+            let first_missing_nonce = self
+                .deferred_pool
+                .last_succ_nonce(*sender, local_nonce)
+                .unwrap_or(local_nonce);
+            for tx in bucket.get_pending_transactions(&local_nonce) {
+                if *tx.nonce() < first_missing_nonce {
+                    pending_txs.push(tx);
+                } else {
+                    queued_txs.push(tx);
+                }
+            }
+        }
+        (pending_txs, queued_txs)
+    }
+
     // Add transaction into deferred pool and maintain its readiness
     // the packed tag provided
     // if force tag is true, the replacement in nonce pool must be happened
@@ -1430,6 +1598,10 @@ impl TransactionPoolInner {
             ));
         }
 
+        if !packed && !force {
+            self.check_sender_admission(&transaction, state_nonce)?;
+        }
+
         let result = self.insert_transaction_without_readiness_check(
             transaction.clone(),
             packed,
@@ -1455,9 +1627,14 @@ impl TransactionPoolInner {
 
 #[cfg(test)]
 mod test_transaction_pool_inner {
-    use super::{DeferredPool, InsertResult, TxWithReadyInfo};
+    use super::{
+        DeferredPool, InsertResult, SenderAdmissionConfig,
+        TransactionPoolInner, TxWithReadyInfo,
+    };
     use crate::transaction_pool::transaction_pool_inner::ReadyAccountPool;
-    use cfx_types::{Address, AddressSpaceUtil, U256};
+    use cfx_types::{
+        Address, AddressSpaceUtil, AddressWithSpace, Space, U256,
+    };
     use keylib::{Generator, KeyPair, Random};
     use primitives::{
         Action, NativeTransaction, SignedTransaction, Transaction,
@@ -1789,4 +1966,144 @@ mod test_transaction_pool_inner {
         assert_eq!(ready_pool.native_pool.packing_pool.len(), 0);
         assert_eq!(ready_pool.native_pool.waiting_pool.len(), 0);
     }
+
+    fn new_test_pool(
+        capacity: usize, local_senders: Vec<AddressWithSpace>,
+    ) -> TransactionPoolInner {
+        TransactionPoolInner::new(
+            capacity,
+            1,
+            1,
+            U256::from(10_000_000),
+            SenderAdmissionConfig {
+                local_senders: local_senders.into_iter().collect(),
+                max_pending_per_sender: 2,
+                max_nonce_gap: 3,
+                min_replace_price_bump: 10,
+            },
+        )
+    }
+
+    /// Insert a transaction whose sender has nonce 0 and enough balance.
+    fn insert_with_zero_nonce(
+        pool: &mut TransactionPoolInner, tx: Arc<SignedTransaction>,
+    ) {
+        let balance = U256::from(10).pow(18.into());
+        let result = pool.insert_transaction_without_readiness_check(
+            tx.clone(),
+            false, /* packed */
+            false, /* force */
+            Some((0.into(), balance)),
+            (0.into(), 0),
+        );
+        assert!(!matches!(result, InsertResult::Failed(_)));
+        pool.recalculate_readiness_with_fixed_info(
+            &tx.sender(),
+            0.into(),
+            balance,
+        );
+    }
+
+    #[test]
+    fn test_sender_admission() {
+        let alice = Random.generate().unwrap();
+        let bob = Random.generate().unwrap();
+        let carol = Random.generate().unwrap();
+        let mut pool =
+            new_test_pool(10, vec![carol.address().with_native_space()]);
+        assert!(pool.is_local_sender(&carol.address().with_native_space()));
+        assert!(!pool.is_local_sender(&carol.address().with_evm_space()));
+
+        insert_with_zero_nonce(&mut pool, new_test_tx(&alice, 0, 100, 0));
+        let tx = new_test_tx(&alice, 1, 100, 0);
+        assert!(pool.check_sender_admission(&tx, 0.into()).is_ok());
+        insert_with_zero_nonce(&mut pool, tx);
+        assert_eq!(
+            pool.sender_pending_count(&alice.address().with_native_space()),
+            2
+        );
+
+        // Too many pending transactions.
+        let tx = new_test_tx(&alice, 2, 100, 0);
+        assert!(pool.check_sender_admission(&tx, 0.into()).is_err());
+        // Replacement requires a 10% higher gas price.
+        assert_eq!(
+            pool.replacement_min_gas_price(
+                &alice.address().with_native_space(),
+                &1.into()
+            ),
+            Some(110.into())
+        );
+        let tx = new_test_tx(&alice, 1, 109, 0);
+        assert!(pool.check_sender_admission(&tx, 0.into()).is_err());
+        let tx = new_test_tx(&alice, 1, 110, 0);
+        assert!(pool.check_sender_admission(&tx, 0.into()).is_ok());
+
+        // Too large nonce gap.
+        let tx = new_test_tx(&bob, 4, 100, 0);
+        assert!(pool.check_sender_admission(&tx, 0.into()).is_err());
+        let tx = new_test_tx(&bob, 3, 100, 0);
+        assert!(pool.check_sender_admission(&tx, 0.into()).is_ok());
+
+        // Local senders are not limited.
+        let tx = new_test_tx(&carol, 10, 100, 0);
+        assert!(pool.check_sender_admission(&tx, 0.into()).is_ok());
+    }
+
+    #[test]
+    fn test_collect_garbage_skips_local_senders() {
+        let alice = Random.generate().unwrap();
+        let bob = Random.generate().unwrap();
+        let carol = Random.generate().unwrap();
+        let mut pool =
+            new_test_pool(2, vec![carol.address().with_native_space()]);
+
+        insert_with_zero_nonce(&mut pool, new_test_tx(&carol, 0, 1, 0));
+        insert_with_zero_nonce(&mut pool, new_test_tx(&bob, 0, 1, 0));
+        assert!(pool.is_full());
+
+        pool.collect_garbage(&new_test_tx(&alice, 0, 10, 0));
+        assert_eq!(pool.total_deferred(), 1);
+        assert!(pool
+            .get_by_address2nonce(carol.address().with_native_space(), 0.into())
+            .is_some());
+        assert!(pool
+            .get_by_address2nonce(bob.address().with_native_space(), 0.into())
+            .is_none());
+
+        // No transaction is evictable for a new transaction any more.
+        insert_with_zero_nonce(&mut pool, new_test_tx(&carol, 1, 1, 0));
+        pool.collect_garbage(&new_test_tx(&alice, 0, 10, 0));
+        assert_eq!(pool.total_deferred(), 2);
+    }
+
+    #[test]
+    fn test_pending_and_queued() {
+        let alice = Random.generate().unwrap();
+        let bob = Random.generate().unwrap();
+        let mut pool =
+            new_test_pool(10, vec![alice.address().with_native_space()]);
+        for nonce in &[0, 1, 3] {
+            insert_with_zero_nonce(
+                &mut pool,
+                new_test_tx(&alice, *nonce, 100, 0),
+            );
+        }
+        insert_with_zero_nonce(&mut pool, new_test_tx(&bob, 0, 100, 0));
+
+        let (pending, queued) =
+            pool.pending_and_queued(Space::Native, Some(alice.address()));
+        let nonces = |txs: Vec<Arc<SignedTransaction>>| -> Vec<U256> {
+            txs.iter().map(|tx| *tx.nonce()).collect()
+        };
+        let mut pending = nonces(pending);
+        pending.sort();
+        assert_eq!(pending, vec![U256::from(0), U256::from(1)]);
+        assert_eq!(nonces(queued), vec![U256::from(3)]);
+
+        let (pending, queued) = pool.pending_and_queued(Space::Native, None);
+        assert_eq!((pending.len(), queued.len()), (3, 1));
+        let (pending, queued) = pool.pending_and_queued(Space::Ethereum, None);
+        assert!(pending.is_empty() && queued.is_empty());
+    }
 }
@@ -238,6 +238,16 @@ pub trait StateOpsTrait {
 
     fn last_distribute_block(&self) -> u64;
 
+    fn interest_rate_per_block(&self) -> U256;
+
+    fn set_interest_rate_per_block(&mut self, interest_rate_per_block: U256);
+
+    /// The PoW base reward voted by the stakers, or zero if no vote has been
+    /// applied.
+    fn pow_base_reward(&self) -> U256;
+
+    fn set_pow_base_reward(&mut self, pow_base_reward: U256);
+
     fn remove_contract(&mut self, address: &AddressWithSpace) -> DbResult<()>;
 
     fn exists(&self, address: &AddressWithSpace) -> DbResult<bool>;
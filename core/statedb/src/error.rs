@@ -27,5 +27,10 @@ error_chain! {
             description("PoS database error")
             display("PoS database error, err={:?}", err)
         }
+
+        NoMerkleData {
+            description("no merkle data")
+            display("merkle data is not available for a reconstructed state")
+        }
     }
 }
@@ -22,7 +22,7 @@ pub use self::{
     statedb_ext::{
         StateDbExt, ACCUMULATE_INTEREST_RATE_KEY,
         DISTRIBUTABLE_POS_INTEREST_KEY, INTEREST_RATE_KEY,
-        LAST_DISTRIBUTE_BLOCK_KEY, TOTAL_BANK_TOKENS_KEY,
+        LAST_DISTRIBUTE_BLOCK_KEY, POW_BASE_REWARD_KEY, TOTAL_BANK_TOKENS_KEY,
         TOTAL_POS_STAKING_TOKENS_KEY, TOTAL_STORAGE_TOKENS_KEY,
         TOTAL_TOKENS_KEY,
     },
@@ -55,6 +55,10 @@ mod impls {
 
         /// Checkpoints allow callers to revert un-committed changes.
         checkpoints: Vec<Checkpoint>,
+
+        /// Whether `accessed_entries` was preloaded with changes not in the
+        /// storage, in which case the merkle data of the storage is stale.
+        preloaded_changes: bool,
     }
 
     // We skip the accessed_entries for getting original value.
@@ -93,9 +97,39 @@ mod impls {
                 accessed_entries: Default::default(),
                 storage,
                 checkpoints: Default::default(),
+                preloaded_changes: false,
+            }
+        }
+
+        /// Create a state db which reads `changes` over `storage`, e.g. the
+        /// key values of a state reconstructed by re-execution over the
+        /// state in `storage`. A `None` value marks a deleted key.
+        pub fn new_with_changes(
+            storage: Storage, changes: &BTreeMap<Key, Value>,
+        ) -> Self {
+            let accessed_entries = changes
+                .iter()
+                .map(|(k, v)| (k.clone(), EntryValue::new(v.clone())))
+                .collect();
+            StateDb {
+                accessed_entries: RwLock::new(accessed_entries),
+                storage,
+                checkpoints: Default::default(),
+                preloaded_changes: true,
             }
         }
 
+        /// Return the key values modified in `accessed_entries` but not yet
+        /// applied to the storage.
+        pub fn modified_entries(&self) -> BTreeMap<Key, Value> {
+            self.accessed_entries
+                .read()
+                .iter()
+                .filter(|(_, v)| v.is_modified())
+                .map(|(k, v)| (k.clone(), v.current_value.clone()))
+                .collect()
+        }
+
         /// Set `key` to `value` in latest checkpoint if not set previously.
         fn update_checkpoint(&mut self, key: &Key, value: Option<Value>) {
             if let Some(checkpoint) = self.checkpoints.last_mut() {
@@ -392,6 +426,8 @@ mod impls {
         fn apply_changes_to_storage(
             &mut self, mut debug_record: Option<&mut ComputeEpochDebugRecord>,
         ) -> Result<()> {
+            // The preloaded changes would be lost.
+            self.check_merkle_data()?;
             let mut storage_layouts_to_rewrite = Default::default();
             let accessed_entries = &*self.accessed_entries.get_mut();
             // First of all, apply all changes to the underlying storage.
@@ -515,12 +551,23 @@ mod impls {
         }
     }
 
+    impl<Storage> StateDb<Storage> {
+        /// The merkle data in the storage doesn't cover preloaded changes.
+        fn check_merkle_data(&self) -> Result<()> {
+            if self.preloaded_changes {
+                bail!(ErrorKind::NoMerkleData);
+            }
+            Ok(())
+        }
+    }
+
     impl<Storage: StorageStateTraitExt> StateDbGetOriginalMethods
         for StateDb<Storage>
     {
         fn get_original_raw_with_proof(
             &self, key: StorageKeyWithSpace,
         ) -> Result<(Option<Box<[u8]>>, StateProof)> {
+            self.check_merkle_data()?;
             let r = Ok(self.storage.get_with_proof(key)?);
             trace!("get_original_raw_with_proof key={:?}, value={:?}", key, r);
             r
@@ -529,6 +576,7 @@ mod impls {
         fn get_original_storage_root(
             &self, address: &AddressWithSpace,
         ) -> Result<StorageRoot> {
+            self.check_merkle_data()?;
             let key = StorageKey::new_storage_root_key(&address.address)
                 .with_space(address.space);
 
@@ -541,6 +589,7 @@ mod impls {
         fn get_original_storage_root_with_proof(
             &self, address: &AddressWithSpace,
         ) -> Result<(StorageRoot, StorageRootProof)> {
+            self.check_merkle_data()?;
             let key = StorageKey::new_storage_root_key(&address.address)
                 .with_space(address.space);
 
@@ -8,7 +8,11 @@ use cfx_storage::{
     utils::access_mode, ErrorKind, MptKeyValue, Result, StorageStateTrait,
 };
 use primitives::{EpochId, StorageKey, StorageKeyWithSpace, MERKLE_NULL_NODE};
-use std::{cell::RefCell, collections::HashMap};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 type StorageValue = Box<[u8]>;
 type RawStorage = HashMap<Vec<u8>, StorageValue>;
@@ -302,3 +306,93 @@ fn test_checkpoint_evict_memory() {
     // value is removed from state-db
     assert_eq!(state_db.get_from_cache(&key(b"00")), None);
 }
+
+#[test]
+fn test_modified_entries() {
+    let mut state_db = init_state_db();
+
+    // reads are not modifications
+    state_db.get_raw(storage_key(b"00")).unwrap();
+    // (11, v0) --> (11, v1)
+    state_db
+        .set_raw(storage_key(b"11"), value(b"v1"), None)
+        .unwrap();
+    // (22, v0) --> (22, v1) --> (22, v0)
+    state_db
+        .set_raw(storage_key(b"22"), value(b"v1"), None)
+        .unwrap();
+    state_db
+        .set_raw(storage_key(b"22"), value(b"v0"), None)
+        .unwrap();
+    // delete (01, v0)
+    state_db.delete(storage_key(b"01"), None).unwrap();
+
+    let expected: BTreeMap<_, Option<Arc<[u8]>>> = [
+        (key(b"01"), None),
+        (key(b"11"), Some(value(b"v1").into())),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+    assert_eq!(state_db.modified_entries(), expected);
+
+    // nothing is written to the storage
+    assert_eq!(state_db.get_storage_mut().get_num_writes(), 0);
+}
+
+#[test]
+fn test_new_with_changes() {
+    let mut contents = RawStorage::new();
+    contents.insert(key(b"00"), value(b"v0"));
+    contents.insert(key(b"01"), value(b"v0"));
+    contents.insert(key(b"11"), value(b"v0"));
+    let changes: BTreeMap<_, Option<Arc<[u8]>>> = [
+        (key(b"01"), None),
+        (key(b"11"), Some(value(b"v1").into())),
+        (key(b"22"), Some(value(b"v1").into())),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+    let mut state_db = StateDbTest::new_with_changes(
+        MockStorage::with_contents(contents),
+        &changes,
+    );
+
+    // the changes are read over the storage
+    assert_eq!(
+        state_db.get_raw(storage_key(b"00")).unwrap(),
+        Some(value(b"v0").into())
+    );
+    assert_eq!(state_db.get_raw(storage_key(b"01")).unwrap(), None);
+    assert_eq!(
+        state_db.get_raw(storage_key(b"11")).unwrap(),
+        Some(value(b"v1").into())
+    );
+    assert_eq!(
+        state_db.get_raw(storage_key(b"22")).unwrap(),
+        Some(value(b"v1").into())
+    );
+    assert_eq!(state_db.get_storage_mut().get_num_reads(), 1);
+
+    // the preloaded changes are not modifications of the state db
+    assert!(state_db.modified_entries().is_empty());
+
+    // delete_all covers the preloaded keys, but not the deleted ones
+    let deleted = state_db
+        .delete_all::<access_mode::Write>(storage_key(b"0"), None)
+        .unwrap();
+    assert_eq!(deleted, vec![(key(b"00"), value(b"v0"))]);
+    state_db
+        .set_raw(storage_key(b"22"), value(b"v2"), None)
+        .unwrap();
+
+    let expected: BTreeMap<_, Option<Arc<[u8]>>> = [
+        (key(b"00"), None),
+        (key(b"22"), Some(value(b"v2").into())),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+    assert_eq!(state_db.modified_entries(), expected);
+}
@@ -2,7 +2,7 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use crate::impls::delta_mpt::node_ref_map::DeltaMptId;
+use crate::{impls::delta_mpt::node_ref_map::DeltaMptId, SnapshotDbType};
 use primitives::account::AccountError;
 use std::{io, num};
 
@@ -101,6 +101,19 @@ error_chain! {
             display("Attempting to create or modify a Snapshot which already exists."),
         }
 
+        SnapshotDbTypeMismatch(configured: SnapshotDbType, found: SnapshotDbType) {
+            description("Snapshots of another db type exist."),
+            display(
+                "The snapshot db type is {:?} but snapshots of type {:?} \
+                 exist. Switching the type requires removing the snapshots.",
+                configured, found),
+        }
+
+        SnapshotDbShared {
+            description("Attempting to write a snapshot db which is shared."),
+            display("Attempting to write a snapshot db which is shared."),
+        }
+
         SnapshotMPTTrieNodeNotFound {
             description("Trie node not found when loading Snapshot MPT."),
             display("Trie node not found when loading Snapshot MPT."),
@@ -18,6 +18,10 @@ pub(super) mod storage_manager;
 
 pub mod defaults {
     pub use super::delta_mpt::DEFAULT_NODE_MAP_SIZE;
+    /// Keep a pair of snapshots in every 10 snapshots for the reconstruction
+    /// of historical states, which bounds the epochs to re-execute for a
+    /// state to about 11 snapshot periods.
+    pub const DEFAULT_ARCHIVE_STATE_SNAPSHOT_INTERVAL: u32 = 10;
     // By default do not check for data-integrity for snapshot mpt for
     // production runs.
     pub const DEFAULT_DEBUG_SNAPSHOT_CHECKER_THREADS: u16 = 0;
@@ -3,7 +3,7 @@
 // See http://www.gnu.org/licenses/
 
 pub type DeltaDbManager = DeltaDbManagerRocksdb;
-pub type SnapshotDbManager = SnapshotDbManagerSqlite;
+pub type SnapshotDbManager = SnapshotDbManagerDispatch;
 pub type SnapshotDb = <SnapshotDbManager as SnapshotDbManagerTrait>::SnapshotDb;
 
 pub struct StateTrees {
@@ -621,7 +621,7 @@ use crate::{
         errors::*,
         storage_db::{
             delta_db_manager_rocksdb::DeltaDbManagerRocksdb,
-            snapshot_db_manager_sqlite::SnapshotDbManagerSqlite,
+            snapshot_db_dispatch::SnapshotDbManagerDispatch,
         },
         storage_manager::storage_manager::StorageManager,
     },
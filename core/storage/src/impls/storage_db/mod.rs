@@ -8,7 +8,10 @@ pub mod delta_db_manager_sqlite;
 pub mod kvdb_rocksdb;
 pub mod kvdb_sqlite;
 pub mod kvdb_sqlite_sharded;
+pub mod snapshot_db_dispatch;
+pub mod snapshot_db_manager_rocksdb;
 pub mod snapshot_db_manager_sqlite;
+pub mod snapshot_db_rocksdb;
 pub mod snapshot_db_sqlite;
 pub mod snapshot_mpt;
 pub mod sqlite;
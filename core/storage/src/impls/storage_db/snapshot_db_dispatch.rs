@@ -0,0 +1,616 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Choose the snapshot db engine at runtime by `SnapshotDbType`, forwarding
+//! all the calls to the manager and the snapshot db of the engine.
+
+macro_rules! dispatch {
+    ($value:expr, $inner:ident => $call:expr) => {
+        match $value {
+            Self::Sqlite($inner) => $call,
+            Self::Rocksdb($inner) => $call,
+        }
+    };
+}
+
+pub enum SnapshotDbManagerDispatch {
+    Sqlite(SnapshotDbManagerSqlite),
+    Rocksdb(SnapshotDbManagerRocksdb),
+}
+
+/// The snapshot dbs are shared with the `already_open_snapshots` of their
+/// manager, so a snapshot db is only writable when it isn't shared, as the
+/// temporary snapshot for full sync.
+pub enum SnapshotDbDispatch {
+    Sqlite(Arc<SnapshotDbSqlite>),
+    Rocksdb(Arc<SnapshotDbRocksdb>),
+}
+
+pub enum SnapshotMptDispatch<SnapshotMptSqlite> {
+    Sqlite(SnapshotMptSqlite),
+    Rocksdb(SnapshotMptRocksdb),
+}
+
+pub enum SnapshotKvdbIterDispatch {
+    Sqlite(KvdbSqliteSharded<Box<[u8]>>),
+    Rocksdb(KvdbRocksdbColumn),
+}
+
+pub struct SnapshotKvdbIterDispatchTag();
+
+impl SnapshotDbManagerDispatch {
+    pub fn new(
+        snapshot_path: PathBuf, max_open_snapshots: u16,
+        snapshot_db_type: SnapshotDbType,
+    ) -> Result<Self>
+    {
+        Self::check_snapshot_db_type(&snapshot_path, snapshot_db_type)?;
+        Ok(match snapshot_db_type {
+            SnapshotDbType::Sqlite => {
+                Self::Sqlite(SnapshotDbManagerSqlite::new(
+                    snapshot_path,
+                    max_open_snapshots,
+                )?)
+            }
+            SnapshotDbType::Rocksdb => {
+                Self::Rocksdb(SnapshotDbManagerRocksdb::new(
+                    snapshot_path,
+                    max_open_snapshots,
+                )?)
+            }
+        })
+    }
+
+    /// Snapshots of an unrecognized name are removed by `scan_persist_state`
+    /// on startup, so we refuse to start when the snapshot dir has snapshots
+    /// of the other type.
+    fn check_snapshot_db_type(
+        snapshot_path: &Path, snapshot_db_type: SnapshotDbType,
+    ) -> Result<()> {
+        if !snapshot_path.exists() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(snapshot_path)? {
+            let file_name = entry?.file_name();
+            let file_name = file_name.to_string_lossy();
+            let found = if file_name.starts_with(
+                SnapshotDbManagerSqlite::SNAPSHOT_DB_SQLITE_DIR_PREFIX,
+            ) {
+                SnapshotDbType::Sqlite
+            } else if file_name.starts_with(
+                SnapshotDbManagerRocksdb::SNAPSHOT_DB_ROCKSDB_DIR_PREFIX,
+            ) {
+                SnapshotDbType::Rocksdb
+            } else {
+                continue;
+            };
+            if found != snapshot_db_type {
+                bail!(ErrorKind::SnapshotDbTypeMismatch(
+                    snapshot_db_type,
+                    found
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SnapshotDbManagerTrait for SnapshotDbManagerDispatch {
+    type SnapshotDb = SnapshotDbDispatch;
+
+    fn get_snapshot_dir(&self) -> &Path {
+        dispatch!(self, manager => manager.get_snapshot_dir())
+    }
+
+    fn get_snapshot_db_name(&self, snapshot_epoch_id: &EpochId) -> String {
+        dispatch!(self, manager => {
+            manager.get_snapshot_db_name(snapshot_epoch_id)
+        })
+    }
+
+    fn get_snapshot_db_path(&self, snapshot_epoch_id: &EpochId) -> PathBuf {
+        dispatch!(self, manager => {
+            manager.get_snapshot_db_path(snapshot_epoch_id)
+        })
+    }
+
+    fn new_snapshot_by_merging<'m>(
+        &self, old_snapshot_epoch_id: &EpochId, snapshot_epoch_id: EpochId,
+        delta_mpt: DeltaMptIterator, in_progress_snapshot_info: SnapshotInfo,
+        snapshot_info_map_rwlock: &'m RwLock<PersistedSnapshotInfoMap>,
+    ) -> Result<(RwLockWriteGuard<'m, PersistedSnapshotInfoMap>, SnapshotInfo)>
+    {
+        dispatch!(self, manager => manager.new_snapshot_by_merging(
+            old_snapshot_epoch_id,
+            snapshot_epoch_id,
+            delta_mpt,
+            in_progress_snapshot_info,
+            snapshot_info_map_rwlock,
+        ))
+    }
+
+    fn get_snapshot_by_epoch_id(
+        &self, snapshot_epoch_id: &EpochId, try_open: bool,
+    ) -> Result<Option<Arc<SnapshotDbDispatch>>> {
+        Ok(match self {
+            Self::Sqlite(manager) => manager
+                .get_snapshot_by_epoch_id(snapshot_epoch_id, try_open)?
+                .map(|db| Arc::new(SnapshotDbDispatch::Sqlite(db))),
+            Self::Rocksdb(manager) => manager
+                .get_snapshot_by_epoch_id(snapshot_epoch_id, try_open)?
+                .map(|db| Arc::new(SnapshotDbDispatch::Rocksdb(db))),
+        })
+    }
+
+    fn destroy_snapshot(&self, snapshot_epoch_id: &EpochId) -> Result<()> {
+        dispatch!(self, manager => manager.destroy_snapshot(snapshot_epoch_id))
+    }
+
+    fn new_temp_snapshot_for_full_sync(
+        &self, snapshot_epoch_id: &EpochId, merkle_root: &MerkleHash,
+    ) -> Result<SnapshotDbDispatch> {
+        Ok(match self {
+            Self::Sqlite(manager) => SnapshotDbDispatch::Sqlite(Arc::new(
+                manager.new_temp_snapshot_for_full_sync(
+                    snapshot_epoch_id,
+                    merkle_root,
+                )?,
+            )),
+            Self::Rocksdb(manager) => SnapshotDbDispatch::Rocksdb(Arc::new(
+                manager.new_temp_snapshot_for_full_sync(
+                    snapshot_epoch_id,
+                    merkle_root,
+                )?,
+            )),
+        })
+    }
+
+    fn finalize_full_sync_snapshot<'m>(
+        &self, snapshot_epoch_id: &EpochId, merkle_root: &MerkleHash,
+        snapshot_info_map_rwlock: &'m RwLock<PersistedSnapshotInfoMap>,
+    ) -> Result<RwLockWriteGuard<'m, PersistedSnapshotInfoMap>>
+    {
+        dispatch!(self, manager => manager.finalize_full_sync_snapshot(
+            snapshot_epoch_id,
+            merkle_root,
+            snapshot_info_map_rwlock,
+        ))
+    }
+}
+
+fn get_exclusive<T>(snapshot_db: &mut Arc<T>) -> Result<&mut T> {
+    Arc::get_mut(snapshot_db).ok_or_else(|| ErrorKind::SnapshotDbShared.into())
+}
+
+impl KeyValueDbTypes for SnapshotDbDispatch {
+    type ValueType = Box<[u8]>;
+}
+
+impl KeyValueDbTraitRead for SnapshotDbDispatch {
+    fn get(&self, key: &[u8]) -> Result<Option<Box<[u8]>>> {
+        dispatch!(self, db => db.get(key))
+    }
+}
+
+impl KeyValueDbTraitOwnedRead for SnapshotDbDispatch {
+    fn get_mut(&mut self, key: &[u8]) -> Result<Option<Box<[u8]>>> {
+        dispatch!(self, db => get_exclusive(db)?.get_mut(key))
+    }
+}
+
+impl KeyValueDbTraitSingleWriter for SnapshotDbDispatch {
+    fn delete(&mut self, key: &[u8]) -> Result<Option<Option<Box<[u8]>>>> {
+        dispatch!(self, db => get_exclusive(db)?.delete(key))
+    }
+
+    fn put(
+        &mut self, key: &[u8], value: &[u8],
+    ) -> Result<Option<Option<Box<[u8]>>>> {
+        dispatch!(self, db => get_exclusive(db)?.put(key, value))
+    }
+}
+
+impl<'db> OpenSnapshotMptTrait<'db> for SnapshotDbDispatch {
+    type SnapshotDbAsOwnedType = SnapshotMptDispatch<
+        <SnapshotDbSqlite as OpenSnapshotMptTrait<'db>>::SnapshotDbAsOwnedType,
+    >;
+    type SnapshotDbBorrowMutType = SnapshotMptDispatch<
+        <SnapshotDbSqlite as OpenSnapshotMptTrait<'db>>::SnapshotDbBorrowMutType,
+    >;
+    type SnapshotDbBorrowSharedType = SnapshotMptDispatch<
+        <SnapshotDbSqlite as OpenSnapshotMptTrait<'db>>::SnapshotDbBorrowSharedType,
+    >;
+
+    fn open_snapshot_mpt_owned(
+        &'db mut self,
+    ) -> Result<Self::SnapshotDbBorrowMutType> {
+        Ok(match self {
+            Self::Sqlite(db) => SnapshotMptDispatch::Sqlite(
+                get_exclusive(db)?.open_snapshot_mpt_owned()?,
+            ),
+            Self::Rocksdb(db) => SnapshotMptDispatch::Rocksdb(
+                get_exclusive(db)?.open_snapshot_mpt_owned()?,
+            ),
+        })
+    }
+
+    fn open_snapshot_mpt_as_owned(
+        &'db self,
+    ) -> Result<Self::SnapshotDbAsOwnedType> {
+        Ok(match self {
+            Self::Sqlite(db) => {
+                SnapshotMptDispatch::Sqlite(db.open_snapshot_mpt_as_owned()?)
+            }
+            Self::Rocksdb(db) => {
+                SnapshotMptDispatch::Rocksdb(db.open_snapshot_mpt_as_owned()?)
+            }
+        })
+    }
+
+    fn open_snapshot_mpt_shared(
+        &'db self,
+    ) -> Result<Self::SnapshotDbBorrowSharedType> {
+        Ok(match self {
+            Self::Sqlite(db) => {
+                SnapshotMptDispatch::Sqlite(db.open_snapshot_mpt_shared()?)
+            }
+            Self::Rocksdb(db) => {
+                SnapshotMptDispatch::Rocksdb(db.open_snapshot_mpt_shared()?)
+            }
+        })
+    }
+}
+
+impl SnapshotDbTrait for SnapshotDbDispatch {
+    type SnapshotKvdbIterTraitTag = SnapshotKvdbIterDispatchTag;
+    type SnapshotKvdbIterType = SnapshotKvdbIterDispatch;
+
+    /// The NULL snapshot has no db, so the engine doesn't matter.
+    fn get_null_snapshot() -> Self {
+        Self::Sqlite(Arc::new(SnapshotDbSqlite::get_null_snapshot()))
+    }
+
+    fn open(
+        _snapshot_path: &Path, _readonly: bool,
+        _already_open_snapshots: &AlreadyOpenSnapshots<Self>,
+        _open_semaphore: &Arc<Semaphore>,
+    ) -> Result<Self>
+    {
+        unreachable!("Snapshot dbs are opened by the manager of their type.")
+    }
+
+    fn create(
+        _snapshot_path: &Path,
+        _already_open_snapshots: &AlreadyOpenSnapshots<Self>,
+        _open_semaphore: &Arc<Semaphore>,
+    ) -> Result<Self>
+    {
+        unreachable!("Snapshot dbs are created by the manager of their type.")
+    }
+
+    fn direct_merge(&mut self) -> Result<MerkleHash> {
+        dispatch!(self, db => get_exclusive(db)?.direct_merge())
+    }
+
+    fn copy_and_merge(
+        &mut self, old_snapshot_db: &SnapshotDbDispatch,
+    ) -> Result<MerkleHash> {
+        match (self, old_snapshot_db) {
+            (Self::Sqlite(db), Self::Sqlite(old_snapshot_db)) => {
+                get_exclusive(db)?.copy_and_merge(old_snapshot_db)
+            }
+            (Self::Rocksdb(db), Self::Rocksdb(old_snapshot_db)) => {
+                get_exclusive(db)?.copy_and_merge(old_snapshot_db)
+            }
+            _ => unreachable!("A manager only opens snapshots of its type."),
+        }
+    }
+
+    fn start_transaction(&mut self) -> Result<()> {
+        dispatch!(self, db => get_exclusive(db)?.start_transaction())
+    }
+
+    fn commit_transaction(&mut self) -> Result<()> {
+        dispatch!(self, db => get_exclusive(db)?.commit_transaction())
+    }
+
+    fn snapshot_kv_iterator(
+        &self,
+    ) -> Result<
+        Wrap<
+            SnapshotKvdbIterDispatch,
+            dyn KeyValueDbIterableTrait<
+                MptKeyValue,
+                [u8],
+                SnapshotKvdbIterDispatchTag,
+            >,
+        >,
+    > {
+        Ok(Wrap(match self {
+            Self::Sqlite(db) => SnapshotKvdbIterDispatch::Sqlite(
+                db.snapshot_kv_iterator()?.take(),
+            ),
+            Self::Rocksdb(db) => SnapshotKvdbIterDispatch::Rocksdb(
+                db.snapshot_kv_iterator()?.take(),
+            ),
+        }))
+    }
+}
+
+impl<SnapshotMptSqlite: SnapshotMptTraitRead> SnapshotMptTraitRead
+    for SnapshotMptDispatch<SnapshotMptSqlite>
+{
+    fn get_merkle_root(&self) -> MerkleHash {
+        dispatch!(self, mpt => mpt.get_merkle_root())
+    }
+
+    fn load_node(
+        &mut self, path: &dyn CompressedPathTrait,
+    ) -> Result<Option<SnapshotMptNode>> {
+        dispatch!(self, mpt => mpt.load_node(path))
+    }
+}
+
+impl<SnapshotMptSqlite: SnapshotMptTraitReadAndIterate>
+    SnapshotMptTraitReadAndIterate for SnapshotMptDispatch<SnapshotMptSqlite>
+{
+    fn iterate_subtree_trie_nodes_without_root(
+        &mut self, path: &dyn CompressedPathTrait,
+    ) -> Result<Box<dyn SnapshotMptIteraterTrait + '_>> {
+        dispatch!(self, mpt => {
+            mpt.iterate_subtree_trie_nodes_without_root(path)
+        })
+    }
+}
+
+impl<SnapshotMptSqlite: SnapshotMptTraitRw> SnapshotMptTraitRw
+    for SnapshotMptDispatch<SnapshotMptSqlite>
+{
+    fn delete_node(&mut self, path: &dyn CompressedPathTrait) -> Result<()> {
+        dispatch!(self, mpt => mpt.delete_node(path))
+    }
+
+    fn write_node(
+        &mut self, path: &dyn CompressedPathTrait, trie_node: &SnapshotMptNode,
+    ) -> Result<()> {
+        dispatch!(self, mpt => mpt.write_node(path, trie_node))
+    }
+}
+
+/// The iterators of the engines have different types, they are boxed.
+type KvdbIterDispatch<'a> =
+    Box<dyn 'a + FallibleIterator<Item = MptKeyValue, Error = Error>>;
+
+impl KeyValueDbIterableTrait<MptKeyValue, [u8], SnapshotKvdbIterDispatchTag>
+    for SnapshotKvdbIterDispatch
+{
+    fn iter_range(
+        &mut self, lower_bound_incl: &[u8], upper_bound_excl: Option<&[u8]>,
+    ) -> Result<
+        Wrap<
+            KvdbIterIterator<MptKeyValue, [u8], SnapshotKvdbIterDispatchTag>,
+            dyn FallibleIterator<Item = MptKeyValue, Error = Error>,
+        >,
+    > {
+        let iter: KvdbIterDispatch = match self {
+            Self::Sqlite(kvdb) => Box::new(
+                kvdb.iter_range(lower_bound_incl, upper_bound_excl)?.take(),
+            ),
+            Self::Rocksdb(kvdb) => Box::new(
+                kvdb.iter_range(lower_bound_incl, upper_bound_excl)?.take(),
+            ),
+        };
+        Ok(Wrap(iter))
+    }
+
+    fn iter_range_excl(
+        &mut self, lower_bound_excl: &[u8], upper_bound_excl: &[u8],
+    ) -> Result<
+        Wrap<
+            KvdbIterIterator<MptKeyValue, [u8], SnapshotKvdbIterDispatchTag>,
+            dyn FallibleIterator<Item = MptKeyValue, Error = Error>,
+        >,
+    > {
+        let iter: KvdbIterDispatch = match self {
+            Self::Sqlite(kvdb) => Box::new(
+                kvdb.iter_range_excl(lower_bound_excl, upper_bound_excl)?
+                    .take(),
+            ),
+            Self::Rocksdb(kvdb) => Box::new(
+                kvdb.iter_range_excl(lower_bound_excl, upper_bound_excl)?
+                    .take(),
+            ),
+        };
+        Ok(Wrap(iter))
+    }
+}
+
+enable_impl_transmute_for_element_satisfy! {
+    generic ;
+    trait 'static + KeyValueDbIterableTrait<MptKeyValue, [u8], SnapshotKvdbIterDispatchTag>;
+    for SnapshotKvdbIterDispatch;
+}
+
+impl
+    WrappedTrait<
+        dyn KeyValueDbIterableTrait<
+            MptKeyValue,
+            [u8],
+            SnapshotKvdbIterDispatchTag,
+        >,
+    > for SnapshotKvdbIterDispatch
+{
+}
+
+impl<'a>
+    WrappedLifetimeFamily<
+        'a,
+        dyn KeyValueDbIterableTrait<
+            MptKeyValue,
+            [u8],
+            SnapshotKvdbIterDispatchTag,
+        >,
+    > for SnapshotKvdbIterDispatch
+{
+    type Out = Self;
+}
+
+impl WrappedTrait<dyn FallibleIterator<Item = MptKeyValue, Error = Error>>
+    for KvdbIterIterator<MptKeyValue, [u8], SnapshotKvdbIterDispatchTag>
+{
+}
+
+impl<'a>
+    WrappedLifetimeFamily<
+        'a,
+        dyn FallibleIterator<Item = MptKeyValue, Error = Error>,
+    > for KvdbIterIterator<MptKeyValue, [u8], SnapshotKvdbIterDispatchTag>
+{
+    type Out = KvdbIterDispatch<'a>;
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_snapshot_db_type_mismatch() {
+        let snapshot_path = env::temp_dir()
+            .join(format!("snapshot_db_dispatch_{}", random::<u64>()));
+        let sqlite_snapshot_path = snapshot_path.join(
+            SnapshotDbManagerSqlite::SNAPSHOT_DB_SQLITE_DIR_PREFIX.to_string()
+                + "00",
+        );
+        fs::create_dir_all(&sqlite_snapshot_path).unwrap();
+
+        let result = SnapshotDbManagerDispatch::new(
+            snapshot_path.clone(),
+            DEFAULT_MAX_OPEN_SNAPSHOTS,
+            SnapshotDbType::Rocksdb,
+        );
+        match result {
+            Err(Error(
+                ErrorKind::SnapshotDbTypeMismatch(
+                    SnapshotDbType::Rocksdb,
+                    SnapshotDbType::Sqlite,
+                ),
+                _,
+            )) => {}
+            _ => panic!("The snapshot db type mismatch isn't detected."),
+        }
+        // The snapshot of the other type is kept.
+        assert!(sqlite_snapshot_path.exists());
+
+        SnapshotDbManagerDispatch::new(
+            snapshot_path.clone(),
+            DEFAULT_MAX_OPEN_SNAPSHOTS,
+            SnapshotDbType::Sqlite,
+        )
+        .unwrap();
+
+        fs::remove_dir_all(&snapshot_path).unwrap();
+    }
+
+    #[test]
+    fn test_temp_snapshot_write() {
+        let snapshot_path = env::temp_dir()
+            .join(format!("snapshot_db_dispatch_{}", random::<u64>()));
+        for snapshot_db_type in
+            &[SnapshotDbType::Sqlite, SnapshotDbType::Rocksdb]
+        {
+            let manager = SnapshotDbManagerDispatch::new(
+                snapshot_path.join(format!("{:?}", snapshot_db_type)),
+                DEFAULT_MAX_OPEN_SNAPSHOTS,
+                *snapshot_db_type,
+            )
+            .unwrap();
+            let mut snapshot_db = manager
+                .new_temp_snapshot_for_full_sync(
+                    &EpochId::repeat_byte(1),
+                    &MERKLE_NULL_NODE,
+                )
+                .unwrap();
+
+            snapshot_db.start_transaction().unwrap();
+            snapshot_db.put(b"key", b"value").unwrap();
+            snapshot_db.commit_transaction().unwrap();
+            assert_eq!(
+                snapshot_db.get(b"key").unwrap().as_deref(),
+                Some(&b"value"[..]),
+            );
+
+            let mut kv_iter =
+                snapshot_db.snapshot_kv_iterator().unwrap().take();
+            let kvs: Vec<MptKeyValue> = kv_iter
+                .iter_range(&[], None)
+                .unwrap()
+                .take()
+                .collect()
+                .unwrap();
+            let expected_kvs: Vec<MptKeyValue> =
+                vec![(b"key".to_vec(), b"value".to_vec().into_boxed_slice())];
+            assert_eq!(kvs, expected_kvs);
+        }
+
+        fs::remove_dir_all(&snapshot_path).unwrap();
+    }
+
+    use super::{SnapshotDbManagerDispatch, SnapshotDbManagerSqlite};
+    use crate::{
+        impls::{
+            defaults::DEFAULT_MAX_OPEN_SNAPSHOTS,
+            errors::{Error, ErrorKind},
+        },
+        storage_db::{
+            KeyValueDbIterableTrait, KeyValueDbTraitRead,
+            KeyValueDbTraitSingleWriter, SnapshotDbManagerTrait,
+            SnapshotDbTrait,
+        },
+        MptKeyValue, SnapshotDbType,
+    };
+    use fallible_iterator::FallibleIterator;
+    use primitives::{EpochId, MERKLE_NULL_NODE};
+    use rand::random;
+    use std::{env, fs};
+}
+
+use crate::{
+    impls::{
+        delta_mpt::DeltaMptIterator,
+        errors::*,
+        merkle_patricia_trie::{CompressedPathTrait, MptKeyValue},
+        storage_db::{
+            kvdb_sqlite_sharded::KvdbSqliteSharded,
+            snapshot_db_manager_rocksdb::SnapshotDbManagerRocksdb,
+            snapshot_db_manager_sqlite::{
+                AlreadyOpenSnapshots, SnapshotDbManagerSqlite,
+            },
+            snapshot_db_rocksdb::{
+                KvdbRocksdbColumn, SnapshotDbRocksdb, SnapshotMptRocksdb,
+            },
+            snapshot_db_sqlite::SnapshotDbSqlite,
+        },
+        storage_manager::PersistedSnapshotInfoMap,
+    },
+    storage_db::{
+        KeyValueDbIterableTrait, KeyValueDbTraitOwnedRead, KeyValueDbTraitRead,
+        KeyValueDbTraitSingleWriter, KeyValueDbTypes, KvdbIterIterator,
+        OpenSnapshotMptTrait, SnapshotDbManagerTrait, SnapshotDbTrait,
+        SnapshotInfo, SnapshotMptIteraterTrait, SnapshotMptNode,
+        SnapshotMptTraitRead, SnapshotMptTraitReadAndIterate,
+        SnapshotMptTraitRw,
+    },
+    utils::{
+        tuple::ElementSatisfy,
+        wrap::{Wrap, WrappedLifetimeFamily, WrappedTrait},
+    },
+    SnapshotDbType,
+};
+use fallible_iterator::FallibleIterator;
+use parking_lot::{RwLock, RwLockWriteGuard};
+use primitives::{EpochId, MerkleHash};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::sync::Semaphore;
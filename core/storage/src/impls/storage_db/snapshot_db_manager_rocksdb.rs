@@ -0,0 +1,587 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+pub struct SnapshotDbManagerRocksdb {
+    snapshot_path: PathBuf,
+    already_open_snapshots: AlreadyOpenSnapshots<SnapshotDbRocksdb>,
+    /// Set a limit on the number of open snapshots. When the limit is reached,
+    /// consensus initiated open should wait, other non-critical opens such as
+    /// rpc initiated opens should simply abort when the limit is reached.
+    open_snapshot_semaphore: Arc<Semaphore>,
+    open_create_delete_lock: Mutex<()>,
+}
+
+impl SnapshotDbManagerRocksdb {
+    pub const SNAPSHOT_DB_ROCKSDB_DIR_PREFIX: &'static str = "rocksdb_";
+
+    pub fn new(
+        snapshot_path: PathBuf, max_open_snapshots: u16,
+    ) -> Result<Self> {
+        if !snapshot_path.exists() {
+            fs::create_dir_all(snapshot_path.clone())?;
+        }
+
+        Ok(Self {
+            snapshot_path,
+            already_open_snapshots: Default::default(),
+            open_snapshot_semaphore: Arc::new(Semaphore::new(
+                max_open_snapshots as usize,
+            )),
+            open_create_delete_lock: Default::default(),
+        })
+    }
+
+    fn open_snapshot_readonly(
+        &self, snapshot_path: PathBuf, try_open: bool,
+    ) -> Result<Option<Arc<SnapshotDbRocksdb>>> {
+        if let Some(already_open) =
+            self.already_open_snapshots.read().get(&snapshot_path)
+        {
+            match already_open {
+                None => {
+                    // Already open for exclusive write
+                    return Ok(None);
+                }
+                Some(open_shared_weak) => {
+                    match Weak::upgrade(open_shared_weak) {
+                        None => {}
+                        Some(already_open) => {
+                            return Ok(Some(already_open));
+                        }
+                    }
+                }
+            }
+        }
+        if !snapshot_path.exists() {
+            return Ok(None);
+        }
+        let semaphore_permit = if try_open {
+            self.open_snapshot_semaphore
+                .try_acquire()
+                .map_err(|_err| ErrorKind::SemaphoreTryAcquireError)?
+        } else {
+            executor::block_on(self.open_snapshot_semaphore.acquire())
+        };
+
+        // To serialize simultaneous opens.
+        let _open_lock = self.open_create_delete_lock.lock();
+        // RocksDB holds a file lock on the db directory, so we must wait for
+        // a dropped db to be fully closed before opening it again. See also
+        // `SnapshotDbManagerSqlite::open_snapshot_readonly`.
+        while let Some(already_open) =
+            self.already_open_snapshots.read().get(&snapshot_path)
+        {
+            match already_open {
+                None => {
+                    // Already open for exclusive write
+                    return Ok(None);
+                }
+                Some(open_shared_weak) => match Weak::upgrade(open_shared_weak)
+                {
+                    None => {
+                        thread::sleep(Duration::from_millis(5));
+                        continue;
+                    }
+                    Some(already_open) => {
+                        return Ok(Some(already_open));
+                    }
+                },
+            }
+        }
+
+        let snapshot_db = Arc::new(SnapshotDbRocksdb::open(
+            snapshot_path.as_path(),
+            /* readonly = */ true,
+            &self.already_open_snapshots,
+            &self.open_snapshot_semaphore,
+        )?);
+
+        semaphore_permit.forget();
+        self.already_open_snapshots
+            .write()
+            .insert(snapshot_path, Some(Arc::downgrade(&snapshot_db)));
+
+        Ok(Some(snapshot_db))
+    }
+
+    fn open_snapshot_write(
+        &self, snapshot_path: PathBuf, create: bool,
+    ) -> Result<SnapshotDbRocksdb> {
+        if self
+            .already_open_snapshots
+            .read()
+            .get(&snapshot_path)
+            .is_some()
+        {
+            bail!(ErrorKind::SnapshotAlreadyExists)
+        }
+
+        let semaphore_permit =
+            executor::block_on(self.open_snapshot_semaphore.acquire());
+        // When an open happens around the same time, we should make sure that
+        // the open returns None.
+        let _open_lock = self.open_create_delete_lock.lock();
+
+        // Simultaneous creation fails here.
+        if self
+            .already_open_snapshots
+            .read()
+            .get(&snapshot_path)
+            .is_some()
+        {
+            bail!(ErrorKind::SnapshotAlreadyExists)
+        }
+
+        let snapshot_db = if create {
+            SnapshotDbRocksdb::create(
+                snapshot_path.as_path(),
+                &self.already_open_snapshots,
+                &self.open_snapshot_semaphore,
+            )
+        } else if snapshot_path.exists() {
+            SnapshotDbRocksdb::open(
+                snapshot_path.as_path(),
+                /* readonly = */ false,
+                &self.already_open_snapshots,
+                &self.open_snapshot_semaphore,
+            )
+        } else {
+            bail!(ErrorKind::SnapshotNotFound);
+        }?;
+
+        semaphore_permit.forget();
+        self.already_open_snapshots
+            .write()
+            .insert(snapshot_path, None);
+        Ok(snapshot_db)
+    }
+
+    pub fn on_close(
+        already_open_snapshots: &AlreadyOpenSnapshots<SnapshotDbRocksdb>,
+        open_semaphore: &Arc<Semaphore>, path: &Path, remove_on_close: bool,
+    )
+    {
+        // Destroy at close. The error is ignored for the same reason as in
+        // `SnapshotDbManagerSqlite::on_close`.
+        if remove_on_close {
+            Self::fs_remove_snapshot(path).ok();
+        }
+        already_open_snapshots.write().remove(path);
+        open_semaphore.add_permits(1);
+    }
+
+    fn fs_remove_snapshot(path: &Path) -> Result<()> {
+        debug!("Remove snapshot at {}", path.display());
+        Ok(fs::remove_dir_all(path)?)
+    }
+
+    fn get_merge_temp_snapshot_db_path(
+        &self, old_snapshot_epoch_id: &EpochId, new_snapshot_epoch_id: &EpochId,
+    ) -> PathBuf {
+        self.snapshot_path.join(
+            Self::SNAPSHOT_DB_ROCKSDB_DIR_PREFIX.to_string()
+                + "merge_temp_"
+                + &old_snapshot_epoch_id.as_ref().to_hex::<String>()
+                + &new_snapshot_epoch_id.as_ref().to_hex::<String>(),
+        )
+    }
+
+    fn get_full_sync_temp_snapshot_db_path(
+        &self, snapshot_epoch_id: &EpochId, merkle_root: &MerkleHash,
+    ) -> PathBuf {
+        self.snapshot_path.join(
+            Self::SNAPSHOT_DB_ROCKSDB_DIR_PREFIX.to_string()
+                + "full_sync_temp_"
+                + &snapshot_epoch_id.as_ref().to_hex::<String>()
+                + &merkle_root.as_ref().to_hex::<String>(),
+        )
+    }
+
+    /// Copy a snapshot with a RocksDB checkpoint, which hard-links the SST
+    /// files of the old snapshot and flushes its memtables into the copy.
+    fn try_copy_snapshot(
+        &self, old_snapshot_epoch_id: &EpochId, new_snapshot_path: &Path,
+    ) -> Result<()> {
+        let old_snapshot_db = self
+            .open_snapshot_readonly(
+                self.get_snapshot_db_path(old_snapshot_epoch_id),
+                /* try_open = */ false,
+            )?
+            .ok_or(Error::from(ErrorKind::SnapshotNotFound))?;
+        if let Err(e) = old_snapshot_db.create_checkpoint(new_snapshot_path) {
+            warn!(
+                "Fail to copy snapshot {:?}, err={:?}",
+                old_snapshot_epoch_id, e
+            );
+            if new_snapshot_path.exists() {
+                fs::remove_dir_all(new_snapshot_path)?;
+            }
+            bail!(ErrorKind::SnapshotCopyFailure);
+        }
+        Ok(())
+    }
+
+    /// Copy the old snapshot and open the copy for write. The copy is removed
+    /// if it can't be opened.
+    fn try_open_snapshot_copy(
+        &self, old_snapshot_epoch_id: &EpochId, new_snapshot_path: &Path,
+    ) -> Result<SnapshotDbRocksdb> {
+        self.try_copy_snapshot(old_snapshot_epoch_id, new_snapshot_path)?;
+        match self.open_snapshot_write(
+            new_snapshot_path.to_path_buf(),
+            /* create = */ false,
+        ) {
+            Ok(snapshot_db) => Ok(snapshot_db),
+            Err(e) => {
+                warn!(
+                    "Fail to open copied snapshot {:?}, err={:?}",
+                    new_snapshot_path, e,
+                );
+                fs::remove_dir_all(new_snapshot_path)?;
+                Err(e)
+            }
+        }
+    }
+
+    fn copy_and_merge(
+        &self, temp_snapshot_db: &mut SnapshotDbRocksdb,
+        old_snapshot_epoch_id: &EpochId,
+    ) -> Result<MerkleHash>
+    {
+        let snapshot_path = self.get_snapshot_db_path(old_snapshot_epoch_id);
+        let maybe_old_snapshot_db = Self::open_snapshot_readonly(
+            self,
+            snapshot_path,
+            /* try_open = */ false,
+        )?;
+        let old_snapshot_db = maybe_old_snapshot_db
+            .ok_or(Error::from(ErrorKind::SnapshotNotFound))?;
+        temp_snapshot_db.copy_and_merge(&old_snapshot_db)
+    }
+
+    fn rename_snapshot_db<P: AsRef<Path>>(
+        old_path: P, new_path: P,
+    ) -> Result<()> {
+        Ok(fs::rename(old_path, new_path)?)
+    }
+}
+
+impl SnapshotDbManagerTrait for SnapshotDbManagerRocksdb {
+    type SnapshotDb = SnapshotDbRocksdb;
+
+    fn get_snapshot_dir(&self) -> &Path { self.snapshot_path.as_path() }
+
+    fn get_snapshot_db_name(&self, snapshot_epoch_id: &EpochId) -> String {
+        Self::SNAPSHOT_DB_ROCKSDB_DIR_PREFIX.to_string()
+            + &snapshot_epoch_id.as_ref().to_hex::<String>()
+    }
+
+    fn get_snapshot_db_path(&self, snapshot_epoch_id: &EpochId) -> PathBuf {
+        self.snapshot_path
+            .join(&self.get_snapshot_db_name(snapshot_epoch_id))
+    }
+
+    fn new_snapshot_by_merging<'m>(
+        &self, old_snapshot_epoch_id: &EpochId, snapshot_epoch_id: EpochId,
+        delta_mpt: DeltaMptIterator,
+        mut in_progress_snapshot_info: SnapshotInfo,
+        snapshot_info_map_rwlock: &'m RwLock<PersistedSnapshotInfoMap>,
+    ) -> Result<(RwLockWriteGuard<'m, PersistedSnapshotInfoMap>, SnapshotInfo)>
+    {
+        debug!(
+            "new_snapshot_by_merging: old={:?} new={:?}",
+            old_snapshot_epoch_id, snapshot_epoch_id
+        );
+        let temp_db_path = self.get_merge_temp_snapshot_db_path(
+            old_snapshot_epoch_id,
+            &snapshot_epoch_id,
+        );
+
+        let mut snapshot_db;
+        let new_snapshot_root = if *old_snapshot_epoch_id == NULL_EPOCH {
+            // direct merge the first snapshot
+            snapshot_db = Self::SnapshotDb::create(
+                temp_db_path.as_path(),
+                &self.already_open_snapshots,
+                &self.open_snapshot_semaphore,
+            )?;
+            snapshot_db.dump_delta_mpt(&delta_mpt)?;
+            snapshot_db.direct_merge()?
+        } else {
+            match self.try_open_snapshot_copy(
+                old_snapshot_epoch_id,
+                temp_db_path.as_path(),
+            ) {
+                Ok(copied_snapshot_db) => {
+                    snapshot_db = copied_snapshot_db;
+                    // Drop copied old snapshot delta mpt dump
+                    snapshot_db.drop_delta_mpt_dump()?;
+
+                    // iterate and insert into temp columns.
+                    snapshot_db.dump_delta_mpt(&delta_mpt)?;
+                    snapshot_db.direct_merge()?
+                }
+                Err(_) => {
+                    snapshot_db = self.open_snapshot_write(
+                        temp_db_path.clone(),
+                        /* create = */ true,
+                    )?;
+                    snapshot_db.dump_delta_mpt(&delta_mpt)?;
+                    self.copy_and_merge(
+                        &mut snapshot_db,
+                        old_snapshot_epoch_id,
+                    )?
+                }
+            }
+        };
+        in_progress_snapshot_info.merkle_root = new_snapshot_root.clone();
+        drop(snapshot_db);
+        let locked = snapshot_info_map_rwlock.write();
+        Self::rename_snapshot_db(
+            &temp_db_path,
+            &self.get_snapshot_db_path(&snapshot_epoch_id),
+        )?;
+
+        Ok((locked, in_progress_snapshot_info))
+    }
+
+    fn get_snapshot_by_epoch_id(
+        &self, snapshot_epoch_id: &EpochId, try_open: bool,
+    ) -> Result<Option<Arc<Self::SnapshotDb>>> {
+        if snapshot_epoch_id.eq(&NULL_EPOCH) {
+            return Ok(Some(Arc::new(Self::SnapshotDb::get_null_snapshot())));
+        } else {
+            let path = self.get_snapshot_db_path(snapshot_epoch_id);
+            self.open_snapshot_readonly(path, try_open)
+        }
+    }
+
+    fn destroy_snapshot(&self, snapshot_epoch_id: &EpochId) -> Result<()> {
+        let path = self.get_snapshot_db_path(snapshot_epoch_id);
+        let maybe_snapshot = loop {
+            match self.already_open_snapshots.read().get(&path) {
+                Some(Some(snapshot)) => {
+                    match Weak::upgrade(snapshot) {
+                        None => {
+                            // This is transient and we wait for the db to be
+                            // fully closed.
+                            thread::sleep(Duration::from_millis(5));
+                            continue;
+                        }
+                        Some(snapshot) => break Some(snapshot),
+                    }
+                }
+                Some(None) => {
+                    // This should not happen because Conflux always write on a
+                    // snapshot db under a temporary name. All completed
+                    // snapshots are readonly.
+                    if cfg!(debug_assertions) {
+                        unreachable!("Try to destroy a snapshot being open exclusively for write.")
+                    } else {
+                        unsafe { unreachable_unchecked() }
+                    }
+                }
+                None => break None,
+            };
+        };
+
+        match maybe_snapshot {
+            None => {
+                if snapshot_epoch_id.ne(&NULL_EPOCH) {
+                    Self::fs_remove_snapshot(&path)?;
+                }
+            }
+            Some(snapshot) => {
+                snapshot.set_remove_on_last_close();
+            }
+        };
+
+        Ok(())
+    }
+
+    fn new_temp_snapshot_for_full_sync(
+        &self, snapshot_epoch_id: &EpochId, merkle_root: &MerkleHash,
+    ) -> Result<Self::SnapshotDb> {
+        let temp_db_path = self.get_full_sync_temp_snapshot_db_path(
+            snapshot_epoch_id,
+            merkle_root,
+        );
+        self.open_snapshot_write(temp_db_path, /* create = */ true)
+    }
+
+    fn finalize_full_sync_snapshot<'m>(
+        &self, snapshot_epoch_id: &EpochId, merkle_root: &MerkleHash,
+        snapshot_info_map_rwlock: &'m RwLock<PersistedSnapshotInfoMap>,
+    ) -> Result<RwLockWriteGuard<'m, PersistedSnapshotInfoMap>>
+    {
+        let temp_db_path = self.get_full_sync_temp_snapshot_db_path(
+            snapshot_epoch_id,
+            merkle_root,
+        );
+        let final_db_path = self.get_snapshot_db_path(snapshot_epoch_id);
+        let locked = snapshot_info_map_rwlock.write();
+        Self::rename_snapshot_db(&temp_db_path, &final_db_path)?;
+        Ok(locked)
+    }
+}
+
+use crate::{
+    impls::{
+        delta_mpt::DeltaMptIterator,
+        errors::*,
+        storage_db::{
+            snapshot_db_manager_sqlite::AlreadyOpenSnapshots,
+            snapshot_db_rocksdb::SnapshotDbRocksdb,
+        },
+        storage_manager::PersistedSnapshotInfoMap,
+    },
+    storage_db::{SnapshotDbManagerTrait, SnapshotDbTrait, SnapshotInfo},
+};
+use futures::executor;
+use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
+use primitives::{EpochId, MerkleHash, NULL_EPOCH};
+use rustc_hex::ToHex;
+use std::{
+    fs,
+    hint::unreachable_unchecked,
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+    thread,
+    time::Duration,
+};
+use tokio::sync::Semaphore;
+
+#[cfg(test)]
+mod tests {
+    fn new_manager_for_testing() -> SnapshotDbManagerRocksdb {
+        let snapshot_path = env::temp_dir()
+            .join(format!("snapshot_db_rocksdb_{}", random::<u64>()));
+        SnapshotDbManagerRocksdb::new(snapshot_path, DEFAULT_MAX_OPEN_SNAPSHOTS)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_copy_snapshot() {
+        let manager = new_manager_for_testing();
+        let old_snapshot_epoch_id = EpochId::repeat_byte(1);
+        let mut snapshot_db = manager
+            .open_snapshot_write(
+                manager.get_snapshot_db_path(&old_snapshot_epoch_id),
+                /* create = */ true,
+            )
+            .unwrap();
+        snapshot_db.put(b"key", b"value").unwrap();
+        snapshot_db.commit_transaction().unwrap();
+        drop(snapshot_db);
+
+        let new_snapshot_path = manager.get_snapshot_dir().join("copy");
+        manager
+            .try_copy_snapshot(&old_snapshot_epoch_id, &new_snapshot_path)
+            .unwrap();
+        let mut copied_snapshot_db = manager
+            .open_snapshot_write(
+                new_snapshot_path.clone(),
+                /* create = */ false,
+            )
+            .unwrap();
+        assert_eq!(
+            copied_snapshot_db.get(b"key").unwrap().as_deref(),
+            Some(&b"value"[..]),
+        );
+
+        // Writes to the copy don't change the old snapshot.
+        copied_snapshot_db.put(b"key", b"new_value").unwrap();
+        copied_snapshot_db.commit_transaction().unwrap();
+        drop(copied_snapshot_db);
+        let old_snapshot_db = manager
+            .get_snapshot_by_epoch_id(
+                &old_snapshot_epoch_id,
+                /* try_open = */ false,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            old_snapshot_db.get(b"key").unwrap().as_deref(),
+            Some(&b"value"[..]),
+        );
+        drop(old_snapshot_db);
+
+        // Copying a missing snapshot fails without leaving a partial copy.
+        let missing_copy_path = manager.get_snapshot_dir().join("missing");
+        assert!(manager
+            .try_copy_snapshot(&EpochId::repeat_byte(2), &missing_copy_path)
+            .is_err());
+        assert!(!missing_copy_path.exists());
+
+        fs::remove_dir_all(manager.get_snapshot_dir()).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_kv_iterator() {
+        let manager = new_manager_for_testing();
+        let mut snapshot_db = manager
+            .open_snapshot_write(
+                manager.get_snapshot_db_path(&EpochId::repeat_byte(1)),
+                /* create = */ true,
+            )
+            .unwrap();
+        // More keys than a batch of the range iterator.
+        let keys: Vec<Vec<u8>> =
+            (0..2500u32).map(|i| i.to_be_bytes().to_vec()).collect();
+        for key in keys.iter().rev() {
+            snapshot_db.put(key, key).unwrap();
+        }
+
+        // The buffered writes are visible to the iterator.
+        let mut kv_iter = snapshot_db.snapshot_kv_iterator().unwrap().take();
+        let kvs: Vec<MptKeyValue> = kv_iter
+            .iter_range(&[], None)
+            .unwrap()
+            .take()
+            .collect()
+            .unwrap();
+        assert_eq!(kvs.len(), keys.len());
+        for ((key, value), expected_key) in kvs.iter().zip(&keys) {
+            assert_eq!(key, expected_key);
+            assert_eq!(&**value, &expected_key[..]);
+        }
+
+        let range_keys: Vec<Vec<u8>> = kv_iter
+            .iter_range_excl(&keys[100], &keys[2100])
+            .unwrap()
+            .take()
+            .map(|(key, _)| Ok(key))
+            .collect()
+            .unwrap();
+        assert_eq!(range_keys, keys[101..2100].to_vec());
+
+        let range_keys: Vec<Vec<u8>> = kv_iter
+            .iter_range(&keys[2400], None)
+            .unwrap()
+            .take()
+            .map(|(key, _)| Ok(key))
+            .collect()
+            .unwrap();
+        assert_eq!(range_keys, keys[2400..].to_vec());
+
+        drop(kv_iter);
+        drop(snapshot_db);
+        fs::remove_dir_all(manager.get_snapshot_dir()).unwrap();
+    }
+
+    use super::SnapshotDbManagerRocksdb;
+    use crate::{
+        impls::defaults::DEFAULT_MAX_OPEN_SNAPSHOTS,
+        storage_db::{
+            KeyValueDbIterableTrait, KeyValueDbTraitRead,
+            KeyValueDbTraitSingleWriter, SnapshotDbManagerTrait,
+            SnapshotDbTrait,
+        },
+        MptKeyValue,
+    };
+    use fallible_iterator::FallibleIterator;
+    use primitives::EpochId;
+    use rand::random;
+    use std::{env, fs};
+}
@@ -23,7 +23,7 @@ pub type AlreadyOpenSnapshots<T> =
     Arc<RwLock<HashMap<PathBuf, Option<Weak<T>>>>>;
 
 impl SnapshotDbManagerSqlite {
-    const SNAPSHOT_DB_SQLITE_DIR_PREFIX: &'static str = "sqlite_";
+    pub const SNAPSHOT_DB_SQLITE_DIR_PREFIX: &'static str = "sqlite_";
 
     pub fn new(
         snapshot_path: PathBuf, max_open_snapshots: u16,
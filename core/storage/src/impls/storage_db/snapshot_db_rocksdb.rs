@@ -0,0 +1,694 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+pub struct SnapshotDbRocksdb {
+    // Option because we need an empty snapshot db for empty snapshot.
+    maybe_db: Option<Arc<Database>>,
+    already_open_snapshots: AlreadyOpenSnapshots<Self>,
+    open_semaphore: Arc<Semaphore>,
+    path: PathBuf,
+    remove_on_close: AtomicBool,
+    /// The number of writes buffered in the overlay of the db since the last
+    /// flush.
+    buffered_writes: usize,
+}
+
+impl Drop for SnapshotDbRocksdb {
+    fn drop(&mut self) {
+        if !self.path.as_os_str().is_empty() {
+            self.maybe_db.take();
+            SnapshotDbManagerRocksdb::on_close(
+                &self.already_open_snapshots,
+                &self.open_semaphore,
+                &self.path,
+                self.remove_on_close.load(Ordering::Relaxed),
+            )
+        }
+    }
+}
+
+impl SnapshotDbRocksdb {
+    /// These two columns are temporary for the merging process, but they
+    /// remain to help other nodes to do 1-step syncing. They must be the last
+    /// two columns so that they can be dropped and recreated.
+    pub const COL_DELTA_KV_DELETE: u32 = 3;
+    pub const COL_DELTA_KV_SET: u32 = 2;
+    /// Key-Value column. Key is unique key in this column.
+    pub const COL_SNAPSHOT_KV: u32 = 0;
+    /// MPT column.
+    pub const COL_SNAPSHOT_MPT: u32 = 1;
+    const ROCKSDB_CONFIG: DatabaseConfig = DatabaseConfig {
+        max_open_files: 512,
+        memory_budget: None,
+        compaction: CompactionProfile {
+            initial_file_size: 512 * 1048576 as u64,
+            block_size: 16 * 1024,
+            write_rate_limit: Some(64 * 1048576 as u64),
+        },
+        columns: 4,
+        disable_wal: false,
+    };
+    /// Buffered writes are flushed to RocksDB in batches of this size to
+    /// bound the memory used by the overlay.
+    const WRITE_BATCH_SIZE: usize = 100_000;
+
+    fn open_db(snapshot_path: &Path) -> Result<Database> {
+        Ok(Database::open(
+            &Self::ROCKSDB_CONFIG,
+            snapshot_path.to_str().unwrap(),
+        )?)
+    }
+
+    fn column(&self, col: u32) -> KvdbRocksdbColumn {
+        KvdbRocksdbColumn {
+            maybe_db: self.maybe_db.clone(),
+            col,
+        }
+    }
+
+    fn write_buffered(&mut self, transaction: DBTransaction) -> Result<()> {
+        // Safe to unwrap since we are not on a NULL snapshot.
+        let db = self.maybe_db.as_ref().unwrap();
+        self.buffered_writes += transaction.ops.len();
+        db.write_buffered(transaction);
+        if self.buffered_writes >= Self::WRITE_BATCH_SIZE {
+            db.flush()?;
+            self.buffered_writes = 0;
+        }
+        Ok(())
+    }
+}
+
+impl KeyValueDbTypes for SnapshotDbRocksdb {
+    type ValueType = Box<[u8]>;
+}
+
+impl KeyValueDbTraitRead for SnapshotDbRocksdb {
+    fn get(&self, key: &[u8]) -> Result<Option<Box<[u8]>>> {
+        match &self.maybe_db {
+            None => Ok(None),
+            Some(db) => Ok(db
+                .get(Self::COL_SNAPSHOT_KV, key)?
+                .map(|db_value| db_value.into_boxed_slice())),
+        }
+    }
+}
+
+impl KeyValueDbTraitOwnedRead for SnapshotDbRocksdb {
+    fn get_mut(&mut self, key: &[u8]) -> Result<Option<Box<[u8]>>> {
+        self.get(key)
+    }
+}
+
+impl KeyValueDbTraitSingleWriter for SnapshotDbRocksdb {
+    fn delete(&mut self, key: &[u8]) -> Result<Option<Option<Box<[u8]>>>> {
+        let mut transaction = DBTransaction::new();
+        transaction.delete(Self::COL_SNAPSHOT_KV, key);
+        self.write_buffered(transaction)?;
+        Ok(None)
+    }
+
+    fn put(
+        &mut self, key: &[u8], value: &[u8],
+    ) -> Result<Option<Option<Box<[u8]>>>> {
+        let mut transaction = DBTransaction::new();
+        transaction.put(Self::COL_SNAPSHOT_KV, key, value);
+        self.write_buffered(transaction)?;
+        Ok(None)
+    }
+}
+
+impl<'db> OpenSnapshotMptTrait<'db> for SnapshotDbRocksdb {
+    type SnapshotDbAsOwnedType = SnapshotMptRocksdb;
+    type SnapshotDbBorrowMutType = SnapshotMptRocksdb;
+    type SnapshotDbBorrowSharedType = SnapshotMptRocksdb;
+
+    fn open_snapshot_mpt_owned(
+        &'db mut self,
+    ) -> Result<Self::SnapshotDbBorrowMutType> {
+        SnapshotMptRocksdb::new(self.maybe_db.clone())
+    }
+
+    fn open_snapshot_mpt_as_owned(
+        &'db self,
+    ) -> Result<Self::SnapshotDbAsOwnedType> {
+        SnapshotMptRocksdb::new(self.maybe_db.clone())
+    }
+
+    fn open_snapshot_mpt_shared(
+        &'db self,
+    ) -> Result<Self::SnapshotDbBorrowSharedType> {
+        SnapshotMptRocksdb::new(self.maybe_db.clone())
+    }
+}
+
+impl SnapshotDbTrait for SnapshotDbRocksdb {
+    type SnapshotKvdbIterTraitTag = KvdbRocksdbIteratorTag;
+    type SnapshotKvdbIterType = KvdbRocksdbColumn;
+
+    fn get_null_snapshot() -> Self {
+        Self {
+            maybe_db: None,
+            already_open_snapshots: Default::default(),
+            open_semaphore: Arc::new(Semaphore::new(0)),
+            path: Default::default(),
+            remove_on_close: Default::default(),
+            buffered_writes: 0,
+        }
+    }
+
+    fn open(
+        snapshot_path: &Path, _readonly: bool,
+        already_open_snapshots: &AlreadyOpenSnapshots<Self>,
+        open_semaphore: &Arc<Semaphore>,
+    ) -> Result<SnapshotDbRocksdb>
+    {
+        Ok(Self {
+            maybe_db: Some(Arc::new(Self::open_db(snapshot_path)?)),
+            already_open_snapshots: already_open_snapshots.clone(),
+            open_semaphore: open_semaphore.clone(),
+            path: snapshot_path.to_path_buf(),
+            remove_on_close: Default::default(),
+            buffered_writes: 0,
+        })
+    }
+
+    fn create(
+        snapshot_path: &Path,
+        already_open_snapshots: &AlreadyOpenSnapshots<Self>,
+        open_snapshots_semaphore: &Arc<Semaphore>,
+    ) -> Result<SnapshotDbRocksdb>
+    {
+        fs::create_dir_all(snapshot_path)?;
+        match Self::open_db(snapshot_path) {
+            Err(e) => {
+                fs::remove_dir_all(&snapshot_path)?;
+                bail!(e);
+            }
+            Ok(db) => Ok(SnapshotDbRocksdb {
+                maybe_db: Some(Arc::new(db)),
+                already_open_snapshots: already_open_snapshots.clone(),
+                open_semaphore: open_snapshots_semaphore.clone(),
+                path: snapshot_path.to_path_buf(),
+                remove_on_close: Default::default(),
+                buffered_writes: 0,
+            }),
+        }
+    }
+
+    fn direct_merge(&mut self) -> Result<MerkleHash> {
+        debug!("direct_merge begins.");
+        self.apply_update_to_kvdb()?;
+
+        let mut set_keys_iter = self.dumped_delta_kv_set_keys_iterator()?;
+        let mut delete_keys_iter =
+            self.dumped_delta_kv_delete_keys_iterator()?;
+
+        self.start_transaction()?;
+        let mut mpt_to_modify = self.open_snapshot_mpt_owned()?;
+
+        let mut mpt_merger = MptMerger::new(
+            None,
+            &mut mpt_to_modify as &mut dyn SnapshotMptTraitRw,
+        );
+        let snapshot_root = mpt_merger.merge_insertion_deletion_separated(
+            delete_keys_iter
+                .iter_range(&[], None)?
+                .take()
+                .map(|(key, _)| Ok((key, ()))),
+            set_keys_iter.iter_range(&[], None)?.take(),
+        )?;
+        self.commit_transaction()?;
+
+        Ok(snapshot_root)
+    }
+
+    fn copy_and_merge(
+        &mut self, old_snapshot_db: &SnapshotDbRocksdb,
+    ) -> Result<MerkleHash> {
+        debug!("copy_and_merge begins.");
+        let mut kv_iter = old_snapshot_db.snapshot_kv_iterator()?.take();
+        let mut iter = kv_iter.iter_range(&[], None)?.take();
+        while let Some((k, v)) = iter.next()? {
+            self.put(&k, &v)?;
+        }
+        self.apply_update_to_kvdb()?;
+
+        let mut set_keys_iter = self.dumped_delta_kv_set_keys_iterator()?;
+        let mut delete_keys_iter =
+            self.dumped_delta_kv_delete_keys_iterator()?;
+        self.start_transaction()?;
+        let mut base_mpt = old_snapshot_db.open_snapshot_mpt_as_owned()?;
+        let mut save_as_mpt = self.open_snapshot_mpt_owned()?;
+        let mut mpt_merger = MptMerger::new(
+            Some(&mut base_mpt as &mut dyn SnapshotMptTraitReadAndIterate),
+            &mut save_as_mpt as &mut dyn SnapshotMptTraitRw,
+        );
+        let snapshot_root = mpt_merger.merge_insertion_deletion_separated(
+            delete_keys_iter
+                .iter_range(&[], None)?
+                .take()
+                .map(|(key, _)| Ok((key, ()))),
+            set_keys_iter.iter_range(&[], None)?.take(),
+        )?;
+        self.commit_transaction()?;
+
+        Ok(snapshot_root)
+    }
+
+    /// RocksDB writes are buffered in the overlay of the db, there is nothing
+    /// to start.
+    fn start_transaction(&mut self) -> Result<()> { Ok(()) }
+
+    fn commit_transaction(&mut self) -> Result<()> {
+        if let Some(db) = &self.maybe_db {
+            db.flush()?;
+            self.buffered_writes = 0;
+        }
+        Ok(())
+    }
+
+    fn snapshot_kv_iterator(
+        &self,
+    ) -> Result<
+        Wrap<
+            Self::SnapshotKvdbIterType,
+            dyn KeyValueDbIterableTrait<
+                MptKeyValue,
+                [u8],
+                KvdbRocksdbIteratorTag,
+            >,
+        >,
+    > {
+        Ok(Wrap(self.column(Self::COL_SNAPSHOT_KV)))
+    }
+}
+
+impl SnapshotDbRocksdb {
+    pub fn set_remove_on_last_close(&self) {
+        self.remove_on_close.store(true, Ordering::Relaxed);
+    }
+
+    /// Create a copy of the snapshot at `path` with a RocksDB checkpoint.
+    pub fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        match &self.maybe_db {
+            // The NULL snapshot is never copied.
+            None => bail!(ErrorKind::SnapshotNotFound),
+            Some(db) => Ok(db.create_checkpoint(path)?),
+        }
+    }
+
+    pub fn dumped_delta_kv_set_keys_iterator(
+        &self,
+    ) -> Result<KvdbRocksdbColumn> {
+        Ok(self.column(Self::COL_DELTA_KV_SET))
+    }
+
+    /// The values in the delete column are empty.
+    pub fn dumped_delta_kv_delete_keys_iterator(
+        &self,
+    ) -> Result<KvdbRocksdbColumn> {
+        Ok(self.column(Self::COL_DELTA_KV_DELETE))
+    }
+
+    pub fn dump_delta_mpt(
+        &mut self, delta_mpt: &DeltaMptIterator,
+    ) -> Result<()> {
+        debug!("dump_delta_mpt starts");
+        self.start_transaction()?;
+        delta_mpt
+            .iterate(&mut DeltaMptMergeDumperRocksdb { snapshot_db: self })?;
+        self.commit_transaction()?;
+
+        Ok(())
+    }
+
+    /// Dropping is optional, because these columns are necessary to provide
+    /// 1-step syncing.
+    pub fn drop_delta_mpt_dump(&mut self) -> Result<()> {
+        self.commit_transaction()?;
+        // Safe to unwrap since we are not on a NULL snapshot.
+        let db = self.maybe_db.as_ref().unwrap();
+        // Dropping a column family is much cheaper than deleting all keys.
+        db.drop_column()?;
+        db.drop_column()?;
+        db.add_column()?;
+        db.add_column()?;
+        Ok(())
+    }
+
+    fn apply_update_to_kvdb(&mut self) -> Result<()> {
+        let mut delete_keys_iter =
+            self.dumped_delta_kv_delete_keys_iterator()?;
+        let mut iter = delete_keys_iter.iter_range(&[], None)?.take();
+        while let Some((k, _)) = iter.next()? {
+            self.delete(&k)?;
+        }
+
+        let mut set_keys_iter = self.dumped_delta_kv_set_keys_iterator()?;
+        let mut iter = set_keys_iter.iter_range(&[], None)?.take();
+        while let Some((k, v)) = iter.next()? {
+            self.put(&k, &v)?;
+        }
+        self.commit_transaction()
+    }
+}
+
+pub struct DeltaMptMergeDumperRocksdb<'a> {
+    snapshot_db: &'a mut SnapshotDbRocksdb,
+}
+
+impl<'a> KVInserter<MptKeyValue> for DeltaMptMergeDumperRocksdb<'a> {
+    fn push(&mut self, x: MptKeyValue) -> Result<()> {
+        let (mpt_key, value) = x;
+        let snapshot_key =
+            StorageKeyWithSpace::from_delta_mpt_key(&mpt_key).to_key_bytes();
+        let mut transaction = DBTransaction::new();
+        if value.len() > 0 {
+            transaction.put(
+                SnapshotDbRocksdb::COL_DELTA_KV_SET,
+                &snapshot_key,
+                &value,
+            );
+        } else {
+            transaction.put(
+                SnapshotDbRocksdb::COL_DELTA_KV_DELETE,
+                &snapshot_key,
+                &[],
+            );
+        }
+        self.snapshot_db.write_buffered(transaction)
+    }
+}
+
+/// A column of a snapshot db, which can be iterated by key range.
+pub struct KvdbRocksdbColumn {
+    maybe_db: Option<Arc<Database>>,
+    col: u32,
+}
+
+pub struct KvdbRocksdbIteratorTag();
+
+impl KeyValueDbIterableTrait<MptKeyValue, [u8], KvdbRocksdbIteratorTag>
+    for KvdbRocksdbColumn
+{
+    fn iter_range(
+        &mut self, lower_bound_incl: &[u8], upper_bound_excl: Option<&[u8]>,
+    ) -> Result<
+        Wrap<
+            KvdbIterIterator<MptKeyValue, [u8], KvdbRocksdbIteratorTag>,
+            dyn FallibleIterator<Item = MptKeyValue, Error = Error>,
+        >,
+    > {
+        Ok(Wrap(KvdbRocksdbRangeIter::new(
+            self.maybe_db.clone(),
+            self.col,
+            lower_bound_incl,
+            /* lower_bound_excluded = */ false,
+            upper_bound_excl,
+        )?))
+    }
+
+    fn iter_range_excl(
+        &mut self, lower_bound_excl: &[u8], upper_bound_excl: &[u8],
+    ) -> Result<
+        Wrap<
+            KvdbIterIterator<MptKeyValue, [u8], KvdbRocksdbIteratorTag>,
+            dyn FallibleIterator<Item = MptKeyValue, Error = Error>,
+        >,
+    > {
+        Ok(Wrap(KvdbRocksdbRangeIter::new(
+            self.maybe_db.clone(),
+            self.col,
+            lower_bound_excl,
+            /* lower_bound_excluded = */ true,
+            Some(upper_bound_excl),
+        )?))
+    }
+}
+
+enable_impl_transmute_for_element_satisfy! {
+    generic ;
+    trait 'static + KeyValueDbIterableTrait<MptKeyValue, [u8], KvdbRocksdbIteratorTag>;
+    for KvdbRocksdbColumn;
+}
+
+impl
+    WrappedTrait<
+        dyn KeyValueDbIterableTrait<MptKeyValue, [u8], KvdbRocksdbIteratorTag>,
+    > for KvdbRocksdbColumn
+{
+}
+
+impl<'a>
+    WrappedLifetimeFamily<
+        'a,
+        dyn KeyValueDbIterableTrait<MptKeyValue, [u8], KvdbRocksdbIteratorTag>,
+    > for KvdbRocksdbColumn
+{
+    type Out = Self;
+}
+
+impl WrappedTrait<dyn FallibleIterator<Item = MptKeyValue, Error = Error>>
+    for KvdbIterIterator<MptKeyValue, [u8], KvdbRocksdbIteratorTag>
+{
+}
+
+impl<'a>
+    WrappedLifetimeFamily<
+        'a,
+        dyn FallibleIterator<Item = MptKeyValue, Error = Error>,
+    > for KvdbIterIterator<MptKeyValue, [u8], KvdbRocksdbIteratorTag>
+{
+    type Out = KvdbRocksdbRangeIter;
+}
+
+/// Iterate over a key range of a column. Keys are loaded in batches, so that
+/// no RocksDB iterator is kept open between calls, and the iterator owns its
+/// db handle.
+pub struct KvdbRocksdbRangeIter {
+    maybe_db: Option<Arc<Database>>,
+    col: u32,
+    next_key: Vec<u8>,
+    next_key_excluded: bool,
+    upper_bound_excl: Option<Vec<u8>>,
+    batch: std::vec::IntoIter<(Box<[u8]>, Box<[u8]>)>,
+    end_reached: bool,
+}
+
+impl KvdbRocksdbRangeIter {
+    const BATCH_SIZE: usize = 1000;
+
+    fn new(
+        maybe_db: Option<Arc<Database>>, col: u32, lower_bound: &[u8],
+        lower_bound_excluded: bool, upper_bound_excl: Option<&[u8]>,
+    ) -> Result<Self>
+    {
+        // Buffered writes aren't visible to RocksDB iterators.
+        if let Some(db) = &maybe_db {
+            db.flush()?;
+        }
+        Ok(Self {
+            maybe_db,
+            col,
+            next_key: lower_bound.to_vec(),
+            next_key_excluded: lower_bound_excluded,
+            upper_bound_excl: upper_bound_excl.map(|k| k.to_vec()),
+            batch: Vec::new().into_iter(),
+            end_reached: false,
+        })
+    }
+}
+
+impl FallibleIterator for KvdbRocksdbRangeIter {
+    type Error = Error;
+    type Item = MptKeyValue;
+
+    fn next(&mut self) -> Result<Option<MptKeyValue>> {
+        if let Some((key, value)) = self.batch.next() {
+            return Ok(Some((key.into_vec(), value)));
+        }
+        let db = match &self.maybe_db {
+            None => return Ok(None),
+            Some(db) => db,
+        };
+        if self.end_reached {
+            return Ok(None);
+        }
+        let batch = db.iter_range(
+            self.col,
+            &self.next_key,
+            self.next_key_excluded,
+            self.upper_bound_excl.as_deref(),
+            Self::BATCH_SIZE,
+        )?;
+        if batch.len() < Self::BATCH_SIZE {
+            self.end_reached = true;
+        }
+        if let Some((key, _)) = batch.last() {
+            self.next_key = key.to_vec();
+            self.next_key_excluded = true;
+        }
+        self.batch = batch.into_iter();
+        Ok(self
+            .batch
+            .next()
+            .map(|(key, value)| (key.into_vec(), value)))
+    }
+}
+
+/// The snapshot MPT stored in the MPT column of a snapshot db.
+pub struct SnapshotMptRocksdb {
+    maybe_db: Option<Arc<Database>>,
+    merkle_root: MerkleHash,
+    buffered_writes: usize,
+}
+
+impl SnapshotMptRocksdb {
+    fn new(maybe_db: Option<Arc<Database>>) -> Result<Self> {
+        let mut mpt = Self {
+            maybe_db,
+            merkle_root: MERKLE_NULL_NODE,
+            buffered_writes: 0,
+        };
+        let path_to_root_node = CompressedPathRaw::default();
+        if let Some(root_node) = mpt.load_node(&path_to_root_node)? {
+            mpt.merkle_root = *root_node.get_merkle();
+        }
+        Ok(mpt)
+    }
+
+    fn write(&mut self, transaction: DBTransaction) -> Result<()> {
+        // Safe to unwrap since we are not writing to a NULL snapshot.
+        let db = self.maybe_db.as_ref().unwrap();
+        self.buffered_writes += transaction.ops.len();
+        db.write_buffered(transaction);
+        if self.buffered_writes >= SnapshotDbRocksdb::WRITE_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some(db) = &self.maybe_db {
+            db.flush()?;
+        }
+        self.buffered_writes = 0;
+        Ok(())
+    }
+}
+
+impl SnapshotMptTraitRead for SnapshotMptRocksdb {
+    fn get_merkle_root(&self) -> MerkleHash { self.merkle_root }
+
+    fn load_node(
+        &mut self, path: &dyn CompressedPathTrait,
+    ) -> Result<Option<SnapshotMptNode>> {
+        let db = match &self.maybe_db {
+            None => return Ok(None),
+            Some(db) => db,
+        };
+        let key = mpt_node_path_to_db_key(path);
+        match db.get(SnapshotDbRocksdb::COL_SNAPSHOT_MPT, &key)? {
+            None => Ok(None),
+            Some(rlp) => {
+                Ok(Some(SnapshotMptNode::load_rlp_and_check(&rlp, path)?))
+            }
+        }
+    }
+}
+
+impl SnapshotMptTraitReadAndIterate for SnapshotMptRocksdb {
+    fn iterate_subtree_trie_nodes_without_root(
+        &mut self, path: &dyn CompressedPathTrait,
+    ) -> Result<Box<dyn SnapshotMptIteraterTrait + '_>> {
+        let begin_key_excl = mpt_node_path_to_db_key(path);
+
+        let mut end_key_excl = begin_key_excl.clone();
+        // The key is non empty. See also comment for compressed_path_to_db_key.
+        *end_key_excl.last_mut().unwrap() += 1;
+
+        Ok(Box::new(
+            KvdbRocksdbRangeIter::new(
+                self.maybe_db.clone(),
+                SnapshotDbRocksdb::COL_SNAPSHOT_MPT,
+                &begin_key_excl,
+                /* lower_bound_excluded = */ true,
+                Some(&end_key_excl),
+            )?
+            .map(|(key, value)| {
+                Ok((
+                    mpt_node_path_from_db_key(&key)?,
+                    SnapshotMptNode::decode(&Rlp::new(&value))?,
+                ))
+            }),
+        ))
+    }
+}
+
+impl SnapshotMptTraitRw for SnapshotMptRocksdb {
+    fn delete_node(&mut self, path: &dyn CompressedPathTrait) -> Result<()> {
+        let mut transaction = DBTransaction::new();
+        transaction.delete(
+            SnapshotDbRocksdb::COL_SNAPSHOT_MPT,
+            &mpt_node_path_to_db_key(path),
+        );
+        self.write(transaction)
+    }
+
+    fn write_node(
+        &mut self, path: &dyn CompressedPathTrait, trie_node: &SnapshotMptNode,
+    ) -> Result<()> {
+        let mut transaction = DBTransaction::new();
+        transaction.put(
+            SnapshotDbRocksdb::COL_SNAPSHOT_MPT,
+            &mpt_node_path_to_db_key(path),
+            &trie_node.rlp_bytes(),
+        );
+        self.write(transaction)
+    }
+}
+
+use crate::{
+    impls::{
+        delta_mpt::DeltaMptIterator,
+        errors::*,
+        merkle_patricia_trie::{
+            CompressedPathRaw, CompressedPathTrait, MptKeyValue, MptMerger,
+        },
+        storage_db::{
+            snapshot_db_manager_rocksdb::SnapshotDbManagerRocksdb,
+            snapshot_db_manager_sqlite::AlreadyOpenSnapshots,
+            snapshot_mpt::{
+                mpt_node_path_from_db_key, mpt_node_path_to_db_key,
+            },
+        },
+    },
+    storage_db::{
+        KeyValueDbIterableTrait, KeyValueDbTraitOwnedRead, KeyValueDbTraitRead,
+        KeyValueDbTraitSingleWriter, KeyValueDbTypes, KvdbIterIterator,
+        OpenSnapshotMptTrait, SnapshotDbTrait, SnapshotMptIteraterTrait,
+        SnapshotMptNode, SnapshotMptTraitRead, SnapshotMptTraitReadAndIterate,
+        SnapshotMptTraitRw,
+    },
+    utils::{
+        tuple::ElementSatisfy,
+        wrap::{Wrap, WrappedLifetimeFamily, WrappedTrait},
+    },
+    KVInserter,
+};
+use fallible_iterator::FallibleIterator;
+use kvdb::DBTransaction;
+use kvdb_rocksdb::{CompactionProfile, Database, DatabaseConfig};
+use primitives::{MerkleHash, StorageKeyWithSpace, MERKLE_NULL_NODE};
+use rlp::{Decodable, Encodable, Rlp};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::Semaphore;
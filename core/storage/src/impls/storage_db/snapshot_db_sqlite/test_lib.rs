@@ -30,8 +30,11 @@ impl MptValueKind for Box<[u8]> {
     }
 }
 
-pub fn check_key_value_load<Value: MptValueKind>(
-    snapshot_db: &SnapshotDbSqlite,
+pub fn check_key_value_load<
+    SnapshotDbType: for<'db> OpenSnapshotMptTrait<'db>,
+    Value: MptValueKind,
+>(
+    snapshot_db: &SnapshotDbType,
     mut kv_iter: impl FallibleIterator<Item = (Vec<u8>, Value), Error = Error>,
     check_value: bool,
 ) -> Result<u64>
@@ -76,7 +79,6 @@ use crate::{
             mpt_cursor::{BasicPathNode, CursorOpenPathTerminal, MptCursor},
             TrieNodeTrait,
         },
-        storage_db::snapshot_db_sqlite::SnapshotDbSqlite,
     },
     storage_db::{snapshot_db::OpenSnapshotMptTrait, SnapshotMptTraitRead},
     utils::access_mode,
@@ -87,7 +89,7 @@ use std::fmt::Debug;
 #[cfg(test)]
 use crate::impls::{
     defaults::DEFAULT_MAX_OPEN_SNAPSHOTS,
-    storage_db::snapshot_db_sqlite::SnapshotDbTrait,
+    storage_db::snapshot_db_sqlite::{SnapshotDbSqlite, SnapshotDbTrait},
 };
 #[cfg(test)]
 use std::{path::Path, sync::Arc};
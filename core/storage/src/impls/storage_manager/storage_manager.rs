@@ -235,6 +235,7 @@ impl StorageManager {
                 snapshot_db_manager: SnapshotDbManager::new(
                     storage_conf.path_snapshot_dir.clone(),
                     storage_conf.max_open_snapshots,
+                    storage_conf.snapshot_db_type,
                 )?,
             }),
             delta_mpts_id_gen: Default::default(),
@@ -390,6 +391,14 @@ impl StorageManager {
         self.storage_conf.consensus_param.snapshot_epoch_count
     }
 
+    pub fn archive_historical_state(&self) -> bool {
+        self.storage_conf.archive_historical_state
+    }
+
+    pub fn archive_state_epoch_interval(&self) -> u64 {
+        self.storage_conf.archive_state_epoch_interval()
+    }
+
     pub fn get_snapshot_info_at_epoch(
         &self, snapshot_epoch_id: &EpochId,
     ) -> Option<SnapshotInfo> {
@@ -399,6 +408,100 @@ impl StorageManager {
             .map(Clone::clone)
     }
 
+    /// Offline integrity check of the current snapshots. Every node of a
+    /// snapshot mpt must be consistent with the merkle hashes of its
+    /// children, and the merkle root must match the one recorded in the
+    /// snapshot info. Returns the snapshots which fail the check together
+    /// with the reason.
+    ///
+    /// With `repair`, the invalid snapshots are destroyed, and their snapshot
+    /// info is removed by `load_persist_state` at the next startup.
+    pub fn check_snapshots(&self, repair: bool) -> Vec<(EpochId, String)> {
+        let mut invalid_snapshots = Vec::new();
+        let current_snapshots = self.current_snapshots.read().clone();
+        for snapshot_info in &current_snapshots {
+            let snapshot_epoch_id = snapshot_info.get_snapshot_epoch_id();
+            if *snapshot_epoch_id == NULL_EPOCH
+                || snapshot_info.snapshot_info_kept_to_provide_sync
+                    == SnapshotKeptToProvideSyncStatus::InfoOnly
+            {
+                continue;
+            }
+            let maybe_snapshot_db =
+                self.snapshot_manager.get_snapshot_by_epoch_id(
+                    snapshot_epoch_id,
+                    /* try_open = */ false,
+                );
+            let result = match maybe_snapshot_db {
+                Ok(Some(snapshot_db)) => Self::check_snapshot_mpt(
+                    &snapshot_db,
+                    &snapshot_info.merkle_root,
+                ),
+                Ok(None) => Ok(Some("snapshot db is missing".into())),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(None) => {}
+                Ok(Some(reason)) => {
+                    invalid_snapshots.push((*snapshot_epoch_id, reason))
+                }
+                Err(e) => invalid_snapshots.push((
+                    *snapshot_epoch_id,
+                    format!("failed to load snapshot mpt: {}", e),
+                )),
+            }
+        }
+        if repair {
+            for (snapshot_epoch_id, _) in &invalid_snapshots {
+                if let Err(e) = self
+                    .snapshot_manager
+                    .get_snapshot_db_manager()
+                    .destroy_snapshot(snapshot_epoch_id)
+                {
+                    warn!(
+                        "Failed to destroy snapshot {:?}: {}",
+                        snapshot_epoch_id, e
+                    );
+                }
+            }
+        }
+        invalid_snapshots
+    }
+
+    fn check_snapshot_mpt(
+        snapshot_db: &SnapshotDb, merkle_root: &MerkleHash,
+    ) -> Result<Option<String>> {
+        let mut mpt = snapshot_db.open_snapshot_mpt_as_owned()?;
+        if mpt.get_merkle_root() != *merkle_root {
+            return Ok(Some(format!(
+                "merkle root mismatch, expected {:?}, got {:?}",
+                merkle_root,
+                mpt.get_merkle_root()
+            )));
+        }
+
+        let root_path = CompressedPathRaw::default();
+        let mut invalid_nodes = 0;
+        if let Some(root_node) = mpt.load_node(&root_path)? {
+            if !root_node.is_valid(&root_path) {
+                invalid_nodes += 1;
+            }
+        }
+        let mut nodes_iter =
+            mpt.iterate_subtree_trie_nodes_without_root(&root_path)?;
+        while let Some((path, node)) = nodes_iter.next()? {
+            if !node.is_valid(&path) {
+                invalid_nodes += 1;
+            }
+        }
+
+        if invalid_nodes > 0 {
+            Ok(Some(format!("{} invalid mpt nodes", invalid_nodes)))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn get_delta_mpt(
         &self, snapshot_epoch_id: &EpochId,
     ) -> Result<Arc<DeltaMpt>> {
@@ -1426,6 +1529,21 @@ fn extra_snapshots_to_keep_predicate(
     find_epoch_nearest_multiple_of: &mut bool,
 ) -> bool
 {
+    // In archive mode a pair of adjacent snapshots is kept in every interval,
+    // so that together with their delta MPTs the states of the epochs after
+    // them remain readable. The states of the other historical epochs are
+    // reconstructed from them by re-execution.
+    if storage_conf.archive_historical_state {
+        let snapshot_epoch_count =
+            storage_conf.consensus_param.snapshot_epoch_count as u64;
+        let interval = storage_conf.archive_state_epoch_interval();
+        if height % interval == 0
+            || (height >= snapshot_epoch_count
+                && (height - snapshot_epoch_count) % interval == 0)
+        {
+            return true;
+        }
+    }
     for conf in &storage_conf.provide_more_snapshot_for_sync {
         match conf {
             ProvideExtraSnapshotSyncConfig::StableCheckpoint => {
@@ -1522,6 +1640,7 @@ use crate::{
             node_ref_map::DeltaMptId,
         },
         errors::*,
+        merkle_patricia_trie::CompressedPathRaw,
         state_manager::{DeltaDbManager, SnapshotDb, SnapshotDbManager},
         storage_db::{
             kvdb_sqlite::{
@@ -1534,8 +1653,9 @@ use crate::{
     },
     snapshot_manager::SnapshotManagerTrait,
     storage_db::{
-        DeltaDbManagerTrait, KeyValueDbIterableTrait, SnapshotDbManagerTrait,
-        SnapshotInfo, SnapshotKeptToProvideSyncStatus,
+        DeltaDbManagerTrait, KeyValueDbIterableTrait, OpenSnapshotMptTrait,
+        SnapshotDbManagerTrait, SnapshotInfo, SnapshotKeptToProvideSyncStatus,
+        SnapshotMptTraitRead, SnapshotMptTraitReadAndIterate,
     },
     storage_dir,
     utils::{arc_ext::*, guarded_value::GuardedValue},
@@ -1562,3 +1682,43 @@ use std::{
     },
     thread::{self, JoinHandle},
 };
+
+#[cfg(test)]
+mod tests {
+    use super::extra_snapshots_to_keep_predicate;
+    use crate::StorageConfiguration;
+
+    fn snapshots_to_keep(storage_conf: &StorageConfiguration) -> Vec<u64> {
+        (1..=8)
+            .map(|i| i * 2000)
+            .filter(|height| {
+                extra_snapshots_to_keep_predicate(
+                    storage_conf,
+                    0,     /* stable_checkpoint_height */
+                    50000, /* era_epoch_count */
+                    *height,
+                    &mut false,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_archive_snapshots_to_keep() {
+        let mut storage_conf =
+            StorageConfiguration::new_default("./test_archive", 2000);
+        storage_conf.provide_more_snapshot_for_sync = vec![];
+        assert!(snapshots_to_keep(&storage_conf).is_empty());
+
+        // A pair of adjacent snapshots is kept in every 3 snapshots.
+        storage_conf.archive_historical_state = true;
+        storage_conf.archive_state_snapshot_interval = 3;
+        assert_eq!(
+            snapshots_to_keep(&storage_conf),
+            vec![2000, 6000, 8000, 12000, 14000]
+        );
+
+        storage_conf.archive_state_snapshot_interval = 1;
+        assert_eq!(snapshots_to_keep(&storage_conf).len(), 8);
+    }
+}
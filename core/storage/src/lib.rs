@@ -100,9 +100,36 @@ impl ProvideExtraSnapshotSyncConfig {
     }
 }
 
+/// The db engine of snapshots. Snapshots aren't converted between the
+/// engines, so the type can't be changed with existing snapshots.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SnapshotDbType {
+    Sqlite,
+    Rocksdb,
+}
+
+impl FromStr for SnapshotDbType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "sqlite" => Ok(Self::Sqlite),
+            "rocksdb" => Ok(Self::Rocksdb),
+            _ => Err(format!("{} is not a valid SnapshotDbType", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StorageConfiguration {
     pub additional_maintained_snapshot_count: u32,
+    /// Serve the state of any pivot epoch on archive nodes, see
+    /// `archive_state_snapshot_interval`.
+    pub archive_historical_state: bool,
+    /// Keep the old pivot snapshots at every this many snapshots, along
+    /// with the snapshots following them, as the bases to reconstruct
+    /// historical states.
+    pub archive_state_snapshot_interval: u32,
     pub consensus_param: ConsensusParam,
     pub debug_snapshot_checker_threads: u16,
     pub delta_mpts_cache_recent_lfu_factor: f64,
@@ -117,6 +144,7 @@ pub struct StorageConfiguration {
     pub path_snapshot_info_db: PathBuf,
     pub provide_more_snapshot_for_sync: Vec<ProvideExtraSnapshotSyncConfig>,
     pub max_open_mpt_count: u32,
+    pub snapshot_db_type: SnapshotDbType,
 }
 
 impl StorageConfiguration {
@@ -126,6 +154,9 @@ impl StorageConfiguration {
         let conflux_data_path = Path::new(conflux_data_dir);
         StorageConfiguration {
             additional_maintained_snapshot_count: 0,
+            archive_historical_state: false,
+            archive_state_snapshot_interval:
+                defaults::DEFAULT_ARCHIVE_STATE_SNAPSHOT_INTERVAL,
             consensus_param: ConsensusParam {
                 snapshot_epoch_count,
             },
@@ -152,8 +183,16 @@ impl StorageConfiguration {
                 ProvideExtraSnapshotSyncConfig::StableCheckpoint,
             ],
             max_open_mpt_count: defaults::DEFAULT_MAX_OPEN_MPT,
+            snapshot_db_type: SnapshotDbType::Sqlite,
         }
     }
+
+    /// The number of epochs between the snapshots kept for the
+    /// reconstruction of historical states.
+    pub fn archive_state_epoch_interval(&self) -> u64 {
+        self.archive_state_snapshot_interval.max(1) as u64
+            * self.consensus_param.snapshot_epoch_count as u64
+    }
 }
 
 pub use self::{
@@ -172,6 +211,8 @@ pub use self::{
         storage_db::{
             kvdb_rocksdb::KvdbRocksdb,
             kvdb_sqlite::{KvdbSqlite, KvdbSqliteStatements},
+            snapshot_db_dispatch::SnapshotDbManagerDispatch,
+            snapshot_db_manager_rocksdb::SnapshotDbManagerRocksdb,
             snapshot_db_manager_sqlite::SnapshotDbManagerSqlite,
             sqlite::SqliteConnection,
         },
@@ -190,4 +231,7 @@ pub use self::{
 #[cfg(any(test, feature = "testonly_code"))]
 pub use self::tests::new_state_manager_for_unit_test as new_storage_manager_for_testing;
 use cfx_internal_common::StateRootWithAuxInfo;
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
@@ -5,6 +5,27 @@
 mod snapshot;
 pub use snapshot::FakeSnapshotMptDb;
 
+/// Generates a `sqlite` and a `rocksdb` test for each listed function, which
+/// takes the `SnapshotDbType` to run against.
+#[cfg(test)]
+macro_rules! snapshot_db_type_tests {
+    ($($test:ident),* $(,)?) => {
+        mod sqlite {
+            $(
+                #[test]
+                fn $test() { super::$test(crate::SnapshotDbType::Sqlite) }
+            )*
+        }
+
+        mod rocksdb {
+            $(
+                #[test]
+                fn $test() { super::$test(crate::SnapshotDbType::Rocksdb) }
+            )*
+        }
+    };
+}
+
 #[cfg(test)]
 mod proofs;
 #[cfg(test)]
@@ -15,6 +36,11 @@ mod state;
 #[cfg(test)]
 const TEST_NUMBER_OF_KEYS: usize = 100000;
 
+/// Snapshot db backends which the storage tests run against.
+#[cfg(test)]
+const TEST_SNAPSHOT_DB_TYPES: [SnapshotDbType; 2] =
+    [SnapshotDbType::Sqlite, SnapshotDbType::Rocksdb];
+
 #[derive(Default)]
 pub struct FakeDbForStateTest {}
 
@@ -63,7 +89,9 @@ pub struct FakeStateManager {
 impl FakeStateManager {
     fn new(
         conflux_data_dir: String, snapshot_epoch_count: u32,
-    ) -> Result<Self> {
+        snapshot_db_type: SnapshotDbType,
+    ) -> Result<Self>
+    {
         // Use a random directory to prevent conflicts in concurrently running
         // tests.
         let unit_test_data_dir =
@@ -77,6 +105,7 @@ impl FakeStateManager {
         storage_conf.delta_mpts_cache_start_size = 1_000_000;
         storage_conf.delta_mpts_node_map_vec_size = 20_000_000;
         storage_conf.delta_mpts_slab_idle_size = 200_000;
+        storage_conf.snapshot_db_type = snapshot_db_type;
 
         Ok(FakeStateManager {
             data_dir: unit_test_data_dir,
@@ -114,6 +143,16 @@ impl DerefMut for FakeStateManager {
 #[cfg(any(test, feature = "testonly_code"))]
 pub fn new_state_manager_for_unit_test_with_snapshot_epoch_count(
     snapshot_epoch_count: u32,
+) -> FakeStateManager {
+    new_state_manager_for_unit_test_with_snapshot_db_type(
+        snapshot_epoch_count,
+        SnapshotDbType::Sqlite,
+    )
+}
+
+#[cfg(any(test, feature = "testonly_code"))]
+pub fn new_state_manager_for_unit_test_with_snapshot_db_type(
+    snapshot_epoch_count: u32, snapshot_db_type: SnapshotDbType,
 ) -> FakeStateManager {
     const WITH_LOGGER: bool = false;
     if WITH_LOGGER {
@@ -141,6 +180,7 @@ pub fn new_state_manager_for_unit_test_with_snapshot_epoch_count(
     FakeStateManager::new(
         "./conflux_unit_test_data_dir".to_string(),
         snapshot_epoch_count,
+        snapshot_db_type,
     )
     .unwrap()
 }
@@ -251,7 +291,9 @@ pub fn print_mpt_key(key: &[u8]) {
 }
 
 #[cfg(any(test, feature = "testonly_code"))]
-use crate::{impls::state_manager::StateManager, StorageConfiguration};
+use crate::{
+    impls::state_manager::StateManager, SnapshotDbType, StorageConfiguration,
+};
 use crate::{
     impls::{
         errors::*,
@@ -28,13 +28,13 @@ fn portion<T>(v: &Vec<T>, k: usize, n: usize) -> &[T] {
 // 1/7 of keys are in                  X           X
 // 1/7 of keys are in                              X
 fn generate_random_state(
-    rng: &mut ChaChaRng,
+    rng: &mut ChaChaRng, snapshot_db_type: SnapshotDbType,
 ) -> (FakeStateManager, State, DeltaMptKeyPadding, Vec<Vec<u8>>) {
     let snapshot_epoch_count = 1;
-    let state_manager =
-        new_state_manager_for_unit_test_with_snapshot_epoch_count(
-            snapshot_epoch_count,
-        );
+    let state_manager = new_state_manager_for_unit_test_with_snapshot_db_type(
+        snapshot_epoch_count,
+        snapshot_db_type,
+    );
 
     // TODO: test other key types as well, not just AccountKey
 
@@ -310,12 +310,22 @@ fn get_invalid_delta_padding(
     new_padding
 }
 
-#[test]
-fn test_valid_state_proof_for_existing_key() {
+snapshot_db_type_tests!(
+    test_valid_state_proof_for_existing_key,
+    test_valid_state_proof_for_nonexistent_key,
+    test_invalid_state_proof,
+    test_valid_node_merkle_proof_for_existing_key,
+    test_valid_node_merkle_proof_for_nonexistent_key,
+    test_invalid_node_merkle_proof,
+    test_recording_storage,
+);
+
+fn test_valid_state_proof_for_existing_key(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
 
     // note: do not drop state_manager (_mgr)
-    let (_mgr, state, padding, keys) = generate_random_state(&mut rng);
+    let (_mgr, state, padding, keys) =
+        generate_random_state(&mut rng, snapshot_db_type);
     let root = state.get_state_root().unwrap().state_root;
 
     for key in keys {
@@ -340,12 +350,14 @@ fn test_valid_state_proof_for_existing_key() {
     }
 }
 
-#[test]
-fn test_valid_state_proof_for_nonexistent_key() {
+fn test_valid_state_proof_for_nonexistent_key(
+    snapshot_db_type: SnapshotDbType,
+) {
     let mut rng = get_rng_for_test();
 
     // note: do not drop state_manager (_mgr)
-    let (_mgr, state, padding, keys) = generate_random_state(&mut rng);
+    let (_mgr, state, padding, keys) =
+        generate_random_state(&mut rng, snapshot_db_type);
     let root = state.get_state_root().unwrap().state_root;
     let keys = generate_nonexistent_keys(&mut rng, &keys);
 
@@ -370,12 +382,12 @@ fn test_valid_state_proof_for_nonexistent_key() {
     }
 }
 
-#[test]
-fn test_invalid_state_proof() {
+fn test_invalid_state_proof(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
 
     // note: do not drop state_manager (_mgr)
-    let (_mgr, state, padding, keys) = generate_random_state(&mut rng);
+    let (_mgr, state, padding, keys) =
+        generate_random_state(&mut rng, snapshot_db_type);
     let root = state.get_state_root().unwrap().state_root;
 
     for key in keys {
@@ -442,12 +454,14 @@ fn test_invalid_state_proof() {
     }
 }
 
-#[test]
-fn test_valid_node_merkle_proof_for_existing_key() {
+fn test_valid_node_merkle_proof_for_existing_key(
+    snapshot_db_type: SnapshotDbType,
+) {
     let mut rng = get_rng_for_test();
 
     // note: do not drop state_manager (_mgr)
-    let (_mgr, state, padding, keys) = generate_random_state(&mut rng);
+    let (_mgr, state, padding, keys) =
+        generate_random_state(&mut rng, snapshot_db_type);
     let root = state.get_state_root().unwrap().state_root;
 
     for key in keys {
@@ -478,12 +492,14 @@ fn test_valid_node_merkle_proof_for_existing_key() {
     }
 }
 
-#[test]
-fn test_valid_node_merkle_proof_for_nonexistent_key() {
+fn test_valid_node_merkle_proof_for_nonexistent_key(
+    snapshot_db_type: SnapshotDbType,
+) {
     let mut rng = get_rng_for_test();
 
     // note: do not drop state_manager (_mgr)
-    let (_mgr, state, padding, keys) = generate_random_state(&mut rng);
+    let (_mgr, state, padding, keys) =
+        generate_random_state(&mut rng, snapshot_db_type);
     let root = state.get_state_root().unwrap().state_root;
     let keys = generate_nonexistent_keys(&mut rng, &keys);
 
@@ -513,12 +529,12 @@ fn test_valid_node_merkle_proof_for_nonexistent_key() {
     }
 }
 
-#[test]
-fn test_invalid_node_merkle_proof() {
+fn test_invalid_node_merkle_proof(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
 
     // note: do not drop state_manager (_mgr)
-    let (_mgr, state, padding, keys) = generate_random_state(&mut rng);
+    let (_mgr, state, padding, keys) =
+        generate_random_state(&mut rng, snapshot_db_type);
     let root = state.get_state_root().unwrap().state_root;
 
     for key in keys {
@@ -592,12 +608,12 @@ fn test_invalid_node_merkle_proof() {
     }
 }
 
-#[test]
-fn test_recording_storage() {
+fn test_recording_storage(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
 
     // note: do not drop state_manager (_mgr)
-    let (_mgr, state, padding, keys) = generate_random_state(&mut rng);
+    let (_mgr, state, padding, keys) =
+        generate_random_state(&mut rng, snapshot_db_type);
 
     let state = RecordingStorage::new(state);
     let root = state.get_state_root().unwrap().state_root;
@@ -687,10 +703,10 @@ use crate::{
     state_manager::*,
     tests::{
         generate_account_keys, get_rng_for_test,
-        new_state_manager_for_unit_test_with_snapshot_epoch_count,
+        new_state_manager_for_unit_test_with_snapshot_db_type,
         FakeStateManager, TEST_NUMBER_OF_KEYS,
     },
-    RecordingStorage,
+    RecordingStorage, SnapshotDbType,
 };
 use cfx_types::H256;
 use primitives::{
@@ -2,9 +2,8 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-#[test]
-fn test_empty_genesis_block() {
-    let state_manager = new_state_manager_for_unit_test();
+fn test_empty_genesis_block(snapshot_db_type: SnapshotDbType) {
+    let state_manager = new_state_manager(snapshot_db_type);
 
     let mut genesis_epoch_id = H256::default();
     genesis_epoch_id.as_bytes_mut()[0] = 1;
@@ -23,10 +22,9 @@ fn test_empty_genesis_block() {
         .unwrap();
 }
 
-#[test]
-fn test_set_get() {
+fn test_set_get(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
-    let state_manager = new_state_manager_for_unit_test();
+    let state_manager = new_state_manager(snapshot_db_type);
     let mut state = state_manager.get_state_for_genesis_write();
     let mut keys: Vec<Vec<u8>> = generate_keys(TEST_NUMBER_OF_KEYS)
         .iter()
@@ -62,9 +60,8 @@ fn test_set_get() {
     state.commit(epoch_id).unwrap();
 }
 
-#[test]
-fn test_get_set_at_second_commit() {
-    let state_manager = new_state_manager_for_unit_test();
+fn test_get_set_at_second_commit(snapshot_db_type: SnapshotDbType) {
+    let state_manager = new_state_manager(snapshot_db_type);
     let keys: Vec<Vec<u8>> = generate_keys(TEST_NUMBER_OF_KEYS);
     let set_size = TEST_NUMBER_OF_KEYS / 10;
     let (keys_0, keys_1_new, keys_remain, keys_1_overwritten) = (
@@ -161,9 +158,8 @@ fn test_get_set_at_second_commit() {
     state_1.commit(epoch_id_1).unwrap();
 }
 
-#[test]
-fn test_snapshot_random_read_performance() {
-    let state_manager = new_state_manager_for_unit_test();
+fn test_snapshot_random_read_performance(snapshot_db_type: SnapshotDbType) {
+    let state_manager = new_state_manager(snapshot_db_type);
     let keys: Vec<Vec<u8>> = generate_keys(TEST_NUMBER_OF_KEYS);
 
     const EPOCHS: u8 = 20;
@@ -256,6 +252,138 @@ fn test_snapshot_random_read_performance() {
     );
 }
 
+snapshot_db_type_tests!(
+    test_empty_genesis_block,
+    test_set_get,
+    test_get_set_at_second_commit,
+    test_snapshot_random_read_performance,
+    test_set_delete,
+    test_set_delete_all,
+    test_set_order,
+    test_set_order_concurrent,
+);
+
+#[test]
+fn test_snapshot_merge_copy_on_write() {
+    const EPOCHS: u8 = 8;
+    let snapshot_epoch_count = 2;
+    let keys: Vec<Vec<u8>> = generate_keys(TEST_NUMBER_OF_KEYS / 100);
+    let epoch_value = |epoch: u8, key: &[u8]| -> Box<[u8]> {
+        [key, &[epoch]].concat().into_boxed_slice()
+    };
+
+    let mut state_roots_by_db_type = vec![];
+    for snapshot_db_type in TEST_SNAPSHOT_DB_TYPES.iter().cloned() {
+        let state_manager =
+            new_state_manager_for_unit_test_with_snapshot_db_type(
+                snapshot_epoch_count,
+                snapshot_db_type,
+            );
+
+        // Overwrite every key in each epoch, so that each snapshot has
+        // different values from the snapshot it's merged from.
+        let mut epoch_ids = Vec::with_capacity(EPOCHS as usize);
+        let mut state_roots = Vec::with_capacity(EPOCHS as usize);
+        let mut state = state_manager.get_state_for_genesis_write();
+        for epoch in 0..EPOCHS {
+            for key in &keys {
+                state
+                    .set(
+                        StorageKey::AccountKey(key).with_native_space(),
+                        epoch_value(epoch, key),
+                    )
+                    .expect("Failed to set key.");
+            }
+            let mut epoch_id = H256::default();
+            epoch_id.as_bytes_mut()[0] = epoch + 1;
+            let state_root = state.compute_state_root().unwrap();
+            state.commit(epoch_id).unwrap();
+            if epoch + 1 < EPOCHS {
+                state = state_manager
+                    .get_state_for_next_epoch(StateIndex::new_for_next_epoch(
+                        &epoch_id,
+                        &state_root,
+                        epoch as u64 + 1,
+                        snapshot_epoch_count,
+                    ))
+                    .unwrap()
+                    .unwrap();
+            }
+            epoch_ids.push(epoch_id);
+            state_roots.push(state_root);
+        }
+
+        // Check every snapshot after all merges, so that a snapshot merged
+        // from an older snapshot must have left the older one unchanged.
+        let storage_manager = state_manager.get_storage_manager();
+        let mut snapshot_epoch_ids = vec![];
+        for state_root in &state_roots {
+            let snapshot_epoch_id = &state_root.aux_info.snapshot_epoch_id;
+            if *snapshot_epoch_id == NULL_EPOCH
+                || snapshot_epoch_ids.contains(snapshot_epoch_id)
+            {
+                continue;
+            }
+            let snapshot_db = storage_manager
+                .wait_for_snapshot(
+                    snapshot_epoch_id,
+                    /* try_open = */ false,
+                )
+                .unwrap()
+                .unwrap()
+                .into()
+                .1;
+            let merkle_root = snapshot_db
+                .open_snapshot_mpt_shared()
+                .unwrap()
+                .get_merkle_root();
+            assert_eq!(merkle_root, state_root.state_root.snapshot_root);
+            snapshot_epoch_ids.push(snapshot_epoch_id.clone());
+        }
+        // At least one snapshot is merged from a non-empty snapshot.
+        assert!(snapshot_epoch_ids.len() >= 2);
+
+        for (epoch, (epoch_id, state_root)) in
+            epoch_ids.iter().zip(&state_roots).enumerate()
+        {
+            let state = state_manager
+                .get_state_no_commit(
+                    StateIndex::new_for_readonly(epoch_id, state_root),
+                    /* try_open = */ false,
+                )
+                .unwrap()
+                .unwrap();
+            for key in &keys {
+                let value = state
+                    .get(StorageKey::AccountKey(key).with_native_space())
+                    .expect("Failed to get key.")
+                    .expect("Failed to get key");
+                assert_eq!(value, epoch_value(epoch as u8, key));
+            }
+        }
+
+        state_roots_by_db_type.push(
+            state_roots
+                .into_iter()
+                .map(|state_root| state_root.state_root)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    // All snapshot db types compute the same state roots.
+    for state_roots in &state_roots_by_db_type[1..] {
+        assert_eq!(state_roots, &state_roots_by_db_type[0]);
+    }
+}
+
+fn new_state_manager(snapshot_db_type: SnapshotDbType) -> FakeStateManager {
+    let snapshot_epoch_count = 10;
+    new_state_manager_for_unit_test_with_snapshot_db_type(
+        snapshot_epoch_count,
+        snapshot_db_type,
+    )
+}
+
 fn simulate_transactions(
     epoch: u8, prev_state_root: &StateRootWithAuxInfo, keys: &[&[u8]],
     state_manager: &FakeStateManager, read_ms: &mut u32, update_ms: &mut u32,
@@ -405,10 +533,9 @@ fn simulate_transactions(
     state_root
 }
 
-#[test]
-fn test_set_delete() {
+fn test_set_delete(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
-    let state_manager = new_state_manager_for_unit_test();
+    let state_manager = new_state_manager(snapshot_db_type);
 
     let mut state = state_manager.get_state_for_genesis_write();
 
@@ -468,10 +595,9 @@ fn test_set_delete() {
     state.commit(epoch_id).unwrap();
 }
 
-#[test]
-fn test_set_delete_all() {
+fn test_set_delete_all(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
-    let state_manager = new_state_manager_for_unit_test();
+    let state_manager = new_state_manager(snapshot_db_type);
 
     let mut state = state_manager.get_state_for_genesis_write();
     let empty_state_root = state.compute_state_root().unwrap();
@@ -562,10 +688,9 @@ fn test_set_delete_all() {
     assert_eq!(state_root, empty_state_root);
 }
 
-#[test]
-fn test_set_order() {
+fn test_set_order(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
-    let state_manager = new_state_manager_for_unit_test();
+    let state_manager = new_state_manager(snapshot_db_type);
     let keys: Vec<Vec<u8>> = generate_keys(500000)
         .iter()
         .filter(|_| rng.gen_bool(0.5))
@@ -627,10 +752,9 @@ fn test_set_order() {
     assert_eq!(merkle_1, merkle_2);
 }
 
-#[test]
-fn test_set_order_concurrent() {
+fn test_set_order_concurrent(snapshot_db_type: SnapshotDbType) {
     let mut rng = get_rng_for_test();
-    let state_manager = new_state_manager_for_unit_test();
+    let state_manager = new_state_manager(snapshot_db_type);
     let keys = Arc::new(
         generate_keys(TEST_NUMBER_OF_KEYS / 10)
             .iter()
@@ -740,17 +864,19 @@ fn test_set_order_concurrent() {
 use crate::{
     state::*,
     state_manager::*,
+    storage_db::{OpenSnapshotMptTrait, SnapshotMptTraitRead},
     tests::{
-        generate_keys, get_rng_for_test, new_state_manager_for_unit_test,
-        FakeStateManager, TEST_NUMBER_OF_KEYS,
+        generate_keys, get_rng_for_test,
+        new_state_manager_for_unit_test_with_snapshot_db_type,
+        FakeStateManager, TEST_NUMBER_OF_KEYS, TEST_SNAPSHOT_DB_TYPES,
     },
     utils::access_mode,
-    StateRootWithAuxInfo,
+    SnapshotDbType, StateRootWithAuxInfo,
 };
 use cfx_types::{
     address_util::AddressUtil, Address, AddressSpaceUtil, H256, U256,
 };
-use primitives::{Account, StorageKey, StorageKeyWithSpace};
+use primitives::{Account, StorageKey, StorageKeyWithSpace, NULL_EPOCH};
 use rand::{
     distributions::{Distribution, Uniform},
     seq::SliceRandom,
@@ -22,8 +22,8 @@ use std::{cmp, collections::HashMap, error, fs, io, mem, path::Path, result};
 
 use parking_lot::{Mutex, MutexGuard, RwLock};
 use rocksdb::{
-    BlockBasedOptions, CFHandle, ColumnFamilyOptions, DBOptions, ReadOptions,
-    Writable, WriteBatch, WriteOptions, DB,
+    BlockBasedOptions, CFHandle, Checkpointer, ColumnFamilyOptions, DBOptions,
+    ReadOptions, SeekKey, Writable, WriteBatch, WriteOptions, DB,
 };
 
 use fs_swap::{swap, swap_nonatomic};
@@ -601,6 +601,93 @@ impl Database {
         }
     }
 
+    /// Get at most `limit` key-value pairs of a column in ascending key order,
+    /// starting from `start` and stopping before `end`. Buffered changes are
+    /// not visible, call `flush` before if necessary.
+    pub fn iter_range(
+        &self, col: u32, start: &[u8], start_excluded: bool,
+        end: Option<&[u8]>, limit: usize,
+    ) -> io::Result<Vec<KeyValuePair>>
+    {
+        match *self.db.read() {
+            Some(ref cfs) => {
+                let mut iter = cfs.db.iter_cf_opt(
+                    cfs.get_cf(col as usize),
+                    ReadOptions::default(),
+                );
+                iter.seek(SeekKey::Key(start)).map_err(other_io_err)?;
+                let mut pairs = Vec::new();
+                while pairs.len() < limit
+                    && iter.valid().map_err(other_io_err)?
+                {
+                    let key = iter.key();
+                    if end.map_or(false, |end| key >= end) {
+                        break;
+                    }
+                    if !(start_excluded && key == start) {
+                        pairs.push((key.into(), iter.value().into()));
+                    }
+                    iter.next().map_err(other_io_err)?;
+                }
+                Ok(pairs)
+            }
+            None => Err(other_io_err("Database is closed")),
+        }
+    }
+
+    /// Get at most `limit` key-value pairs of a column in descending key
+    /// order, starting from the last key before `end` and stopping before
+    /// `start`. Buffered changes are not visible, call `flush` before if
+    /// necessary.
+    pub fn iter_range_rev(
+        &self, col: u32, start: &[u8], end: &[u8], limit: usize,
+    ) -> io::Result<Vec<KeyValuePair>> {
+        match *self.db.read() {
+            Some(ref cfs) => {
+                let mut iter = cfs.db.iter_cf_opt(
+                    cfs.get_cf(col as usize),
+                    ReadOptions::default(),
+                );
+                iter.seek_for_prev(SeekKey::Key(end))
+                    .map_err(other_io_err)?;
+                let mut pairs = Vec::new();
+                while pairs.len() < limit
+                    && iter.valid().map_err(other_io_err)?
+                {
+                    let key = iter.key();
+                    if key < start {
+                        break;
+                    }
+                    if key != end {
+                        pairs.push((key.into(), iter.value().into()));
+                    }
+                    iter.prev().map_err(other_io_err)?;
+                }
+                Ok(pairs)
+            }
+            None => Err(other_io_err("Database is closed")),
+        }
+    }
+
+    /// Create an openable copy of the database at `path`, which must not
+    /// exist. Buffered changes are flushed first. SST files are hard-linked
+    /// when `path` is on the same file system.
+    pub fn create_checkpoint(&self, path: &Path) -> io::Result<()> {
+        self.flush()?;
+        match *self.db.read() {
+            Some(ref cfs) => {
+                let mut checkpointer =
+                    Checkpointer::new(&cfs.db).map_err(other_io_err)?;
+                // Always flush the memtables, so that the checkpoint doesn't
+                // depend on the WAL.
+                checkpointer
+                    .create_at(path, None, /* log_size_for_flush = */ 0)
+                    .map_err(other_io_err)
+            }
+            None => Err(other_io_err("Database is closed")),
+        }
+    }
+
     /// Close the database
     fn close(&self) {
         *self.db.write() = None;
@@ -784,6 +871,39 @@ mod tests {
         //        assert_eq!(&*contents[1].0, key2.as_bytes());
         //        assert_eq!(&*contents[1].1, b"dog");
 
+        let contents = db.iter_range(0, &[], false, None, 10).unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(&*contents[0].0, key1.as_bytes());
+        assert_eq!(&*contents[0].1, b"cat");
+        assert_eq!(&*contents[1].0, key2.as_bytes());
+        assert_eq!(&*contents[1].1, b"dog");
+        let contents =
+            db.iter_range(0, key1.as_bytes(), true, None, 10).unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(&*contents[0].0, key2.as_bytes());
+        let contents = db
+            .iter_range(0, &[], false, Some(key2.as_bytes()), 10)
+            .unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(&*contents[0].0, key1.as_bytes());
+        assert_eq!(db.iter_range(0, &[], false, None, 1).unwrap().len(), 1);
+        let contents = db.iter_range_rev(0, &[], &[0xff; 33], 10).unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(&*contents[0].0, key2.as_bytes());
+        assert_eq!(&*contents[1].0, key1.as_bytes());
+        let contents =
+            db.iter_range_rev(0, &[], key2.as_bytes(), 10).unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(&*contents[0].0, key1.as_bytes());
+        let contents =
+            db.iter_range_rev(0, key2.as_bytes(), &[0xff; 33], 10).unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(&*contents[0].0, key2.as_bytes());
+        assert_eq!(
+            db.iter_range_rev(0, &[], &[0xff; 33], 1).unwrap().len(),
+            1
+        );
+
         let mut batch = db.transaction();
         batch.delete(0, key1.as_bytes());
         db.write(batch).unwrap();
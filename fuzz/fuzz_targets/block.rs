@@ -0,0 +1,43 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+#![no_main]
+
+use arbitrary::Unstructured;
+use conflux_fuzz::{arbitrary_block, check_round_trip};
+use libfuzzer_sys::fuzz_target;
+use primitives::Block;
+use rlp::Rlp;
+
+fuzz_target!(|data: &[u8]| {
+    let rlp = Rlp::new(data);
+    if let Ok(block) = Block::decode_with_tx_public(&rlp) {
+        let encoded = block.encode_with_tx_public();
+        let decoded = Block::decode_with_tx_public(&Rlp::new(&encoded))
+            .expect("decode(encode(x)) fails");
+        assert_eq!(decoded.encode_with_tx_public(), encoded);
+    }
+    if let Ok(block) = rlp.as_val::<Block>() {
+        check_round_trip(&block);
+    }
+
+    if let Ok(block) = arbitrary_block(&mut Unstructured::new(data)) {
+        let encoded = block.encode_with_tx_public();
+        let decoded = Block::decode_with_tx_public(&Rlp::new(&encoded))
+            .expect("decode(encode(x)) fails");
+        assert_eq!(decoded.block_header, block.block_header);
+        assert_eq!(decoded.transactions.len(), block.transactions.len());
+        for (decoded, tx) in
+            decoded.transactions.iter().zip(&block.transactions)
+        {
+            assert_eq!(
+                decoded.transaction.transaction,
+                tx.transaction.transaction
+            );
+            assert_eq!(decoded.sender, tx.sender);
+            assert_eq!(decoded.public, tx.public);
+        }
+        check_round_trip(&block);
+    }
+});
@@ -0,0 +1,40 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+#![no_main]
+
+use arbitrary::Unstructured;
+use cfxkey::{KeyPair, Secret};
+use conflux_fuzz::arbitrary_discovery_packet;
+use libfuzzer_sys::fuzz_target;
+use network::{decode_discovery_packet, DiscoveryPacket};
+use rlp::Rlp;
+
+fuzz_target!(|data: &[u8]| {
+    // A mutated datagram rarely passes the hash and the signature checks, so
+    // the payload is also decoded as if it's signed, with the first byte as
+    // the packet id.
+    let _ = decode_discovery_packet(data);
+    if let Some((packet_id, payload)) = data.split_first() {
+        if let Ok(Some(packet)) =
+            DiscoveryPacket::decode(*packet_id, &Rlp::new(payload))
+        {
+            check_packet_round_trip(&packet);
+        }
+    }
+
+    if let Ok(packet) = arbitrary_discovery_packet(&mut Unstructured::new(data))
+    {
+        check_packet_round_trip(&packet);
+    }
+});
+
+fn check_packet_round_trip(packet: &DiscoveryPacket) {
+    let key = KeyPair::from_secret(Secret::from([1u8; 32])).unwrap();
+    let datagram = packet.assemble(key.secret()).unwrap();
+    let (node_id, decoded) = decode_discovery_packet(&datagram[1..])
+        .expect("decode(encode(x)) fails");
+    assert_eq!(node_id, *key.public());
+    assert_eq!(decoded.as_ref(), Some(packet));
+}
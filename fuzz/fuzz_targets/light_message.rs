@@ -0,0 +1,17 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+#![no_main]
+
+use arbitrary::Unstructured;
+use cfxcore::message::decode_msg;
+use conflux_fuzz::{check_arbitrary_light_message, decode_light_message};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((msg_id, rlp)) = decode_msg(data) {
+        decode_light_message(msg_id, &rlp);
+    }
+    let _ = check_arbitrary_light_message(&mut Unstructured::new(data));
+});
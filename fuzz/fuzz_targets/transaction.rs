@@ -0,0 +1,29 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+#![no_main]
+
+use arbitrary::Unstructured;
+use conflux_fuzz::{arbitrary_transaction, check_round_trip};
+use keccak_hash::keccak;
+use libfuzzer_sys::fuzz_target;
+use primitives::TransactionWithSignature;
+use rlp::Rlp;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(tx) = Rlp::new(data).as_val::<TransactionWithSignature>() {
+        assert_eq!(tx.hash(), keccak(Rlp::new(data).as_raw()));
+        check_round_trip(&tx);
+        // The sender is recovered from the untrusted signature.
+        let _ = tx.recover_public();
+    }
+
+    if let Ok(tx) = arbitrary_transaction(&mut Unstructured::new(data)) {
+        let encoded = rlp::encode(&tx);
+        let decoded: TransactionWithSignature = rlp::decode(&encoded).unwrap();
+        assert_eq!(decoded.transaction, tx.transaction);
+        assert_eq!(decoded.hash(), tx.hash());
+        assert_eq!(decoded.rlp_size(), encoded.len());
+    }
+});
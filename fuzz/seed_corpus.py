@@ -0,0 +1,125 @@
+#!/usr/bin/env python3
+"""Write well-formed inputs for each fuzz target into fuzz/corpus/<target>/.
+
+Usage: python3 fuzz/seed_corpus.py
+"""
+
+import os
+
+CORPUS = os.path.join(os.path.dirname(os.path.abspath(__file__)), "corpus")
+
+
+def encode_length(length, offset):
+    if length < 56:
+        return bytes([offset + length])
+    length_bytes = length.to_bytes((length.bit_length() + 7) // 8, "big")
+    return bytes([offset + 55 + len(length_bytes)]) + length_bytes
+
+
+def rlp(item):
+    if isinstance(item, int):
+        item = item.to_bytes((item.bit_length() + 7) // 8, "big")
+    if isinstance(item, bytes):
+        if len(item) == 1 and item[0] < 0x80:
+            return item
+        return encode_length(len(item), 0x80) + item
+    payload = b"".join(rlp(x) for x in item)
+    return encode_length(len(payload), 0xc0) + payload
+
+
+def h256(n):
+    return bytes([n]) * 32
+
+
+def address(n):
+    return bytes([n]) * 20
+
+
+def msg(msg_id, body):
+    # The message id is appended to the RLP in leb128.
+    return rlp(body) + bytes([msg_id])
+
+
+def native_tx(nonce, action, data=b""):
+    unsigned = [nonce, 1, 21000, action, 10**18, 0, 100, 1, data]
+    return [unsigned, 1, h256(2), h256(3)]
+
+
+def eth_tx(nonce, action, data=b""):
+    # legacy v = 35 + chain_id * 2 + standard v, with chain id 1.
+    return [nonce, 1, 21000, action, 10**18, data, 38, h256(2), h256(3)]
+
+
+def header(parent, height, referees=(), custom=()):
+    fields = [
+        parent, height, 1600000000, address(0x10), h256(4), h256(5),
+        h256(6), h256(7), 0, 30000, 0, 30000000, list(referees), 1,
+    ]
+    return fields + list(custom)
+
+
+def block(txs, with_public):
+    if with_public:
+        txs = [[tx, address(0x11), [b"\x04" * 64]] for tx in txs]
+    return [header(h256(8), 1, [h256(9)]), txs]
+
+
+def endpoint(ip):
+    return [ip, 32323, 32323]
+
+
+SEEDS = {
+    "sync_message": {
+        "get_block_headers": msg(0x05, [1, [h256(1), h256(2)]]),
+        "get_blocks": msg(0x0c, [2, 1, [h256(1)]]),
+        "get_terminal_block_hashes": msg(0x0b, [3]),
+        "get_block_hashes_by_epoch": msg(0x17, [4, [1, 2, 3]]),
+        "new_block": msg(
+            0x09, block([native_tx(0, address(1))], False)
+        ),
+    },
+    "light_message": {
+        "get_state_roots": msg(0x01, [1, [1, 2]]),
+        "get_state_entries": msg(0x03, [2, [[1, b"key"]]]),
+        "get_block_headers": msg(0x07, [3, [h256(1)]]),
+        "get_txs": msg(0x0e, [4, [h256(1), h256(2)]]),
+    },
+    "transaction": {
+        "native_call": rlp(native_tx(0, address(1))),
+        "native_create": rlp(native_tx(1, b"", b"\x60\x00")),
+        "eth_call": rlp(eth_tx(0, address(1))),
+    },
+    "block": {
+        "empty": rlp(block([], True)),
+        "two_txs": rlp(
+            block([native_tx(0, address(1)), eth_tx(0, address(1))], True)
+        ),
+        "custom_field": rlp(
+            [header(h256(8), 2, custom=[b"\x01\x02"]), []]
+        ),
+    },
+    # The payload as it's signed: the packet id followed by the RLP.
+    "discovery_packet": {
+        "ping": bytes([1])
+        + rlp([1, endpoint(bytes([127, 0, 0, 1])),
+               endpoint(bytes([10, 0, 0, 1])), 1600000000]),
+        "pong": bytes([2])
+        + rlp([endpoint(bytes(15) + b"\x01"), h256(1), 1600000000]),
+        "find_node": bytes([3]) + rlp([[b"archive"], [b"true"], 1600000000]),
+        "neighbours": bytes([4])
+        + rlp([[[bytes([127, 0, 0, 1]), 32323, 32323, b"\x01" * 64]], 1, 0]),
+    },
+}
+
+
+def main():
+    for target, seeds in SEEDS.items():
+        directory = os.path.join(CORPUS, target)
+        os.makedirs(directory, exist_ok=True)
+        for name, data in seeds.items():
+            with open(os.path.join(directory, name), "wb") as f:
+                f.write(data)
+
+
+if __name__ == "__main__":
+    main()
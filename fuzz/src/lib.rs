@@ -0,0 +1,623 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Structure-aware generators and the properties shared by the fuzz targets.
+//!
+//! Every target decodes the input as a peer would send it, and checks that
+//! whatever is accepted is encoded canonically, i.e. `decode(encode(x)) == x`.
+//! The same input is then used to build a well-formed value, which has to
+//! survive `decode(encode(x))` unchanged. Not all the messages implement
+//! `PartialEq`, so the values are compared by their encodings.
+
+use arbitrary::{Result, Unstructured};
+use cfx_types::{Address, H256, H512, U256};
+use cfxcore::{
+    light_protocol::message as light,
+    message::{decode_msg, Message, MsgId},
+    sync::message as sync,
+};
+use network::{
+    node_table::{NodeEndpoint, NodeEntry, NodeId},
+    DiscoveryPacket, FindNodeMessage, NeighborsChunkMessage,
+};
+use primitives::{
+    transaction::{
+        Action, Eip155Transaction, NativeTransaction, Transaction,
+        TransactionWithSignatureSerializePart,
+    },
+    Block, BlockHeader, BlockHeaderBuilder, SignedTransaction,
+    TransactionWithSignature,
+};
+use rlp::{Decodable, Encodable, Rlp};
+use std::{
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    sync::Arc,
+};
+
+/// Check that `value` is the same after encoding and decoding it.
+pub fn check_round_trip<T: Encodable + Decodable>(value: &T) {
+    let encoded = rlp::encode(value);
+    let decoded: T = rlp::decode(&encoded).expect("decode(encode(x)) fails");
+    assert_eq!(rlp::encode(&decoded), encoded, "decode(encode(x)) != x");
+}
+
+/// Decode `rlp` as `T`, checking the round trip if it's accepted. Returns
+/// whether it's accepted.
+pub fn check_decode<T: Encodable + Decodable>(rlp: &Rlp) -> bool {
+    match rlp.as_val::<T>() {
+        Ok(value) => {
+            check_round_trip(&value);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Check the round trip of a message, and that its frame, i.e. the RLP
+/// followed by the message id, is decoded by `decode` as a peer would.
+pub fn check_message<T: Message + Encodable + Decodable>(
+    msg: &T, decode: fn(MsgId, &Rlp) -> bool,
+) {
+    check_round_trip(msg);
+    let frame = msg.encode();
+    let (msg_id, rlp) = decode_msg(&frame).expect("invalid message frame");
+    assert_eq!(msg_id, msg.msg_id());
+    assert!(
+        decode(msg_id, &rlp),
+        "message {} not decoded",
+        msg.msg_name()
+    );
+}
+
+/// Decode a sync protocol message by its id, like `handle_rlp_message`.
+/// Returns whether the message is accepted.
+pub fn decode_sync_message(msg_id: MsgId, rlp: &Rlp) -> bool {
+    match msg_id {
+        sync::msgid::STATUS_V2 => check_decode::<sync::StatusV2>(rlp),
+        sync::msgid::STATUS_V3 => check_decode::<sync::StatusV3>(rlp),
+        sync::msgid::HEARTBEAT => check_decode::<sync::Heartbeat>(rlp),
+        sync::msgid::NEW_BLOCK => check_decode::<sync::NewBlock>(rlp),
+        sync::msgid::NEW_BLOCK_HASHES => {
+            check_decode::<sync::NewBlockHashes>(rlp)
+        }
+        sync::msgid::GET_BLOCK_HEADERS => {
+            check_decode::<sync::GetBlockHeaders>(rlp)
+        }
+        sync::msgid::GET_BLOCK_HEADERS_RESPONSE => {
+            check_decode::<sync::GetBlockHeadersResponse>(rlp)
+        }
+        sync::msgid::GET_BLOCKS => check_decode::<sync::GetBlocks>(rlp),
+        sync::msgid::GET_BLOCKS_RESPONSE => {
+            check_decode::<sync::GetBlocksResponse>(rlp)
+        }
+        sync::msgid::GET_BLOCKS_WITH_PUBLIC_RESPONSE => {
+            check_decode::<sync::GetBlocksWithPublicResponse>(rlp)
+        }
+        sync::msgid::GET_TERMINAL_BLOCK_HASHES => {
+            check_decode::<sync::GetTerminalBlockHashes>(rlp)
+        }
+        sync::msgid::GET_TERMINAL_BLOCK_HASHES_RESPONSE => {
+            check_decode::<sync::GetTerminalBlockHashesResponse>(rlp)
+        }
+        sync::msgid::GET_CMPCT_BLOCKS => {
+            check_decode::<sync::GetCompactBlocks>(rlp)
+        }
+        sync::msgid::GET_CMPCT_BLOCKS_RESPONSE => {
+            check_decode::<sync::GetCompactBlocksResponse>(rlp)
+        }
+        sync::msgid::GET_BLOCK_TXN => check_decode::<sync::GetBlockTxn>(rlp),
+        sync::msgid::GET_BLOCK_TXN_RESPONSE => {
+            check_decode::<sync::GetBlockTxnResponse>(rlp)
+        }
+        sync::msgid::TRANSACTIONS => check_decode::<sync::Transactions>(rlp),
+        sync::msgid::DYNAMIC_CAPABILITY_CHANGE => {
+            check_decode::<sync::DynamicCapabilityChange>(rlp)
+        }
+        sync::msgid::TRANSACTION_DIGESTS => {
+            check_decode::<sync::TransactionDigests>(rlp)
+        }
+        sync::msgid::GET_TRANSACTIONS => {
+            check_decode::<sync::GetTransactions>(rlp)
+        }
+        sync::msgid::GET_TRANSACTIONS_FROM_TX_HASHES => {
+            check_decode::<sync::GetTransactionsFromTxHashes>(rlp)
+        }
+        sync::msgid::GET_TRANSACTIONS_RESPONSE => {
+            check_decode::<sync::GetTransactionsResponse>(rlp)
+        }
+        sync::msgid::GET_TRANSACTIONS_FROM_TX_HASHES_RESPONSE => {
+            check_decode::<sync::GetTransactionsFromTxHashesResponse>(rlp)
+        }
+        sync::msgid::GET_BLOCK_HASHES_BY_EPOCH => {
+            check_decode::<sync::GetBlockHashesByEpoch>(rlp)
+        }
+        sync::msgid::GET_BLOCK_HASHES_RESPONSE => {
+            check_decode::<sync::GetBlockHashesResponse>(rlp)
+        }
+        sync::msgid::GET_SNAPSHOT_MANIFEST => {
+            check_decode::<sync::SnapshotManifestRequest>(rlp)
+        }
+        sync::msgid::GET_SNAPSHOT_MANIFEST_RESPONSE => {
+            check_decode::<sync::SnapshotManifestResponse>(rlp)
+        }
+        sync::msgid::GET_SNAPSHOT_CHUNK => {
+            check_decode::<sync::SnapshotChunkRequest>(rlp)
+        }
+        sync::msgid::GET_SNAPSHOT_CHUNK_RESPONSE => {
+            check_decode::<sync::SnapshotChunkResponse>(rlp)
+        }
+        sync::msgid::STATE_SYNC_CANDIDATE_REQUEST => {
+            check_decode::<sync::StateSyncCandidateRequest>(rlp)
+        }
+        sync::msgid::STATE_SYNC_CANDIDATE_RESPONSE => {
+            check_decode::<sync::StateSyncCandidateResponse>(rlp)
+        }
+        sync::msgid::THROTTLED => check_decode::<sync::Throttled>(rlp),
+        _ => false,
+    }
+}
+
+/// Decode a light protocol message by its id, like the light node handler
+/// and the light protocol provider. Returns whether the message is accepted.
+pub fn decode_light_message(msg_id: MsgId, rlp: &Rlp) -> bool {
+    match msg_id {
+        light::msgid::STATUS_PING_DEPRECATED => {
+            check_decode::<light::StatusPingDeprecatedV1>(rlp)
+        }
+        light::msgid::STATUS_PONG_DEPRECATED => {
+            check_decode::<light::StatusPongDeprecatedV1>(rlp)
+        }
+        light::msgid::STATUS_PING_V2 => {
+            check_decode::<light::StatusPingV2>(rlp)
+        }
+        light::msgid::STATUS_PONG_V2 => {
+            check_decode::<light::StatusPongV2>(rlp)
+        }
+        light::msgid::GET_STATE_ROOTS => {
+            check_decode::<light::GetStateRoots>(rlp)
+        }
+        light::msgid::STATE_ROOTS => check_decode::<light::StateRoots>(rlp),
+        light::msgid::GET_STATE_ENTRIES => {
+            check_decode::<light::GetStateEntries>(rlp)
+        }
+        light::msgid::STATE_ENTRIES => check_decode::<light::StateEntries>(rlp),
+        light::msgid::GET_BLOCK_HASHES_BY_EPOCH => {
+            check_decode::<light::GetBlockHashesByEpoch>(rlp)
+        }
+        light::msgid::BLOCK_HASHES => check_decode::<light::BlockHashes>(rlp),
+        light::msgid::GET_BLOCK_HEADERS => {
+            check_decode::<light::GetBlockHeaders>(rlp)
+        }
+        light::msgid::BLOCK_HEADERS => check_decode::<light::BlockHeaders>(rlp),
+        light::msgid::NEW_BLOCK_HASHES => {
+            check_decode::<light::NewBlockHashes>(rlp)
+        }
+        light::msgid::SEND_RAW_TX => check_decode::<light::SendRawTx>(rlp),
+        light::msgid::GET_RECEIPTS => check_decode::<light::GetReceipts>(rlp),
+        light::msgid::RECEIPTS => check_decode::<light::Receipts>(rlp),
+        light::msgid::GET_TXS => check_decode::<light::GetTxs>(rlp),
+        light::msgid::TXS => check_decode::<light::Txs>(rlp),
+        light::msgid::GET_WITNESS_INFO => {
+            check_decode::<light::GetWitnessInfo>(rlp)
+        }
+        light::msgid::WITNESS_INFO => check_decode::<light::WitnessInfo>(rlp),
+        light::msgid::GET_BLOOMS => check_decode::<light::GetBlooms>(rlp),
+        light::msgid::BLOOMS => check_decode::<light::Blooms>(rlp),
+        light::msgid::GET_BLOCK_TXS => check_decode::<light::GetBlockTxs>(rlp),
+        light::msgid::BLOCK_TXS => check_decode::<light::BlockTxs>(rlp),
+        light::msgid::GET_TX_INFOS => check_decode::<light::GetTxInfos>(rlp),
+        light::msgid::TX_INFOS => check_decode::<light::TxInfos>(rlp),
+        light::msgid::GET_STORAGE_ROOTS => {
+            check_decode::<light::GetStorageRoots>(rlp)
+        }
+        light::msgid::STORAGE_ROOTS => check_decode::<light::StorageRoots>(rlp),
+        light::msgid::THROTTLED => check_decode::<sync::Throttled>(rlp),
+        _ => false,
+    }
+}
+
+pub fn arbitrary_vec<T>(
+    u: &mut Unstructured, mut f: impl FnMut(&mut Unstructured) -> Result<T>,
+) -> Result<Vec<T>> {
+    let len = u.arbitrary_len::<u64>()?;
+    (0..len).map(|_| f(u)).collect()
+}
+
+pub fn arbitrary_h256(u: &mut Unstructured) -> Result<H256> {
+    Ok(H256::from(u.arbitrary::<[u8; 32]>()?))
+}
+
+pub fn arbitrary_u256(u: &mut Unstructured) -> Result<U256> {
+    Ok(U256::from_big_endian(&u.arbitrary::<[u8; 32]>()?))
+}
+
+pub fn arbitrary_address(u: &mut Unstructured) -> Result<Address> {
+    Ok(Address::from(u.arbitrary::<[u8; 20]>()?))
+}
+
+pub fn arbitrary_hashes(u: &mut Unstructured) -> Result<Vec<H256>> {
+    arbitrary_vec(u, arbitrary_h256)
+}
+
+fn arbitrary_action(u: &mut Unstructured) -> Result<Action> {
+    Ok(if u.arbitrary()? {
+        Action::Call(arbitrary_address(u)?)
+    } else {
+        Action::Create
+    })
+}
+
+/// A signed transaction of either space. The signature is not valid.
+pub fn arbitrary_transaction(
+    u: &mut Unstructured,
+) -> Result<TransactionWithSignature> {
+    let (unsigned, v) = if u.arbitrary()? {
+        let tx = NativeTransaction {
+            nonce: arbitrary_u256(u)?,
+            gas_price: arbitrary_u256(u)?,
+            gas: arbitrary_u256(u)?,
+            action: arbitrary_action(u)?,
+            value: arbitrary_u256(u)?,
+            storage_limit: u.arbitrary()?,
+            epoch_height: u.arbitrary()?,
+            chain_id: u.arbitrary()?,
+            data: u.arbitrary()?,
+        };
+        (Transaction::Native(tx), u.arbitrary()?)
+    } else {
+        let tx = Eip155Transaction {
+            nonce: arbitrary_u256(u)?,
+            gas_price: arbitrary_u256(u)?,
+            gas: arbitrary_u256(u)?,
+            action: arbitrary_action(u)?,
+            value: arbitrary_u256(u)?,
+            chain_id: u.arbitrary()?,
+            data: u.arbitrary()?,
+        };
+        // Only the standard v values are encoded in the legacy v.
+        (Transaction::Ethereum(tx), u.int_in_range(0..=1)?)
+    };
+    let transaction = TransactionWithSignatureSerializePart {
+        unsigned,
+        v,
+        r: arbitrary_u256(u)?,
+        s: arbitrary_u256(u)?,
+    };
+    Ok(TransactionWithSignature {
+        hash: keccak_hash::keccak(rlp::encode(&transaction)),
+        transaction,
+        rlp_size: None,
+    })
+}
+
+pub fn arbitrary_signed_transaction(
+    u: &mut Unstructured,
+) -> Result<SignedTransaction> {
+    Ok(SignedTransaction {
+        transaction: arbitrary_transaction(u)?,
+        sender: arbitrary_address(u)?,
+        public: if u.arbitrary()? {
+            Some(H512::from_slice(u.bytes(64)?))
+        } else {
+            None
+        },
+    })
+}
+
+pub fn arbitrary_block_header(u: &mut Unstructured) -> Result<BlockHeader> {
+    let pos_reference = if u.arbitrary()? {
+        Some(arbitrary_h256(u)?)
+    } else {
+        None
+    };
+    // The custom fields are raw RLP items. Byte strings are used since a list
+    // after the nonce can be taken as the PoS reference.
+    let custom = arbitrary_vec(u, |u| {
+        Ok(rlp::encode(&u.arbitrary::<Vec<u8>>()?).to_vec())
+    })?;
+    Ok(BlockHeaderBuilder::new()
+        .with_parent_hash(arbitrary_h256(u)?)
+        .with_height(u.arbitrary()?)
+        .with_timestamp(u.arbitrary()?)
+        .with_author(arbitrary_address(u)?)
+        .with_transactions_root(arbitrary_h256(u)?)
+        .with_deferred_state_root(arbitrary_h256(u)?)
+        .with_deferred_receipts_root(arbitrary_h256(u)?)
+        .with_deferred_logs_bloom_hash(arbitrary_h256(u)?)
+        .with_blame(u.arbitrary()?)
+        .with_difficulty(arbitrary_u256(u)?)
+        .with_adaptive(u.arbitrary()?)
+        .with_gas_limit(arbitrary_u256(u)?)
+        .with_referee_hashes(arbitrary_hashes(u)?)
+        .with_custom(custom)
+        .with_nonce(arbitrary_u256(u)?)
+        .with_pos_reference(pos_reference)
+        .build())
+}
+
+pub fn arbitrary_block(u: &mut Unstructured) -> Result<Block> {
+    let block_header = arbitrary_block_header(u)?;
+    let transactions =
+        arbitrary_vec(u, |u| Ok(Arc::new(arbitrary_signed_transaction(u)?)))?;
+    Ok(Block::new(block_header, transactions))
+}
+
+/// Build a sync protocol message and check its round trip.
+pub fn check_arbitrary_sync_message(u: &mut Unstructured) -> Result<()> {
+    match u.int_in_range(0..=16)? {
+        0 => check_message(
+            &sync::NewBlockHashes {
+                block_hashes: arbitrary_hashes(u)?,
+            },
+            decode_sync_message,
+        ),
+        1 => check_message(
+            &sync::NewBlock {
+                block: arbitrary_block(u)?,
+            },
+            decode_sync_message,
+        ),
+        2 => check_message(
+            &sync::Heartbeat {
+                best_epoch: u.arbitrary()?,
+                terminal_block_hashes: arbitrary_hashes(u)?,
+            },
+            decode_sync_message,
+        ),
+        3 => check_message(
+            &sync::GetBlockHeaders {
+                request_id: u.arbitrary()?,
+                hashes: arbitrary_hashes(u)?,
+            },
+            decode_sync_message,
+        ),
+        4 => check_message(
+            &sync::GetBlockHeadersResponse {
+                request_id: u.arbitrary()?,
+                headers: arbitrary_vec(u, arbitrary_block_header)?,
+            },
+            decode_sync_message,
+        ),
+        5 => check_message(
+            &sync::GetBlocks {
+                request_id: u.arbitrary()?,
+                with_public: u.arbitrary()?,
+                hashes: arbitrary_hashes(u)?,
+                preferred_node_type: None,
+            },
+            decode_sync_message,
+        ),
+        6 => check_message(
+            &sync::GetBlocksResponse {
+                request_id: u.arbitrary()?,
+                blocks: arbitrary_vec(u, arbitrary_block)?,
+            },
+            decode_sync_message,
+        ),
+        7 => check_message(
+            &sync::GetBlocksWithPublicResponse {
+                request_id: u.arbitrary()?,
+                blocks: arbitrary_vec(u, arbitrary_block)?,
+            },
+            decode_sync_message,
+        ),
+        8 => check_message(
+            &sync::GetCompactBlocks {
+                request_id: u.arbitrary()?,
+                hashes: arbitrary_hashes(u)?,
+            },
+            decode_sync_message,
+        ),
+        9 => check_message(
+            &sync::GetBlockTxn {
+                request_id: u.arbitrary()?,
+                block_hash: arbitrary_h256(u)?,
+                index_skips: u.arbitrary()?,
+            },
+            decode_sync_message,
+        ),
+        10 => check_message(
+            &sync::GetBlockTxnResponse {
+                request_id: u.arbitrary()?,
+                block_hash: arbitrary_h256(u)?,
+                block_txn: arbitrary_vec(u, arbitrary_transaction)?,
+            },
+            decode_sync_message,
+        ),
+        11 => check_message(
+            &sync::Transactions {
+                transactions: arbitrary_vec(u, arbitrary_transaction)?,
+            },
+            decode_sync_message,
+        ),
+        12 => check_message(
+            &sync::GetTransactionsResponse {
+                request_id: u.arbitrary()?,
+                transactions: arbitrary_vec(u, arbitrary_transaction)?,
+                tx_hashes: arbitrary_hashes(u)?,
+            },
+            decode_sync_message,
+        ),
+        13 => check_message(
+            &sync::GetBlockHashesByEpoch {
+                request_id: u.arbitrary()?,
+                epochs: u.arbitrary()?,
+            },
+            decode_sync_message,
+        ),
+        14 => check_message(
+            &sync::GetBlockHashesResponse {
+                request_id: u.arbitrary()?,
+                hashes: arbitrary_hashes(u)?,
+            },
+            decode_sync_message,
+        ),
+        15 => check_message(
+            &sync::GetTerminalBlockHashesResponse {
+                request_id: u.arbitrary()?,
+                hashes: arbitrary_hashes(u)?,
+            },
+            decode_sync_message,
+        ),
+        _ => check_message(
+            &sync::Throttled {
+                msg_id: u.int_in_range(0..=0x3fff)?,
+                wait_time_nanos: u.arbitrary()?,
+                request_id: u.arbitrary()?,
+            },
+            decode_sync_message,
+        ),
+    }
+    Ok(())
+}
+
+/// Build a light protocol message and check its round trip.
+pub fn check_arbitrary_light_message(u: &mut Unstructured) -> Result<()> {
+    match u.int_in_range(0..=10)? {
+        0 => check_message(
+            &light::GetBlockHashesByEpoch {
+                request_id: u.arbitrary()?,
+                epochs: u.arbitrary()?,
+            },
+            decode_light_message,
+        ),
+        1 => check_message(
+            &light::BlockHashes {
+                request_id: u.arbitrary()?,
+                hashes: arbitrary_hashes(u)?,
+            },
+            decode_light_message,
+        ),
+        2 => check_message(
+            &light::GetBlockHeaders {
+                request_id: u.arbitrary()?,
+                hashes: arbitrary_hashes(u)?,
+            },
+            decode_light_message,
+        ),
+        3 => check_message(
+            &light::BlockHeaders {
+                request_id: u.arbitrary()?,
+                headers: arbitrary_vec(u, arbitrary_block_header)?,
+            },
+            decode_light_message,
+        ),
+        4 => check_message(
+            &light::NewBlockHashes {
+                hashes: arbitrary_hashes(u)?,
+            },
+            decode_light_message,
+        ),
+        5 => check_message(
+            &light::SendRawTx {
+                raw: rlp::encode(&arbitrary_transaction(u)?).to_vec(),
+            },
+            decode_light_message,
+        ),
+        6 => check_message(
+            &light::GetTxs {
+                request_id: u.arbitrary()?,
+                hashes: arbitrary_hashes(u)?,
+            },
+            decode_light_message,
+        ),
+        7 => check_message(
+            &light::Txs {
+                request_id: u.arbitrary()?,
+                txs: arbitrary_vec(u, arbitrary_signed_transaction)?,
+            },
+            decode_light_message,
+        ),
+        8 => check_message(
+            &light::BlockTxs {
+                request_id: u.arbitrary()?,
+                block_txs: arbitrary_vec(u, |u| {
+                    Ok(light::BlockTxsWithHash {
+                        hash: arbitrary_h256(u)?,
+                        block_txs: arbitrary_vec(
+                            u,
+                            arbitrary_signed_transaction,
+                        )?,
+                    })
+                })?,
+            },
+            decode_light_message,
+        ),
+        9 => check_message(
+            &light::WitnessInfo {
+                request_id: u.arbitrary()?,
+                infos: arbitrary_vec(u, |u| {
+                    Ok(light::WitnessInfoWithHeight {
+                        height: u.arbitrary()?,
+                        state_root_hashes: arbitrary_hashes(u)?,
+                        receipt_hashes: arbitrary_hashes(u)?,
+                        bloom_hashes: arbitrary_hashes(u)?,
+                    })
+                })?,
+            },
+            decode_light_message,
+        ),
+        _ => check_message(
+            &light::GetStateRoots {
+                request_id: u.arbitrary()?,
+                epochs: u.arbitrary()?,
+            },
+            decode_light_message,
+        ),
+    }
+    Ok(())
+}
+
+fn arbitrary_endpoint(u: &mut Unstructured) -> Result<NodeEndpoint> {
+    // Only the IP address and the port are encoded.
+    let address = if u.arbitrary()? {
+        SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::from(u.arbitrary::<u32>()?),
+            u.arbitrary()?,
+        ))
+    } else {
+        SocketAddr::V6(SocketAddrV6::new(
+            Ipv6Addr::from(u.arbitrary::<u128>()?),
+            u.arbitrary()?,
+            0,
+            0,
+        ))
+    };
+    Ok(NodeEndpoint {
+        address,
+        udp_port: u.arbitrary()?,
+    })
+}
+
+pub fn arbitrary_discovery_packet(
+    u: &mut Unstructured,
+) -> Result<DiscoveryPacket> {
+    Ok(match u.int_in_range(0..=3)? {
+        0 => DiscoveryPacket::Ping {
+            from: arbitrary_endpoint(u)?,
+            to: arbitrary_endpoint(u)?,
+            expire_timestamp: u.arbitrary()?,
+        },
+        1 => DiscoveryPacket::Pong {
+            to: arbitrary_endpoint(u)?,
+            echo_hash: arbitrary_h256(u)?,
+            expire_timestamp: u.arbitrary()?,
+        },
+        2 => DiscoveryPacket::FindNode(FindNodeMessage::new(
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+        )),
+        _ => DiscoveryPacket::Neighbours(NeighborsChunkMessage {
+            neighbors: arbitrary_vec(u, |u| {
+                Ok(NodeEntry {
+                    id: NodeId::from_slice(u.bytes(64)?),
+                    endpoint: arbitrary_endpoint(u)?,
+                })
+            })?,
+            num_chunks: u.arbitrary()?,
+            chunk_index: u.arbitrary()?,
+        }),
+    })
+}
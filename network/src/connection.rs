@@ -3,6 +3,7 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
+    encryption::{PacketCipher, TAG_SIZE},
     io::{IoContext, StreamToken},
     throttling::THROTTLING_SERVICE,
     Error, ErrorKind,
@@ -72,6 +73,23 @@ lazy_static! {
             "network_system_data",
             "send_queue_size"
         );
+    static ref COMPRESS_INPUT_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_system_data", "compress_input");
+    static ref COMPRESS_OUTPUT_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_system_data", "compress_output");
+    static ref COMPRESS_SKIPPED_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_system_data", "compress_skipped");
+    static ref DECOMPRESS_INPUT_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_system_data", "decompress_input");
+    static ref DECOMPRESS_OUTPUT_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_system_data", "decompress_output");
+    /// Compressed size in percent of the original size.
+    static ref COMPRESSION_RATIO: Arc<dyn Histogram> =
+        Sample::ExpDecay(0.015).register_with_group(
+            "network_system_data",
+            "compression_ratio",
+            1024
+        );
 }
 
 /// Connection write status.
@@ -85,6 +103,52 @@ pub enum WriteStatus {
 
 const MAX_PAYLOAD_SIZE: usize = (1 << 24) - 1;
 
+/// Compress the packet data with snappy.
+///
+/// Returns `None` if the compressed data is not smaller than the original
+/// one, in which case the data should be sent uncompressed.
+pub fn compress(data: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    let compressed = snap::raw::Encoder::new()
+        .compress_vec(data)
+        .map_err(|e| ErrorKind::Msg(format!("compress failed: {}", e)))?;
+
+    COMPRESS_INPUT_METER.mark(data.len());
+    if compressed.len() >= data.len() {
+        COMPRESS_SKIPPED_METER.mark(1);
+        return Ok(None);
+    }
+
+    COMPRESS_OUTPUT_METER.mark(compressed.len());
+    COMPRESSION_RATIO.update((compressed.len() * 100 / data.len()) as u64);
+    Ok(Some(compressed))
+}
+
+/// Decompress the packet data that compressed with snappy.
+///
+/// The decompressed length is checked against the maximum payload size
+/// before decompression, so that a malicious peer could not exhaust memory
+/// with a small packet.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let len = snap::raw::decompress_len(data).map_err(|e| {
+        debug!("failed to decompress packet: {}", e);
+        Error::from(ErrorKind::BadProtocol)
+    })?;
+    if len > MAX_PAYLOAD_SIZE {
+        debug!("decompressed packet is oversized, len = {}", len);
+        return Err(ErrorKind::OversizedPacket.into());
+    }
+
+    let decompressed =
+        snap::raw::Decoder::new().decompress_vec(data).map_err(|e| {
+            debug!("failed to decompress packet: {}", e);
+            Error::from(ErrorKind::BadProtocol)
+        })?;
+
+    DECOMPRESS_INPUT_METER.mark(data.len());
+    DECOMPRESS_OUTPUT_METER.mark(decompressed.len());
+    Ok(decompressed)
+}
+
 pub trait GenericSocket: Read + Write {}
 
 impl GenericSocket for TcpStream {}
@@ -113,6 +177,8 @@ struct Packet {
     original_is_high_priority: bool,
     throttling_size: usize,
     creation_time: Instant,
+    // whether to encrypt the data before sending out.
+    encrypted: bool,
 }
 
 impl Packet {
@@ -131,6 +197,7 @@ impl Packet {
             original_is_high_priority: is_high_priority,
             throttling_size,
             creation_time: Instant::now(),
+            encrypted: false,
         })
     }
 
@@ -186,6 +253,8 @@ pub struct GenericConnection<Socket: GenericSocket> {
     registered: AtomicBool,
     /// Assemble packet with extra information before sending out.
     assembler: Box<dyn PacketAssembler>,
+    /// Encrypt and decrypt packets once encryption negotiated in session.
+    cipher: Option<PacketCipher>,
 }
 
 impl<Socket: GenericSocket> GenericConnection<Socket> {
@@ -225,17 +294,27 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
             }
         }
 
-        let packet = self.assembler.load(&mut self.recv_buf);
+        let packet = match self.assembler.load(&mut self.recv_buf) {
+            Some(packet) => packet,
+            None => return Ok(None),
+        };
 
-        if let Some(ref p) = packet {
-            trace!(
-                "Packet received, token = {}, size = {}",
-                self.token,
-                p.len()
-            );
-        }
+        trace!(
+            "Packet received, token = {}, size = {}",
+            self.token,
+            packet.len()
+        );
 
-        Ok(packet.map(|p| p.freeze()))
+        match self.cipher {
+            Some(ref mut cipher) => {
+                let mut data = packet.to_vec();
+                cipher.open(&mut data).map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+                })?;
+                Ok(Some(data.into()))
+            }
+            None => Ok(Some(packet.freeze())),
+        }
     }
 
     /// Send the specified data out immediately
@@ -249,6 +328,9 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
             data
         );
 
+        if let Some(ref mut cipher) = self.cipher {
+            cipher.seal(&mut data)?;
+        }
         self.assembler.assemble(&mut data)?;
         let size = self.socket.write(&data)?;
 
@@ -273,6 +355,15 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
                 None => return Ok(WriteStatus::Complete),
             };
 
+            // The counter based nonce requires to encrypt packets in the
+            // order of sending out.
+            if packet.encrypted {
+                self.cipher
+                    .as_mut()
+                    .expect("cipher should be set for encrypted packet")
+                    .seal(&mut packet.data)?;
+            }
+
             // assemble packet to send, e.g. prefix length to packet
             self.assembler.assemble(&mut packet.data)?;
 
@@ -337,13 +428,16 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
     {
         if !data.is_empty() {
             let size = data.len();
-            if self.assembler.is_oversized(size) {
+            let encrypted = self.cipher.is_some();
+            let sending_size = if encrypted { size + TAG_SIZE } else { size };
+            if self.assembler.is_oversized(sending_size) {
                 return Err(ErrorKind::OversizedPacket.into());
             }
 
             trace!("Sending packet, token = {}, size = {}", self.token, size);
 
-            let packet = Packet::new(data, priority)?;
+            let mut packet = Packet::new(data, priority)?;
+            packet.encrypted = encrypted;
             self.send_queue.push_back(packet, priority);
 
             SEND_METER.mark(size);
@@ -371,6 +465,14 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
     }
 
     pub fn is_sending(&self) -> bool { self.interest.is_writable() }
+
+    /// Encrypt all packets to send and decrypt all packets received from now
+    /// on. Packets already in the send queue are sent out without encryption.
+    pub fn enable_encryption(&mut self, cipher: PacketCipher) {
+        self.cipher = Some(cipher);
+    }
+
+    pub fn is_encrypted(&self) -> bool { self.cipher.is_some() }
 }
 
 pub type Connection = GenericConnection<TcpStream>;
@@ -386,6 +488,7 @@ impl Connection {
             interest: Ready::hup() | Ready::readable(),
             registered: AtomicBool::new(false),
             assembler: Box::new(PacketWithLenAssembler::default()),
+            cipher: None,
         }
     }
 
@@ -478,6 +581,7 @@ impl Connection {
                 .len_by_priority(SendQueuePriority::High),
             interest: format!("{:?}", self.interest),
             registered: self.registered.load(AtomicOrdering::SeqCst),
+            encrypted: self.is_encrypted(),
         }
     }
 }
@@ -493,6 +597,7 @@ pub struct ConnectionDetails {
     pub priority_queue_high: usize,
     pub interest: String,
     pub registered: bool,
+    pub encrypted: bool,
 }
 
 /// Assembler that prefix packet with length information.
@@ -674,6 +779,7 @@ mod tests {
                 interest: Ready::hup() | Ready::readable(),
                 registered: AtomicBool::new(false),
                 assembler: Box::new(PacketWithLenAssembler::new(1, None)),
+                cipher: None,
             }
         }
     }
@@ -738,6 +844,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_compress_roundtrip() {
+        let data = vec![7u8; 4096];
+        let compressed = compress(&data).unwrap().unwrap();
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed).unwrap(), data);
+
+        // incompressible data is left uncompressed
+        assert!(compress(&[1, 2, 3]).unwrap().is_none());
+
+        // corrupted data
+        assert!(decompress(&[0xff, 0xff, 0xff, 0xff, 0xff]).is_err());
+    }
+
     #[test]
     fn test_assembler_oversized() {
         let assembler = PacketWithLenAssembler::default();
@@ -4,6 +4,7 @@
 
 use crate::{
     hash::keccak,
+    node_allowlist::{NodeAllowlist, ALLOWLIST_REJECTED_DISCOVERY_METER},
     node_database::NodeDatabase,
     node_table::{NodeId, *},
     service::{UdpIoContext, MAX_DATAGRAM_SIZE, UDP_PROTOCOL_DISCOVERY},
@@ -18,6 +19,7 @@ use rlp_derive::{RlpDecodable, RlpEncodable};
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     net::{IpAddr, SocketAddr},
+    sync::Arc,
     time::{Instant, SystemTime, UNIX_EPOCH},
 };
 use throttling::time_window_bucket::TimeWindowBucket;
@@ -79,6 +81,8 @@ pub struct Discovery {
     check_timestamps: bool,
     adding_nodes: Vec<NodeEntry>,
     ip_filter: IpFilter,
+    /// Only discover the allowed nodes in private network mode.
+    node_allowlist: Option<Arc<NodeAllowlist>>,
     pub disc_option: DiscoveryOption,
 
     // Limits the response for PING/FIND_NODE packets
@@ -91,6 +95,7 @@ pub struct Discovery {
 impl Discovery {
     pub fn new(
         key: &KeyPair, public: NodeEndpoint, ip_filter: IpFilter,
+        node_allowlist: Option<Arc<NodeAllowlist>>,
         config: DiscoveryConfiguration,
     ) -> Discovery
     {
@@ -107,6 +112,7 @@ impl Discovery {
             check_timestamps: true,
             adding_nodes: Vec::new(),
             ip_filter,
+            node_allowlist,
             disc_option: DiscoveryOption {
                 general: true,
                 archive: false,
@@ -124,7 +130,16 @@ impl Discovery {
     }
 
     fn is_allowed(&self, entry: &NodeEntry) -> bool {
-        entry.endpoint.is_allowed(&self.ip_filter) && entry.id != self.id
+        entry.endpoint.is_allowed(&self.ip_filter)
+            && entry.id != self.id
+            && self.is_allowlisted(&entry.id)
+    }
+
+    fn is_allowlisted(&self, id: &NodeId) -> bool {
+        match self.node_allowlist {
+            Some(ref allowlist) => allowlist.is_allowed(id),
+            None => true,
+        }
     }
 
     pub fn try_ping_nodes(
@@ -163,17 +178,13 @@ impl Discovery {
     fn ping(
         &mut self, uio: &UdpIoContext, node: &NodeEntry,
     ) -> Result<(), Error> {
-        let mut rlp = RlpStream::new_list(4);
-        rlp.append(&DISCOVER_PROTOCOL_VERSION);
-        self.public_endpoint.to_rlp_list(&mut rlp);
-        node.endpoint.to_rlp_list(&mut rlp);
-        rlp.append(&self.config.expire_timestamp());
-        let hash = self.send_packet(
-            uio,
-            PACKET_PING,
-            &node.endpoint.udp_address(),
-            &rlp.drain(),
-        )?;
+        let packet = DiscoveryPacket::Ping {
+            from: self.public_endpoint.clone(),
+            to: node.endpoint.clone(),
+            expire_timestamp: self.config.expire_timestamp(),
+        };
+        let hash =
+            self.send_packet(uio, &packet, &node.endpoint.udp_address())?;
 
         self.in_flight_pings.insert(
             node.id.clone(),
@@ -189,11 +200,11 @@ impl Discovery {
     }
 
     fn send_packet(
-        &mut self, uio: &UdpIoContext, packet_id: u8, address: &SocketAddr,
-        payload: &[u8],
+        &mut self, uio: &UdpIoContext, packet: &DiscoveryPacket,
+        address: &SocketAddr,
     ) -> Result<H256, Error>
     {
-        let packet = assemble_packet(packet_id, payload, &self.secret)?;
+        let packet = packet.assemble(&self.secret)?;
         let hash = H256::from_slice(&packet[1..=32]);
         self.send_to(uio, packet, address.clone());
         Ok(hash)
@@ -208,30 +219,44 @@ impl Discovery {
     pub fn on_packet(
         &mut self, uio: &UdpIoContext, packet: &[u8], from: SocketAddr,
     ) -> Result<(), Error> {
-        // validate packet
-        if packet.len() < 32 + 65 + 4 + 1 {
-            return Err(ErrorKind::BadProtocol.into());
-        }
+        let (hash_signed, node_id, signed) = open_packet(packet)?;
 
-        let hash_signed = keccak(&packet[32..]);
-        if hash_signed[..] != packet[0..32] {
-            return Err(ErrorKind::BadProtocol.into());
+        if !self.is_allowlisted(&node_id) {
+            trace!("Ignore UDP packet from node not allowlisted: {:?}", from);
+            ALLOWLIST_REJECTED_DISCOVERY_METER.mark(1);
+            return Ok(());
         }
 
-        let signed = &packet[(32 + 65)..];
-        let signature = H520::from_slice(&packet[32..(32 + 65)]);
-        let node_id = recover(&signature.into(), &keccak(signed))?;
-
         let packet_id = signed[0];
         let rlp = Rlp::new(&signed[1..]);
-        match packet_id {
-            PACKET_PING => {
-                self.on_ping(uio, &rlp, &node_id, &from, hash_signed.as_bytes())
+        match DiscoveryPacket::decode(packet_id, &rlp)? {
+            Some(DiscoveryPacket::Ping {
+                from: ping_from,
+                to: ping_to,
+                expire_timestamp,
+            }) => self.on_ping(
+                uio,
+                ping_from,
+                ping_to,
+                expire_timestamp,
+                &node_id,
+                &from,
+                hash_signed,
+            ),
+            Some(DiscoveryPacket::Pong {
+                echo_hash,
+                expire_timestamp,
+                ..
+            }) => {
+                self.on_pong(uio, echo_hash, expire_timestamp, &node_id, &from)
+            }
+            Some(DiscoveryPacket::FindNode(msg)) => {
+                self.on_find_node(uio, msg, &node_id, &from)
+            }
+            Some(DiscoveryPacket::Neighbours(msg)) => {
+                self.on_neighbours(uio, msg, &node_id, &from)
             }
-            PACKET_PONG => self.on_pong(uio, &rlp, &node_id, &from),
-            PACKET_FIND_NODE => self.on_find_node(uio, &rlp, &node_id, &from),
-            PACKET_NEIGHBOURS => self.on_neighbours(uio, &rlp, &node_id, &from),
-            _ => {
+            None => {
                 debug!("Unknown UDP packet: {}", packet_id);
                 Ok(())
             }
@@ -253,8 +278,9 @@ impl Discovery {
     }
 
     fn on_ping(
-        &mut self, uio: &UdpIoContext, rlp: &Rlp, node_id: &NodeId,
-        from: &SocketAddr, echo_hash: &[u8],
+        &mut self, uio: &UdpIoContext, ping_from: NodeEndpoint,
+        ping_to: NodeEndpoint, timestamp: u64, node_id: &NodeId,
+        from: &SocketAddr, echo_hash: H256,
     ) -> Result<(), Error>
     {
         trace!("Got Ping from {:?}", &from);
@@ -266,12 +292,8 @@ impl Discovery {
             .into());
         }
 
-        let ping_from = NodeEndpoint::from_rlp(&rlp.at(1)?)?;
-        let ping_to = NodeEndpoint::from_rlp(&rlp.at(2)?)?;
-        let timestamp: u64 = rlp.val_at(3)?;
         self.check_timestamp(timestamp)?;
 
-        let mut response = RlpStream::new_list(3);
         let pong_to = NodeEndpoint {
             address: from.clone(),
             udp_port: ping_from.udp_port,
@@ -282,12 +304,13 @@ impl Discovery {
         // use it in order to get the node's address.
         // So this is a temporary fix so that older Parity versions don't brake
         // completely.
-        ping_to.to_rlp_list(&mut response);
-        // pong_to.to_rlp_list(&mut response);
-
-        response.append(&echo_hash);
-        response.append(&self.config.expire_timestamp());
-        self.send_packet(uio, PACKET_PONG, from, &response.drain())?;
+        let response = DiscoveryPacket::Pong {
+            to: ping_to,
+            // to: pong_to.clone(),
+            echo_hash,
+            expire_timestamp: self.config.expire_timestamp(),
+        };
+        self.send_packet(uio, &response, from)?;
 
         let entry = NodeEntry {
             id: node_id.clone(),
@@ -307,14 +330,11 @@ impl Discovery {
     }
 
     fn on_pong(
-        &mut self, uio: &UdpIoContext, rlp: &Rlp, node_id: &NodeId,
-        from: &SocketAddr,
+        &mut self, uio: &UdpIoContext, echo_hash: H256, timestamp: u64,
+        node_id: &NodeId, from: &SocketAddr,
     ) -> Result<(), Error>
     {
         trace!("Got Pong from {:?} ; node_id={:#x}", &from, node_id);
-        let _pong_to = NodeEndpoint::from_rlp(&rlp.at(0)?)?;
-        let echo_hash: H256 = rlp.val_at(1)?;
-        let timestamp: u64 = rlp.val_at(2)?;
         self.check_timestamp(timestamp)?;
 
         let expected_node = match self.in_flight_pings.entry(*node_id) {
@@ -347,7 +367,7 @@ impl Discovery {
     }
 
     fn on_find_node(
-        &mut self, uio: &UdpIoContext, rlp: &Rlp, _node: &NodeId,
+        &mut self, uio: &UdpIoContext, msg: FindNodeMessage, _node: &NodeId,
         from: &SocketAddr,
     ) -> Result<(), Error>
     {
@@ -360,30 +380,31 @@ impl Discovery {
             .into());
         }
 
-        let msg: FindNodeMessage = rlp.as_val()?;
         self.check_timestamp(msg.expire_timestamp)?;
-        let neighbors = msg.sample(
+        let mut neighbors = msg.sample(
             &*uio.node_db.read(),
             &self.ip_filter,
             self.config.discover_node_count,
         )?;
+        neighbors.retain(|n| self.is_allowlisted(&n.id));
 
         trace!("Sample {} Neighbours for {:?}", neighbors.len(), &from);
 
         let chunk_size = (MAX_DATAGRAM_SIZE - (1 + 109)) / 90;
         let chunks = NeighborsChunkMessage::chunks(neighbors, chunk_size);
 
-        for chunk in &chunks {
-            self.send_packet(uio, PACKET_NEIGHBOURS, from, &chunk.rlp_bytes())?;
+        let num_chunks = chunks.len();
+        for chunk in chunks {
+            self.send_packet(uio, &DiscoveryPacket::Neighbours(chunk), from)?;
         }
 
-        trace!("Sent {} Neighbours chunks to {:?}", chunks.len(), &from);
+        trace!("Sent {} Neighbours chunks to {:?}", num_chunks, &from);
         Ok(())
     }
 
     fn on_neighbours(
-        &mut self, uio: &UdpIoContext, rlp: &Rlp, node_id: &NodeId,
-        from: &SocketAddr,
+        &mut self, uio: &UdpIoContext, msg: NeighborsChunkMessage,
+        node_id: &NodeId, from: &SocketAddr,
     ) -> Result<(), Error>
     {
         let mut entry = match self.in_flight_find_nodes.entry(*node_id) {
@@ -394,7 +415,6 @@ impl Discovery {
             }
         };
 
-        let msg: NeighborsChunkMessage = rlp.as_val()?;
         let request = entry.get_mut();
 
         if !msg.update(request)? {
@@ -531,9 +551,8 @@ impl Discovery {
 
         self.send_packet(
             uio,
-            PACKET_FIND_NODE,
+            &DiscoveryPacket::FindNode(msg),
             &node.endpoint.udp_address(),
-            &msg.rlp_bytes(),
         )?;
 
         self.in_flight_find_nodes
@@ -643,6 +662,122 @@ impl Discovery {
     }
 }
 
+/// The packets of the discovery protocol, i.e. the UDP datagrams of
+/// `UDP_PROTOCOL_DISCOVERY`.
+#[derive(Debug, PartialEq)]
+pub enum DiscoveryPacket {
+    Ping {
+        from: NodeEndpoint,
+        to: NodeEndpoint,
+        expire_timestamp: u64,
+    },
+    Pong {
+        to: NodeEndpoint,
+        /// The hash of the Ping packet.
+        echo_hash: H256,
+        expire_timestamp: u64,
+    },
+    FindNode(FindNodeMessage),
+    Neighbours(NeighborsChunkMessage),
+}
+
+impl DiscoveryPacket {
+    fn packet_id(&self) -> u8 {
+        match self {
+            DiscoveryPacket::Ping { .. } => PACKET_PING,
+            DiscoveryPacket::Pong { .. } => PACKET_PONG,
+            DiscoveryPacket::FindNode(_) => PACKET_FIND_NODE,
+            DiscoveryPacket::Neighbours(_) => PACKET_NEIGHBOURS,
+        }
+    }
+
+    fn rlp_bytes(&self) -> Bytes {
+        match self {
+            DiscoveryPacket::Ping {
+                from,
+                to,
+                expire_timestamp,
+            } => {
+                let mut rlp = RlpStream::new_list(4);
+                rlp.append(&DISCOVER_PROTOCOL_VERSION);
+                from.to_rlp_list(&mut rlp);
+                to.to_rlp_list(&mut rlp);
+                rlp.append(expire_timestamp);
+                rlp.drain()
+            }
+            DiscoveryPacket::Pong {
+                to,
+                echo_hash,
+                expire_timestamp,
+            } => {
+                let mut rlp = RlpStream::new_list(3);
+                to.to_rlp_list(&mut rlp);
+                rlp.append(echo_hash);
+                rlp.append(expire_timestamp);
+                rlp.drain()
+            }
+            DiscoveryPacket::FindNode(msg) => msg.rlp_bytes(),
+            DiscoveryPacket::Neighbours(msg) => msg.rlp_bytes(),
+        }
+    }
+
+    /// Returns `None` for an unknown packet id.
+    pub fn decode(packet_id: u8, rlp: &Rlp) -> Result<Option<Self>, Error> {
+        let packet = match packet_id {
+            PACKET_PING => DiscoveryPacket::Ping {
+                from: NodeEndpoint::from_rlp(&rlp.at(1)?)?,
+                to: NodeEndpoint::from_rlp(&rlp.at(2)?)?,
+                expire_timestamp: rlp.val_at(3)?,
+            },
+            PACKET_PONG => DiscoveryPacket::Pong {
+                to: NodeEndpoint::from_rlp(&rlp.at(0)?)?,
+                echo_hash: rlp.val_at(1)?,
+                expire_timestamp: rlp.val_at(2)?,
+            },
+            PACKET_FIND_NODE => DiscoveryPacket::FindNode(rlp.as_val()?),
+            PACKET_NEIGHBOURS => DiscoveryPacket::Neighbours(rlp.as_val()?),
+            _ => return Ok(None),
+        };
+        Ok(Some(packet))
+    }
+
+    /// Sign the packet, returning the datagram with the protocol byte.
+    pub fn assemble(&self, secret: &Secret) -> Result<Bytes, Error> {
+        assemble_packet(self.packet_id(), &self.rlp_bytes(), secret)
+    }
+}
+
+/// Decode a datagram as it's received from a peer, without the protocol
+/// byte, and return the sender and the packet.
+#[cfg(any(test, feature = "fuzzing"))]
+pub fn decode_packet(
+    packet: &[u8],
+) -> Result<(NodeId, Option<DiscoveryPacket>), Error> {
+    let (_, node_id, signed) = open_packet(packet)?;
+    Ok((
+        node_id,
+        DiscoveryPacket::decode(signed[0], &Rlp::new(&signed[1..]))?,
+    ))
+}
+
+/// Check the hash and the signature of a datagram without the protocol byte,
+/// and return the hash, the sender and the signed part.
+fn open_packet(packet: &[u8]) -> Result<(H256, NodeId, &[u8]), Error> {
+    if packet.len() < 32 + 65 + 4 + 1 {
+        return Err(ErrorKind::BadProtocol.into());
+    }
+
+    let hash_signed = keccak(&packet[32..]);
+    if hash_signed[..] != packet[0..32] {
+        return Err(ErrorKind::BadProtocol.into());
+    }
+
+    let signed = &packet[(32 + 65)..];
+    let signature = H520::from_slice(&packet[32..(32 + 65)]);
+    let node_id = recover(&signature.into(), &keccak(signed))?;
+    Ok((hash_signed, node_id, signed))
+}
+
 fn assemble_packet(
     packet_id: u8, bytes: &[u8], secret: &Secret,
 ) -> Result<Bytes, Error> {
@@ -673,15 +808,15 @@ pub struct DiscoveryOption {
     pub archive: bool,
 }
 
-#[derive(RlpEncodable, RlpDecodable)]
-struct FindNodeMessage {
+#[derive(Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct FindNodeMessage {
     pub tag_key: Option<String>,
     pub tag_value: Option<String>,
     pub expire_timestamp: u64,
 }
 
 impl FindNodeMessage {
-    fn new(
+    pub fn new(
         tag_key: Option<String>, tag_value: Option<String>,
         expire_timestamp: u64,
     ) -> Self
@@ -721,11 +856,11 @@ impl FindNodeMessage {
     }
 }
 
-#[derive(RlpEncodable, RlpDecodable)]
-struct NeighborsChunkMessage {
-    neighbors: Vec<NodeEntry>,
-    num_chunks: usize,
-    chunk_index: usize,
+#[derive(Debug, PartialEq, RlpEncodable, RlpDecodable)]
+pub struct NeighborsChunkMessage {
+    pub neighbors: Vec<NodeEntry>,
+    pub num_chunks: usize,
+    pub chunk_index: usize,
 }
 
 impl NeighborsChunkMessage {
@@ -788,3 +923,67 @@ impl NeighborsChunkMessage {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cfxkey::{Generator, Random};
+
+    #[test]
+    fn test_packet_round_trip() {
+        let key = Random.generate().unwrap();
+        let endpoint = NodeEndpoint {
+            address: "10.0.0.1:32323".parse().unwrap(),
+            udp_port: 32324,
+        };
+        let packets = vec![
+            DiscoveryPacket::Ping {
+                from: endpoint.clone(),
+                to: NodeEndpoint {
+                    address: "[2001:db8::1]:32323".parse().unwrap(),
+                    udp_port: 32323,
+                },
+                expire_timestamp: 1_600_000_000,
+            },
+            DiscoveryPacket::Pong {
+                to: endpoint.clone(),
+                echo_hash: H256::random(),
+                expire_timestamp: 1_600_000_000,
+            },
+            DiscoveryPacket::FindNode(FindNodeMessage::new(
+                Some(NODE_TAG_NODE_TYPE.into()),
+                Some(NODE_TAG_ARCHIVE.into()),
+                1_600_000_000,
+            )),
+            DiscoveryPacket::Neighbours(NeighborsChunkMessage {
+                neighbors: vec![NodeEntry {
+                    id: NodeId::random(),
+                    endpoint,
+                }],
+                num_chunks: 2,
+                chunk_index: 1,
+            }),
+        ];
+        for packet in packets {
+            let datagram = packet.assemble(key.secret()).unwrap();
+            assert_eq!(datagram[0], UDP_PROTOCOL_DISCOVERY);
+            let (node_id, decoded) = decode_packet(&datagram[1..]).unwrap();
+            assert_eq!(node_id, *key.public());
+            assert_eq!(decoded, Some(packet));
+        }
+    }
+
+    #[test]
+    fn test_reject_tampered_packet() {
+        let key = Random.generate().unwrap();
+        let packet = DiscoveryPacket::FindNode(FindNodeMessage::new(
+            None,
+            None,
+            1_600_000_000,
+        ));
+        let mut datagram = packet.assemble(key.secret()).unwrap();
+        let last = datagram.len() - 1;
+        datagram[last] ^= 1;
+        assert!(decode_packet(&datagram[1..]).is_err());
+    }
+}
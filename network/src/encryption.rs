@@ -0,0 +1,344 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{node_table::NodeId, Error, ErrorKind};
+use cfx_types::{Public, H256};
+use chacha20poly1305::{
+    aead::{AeadInPlace, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use keccak_hash::keccak;
+use keylib::{crypto::ecdh, Generator, KeyPair, Random, Secret};
+use std::{fmt, str::FromStr};
+
+/// Size of the authentication tag appended to each encrypted packet.
+pub const TAG_SIZE: usize = 16;
+
+const INITIATOR_KEY_LABEL: &[u8] = b"conflux-p2p-initiator";
+const RESPONDER_KEY_LABEL: &[u8] = b"conflux-p2p-responder";
+
+/// Prefix of the handshake nonce of a peer that supports encryption. Unlike
+/// the Hello packet, the nonces are exchanged in the authenticated handshake,
+/// so a man in the middle can not strip or forge the encryption support
+/// without being detected.
+const ENCRYPTION_NONCE_PREFIX: &[u8] = b"cfx-aead";
+
+/// Whether to encrypt the protocol packets after handshake.
+///
+/// Encryption is negotiated in Hello packet, and only enabled if both peers
+/// support it. With `Required` mode, sessions with peers that do not support
+/// encryption are disconnected. The support of encryption is also marked in
+/// the handshake nonce, see `handshake_nonce`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionMode {
+    Disabled,
+    Optional,
+    Required,
+}
+
+impl Default for EncryptionMode {
+    fn default() -> Self { EncryptionMode::Disabled }
+}
+
+impl FromStr for EncryptionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "disabled" => Ok(EncryptionMode::Disabled),
+            "optional" => Ok(EncryptionMode::Optional),
+            "required" => Ok(EncryptionMode::Required),
+            _ => Err(format!("Invalid encryption mode: {}", s)),
+        }
+    }
+}
+
+/// Generate a random handshake nonce, which is marked if encryption is
+/// enabled by `mode`.
+pub fn handshake_nonce(mode: EncryptionMode) -> H256 {
+    let mut nonce = H256::random();
+    if mode != EncryptionMode::Disabled {
+        nonce.as_bytes_mut()[..ENCRYPTION_NONCE_PREFIX.len()]
+            .copy_from_slice(ENCRYPTION_NONCE_PREFIX);
+    }
+    nonce
+}
+
+/// Secrets exchanged during handshake, which are used to derive the keys
+/// for packet encryption.
+pub struct HandshakeSecrets {
+    /// ECDH shared secret of the node keys of the two peers.
+    shared: Secret,
+    /// Ephemeral key pair of this peer, whose public key is sent in Hello.
+    /// It is dropped once the keys are derived, so that the recorded packets
+    /// can not be decrypted even if the node keys are leaked later.
+    ephemeral: KeyPair,
+    /// Nonce of the peer that starts the handshake.
+    initiator_nonce: H256,
+    /// Nonce of the peer that accepts the handshake.
+    responder_nonce: H256,
+    /// Whether this peer starts the handshake.
+    originated: bool,
+}
+
+impl HandshakeSecrets {
+    pub fn new(
+        secret: &Secret, remote_id: &NodeId, initiator_nonce: H256,
+        responder_nonce: H256, originated: bool,
+    ) -> Result<Self, Error>
+    {
+        Ok(HandshakeSecrets {
+            shared: ecdh::agree(secret, remote_id)?,
+            ephemeral: Random.generate()?,
+            initiator_nonce,
+            responder_nonce,
+            originated,
+        })
+    }
+
+    pub fn ephemeral_public(&self) -> &Public { self.ephemeral.public() }
+
+    /// Whether the remote peer supports encryption according to its
+    /// handshake nonce.
+    pub fn remote_supports_encryption(&self) -> bool {
+        let remote_nonce = if self.originated {
+            &self.responder_nonce
+        } else {
+            &self.initiator_nonce
+        };
+        remote_nonce.as_bytes().starts_with(ENCRYPTION_NONCE_PREFIX)
+    }
+
+    /// Derive the packet cipher with the ephemeral public key of the remote
+    /// peer. The keys depend on both the node keys and the ephemeral keys,
+    /// as well as the handshake nonces which mark the encryption support.
+    pub fn into_cipher(
+        self, remote_ephemeral: &Public,
+    ) -> Result<PacketCipher, Error> {
+        let ephemeral_shared =
+            ecdh::agree(self.ephemeral.secret(), remote_ephemeral)?;
+        let (initiator_ephemeral, responder_ephemeral) = if self.originated {
+            (self.ephemeral.public(), remote_ephemeral)
+        } else {
+            (remote_ephemeral, self.ephemeral.public())
+        };
+
+        let derive_key = |label: &[u8]| {
+            let mut material = Vec::with_capacity(
+                H256::len_bytes() * 4 + Public::len_bytes() * 2 + label.len(),
+            );
+            material.extend_from_slice(self.shared.as_bytes());
+            material.extend_from_slice(ephemeral_shared.as_bytes());
+            material.extend_from_slice(self.initiator_nonce.as_bytes());
+            material.extend_from_slice(self.responder_nonce.as_bytes());
+            material.extend_from_slice(initiator_ephemeral.as_bytes());
+            material.extend_from_slice(responder_ephemeral.as_bytes());
+            material.extend_from_slice(label);
+            keccak(&material)
+        };
+
+        Ok(PacketCipher::new(
+            &derive_key(INITIATOR_KEY_LABEL),
+            &derive_key(RESPONDER_KEY_LABEL),
+            self.originated,
+        ))
+    }
+}
+
+/// Authenticated encryption of packets with ChaCha20-Poly1305.
+///
+/// Each direction uses a separate key derived from the handshake secrets,
+/// and a counter as nonce that increased for every packet. Since packets are
+/// transferred over TCP in order, the counter need not be sent along with
+/// the packet, and any replayed, reordered or dropped packet fails to be
+/// decrypted.
+pub struct PacketCipher {
+    egress: ChaCha20Poly1305,
+    ingress: ChaCha20Poly1305,
+    egress_counter: u64,
+    ingress_counter: u64,
+}
+
+impl PacketCipher {
+    fn new(
+        initiator_key: &H256, responder_key: &H256, originated: bool,
+    ) -> Self {
+        let (egress_key, ingress_key) = if originated {
+            (initiator_key, responder_key)
+        } else {
+            (responder_key, initiator_key)
+        };
+
+        PacketCipher {
+            egress: ChaCha20Poly1305::new(Key::from_slice(
+                egress_key.as_bytes(),
+            )),
+            ingress: ChaCha20Poly1305::new(Key::from_slice(
+                ingress_key.as_bytes(),
+            )),
+            egress_counter: 0,
+            ingress_counter: 0,
+        }
+    }
+
+    /// Encrypt the packet in place, and append the authentication tag.
+    pub fn seal(&mut self, data: &mut Vec<u8>) -> Result<(), Error> {
+        let nonce = Self::nonce(self.egress_counter);
+        self.egress
+            .encrypt_in_place(&nonce, &[], data)
+            .map_err(|_| ErrorKind::Msg("failed to encrypt packet".into()))?;
+        self.egress_counter += 1;
+        Ok(())
+    }
+
+    /// Verify the authentication tag and decrypt the packet in place.
+    pub fn open(&mut self, data: &mut Vec<u8>) -> Result<(), Error> {
+        let nonce = Self::nonce(self.ingress_counter);
+        self.ingress.decrypt_in_place(&nonce, &[], data).map_err(|_| {
+            debug!("failed to decrypt packet, len = {}", data.len());
+            Error::from(ErrorKind::Auth)
+        })?;
+        self.ingress_counter += 1;
+        Ok(())
+    }
+
+    fn nonce(counter: u64) -> Nonce {
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&counter.to_le_bytes());
+        *Nonce::from_slice(&nonce)
+    }
+}
+
+impl fmt::Debug for PacketCipher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "PacketCipher {{ egress_counter: {}, ingress_counter: {} }}",
+            self.egress_counter, self.ingress_counter
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keylib::{Generator, Random};
+
+    fn secrets_pair() -> (HandshakeSecrets, HandshakeSecrets) {
+        let initiator = Random.generate().unwrap();
+        let responder = Random.generate().unwrap();
+        let initiator_nonce = handshake_nonce(EncryptionMode::Optional);
+        let responder_nonce = handshake_nonce(EncryptionMode::Optional);
+
+        let initiator_secrets = HandshakeSecrets::new(
+            initiator.secret(),
+            responder.public(),
+            initiator_nonce,
+            responder_nonce,
+            true,
+        )
+        .unwrap();
+        let responder_secrets = HandshakeSecrets::new(
+            responder.secret(),
+            initiator.public(),
+            initiator_nonce,
+            responder_nonce,
+            false,
+        )
+        .unwrap();
+
+        (initiator_secrets, responder_secrets)
+    }
+
+    fn cipher_pair() -> (PacketCipher, PacketCipher) {
+        let (initiator, responder) = secrets_pair();
+        let initiator_ephemeral = *initiator.ephemeral_public();
+        let responder_ephemeral = *responder.ephemeral_public();
+
+        (
+            initiator.into_cipher(&responder_ephemeral).unwrap(),
+            responder.into_cipher(&initiator_ephemeral).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_handshake_nonce() {
+        let (initiator, responder) = secrets_pair();
+        assert!(initiator.remote_supports_encryption());
+        assert!(responder.remote_supports_encryption());
+
+        let nonce = handshake_nonce(EncryptionMode::Disabled);
+        let secrets = HandshakeSecrets::new(
+            Random.generate().unwrap().secret(),
+            Random.generate().unwrap().public(),
+            nonce,
+            handshake_nonce(EncryptionMode::Required),
+            true,
+        )
+        .unwrap();
+        assert!(secrets.remote_supports_encryption());
+
+        let secrets = HandshakeSecrets::new(
+            Random.generate().unwrap().secret(),
+            Random.generate().unwrap().public(),
+            handshake_nonce(EncryptionMode::Required),
+            nonce,
+            true,
+        )
+        .unwrap();
+        assert!(!secrets.remote_supports_encryption());
+    }
+
+    #[test]
+    fn test_ephemeral_key_mismatch() {
+        let (initiator, responder) = secrets_pair();
+        let initiator_ephemeral = *initiator.ephemeral_public();
+        // a man in the middle replaces the ephemeral key of the responder
+        let forged = *Random.generate().unwrap().public();
+
+        let mut initiator = initiator.into_cipher(&forged).unwrap();
+        let mut responder =
+            responder.into_cipher(&initiator_ephemeral).unwrap();
+
+        let mut packet = vec![1, 2, 3];
+        initiator.seal(&mut packet).unwrap();
+        assert!(responder.open(&mut packet).is_err());
+    }
+
+    #[test]
+    fn test_seal_open() {
+        let (mut initiator, mut responder) = cipher_pair();
+
+        for i in 0..3u8 {
+            let mut packet = vec![i; 100];
+            initiator.seal(&mut packet).unwrap();
+            assert_eq!(packet.len(), 100 + TAG_SIZE);
+            assert_ne!(&packet[..100], &[i; 100][..]);
+            responder.open(&mut packet).unwrap();
+            assert_eq!(packet, vec![i; 100]);
+
+            let mut packet = vec![i; 10];
+            responder.seal(&mut packet).unwrap();
+            initiator.open(&mut packet).unwrap();
+            assert_eq!(packet, vec![i; 10]);
+        }
+    }
+
+    #[test]
+    fn test_open_tampered_or_replayed() {
+        let (mut initiator, mut responder) = cipher_pair();
+
+        let mut packet = vec![1, 2, 3];
+        initiator.seal(&mut packet).unwrap();
+        let mut replayed = packet.clone();
+        let mut tampered = packet.clone();
+        tampered[0] ^= 1;
+
+        // failed to open will not advance the counter
+        assert!(responder.open(&mut tampered).is_err());
+        responder.open(&mut packet).unwrap();
+        assert_eq!(packet, vec![1, 2, 3]);
+        assert!(responder.open(&mut replayed).is_err());
+    }
+}
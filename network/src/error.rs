@@ -14,6 +14,9 @@ pub enum DisconnectReason {
     IpLimited,
     UpdateNodeIdFailed,
     Blacklisted,
+    EncryptionRequired,
+    NotAllowlisted,
+    EncryptionMismatch,
     Custom(String),
     Unknown,
 }
@@ -27,6 +30,9 @@ impl DisconnectReason {
             DisconnectReason::IpLimited => 3,
             DisconnectReason::UpdateNodeIdFailed => 4,
             DisconnectReason::Blacklisted => 5,
+            DisconnectReason::EncryptionRequired => 6,
+            DisconnectReason::NotAllowlisted => 7,
+            DisconnectReason::EncryptionMismatch => 8,
             DisconnectReason::Custom(_) => 100,
             DisconnectReason::Unknown => 0xff,
         }
@@ -60,6 +66,9 @@ impl Decodable for DisconnectReason {
             3 => Ok(DisconnectReason::IpLimited),
             4 => Ok(DisconnectReason::UpdateNodeIdFailed),
             5 => Ok(DisconnectReason::Blacklisted),
+            6 => Ok(DisconnectReason::EncryptionRequired),
+            7 => Ok(DisconnectReason::NotAllowlisted),
+            8 => Ok(DisconnectReason::EncryptionMismatch),
             100 => match std::str::from_utf8(&raw[1..]) {
                 Err(_) => {
                     Err(DecoderError::Custom("Unable to decode message part"))
@@ -80,6 +89,9 @@ impl fmt::Display for DisconnectReason {
             DisconnectReason::IpLimited => "IP limited",
             DisconnectReason::UpdateNodeIdFailed => "Update node id failed",
             DisconnectReason::Blacklisted => "blacklisted",
+            DisconnectReason::EncryptionRequired => "encryption required",
+            DisconnectReason::NotAllowlisted => "not allowlisted",
+            DisconnectReason::EncryptionMismatch => "encryption mismatch",
             DisconnectReason::Custom(ref msg) => &msg[..],
             DisconnectReason::Unknown => "unknown",
         };
@@ -246,6 +258,9 @@ mod tests {
         check_rlp(WrongEndpointInfo);
         check_rlp(IpLimited);
         check_rlp(UpdateNodeIdFailed);
+        check_rlp(EncryptionRequired);
+        check_rlp(NotAllowlisted);
+        check_rlp(EncryptionMismatch);
         check_rlp(Unknown);
 
         check_rlp(Custom("".to_owned()));
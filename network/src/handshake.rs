@@ -19,8 +19,11 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
-    connection::Connection, node_table::NodeId, service::HostMetadata, Error,
-    ErrorKind,
+    connection::Connection,
+    encryption::{handshake_nonce, EncryptionMode, HandshakeSecrets},
+    node_table::NodeId,
+    service::HostMetadata,
+    Error, ErrorKind,
 };
 use cfx_types::{Public, H256};
 use io::{IoContext, StreamToken};
@@ -64,18 +67,27 @@ pub struct Handshake {
     pub state: HandshakeState,
     /// nonce for verification
     nonce: H256,
+    /// nonce of remote peer
+    remote_nonce: H256,
+    /// Whether the handshake is started by this node
+    originated: bool,
 }
 
 impl Handshake {
-    /// Create a new handshake object
+    /// Create a new handshake object. The nonce is marked if encryption is
+    /// enabled by `encryption_mode`.
     pub fn new(
         token: StreamToken, id: Option<&NodeId>, socket: TcpStream,
-    ) -> Self {
+        encryption_mode: EncryptionMode,
+    ) -> Self
+    {
         Handshake {
             id: id.cloned().unwrap_or_else(NodeId::default),
             connection: Connection::new(token, socket),
             state: HandshakeState::New,
-            nonce: H256::random(),
+            nonce: handshake_nonce(encryption_mode),
+            remote_nonce: H256::zero(),
+            originated: id.is_some(),
         }
     }
 
@@ -98,6 +110,24 @@ impl Handshake {
     /// Check if handshake is complete
     pub fn done(&self) -> bool { self.state == HandshakeState::StartSession }
 
+    /// Get the secrets to derive keys for packet encryption. It should be
+    /// called after handshake completed.
+    pub fn secrets(&self, secret: &Secret) -> Result<HandshakeSecrets, Error> {
+        let (initiator_nonce, responder_nonce) = if self.originated {
+            (self.nonce, self.remote_nonce)
+        } else {
+            (self.remote_nonce, self.nonce)
+        };
+
+        HandshakeSecrets::new(
+            secret,
+            &self.id,
+            initiator_nonce,
+            responder_nonce,
+            self.originated,
+        )
+    }
+
     /// Readable IO handler. Drives the state change.
     pub fn readable<Message>(
         &mut self, io: &IoContext<Message>, host: &HostMetadata,
@@ -189,6 +219,7 @@ impl Handshake {
 
         let (remote_public, remote_nonce) = auth.split_at(NodeId::len_bytes());
         self.id.assign_from_slice(remote_public);
+        self.remote_nonce = H256::from_slice(remote_nonce);
 
         self.write_ack_of_auth(io, remote_nonce)
     }
@@ -264,6 +295,7 @@ impl Handshake {
             debug!("failed to read ack of auth, nonce mismatch");
             return Err(ErrorKind::BadProtocol.into());
         }
+        self.remote_nonce = H256::from_slice(remote_nonce);
 
         self.write_ack_of_ack(io, remote_nonce)
     }
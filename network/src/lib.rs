@@ -24,12 +24,14 @@ extern crate keccak_hash as hash;
 extern crate libc;
 extern crate parity_path;
 extern crate rand;
+extern crate snap;
 #[macro_use]
 extern crate enum_map_derive;
 extern crate strum;
 #[macro_use]
 extern crate strum_macros;
 extern crate cfxkey as keylib;
+extern crate chacha20poly1305;
 extern crate keccak_hash;
 
 pub const PROTOCOL_ID_SIZE: usize = 3;
@@ -39,10 +41,12 @@ pub type PeerId = usize;
 
 mod connection;
 mod discovery;
+mod encryption;
 mod error;
 mod handshake;
 mod ip;
 mod ip_utils;
+mod node_allowlist;
 mod node_database;
 pub mod node_table;
 pub mod service;
@@ -51,14 +55,22 @@ mod session_manager;
 pub mod throttling;
 
 pub use crate::{
+    encryption::EncryptionMode,
     error::{DisconnectReason, Error, ErrorKind, ThrottlingReason},
     ip::SessionIpLimitConfig,
+    node_allowlist::AllowlistReloadResult,
     node_table::Node,
     service::NetworkService,
     session::SessionDetails,
 };
 pub use io::TimerToken;
 
+#[cfg(feature = "fuzzing")]
+pub use crate::discovery::{
+    decode_packet as decode_discovery_packet, DiscoveryPacket, FindNodeMessage,
+    NeighborsChunkMessage,
+};
+
 use crate::{
     node_table::NodeId,
     service::{
@@ -80,7 +92,7 @@ use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     str::{self, FromStr},
     sync::Arc,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 pub const NODE_TAG_NODE_TYPE: &str = "node_type";
@@ -139,6 +151,15 @@ pub struct NetworkConfiguration {
     /// Maximum number of P2P nodes for subnet B (ip/16).
     pub subnet_quota: usize,
     pub session_ip_limit_config: SessionIpLimitConfig,
+    /// Minimum size in bytes of a protocol packet to be compressed with
+    /// snappy. Compression is only applied to sessions whose remote peer
+    /// advertises the capability in Hello. `None` disables compression.
+    pub compression_threshold: Option<usize>,
+    /// Whether to encrypt protocol packets with keys derived from handshake.
+    pub encryption_mode: EncryptionMode,
+    /// Path of the node allowlist file for private network mode. If set,
+    /// only the listed nodes are allowed to connect and be discovered.
+    pub allowlist_path: Option<String>,
 
     pub discovery_config: DiscoveryConfiguration,
 }
@@ -176,6 +197,9 @@ impl NetworkConfiguration {
             test_mode: false,
             subnet_quota: 32,
             session_ip_limit_config: SessionIpLimitConfig::default(),
+            compression_threshold: None,
+            encryption_mode: EncryptionMode::Disabled,
+            allowlist_path: None,
             discovery_config,
         }
     }
@@ -297,6 +321,33 @@ pub enum UpdateNodeOperation {
     Remove,
 }
 
+/// Limits of the number of sessions, which can be adjusted at runtime by Debug
+/// RPC. Reducing a limit does not disconnect the existing sessions.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLimits {
+    pub max_incoming_peers: usize,
+    pub max_outgoing_peers: usize,
+    pub max_outgoing_peers_archive: usize,
+}
+
+/// The session limits to change, and the unset ones are kept.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLimitsUpdate {
+    pub max_incoming_peers: Option<usize>,
+    pub max_outgoing_peers: Option<usize>,
+    pub max_outgoing_peers_archive: Option<usize>,
+}
+
+/// Result of changing the session limits, which is used by Debug RPC.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLimitsUpdateResult {
+    pub previous: SessionLimits,
+    pub current: SessionLimits,
+}
+
 pub trait NetworkContext {
     fn get_protocol(&self) -> ProtocolId;
 
@@ -325,6 +376,10 @@ pub trait NetworkContext {
     fn is_peer_self(&self, _node_id: &NodeId) -> bool;
 
     fn self_node_id(&self) -> NodeId;
+
+    /// The time that the request timeouts are based on. A simulated network
+    /// returns the time of its virtual clock.
+    fn now(&self) -> Instant { Instant::now() }
 }
 
 #[derive(Debug, Clone)]
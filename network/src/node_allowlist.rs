@@ -0,0 +1,201 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    node_table::{Node, NodeId},
+    Error, ErrorKind,
+};
+use lazy_static::lazy_static;
+use metrics::{register_meter_with_group, Meter};
+use parking_lot::RwLock;
+use serde_derive::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+lazy_static! {
+    pub static ref ALLOWLIST_REJECTED_HANDSHAKE_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_allowlist", "rejected_handshake");
+    pub static ref ALLOWLIST_REJECTED_DISCOVERY_METER: Arc<dyn Meter> =
+        register_meter_with_group("network_allowlist", "rejected_discovery");
+}
+
+/// Allowlist of node ids for private network mode.
+///
+/// Once configured, only the listed nodes could complete the handshake, and
+/// discovery only works with the listed nodes. The allowlist file contains
+/// one node per line, either as a node id in hex, or as a node url
+/// `cfxnode://<node id>@<ip>:<port>`. Nodes with url are connected as static
+/// peers. Empty lines and lines start with `#` are ignored.
+///
+/// The reserved nodes of the configuration are always allowed, whether or
+/// not they are listed in the file.
+pub struct NodeAllowlist {
+    path: PathBuf,
+    reserved_nodes: HashSet<NodeId>,
+    inner: RwLock<AllowlistInner>,
+}
+
+#[derive(Default)]
+struct AllowlistInner {
+    ids: HashSet<NodeId>,
+    /// Nodes with endpoint, which are connected as static peers.
+    static_nodes: HashMap<NodeId, Node>,
+}
+
+/// Result of reloading the allowlist, which is used by Debug RPC.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllowlistReloadResult {
+    pub previous_count: usize,
+    pub current_count: usize,
+    pub added: Vec<NodeId>,
+    pub removed: Vec<NodeId>,
+}
+
+impl NodeAllowlist {
+    pub fn load<P: AsRef<Path>>(
+        path: P, reserved_nodes: HashSet<NodeId>,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let inner = Self::read_file(&path)?;
+        info!(
+            "Node allowlist loaded, path = {:?}, count = {}",
+            path,
+            inner.ids.len()
+        );
+
+        Ok(NodeAllowlist {
+            path,
+            reserved_nodes,
+            inner: RwLock::new(inner),
+        })
+    }
+
+    /// Reload the allowlist from file. The current allowlist is kept if
+    /// failed to read the file.
+    pub fn reload(&self) -> Result<AllowlistReloadResult, Error> {
+        let new_inner = Self::read_file(&self.path)?;
+        let mut inner = self.inner.write();
+
+        let added = new_inner.ids.difference(&inner.ids).cloned().collect();
+        let removed = inner.ids.difference(&new_inner.ids).cloned().collect();
+        let result = AllowlistReloadResult {
+            previous_count: inner.ids.len(),
+            current_count: new_inner.ids.len(),
+            added,
+            removed,
+        };
+        *inner = new_inner;
+
+        info!("Node allowlist reloaded, {:?}", result);
+        Ok(result)
+    }
+
+    pub fn contains(&self, id: &NodeId) -> bool {
+        self.inner.read().ids.contains(id)
+    }
+
+    /// Returns whether the node is listed or is a configured reserved node.
+    pub fn is_allowed(&self, id: &NodeId) -> bool {
+        self.contains(id) || self.reserved_nodes.contains(id)
+    }
+
+    /// Get the allowed nodes that have endpoint configured.
+    pub fn static_nodes(&self) -> Vec<Node> {
+        self.inner.read().static_nodes.values().cloned().collect()
+    }
+
+    fn read_file(path: &Path) -> Result<AllowlistInner, Error> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::from(ErrorKind::Msg(format!(
+                "failed to read node allowlist {:?}: {}",
+                path, e
+            )))
+        })?;
+
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Result<AllowlistInner, Error> {
+        let mut inner = AllowlistInner::default();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with("cfxnode://") {
+                let node = Node::from_str(line)?;
+                inner.ids.insert(node.id);
+                inner.static_nodes.insert(node.id, node);
+            } else {
+                let id = NodeId::from_str(line.trim_start_matches("0x"))
+                    .map_err(|_| {
+                        Error::from(ErrorKind::Msg(format!(
+                            "invalid node id in allowlist: {}",
+                            line
+                        )))
+                    })?;
+                inner.ids.insert(id);
+            }
+        }
+
+        Ok(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID1: &str = "a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c";
+    const ID2: &str = "c1c8b6e4a3d9e4f0b1a36c6b5d0f4e5dc1c8b6e4a3d9e4f0b1a36c6b5d0f4e5dc1c8b6e4a3d9e4f0b1a36c6b5d0f4e5dc1c8b6e4a3d9e4f0b1a36c6b5d0f4e5d";
+
+    #[test]
+    fn test_parse() {
+        let content = format!(
+            "# consortium nodes\n\n0x{}\ncfxnode://{}@127.0.0.1:32323\n",
+            ID1, ID2
+        );
+        let inner = NodeAllowlist::parse(&content).unwrap();
+
+        let id1 = NodeId::from_str(ID1).unwrap();
+        let id2 = NodeId::from_str(ID2).unwrap();
+        assert_eq!(inner.ids.len(), 2);
+        assert!(inner.ids.contains(&id1));
+        assert!(inner.ids.contains(&id2));
+        assert_eq!(inner.static_nodes.len(), 1);
+        assert!(inner.static_nodes.contains_key(&id2));
+
+        assert!(NodeAllowlist::parse("not a node id").is_err());
+    }
+
+    #[test]
+    fn test_reserved_nodes_allowed() {
+        let dir = tempdir::TempDir::new("allowlist").unwrap();
+        let path = dir.path().join("allowlist");
+        fs::write(&path, format!("0x{}\n", ID1)).unwrap();
+
+        let id1 = NodeId::from_str(ID1).unwrap();
+        let id2 = NodeId::from_str(ID2).unwrap();
+        let allowlist =
+            NodeAllowlist::load(&path, vec![id2].into_iter().collect())
+                .unwrap();
+        assert!(allowlist.is_allowed(&id1));
+        assert!(allowlist.is_allowed(&id2));
+        assert!(!allowlist.contains(&id2));
+
+        // Reserved nodes stay allowed after the file is reloaded.
+        fs::write(&path, "").unwrap();
+        allowlist.reload().unwrap();
+        assert!(!allowlist.is_allowed(&id1));
+        assert!(allowlist.is_allowed(&id2));
+    }
+}
@@ -157,7 +157,7 @@ impl FromStr for NodeEndpoint {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct NodeEntry {
     pub id: NodeId,
     pub endpoint: NodeEndpoint,
@@ -11,7 +11,10 @@ use std::{
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::{atomic::Ordering as AtomicOrdering, Arc},
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
@@ -40,6 +43,7 @@ use crate::{
     handshake::BYPASS_CRYPTOGRAPHY,
     io::*,
     ip_utils::{map_external_address, select_public_address},
+    node_allowlist::{AllowlistReloadResult, NodeAllowlist},
     node_database::NodeDatabase,
     node_table::*,
     parse_msg_id_leb128_2_bytes_at_most,
@@ -47,8 +51,9 @@ use crate::{
     session_manager::SessionManager,
     Error, ErrorKind, HandlerWorkType, IpFilter, NatType, NetworkConfiguration,
     NetworkContext as NetworkContextTrait, NetworkIoMessage,
-    NetworkProtocolHandler, PeerInfo, ProtocolId, ProtocolInfo,
-    UpdateNodeOperation, NODE_TAG_ARCHIVE, NODE_TAG_NODE_TYPE,
+    NetworkProtocolHandler, PeerInfo, ProtocolId, ProtocolInfo, SessionLimits,
+    SessionLimitsUpdate, SessionLimitsUpdateResult, UpdateNodeOperation,
+    NODE_TAG_ARCHIVE, NODE_TAG_NODE_TYPE,
 };
 
 use super::DisconnectReason;
@@ -406,6 +411,27 @@ impl NetworkService {
         true
     }
 
+    /// Reload the node allowlist of private network mode from file.
+    pub fn reload_allowlist(&self) -> Result<AllowlistReloadResult, Error> {
+        let (inner, io_service) = match (&self.inner, &self.io_service) {
+            (Some(inner), Some(io_service)) => (inner, io_service),
+            _ => bail!("Network service not started yet!"),
+        };
+        let io = IoContext::new(io_service.channel(), 0);
+        inner.reload_allowlist(&io)
+    }
+
+    /// Change the limits of the number of sessions at runtime.
+    pub fn set_session_limits(
+        &self, update: SessionLimitsUpdate,
+    ) -> Result<SessionLimitsUpdateResult, Error> {
+        let inner = match self.inner {
+            Some(ref inner) => inner,
+            None => bail!("Network service not started yet!"),
+        };
+        Ok(inner.set_session_limits(update))
+    }
+
     pub fn save_node_db(&self) {
         if let Some(inner) = &self.inner {
             inner.node_db.write().save();
@@ -457,6 +483,12 @@ pub struct NetworkServiceInner {
     pub node_db: RwLock<NodeDatabase>,
     reserved_nodes: RwLock<HashSet<NodeId>>,
     dropped_nodes: RwLock<HashSet<NodeId>>,
+    /// Allowlist of nodes in private network mode.
+    node_allowlist: Option<Arc<NodeAllowlist>>,
+    /// Limits of outgoing sessions, initialized by the configuration and
+    /// adjustable at runtime.
+    max_outgoing_peers: AtomicUsize,
+    max_outgoing_peers_archive: AtomicUsize,
 
     is_consortium: bool,
 
@@ -602,6 +634,20 @@ impl NetworkServiceInner {
             },
         };
 
+        let node_allowlist = match config.allowlist_path {
+            Some(ref path) => {
+                // The configured reserved nodes are always allowed.
+                let reserved_nodes = config
+                    .reserved_nodes
+                    .iter()
+                    .filter_map(|n| Node::from_str(n).ok())
+                    .map(|n| n.id)
+                    .collect();
+                Some(Arc::new(NodeAllowlist::load(path, reserved_nodes)?))
+            }
+            None => None,
+        };
+
         let allow_ips = config.ip_filter.clone();
         let discovery = {
             if config.discovery_enabled {
@@ -609,6 +655,7 @@ impl NetworkServiceInner {
                     &keys,
                     public_endpoint.clone(),
                     allow_ips,
+                    node_allowlist.clone(),
                     config.discovery_config.clone(),
                 ))
             } else {
@@ -649,6 +696,11 @@ impl NetworkServiceInner {
             )),
             reserved_nodes: RwLock::new(HashSet::new()),
             dropped_nodes: RwLock::new(HashSet::new()),
+            node_allowlist,
+            max_outgoing_peers: AtomicUsize::new(config.max_outgoing_peers),
+            max_outgoing_peers_archive: AtomicUsize::new(
+                config.max_outgoing_peers_archive,
+            ),
             is_consortium: config.is_consortium,
             delayed_queue: None,
         };
@@ -664,6 +716,8 @@ impl NetworkServiceInner {
             }
         }
 
+        inner.add_allowlist_static_nodes();
+
         Ok(inner)
     }
 
@@ -724,6 +778,105 @@ impl NetworkServiceInner {
         Ok(())
     }
 
+    /// Connect to the allowlisted nodes with endpoint as static peers.
+    fn add_allowlist_static_nodes(&self) {
+        let allowlist = match self.node_allowlist {
+            Some(ref allowlist) => allowlist,
+            None => return,
+        };
+
+        let mut reserved_nodes = self.reserved_nodes.write();
+        for n in allowlist.static_nodes() {
+            self.node_db.write().insert_trusted(NodeEntry {
+                id: n.id,
+                endpoint: n.endpoint,
+            });
+            reserved_nodes.insert(n.id);
+        }
+    }
+
+    /// Returns whether the node is allowed to connect. All nodes are allowed
+    /// if node allowlist not configured, and the configured reserved nodes
+    /// are always allowed.
+    pub fn is_node_allowed(&self, id: &NodeId) -> bool {
+        match self.node_allowlist {
+            Some(ref allowlist) => allowlist.is_allowed(id),
+            None => true,
+        }
+    }
+
+    fn session_limits(&self) -> SessionLimits {
+        SessionLimits {
+            max_incoming_peers: self.sessions.max_ingress_sessions(),
+            max_outgoing_peers: self
+                .max_outgoing_peers
+                .load(AtomicOrdering::Relaxed),
+            max_outgoing_peers_archive: self
+                .max_outgoing_peers_archive
+                .load(AtomicOrdering::Relaxed),
+        }
+    }
+
+    /// Change the session limits. The new outgoing limits take effect in the
+    /// next housekeeping, and the new incoming limit takes effect for new
+    /// connections.
+    fn set_session_limits(
+        &self, update: SessionLimitsUpdate,
+    ) -> SessionLimitsUpdateResult {
+        let previous = self.session_limits();
+        if let Some(max_incoming_peers) = update.max_incoming_peers {
+            self.sessions.set_max_ingress_sessions(max_incoming_peers);
+        }
+        if let Some(max_outgoing_peers) = update.max_outgoing_peers {
+            self.max_outgoing_peers
+                .store(max_outgoing_peers, AtomicOrdering::Relaxed);
+        }
+        if let Some(max_outgoing_peers_archive) =
+            update.max_outgoing_peers_archive
+        {
+            self.max_outgoing_peers_archive
+                .store(max_outgoing_peers_archive, AtomicOrdering::Relaxed);
+        }
+        let result = SessionLimitsUpdateResult {
+            previous,
+            current: self.session_limits(),
+        };
+
+        info!("Session limits changed, {:?}", result);
+        result
+    }
+
+    /// Reload the node allowlist from file, and disconnect the sessions of
+    /// nodes that are no longer allowed. The configured reserved nodes are
+    /// kept even if they are removed from the allowlist.
+    fn reload_allowlist(
+        &self, io: &IoContext<NetworkIoMessage>,
+    ) -> Result<AllowlistReloadResult, Error> {
+        let allowlist = match self.node_allowlist {
+            Some(ref allowlist) => allowlist,
+            None => bail!("Node allowlist not configured!"),
+        };
+
+        let result = allowlist.reload()?;
+
+        self.reserved_nodes
+            .write()
+            .retain(|id| self.is_node_allowed(id));
+        self.add_allowlist_static_nodes();
+
+        for id in result.removed.iter().filter(|id| !self.is_node_allowed(id)) {
+            self.kill_connection(
+                id,
+                io,
+                true,
+                None,
+                "removed from allowlist", // reason
+            );
+        }
+
+        Ok(result)
+    }
+
     fn initialize_udp_protocols(
         &self, io: &IoContext<NetworkIoMessage>,
     ) -> Result<(), Error> {
@@ -831,12 +984,12 @@ impl NetworkServiceInner {
             self.sessions.stat();
         let samples;
         {
-            let egress_attempt_count = if self.config.max_outgoing_peers
+            let max_outgoing_peers =
+                self.max_outgoing_peers.load(AtomicOrdering::Relaxed);
+            let egress_attempt_count = if max_outgoing_peers
                 > egress_count + sampled_archive_nodes.len()
             {
-                self.config.max_outgoing_peers
-                    - egress_count
-                    - sampled_archive_nodes.len()
+                max_outgoing_peers - egress_count - sampled_archive_nodes.len()
             } else {
                 0
             };
@@ -857,7 +1010,11 @@ impl NetworkServiceInner {
         let max_handshakes_per_round = self.config.max_handshakes / 2;
         let mut started: usize = 0;
         for id in nodes
-            .filter(|id| !self.sessions.contains_node(id) && *id != self_id)
+            .filter(|id| {
+                !self.sessions.contains_node(id)
+                    && *id != self_id
+                    && self.is_node_allowed(id)
+            })
             .take(min(
                 max_handshakes_per_round,
                 self.config.max_handshakes.saturating_sub(handshake_count),
@@ -882,7 +1039,10 @@ impl NetworkServiceInner {
 
     /// Sample archive nodes for outgoing connections if not enough.
     fn sample_archive_nodes(&self) -> HashSet<NodeId> {
-        if self.config.max_outgoing_peers_archive == 0 {
+        let max_outgoing_peers_archive = self
+            .max_outgoing_peers_archive
+            .load(AtomicOrdering::Relaxed);
+        if max_outgoing_peers_archive == 0 {
             return HashSet::new();
         }
 
@@ -890,12 +1050,12 @@ impl NetworkServiceInner {
         let value: String = NODE_TAG_ARCHIVE.into();
         let archive_sessions = self.sessions.count_with_tag(&key, &value);
 
-        if archive_sessions >= self.config.max_outgoing_peers_archive {
+        if archive_sessions >= max_outgoing_peers_archive {
             return HashSet::new();
         }
 
         self.node_db.read().sample_trusted_node_ids_with_tag(
-            (self.config.max_outgoing_peers_archive - archive_sessions) as u32,
+            (max_outgoing_peers_archive - archive_sessions) as u32,
             &key,
             &value,
         )
@@ -1577,11 +1737,12 @@ impl IoHandler<NetworkIoMessage> for NetworkServiceInner {
                 // Run the _slow_ discovery if enough peers are connected
                 let disc_general = self.has_enough_outgoing_peers(
                     None,
-                    self.config.max_outgoing_peers,
+                    self.max_outgoing_peers.load(AtomicOrdering::Relaxed),
                 );
                 let disc_archive = self.has_enough_outgoing_peers(
                     Some((NODE_TAG_NODE_TYPE, NODE_TAG_ARCHIVE)),
-                    self.config.max_outgoing_peers_archive,
+                    self.max_outgoing_peers_archive
+                        .load(AtomicOrdering::Relaxed),
                 );
                 if disc_general || disc_archive {
                     self.discovery.lock().as_mut().map(|d| {
@@ -1598,11 +1759,12 @@ impl IoHandler<NetworkIoMessage> for NetworkServiceInner {
                 // Run the fast discovery if not enough peers are connected
                 let disc_general = !self.has_enough_outgoing_peers(
                     None,
-                    self.config.max_outgoing_peers,
+                    self.max_outgoing_peers.load(AtomicOrdering::Relaxed),
                 );
                 let disc_archive = !self.has_enough_outgoing_peers(
                     Some((NODE_TAG_NODE_TYPE, NODE_TAG_ARCHIVE)),
-                    self.config.max_outgoing_peers_archive,
+                    self.max_outgoing_peers_archive
+                        .load(AtomicOrdering::Relaxed),
                 );
                 if disc_general || disc_archive {
                     self.discovery.lock().as_mut().map(|d| {
@@ -2190,3 +2352,112 @@ impl Decodable for ProtocolVersion {
         Ok(Self(rlp.as_val()?))
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::DiscoveryConfiguration;
+    use diem_crypto::{PrivateKey, Uniform};
+    use tempdir::TempDir;
+
+    const STATIC_ID: &str = "a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c";
+    const ALLOWED_ID: &str = "c1c8b6e4a3d9e4f0b1a36c6b5d0f4e5dc1c8b6e4a3d9e4f0b1a36c6b5d0f4e5dc1c8b6e4a3d9e4f0b1a36c6b5d0f4e5dc1c8b6e4a3d9e4f0b1a36c6b5d0f4e5d";
+    const RESERVED_ID: &str = "3f1e4b5a6c7d8e9f0a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60713f1e4b5a6c7d8e9f0a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f6071";
+
+    pub(crate) fn new_test_config() -> NetworkConfiguration {
+        let mut config =
+            NetworkConfiguration::new(10, DiscoveryConfiguration::default());
+        config.config_path = None;
+        config.listen_address = Some("127.0.0.1:0".parse().unwrap());
+        config.udp_port = Some(0);
+        config.nat_enabled = false;
+        config.max_incoming_peers = 8;
+        config.max_outgoing_peers = 4;
+        config.max_outgoing_peers_archive = 1;
+        config
+    }
+
+    pub(crate) fn new_test_inner(
+        config: &NetworkConfiguration,
+    ) -> NetworkServiceInner {
+        let pos_pub_keys = (
+            ConsensusPrivateKey::generate_for_testing().public_key(),
+            ConsensusVRFPrivateKey::generate_for_testing().public_key(),
+        );
+        NetworkServiceInner::new(config, pos_pub_keys).unwrap()
+    }
+
+    #[test]
+    fn test_set_session_limits() {
+        let config = new_test_config();
+        let mut service = NetworkService::new(config.clone());
+        let update = SessionLimitsUpdate {
+            max_incoming_peers: Some(16),
+            max_outgoing_peers: None,
+            max_outgoing_peers_archive: Some(0),
+        };
+
+        // Rejected before the network is started.
+        assert!(service.set_session_limits(update).is_err());
+
+        service.inner = Some(Arc::new(new_test_inner(&config)));
+        let result = service.set_session_limits(update).unwrap();
+        assert_eq!(
+            result.previous,
+            SessionLimits {
+                max_incoming_peers: 8,
+                max_outgoing_peers: 4,
+                max_outgoing_peers_archive: 1,
+            }
+        );
+        assert_eq!(
+            result.current,
+            SessionLimits {
+                max_incoming_peers: 16,
+                max_outgoing_peers: 4,
+                max_outgoing_peers_archive: 0,
+            }
+        );
+        let inner = service.inner.as_ref().unwrap();
+        assert_eq!(inner.session_limits(), result.current);
+        assert_eq!(inner.sessions.max_ingress_sessions(), 16);
+    }
+
+    #[test]
+    fn test_reload_allowlist_keeps_reserved_nodes() {
+        let dir = TempDir::new("allowlist").unwrap();
+        let path = dir.path().join("allowlist");
+        let content = format!(
+            "cfxnode://{}@127.0.0.1:32323\n0x{}\n",
+            STATIC_ID, ALLOWED_ID
+        );
+        fs::write(&path, content).unwrap();
+        let mut config = new_test_config();
+        config.allowlist_path = Some(path.to_string_lossy().into_owned());
+        config.reserved_nodes =
+            vec![format!("cfxnode://{}@127.0.0.1:32324", RESERVED_ID)];
+        let inner = new_test_inner(&config);
+
+        let static_id = NodeId::from_str(STATIC_ID).unwrap();
+        let allowed_id = NodeId::from_str(ALLOWED_ID).unwrap();
+        let reserved_id = NodeId::from_str(RESERVED_ID).unwrap();
+        // The configured reserved node is allowed without being listed.
+        assert!(inner.is_node_allowed(&reserved_id));
+        assert!(inner.reserved_nodes.read().contains(&reserved_id));
+        assert!(inner.reserved_nodes.read().contains(&static_id));
+
+        // Remove the static node from the allowlist.
+        fs::write(&path, format!("0x{}\n", ALLOWED_ID)).unwrap();
+        let io = IoContext::new(IoChannel::disconnected(), 0);
+        let result = inner.reload_allowlist(&io).unwrap();
+        assert_eq!(result.removed, vec![static_id]);
+        assert!(result.added.is_empty());
+
+        let reserved_nodes = inner.reserved_nodes.read();
+        assert!(reserved_nodes.contains(&reserved_id));
+        assert!(!reserved_nodes.contains(&static_id));
+        assert!(inner.is_node_allowed(&reserved_id));
+        assert!(inner.is_node_allowed(&allowed_id));
+        assert!(!inner.is_node_allowed(&static_id));
+    }
+}
@@ -3,8 +3,13 @@
 // See http://www.gnu.org/licenses/
 
 use crate::{
-    connection::{Connection, ConnectionDetails, SendQueueStatus, WriteStatus},
+    connection::{
+        compress, decompress, Connection, ConnectionDetails, SendQueueStatus,
+        WriteStatus,
+    },
+    encryption::{EncryptionMode, HandshakeSecrets},
     handshake::Handshake,
+    node_allowlist::ALLOWLIST_REJECTED_HANDSHAKE_METER,
     node_table::{NodeEndpoint, NodeEntry, NodeId},
     parse_msg_id_leb128_2_bytes_at_most,
     service::{NetworkServiceInner, ProtocolVersion},
@@ -12,6 +17,7 @@ use crate::{
     SessionMetadata, UpdateNodeOperation, PROTOCOL_ID_SIZE,
 };
 use bytes::Bytes;
+use cfx_types::Public;
 use diem_crypto::{bls::BLS_PUBLIC_KEY_LENGTH, ValidCryptoMaterial};
 use diem_types::validator_config::{ConsensusPublicKey, ConsensusVRFPublicKey};
 use io::*;
@@ -34,9 +40,12 @@ use std::{
 /// packet to exchange the supported protocols. Then, session is ready to send
 /// and receive protocol packets.
 ///
-/// Conflux do not use AES based encrypted connection to send protocol packets.
-/// This is because that Conflux has high TPS, and the encryption/decryption
-/// workloads are very heavy (about 20% CPU time in 3000 TPS).
+/// By default, Conflux do not use AES based encrypted connection to send
+/// protocol packets. This is because that Conflux has high TPS, and the
+/// encryption/decryption workloads are very heavy (about 20% CPU time in 3000
+/// TPS). For deployments that require confidentiality (e.g. consortium
+/// chains), packets after Hello could be encrypted with ChaCha20-Poly1305 if
+/// negotiated in Hello packet, see `EncryptionMode`.
 pub struct Session {
     /// Session information
     pub metadata: SessionMetadata,
@@ -55,6 +64,12 @@ pub struct Session {
     last_read: Instant,
     last_write: (Instant, WriteStatus),
     pos_public_key: Option<(ConsensusPublicKey, ConsensusVRFPublicKey)>,
+    /// Minimum size of protocol packets to compress, which is set once both
+    /// peers advertised the compression capability in Hello packet.
+    compression_threshold: Option<usize>,
+    /// Secrets exchanged in handshake to derive the keys for packet
+    /// encryption, which is consumed once encryption enabled.
+    handshake_secrets: Option<HandshakeSecrets>,
 }
 
 /// Session state.
@@ -98,6 +113,24 @@ pub const PACKET_USER: u8 = 0x10;
 pub const PACKET_HEADER_VERSION: u8 = 0;
 /// The header version where extension is introduced.
 const HEADER_VERSION_WITH_EXTENSION: u8 = 0;
+/// Extension to negotiate and mark packet compression. In Hello packet, it
+/// indicates that the sender accepts compressed protocol packets. In protocol
+/// packet, it indicates that the packet data is compressed.
+///
+/// The extension data format is: [ EXTENSION_COMPRESSION || algorithm ]
+const EXTENSION_COMPRESSION: u8 = 0x01;
+/// Snappy raw format without framing, since the packet is already framed.
+const COMPRESSION_ALGORITHM_SNAPPY: u8 = 0x01;
+/// Extension in Hello packet to indicate that the sender supports packet
+/// encryption. Once both peers advertised it, all packets after Hello are
+/// encrypted at connection level. The extension itself is not authenticated,
+/// so it must agree with the encryption support marked in the handshake
+/// nonce, and the ephemeral public key is mixed into the packet keys.
+///
+/// The extension data format is:
+///     [ EXTENSION_ENCRYPTION || algorithm || ephemeral public key ]
+const EXTENSION_ENCRYPTION: u8 = 0x02;
+const ENCRYPTION_ALGORITHM_CHACHA20_POLY1305: u8 = 0x01;
 
 impl Session {
     /// Create a new instance of `Session`, which starts to handshake with
@@ -111,7 +144,8 @@ impl Session {
     {
         let originated = id.is_some();
 
-        let mut handshake = Handshake::new(token, id, socket);
+        let mut handshake =
+            Handshake::new(token, id, socket, host.config.encryption_mode);
         handshake.start(io, &host.metadata)?;
 
         Ok(Session {
@@ -129,6 +163,8 @@ impl Session {
             last_read: Instant::now(),
             last_write: (Instant::now(), WriteStatus::Complete),
             pos_public_key,
+            compression_threshold: None,
+            handshake_secrets: None,
         })
     }
 
@@ -223,6 +259,22 @@ impl Session {
             self.metadata.id = Some(id);
         }
 
+        // refuse session if the node is not allowlisted in private network
+        if let Some(ref id) = self.metadata.id {
+            if !host.is_node_allowed(id) {
+                debug!("Refuse session of node not allowlisted, id = {:?}", id);
+                ALLOWLIST_REJECTED_HANDSHAKE_METER.mark(1);
+                return Err(
+                    self.send_disconnect(DisconnectReason::NotAllowlisted)
+                );
+            }
+        }
+
+        if host.config.encryption_mode != EncryptionMode::Disabled {
+            self.handshake_secrets =
+                Some(wrapper.get().secrets(host.metadata.secret())?);
+        }
+
         // write HELLO packet to remote peer
         self.state = State::Session(wrapper.take().connection);
         self.write_hello(io, host)?;
@@ -311,6 +363,14 @@ impl Session {
                 // Handle Hello packet to exchange protocols
                 let rlp = Rlp::new(&packet.data);
                 let pos_public_key = self.read_hello(&rlp, host)?;
+
+                // Enable compression if both peers support it.
+                if has_compression_extension(&packet.extensions) {
+                    self.compression_threshold =
+                        host.config.compression_threshold;
+                }
+                self.negotiate_encryption(&packet.extensions, host)?;
+
                 Ok(SessionDataWithDisconnectInfo {
                     session_data: SessionData::Ready { pos_public_key },
                     token_to_disconnect,
@@ -325,15 +385,23 @@ impl Session {
                 );
                 Err(ErrorKind::Disconnect(reason).into())
             }
-            PACKET_USER => Ok(SessionDataWithDisconnectInfo {
-                session_data: SessionData::Message {
-                    data: packet.data.to_vec(),
-                    protocol: packet
-                        .protocol
-                        .expect("protocol should available for USER packet"),
-                },
-                token_to_disconnect: None,
-            }),
+            PACKET_USER => {
+                let data = if has_compression_extension(&packet.extensions) {
+                    decompress(&packet.data)?
+                } else {
+                    packet.data.to_vec()
+                };
+
+                Ok(SessionDataWithDisconnectInfo {
+                    session_data: SessionData::Message {
+                        data,
+                        protocol: packet.protocol.expect(
+                            "protocol should available for USER packet",
+                        ),
+                    },
+                    token_to_disconnect: None,
+                })
+            }
             _ => {
                 debug!(
                     "read packet UNKNOWN, packet_id = {:?}, session = {:?}",
@@ -493,7 +561,64 @@ impl Session {
         }
     }
 
+    /// Enable packet encryption if both peers support it, or disconnect the
+    /// peer that does not support encryption if it is required. The Hello
+    /// extension should agree with the handshake nonce of remote peer,
+    /// otherwise the extension may be stripped by a man in the middle.
+    fn negotiate_encryption(
+        &mut self, extensions: &[Vec<u8>], host: &NetworkServiceInner,
+    ) -> Result<(), Error> {
+        let mode = host.config.encryption_mode;
+        if mode == EncryptionMode::Disabled {
+            return Ok(());
+        }
+
+        let secrets = self
+            .handshake_secrets
+            .take()
+            .expect("should have handshake secrets if encryption enabled");
+        let remote_ephemeral = parse_encryption_extension(extensions)?;
+
+        if remote_ephemeral.is_some() != secrets.remote_supports_encryption() {
+            debug!(
+                "encryption support mismatch between Hello and handshake, session = {:?}",
+                self
+            );
+            return Err(
+                self.send_disconnect(DisconnectReason::EncryptionMismatch)
+            );
+        }
+
+        let remote_ephemeral = match remote_ephemeral {
+            Some(remote_ephemeral) => remote_ephemeral,
+            None if mode == EncryptionMode::Required => {
+                debug!(
+                    "peer does not support encryption, session = {:?}",
+                    self
+                );
+                return Err(
+                    self.send_disconnect(DisconnectReason::EncryptionRequired)
+                );
+            }
+            None => {
+                info!(
+                    "peer does not support encryption, fall back to plaintext, session = {:?}",
+                    self
+                );
+                return Ok(());
+            }
+        };
+
+        let cipher = secrets.into_cipher(&remote_ephemeral)?;
+        self.connection_mut().enable_encryption(cipher);
+        debug!("Encryption enabled, session = {:?}", self);
+
+        Ok(())
+    }
+
     /// Assemble a packet with specified protocol id, packet id and data.
+    /// Protocol packets are compressed if compression is enabled for this
+    /// session and the data size reaches the threshold.
     /// Return concrete error if session is expired or the protocol id is
     /// invalid.
     fn prepare_packet(
@@ -515,6 +640,20 @@ impl Session {
             return Err(ErrorKind::Expired.into());
         }
 
+        if let Some(threshold) = self.compression_threshold {
+            if protocol.is_some() && data.len() >= threshold {
+                if let Some(compressed) = compress(&data)? {
+                    return Ok(SessionPacket::assemble_with_extensions(
+                        packet_id,
+                        self.metadata.peer_header_version,
+                        protocol,
+                        compressed,
+                        &[compression_extension()],
+                    ));
+                }
+            }
+        }
+
         Ok(SessionPacket::assemble(
             packet_id,
             self.metadata.peer_header_version,
@@ -601,6 +740,10 @@ impl Session {
         &mut self, io: &IoContext<Message>, host: &NetworkServiceInner,
     ) -> Result<(), Error> {
         debug!("Sending Hello, session = {:?}", self);
+        if self.expired() {
+            return Err(ErrorKind::Expired.into());
+        }
+
         let mut rlp = RlpStream::new_list(4);
         rlp.append(&host.metadata.network_id);
         rlp.append_list(&*host.metadata.protocols.read());
@@ -611,15 +754,26 @@ impl Session {
             &mut self.pos_public_key.as_ref().unwrap().1.to_bytes().to_vec(),
         );
         rlp.append(&key_bytes);
-        self.send_packet(
-            io,
-            None,
-            ProtocolVersion::default(),
+
+        // Advertise the compression capability via packet extension, which
+        // is ignored by peers that do not support it.
+        let mut extensions = Vec::new();
+        if host.config.compression_threshold.is_some() {
+            extensions.push(compression_extension());
+        }
+        if let Some(ref secrets) = self.handshake_secrets {
+            extensions.push(encryption_extension(secrets.ephemeral_public()));
+        }
+        let packet = SessionPacket::assemble_with_extensions(
             PACKET_HELLO,
+            self.metadata.peer_header_version,
+            None,
             rlp.drain(),
-            SendQueuePriority::High,
-        )
-        .map(|_| ())
+            &extensions,
+        );
+        self.connection_mut()
+            .send(io, packet, SendQueuePriority::High)
+            .map(|_| ())
     }
 
     /// Writable IO handler. Sends pending packets.
@@ -649,6 +803,7 @@ impl Session {
             last_read: format!("{:?}", self.last_read.elapsed()),
             last_write: format!("{:?}", self.last_write.0.elapsed()),
             last_write_status: format!("{:?}", self.last_write.1),
+            compression_enabled: self.compression_threshold.is_some(),
         }
     }
 
@@ -696,6 +851,7 @@ pub struct SessionDetails {
     pub last_read: String,
     pub last_write: String,
     pub last_write_status: String,
+    pub compression_enabled: bool,
 }
 
 /// MovableWrapper is a util to move a value out of a struct.
@@ -763,16 +919,36 @@ impl SessionPacket {
     // data + Option<protocol> + protocol_flag + packet_id
     fn assemble(
         id: u8, header_version: u8, protocol: Option<ProtocolId>,
-        mut data: Vec<u8>,
+        data: Vec<u8>,
     ) -> Vec<u8>
     {
+        Self::assemble_with_extensions(id, header_version, protocol, data, &[])
+    }
+
+    // data + extensions + Option<protocol> + header_byte + packet_id
+    fn assemble_with_extensions(
+        id: u8, header_version: u8, protocol: Option<ProtocolId>,
+        mut data: Vec<u8>, extensions: &[Vec<u8>],
+    ) -> Vec<u8>
+    {
+        // Extensions are parsed from the end of packet, so the first
+        // extension should be appended at last.
+        for (i, extension) in extensions.iter().rev().enumerate() {
+            assert!(extension.len() < 0x80, "extension is oversized");
+            data.extend_from_slice(extension);
+            let has_next_extension = (i != 0) as u8;
+            data.push(((extension.len() as u8) << 1) + has_next_extension);
+        }
+        let has_extension = !extensions.is_empty() as u8;
+
         let mut protocol_flag = 0;
         if let Some(protocol) = protocol {
             data.extend_from_slice(&protocol);
             protocol_flag = 1;
         }
 
-        let header_byte = (header_version << 1) + protocol_flag;
+        let header_byte =
+            (has_extension << 4) + (header_version << 1) + protocol_flag;
         data.push(header_byte);
         data.push(id);
 
@@ -855,6 +1031,10 @@ impl SessionPacket {
     ) -> Result<(Bytes, Vec<Vec<u8>>), Error> {
         let mut extensions = Vec::new();
         while has_extension {
+            if data.is_empty() {
+                debug!("failed to parse session packet, extension missed.");
+                bail!(ErrorKind::BadProtocol);
+            }
             let extension_byte = data.split_off(data.len() - 1)[0];
             let extension_len = (extension_byte >> 1) as usize;
             has_extension = (extension_byte & 1) != 0;
@@ -870,6 +1050,44 @@ impl SessionPacket {
     }
 }
 
+fn compression_extension() -> Vec<u8> {
+    vec![EXTENSION_COMPRESSION, COMPRESSION_ALGORITHM_SNAPPY]
+}
+
+fn has_compression_extension(extensions: &[Vec<u8>]) -> bool {
+    extensions.contains(&compression_extension())
+}
+
+fn encryption_extension(ephemeral_public: &Public) -> Vec<u8> {
+    let mut extension =
+        vec![EXTENSION_ENCRYPTION, ENCRYPTION_ALGORITHM_CHACHA20_POLY1305];
+    extension.extend_from_slice(ephemeral_public.as_bytes());
+    extension
+}
+
+/// Parse the encryption extension in Hello packet, and return the ephemeral
+/// public key of remote peer if it supports encryption.
+fn parse_encryption_extension(
+    extensions: &[Vec<u8>],
+) -> Result<Option<Public>, Error> {
+    let extension = match extensions
+        .iter()
+        .find(|extension| extension.first() == Some(&EXTENSION_ENCRYPTION))
+    {
+        Some(extension) => extension,
+        None => return Ok(None),
+    };
+
+    if extension.len() != 2 + Public::len_bytes()
+        || extension[1] != ENCRYPTION_ALGORITHM_CHACHA20_POLY1305
+    {
+        debug!("invalid encryption extension in Hello packet");
+        bail!(ErrorKind::BadProtocol);
+    }
+
+    Ok(Some(Public::from_slice(&extension[2..])))
+}
+
 impl fmt::Debug for SessionPacket {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -885,6 +1103,213 @@ impl fmt::Debug for SessionPacket {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        service::tests::{new_test_config, new_test_inner},
+        NetworkIoMessage,
+    };
+    use parking_lot::RwLock;
+    use std::{net::TcpListener as StdTcpListener, sync::Arc, thread};
+
+    const TEST_PROTOCOL: ProtocolId = *b"tst";
+
+    fn new_test_host(encryption_mode: EncryptionMode) -> NetworkServiceInner {
+        let mut config = new_test_config();
+        // Hello requires a valid endpoint with UDP port.
+        config.udp_port = None;
+        config.encryption_mode = encryption_mode;
+        let host = new_test_inner(&config);
+        let protocol = ProtocolInfo {
+            protocol: TEST_PROTOCOL,
+            version: ProtocolVersion(1),
+        };
+        host.metadata.protocols.write().push(protocol.clone());
+        host.metadata
+            .minimum_peer_protocol_version
+            .write()
+            .push(protocol);
+        host
+    }
+
+    fn test_io() -> IoContext<NetworkIoMessage> {
+        IoContext::new(IoChannel::disconnected(), 0)
+    }
+
+    /// Create an egress session of `egress` and an ingress session of
+    /// `ingress` over a loopback TCP connection.
+    fn connect(
+        egress: &NetworkServiceInner, ingress: &NetworkServiceInner,
+    ) -> [Arc<RwLock<Session>>; 2] {
+        let listener = StdTcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let egress_socket = TcpStream::connect(&address).unwrap();
+        let (ingress_socket, remote_address) = listener.accept().unwrap();
+        let ingress_socket = TcpStream::from_stream(ingress_socket).unwrap();
+
+        let io = test_io();
+        let egress_idx = egress
+            .sessions
+            .create(
+                egress_socket,
+                address,
+                Some(ingress.metadata.id()),
+                &io,
+                egress,
+            )
+            .unwrap();
+        let ingress_idx = ingress
+            .sessions
+            .create(ingress_socket, remote_address, None, &io, ingress)
+            .unwrap();
+        [
+            egress.sessions.get(egress_idx).unwrap(),
+            ingress.sessions.get(ingress_idx).unwrap(),
+        ]
+    }
+
+    /// Send the pending packets of the session, and read all the packets
+    /// received.
+    fn poll(
+        session: &RwLock<Session>, host: &NetworkServiceInner,
+    ) -> Result<Vec<SessionData>, Error> {
+        let io = test_io();
+        let mut session = session.write();
+        session.writable(&io)?;
+        let mut received = Vec::new();
+        loop {
+            match session.readable(&io, host)?.session_data {
+                SessionData::None => return Ok(received),
+                data => received.push(data),
+            }
+        }
+    }
+
+    /// Poll both sessions until both of them are ready, or both of them fail,
+    /// and return the error of each session if any.
+    fn exchange_hello(
+        hosts: [&NetworkServiceInner; 2], sessions: &[Arc<RwLock<Session>>; 2],
+    ) -> [Option<Error>; 2] {
+        let mut errors = [None, None];
+        for _ in 0..200 {
+            for i in 0..2 {
+                if errors[i].is_none() {
+                    if let Err(e) = poll(&sessions[i], hosts[i]) {
+                        errors[i] = Some(e);
+                    }
+                }
+            }
+            let ready = sessions.iter().all(|s| s.read().is_ready());
+            let failed = errors.iter().all(Option::is_some);
+            if (ready && errors.iter().all(Option::is_none)) || failed {
+                return errors;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("Hello not exchanged in time, errors = {:?}", errors);
+    }
+
+    fn is_encrypted(session: &RwLock<Session>) -> bool {
+        session.read().details().connection.encrypted
+    }
+
+    #[test]
+    fn test_session_encryption_negotiated() {
+        let egress = new_test_host(EncryptionMode::Optional);
+        let ingress = new_test_host(EncryptionMode::Required);
+        let sessions = connect(&egress, &ingress);
+
+        let errors = exchange_hello([&egress, &ingress], &sessions);
+        assert!(errors.iter().all(Option::is_none), "{:?}", errors);
+        assert!(is_encrypted(&sessions[0]));
+        assert!(is_encrypted(&sessions[1]));
+
+        // Both peers derive the same keys, so the encrypted packets are
+        // received in both directions.
+        for (from, to) in [(0, 1), (1, 0)].iter().cloned() {
+            let data = vec![from as u8; 100];
+            sessions[from]
+                .write()
+                .send_packet(
+                    &test_io(),
+                    Some(TEST_PROTOCOL),
+                    ProtocolVersion(1),
+                    PACKET_USER,
+                    data.clone(),
+                    SendQueuePriority::High,
+                )
+                .unwrap();
+            let hosts = [&egress, &ingress];
+            poll(&sessions[from], hosts[from]).unwrap();
+            let mut received = Vec::new();
+            for _ in 0..200 {
+                received.extend(poll(&sessions[to], hosts[to]).unwrap());
+                if !received.is_empty() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(5));
+            }
+            match received.as_slice() {
+                [SessionData::Message {
+                    data: received_data,
+                    protocol,
+                }] => {
+                    assert_eq!(received_data, &data);
+                    assert_eq!(protocol, &TEST_PROTOCOL);
+                }
+                _ => panic!("expect one message, received {}", received.len()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_session_encryption_optional() {
+        let egress = new_test_host(EncryptionMode::Disabled);
+        let ingress = new_test_host(EncryptionMode::Optional);
+        let sessions = connect(&egress, &ingress);
+
+        let errors = exchange_hello([&egress, &ingress], &sessions);
+        assert!(errors.iter().all(Option::is_none), "{:?}", errors);
+        assert!(!is_encrypted(&sessions[0]));
+        assert!(!is_encrypted(&sessions[1]));
+    }
+
+    #[test]
+    fn test_session_encryption_required() {
+        let egress = new_test_host(EncryptionMode::Disabled);
+        let ingress = new_test_host(EncryptionMode::Required);
+        let sessions = connect(&egress, &ingress);
+
+        // The ingress peer disconnects, and the egress peer receives the
+        // reason.
+        for error in exchange_hello([&egress, &ingress], &sessions).iter() {
+            match error.as_ref().map(Error::kind) {
+                Some(ErrorKind::Disconnect(
+                    DisconnectReason::EncryptionRequired,
+                )) => {}
+                kind => panic!("unexpected error {:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn test_session_encryption_extension_stripped() {
+        let mut egress = new_test_host(EncryptionMode::Optional);
+        let ingress = new_test_host(EncryptionMode::Optional);
+        let sessions = connect(&egress, &ingress);
+
+        // The handshake nonce of egress peer is marked, but its Hello is sent
+        // without the encryption extension, as if stripped by a man in the
+        // middle. The ingress peer disconnects instead of falling back to
+        // plaintext.
+        egress.config.encryption_mode = EncryptionMode::Disabled;
+        for error in exchange_hello([&egress, &ingress], &sessions).iter() {
+            match error.as_ref().map(Error::kind) {
+                Some(ErrorKind::Disconnect(
+                    DisconnectReason::EncryptionMismatch,
+                )) => {}
+                kind => panic!("unexpected error {:?}", kind),
+            }
+        }
+    }
 
     #[test]
     fn test_packet_assemble() {
@@ -901,6 +1326,36 @@ mod tests {
         assert_eq!(packet, vec![2, 4, 8, 8, 8, 1, 6]);
     }
 
+    #[test]
+    fn test_packet_with_extensions() {
+        let packet = SessionPacket::assemble_with_extensions(
+            PACKET_USER,
+            PACKET_HEADER_VERSION,
+            Some([8; 3]),
+            vec![2, 4],
+            &[vec![5], vec![6, 7]],
+        );
+        assert_eq!(
+            packet,
+            vec![2, 4, 6, 7, 4, 5, 3, 8, 8, 8, 0x11, PACKET_USER]
+        );
+
+        let packet = SessionPacket::parse(packet.into()).unwrap();
+        assert_eq!(
+            packet,
+            SessionPacket {
+                id: PACKET_USER,
+                header_version: 0,
+                protocol: Some([8; 3]),
+                data: vec![2, 4].into(),
+                extensions: vec![vec![5], vec![6, 7]],
+            }
+        );
+
+        // extension flag set without extension
+        assert!(SessionPacket::parse(vec![0x10, 20].into()).is_err());
+    }
+
     #[test]
     fn test_packet_parse() {
         // packet id missed
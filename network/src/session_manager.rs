@@ -41,8 +41,8 @@ pub struct SessionManager {
     /// Token id offset.
     offset: usize,
 
-    /// used to limit the ingress sessions.
-    max_ingress_sessions: usize,
+    /// used to limit the ingress sessions, which can be adjusted at runtime.
+    max_ingress_sessions: AtomicUsize,
     cur_ingress_sessions: AtomicUsize,
 
     /// session indices
@@ -69,7 +69,7 @@ impl SessionManager {
             sessions: RwLock::new(Slab::with_capacity(capacity)),
             offset,
             capacity,
-            max_ingress_sessions,
+            max_ingress_sessions: AtomicUsize::new(max_ingress_sessions),
             cur_ingress_sessions: AtomicUsize::new(0),
             node_id_index: RwLock::new(HashMap::new()),
             ip_limit: RwLock::new(new_session_ip_limit(ip_limit_config)),
@@ -114,6 +114,18 @@ impl SessionManager {
         self.tag_index.read().count_with_tag(key, value)
     }
 
+    /// Get the maximum number of ingress sessions.
+    pub fn max_ingress_sessions(&self) -> usize {
+        self.max_ingress_sessions.load(Ordering::Relaxed)
+    }
+
+    /// Set the maximum number of ingress sessions. The existing sessions are
+    /// not affected.
+    pub fn set_max_ingress_sessions(&self, max_ingress_sessions: usize) {
+        self.max_ingress_sessions
+            .store(max_ingress_sessions, Ordering::Relaxed);
+    }
+
     /// Retrieves the session count of handshakes, egress and ingress.
     pub fn stat(&self) -> (usize, usize, usize) {
         let mut handshakes = 0;
@@ -168,11 +180,12 @@ impl SessionManager {
 
         // limits ingress sessions whose node id is `None`.
         let ingress = self.cur_ingress_sessions.load(Ordering::Relaxed);
-        if id.is_none() && ingress >= self.max_ingress_sessions {
+        let max_ingress = self.max_ingress_sessions();
+        if id.is_none() && ingress >= max_ingress {
             debug!("SessionManager.create: leave on maximum ingress sessions reached");
             return Err(format!(
                 "maximum ingress sessions reached, current = {}, max = {}",
-                ingress, self.max_ingress_sessions
+                ingress, max_ingress
             ));
         }
 
@@ -53,6 +53,14 @@ pub enum FilterError {
         max_gap: u64,
     },
 
+    /// Too many epochs in the range may contain matching logs according to
+    /// the log bloom index.
+    TooManyCandidateEpochs {
+        from_epoch: u64,
+        to_epoch: u64,
+        max_epochs: u64,
+    },
+
     /// Roots for verifying the requested epochs are unavailable.
     UnableToVerify {
         epoch: u64,
@@ -128,6 +136,15 @@ impl fmt::Display for FilterError {
                     from_epoch, to_epoch, max_gap
                 }
             }
+            TooManyCandidateEpochs {
+                from_epoch,
+                to_epoch,
+                max_epochs,
+            } => format! {
+                "More than {} epochs between from_epoch and to_epoch may contain matching logs \
+                (from: {}, to: {})",
+                max_epochs, from_epoch, to_epoch
+            },
             UnableToVerify {
                 epoch,
                 latest_verifiable,
@@ -181,6 +198,17 @@ pub enum LogFilter {
     },
 }
 
+/// The position of a page in the results of an epoch log filter. The logs
+/// which are already returned are those in the epochs after `epoch` and the
+/// last `skip` matching logs of `epoch`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogCursor {
+    pub epoch: u64,
+    /// The pivot hash of `epoch`, to detect pivot chain reorgs between pages.
+    pub pivot_hash: H256,
+    pub skip: usize,
+}
+
 /// Log event Filter.
 #[derive(Clone, Debug, PartialEq)]
 pub struct LogFilterParams {
@@ -0,0 +1,66 @@
+// Copyright 2019 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use clap;
+use client::{configuration::Configuration, db_check::check_db};
+
+#[derive(Debug, PartialEq)]
+pub enum DbCmd {
+    Check(CheckDb),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CheckDb {
+    pub from_epoch: Option<u64>,
+    pub to_epoch: Option<u64>,
+    pub repair: bool,
+}
+
+impl CheckDb {
+    pub fn new(matches: &clap::ArgMatches) -> Result<Self, String> {
+        Ok(Self {
+            from_epoch: parse_epoch(matches, "from-epoch")?,
+            to_epoch: parse_epoch(matches, "to-epoch")?,
+            repair: matches.is_present("repair"),
+        })
+    }
+}
+
+fn parse_epoch(
+    matches: &clap::ArgMatches, name: &str,
+) -> Result<Option<u64>, String> {
+    matches
+        .value_of(name)
+        .map(|epoch| {
+            epoch
+                .parse()
+                .map_err(|e| format!("Invalid --{} {}: {}", name, epoch, e))
+        })
+        .transpose()
+}
+
+pub fn execute(cmd: DbCmd, conf: &Configuration) -> Result<String, String> {
+    match cmd {
+        DbCmd::Check(check_cmd) => check(check_cmd, conf),
+    }
+}
+
+fn check(check_cmd: CheckDb, conf: &Configuration) -> Result<String, String> {
+    if let (Some(from_epoch), Some(to_epoch)) =
+        (check_cmd.from_epoch, check_cmd.to_epoch)
+    {
+        if from_epoch > to_epoch {
+            return Err(format!(
+                "--from-epoch {} is larger than --to-epoch {}",
+                from_epoch, to_epoch
+            ));
+        }
+    }
+    check_db(
+        conf,
+        check_cmd.from_epoch,
+        check_cmd.to_epoch,
+        check_cmd.repair,
+    )
+}
@@ -16,14 +16,13 @@ use client::{
     configuration::Configuration,
     full::FullClient,
     light::LightClient,
+    logging,
 };
-use command::account::{AccountCmd, ImportAccounts, ListAccounts, NewAccount};
-use log::{info, LevelFilter};
-use log4rs::{
-    append::{console::ConsoleAppender, file::FileAppender},
-    config::{Appender, Config as LogConfig, Logger, Root},
-    encode::pattern::PatternEncoder,
+use command::{
+    account::{AccountCmd, ImportAccounts, ListAccounts, NewAccount},
+    db::{CheckDb, DbCmd},
 };
+use log::info;
 use network::throttling::THROTTLING_SERVICE;
 use parking_lot::{Condvar, Mutex};
 use std::sync::Arc;
@@ -64,71 +63,7 @@ fn main() -> Result<(), String> {
 
     let conf = Configuration::parse(&matches)?;
 
-    // If log_conf is provided, use it for log configuration and ignore
-    // log_file and log_level. Otherwise, set stdout to INFO and set
-    // all our crate log to log_level.
-    match conf.raw_conf.log_conf {
-        Some(ref log_conf) => {
-            log4rs::init_file(log_conf, Default::default()).map_err(|e| {
-                format!(
-                    "failed to initialize log with log config file: {:?}",
-                    e
-                )
-            })?;
-        }
-        None => {
-            let mut conf_builder =
-                LogConfig::builder().appender(Appender::builder().build(
-                    "stdout",
-                    Box::new(ConsoleAppender::builder().build()),
-                ));
-            let mut root_builder = Root::builder().appender("stdout");
-            if let Some(ref log_file) = conf.raw_conf.log_file {
-                conf_builder =
-                    conf_builder.appender(Appender::builder().build(
-                        "logfile",
-                        Box::new(
-                            FileAppender::builder().encoder(
-                                Box::new(
-                                    PatternEncoder::new(
-                                        "{d} {h({l}):5.5} {T:<20.20} {t:12.12} - {m}{n}")))
-                                .build(log_file)
-                                .map_err(
-                                    |e| format!("failed to build log pattern: {:?}", e))?,
-                        ),
-                    ));
-                root_builder = root_builder.appender("logfile");
-            };
-            // Should add new crate names here
-            for crate_name in [
-                "blockgen",
-                "cfxcore",
-                "cfx_statedb",
-                "cfx_storage",
-                "conflux",
-                "db",
-                "keymgr",
-                "network",
-                "txgen",
-                "client",
-                "primitives",
-                "io",
-            ]
-            .iter()
-            {
-                conf_builder = conf_builder.logger(
-                    Logger::builder()
-                        .build(*crate_name, conf.raw_conf.log_level),
-                );
-            }
-            let log_config = conf_builder
-                .build(root_builder.build(LevelFilter::Info))
-                .map_err(|e| format!("failed to build log config: {:?}", e))?;
-            log4rs::init_config(log_config).map_err(|e| {
-                format!("failed to initialize log with config: {:?}", e)
-            })?;
-        }
-    };
+    logging::init_logging(&conf)?;
 
     THROTTLING_SERVICE.write().initialize(
         conf.raw_conf.egress_queue_capacity,
@@ -202,6 +137,19 @@ fn handle_sub_command(matches: &ArgMatches) -> Result<Option<String>, String> {
         return Ok(Some(execute_output));
     }
 
+    // db sub-commands
+    if let ("db", Some(db_matches)) = matches.subcommand() {
+        let db_cmd = match db_matches.subcommand() {
+            ("check", Some(check_matches)) => {
+                DbCmd::Check(CheckDb::new(check_matches)?)
+            }
+            _ => unreachable!(),
+        };
+        let conf = Configuration::parse(matches)?;
+        let execute_output = command::db::execute(db_cmd, &conf)?;
+        return Ok(Some(execute_output));
+    }
+
     // general RPC commands
     let mut subcmd_matches = matches;
     while let Some(m) = subcmd_matches.subcommand().1 {
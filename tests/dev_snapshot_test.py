@@ -0,0 +1,54 @@
+#!/usr/bin/env python3
+"""Test that evm_revert rolls the dev chain back to the state of evm_snapshot.
+"""
+from conflux.rpc import RpcClient
+from test_framework.test_framework import ConfluxTestFramework
+from test_framework.util import *
+
+
+class DevSnapshotTest(ConfluxTestFramework):
+    def set_test_params(self):
+        self.num_nodes = 1
+        self.conf_parameters["mode"] = '"dev"'
+
+    def setup_network(self):
+        self.setup_nodes()
+
+    def run_test(self):
+        node = self.nodes[0]
+        rpc = RpcClient(node)
+        receiver = rpc.rand_addr()
+        value = 10 ** 18
+
+        # Automine packs and executes the transaction before the RPC returns.
+        rpc.send_tx(rpc.new_tx(receiver=receiver, value=value))
+        assert_equal(rpc.get_balance(receiver), value)
+
+        epoch = rpc.epoch_number()
+        genesis_balance = rpc.get_balance(rpc.GENESIS_ADDR)
+        total_issued = rpc.get_supply_info()["totalIssued"]
+        snapshot_id = node.evm_snapshot()
+
+        tx_hash = rpc.send_tx(rpc.new_tx(receiver=receiver, value=value))
+        assert_equal(rpc.get_balance(receiver), 2 * value)
+        for _ in range(3):
+            node.evm_mine()
+        assert_greater_than(int(rpc.get_supply_info()["totalIssued"], 0),
+                            int(total_issued, 0))
+
+        assert node.evm_revert(snapshot_id)
+        assert_equal(rpc.epoch_number(), epoch)
+        assert_equal(rpc.get_balance(rpc.GENESIS_ADDR), genesis_balance)
+        assert_equal(rpc.get_balance(receiver), value)
+        assert_equal(rpc.get_supply_info()["totalIssued"], total_issued)
+        # The snapshot can only be reverted to once.
+        assert not node.evm_revert(snapshot_id)
+
+        # The reverted transaction is pending again.
+        node.evm_mine()
+        assert checktx(node, tx_hash)
+        assert_equal(rpc.get_balance(receiver), 2 * value)
+
+
+if __name__ == '__main__':
+    DevSnapshotTest().main()
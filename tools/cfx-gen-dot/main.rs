@@ -2,11 +2,16 @@
 // Conflux is free software and distributed under GNU General Public License.
 // See http://www.gnu.org/licenses/
 
-use cfx_types::{hexstr_to_h256, H256};
+use cfx_types::{hexstr_to_h256, H256, U64};
+use client::rpc::types::ConsensusBlockInfo;
+use futures::future::Future;
+use jsonrpc_core::Params;
+use jsonrpc_core_client::{transports::http::connect, RawClient};
+use jsonrpc_http_server::hyper::rt;
 use primitives::Block;
 use std::{
-    collections::{HashSet, VecDeque},
-    sync::Arc,
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::{mpsc::channel, Arc},
 };
 
 fn open_db(db_path: &str) -> std::io::Result<Arc<db::SystemDB>> {
@@ -83,10 +88,152 @@ fn print_graph(db: &Arc<db::SystemDB>, from: &H256, max_depth: u32) {
     println!("}}");
 }
 
-struct Config {
-    db_path: String,
-    from_block: H256,
-    max_depth: u32,
+fn fetch_consensus_dag(
+    url: &str, from_epoch: u64, to_epoch: u64, with_anticone: bool,
+) -> Result<Vec<ConsensusBlockInfo>, String> {
+    let (sender, receiver) = channel();
+    let params = Params::Array(vec![
+        serde_json::to_value(U64::from(from_epoch)).unwrap(),
+        serde_json::to_value(U64::from(to_epoch)).unwrap(),
+        serde_json::Value::Bool(with_anticone),
+    ]);
+
+    rt::run(
+        connect::<RawClient>(url)
+            .and_then(move |client| {
+                client.call_method("consensus_dag", params).then(
+                    move |result| {
+                        sender.send(result).expect("channel should work fine");
+                        Ok(())
+                    },
+                )
+            })
+            .map_err(|e| eprintln!("future error: {:?}", e)),
+    );
+
+    let result = receiver
+        .recv()
+        .map_err(|_| format!("failed to connect to {}", url))?
+        .map_err(|e| format!("{:?}", e))?;
+    serde_json::from_value(result).map_err(|e| format!("{:?}", e))
+}
+
+fn fmt_block_label(block: &ConsensusBlockInfo) -> String {
+    let mut flags = Vec::new();
+    if block.is_pivot {
+        flags.push("pivot");
+    }
+    if block.is_on_timer_chain {
+        flags.push("timer-chain");
+    } else if block.is_timer {
+        flags.push("timer");
+    }
+    if block.is_heavy {
+        flags.push("heavy");
+    }
+    if block.adaptive {
+        flags.push("adaptive");
+    }
+    if block.partial_invalid {
+        flags.push("partial-invalid");
+    }
+    if block.pending {
+        flags.push("pending");
+    }
+
+    let mut label = format!(
+        "{}\\nheight={} blame={}\\nweight={} subtree={}\\npast={}",
+        fmt_hash(&block.hash),
+        block.height,
+        block.blame,
+        block.weight,
+        block.subtree_weight,
+        block.past_size,
+    );
+    if let Some(anticone_size) = &block.anticone_size {
+        label += &format!(" anticone={}", anticone_size);
+    }
+    if let Some(era) = &block.era_block_hash {
+        label += &format!("\\nera={}", fmt_hash(era));
+    }
+    if !flags.is_empty() {
+        label += &format!("\\n[{}]", flags.join(","));
+    }
+    label
+}
+
+fn print_consensus_graph(blocks: &[ConsensusBlockInfo]) {
+    println!("digraph G {{");
+    println!("rankdir=\"RL\";");
+    println!("node [shape=box];");
+
+    let mut epochs: BTreeMap<u64, Vec<&ConsensusBlockInfo>> = BTreeMap::new();
+    for block in blocks {
+        epochs
+            .entry(block.epoch_number.map_or(u64::MAX, |e| e.as_u64()))
+            .or_insert_with(Vec::new)
+            .push(block);
+    }
+
+    for (epoch, epoch_blocks) in &epochs {
+        println!("subgraph \"cluster_{}\" {{", epoch);
+        println!("label=\"epoch {}\";", epoch);
+        for block in epoch_blocks {
+            let mut style = vec!["rounded"];
+            if block.is_pivot {
+                style.push("filled");
+            }
+            if block.is_on_timer_chain {
+                style.push("bold");
+            }
+            if block.adaptive {
+                style.push("dashed");
+            }
+            let color = if block.partial_invalid {
+                "red"
+            } else {
+                "black"
+            };
+            println!(
+                "\"{}\" [label=\"{}\", style=\"{}\", color={}, fillcolor=lightblue];",
+                fmt_hash(&block.hash),
+                fmt_block_label(block),
+                style.join(","),
+                color
+            );
+        }
+        println!("}}");
+    }
+
+    // Only the edges between the rendered blocks are printed.
+    let rendered: HashSet<H256> = blocks.iter().map(|b| b.hash).collect();
+    for block in blocks {
+        if rendered.contains(&block.parent_hash) {
+            print_edge(&block.hash, &block.parent_hash);
+        }
+        for referee in &block.referee_hashes {
+            if rendered.contains(referee) {
+                print_ref_edge(&block.hash, referee);
+            }
+        }
+    }
+
+    println!("}}");
+}
+
+enum Config {
+    Db {
+        db_path: String,
+        from_block: H256,
+        max_depth: u32,
+    },
+    Rpc {
+        url: String,
+        from_epoch: u64,
+        to_epoch: u64,
+        with_anticone: bool,
+        json: bool,
+    },
 }
 
 // from /src/main.rs
@@ -101,13 +248,20 @@ fn parse_config() -> Config {
     let matches = clap::App::new("cfx-gen-dot")
         .version("0.1")
         .about(
-"Generate Graphviz dot files from your local blockchain db
+"Generate Graphviz dot files from your local blockchain db, or from the
+consensus graph of a running node with consensus annotations
 Example usage:
     cfx-gen-dot
         --db-path ./run/blockchain_db
         --from-block 0x3159d8d9b125a738cc226a9b85f6d7fa0da1567018c6771f9bf658e83496834d
         --max-depth 10000
         > graph.dot
+    cfx-gen-dot
+        --rpc-url http://127.0.0.1:12539
+        --from-epoch 1000
+        --to-epoch 1020
+        --with-anticone
+        > graph.dot
     dot -Tsvg graph.dot -o graph.svg")
         .arg(
             clap::Arg::with_name("db-path")
@@ -115,7 +269,9 @@ Example usage:
                 .value_name("PATH")
                 .help("Specifies local blockchain db directory")
                 .takes_value(true)
-                .required(true),
+                .required_unless("rpc-url")
+                .conflicts_with("rpc-url")
+                .requires_all(&["from-block", "max-depth"]),
         )
         .arg(
             clap::Arg::with_name("from-block")
@@ -123,7 +279,7 @@ Example usage:
                 .value_name("HASH")
                 .help("Sets starting block of DAG traversal")
                 .takes_value(true)
-                .required(true),
+                .requires("db-path"),
         )
         .arg(
             clap::Arg::with_name("max-depth")
@@ -131,11 +287,65 @@ Example usage:
                 .value_name("NUM")
                 .help("Sets maximum depth for traversal")
                 .takes_value(true)
-                .required(true)
+                .requires("db-path")
                 .validator(from_str_validator::<u32>),
         )
+        .arg(
+            clap::Arg::with_name("rpc-url")
+                .long("rpc-url")
+                .value_name("URL")
+                .help("Specifies the local RPC of a running node with the debug APIs enabled")
+                .takes_value(true)
+                .requires_all(&["from-epoch", "to-epoch"]),
+        )
+        .arg(
+            clap::Arg::with_name("from-epoch")
+                .long("from-epoch")
+                .value_name("NUM")
+                .help("Sets the first epoch to render")
+                .takes_value(true)
+                .requires("rpc-url")
+                .validator(from_str_validator::<u64>),
+        )
+        .arg(
+            clap::Arg::with_name("to-epoch")
+                .long("to-epoch")
+                .value_name("NUM")
+                .help("Sets the last epoch to render")
+                .takes_value(true)
+                .requires("rpc-url")
+                .validator(from_str_validator::<u64>),
+        )
+        .arg(
+            clap::Arg::with_name("with-anticone")
+                .long("with-anticone")
+                .help("Annotates the blocks with their anticone sizes, which are expensive to compute, so fewer epochs can be rendered")
+                .requires("rpc-url"),
+        )
+        .arg(
+            clap::Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Sets the output format of the epoch range")
+                .takes_value(true)
+                .possible_values(&["dot", "json"])
+                .default_value("dot")
+                .requires("rpc-url"),
+        )
         .get_matches();
 
+    if let Some(url) = matches.value_of("rpc-url") {
+        let parse_epoch =
+            |name| matches.value_of(name).unwrap().parse::<u64>().unwrap();
+        return Config::Rpc {
+            url: String::from(url),
+            from_epoch: parse_epoch("from-epoch"),
+            to_epoch: parse_epoch("to-epoch"),
+            with_anticone: matches.is_present("with-anticone"),
+            json: matches.value_of("format") == Some("json"),
+        };
+    }
+
     let db_path = matches.value_of("db-path").unwrap();
     let max_depth = matches
         .value_of("max-depth")
@@ -153,7 +363,7 @@ Example usage:
         hexstr_to_h256(from)
     };
 
-    Config {
+    Config::Db {
         db_path: String::from(db_path),
         from_block,
         max_depth,
@@ -161,7 +371,43 @@ Example usage:
 }
 
 fn main() {
-    let config = parse_config();
-    let db = open_db(&config.db_path).unwrap();
-    print_graph(&db, &config.from_block, config.max_depth);
+    match parse_config() {
+        Config::Db {
+            db_path,
+            from_block,
+            max_depth,
+        } => {
+            let db = open_db(&db_path).unwrap();
+            print_graph(&db, &from_block, max_depth);
+        }
+        Config::Rpc {
+            url,
+            from_epoch,
+            to_epoch,
+            with_anticone,
+            json,
+        } => {
+            let blocks = match fetch_consensus_dag(
+                &url,
+                from_epoch,
+                to_epoch,
+                with_anticone,
+            ) {
+                Ok(blocks) => blocks,
+                Err(e) => {
+                    eprintln!("failed to fetch consensus DAG: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&blocks)
+                        .expect("serialization cannot fail; qed")
+                );
+            } else {
+                print_consensus_graph(&blocks);
+            }
+        }
+    }
 }
@@ -0,0 +1,126 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The JSON format of the filled GeneralStateTests of `ethereum/tests`. The
+//! VMTests in `GeneralStateTests/VMTests` share the same format.
+//!
+//! Numbers and byte strings are kept as the hex strings in the fixture and
+//! parsed with the helpers at the bottom of this file, because the fixtures
+//! are not consistent about leading zeros.
+
+use cfx_types::{Address, H256, U256};
+use rustc_hex::FromHex;
+use serde_derive::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path, str::FromStr};
+
+/// A fixture file maps test names to tests.
+pub type StateTestFile = BTreeMap<String, StateTest>;
+
+#[derive(Debug, Deserialize)]
+pub struct StateTest {
+    pub env: TestEnv,
+    pub pre: BTreeMap<String, TestAccount>,
+    pub transaction: TestTransaction,
+    /// The expected results, keyed by the fork name.
+    pub post: BTreeMap<String, Vec<PostState>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestEnv {
+    pub current_coinbase: String,
+    pub current_difficulty: String,
+    pub current_gas_limit: String,
+    pub current_number: String,
+    pub current_timestamp: String,
+    pub previous_hash: Option<String>,
+    pub current_base_fee: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TestAccount {
+    pub balance: String,
+    pub code: String,
+    pub nonce: String,
+    pub storage: BTreeMap<String, String>,
+}
+
+/// The transaction template. A test case picks one element from each of
+/// `data`, `gas_limit` and `value` with the `indexes` of a `PostState`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestTransaction {
+    pub data: Vec<String>,
+    pub gas_limit: Vec<String>,
+    pub gas_price: Option<String>,
+    pub max_fee_per_gas: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    pub access_lists: Option<serde_json::Value>,
+    pub nonce: String,
+    pub secret_key: String,
+    /// The receiver, or an empty string for contract creation.
+    pub to: String,
+    pub value: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostState {
+    /// The root of the post state trie.
+    pub hash: String,
+    /// The keccak hash of the RLP encoded logs.
+    pub logs: String,
+    pub indexes: PostStateIndexes,
+    /// Set when the transaction is invalid and must not be executed.
+    pub expect_exception: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostStateIndexes {
+    pub data: usize,
+    pub gas: usize,
+    pub value: usize,
+}
+
+pub fn load_test_file(path: &Path) -> Result<StateTestFile, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {:?}: {}", path, e))
+}
+
+fn strip_hex_prefix(s: &str) -> &str { s.strip_prefix("0x").unwrap_or(s) }
+
+pub fn parse_u256(s: &str) -> Result<U256, String> {
+    let digits = strip_hex_prefix(s);
+    if digits.is_empty() {
+        return Ok(U256::zero());
+    }
+    U256::from_str_radix(digits, 16)
+        .map_err(|e| format!("Invalid number {}: {:?}", s, e))
+}
+
+pub fn parse_u64(s: &str) -> Result<u64, String> {
+    let value = parse_u256(s)?;
+    if value > U256::from(u64::max_value()) {
+        return Err(format!("Number {} overflows u64", s));
+    }
+    Ok(value.as_u64())
+}
+
+pub fn parse_bytes(s: &str) -> Result<Vec<u8>, String> {
+    strip_hex_prefix(s)
+        .from_hex()
+        .map_err(|e| format!("Invalid hex string {}: {}", s, e))
+}
+
+pub fn parse_address(s: &str) -> Result<Address, String> {
+    Address::from_str(strip_hex_prefix(s))
+        .map_err(|e| format!("Invalid address {}: {}", s, e))
+}
+
+pub fn parse_h256(s: &str) -> Result<H256, String> {
+    H256::from_str(strip_hex_prefix(s))
+        .map_err(|e| format!("Invalid hash {}: {}", s, e))
+}
@@ -0,0 +1,26 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! Run the GeneralStateTests of `ethereum/tests` against the eSpace EVM.
+//!
+//! Each test case builds the pre state in an in-memory `StateDb`, executes
+//! the transaction through the `Executive` with the eSpace `Spec`, and
+//! compares the Ethereum state root and logs hash of the post state with the
+//! expected ones. The runner refunds the minimum charge of 3/4 of the gas
+//! limit of eSpace before the comparison, so that the gas charged matches
+//! Ethereum. Conflux diverges from Ethereum on purpose in a few other places,
+//! e.g. the gas refunds, and the tests affected are listed in a skip list.
+
+pub mod fixture;
+pub mod runner;
+pub mod skip;
+pub mod storage;
+pub mod trie;
+
+pub use self::{
+    runner::{
+        CaseReport, CaseResult, RunnerConfig, StateTestRunner, DEFAULT_FORK,
+    },
+    skip::SkipList,
+};
@@ -0,0 +1,95 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use evm_statetests::{
+    CaseResult, RunnerConfig, SkipList, StateTestRunner, DEFAULT_FORK,
+};
+use std::{path::Path, process};
+
+fn parse_config(matches: &clap::ArgMatches) -> Result<RunnerConfig, String> {
+    let forks = match matches.values_of("fork") {
+        Some(forks) => forks.map(String::from).collect(),
+        None => vec![DEFAULT_FORK.to_string()],
+    };
+    let skip_list = match matches.value_of("skip-list") {
+        Some(path) => SkipList::load(Path::new(path))?,
+        None => Default::default(),
+    };
+    Ok(RunnerConfig { forks, skip_list })
+}
+
+fn main() {
+    let matches = clap::App::new("evm-statetests")
+        .version("0.1")
+        .about(
+"Run the GeneralStateTests of ethereum/tests against the eSpace EVM
+Example usage:
+    evm-statetests
+        --skip-list tools/evm-statetests/skip_list.txt
+        ../ethereum-tests/LegacyTests/Constantinople/GeneralStateTests")
+        .arg(
+            clap::Arg::with_name("path")
+                .value_name("PATH")
+                .help("Fixture files, or directories searched for them")
+                .multiple(true)
+                .required(true),
+        )
+        .arg(
+            clap::Arg::with_name("fork")
+                .long("fork")
+                .value_name("NAME")
+                .help("Checks the expected results of the fork [default: ConstantinopleFix]")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            clap::Arg::with_name("skip-list")
+                .long("skip-list")
+                .value_name("FILE")
+                .help("Skips the tests listed in the file")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .help("Prints the passed and skipped tests too"),
+        )
+        .get_matches();
+
+    let config = parse_config(&matches).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    let verbose = matches.is_present("verbose");
+    let runner = StateTestRunner::new(config);
+
+    let (mut passed, mut skipped, mut unsupported, mut failed) = (0, 0, 0, 0);
+    for path in matches.values_of("path").unwrap() {
+        let reports = runner.run_path(Path::new(path)).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        });
+        for report in reports {
+            match report.result {
+                CaseResult::Passed => passed += 1,
+                CaseResult::Skipped(_) => skipped += 1,
+                CaseResult::Unsupported(_) => unsupported += 1,
+                CaseResult::Failed(_) => failed += 1,
+            }
+            if verbose || matches!(report.result, CaseResult::Failed(_)) {
+                println!("{}", report);
+            }
+        }
+    }
+
+    println!(
+        "{} passed, {} failed, {} skipped, {} unsupported",
+        passed, failed, skipped, unsupported
+    );
+    if failed > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,531 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::{
+    fixture::{
+        load_test_file, parse_address, parse_bytes, parse_h256, parse_u256,
+        parse_u64, PostState, StateTest,
+    },
+    skip::SkipList,
+    storage::InMemoryStorage,
+    trie::{ethereum_logs_hash, ethereum_state_root, EthereumAccount},
+};
+use cfx_state::{
+    state_trait::{StateOpsTrait, StateTrait},
+    CleanupMode,
+};
+use cfx_statedb::{Error as DbError, StateDbGeneric};
+use cfx_types::{Address, AddressSpaceUtil, Space, H256, U256, U512};
+use cfxcore::{
+    executive::{
+        gas_required_for, ExecutionOutcome, ExecutiveGeneric, TransactOptions,
+    },
+    machine::{new_machine_with_builtin, Machine},
+    spec::CommonParams,
+    state::StateGeneric,
+    vm::{Env, Spec},
+    vm_factory::VmFactory,
+};
+use cfxkey::Secret;
+use primitives::{
+    Action, Eip155Transaction, LogEntry, SignedTransaction, SkipInputCheck,
+    StorageKey, StorageKeyWithSpace, Transaction,
+};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// The fork of the expected results to check against. eSpace prices the
+/// transaction data like Ethereum before Istanbul and has no EIP-2929 access
+/// lists, so Petersburg is the closest.
+pub const DEFAULT_FORK: &str = "ConstantinopleFix";
+
+type State = StateGeneric<InMemoryStorage>;
+
+pub struct RunnerConfig {
+    /// The forks whose expected results are checked.
+    pub forks: Vec<String>,
+    pub skip_list: SkipList,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            forks: vec![DEFAULT_FORK.to_string()],
+            skip_list: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CaseResult {
+    Passed,
+    /// Skipped by the skip list, with the reason.
+    Skipped(String),
+    /// The test uses a feature eSpace doesn't have, e.g. typed transactions.
+    Unsupported(String),
+    Failed(String),
+}
+
+/// The result of one post state, i.e. one fork and one combination of the
+/// transaction data, gas limit and value.
+#[derive(Debug)]
+pub struct CaseReport {
+    pub name: String,
+    pub result: CaseResult,
+}
+
+impl fmt::Display for CaseReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.result {
+            CaseResult::Passed => write!(f, "{}: passed", self.name),
+            CaseResult::Skipped(reason) => {
+                write!(f, "{}: skipped ({})", self.name, reason)
+            }
+            CaseResult::Unsupported(reason) => {
+                write!(f, "{}: unsupported ({})", self.name, reason)
+            }
+            CaseResult::Failed(reason) => {
+                write!(f, "{}: FAILED {}", self.name, reason)
+            }
+        }
+    }
+}
+
+pub struct StateTestRunner {
+    config: RunnerConfig,
+    machine: Machine,
+}
+
+impl StateTestRunner {
+    pub fn new(config: RunnerConfig) -> Self {
+        Self {
+            config,
+            machine: new_machine_with_builtin(
+                CommonParams::default(),
+                VmFactory::new(1024 * 32),
+            ),
+        }
+    }
+
+    /// Run the fixture file at `root`, or all the fixture files under the
+    /// directory `root`.
+    pub fn run_path(&self, root: &Path) -> Result<Vec<CaseReport>, String> {
+        let mut files = Vec::new();
+        collect_fixture_files(root, &mut files)?;
+        files.sort();
+        let mut reports = Vec::new();
+        for path in files {
+            let file = match path.strip_prefix(root) {
+                Ok(relative) if !relative.as_os_str().is_empty() => relative,
+                _ => path.file_name().map(Path::new).unwrap_or(path.as_path()),
+            };
+            // Use `/` in the skip list patterns on all platforms.
+            let file = file
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            reports.extend(self.run_file(&path, &file)?);
+        }
+        Ok(reports)
+    }
+
+    /// Run all the tests in a fixture file. `file` is the path relative to
+    /// the test root, which is matched against the skip list.
+    pub fn run_file(
+        &self, path: &Path, file: &str,
+    ) -> Result<Vec<CaseReport>, String> {
+        let tests = load_test_file(path)?;
+        let mut reports = Vec::new();
+        for (test_name, test) in &tests {
+            let skip_reason =
+                self.config.skip_list.skip_reason(file, test_name);
+            for fork in &self.config.forks {
+                for (index, post) in
+                    test.post.get(fork).into_iter().flatten().enumerate()
+                {
+                    let name = format!(
+                        "{}::{}[{}:{}] d{}g{}v{}",
+                        file,
+                        test_name,
+                        fork,
+                        index,
+                        post.indexes.data,
+                        post.indexes.gas,
+                        post.indexes.value
+                    );
+                    let result = match skip_reason {
+                        Some(reason) => CaseResult::Skipped(reason.into()),
+                        None => self
+                            .run_case(test, post)
+                            .unwrap_or_else(CaseResult::Failed),
+                    };
+                    reports.push(CaseReport { name, result });
+                }
+            }
+        }
+        Ok(reports)
+    }
+
+    fn run_case(
+        &self, test: &StateTest, post: &PostState,
+    ) -> Result<CaseResult, String> {
+        let transaction = &test.transaction;
+        if transaction.max_fee_per_gas.is_some() {
+            return Ok(CaseResult::Unsupported("EIP-1559 transaction".into()));
+        }
+        if let Some(access_lists) = &transaction.access_lists {
+            if !access_lists[post.indexes.data].is_null() {
+                return Ok(CaseResult::Unsupported(
+                    "EIP-2930 transaction".into(),
+                ));
+            }
+        }
+
+        let number = parse_u64(&test.env.current_number)?;
+        let env = Env {
+            number,
+            author: parse_address(&test.env.current_coinbase)?,
+            timestamp: parse_u64(&test.env.current_timestamp)?,
+            difficulty: parse_u256(&test.env.current_difficulty)?,
+            gas_limit: parse_u256(&test.env.current_gas_limit)?,
+            last_hash: match &test.env.previous_hash {
+                Some(hash) => parse_h256(hash)?,
+                None => H256::zero(),
+            },
+            epoch_height: number,
+            ..Default::default()
+        };
+        let spec = self.machine.spec(number);
+
+        let storage = InMemoryStorage::default();
+        let mut state = new_state(&storage)?;
+        self.init_pre_state(&mut state, test, &spec)?;
+        state.commit(H256::zero(), None).map_err(db_error)?;
+
+        let tx = build_transaction(test, post)?;
+        let mut state = new_state(&storage)?;
+        let (logs, rejected) =
+            match self.execute(&mut state, &env, &spec, &tx)? {
+                Ok(logs) => {
+                    state
+                        .commit(H256::from_low_u64_be(1), None)
+                        .map_err(db_error)?;
+                    (logs, None)
+                }
+                // Like an invalid transaction in Ethereum, the rejected
+                // transaction doesn't change the state.
+                Err(reason) => (vec![], Some(reason)),
+            };
+
+        let mut errors = Vec::new();
+        match (&rejected, &post.expect_exception) {
+            (Some(reason), None) => {
+                errors.push(format!("transaction rejected: {}", reason))
+            }
+            (None, Some(exception)) => errors.push(format!(
+                "transaction executed, expected exception {}",
+                exception
+            )),
+            _ => {}
+        }
+        let state_root = ethereum_state_root(&read_post_state(&storage)?);
+        let expected_state_root = parse_h256(&post.hash)?;
+        if state_root != expected_state_root {
+            errors.push(format!(
+                "state root {:?}, expected {:?}",
+                state_root, expected_state_root
+            ));
+        }
+        let logs_hash = ethereum_logs_hash(&logs);
+        let expected_logs_hash = parse_h256(&post.logs)?;
+        if logs_hash != expected_logs_hash {
+            errors.push(format!(
+                "logs hash {:?}, expected {:?}",
+                logs_hash, expected_logs_hash
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(CaseResult::Passed)
+        } else {
+            Ok(CaseResult::Failed(errors.join("; ")))
+        }
+    }
+
+    fn init_pre_state(
+        &self, state: &mut State, test: &StateTest, spec: &Spec,
+    ) -> Result<(), String> {
+        for (address, account) in &test.pre {
+            let address = parse_address(address)?.with_evm_space();
+            let balance = parse_u256(&account.balance)?;
+            state
+                .add_balance(
+                    &address,
+                    &balance,
+                    CleanupMode::ForceCreate,
+                    spec.account_start_nonce,
+                )
+                .map_err(db_error)?;
+            state.add_total_issued(balance);
+            state.add_total_evm_tokens(balance);
+            state
+                .set_nonce(&address, &parse_u256(&account.nonce)?)
+                .map_err(db_error)?;
+            let code = parse_bytes(&account.code)?;
+            if !code.is_empty() {
+                state
+                    .init_code(&address, code, Address::zero())
+                    .map_err(db_error)?;
+            }
+            for (key, value) in &account.storage {
+                let value = parse_u256(value)?;
+                if !value.is_zero() {
+                    state
+                        .set_storage(
+                            &address,
+                            u256_to_key(&parse_u256(key)?),
+                            value,
+                            Address::zero(),
+                        )
+                        .map_err(db_error)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute the transaction and return the logs, or the reason why the
+    /// transaction is rejected as invalid.
+    fn execute(
+        &self, state: &mut State, env: &Env, spec: &Spec,
+        tx: &SignedTransaction,
+    ) -> Result<Result<Vec<LogEntry>, String>, String>
+    {
+        // The checks below are done by the transaction pool and the block
+        // verification in Conflux, and the executive assumes they pass.
+        let intrinsic_gas =
+            gas_required_for(tx.action() == &Action::Create, tx.data(), spec);
+        if *tx.gas() < intrinsic_gas.into() {
+            return Ok(Err(format!(
+                "gas below intrinsic gas {}",
+                intrinsic_gas
+            )));
+        }
+        if *tx.gas() > env.gas_limit {
+            return Ok(Err("gas limit exceeds block gas limit".into()));
+        }
+        let balance = state.balance(&tx.sender()).map_err(db_error)?;
+        let cost = tx.gas().full_mul(*tx.gas_price()) + U512::from(*tx.value());
+        if cost > U512::from(balance) {
+            return Ok(Err("insufficient balance".into()));
+        }
+
+        let outcome = ExecutiveGeneric::new(state, env, &self.machine, spec)
+            .transact(tx, TransactOptions::with_no_tracing())
+            .map_err(db_error)?;
+        let executed = match outcome {
+            ExecutionOutcome::NotExecutedDrop(e) => {
+                return Ok(Err(format!("{:?}", e)));
+            }
+            ExecutionOutcome::NotExecutedToReconsiderPacking(e) => {
+                return Ok(Err(format!("{:?}", e)));
+            }
+            ExecutionOutcome::ExecutionErrorBumpNonce(_, executed) => executed,
+            ExecutionOutcome::Finished(executed) => executed,
+        };
+
+        // A transaction using less than 3/4 of its gas limit is charged 3/4
+        // of the gas limit in Conflux. Refund the part above the gas used,
+        // so that the post state can be compared with Ethereum's.
+        let fee = executed.gas_used * tx.gas_price();
+        let overcharge = executed.fee - fee;
+        if !overcharge.is_zero() {
+            state
+                .add_balance(
+                    &tx.sender(),
+                    &overcharge,
+                    CleanupMode::NoEmpty,
+                    spec.account_start_nonce,
+                )
+                .map_err(db_error)?;
+        }
+        // The fee is distributed with the block rewards in Conflux, while
+        // Ethereum pays it to the coinbase after each transaction.
+        state
+            .add_balance(
+                &env.author.with_evm_space(),
+                &fee,
+                CleanupMode::NoEmpty,
+                spec.account_start_nonce,
+            )
+            .map_err(db_error)?;
+        state.add_total_evm_tokens(executed.fee);
+        Ok(Ok(executed.logs))
+    }
+}
+
+fn collect_fixture_files(
+    path: &Path, files: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let entries = fs::read_dir(path)
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    for entry in entries {
+        let entry_path = entry
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?
+            .path();
+        if entry_path.is_dir() {
+            collect_fixture_files(&entry_path, files)?;
+        } else if entry_path.extension().map_or(false, |ext| ext == "json") {
+            files.push(entry_path);
+        }
+    }
+    Ok(())
+}
+
+fn new_state(storage: &InMemoryStorage) -> Result<State, String> {
+    State::new(StateDbGeneric::new(storage.clone())).map_err(db_error)
+}
+
+fn db_error(e: DbError) -> String { format!("Db error: {}", e) }
+
+fn u256_to_key(value: &U256) -> Vec<u8> {
+    let mut key = vec![0u8; 32];
+    value.to_big_endian(&mut key);
+    key
+}
+
+fn build_transaction(
+    test: &StateTest, post: &PostState,
+) -> Result<SignedTransaction, String> {
+    let transaction = &test.transaction;
+    let pick = |values: &[String], index: usize| {
+        values
+            .get(index)
+            .cloned()
+            .ok_or_else(|| format!("Index {} out of range", index))
+    };
+    let action = if transaction.to.is_empty() {
+        Action::Create
+    } else {
+        Action::Call(parse_address(&transaction.to)?)
+    };
+    let unsigned = Transaction::Ethereum(Eip155Transaction {
+        nonce: parse_u256(&transaction.nonce)?,
+        gas_price: match &transaction.gas_price {
+            Some(gas_price) => parse_u256(gas_price)?,
+            None => U256::zero(),
+        },
+        gas: parse_u256(&pick(&transaction.gas_limit, post.indexes.gas)?)?,
+        action,
+        value: parse_u256(&pick(&transaction.value, post.indexes.value)?)?,
+        chain_id: None,
+        data: parse_bytes(&pick(&transaction.data, post.indexes.data)?)?,
+    });
+    let secret = Secret::from(parse_h256(&transaction.secret_key)?);
+    Ok(unsigned.sign(&secret))
+}
+
+/// Read all the eSpace accounts and their storage back from the raw key
+/// values.
+fn read_post_state(
+    storage: &InMemoryStorage,
+) -> Result<BTreeMap<Address, EthereumAccount>, String> {
+    let mut storage_keys = BTreeMap::<Address, Vec<Vec<u8>>>::new();
+    for key in storage.contents().read().keys() {
+        let key = StorageKeyWithSpace::from_key_bytes::<SkipInputCheck>(key);
+        if key.space != Space::Ethereum {
+            continue;
+        }
+        match key.key {
+            StorageKey::AccountKey(address_bytes) => {
+                storage_keys
+                    .entry(Address::from_slice(address_bytes))
+                    .or_default();
+            }
+            StorageKey::StorageKey {
+                address_bytes,
+                storage_key,
+            } => storage_keys
+                .entry(Address::from_slice(address_bytes))
+                .or_default()
+                .push(storage_key.to_vec()),
+            _ => {}
+        }
+    }
+
+    let state = new_state(storage)?;
+    let mut accounts = BTreeMap::new();
+    for (address, keys) in storage_keys {
+        let address_with_space = address.with_evm_space();
+        if !state.exists(&address_with_space).map_err(db_error)? {
+            continue;
+        }
+        let mut account = EthereumAccount {
+            nonce: state.nonce(&address_with_space).map_err(db_error)?,
+            balance: state.balance(&address_with_space).map_err(db_error)?,
+            code: state
+                .code(&address_with_space)
+                .map_err(db_error)?
+                .map_or(vec![], |code| code.to_vec()),
+            storage: Default::default(),
+        };
+        for key in keys {
+            let value = state
+                .storage_at(&address_with_space, &key)
+                .map_err(db_error)?;
+            if !value.is_zero() {
+                account.storage.insert(H256::from_slice(&key), value);
+            }
+        }
+        accounts.insert(address, account);
+    }
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_root() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/res")
+    }
+
+    #[test]
+    fn test_run_fixture() {
+        let runner = StateTestRunner::new(RunnerConfig::default());
+        let reports = runner.run_path(&fixture_root()).unwrap();
+        // The first case is charged 3/4 of its gas limit in Conflux, and the
+        // last one runs out of gas.
+        assert_eq!(reports.len(), 3);
+        for report in &reports {
+            assert_eq!(report.result, CaseResult::Passed, "{}", report);
+        }
+        assert_eq!(
+            reports[0].name,
+            "stExample/logToContract.json::logToContract\
+             [ConstantinopleFix:0] d0g0v0"
+        );
+    }
+
+    #[test]
+    fn test_skip_fixture() {
+        let runner = StateTestRunner::new(RunnerConfig {
+            skip_list: SkipList::parse("stExample/*  # Example\n"),
+            ..Default::default()
+        });
+        let reports = runner.run_path(&fixture_root()).unwrap();
+        assert_eq!(reports.len(), 3);
+        for report in &reports {
+            assert_eq!(report.result, CaseResult::Skipped("Example".into()));
+        }
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use std::{fs, path::Path};
+
+/// Tests which are known to diverge from Ethereum on purpose.
+///
+/// Each line of a skip list file is a pattern, optionally followed by a `#`
+/// comment giving the reason. A pattern is matched against the test name and
+/// against the path of the fixture file relative to the test root, and `*`
+/// matches any sequence of characters, e.g. `stRefundTest/*` skips a whole
+/// directory and `*Refund*` skips every test with `Refund` in the name.
+#[derive(Debug, Default)]
+pub struct SkipList {
+    entries: Vec<SkipEntry>,
+}
+
+#[derive(Debug)]
+struct SkipEntry {
+    pattern: String,
+    reason: String,
+}
+
+impl SkipList {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut entries = Vec::new();
+        for line in content.lines() {
+            let (pattern, reason) = match line.find('#') {
+                Some(pos) => (&line[..pos], line[pos + 1..].trim()),
+                None => (line, ""),
+            };
+            let pattern = pattern.trim();
+            if !pattern.is_empty() {
+                entries.push(SkipEntry {
+                    pattern: pattern.to_string(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+        Self { entries }
+    }
+
+    /// Return the reason if the test is skipped.
+    pub fn skip_reason(&self, file: &str, test_name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| {
+                glob_match(&entry.pattern, test_name)
+                    || glob_match(&entry.pattern, file)
+            })
+            .map(|entry| entry.reason.as_str())
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // There is always a first part, which is empty if the pattern starts
+    // with `*`.
+    let first = parts.next().unwrap();
+    if !text.starts_with(first) {
+        return false;
+    }
+    let mut rest = &text[first.len()..];
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        // No `*` in the pattern.
+        None => return rest.is_empty(),
+        Some(last) => last,
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("abc", "abc"));
+        assert!(!glob_match("abc", "abcd"));
+        assert!(glob_match("stRefundTest/*", "stRefundTest/refund50_1.json"));
+        assert!(!glob_match(
+            "stRefundTest/*",
+            "stSStoreTest/sstore_0to0.json"
+        ));
+        assert!(glob_match("*Refund*", "suicideRefundCheck"));
+        assert!(glob_match("a*b*c", "aXbYc"));
+        assert!(!glob_match("a*b*c", "aXcYb"));
+        assert!(!glob_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_skip_reason() {
+        let skip_list = SkipList::parse(
+            "# Comment line\n\
+             \n\
+             stRefundTest/*  # No gas refund\n\
+             create2collisionBalance\n",
+        );
+        assert_eq!(
+            skip_list.skip_reason("stRefundTest/refund50_1.json", "refund50_1"),
+            Some("No gas refund")
+        );
+        assert_eq!(
+            skip_list.skip_reason(
+                "stCreate2/create2collisionBalance.json",
+                "create2collisionBalance"
+            ),
+            Some("")
+        );
+        assert_eq!(
+            skip_list.skip_reason("stExample/add11.json", "add11"),
+            None
+        );
+    }
+}
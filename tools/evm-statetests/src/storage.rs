@@ -0,0 +1,93 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_internal_common::StateRootWithAuxInfo;
+use cfx_storage::{
+    utils::access_mode, ErrorKind, MptKeyValue, Result, StorageStateTrait,
+};
+use parking_lot::RwLock;
+use primitives::{EpochId, StorageKeyWithSpace, MERKLE_NULL_NODE};
+use std::{collections::HashMap, sync::Arc};
+
+pub type RawStorage = HashMap<Vec<u8>, Box<[u8]>>;
+
+/// A storage without the MPT, which keeps the raw key values in a map shared
+/// by all the clones, so that the post state can be read after the state
+/// which wrote it is dropped.
+#[derive(Clone, Default)]
+pub struct InMemoryStorage {
+    contents: Arc<RwLock<RawStorage>>,
+}
+
+impl InMemoryStorage {
+    pub fn contents(&self) -> &Arc<RwLock<RawStorage>> { &self.contents }
+}
+
+impl StorageStateTrait for InMemoryStorage {
+    fn get(
+        &self, access_key: StorageKeyWithSpace,
+    ) -> Result<Option<Box<[u8]>>> {
+        Ok(self
+            .contents
+            .read()
+            .get(&access_key.to_key_bytes())
+            .cloned())
+    }
+
+    fn set(
+        &mut self, access_key: StorageKeyWithSpace, value: Box<[u8]>,
+    ) -> Result<()> {
+        self.contents
+            .write()
+            .insert(access_key.to_key_bytes(), value);
+        Ok(())
+    }
+
+    fn delete(&mut self, access_key: StorageKeyWithSpace) -> Result<()> {
+        self.contents.write().remove(&access_key.to_key_bytes());
+        Ok(())
+    }
+
+    fn delete_test_only(
+        &mut self, access_key: StorageKeyWithSpace,
+    ) -> Result<Option<Box<[u8]>>> {
+        Ok(self.contents.write().remove(&access_key.to_key_bytes()))
+    }
+
+    fn delete_all<AM: access_mode::AccessMode>(
+        &mut self, access_key_prefix: StorageKeyWithSpace,
+    ) -> Result<Option<Vec<MptKeyValue>>> {
+        let prefix = access_key_prefix.to_key_bytes();
+        let mut contents = self.contents.write();
+        let deleted_kvs: Vec<MptKeyValue> = contents
+            .iter()
+            .filter(|(k, _)| k.starts_with(&prefix))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        if !AM::is_read_only() {
+            for (k, _) in &deleted_kvs {
+                contents.remove(k);
+            }
+        }
+        if deleted_kvs.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(deleted_kvs))
+        }
+    }
+
+    /// The Conflux state root isn't comparable with the Ethereum one, which is
+    /// computed from the raw key values by `trie::ethereum_state_root`.
+    fn compute_state_root(&mut self) -> Result<StateRootWithAuxInfo> {
+        Ok(StateRootWithAuxInfo::genesis(&MERKLE_NULL_NODE))
+    }
+
+    fn get_state_root(&self) -> Result<StateRootWithAuxInfo> {
+        Err(ErrorKind::Msg("No state root".to_owned()).into())
+    }
+
+    fn commit(&mut self, _epoch: EpochId) -> Result<StateRootWithAuxInfo> {
+        self.compute_state_root()
+    }
+}
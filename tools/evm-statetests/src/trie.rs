@@ -0,0 +1,227 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The Ethereum Merkle Patricia Trie root, which is what the fixtures commit
+//! the post state to. Conflux uses a different MPT, so the root is computed
+//! here from the accounts read back from the state.
+
+use cfx_types::{Address, H256, U256};
+use keccak_hash::{keccak, KECCAK_EMPTY, KECCAK_NULL_RLP};
+use primitives::LogEntry;
+use rlp::RlpStream;
+use std::collections::BTreeMap;
+
+/// An account in the Ethereum state trie.
+#[derive(Debug, Default)]
+pub struct EthereumAccount {
+    pub nonce: U256,
+    pub balance: U256,
+    pub code: Vec<u8>,
+    /// The non-zero storage slots.
+    pub storage: BTreeMap<H256, U256>,
+}
+
+impl EthereumAccount {
+    fn storage_root(&self) -> H256 {
+        sec_trie_root(
+            self.storage.iter().map(|(key, value)| {
+                (key.as_bytes().to_vec(), rlp::encode(value))
+            }),
+        )
+    }
+
+    fn rlp_bytes(&self) -> Vec<u8> {
+        let code_hash = if self.code.is_empty() {
+            KECCAK_EMPTY
+        } else {
+            keccak(&self.code)
+        };
+        let mut stream = RlpStream::new_list(4);
+        stream
+            .append(&self.nonce)
+            .append(&self.balance)
+            .append(&self.storage_root())
+            .append(&code_hash);
+        stream.out()
+    }
+}
+
+pub fn ethereum_state_root(
+    accounts: &BTreeMap<Address, EthereumAccount>,
+) -> H256 {
+    sec_trie_root(accounts.iter().map(|(address, account)| {
+        (address.as_bytes().to_vec(), account.rlp_bytes())
+    }))
+}
+
+/// The hash of the logs in a post state, which is the keccak hash of the RLP
+/// list of `[address, topics, data]`. The Conflux encoding of `LogEntry`
+/// also contains the space, so the logs are encoded here.
+pub fn ethereum_logs_hash(logs: &[LogEntry]) -> H256 {
+    let mut stream = RlpStream::new_list(logs.len());
+    for log in logs {
+        stream.begin_list(3);
+        stream.append(&log.address);
+        stream.append_list(&log.topics);
+        stream.append(&log.data);
+    }
+    keccak(stream.out())
+}
+
+/// The root of a secure trie, where the keys are hashed before insertion.
+pub fn sec_trie_root<I>(items: I) -> H256
+where I: IntoIterator<Item = (Vec<u8>, Vec<u8>)> {
+    trie_root(
+        items
+            .into_iter()
+            .map(|(key, value)| (keccak(key).as_bytes().to_vec(), value)),
+    )
+}
+
+pub fn trie_root<I>(items: I) -> H256
+where I: IntoIterator<Item = (Vec<u8>, Vec<u8>)> {
+    let mut nibble_items: Vec<(Vec<u8>, Vec<u8>)> = items
+        .into_iter()
+        .map(|(key, value)| (to_nibbles(&key), value))
+        .collect();
+    if nibble_items.is_empty() {
+        return KECCAK_NULL_RLP;
+    }
+    nibble_items.sort();
+    nibble_items.dedup_by(|a, b| a.0 == b.0);
+    keccak(encode_node(&nibble_items, 0))
+}
+
+fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| vec![b >> 4, b & 0x0f]).collect()
+}
+
+/// The compact encoding of a nibble path, with the flag telling whether it
+/// ends in a leaf and whether the number of nibbles is odd.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 } + (nibbles.len() % 2) as u8;
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push((flag << 4) | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag << 4);
+        nibbles
+    };
+    for pair in rest.chunks(2) {
+        encoded.push((pair[0] << 4) | pair[1]);
+    }
+    encoded
+}
+
+/// Encode the node for the sorted, non-empty `items` which share the first
+/// `depth` nibbles of their keys.
+fn encode_node(items: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+    if items.len() == 1 {
+        let (key, value) = &items[0];
+        let mut stream = RlpStream::new_list(2);
+        stream
+            .append(&hex_prefix(&key[depth..], true))
+            .append(value);
+        return stream.out();
+    }
+
+    // The items are sorted, so the common prefix of all the keys is the one
+    // of the first and the last key.
+    let first = &items[0].0;
+    let last = &items[items.len() - 1].0;
+    let shared = first[depth..]
+        .iter()
+        .zip(last[depth..].iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared > 0 {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&hex_prefix(&first[depth..depth + shared], false));
+        append_child(&mut stream, items, depth + shared);
+        return stream.out();
+    }
+
+    let mut stream = RlpStream::new_list(17);
+    // A key which ends at this node is the first item after sorting.
+    let (value, mut begin) = if items[0].0.len() == depth {
+        (Some(&items[0].1), 1)
+    } else {
+        (None, 0)
+    };
+    for nibble in 0..16u8 {
+        let end = begin
+            + items[begin..]
+                .iter()
+                .take_while(|(key, _)| key[depth] == nibble)
+                .count();
+        if begin == end {
+            stream.append_empty_data();
+        } else {
+            append_child(&mut stream, &items[begin..end], depth + 1);
+        }
+        begin = end;
+    }
+    match value {
+        Some(value) => stream.append(value),
+        None => stream.append_empty_data(),
+    };
+    stream.out()
+}
+
+/// Nodes shorter than a hash are inlined into their parent.
+fn append_child(
+    stream: &mut RlpStream, items: &[(Vec<u8>, Vec<u8>)], depth: usize,
+) {
+    let encoded = encode_node(items, depth);
+    if encoded.len() < 32 {
+        stream.append_raw(&encoded, 1);
+    } else {
+        stream.append(&keccak(&encoded));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn root_of(items: &[(&str, &str)]) -> H256 {
+        trie_root(items.iter().map(|(key, value)| {
+            (key.as_bytes().to_vec(), value.as_bytes().to_vec())
+        }))
+    }
+
+    #[test]
+    fn test_empty_trie_root() {
+        assert_eq!(root_of(&[]), KECCAK_NULL_RLP);
+    }
+
+    #[test]
+    fn test_trie_root() {
+        assert_eq!(
+            root_of(&[
+                ("do", "verb"),
+                ("horse", "stallion"),
+                ("doge", "coin"),
+                ("dog", "puppy"),
+            ]),
+            H256::from_str(
+                "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_empty_logs_hash() {
+        assert_eq!(
+            ethereum_logs_hash(&[]),
+            H256::from_str(
+                "1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347"
+            )
+            .unwrap()
+        );
+    }
+}
@@ -14,9 +14,22 @@ extern crate rand;
 extern crate rustc_hex;
 extern crate secret_store;
 
+mod rpc;
+mod scenario;
+mod stats;
+mod workload;
+
+pub use crate::{
+    rpc::RpcSink,
+    scenario::{ScenarioGenerator, TransactionSink},
+    stats::LoadReport,
+    workload::WorkloadSpec,
+};
+
 use crate::bytes::Bytes;
 use cfx_types::{
-    Address, AddressSpaceUtil, BigEndianHash, H256, H512, U256, U512,
+    Address, AddressSpaceUtil, AddressWithSpace, AllChainID, BigEndianHash,
+    Space, H256, H512, U256, U512,
 };
 use cfxcore::{
     executive::contract_address, vm::CreateContractAddress,
@@ -58,16 +71,22 @@ pub struct TransactionGeneratorConfig {
     pub generate_tx: bool,
     pub period: time::Duration,
     pub account_count: usize,
+    /// Generate this workload instead of the transfers between genesis
+    /// accounts.
+    pub workload: Option<WorkloadSpec>,
 }
 
 impl TransactionGeneratorConfig {
     pub fn new(
         generate_tx: bool, period_ms: u64, account_count: usize,
-    ) -> Self {
+        workload: Option<WorkloadSpec>,
+    ) -> Self
+    {
         TransactionGeneratorConfig {
             generate_tx,
             period: time::Duration::from_micros(period_ms),
             account_count,
+            workload,
         }
     }
 }
@@ -118,41 +137,80 @@ impl TransactionGenerator {
         self.join_handle.write().replace(join_handle);
     }
 
-    pub fn generate_transactions_with_multiple_genesis_accounts(
-        txgen: Arc<TransactionGenerator>,
-        tx_config: TransactionGeneratorConfig,
-        genesis_accounts: HashMap<Address, U256>,
-    )
-    {
+    /// Wait until the start index of the usable genesis accounts is set and
+    /// the node leaves the catch-up mode. Returns `None` if the generator is
+    /// stopped.
+    fn wait_for_start(&self) -> Option<usize> {
         loop {
-            let account_start = txgen.account_start_index.read();
+            let account_start = self.account_start_index.read();
             if account_start.is_some() {
                 break;
             }
         }
-        let account_start_index = txgen.account_start_index.read().unwrap();
-        let mut nonce_map: HashMap<Address, U256> = HashMap::new();
-        let mut balance_map: HashMap<Address, U256> = HashMap::new();
-        let mut address_secret_pair: HashMap<Address, Secret> = HashMap::new();
-        let mut addresses: Vec<Address> = Vec::new();
-
-        debug!("Tx Generation Config {:?}", tx_config.generate_tx);
-
-        let mut tx_n = 0;
         // Wait for initial tx
         loop {
-            match *txgen.state.read() {
-                TransGenState::Stop => return,
+            match *self.state.read() {
+                TransGenState::Stop => return None,
                 _ => {}
             }
 
             // Do not generate tx in catch_up_mode
-            if txgen.sync.catch_up_mode() {
+            if self.sync.catch_up_mode() {
                 thread::sleep(Duration::from_millis(100));
                 continue;
             }
             break;
         }
+        *self.account_start_index.read()
+    }
+
+    /// Generate the workload of `spec` with the first usable genesis account
+    /// as the funder of the load accounts.
+    pub fn generate_transactions_with_workload(
+        txgen: Arc<TransactionGenerator>, spec: WorkloadSpec,
+    ) {
+        let account_start_index = match txgen.wait_for_start() {
+            Some(index) => index,
+            None => return,
+        };
+        let funder = txgen.secret_store.get_keypair(account_start_index);
+        let keep_running =
+            || matches!(*txgen.state.read(), TransGenState::Start);
+        let on_report = |report: &LoadReport| info!("Workload: {}", report);
+
+        let result =
+            ScenarioGenerator::new(&spec, &*txgen).and_then(|mut generator| {
+                if generator.setup(&funder, &keep_running)? {
+                    Ok(Some(generator.run(&keep_running, &on_report)))
+                } else {
+                    Ok(None)
+                }
+            });
+        match result {
+            Ok(Some(report)) => info!("Workload finished: {}", report),
+            Ok(None) => {}
+            Err(e) => warn!("Workload generation failed: {}", e),
+        }
+    }
+
+    pub fn generate_transactions_with_multiple_genesis_accounts(
+        txgen: Arc<TransactionGenerator>,
+        tx_config: TransactionGeneratorConfig,
+        genesis_accounts: HashMap<Address, U256>,
+    )
+    {
+        let account_start_index = match txgen.wait_for_start() {
+            Some(index) => index,
+            None => return,
+        };
+        let mut nonce_map: HashMap<Address, U256> = HashMap::new();
+        let mut balance_map: HashMap<Address, U256> = HashMap::new();
+        let mut address_secret_pair: HashMap<Address, Secret> = HashMap::new();
+        let mut addresses: Vec<Address> = Vec::new();
+
+        debug!("Tx Generation Config {:?}", tx_config.generate_tx);
+
+        let mut tx_n = 0;
 
         debug!("Setup Usable Genesis Accounts");
         for i in 0..tx_config.account_count {
@@ -279,6 +337,38 @@ impl TransactionGenerator {
     }
 }
 
+impl TransactionSink for TransactionGenerator {
+    fn chain_id(&self) -> Result<AllChainID, String> {
+        Ok(self.consensus.best_chain_id())
+    }
+
+    fn epoch_number(&self) -> Result<u64, String> {
+        Ok(self.consensus.best_epoch_number())
+    }
+
+    fn account(
+        &self, address: &AddressWithSpace,
+    ) -> Result<(U256, U256), String> {
+        self.txpool
+            .get_state_account_info(address)
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    fn submit(&self, tx: SignedTransaction) -> Result<(), String> {
+        let (txs, fail) =
+            self.txpool.insert_new_transactions(vec![tx.transaction]);
+        if let Some((_, e)) = fail.into_iter().next() {
+            return Err(e);
+        }
+        self.sync.append_received_transactions(txs);
+        Ok(())
+    }
+
+    fn is_included(&self, hash: &H256, _space: Space) -> Result<bool, String> {
+        Ok(self.consensus.get_transaction_info_by_hash(hash).is_some())
+    }
+}
+
 /// This tx generator directly push simple transactions and erc20 transactions
 /// into blocks. It's used in Ethereum e2d replay test.
 pub struct DirectTransactionGenerator {
@@ -0,0 +1,77 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfxkey::{KeyPair, Secret};
+use std::{fs, path::Path, process, str::FromStr};
+use txgen::{LoadReport, RpcSink, ScenarioGenerator, WorkloadSpec};
+
+fn load_funder(path: &str) -> Result<KeyPair, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let secret = content.lines().next().unwrap_or_default().trim();
+    let secret = Secret::from_str(secret.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid funder secret: {:?}", e))?;
+    KeyPair::from_secret(secret)
+        .map_err(|e| format!("Invalid funder secret: {:?}", e))
+}
+
+fn run(matches: &clap::ArgMatches) -> Result<LoadReport, String> {
+    let spec = WorkloadSpec::from_file(Path::new(
+        matches.value_of("workload").unwrap(),
+    ))?;
+    let funder = load_funder(matches.value_of("funder-key-file").unwrap())?;
+    let sink = RpcSink::new(matches.value_of("url").unwrap().into())?;
+
+    let keep_running = || true;
+    let mut generator = ScenarioGenerator::new(&spec, &sink)?;
+    generator.setup(&funder, &keep_running)?;
+    Ok(generator.run(&keep_running, &|report| println!("{}", report)))
+}
+
+fn main() {
+    env_logger::try_init().expect("Logger initialized only once.");
+
+    let matches = clap::App::new("txgen")
+        .version("0.1")
+        .about(
+"Generate the transaction workload described in a spec file against the RPC of a node
+Example usage:
+    txgen
+        --workload workload.toml
+        --url http://127.0.0.1:12537
+        --funder-key-file funder.key")
+        .arg(
+            clap::Arg::with_name("workload")
+                .long("workload")
+                .value_name("FILE")
+                .help("The TOML workload spec")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            clap::Arg::with_name("url")
+                .long("url")
+                .value_name("URL")
+                .help("The HTTP JSON-RPC endpoint of the node")
+                .takes_value(true)
+                .required(true),
+        )
+        .arg(
+            clap::Arg::with_name("funder-key-file")
+                .long("funder-key-file")
+                .value_name("FILE")
+                .help("A file with the hex secret key of the native space account funding the load accounts")
+                .takes_value(true)
+                .required(true),
+        )
+        .get_matches();
+
+    match run(&matches) {
+        Ok(report) => println!("Finished: {}", report),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use crate::scenario::TransactionSink;
+use cfx_addr::{cfx_addr_encode, EncodingOptions, Network};
+use cfx_types::{AddressWithSpace, AllChainID, Space, H256, U256};
+use network::NetworkConfiguration;
+use primitives::SignedTransaction;
+use rlp::Encodable;
+use rustc_hex::ToHex;
+use serde_json::{json, Value};
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+const CONNECT_TIMEOUT_MS: u64 = 10_000;
+
+/// Submits the generated transactions through the HTTP JSON-RPC of a node.
+pub struct RpcSink {
+    url: String,
+    network: Network,
+    next_id: AtomicU64,
+}
+
+impl RpcSink {
+    pub fn new(url: String) -> Result<Self, String> {
+        let mut sink = RpcSink {
+            url,
+            network: Network::Main,
+            next_id: AtomicU64::new(0),
+        };
+        let status = sink.call("cfx_getStatus", json!([]))?;
+        sink.network = NetworkConfiguration::network_id_to_known_cfx_network(
+            parse_u64(&status["networkId"])?,
+        );
+        Ok(sink)
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response = ureq::post(&self.url)
+            .timeout_connect(CONNECT_TIMEOUT_MS)
+            .send_json(request);
+        if let Some(e) = response.synthetic_error() {
+            return Err(format!("{} failed: {}", method, e));
+        }
+        let mut body: Value = response
+            .into_json()
+            .map_err(|e| format!("Invalid response to {}: {}", method, e))?;
+        if let Some(e) = body.get("error") {
+            return Err(format!("{} failed: {}", method, e));
+        }
+        Ok(body["result"].take())
+    }
+
+    fn encode_address(&self, address: &AddressWithSpace) -> Value {
+        match address.space {
+            Space::Native => json!(cfx_addr_encode(
+                address.address.as_bytes(),
+                self.network,
+                EncodingOptions::Simple,
+            )
+            .expect("the network id is valid")),
+            Space::Ethereum => json!(format!("{:?}", address.address)),
+        }
+    }
+}
+
+impl TransactionSink for RpcSink {
+    fn chain_id(&self) -> Result<AllChainID, String> {
+        let status = self.call("cfx_getStatus", json!([]))?;
+        Ok(AllChainID::new(
+            parse_u64(&status["chainId"])? as u32,
+            parse_u64(&status["ethereumSpaceChainId"])? as u32,
+        ))
+    }
+
+    fn epoch_number(&self) -> Result<u64, String> {
+        parse_u64(&self.call("cfx_epochNumber", json!([]))?)
+    }
+
+    fn account(
+        &self, address: &AddressWithSpace,
+    ) -> Result<(U256, U256), String> {
+        let encoded = self.encode_address(address);
+        let (nonce, balance) = match address.space {
+            Space::Native => (
+                self.call("cfx_getNextNonce", json!([encoded]))?,
+                self.call("cfx_getBalance", json!([encoded]))?,
+            ),
+            Space::Ethereum => (
+                self.call(
+                    "eth_getTransactionCount",
+                    json!([encoded, "latest"]),
+                )?,
+                self.call("eth_getBalance", json!([encoded, "latest"]))?,
+            ),
+        };
+        Ok((parse_u256(&nonce)?, parse_u256(&balance)?))
+    }
+
+    fn submit(&self, tx: SignedTransaction) -> Result<(), String> {
+        let method = match tx.space() {
+            Space::Native => "cfx_sendRawTransaction",
+            Space::Ethereum => "eth_sendRawTransaction",
+        };
+        let raw =
+            format!("0x{}", tx.transaction.rlp_bytes().to_hex::<String>());
+        self.call(method, json!([raw])).map(|_| ())
+    }
+
+    fn is_included(&self, hash: &H256, space: Space) -> Result<bool, String> {
+        let method = match space {
+            Space::Native => "cfx_getTransactionByHash",
+            Space::Ethereum => "eth_getTransactionByHash",
+        };
+        let tx = self.call(method, json!([format!("{:?}", hash)]))?;
+        Ok(!tx.is_null() && !tx["blockHash"].is_null())
+    }
+}
+
+fn parse_u64(value: &Value) -> Result<u64, String> {
+    let hex = value
+        .as_str()
+        .ok_or_else(|| format!("Expect a quantity, got {}", value))?;
+    u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("Invalid quantity {}: {}", hex, e))
+}
+
+fn parse_u256(value: &Value) -> Result<U256, String> {
+    let hex = value
+        .as_str()
+        .ok_or_else(|| format!("Expect a quantity, got {}", value))?;
+    U256::from_str(hex.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid quantity {}: {:?}", hex, e))
+}
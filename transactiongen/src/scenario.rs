@@ -0,0 +1,472 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The scenario-driven generator, which creates and funds its own accounts,
+//! deploys the contracts of a `WorkloadSpec`, and then submits the mix of
+//! transfers and calls of the spec at the target rate.
+
+use crate::{
+    bytes::Bytes,
+    stats::{LoadReport, LoadStats},
+    workload::WorkloadSpec,
+    TX_GEN_METER,
+};
+use cfx_parameters::internal_contract_addresses::CROSS_SPACE_CONTRACT_ADDRESS;
+use cfx_types::{
+    AddressSpaceUtil, AddressWithSpace, AllChainID, Space, H256, U256,
+};
+use cfxcore::{executive::contract_address, vm::CreateContractAddress};
+use keccak_hash::keccak;
+use keylib::{Generator, KeyPair, Random};
+use primitives::{
+    transaction::{Action, Eip155Transaction, NativeTransaction},
+    SignedTransaction, Transaction,
+};
+use rand::{distributions::WeightedIndex, prelude::*};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Where the generated transactions go, e.g. the local transaction pool or
+/// the RPC of a remote node.
+pub trait TransactionSink {
+    fn chain_id(&self) -> Result<AllChainID, String>;
+
+    fn epoch_number(&self) -> Result<u64, String>;
+
+    /// Returns the nonce and the balance of the account.
+    fn account(
+        &self, address: &AddressWithSpace,
+    ) -> Result<(U256, U256), String>;
+
+    fn submit(&self, tx: SignedTransaction) -> Result<(), String>;
+
+    /// Whether the transaction has been packed into a block.
+    fn is_included(&self, hash: &H256, space: Space) -> Result<bool, String>;
+}
+
+/// The number of pending transactions checked for inclusion in each poll, so
+/// that polling doesn't slow down the submission.
+const MAX_INCLUSION_CHECKS_PER_POLL: usize = 1000;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long to wait for the accounts to be funded and the contracts to be
+/// deployed.
+const SETUP_TIMEOUT: Duration = Duration::from_secs(300);
+const TRANSFER_GAS: u64 = 21000;
+const CROSS_SPACE_TRANSFER_GAS: u64 = 300_000;
+
+struct LoadAccount {
+    key_pair: KeyPair,
+    address: AddressWithSpace,
+    nonce: U256,
+}
+
+impl LoadAccount {
+    fn new(key_pair: KeyPair, space: Space) -> Self {
+        let address = match space {
+            Space::Native => key_pair.address(),
+            Space::Ethereum => key_pair.evm_address(),
+        };
+        LoadAccount {
+            key_pair,
+            address: address.with_space(space),
+            nonce: U256::zero(),
+        }
+    }
+}
+
+enum LoadKind {
+    Transfer,
+    Call(usize),
+}
+
+/// The fields of a transaction not decided by the sender account.
+struct TxFields {
+    action: Action,
+    value: U256,
+    gas: u64,
+    storage_limit: u64,
+    gas_price: U256,
+    data: Bytes,
+}
+
+pub struct ScenarioGenerator<'a> {
+    spec: &'a WorkloadSpec,
+    sink: &'a dyn TransactionSink,
+    rng: StdRng,
+    chain_id: AllChainID,
+    epoch_number: u64,
+    native_accounts: Vec<LoadAccount>,
+    ethereum_accounts: Vec<LoadAccount>,
+    /// The addresses of the deployed contracts, in the order of the spec.
+    contracts: Vec<AddressWithSpace>,
+}
+
+impl<'a> ScenarioGenerator<'a> {
+    pub fn new(
+        spec: &'a WorkloadSpec, sink: &'a dyn TransactionSink,
+    ) -> Result<Self, String> {
+        Ok(ScenarioGenerator {
+            spec,
+            sink,
+            rng: StdRng::seed_from_u64(spec.seed),
+            chain_id: sink.chain_id()?,
+            epoch_number: sink.epoch_number()?,
+            native_accounts: Vec::new(),
+            ethereum_accounts: Vec::new(),
+            contracts: Vec::new(),
+        })
+    }
+
+    /// Fund the load accounts from `funder`, which is a native space account,
+    /// and deploy the contracts. Returns false if `keep_running` turns false
+    /// before the setup completes.
+    pub fn setup(
+        &mut self, funder: &KeyPair, keep_running: &dyn Fn() -> bool,
+    ) -> Result<bool, String> {
+        let mut funder = LoadAccount::new(funder.clone(), Space::Native);
+        funder.nonce = self.sink.account(&funder.address)?.0;
+
+        let balance = self.spec.accounts.balance();
+        for space in &[Space::Native, Space::Ethereum] {
+            for _ in 0..self.spec.accounts.count(*space) {
+                let account = LoadAccount::new(
+                    Random.generate().expect("Fail to generate KeyPair."),
+                    *space,
+                );
+                let gas_price = self.spec.gas_price.sample(&mut self.rng);
+                let fields = match space {
+                    Space::Native => TxFields {
+                        action: Action::Call(account.address.address),
+                        value: balance,
+                        gas: TRANSFER_GAS,
+                        storage_limit: 0,
+                        gas_price,
+                        data: Bytes::new(),
+                    },
+                    // Move the balance to eSpace through the cross-space
+                    // internal contract.
+                    Space::Ethereum => {
+                        let mut data =
+                            keccak(b"transferEVM(bytes20)")[..4].to_vec();
+                        data.extend_from_slice(&account.address.address[..]);
+                        data.resize(4 + 32, 0);
+                        TxFields {
+                            action: Action::Call(*CROSS_SPACE_CONTRACT_ADDRESS),
+                            value: balance,
+                            gas: CROSS_SPACE_TRANSFER_GAS,
+                            storage_limit: 0,
+                            gas_price,
+                            data,
+                        }
+                    }
+                };
+                self.sink.submit(self.sign(&funder, fields))?;
+                funder.nonce += 1.into();
+                match space {
+                    Space::Native => self.native_accounts.push(account),
+                    Space::Ethereum => self.ethereum_accounts.push(account),
+                }
+            }
+        }
+        info!(
+            "Funding {} native and {} eSpace load accounts",
+            self.native_accounts.len(),
+            self.ethereum_accounts.len()
+        );
+        let accounts: Vec<_> = self
+            .native_accounts
+            .iter()
+            .chain(self.ethereum_accounts.iter())
+            .map(|account| account.address)
+            .collect();
+        let funded = self.wait_until(keep_running, |sink| {
+            for address in &accounts {
+                if sink.account(address)?.1.is_zero() {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })?;
+        if !funded {
+            return Ok(false);
+        }
+
+        let spec = self.spec;
+        let mut deployments = Vec::new();
+        for contract in &spec.contracts {
+            let gas_price = spec.gas_price.sample(&mut self.rng);
+            let (address, tx) = {
+                let deployer = match contract.space {
+                    Space::Native => &self.native_accounts[0],
+                    Space::Ethereum => &self.ethereum_accounts[0],
+                };
+                let address_scheme = match contract.space {
+                    Space::Native => {
+                        CreateContractAddress::FromSenderNonceAndCodeHash
+                    }
+                    Space::Ethereum => CreateContractAddress::FromSenderNonce,
+                };
+                let (address, _) = contract_address(
+                    address_scheme,
+                    0.into(),
+                    &deployer.address,
+                    &deployer.nonce,
+                    &contract.code,
+                );
+                let tx = self.sign(
+                    deployer,
+                    TxFields {
+                        action: Action::Create,
+                        value: U256::zero(),
+                        gas: contract.gas,
+                        storage_limit: contract.storage_limit,
+                        gas_price,
+                        data: contract.code.clone(),
+                    },
+                );
+                (address, tx)
+            };
+            deployments.push((tx.hash(), contract.space));
+            self.sink.submit(tx)?;
+            self.accounts_mut(contract.space)[0].nonce += 1.into();
+            info!("Deploying contract {} at {:?}", contract.name, address);
+            self.contracts.push(address);
+        }
+        self.wait_until(keep_running, |sink| {
+            for (hash, space) in &deployments {
+                if !sink.is_included(hash, *space)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })
+    }
+
+    /// Submit the load until the duration of the spec passes or
+    /// `keep_running` turns false.
+    pub fn run(
+        &mut self, keep_running: &dyn Fn() -> bool,
+        on_report: &dyn Fn(&LoadReport),
+    ) -> LoadReport {
+        let mut kinds = Vec::new();
+        let mut weights = Vec::new();
+        if self.spec.transfer.weight > 0 {
+            kinds.push(LoadKind::Transfer);
+            weights.push(self.spec.transfer.weight);
+        }
+        for (i, call) in self.spec.calls.iter().enumerate() {
+            if call.weight > 0 {
+                kinds.push(LoadKind::Call(i));
+                weights.push(call.weight);
+            }
+        }
+        // There is a positive weight as checked in `WorkloadSpec::validate`.
+        let kind_index = WeightedIndex::new(&weights).unwrap();
+        let period = Duration::from_secs(1) / self.spec.target_tps as u32;
+        let report_interval =
+            Duration::from_secs(self.spec.report_interval_secs);
+
+        let start = Instant::now();
+        let mut stats = LoadStats::new(start);
+        let mut last_poll = start;
+        let mut last_report = start;
+        let mut tx_n = 0u32;
+        info!("Start generating workload");
+        while keep_running() {
+            let now = Instant::now();
+            if let Some(duration_secs) = self.spec.duration_secs {
+                if now.duration_since(start).as_secs() >= duration_secs {
+                    break;
+                }
+            }
+
+            let (space, index, tx) =
+                match kinds[kind_index.sample(&mut self.rng)] {
+                    LoadKind::Transfer => self.next_transfer(),
+                    LoadKind::Call(i) => self.next_call(i),
+                };
+            let hash = tx.hash();
+            match self.sink.submit(tx) {
+                Ok(()) => {
+                    self.accounts_mut(space)[index].nonce += 1.into();
+                    stats.on_submitted(hash, space, now);
+                    TX_GEN_METER.mark(1);
+                }
+                Err(e) => {
+                    debug!("Transaction {:?} is rejected: {}", hash, e);
+                    stats.on_rejected();
+                    self.resync_nonce(space, index);
+                }
+            }
+            tx_n += 1;
+
+            let now = Instant::now();
+            if now.duration_since(last_poll) >= POLL_INTERVAL {
+                last_poll = now;
+                self.poll(&mut stats, now);
+            }
+            if now.duration_since(last_report) >= report_interval {
+                last_report = now;
+                on_report(&stats.report(now));
+            }
+
+            // Keep the target rate on average.
+            if let Some(time_left) =
+                (period * tx_n).checked_sub(now.duration_since(start))
+            {
+                thread::sleep(time_left);
+            }
+        }
+
+        let now = Instant::now();
+        self.poll(&mut stats, now);
+        stats.report(now)
+    }
+
+    fn poll(&mut self, stats: &mut LoadStats, now: Instant) {
+        match self.sink.epoch_number() {
+            Ok(epoch_number) => self.epoch_number = epoch_number,
+            Err(e) => warn!("Failed to get the epoch number: {}", e),
+        }
+        let sink = self.sink;
+        stats.poll_inclusion(
+            now,
+            MAX_INCLUSION_CHECKS_PER_POLL,
+            |hash, space| match sink.is_included(hash, space) {
+                Ok(included) => included,
+                Err(e) => {
+                    warn!("Failed to check transaction {:?}: {}", hash, e);
+                    false
+                }
+            },
+        );
+    }
+
+    fn next_transfer(&mut self) -> (Space, usize, SignedTransaction) {
+        let space = if self.rng.gen_bool(self.spec.transfer.ethereum_ratio) {
+            Space::Ethereum
+        } else {
+            Space::Native
+        };
+        let count = self.accounts(space).len();
+        let sender = self.rng.gen_range(0, count);
+        let receiver = self.rng.gen_range(0, count);
+        let gas_price = self.spec.gas_price.sample(&mut self.rng);
+        let accounts = self.accounts(space);
+        let tx = self.sign(
+            &accounts[sender],
+            TxFields {
+                action: Action::Call(accounts[receiver].address.address),
+                value: self.spec.transfer.value.into(),
+                gas: TRANSFER_GAS,
+                storage_limit: 0,
+                gas_price,
+                data: Bytes::new(),
+            },
+        );
+        (space, sender, tx)
+    }
+
+    fn next_call(
+        &mut self, call_index: usize,
+    ) -> (Space, usize, SignedTransaction) {
+        let call = &self.spec.calls[call_index];
+        let contract_index = self
+            .spec
+            .contracts
+            .iter()
+            .position(|contract| contract.name == call.contract)
+            .expect("checked in WorkloadSpec::validate");
+        let contract = self.contracts[contract_index];
+        let count = self.accounts(contract.space).len();
+        let sender = self.rng.gen_range(0, count);
+        let gas_price = self.spec.gas_price.sample(&mut self.rng);
+        let tx = self.sign(
+            &self.accounts(contract.space)[sender],
+            TxFields {
+                action: Action::Call(contract.address),
+                value: call.value.into(),
+                gas: call.gas,
+                storage_limit: call.storage_limit,
+                gas_price,
+                data: call.data.clone(),
+            },
+        );
+        (contract.space, sender, tx)
+    }
+
+    fn resync_nonce(&mut self, space: Space, index: usize) {
+        let address = self.accounts(space)[index].address;
+        match self.sink.account(&address) {
+            Ok((nonce, _)) => self.accounts_mut(space)[index].nonce = nonce,
+            Err(e) => warn!("Failed to get the nonce of {:?}: {}", address, e),
+        }
+    }
+
+    fn accounts(&self, space: Space) -> &[LoadAccount] {
+        match space {
+            Space::Native => &self.native_accounts,
+            Space::Ethereum => &self.ethereum_accounts,
+        }
+    }
+
+    fn accounts_mut(&mut self, space: Space) -> &mut Vec<LoadAccount> {
+        match space {
+            Space::Native => &mut self.native_accounts,
+            Space::Ethereum => &mut self.ethereum_accounts,
+        }
+    }
+
+    /// Sign a transaction of the sender's space.
+    fn sign(
+        &self, sender: &LoadAccount, fields: TxFields,
+    ) -> SignedTransaction {
+        let tx: Transaction = match sender.address.space {
+            Space::Native => NativeTransaction {
+                nonce: sender.nonce,
+                gas_price: fields.gas_price,
+                gas: fields.gas.into(),
+                action: fields.action,
+                value: fields.value,
+                storage_limit: fields.storage_limit,
+                epoch_height: self.epoch_number,
+                chain_id: self.chain_id.in_native_space(),
+                data: fields.data,
+            }
+            .into(),
+            Space::Ethereum => Eip155Transaction {
+                nonce: sender.nonce,
+                gas_price: fields.gas_price,
+                gas: fields.gas.into(),
+                action: fields.action,
+                value: fields.value,
+                chain_id: Some(self.chain_id.in_evm_space()),
+                data: fields.data,
+            }
+            .into(),
+        };
+        tx.sign(sender.key_pair.secret())
+    }
+
+    /// Poll `done` until it returns true. Returns false if `keep_running`
+    /// turns false first.
+    fn wait_until<F>(
+        &self, keep_running: &dyn Fn() -> bool, mut done: F,
+    ) -> Result<bool, String>
+    where F: FnMut(&dyn TransactionSink) -> Result<bool, String> {
+        let start = Instant::now();
+        while keep_running() {
+            if done(self.sink)? {
+                return Ok(true);
+            }
+            if start.elapsed() > SETUP_TIMEOUT {
+                return Err("Timeout waiting for the setup transactions".into());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+use cfx_types::{Space, H256};
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The submissions of a load run and the time it takes them to be packed into
+/// a block.
+pub struct LoadStats {
+    start: Instant,
+    submitted: u64,
+    rejected: u64,
+    /// The transactions not yet seen in a block, in submission order.
+    pending: VecDeque<(H256, Space, Instant)>,
+    latencies: Vec<Duration>,
+}
+
+impl LoadStats {
+    pub fn new(start: Instant) -> Self {
+        LoadStats {
+            start,
+            submitted: 0,
+            rejected: 0,
+            pending: VecDeque::new(),
+            latencies: Vec::new(),
+        }
+    }
+
+    pub fn on_submitted(&mut self, hash: H256, space: Space, at: Instant) {
+        self.submitted += 1;
+        self.pending.push_back((hash, space, at));
+    }
+
+    pub fn on_rejected(&mut self) { self.rejected += 1; }
+
+    /// Check at most `limit` of the oldest pending transactions with
+    /// `is_included`, and record the latency of those included at `now`.
+    pub fn poll_inclusion<F>(
+        &mut self, now: Instant, limit: usize, mut is_included: F,
+    ) where F: FnMut(&H256, Space) -> bool {
+        let mut still_pending = 0;
+        for _ in 0..limit.min(self.pending.len()) {
+            let (hash, space, at) = self.pending.pop_front().unwrap();
+            if is_included(&hash, space) {
+                self.latencies.push(now.duration_since(at));
+            } else {
+                self.pending.push_back((hash, space, at));
+                still_pending += 1;
+            }
+        }
+        // Move the checked ones back to the front to keep the submission
+        // order.
+        self.pending.rotate_right(still_pending);
+    }
+
+    pub fn report(&self, now: Instant) -> LoadReport {
+        let elapsed = now.duration_since(self.start);
+        let mut latencies = self.latencies.clone();
+        latencies.sort();
+        LoadReport {
+            elapsed,
+            submitted: self.submitted,
+            rejected: self.rejected,
+            included: latencies.len() as u64,
+            pending: self.pending.len() as u64,
+            submission_tps: if elapsed.as_secs_f64() > 0.0 {
+                self.submitted as f64 / elapsed.as_secs_f64()
+            } else {
+                0.0
+            },
+            latency_p50: percentile(&latencies, 50),
+            latency_p90: percentile(&latencies, 90),
+            latency_p99: percentile(&latencies, 99),
+            latency_max: latencies.last().cloned(),
+        }
+    }
+}
+
+/// The nearest-rank percentile of sorted values.
+fn percentile(sorted: &[Duration], p: usize) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (sorted.len() * p + 99) / 100;
+    Some(sorted[rank.max(1) - 1])
+}
+
+#[derive(Debug)]
+pub struct LoadReport {
+    pub elapsed: Duration,
+    pub submitted: u64,
+    pub rejected: u64,
+    pub included: u64,
+    pub pending: u64,
+    pub submission_tps: f64,
+    pub latency_p50: Option<Duration>,
+    pub latency_p90: Option<Duration>,
+    pub latency_p99: Option<Duration>,
+    pub latency_max: Option<Duration>,
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "elapsed={:?} submitted={} rejected={} submission_tps={:.1} \
+             included={} pending={} inclusion_latency: p50={:?} p90={:?} \
+             p99={:?} max={:?}",
+            self.elapsed,
+            self.submitted,
+            self.rejected,
+            self.submission_tps,
+            self.included,
+            self.pending,
+            self.latency_p50,
+            self.latency_p90,
+            self.latency_p99,
+            self.latency_max,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{percentile, LoadStats};
+    use cfx_types::{Space, H256};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_percentile() {
+        let values: Vec<_> = (1..=10).map(Duration::from_secs).collect();
+        assert_eq!(percentile(&[], 50), None);
+        assert_eq!(percentile(&values, 50), Some(Duration::from_secs(5)));
+        assert_eq!(percentile(&values, 90), Some(Duration::from_secs(9)));
+        assert_eq!(percentile(&values, 99), Some(Duration::from_secs(10)));
+        assert_eq!(percentile(&values, 0), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_poll_inclusion() {
+        let start = Instant::now();
+        let mut stats = LoadStats::new(start);
+        for i in 0..5u64 {
+            stats.on_submitted(
+                H256::from_low_u64_be(i),
+                Space::Native,
+                start + Duration::from_secs(i),
+            );
+        }
+        stats.on_rejected();
+
+        // Only the first three are checked, and the second one is included.
+        let mut checked = vec![];
+        stats.poll_inclusion(start + Duration::from_secs(10), 3, |hash, _| {
+            checked.push(hash.to_low_u64_be());
+            hash.to_low_u64_be() == 1
+        });
+        assert_eq!(checked, vec![0, 1, 2]);
+
+        checked.clear();
+        stats.poll_inclusion(start + Duration::from_secs(10), 10, |hash, _| {
+            checked.push(hash.to_low_u64_be());
+            false
+        });
+        assert_eq!(checked, vec![0, 2, 3, 4]);
+
+        let report = stats.report(start + Duration::from_secs(10));
+        assert_eq!(report.submitted, 5);
+        assert_eq!(report.rejected, 1);
+        assert_eq!(report.included, 1);
+        assert_eq!(report.pending, 4);
+        assert_eq!(report.latency_p50, Some(Duration::from_secs(9)));
+    }
+}
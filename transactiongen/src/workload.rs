@@ -0,0 +1,394 @@
+// Copyright 2021 Conflux Foundation. All rights reserved.
+// Conflux is free software and distributed under GNU General Public License.
+// See http://www.gnu.org/licenses/
+
+//! The workload spec of the scenario-driven generator, loaded from a TOML
+//! file. An example:
+//!
+//! ```toml
+//! target_tps = 200
+//! duration_secs = 600
+//!
+//! [accounts]
+//! native = 50
+//! ethereum = 20
+//!
+//! [gas_price]
+//! distribution = "uniform"
+//! min = 1
+//! max = 100
+//!
+//! [transfer]
+//! weight = 5
+//! ethereum_ratio = 0.3
+//!
+//! [[contract]]
+//! name = "counter"
+//! space = "Ethereum"
+//! code = "0x6080..."
+//!
+//! [[call]]
+//! contract = "counter"
+//! data = "0xd09de08a"
+//! weight = 2
+//! ```
+
+use crate::bytes::Bytes;
+use cfx_types::{Space, U256};
+use rand::{distributions::WeightedIndex, prelude::*};
+use rustc_hex::FromHex;
+use serde::Deserializer;
+use serde_derive::Deserialize;
+use std::{collections::HashSet, fs, path::Path};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkloadSpec {
+    /// The number of transactions submitted per second.
+    pub target_tps: u64,
+    /// Stop after the duration. Run until the generator is stopped if
+    /// not set.
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+    #[serde(default = "default_report_interval_secs")]
+    pub report_interval_secs: u64,
+    /// The seed of the random choices, so that a workload can be replayed.
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub accounts: AccountsSpec,
+    #[serde(default)]
+    pub gas_price: GasPriceDistribution,
+    #[serde(default)]
+    pub transfer: TransferSpec,
+    #[serde(default, rename = "contract")]
+    pub contracts: Vec<ContractSpec>,
+    #[serde(default, rename = "call")]
+    pub calls: Vec<CallSpec>,
+}
+
+/// The accounts sending the load, which are created and funded by the
+/// generator before the load starts.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountsSpec {
+    #[serde(default = "default_native_accounts")]
+    pub native: usize,
+    #[serde(default)]
+    pub ethereum: usize,
+    /// The balance of each account in CFX.
+    #[serde(default = "default_balance_cfx")]
+    pub balance_cfx: u64,
+}
+
+impl Default for AccountsSpec {
+    fn default() -> Self {
+        AccountsSpec {
+            native: default_native_accounts(),
+            ethereum: 0,
+            balance_cfx: default_balance_cfx(),
+        }
+    }
+}
+
+impl AccountsSpec {
+    pub fn count(&self, space: Space) -> usize {
+        match space {
+            Space::Native => self.native,
+            Space::Ethereum => self.ethereum,
+        }
+    }
+
+    pub fn balance(&self) -> U256 {
+        U256::from(self.balance_cfx) * U256::from(10).pow(18.into())
+    }
+}
+
+/// The gas price of each transaction in Drip.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "distribution", rename_all = "snake_case")]
+pub enum GasPriceDistribution {
+    Fixed { price: u64 },
+    Uniform { min: u64, max: u64 },
+    Weighted { prices: Vec<u64>, weights: Vec<u32> },
+}
+
+impl Default for GasPriceDistribution {
+    fn default() -> Self { GasPriceDistribution::Fixed { price: 1 } }
+}
+
+impl GasPriceDistribution {
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> U256 {
+        let price = match self {
+            GasPriceDistribution::Fixed { price } => *price,
+            GasPriceDistribution::Uniform { min, max } => {
+                rng.gen_range(*min, *max + 1)
+            }
+            GasPriceDistribution::Weighted { prices, weights } => {
+                // Checked in `WorkloadSpec::validate`.
+                let index = WeightedIndex::new(weights).unwrap();
+                prices[index.sample(rng)]
+            }
+        };
+        price.into()
+    }
+}
+
+/// Plain value transfers between the load accounts.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransferSpec {
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    /// The transferred value in Drip.
+    #[serde(default)]
+    pub value: u64,
+    /// The fraction of the transfers sent in eSpace.
+    #[serde(default)]
+    pub ethereum_ratio: f64,
+}
+
+impl Default for TransferSpec {
+    fn default() -> Self {
+        TransferSpec {
+            weight: default_weight(),
+            value: 0,
+            ethereum_ratio: 0.0,
+        }
+    }
+}
+
+/// A contract deployed before the load starts. The first load account of the
+/// space deploys it.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContractSpec {
+    pub name: String,
+    #[serde(default = "default_space")]
+    pub space: Space,
+    /// The init code.
+    #[serde(deserialize_with = "deserialize_hex")]
+    pub code: Bytes,
+    #[serde(default = "default_deploy_gas")]
+    pub gas: u64,
+    #[serde(default = "default_deploy_storage_limit")]
+    pub storage_limit: u64,
+}
+
+/// A call to a deployed contract, sent by a random load account of the
+/// contract's space.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CallSpec {
+    pub contract: String,
+    #[serde(default, deserialize_with = "deserialize_hex")]
+    pub data: Bytes,
+    /// The transferred value in Drip.
+    #[serde(default)]
+    pub value: u64,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default = "default_call_gas")]
+    pub gas: u64,
+    #[serde(default = "default_call_storage_limit")]
+    pub storage_limit: u64,
+}
+
+impl WorkloadSpec {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            format!("Failed to read workload {}: {}", path.display(), e)
+        })?;
+        Self::from_toml(&content)
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        let spec: WorkloadSpec = toml::from_str(content)
+            .map_err(|e| format!("Invalid workload: {}", e))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.target_tps == 0 {
+            return Err("target_tps must be positive".into());
+        }
+        if self.report_interval_secs == 0 {
+            return Err("report_interval_secs must be positive".into());
+        }
+        match &self.gas_price {
+            GasPriceDistribution::Fixed { .. } => {}
+            GasPriceDistribution::Uniform { min, max } => {
+                if min > max {
+                    return Err("gas_price.min is larger than max".into());
+                }
+            }
+            GasPriceDistribution::Weighted { prices, weights } => {
+                if prices.len() != weights.len() {
+                    return Err("gas_price.prices and weights have different \
+                                lengths"
+                        .into());
+                }
+                WeightedIndex::new(weights)
+                    .map_err(|e| format!("Invalid gas_price.weights: {}", e))?;
+            }
+        }
+
+        let ratio = self.transfer.ethereum_ratio;
+        if !(0.0..=1.0).contains(&ratio) {
+            return Err("transfer.ethereum_ratio must be in [0, 1]".into());
+        }
+        if self.transfer.weight > 0 {
+            if ratio < 1.0 {
+                self.check_accounts(Space::Native, "native transfers")?;
+            }
+            if ratio > 0.0 {
+                self.check_accounts(Space::Ethereum, "eSpace transfers")?;
+            }
+        }
+
+        let mut names = HashSet::new();
+        for contract in &self.contracts {
+            if !names.insert(&contract.name) {
+                return Err(format!(
+                    "Contract {} is defined twice",
+                    contract.name
+                ));
+            }
+            self.check_accounts(
+                contract.space,
+                &format!("contract {}", contract.name),
+            )?;
+        }
+        for call in &self.calls {
+            if !names.contains(&call.contract) {
+                return Err(format!(
+                    "Call to undefined contract {}",
+                    call.contract
+                ));
+            }
+        }
+
+        if self.transfer.weight == 0
+            && self.calls.iter().all(|call| call.weight == 0)
+        {
+            return Err("The transfer and call weights are all zero".into());
+        }
+        Ok(())
+    }
+
+    fn check_accounts(&self, space: Space, usage: &str) -> Result<(), String> {
+        if self.accounts.count(space) == 0 {
+            Err(format!("No {:?} space accounts for {}", space, usage))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn contract(&self, name: &str) -> Option<&ContractSpec> {
+        self.contracts.iter().find(|contract| contract.name == name)
+    }
+}
+
+fn deserialize_hex<'de, D>(deserializer: D) -> Result<Bytes, D::Error>
+where D: Deserializer<'de> {
+    let s: String = serde::Deserialize::deserialize(deserializer)?;
+    s.trim_start_matches("0x")
+        .from_hex()
+        .map_err(|e| serde::de::Error::custom(format!("{}", e)))
+}
+
+fn default_report_interval_secs() -> u64 { 10 }
+
+fn default_native_accounts() -> usize { 10 }
+
+fn default_balance_cfx() -> u64 { 1000 }
+
+fn default_weight() -> u32 { 1 }
+
+fn default_space() -> Space { Space::Native }
+
+fn default_deploy_gas() -> u64 { 3_000_000 }
+
+fn default_deploy_storage_limit() -> u64 { 10_000 }
+
+fn default_call_gas() -> u64 { 100_000 }
+
+fn default_call_storage_limit() -> u64 { 1_024 }
+
+#[cfg(test)]
+mod tests {
+    use super::{GasPriceDistribution, WorkloadSpec};
+    use cfx_types::Space;
+
+    #[test]
+    fn test_parse_workload() {
+        let spec = WorkloadSpec::from_toml(
+            r#"
+            target_tps = 200
+            duration_secs = 60
+
+            [accounts]
+            native = 5
+            ethereum = 3
+
+            [gas_price]
+            distribution = "weighted"
+            prices = [1, 10]
+            weights = [9, 1]
+
+            [transfer]
+            ethereum_ratio = 0.5
+
+            [[contract]]
+            name = "counter"
+            space = "Ethereum"
+            code = "0x6001"
+
+            [[call]]
+            contract = "counter"
+            data = "0xd09de08a"
+            weight = 3
+            "#,
+        )
+        .unwrap();
+        assert_eq!(spec.target_tps, 200);
+        assert_eq!(spec.duration_secs, Some(60));
+        assert_eq!(spec.report_interval_secs, 10);
+        assert_eq!(spec.accounts.count(Space::Ethereum), 3);
+        assert!(matches!(
+            spec.gas_price,
+            GasPriceDistribution::Weighted { .. }
+        ));
+        assert_eq!(spec.transfer.weight, 1);
+        let contract = spec.contract("counter").unwrap();
+        assert_eq!(contract.space, Space::Ethereum);
+        assert_eq!(contract.code, vec![0x60, 0x01]);
+        assert_eq!(spec.calls[0].data, vec![0xd0, 0x9d, 0xe0, 0x8a]);
+        assert_eq!(spec.calls[0].gas, 100_000);
+    }
+
+    #[test]
+    fn test_reject_invalid_workload() {
+        // No target rate.
+        assert!(WorkloadSpec::from_toml("").is_err());
+        // Unknown field.
+        assert!(WorkloadSpec::from_toml("target_tps = 1\ntps = 2").is_err());
+        // eSpace transfers without eSpace accounts.
+        assert!(WorkloadSpec::from_toml(
+            "target_tps = 1\n[transfer]\nethereum_ratio = 0.5"
+        )
+        .is_err());
+        // Call to an undefined contract.
+        assert!(WorkloadSpec::from_toml(
+            "target_tps = 1\n[[call]]\ncontract = \"token\""
+        )
+        .is_err());
+        // Inverted gas price range.
+        assert!(WorkloadSpec::from_toml(
+            "target_tps = 1\n[gas_price]\ndistribution = \"uniform\"\nmin = \
+             2\nmax = 1"
+        )
+        .is_err());
+    }
+}
@@ -7,7 +7,8 @@ use malloc_size_of_derive::MallocSizeOf as DeriveMallocSizeOf;
 use parking_lot::Mutex;
 use std::{
     cmp::{max, min},
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    fmt,
     fs::read_to_string,
     hash::Hash,
     str::FromStr,
@@ -255,6 +256,22 @@ impl FromStr for TokenBucket {
     }
 }
 
+/// Formats the bucket in the same format as `FromStr`, with the current number
+/// of tokens rather than the initial one.
+impl fmt::Display for TokenBucket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{}",
+            self.cpu_tokens.max_tokens,
+            self.cpu_tokens.cur_tokens,
+            self.cpu_tokens.recharge_rate,
+            self.cpu_tokens.default_cost,
+            self.max_throttled_counter
+        )
+    }
+}
+
 #[derive(Default, DeriveMallocSizeOf)]
 pub struct TokenBucketManager {
     // manage buckets by name
@@ -274,6 +291,14 @@ impl TokenBucketManager {
         self.buckets.get(name).cloned()
     }
 
+    /// Describes all the buckets by name.
+    pub fn describe(&self) -> BTreeMap<String, String> {
+        self.buckets
+            .iter()
+            .map(|(name, bucket)| (name.clone(), bucket.lock().to_string()))
+            .collect()
+    }
+
     pub fn load(
         toml_file: &str, section: Option<&str>,
     ) -> Result<Self, String> {
@@ -346,6 +371,12 @@ mod tests {
     use crate::token_bucket::{ThrottleResult, TokenBucket};
     use std::{thread::sleep, time::Duration};
 
+    #[test]
+    fn test_display() {
+        let bucket: TokenBucket = "20,10,5,1,3".parse().unwrap();
+        assert_eq!(bucket.to_string(), "20,10,5,1,3");
+    }
+
     #[test]
     fn test_init_tokens() {
         // empty bucket